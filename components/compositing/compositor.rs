@@ -13,12 +13,13 @@ use euclid::point::TypedPoint2D;
 use euclid::rect::TypedRect;
 use euclid::scale_factor::ScaleFactor;
 use euclid::size::TypedSize2D;
-use euclid::{Matrix4D, Point2D, Rect, Size2D};
+use euclid::{Matrix4D, Point2D, Rect, SideOffsets2D, Size2D};
 use gfx::paint_thread::{ChromeToPaintMsg, PaintRequest};
-use gfx_traits::{color, Epoch, FrameTreeId, LayerId, LayerKind, LayerProperties, ScrollPolicy};
+use gfx_traits::{LayerAnimation, LayerProperties, ScrollPolicy};
+use gfx_traits::{color, Epoch, FrameTreeId, LayerId, LayerKind};
 use gleam::gl;
 use gleam::gl::types::{GLint, GLsizei};
-use image::{DynamicImage, ImageFormat, RgbImage};
+use image::{DynamicImage, ImageFormat, RgbImage, imageops};
 use ipc_channel::ipc::{self, IpcSender, IpcSharedMemory};
 use ipc_channel::router::ROUTER;
 use layers::geometry::{DevicePixel, LayerPixel};
@@ -27,33 +28,39 @@ use layers::platform::surface::NativeDisplay;
 use layers::rendergl;
 use layers::rendergl::RenderContext;
 use layers::scene::Scene;
-use msg::constellation_msg::{Image, PixelFormat};
-use msg::constellation_msg::{Key, KeyModifiers, KeyState, LoadData};
+use msg::constellation_msg::{CustomCursor, Key, KeyModifiers, KeyState, LoadData};
 use msg::constellation_msg::{NavigationDirection, PipelineId, PipelineIndex, PipelineNamespaceId};
-use msg::constellation_msg::{WindowSizeData, WindowSizeType};
+use msg::constellation_msg::{Snapshot, SnapshotFormat, WindowSizeData, WindowSizeType};
 use profile_traits::mem::{self, ReportKind, Reporter, ReporterRequest};
 use profile_traits::time::{self, ProfilerCategory, profile};
-use script_traits::CompositorEvent::{MouseMoveEvent, MouseButtonEvent, TouchEvent};
-use script_traits::{AnimationState, AnimationTickType, ConstellationControlMsg};
-use script_traits::{ConstellationMsg, LayoutControlMsg, MouseButton};
+use script_traits::CompositorEvent::{MouseMoveEvent, MouseButtonEvent, PickNodeEvent, TouchEvent};
+use script_traits::{AccessibilityAction, AccessibleNode};
+use script_traits::{AllowedTouchActions, AnimationState, AnimationTickType};
+use script_traits::ConstellationControlMsg;
+use script_traits::{ConstellationMsg, HitTestTree, LayoutControlMsg, MouseButton};
 use script_traits::{MouseEventType, TouchpadPressurePhase, TouchEventType, TouchId};
+use script_traits::{UsedFontFace, VisualViewportData};
 use std::collections::hash_map::Entry::{Occupied, Vacant};
+use std::cmp::min;
 use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::mem as std_mem;
 use std::rc::Rc;
 use std::sync::mpsc::Sender;
+use style_traits::cursor::Cursor;
+use style_traits::forced_colors::ForcedColors;
 use style_traits::viewport::ViewportConstraints;
 use surface_map::SurfaceMap;
 use time::{precise_time_ns, precise_time_s};
-use touch::{TouchHandler, TouchAction};
+use touch::{TouchAction, TouchHandler, TouchState};
 use url::Url;
 use util::geometry::{PagePx, ScreenPx, ViewportPx};
 use util::print_tree::PrintTree;
 use util::{opts, prefs};
 use webrender;
 use webrender_traits::{self, ScrollEventPhase};
-use windowing::{self, MouseWindowEvent, WindowEvent, WindowMethods, WindowNavigateMsg};
+use windowing::WindowNavigateMsg;
+use windowing::{self, DeviceChooserResponder, MouseWindowEvent, WindowEvent, WindowMethods};
 
 #[derive(Debug, PartialEq)]
 enum UnableToComposite {
@@ -78,6 +85,12 @@ const BUFFER_MAP_SIZE: usize = 10000000;
 const MAX_ZOOM: f32 = 8.0;
 const MIN_ZOOM: f32 = 0.1;
 
+/// How long smooth scrolls take, in seconds.
+const SMOOTH_SCROLL_DURATION: f64 = 0.3;
+
+/// How far away, in px, scroll containers with `proximity` snapping snap to snap positions.
+const SCROLL_SNAP_PROXIMITY: i32 = 100;
+
 trait ConvertPipelineIdFromWebRender {
     fn from_webrender(&self) -> PipelineId;
 }
@@ -130,6 +143,9 @@ pub struct IOCompositor<Window: WindowMethods> {
     /// The overridden viewport.
     viewport: Option<(TypedPoint2D<DevicePixel, u32>, TypedSize2D<DevicePixel, u32>)>,
 
+    /// The parts of the window that on-screen keyboards cover, in device pixels.
+    keyboard_insets: SideOffsets2D<f32>,
+
     /// "Mobile-style" zoom that does not reflow the page.
     viewport_zoom: ScaleFactor<PagePx, ViewportPx, f32>,
 
@@ -211,6 +227,21 @@ pub struct IOCompositor<Window: WindowMethods> {
     /// Whether a scroll is in progress; i.e. whether the user's fingers are down.
     scroll_in_progress: bool,
 
+    /// Whether the browser chrome is displaying a tooltip.
+    tooltip_shown: bool,
+
+    /// The URL of the link under the mouse, which the browser chrome is displaying as its
+    /// status.
+    status: Option<String>,
+
+    /// Whether the devtools node picker is active, in which case the next click picks the node
+    /// under it instead of being sent to the page.
+    node_picker_enabled: bool,
+
+    /// The number of requests blocked by tracking protection so far, for each pipeline that had
+    /// any blocked.
+    blocked_request_counts: HashMap<PipelineId, usize>,
+
     /// The webrender renderer, if enabled.
     webrender: Option<webrender::Renderer>,
 
@@ -263,6 +294,15 @@ struct PipelineDetails {
 
     /// Whether there are animation callbacks
     animation_callbacks_running: bool,
+
+    /// The animations of layers that layout has handed off to us.
+    layer_animations: Vec<LayerAnimation>,
+
+    /// The smooth scrolls of layers that script has started.
+    smooth_scrolls: Vec<SmoothScroll>,
+
+    /// What pointer events can hit in the current display list.
+    hit_test_tree: HitTestTree,
 }
 
 impl PipelineDetails {
@@ -272,10 +312,39 @@ impl PipelineDetails {
             current_epoch: Epoch(0),
             animations_running: false,
             animation_callbacks_running: false,
+            layer_animations: vec![],
+            smooth_scrolls: vec![],
+            hit_test_tree: HitTestTree::default(),
         }
     }
 }
 
+/// A scroll of a layer that script asked to be smooth, which moves the layer a little further
+/// on each composite.
+struct SmoothScroll {
+    layer_id: LayerId,
+    /// When the scroll started, as returned by `time::precise_time_s()`.
+    start_time: f64,
+    /// Where the layer was scrolled to when the scroll started.
+    start: Point2D<f32>,
+    /// Where the layer is scrolled to when the scroll ends.
+    end: Point2D<f32>,
+}
+
+impl SmoothScroll {
+    /// Where the layer is scrolled to at the given time, slowing down toward the end.
+    fn point_at(&self, time: f64) -> Point2D<f32> {
+        let progress = ((time - self.start_time) / SMOOTH_SCROLL_DURATION).max(0.0).min(1.0);
+        let eased_progress = (1.0 - (1.0 - progress).powi(3)) as f32;
+        Point2D::new(self.start.x + (self.end.x - self.start.x) * eased_progress,
+                     self.start.y + (self.end.y - self.start.y) * eased_progress)
+    }
+
+    fn is_finished(&self, time: f64) -> bool {
+        time >= self.start_time + SMOOTH_SCROLL_DURATION
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Debug)]
 enum CompositeTarget {
     /// Normal composition to a window
@@ -432,6 +501,7 @@ impl<Window: WindowMethods> IOCompositor<Window> {
             }),
             window_size: window_size,
             viewport: None,
+            keyboard_insets: SideOffsets2D::zero(),
             scale_factor: scale_factor,
             channel_to_self: state.sender.clone_compositor_proxy(),
             delayed_composition_timer: DelayedCompositionTimerProxy::new(state.sender),
@@ -458,6 +528,10 @@ impl<Window: WindowMethods> IOCompositor<Window> {
             pending_subpages: HashSet::new(),
             last_mouse_move_recipient: None,
             scroll_in_progress: false,
+            tooltip_shown: false,
+            status: None,
+            node_picker_enabled: false,
+            blocked_request_counts: HashMap::new(),
             webrender: state.webrender,
             webrender_api: webrender_api,
         }
@@ -541,6 +615,18 @@ impl<Window: WindowMethods> IOCompositor<Window> {
                 self.change_running_animations_state(pipeline_id, animation_state);
             }
 
+            (Msg::SetLayerAnimations(pipeline_id, animations), ShutdownState::NotShuttingDown) => {
+                let start_compositing = !animations.is_empty();
+                self.pipeline_details(pipeline_id).layer_animations = animations;
+                if start_compositing {
+                    self.composite_if_necessary(CompositingReason::Animation);
+                }
+            }
+
+            (Msg::SetHitTestTree(pipeline_id, hit_test_tree), ShutdownState::NotShuttingDown) => {
+                self.pipeline_details(pipeline_id).hit_test_tree = hit_test_tree;
+            }
+
             (Msg::ChangePageTitle(pipeline_id, title), ShutdownState::NotShuttingDown) => {
                 self.change_page_title(pipeline_id, title);
             }
@@ -601,9 +687,16 @@ impl<Window: WindowMethods> IOCompositor<Window> {
                 }
             }
 
-            (Msg::ScrollFragmentPoint(pipeline_id, layer_id, point, _),
+            (Msg::ScrollFragmentPoint(pipeline_id, layer_id, point, smooth),
              ShutdownState::NotShuttingDown) => {
-                self.scroll_fragment_to_point(pipeline_id, layer_id, point);
+                if smooth {
+                    self.start_smooth_scroll(pipeline_id, layer_id, point);
+                } else {
+                    self.pipeline_details(pipeline_id).smooth_scrolls.retain(|scroll| {
+                        scroll.layer_id != layer_id
+                    });
+                    self.scroll_fragment_to_point(pipeline_id, layer_id, point);
+                }
             }
 
             (Msg::MoveTo(point),
@@ -624,8 +717,18 @@ impl<Window: WindowMethods> IOCompositor<Window> {
                 }
             }
 
-            (Msg::Status(message), ShutdownState::NotShuttingDown) => {
-                self.window.status(message);
+            (Msg::ShowTooltip(text, anchor), ShutdownState::NotShuttingDown) => {
+                let anchor = Rect::from_untyped(&(anchor * self.device_pixels_per_page_px().get()));
+                self.window.show_tooltip(text, anchor);
+                self.tooltip_shown = true;
+            }
+
+            (Msg::HideTooltip, ShutdownState::NotShuttingDown) => {
+                self.hide_tooltip();
+            }
+
+            (Msg::PageError(pipeline_id, error), ShutdownState::NotShuttingDown) => {
+                self.window.page_error(pipeline_id, error);
             }
 
             (Msg::LoadStart(back, forward), ShutdownState::NotShuttingDown) => {
@@ -671,15 +774,14 @@ impl<Window: WindowMethods> IOCompositor<Window> {
                 self.touch_handler.on_event_processed(result);
             }
 
-            (Msg::SetCursor(cursor), ShutdownState::NotShuttingDown) => {
-                self.window.set_cursor(cursor)
+            (Msg::SetCursor(cursor, custom_cursor), ShutdownState::NotShuttingDown) => {
+                self.set_cursor(cursor, custom_cursor.as_ref())
             }
 
-            (Msg::CreatePng(reply), ShutdownState::NotShuttingDown) => {
-                let res = self.composite_specific_target(CompositeTarget::WindowAndPng);
-                let img = res.unwrap_or(None);
-                if let Err(e) = reply.send(img) {
-                    warn!("Sending reply to create png failed ({}).", e);
+            (Msg::RenderToImage(rect, format, reply), ShutdownState::NotShuttingDown) => {
+                let snapshot = self.render_to_image(rect, format);
+                if let Err(e) = reply.send(snapshot) {
+                    warn!("Sending reply to render to image failed ({}).", e);
                 }
             }
 
@@ -736,6 +838,11 @@ impl<Window: WindowMethods> IOCompositor<Window> {
             (Msg::PipelineExited(pipeline_id, sender), _) => {
                 debug!("Compositor got pipeline exited: {:?}", pipeline_id);
                 self.pending_subpages.remove(&pipeline_id);
+                self.blocked_request_counts.remove(&pipeline_id);
+                if let Some(details) = self.pipeline_details.get_mut(&pipeline_id) {
+                    details.layer_animations.clear();
+                    details.smooth_scrolls.clear();
+                }
                 self.remove_pipeline_root_layer(pipeline_id);
                 let _ = sender.send(());
             }
@@ -752,6 +859,21 @@ impl<Window: WindowMethods> IOCompositor<Window> {
                 }
             }
 
+            (Msg::SetNodePicker(enabled), ShutdownState::NotShuttingDown) => {
+                self.node_picker_enabled = enabled;
+            }
+
+            (Msg::BlockedRequestsCount(pipeline_id, count), ShutdownState::NotShuttingDown) => {
+                self.blocked_request_counts.insert(pipeline_id, count);
+                if self.root_pipeline.as_ref().map(|pipeline| pipeline.id) == Some(pipeline_id) {
+                    self.window.set_blocked_request_count(count);
+                }
+            }
+
+            (Msg::ChooseDevice(kind, devices, sender), ShutdownState::NotShuttingDown) => {
+                self.window.choose_device(kind, devices, DeviceChooserResponder(sender));
+            }
+
             // When we are shutting_down, we need to avoid performing operations
             // such as Paint that may crash because we have begun tearing down
             // the rest of our resources.
@@ -775,7 +897,7 @@ impl<Window: WindowMethods> IOCompositor<Window> {
                 if !self.pipeline_details(pipeline_id).animation_callbacks_running {
                     self.pipeline_details(pipeline_id).animation_callbacks_running =
                         true;
-                    self.tick_animations_for_pipeline(pipeline_id);
+                    self.tick_animations_for_pipeline(pipeline_id, precise_time_s());
                 }
             }
             AnimationState::NoAnimationsPresent => {
@@ -829,6 +951,10 @@ impl<Window: WindowMethods> IOCompositor<Window> {
         self.pending_subpages.clear();
 
         self.root_pipeline = Some(frame_tree.pipeline.clone());
+        let blocked_request_count = self.blocked_request_counts.get(&frame_tree.pipeline.id)
+                                                               .cloned()
+                                                               .unwrap_or(0);
+        self.window.set_blocked_request_count(blocked_request_count);
 
         if let Some(ref webrender_api) = self.webrender_api {
             let pipeline_id = frame_tree.pipeline.id.to_webrender();
@@ -1220,6 +1346,75 @@ impl<Window: WindowMethods> IOCompositor<Window> {
         }
     }
 
+    fn start_smooth_scroll(&mut self,
+                           pipeline_id: PipelineId,
+                           layer_id: LayerId,
+                           point: Point2D<f32>) {
+        let start = match self.find_layer_with_pipeline_and_layer_id(pipeline_id, layer_id) {
+            Some(ref layer) => {
+                // Layers are moved to the opposite of their scroll offset.
+                let scroll_offset = layer.extra_data.borrow().scroll_offset;
+                Point2D::new(-scroll_offset.x.get(), -scroll_offset.y.get())
+            }
+            // Scroll to the point once the layer exists, as there is nothing to animate yet.
+            None => return self.scroll_fragment_to_point(pipeline_id, layer_id, point),
+        };
+
+        {
+            // A new scroll of a layer replaces the one in progress, from where that got to.
+            let smooth_scrolls = &mut self.pipeline_details(pipeline_id).smooth_scrolls;
+            smooth_scrolls.retain(|scroll| scroll.layer_id != layer_id);
+            smooth_scrolls.push(SmoothScroll {
+                layer_id: layer_id,
+                start_time: precise_time_s(),
+                start: start,
+                end: point,
+            });
+        }
+        self.composite_if_necessary(CompositingReason::Animation);
+    }
+
+    /// Once the user has finished scrolling, scrolls the innermost scroll container at `cursor`
+    /// that snaps to where it snaps to, per CSS-SCROLL-SNAP.
+    fn snap_scroll_position(&mut self, cursor: TypedPoint2D<DevicePixel, i32>) {
+        let result = match self.find_topmost_layer_at_point(cursor.as_f32() / self.scene.scale) {
+            Some(result) => result,
+            None => return,
+        };
+        let pipeline_id = result.layer.pipeline_id();
+        let point = result.point.to_untyped();
+        let point = Point2D::new(Au::from_f32_px(point.x), Au::from_f32_px(point.y));
+        let (layer_id, snap) = match self.pipeline_details.get(&pipeline_id) {
+            Some(details) => match details.hit_test_tree.scroll_snap_at(&point) {
+                Some((layer_id, snap)) => (layer_id, snap.clone()),
+                None => return,
+            },
+            None => return,
+        };
+
+        let scroll_offset =
+            match self.find_layer_with_pipeline_and_layer_id(pipeline_id, layer_id) {
+                Some(ref layer) => layer.extra_data.borrow().scroll_offset,
+                None => return,
+            };
+        // Layers are moved to the opposite of their scroll offset.
+        let position = Point2D::new(Au::from_f32_px(-scroll_offset.x.get()),
+                                    Au::from_f32_px(-scroll_offset.y.get()));
+        let snapped = snap.snap(&position, Au::from_px(SCROLL_SNAP_PROXIMITY));
+        if snapped != position {
+            self.start_smooth_scroll(pipeline_id,
+                                     layer_id,
+                                     Point2D::new(snapped.x.to_f32_px(), snapped.y.to_f32_px()));
+        }
+    }
+
+    /// Stops the smooth scrolls, as the user scrolling takes over from them.
+    fn cancel_smooth_scrolls(&mut self) {
+        for details in self.pipeline_details.values_mut() {
+            details.smooth_scrolls.clear();
+        }
+    }
+
     fn handle_window_message(&mut self, event: WindowEvent) {
         match event {
             WindowEvent::Idle => {}
@@ -1236,6 +1431,11 @@ impl<Window: WindowMethods> IOCompositor<Window> {
               self.viewport = Some((point, size));
             }
 
+            WindowEvent::KeyboardInsets(insets) => {
+                self.keyboard_insets = insets;
+                self.send_visual_viewport();
+            }
+
             WindowEvent::Resize(size) => {
                 self.on_resize_window_event(size);
             }
@@ -1297,6 +1497,13 @@ impl<Window: WindowMethods> IOCompositor<Window> {
                 self.on_key_event(key, state, modifiers);
             }
 
+            WindowEvent::SetTrackingProtectionForSite(site, enabled) => {
+                let msg = ConstellationMsg::SetTrackingProtectionForSite(site, enabled);
+                if let Err(e) = self.constellation_chan.send(msg) {
+                    warn!("Sending tracking protection change to constellation failed ({}).", e);
+                }
+            }
+
             WindowEvent::Quit => {
                 if self.shutdown_state == ShutdownState::NotShuttingDown {
                     debug!("Shutting down the constellation for WindowEvent::Quit");
@@ -1324,6 +1531,7 @@ impl<Window: WindowMethods> IOCompositor<Window> {
 
         self.scene.set_root_layer_size(new_size.as_f32());
         self.send_window_size(WindowSizeType::Resize);
+        self.send_visual_viewport();
     }
 
     fn on_load_url_window_event(&mut self, url_string: String) {
@@ -1360,6 +1568,15 @@ impl<Window: WindowMethods> IOCompositor<Window> {
             MouseWindowEvent::MouseUp(_, p) => p,
         };
 
+        if self.node_picker_enabled {
+            // Swallow the whole click, so that the page does not react to it.
+            if let MouseWindowEvent::Click(..) = mouse_window_event {
+                self.node_picker_enabled = false;
+                self.pick_node_at_point(point);
+            }
+            return
+        }
+
         if let Some(ref webrender_api) = self.webrender_api {
             let root_pipeline_id = match self.get_root_pipeline_id() {
                 Some(root_pipeline_id) => root_pipeline_id,
@@ -1398,24 +1615,51 @@ impl<Window: WindowMethods> IOCompositor<Window> {
         }
     }
 
+    /// Sends a `PickNodeEvent` for the given point to the pipeline under it.
+    fn pick_node_at_point(&self, point: TypedPoint2D<DevicePixel, f32>) {
+        if let Some(ref webrender_api) = self.webrender_api {
+            let (translated_point, translated_pipeline_id) =
+                webrender_api.translate_point_to_layer_space(&point.to_untyped());
+            let translated_pipeline_id = translated_pipeline_id.from_webrender();
+            let msg = ConstellationControlMsg::SendEvent(translated_pipeline_id,
+                                                         PickNodeEvent(translated_point));
+            if let Some(pipeline) = self.pipeline(translated_pipeline_id) {
+                if let Err(e) = pipeline.script_chan.send(msg) {
+                    warn!("Sending control event to script failed ({}).", e);
+                }
+            }
+            return
+        }
+
+        if let Some(result) = self.find_topmost_layer_at_point(point / self.scene.scale) {
+            result.layer.send_event(self, PickNodeEvent(result.point.to_untyped()));
+        }
+    }
+
     fn on_mouse_window_move_event_class(&mut self, cursor: TypedPoint2D<DevicePixel, f32>) {
         if opts::get().convert_mouse_to_touch {
             self.on_touch_move(TouchId(0), cursor);
             return
         }
 
-        if let Some(ref webrender_api) = self.webrender_api {
-            let root_pipeline_id = match self.get_root_pipeline_id() {
-                Some(root_pipeline_id) => root_pipeline_id,
-                None => return,
-            };
-            if self.pipeline(root_pipeline_id).is_none() {
-                return;
+        let translated = match self.webrender_api {
+            Some(ref webrender_api) => {
+                let root_pipeline_id = match self.get_root_pipeline_id() {
+                    Some(root_pipeline_id) => root_pipeline_id,
+                    None => return,
+                };
+                if self.pipeline(root_pipeline_id).is_none() {
+                    return;
+                }
+
+                Some(webrender_api.translate_point_to_layer_space(&cursor.to_untyped()))
             }
+            None => None,
+        };
 
-            let (translated_point, translated_pipeline_id) =
-                webrender_api.translate_point_to_layer_space(&cursor.to_untyped());
+        if let Some((translated_point, translated_pipeline_id)) = translated {
             let translated_pipeline_id = translated_pipeline_id.from_webrender();
+            self.update_hover(translated_pipeline_id, translated_point);
             let event_to_send = MouseMoveEvent(Some(translated_point));
             let msg = ConstellationControlMsg::SendEvent(translated_pipeline_id, event_to_send);
             if let Some(pipeline) = self.pipeline(translated_pipeline_id) {
@@ -1442,15 +1686,74 @@ impl<Window: WindowMethods> IOCompositor<Window> {
                 }
 
                 self.last_mouse_move_recipient = Some(result.layer.pipeline_id());
+                self.update_hover(result.layer.pipeline_id(), result.point.to_untyped());
                 result.layer.send_mouse_move_event(self, result.point);
             }
-            None => {}
+            None => self.set_status(None),
+        }
+    }
+
+    /// Shows the cursor of what is at `point`, in the page coordinates of the given pipeline,
+    /// and the URL of the link it is in, if any, without waiting for layout to hit test the
+    /// mouse move event.
+    fn update_hover(&mut self, pipeline_id: PipelineId, point: Point2D<f32>) {
+        let link = {
+            let hit_test_tree = match self.pipeline_details.get(&pipeline_id) {
+                Some(details) => &details.hit_test_tree,
+                None => return,
+            };
+            let point = Point2D::new(Au::from_f32_px(point.x), Au::from_f32_px(point.y));
+            match hit_test_tree.hit_test(&point) {
+                Some(item) => {
+                    self.set_cursor(item.cursor, hit_test_tree.custom_cursors.get(&item.node));
+                    hit_test_tree.links.get(&item.node).cloned()
+                }
+                None => {
+                    self.set_cursor(Cursor::DefaultCursor, None);
+                    None
+                }
+            }
+        };
+        self.set_status(link);
+    }
+
+    /// Shows `link` as the status of the browser chrome, unless it already is.
+    fn set_status(&mut self, link: Option<String>) {
+        if link != self.status {
+            self.window.status(link.clone());
+            self.status = link;
         }
     }
 
+    fn hide_tooltip(&mut self) {
+        if self.tooltip_shown {
+            self.window.hide_tooltip();
+            self.tooltip_shown = false;
+        }
+    }
+
+    fn set_cursor(&self, cursor: Cursor, custom_cursor: Option<&CustomCursor>) {
+        if let Some(custom_cursor) = custom_cursor {
+            if self.window.set_custom_cursor(custom_cursor) {
+                return
+            }
+        }
+        self.window.set_cursor(cursor);
+    }
+
     fn on_touch_down(&mut self, identifier: TouchId, point: TypedPoint2D<DevicePixel, f32>) {
-        self.touch_handler.on_touch_down(identifier, point);
-        if let Some(result) = self.find_topmost_layer_at_point(point / self.scene.scale) {
+        let result = self.find_topmost_layer_at_point(point / self.scene.scale);
+        // The `touch-action` of the page decides whether the touch may pan before script has
+        // handled the touch start, so that it does not have to wait for script where it may not.
+        let allowed_actions = result.as_ref().and_then(|result| {
+            let point = result.point.to_untyped();
+            let point = Point2D::new(Au::from_f32_px(point.x), Au::from_f32_px(point.y));
+            self.pipeline_details.get(&result.layer.pipeline_id()).map(|details| {
+                details.hit_test_tree.allowed_touch_actions_at(&point)
+            })
+        }).unwrap_or_else(AllowedTouchActions::all);
+        self.touch_handler.on_touch_down(identifier, point, allowed_actions);
+        if let Some(result) = result {
             result.layer.send_event(self, TouchEvent(TouchEventType::Down, identifier,
                                                      result.point.to_untyped()));
         }
@@ -1489,8 +1792,18 @@ impl<Window: WindowMethods> IOCompositor<Window> {
             result.layer.send_event(self, TouchEvent(TouchEventType::Up, identifier,
                                                      result.point.to_untyped()));
         }
-        if let TouchAction::Click = self.touch_handler.on_touch_up(identifier, point) {
-            self.simulate_mouse_click(point);
+        let was_panning = self.touch_handler.state == TouchState::Panning;
+        match self.touch_handler.on_touch_up(identifier, point) {
+            TouchAction::Click => self.simulate_mouse_click(point),
+            // Lifting the finger ends the scroll of a pan.
+            _ if was_panning && self.touch_handler.state == TouchState::Nothing => {
+                match point.cast() {
+                    Some(point) => self.on_scroll_end_window_event(Point2D::typed(0.0, 0.0),
+                                                                   point),
+                    None => error!("Point cast failed."),
+                }
+            }
+            _ => {}
         }
     }
 
@@ -1520,6 +1833,9 @@ impl<Window: WindowMethods> IOCompositor<Window> {
     fn on_scroll_window_event(&mut self,
                               delta: TypedPoint2D<DevicePixel, f32>,
                               cursor: TypedPoint2D<DevicePixel, i32>) {
+        // The tooltip would no longer be next to what it describes.
+        self.hide_tooltip();
+        self.cancel_smooth_scrolls();
         self.pending_scroll_zoom_events.push(ScrollZoomEvent {
             magnification: 1.0,
             delta: delta,
@@ -1533,6 +1849,7 @@ impl<Window: WindowMethods> IOCompositor<Window> {
                                     delta: TypedPoint2D<DevicePixel, f32>,
                                     cursor: TypedPoint2D<DevicePixel, i32>) {
         self.scroll_in_progress = true;
+        self.cancel_smooth_scrolls();
         self.pending_scroll_zoom_events.push(ScrollZoomEvent {
             magnification: 1.0,
             delta: delta,
@@ -1619,6 +1936,10 @@ impl<Window: WindowMethods> IOCompositor<Window> {
                     }
 
                     self.perform_updates_after_scroll();
+
+                    if event.phase == ScrollEventPhase::End {
+                        self.snap_scroll_position(event.cursor);
+                    }
                 }
 
                 if had_events {
@@ -1691,7 +2012,13 @@ impl<Window: WindowMethods> IOCompositor<Window> {
     }
 
     /// If there are any animations running, dispatches appropriate messages to the constellation.
+    /// All of them are sampled at the same time, which script and layout are ticked with too, so
+    /// that the animations that each of them runs stay in phase.
     fn process_animations(&mut self) {
+        let now = precise_time_s();
+        self.sample_layer_animations(now);
+        self.sample_smooth_scrolls(now);
+
         let mut pipeline_ids = vec![];
         for (pipeline_id, pipeline_details) in &self.pipeline_details {
             if pipeline_details.animations_running ||
@@ -1700,11 +2027,71 @@ impl<Window: WindowMethods> IOCompositor<Window> {
             }
         }
         for pipeline_id in &pipeline_ids {
-            self.tick_animations_for_pipeline(*pipeline_id)
+            self.tick_animations_for_pipeline(*pipeline_id, now)
         }
     }
 
-    fn tick_animations_for_pipeline(&mut self, pipeline_id: PipelineId) {
+    /// Moves the layers with handed off animations to where their animations are now. Layout is
+    /// ticked once the animations of a pipeline are over, so that it catches up with them.
+    fn sample_layer_animations(&mut self, now: f64) {
+        let mut transforms = vec![];
+        let mut finished_pipeline_ids = vec![];
+        for (pipeline_id, pipeline_details) in &mut self.pipeline_details {
+            if pipeline_details.layer_animations.is_empty() {
+                continue
+            }
+            for animation in &pipeline_details.layer_animations {
+                transforms.push((*pipeline_id, animation.layer_id, animation.transform_at(now)));
+            }
+            pipeline_details.layer_animations.retain(|animation| !animation.is_finished(now));
+            if pipeline_details.layer_animations.is_empty() {
+                finished_pipeline_ids.push(*pipeline_id);
+            }
+        }
+
+        if !transforms.is_empty() {
+            for (pipeline_id, layer_id, transform) in transforms {
+                if let Some(layer) = self.find_layer_with_pipeline_and_layer_id(pipeline_id,
+                                                                                layer_id) {
+                    *layer.transform.borrow_mut() = transform;
+                }
+            }
+            self.send_buffer_requests_for_all_layers();
+            self.composite_if_necessary(CompositingReason::Animation);
+        }
+        for pipeline_id in finished_pipeline_ids {
+            let msg = ConstellationMsg::TickAnimation(pipeline_id, AnimationTickType::Layout, now);
+            if let Err(e) = self.constellation_chan.send(msg) {
+                warn!("Sending tick to constellation failed ({}).", e);
+            }
+        }
+    }
+
+    /// Moves the layers that are scrolled smoothly to where their scrolls are now.
+    fn sample_smooth_scrolls(&mut self, now: f64) {
+        let mut points = vec![];
+        for (pipeline_id, pipeline_details) in &mut self.pipeline_details {
+            for scroll in &pipeline_details.smooth_scrolls {
+                points.push((*pipeline_id, scroll.layer_id, scroll.point_at(now)));
+            }
+            pipeline_details.smooth_scrolls.retain(|scroll| !scroll.is_finished(now));
+        }
+
+        if points.is_empty() {
+            return
+        }
+        for (pipeline_id, layer_id, point) in points {
+            self.move_layer(pipeline_id, layer_id, Point2D::from_untyped(&point));
+        }
+        self.perform_updates_after_scroll();
+        self.send_viewport_rects_for_all_layers();
+        self.composite_if_necessary(CompositingReason::Animation);
+    }
+
+    /// Ticks the animations of a pipeline at the time `now`. When script has animation frame
+    /// callbacks, only script is ticked, since layout samples its animations in the reflow that
+    /// follows them instead of laying out again.
+    fn tick_animations_for_pipeline(&mut self, pipeline_id: PipelineId, now: f64) {
         self.schedule_delayed_composite_if_necessary();
         let animation_callbacks_running = self.pipeline_details(pipeline_id).animation_callbacks_running;
         let animation_type = if animation_callbacks_running {
@@ -1712,7 +2099,7 @@ impl<Window: WindowMethods> IOCompositor<Window> {
         } else {
             AnimationTickType::Layout
         };
-        let msg = ConstellationMsg::TickAnimation(pipeline_id, animation_type);
+        let msg = ConstellationMsg::TickAnimation(pipeline_id, animation_type, now);
         if let Err(e) = self.constellation_chan.send(msg) {
             warn!("Sending tick to constellation failed ({}).", e);
         }
@@ -1891,6 +2278,31 @@ impl<Window: WindowMethods> IOCompositor<Window> {
             Some(ref root) => self.send_viewport_rect_for_layer(root.clone()),
             None => {},
         }
+        self.send_visual_viewport();
+    }
+
+    /// Sends the part of the page that is visible on screen to the root pipeline: the viewport,
+    /// scaled by the pinch zoom, without the parts that on-screen keyboards cover.
+    fn send_visual_viewport(&self) {
+        let (pipeline, root) = match (self.root_pipeline.as_ref(), self.scene.root.as_ref()) {
+            (Some(pipeline), Some(root)) => (pipeline, root),
+            _ => return,
+        };
+        let scale = self.device_pixels_per_page_px().get();
+        let insets = &self.keyboard_insets;
+        let scroll_offset = root.extra_data.borrow().scroll_offset.to_untyped();
+        let window_size = self.window_size.as_f32().to_untyped();
+        let data = VisualViewportData {
+            page_offset: Point2D::new(insets.left / scale - scroll_offset.x,
+                                      insets.top / scale - scroll_offset.y),
+            size: Size2D::new(((window_size.width - insets.horizontal()) / scale).max(0.0),
+                              ((window_size.height - insets.vertical()) / scale).max(0.0)),
+            scale: self.viewport_zoom.get(),
+        };
+        let msg = ConstellationControlMsg::VisualViewport(pipeline.id, data);
+        if let Err(e) = pipeline.script_chan.send(msg) {
+            warn!("Send visual viewport to script failed ({})", e);
+        }
     }
 
     /// Returns true if any buffer requests were sent or false otherwise.
@@ -1977,6 +2389,12 @@ impl<Window: WindowMethods> IOCompositor<Window> {
             if details.animation_callbacks_running {
                 return true;
             }
+            if !details.layer_animations.is_empty() {
+                return true;
+            }
+            if !details.smooth_scrolls.is_empty() {
+                return true;
+            }
         }
 
         false
@@ -2074,10 +2492,10 @@ impl<Window: WindowMethods> IOCompositor<Window> {
     /// Returns Ok if composition was performed or Err if it was not possible to composite
     /// for some reason. If CompositeTarget is Window or Png no image data is returned;
     /// in the latter case the image is written directly to a file. If CompositeTarget
-    /// is WindowAndPng Ok(Some(png::Image)) is returned.
+    /// is WindowAndPng Ok(Some(RgbImage)) is returned.
     fn composite_specific_target(&mut self,
                                  target: CompositeTarget)
-                                 -> Result<Option<Image>, UnableToComposite> {
+                                 -> Result<Option<RgbImage>, UnableToComposite> {
         if self.context.is_none() && self.webrender.is_none() {
             return Err(UnableToComposite::NoContext)
         }
@@ -2169,16 +2587,7 @@ impl<Window: WindowMethods> IOCompositor<Window> {
         let rv = match target {
             CompositeTarget::Window => None,
             CompositeTarget::WindowAndPng => {
-                let img = self.draw_img(render_target_info,
-                                        width,
-                                        height);
-                Some(Image {
-                    width: img.width(),
-                    height: img.height(),
-                    format: PixelFormat::RGB8,
-                    bytes: IpcSharedMemory::from_bytes(&*img),
-                    id: None,
-                })
+                Some(self.draw_img(render_target_info, width, height))
             }
             CompositeTarget::PngFile => {
                 profile(ProfilerCategory::ImageSaving, None, self.time_profiler_chan.clone(), || {
@@ -2550,6 +2959,43 @@ impl<Window> CompositorEventListener for IOCompositor<Window> where Window: Wind
         self.viewport_zoom.get() as f32
     }
 
+    fn render_to_image(&mut self, rect: Option<Rect<f32>>, format: SnapshotFormat)
+                       -> Option<Snapshot> {
+        let mut img = match self.composite_specific_target(CompositeTarget::WindowAndPng) {
+            Ok(Some(img)) => img,
+            _ => return None,
+        };
+
+        if let Some(rect) = rect {
+            let scale = self.device_pixels_per_page_px().get();
+            let x = min((rect.origin.x * scale).max(0.) as u32, img.width());
+            let y = min((rect.origin.y * scale).max(0.) as u32, img.height());
+            let width = min((rect.size.width * scale).max(0.) as u32, img.width() - x);
+            let height = min((rect.size.height * scale).max(0.) as u32, img.height() - y);
+            let cropped = imageops::crop(&mut img, x, y, width, height).to_image();
+            img = cropped;
+        }
+
+        let (width, height) = img.dimensions();
+        let bytes = match format {
+            SnapshotFormat::Rgba => DynamicImage::ImageRgb8(img).to_rgba().into_raw(),
+            SnapshotFormat::Png => {
+                let mut png = vec![];
+                if let Err(e) = DynamicImage::ImageRgb8(img).save(&mut png, ImageFormat::PNG) {
+                    error!("Failed to encode snapshot ({}).", e);
+                    return None;
+                }
+                png
+            }
+        };
+        Some(Snapshot {
+            width: width,
+            height: height,
+            format: format,
+            bytes: IpcSharedMemory::from_bytes(&bytes),
+        })
+    }
+
     fn title_for_main_frame(&self) {
         let root_pipeline_id = match self.root_pipeline {
             None => return,
@@ -2560,6 +3006,78 @@ impl<Window> CompositorEventListener for IOCompositor<Window> where Window: Wind
             warn!("Failed to send pipeline title ({}).", e);
         }
     }
+
+    fn accessibility_tree(&self) -> Option<AccessibleNode> {
+        let root_pipeline_id = match self.root_pipeline {
+            None => return None,
+            Some(ref root_pipeline) => root_pipeline.id,
+        };
+        let (sender, receiver) = ipc::channel().unwrap();
+        let msg = ConstellationMsg::GetAccessibilityTree(root_pipeline_id, sender);
+        if let Err(e) = self.constellation_chan.send(msg) {
+            warn!("Failed to request the accessibility tree ({}).", e);
+            return None;
+        }
+        receiver.recv().unwrap_or(None)
+    }
+
+    fn perform_accessibility_action(&self, action: AccessibilityAction) {
+        let root_pipeline_id = match self.root_pipeline {
+            None => return,
+            Some(ref root_pipeline) => root_pipeline.id,
+        };
+        let msg = ConstellationMsg::AccessibilityAction(root_pipeline_id, action);
+        if let Err(e) = self.constellation_chan.send(msg) {
+            warn!("Failed to send accessibility action ({}).", e);
+        }
+    }
+
+    fn set_author_styles_enabled(&self, enabled: bool) {
+        let root_pipeline_id = match self.root_pipeline {
+            None => return,
+            Some(ref root_pipeline) => root_pipeline.id,
+        };
+        let msg = ConstellationMsg::SetAuthorStylesEnabled(root_pipeline_id, enabled);
+        if let Err(e) = self.constellation_chan.send(msg) {
+            warn!("Failed to toggle author styles ({}).", e);
+        }
+    }
+
+    fn set_forced_colors(&self, forced_colors: Option<ForcedColors>) {
+        let root_pipeline_id = match self.root_pipeline {
+            None => return,
+            Some(ref root_pipeline) => root_pipeline.id,
+        };
+        let msg = ConstellationMsg::SetForcedColors(root_pipeline_id, forced_colors);
+        if let Err(e) = self.constellation_chan.send(msg) {
+            warn!("Failed to set forced colors ({}).", e);
+        }
+    }
+
+    fn set_text_zoom(&self, text_zoom: f32) {
+        let root_pipeline_id = match self.root_pipeline {
+            None => return,
+            Some(ref root_pipeline) => root_pipeline.id,
+        };
+        let msg = ConstellationMsg::SetTextZoom(root_pipeline_id, text_zoom);
+        if let Err(e) = self.constellation_chan.send(msg) {
+            warn!("Failed to set text zoom ({}).", e);
+        }
+    }
+
+    fn used_font_faces(&self) -> Option<Vec<UsedFontFace>> {
+        let root_pipeline_id = match self.root_pipeline {
+            None => return None,
+            Some(ref root_pipeline) => root_pipeline.id,
+        };
+        let (sender, receiver) = ipc::channel().unwrap();
+        let msg = ConstellationMsg::GetUsedFontFaces(root_pipeline_id, sender);
+        if let Err(e) = self.constellation_chan.send(msg) {
+            warn!("Failed to request the used font faces ({}).", e);
+            return None;
+        }
+        receiver.recv().unwrap_or(None)
+    }
 }
 
 /// Why we performed a composite. This is used for debugging.
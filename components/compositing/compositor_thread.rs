@@ -7,19 +7,25 @@
 use SendableFrameTree;
 use compositor::{self, CompositingReason};
 use euclid::point::Point2D;
+use euclid::rect::Rect;
 use euclid::size::Size2D;
-use gfx_traits::{Epoch, FrameTreeId, LayerId, LayerProperties, PaintListener};
+use gfx_traits::{Epoch, FrameTreeId, LayerAnimation, LayerId, LayerProperties, PaintListener};
 use ipc_channel::ipc::IpcSender;
 use layers::layers::{BufferRequest, LayerBufferSet};
 use layers::platform::surface::{NativeDisplay, NativeSurface};
-use msg::constellation_msg::{Image, Key, KeyModifiers, KeyState, PipelineId};
+use msg::constellation_msg::SnapshotFormat;
+use msg::constellation_msg::{CustomCursor, Key, KeyModifiers, KeyState, PipelineId, Snapshot};
+use net_traits::{DeviceChoice, DeviceKind};
 use profile_traits::mem;
 use profile_traits::time;
-use script_traits::{AnimationState, ConstellationMsg, EventResult};
+use script_traits::{AccessibilityAction, AccessibleNode};
+use script_traits::{AnimationState, ConstellationMsg, EventResult, HitTestTree, PageError};
+use script_traits::UsedFontFace;
 use std::fmt::{Debug, Error, Formatter};
 use std::rc::Rc;
 use std::sync::mpsc::{Receiver, Sender, channel};
 use style_traits::cursor::Cursor;
+use style_traits::forced_colors::ForcedColors;
 use style_traits::viewport::ViewportConstraints;
 use url::Url;
 use webrender;
@@ -142,6 +148,10 @@ pub enum Msg {
     ChangePageUrl(PipelineId, Url),
     /// Alerts the compositor that the given pipeline has changed whether it is running animations.
     ChangeRunningAnimationsState(PipelineId, AnimationState),
+    /// Hands the animations of layers of the given pipeline off to the compositor.
+    SetLayerAnimations(PipelineId, Vec<LayerAnimation>),
+    /// Replaces what pointer events can hit in the given pipeline.
+    SetHitTestTree(PipelineId, HitTestTree),
     /// Replaces the current frame tree, typically called during main frame navigation.
     SetFrameTree(SendableFrameTree, IpcSender<()>, Sender<ConstellationMsg>),
     /// The load of a page has begun: (can go back, can go forward).
@@ -156,10 +166,11 @@ pub enum Msg {
     KeyEvent(Key, KeyState, KeyModifiers),
     /// Script has handled a touch event, and either prevented or allowed default actions.
     TouchEventProcessed(EventResult),
-    /// Changes the cursor.
-    SetCursor(Cursor),
-    /// Composite to a PNG file and return the Image over a passed channel.
-    CreatePng(IpcSender<Option<Image>>),
+    /// Changes the cursor, to the custom cursor if the window supports it.
+    SetCursor(Cursor, Option<CustomCursor>),
+    /// Composite, and return a snapshot of the window contents, or of the given rectangle of them
+    /// in page pixels, over a passed channel.
+    RenderToImage(Option<Rect<f32>>, SnapshotFormat, IpcSender<Option<Snapshot>>),
     /// Informs the compositor that the paint thread for the given pipeline has exited.
     PaintThreadExited(PipelineId),
     /// Alerts the compositor that the viewport has been constrained in some manner
@@ -175,8 +186,13 @@ pub enum Msg {
     ReturnUnusedNativeSurfaces(Vec<NativeSurface>),
     /// Collect memory reports and send them back to the given mem::ReportsChan.
     CollectMemoryReports(mem::ReportsChan),
-    /// A status message to be displayed by the browser chrome.
-    Status(Option<String>),
+    /// A tooltip to be displayed by the browser chrome, next to the given rect of the viewport,
+    /// in CSS pixels.
+    ShowTooltip(String, Rect<f32>),
+    /// Hide the tooltip displayed by the browser chrome.
+    HideTooltip,
+    /// An error of the page of the given pipeline, to be reported to the embedder.
+    PageError(PipelineId, PageError),
     /// Get Window Informations size and position
     GetClientWindow(IpcSender<(Size2D<u32>, Point2D<i32>)>),
     /// Move the window to a point
@@ -185,6 +201,13 @@ pub enum Msg {
     ResizeTo(Size2D<u32>),
     /// Get scroll offset of a layer
     GetScrollOffset(PipelineId, LayerId, IpcSender<Point2D<f32>>),
+    /// Turn the next click into a devtools node pick (if true), or stop doing so.
+    SetNodePicker(bool),
+    /// The number of requests of a pipeline blocked by tracking protection so far.
+    BlockedRequestsCount(PipelineId, usize),
+    /// Let the user choose which of the given devices a page may access, sending back the id of
+    /// the chosen one.
+    ChooseDevice(DeviceKind, Vec<DeviceChoice>, IpcSender<Option<String>>),
     /// A pipeline was shut down.
     // This message acts as a synchronization point between the constellation,
     // when it shuts down a pipeline, to the compositor; when the compositor
@@ -203,6 +226,8 @@ impl Debug for Msg {
             Msg::ScrollFragmentPoint(..) => write!(f, "ScrollFragmentPoint"),
             Msg::AssignPaintedBuffers(..) => write!(f, "AssignPaintedBuffers"),
             Msg::ChangeRunningAnimationsState(..) => write!(f, "ChangeRunningAnimationsState"),
+            Msg::SetLayerAnimations(..) => write!(f, "SetLayerAnimations"),
+            Msg::SetHitTestTree(..) => write!(f, "SetHitTestTree"),
             Msg::ChangePageTitle(..) => write!(f, "ChangePageTitle"),
             Msg::ChangePageUrl(..) => write!(f, "ChangePageUrl"),
             Msg::SetFrameTree(..) => write!(f, "SetFrameTree"),
@@ -213,7 +238,7 @@ impl Debug for Msg {
             Msg::KeyEvent(..) => write!(f, "KeyEvent"),
             Msg::TouchEventProcessed(..) => write!(f, "TouchEventProcessed"),
             Msg::SetCursor(..) => write!(f, "SetCursor"),
-            Msg::CreatePng(..) => write!(f, "CreatePng"),
+            Msg::RenderToImage(..) => write!(f, "RenderToImage"),
             Msg::PaintThreadExited(..) => write!(f, "PaintThreadExited"),
             Msg::ViewportConstrained(..) => write!(f, "ViewportConstrained"),
             Msg::IsReadyToSaveImageReply(..) => write!(f, "IsReadyToSaveImageReply"),
@@ -221,12 +246,17 @@ impl Debug for Msg {
             Msg::HeadParsed => write!(f, "HeadParsed"),
             Msg::ReturnUnusedNativeSurfaces(..) => write!(f, "ReturnUnusedNativeSurfaces"),
             Msg::CollectMemoryReports(..) => write!(f, "CollectMemoryReports"),
-            Msg::Status(..) => write!(f, "Status"),
+            Msg::ShowTooltip(..) => write!(f, "ShowTooltip"),
+            Msg::HideTooltip => write!(f, "HideTooltip"),
+            Msg::PageError(..) => write!(f, "PageError"),
             Msg::GetClientWindow(..) => write!(f, "GetClientWindow"),
             Msg::MoveTo(..) => write!(f, "MoveTo"),
             Msg::ResizeTo(..) => write!(f, "ResizeTo"),
             Msg::PipelineExited(..) => write!(f, "PipelineExited"),
             Msg::GetScrollOffset(..) => write!(f, "GetScrollOffset"),
+            Msg::SetNodePicker(..) => write!(f, "SetNodePicker"),
+            Msg::BlockedRequestsCount(..) => write!(f, "BlockedRequestsCount"),
+            Msg::ChooseDevice(..) => write!(f, "ChooseDevice"),
         }
     }
 }
@@ -249,6 +279,24 @@ pub trait CompositorEventListener {
     fn pinch_zoom_level(&self) -> f32;
     /// Requests that the compositor send the title for the main frame as soon as possible.
     fn title_for_main_frame(&self);
+    /// Composite, and return a snapshot of the window contents, or of the given rectangle of them
+    /// in page pixels. Returns None if the rendering is not stable yet.
+    fn render_to_image(&mut self, rect: Option<Rect<f32>>, format: SnapshotFormat)
+                       -> Option<Snapshot>;
+    /// Returns the accessibility tree of the document of the main frame, if there is one.
+    fn accessibility_tree(&self) -> Option<AccessibleNode>;
+    /// Performs an action of an assistive technology in the document of the main frame.
+    fn perform_accessibility_action(&self, action: AccessibilityAction);
+    /// Sets whether author styles apply to the document of the main frame.
+    fn set_author_styles_enabled(&self, enabled: bool);
+    /// Puts the document of the main frame in the forced colors mode of the given palette, or
+    /// out of forced colors modes.
+    fn set_forced_colors(&self, forced_colors: Option<ForcedColors>);
+    /// Zooms the text of the document of the main frame by the given factor.
+    fn set_text_zoom(&self, text_zoom: f32);
+    /// Returns the font faces that the text of the document of the main frame is laid out in, if
+    /// there is one.
+    fn used_font_faces(&self) -> Option<Vec<UsedFontFace>>;
 }
 
 /// Data used to construct a compositor.
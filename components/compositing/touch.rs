@@ -2,10 +2,10 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use euclid::point::TypedPoint2D;
+use euclid::point::{Point2D, TypedPoint2D};
 use euclid::scale_factor::ScaleFactor;
 use layers::geometry::DevicePixel;
-use script_traits::{EventResult, TouchId};
+use script_traits::{AllowedTouchActions, EventResult, TouchId};
 use self::TouchState::*;
 
 /// Minimum number of ScreenPx to begin touch scrolling.
@@ -14,6 +14,8 @@ const TOUCH_PAN_MIN_SCREEN_PX: f32 = 20.0;
 pub struct TouchHandler {
     pub state: TouchState,
     pub active_touch_points: Vec<TouchPoint>,
+    /// What the `touch-action` where the current touch sequence started allows it to do.
+    pub allowed_actions: AllowedTouchActions,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -43,6 +45,10 @@ pub enum TouchState {
     /// A single touch point is active and may perform click or pan default actions.
     /// Contains the initial touch location.
     Touching,
+    /// A single touch point is active where `touch-action` allows neither panning nor zooming,
+    /// so there is no need to wait for script before dispatching its moves. It may still click,
+    /// unless script prevents it.
+    TouchActionNone,
     /// A single touch point is active and has started panning.
     Panning,
     /// A two-finger pinch zoom gesture is active.
@@ -71,18 +77,29 @@ impl TouchHandler {
         TouchHandler {
             state: Nothing,
             active_touch_points: Vec::new(),
+            allowed_actions: AllowedTouchActions::all(),
         }
     }
 
-    pub fn on_touch_down(&mut self, id: TouchId, point: TypedPoint2D<DevicePixel, f32>) {
+    /// Handles a new touch point, given what the `touch-action` at its location allows.
+    pub fn on_touch_down(&mut self,
+                         id: TouchId,
+                         point: TypedPoint2D<DevicePixel, f32>,
+                         allowed_actions: AllowedTouchActions) {
         let point = TouchPoint::new(id, point);
         self.active_touch_points.push(point);
 
+        if self.state == Nothing {
+            self.allowed_actions = allowed_actions;
+        }
         self.state = match self.state {
+            Nothing if !allowed_actions.any() => TouchActionNone,
             Nothing               => WaitingForScript,
-            Touching | Panning    => Pinching,
+            Touching | Panning if self.allowed_actions.zoom => Pinching,
+            Touching | Panning    => MultiTouch,
             WaitingForScript      => WaitingForScript,
             DefaultPrevented      => DefaultPrevented,
+            TouchActionNone       => MultiTouch,
             Pinching | MultiTouch => MultiTouch,
         };
     }
@@ -107,14 +124,23 @@ impl TouchHandler {
                    delta.y.get().abs() > TOUCH_PAN_MIN_SCREEN_PX
                 {
                     self.state = Panning;
-                    TouchAction::Scroll(delta)
+                    TouchAction::Scroll(self.allowed_pan(delta))
                 } else {
                     TouchAction::NoAction
                 }
             }
             Panning => {
                 let delta = point - old_point;
-                TouchAction::Scroll(delta)
+                TouchAction::Scroll(self.allowed_pan(delta))
+            }
+            TouchActionNone => {
+                // A touch that moves too far to be a click only dispatches events.
+                let delta = point - old_point;
+                if delta.x.get().abs() > TOUCH_PAN_MIN_SCREEN_PX ||
+                   delta.y.get().abs() > TOUCH_PAN_MIN_SCREEN_PX {
+                    self.state = DefaultPrevented;
+                }
+                TouchAction::DispatchEvent
             }
             DefaultPrevented => {
                 TouchAction::DispatchEvent
@@ -136,7 +162,8 @@ impl TouchHandler {
 
         // If we're still waiting to see whether this is a click or pan, remember the original
         // location.  Otherwise, update the touch point with the latest location.
-        if self.state != Touching && self.state != WaitingForScript {
+        if self.state != Touching && self.state != WaitingForScript &&
+                self.state != TouchActionNone {
             self.active_touch_points[idx].point = point;
         }
         action
@@ -153,7 +180,7 @@ impl TouchHandler {
             }
         }
         match self.state {
-            Touching => {
+            Touching | TouchActionNone => {
                 // FIXME: If the duration exceeds some threshold, send a contextmenu event instead.
                 // FIXME: Don't send a click if preventDefault is called on the touchend event.
                 self.state = Nothing;
@@ -188,7 +215,7 @@ impl TouchHandler {
         }
         match self.state {
             Nothing => {}
-            Touching | Panning => {
+            Touching | Panning | TouchActionNone => {
                 self.state = Nothing;
             }
             Pinching => {
@@ -203,18 +230,33 @@ impl TouchHandler {
     }
 
     pub fn on_event_processed(&mut self, result: EventResult) {
-        if let WaitingForScript = self.state {
-            self.state = match result {
-                EventResult::DefaultPrevented => DefaultPrevented,
-                EventResult::DefaultAllowed => match self.touch_count() {
-                    1 => Touching,
-                    2 => Pinching,
-                    _ => MultiTouch,
+        match self.state {
+            WaitingForScript => {
+                self.state = match result {
+                    EventResult::DefaultPrevented => DefaultPrevented,
+                    EventResult::DefaultAllowed => match self.touch_count() {
+                        1 => Touching,
+                        2 if self.allowed_actions.zoom => Pinching,
+                        _ => MultiTouch,
+                    }
+                }
+            }
+            TouchActionNone => {
+                if let EventResult::DefaultPrevented = result {
+                    self.state = DefaultPrevented
                 }
             }
+            _ => {}
         }
     }
 
+    /// The part of `delta` that the `touch-action` of the touch sequence allows it to pan by.
+    fn allowed_pan(&self, delta: TypedPoint2D<DevicePixel, f32>)
+                   -> TypedPoint2D<DevicePixel, f32> {
+        Point2D::typed(if self.allowed_actions.pan_x { delta.x.get() } else { 0.0 },
+                       if self.allowed_actions.pan_y { delta.y.get() } else { 0.0 })
+    }
+
     fn touch_count(&self) -> usize {
         self.active_touch_points.len()
     }
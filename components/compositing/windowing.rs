@@ -6,15 +6,19 @@
 
 use compositor_thread::{CompositorProxy, CompositorReceiver};
 use euclid::point::TypedPoint2D;
+use euclid::rect::TypedRect;
 use euclid::scale_factor::ScaleFactor;
 use euclid::size::TypedSize2D;
-use euclid::{Point2D, Size2D};
+use euclid::{Point2D, SideOffsets2D, Size2D};
 use layers::geometry::DevicePixel;
 use layers::platform::surface::NativeDisplay;
-use msg::constellation_msg::{Key, KeyModifiers, KeyState};
+use ipc_channel::ipc::IpcSender;
+use msg::constellation_msg::{CustomCursor, Key, KeyModifiers, KeyState, PipelineId};
+use net_traits::{DeviceChoice, DeviceKind, UrlClassifier};
 use net_traits::net_error_list::NetError;
-use script_traits::{MouseButton, TouchpadPressurePhase, TouchEventType, TouchId};
+use script_traits::{MouseButton, PageError, TouchpadPressurePhase, TouchEventType, TouchId};
 use std::fmt::{Debug, Error, Formatter};
+use std::sync::Arc;
 use style_traits::cursor::Cursor;
 use url::Url;
 use util::geometry::ScreenPx;
@@ -54,6 +58,9 @@ pub enum WindowEvent {
     TouchpadPressure(TypedPoint2D<DevicePixel, f32>, f32, TouchpadPressurePhase),
     /// Sent when you want to override the viewport.
     Viewport(TypedPoint2D<DevicePixel, u32>, TypedSize2D<DevicePixel, u32>),
+    /// Sent when an on-screen keyboard appears, disappears or changes size, with the parts of
+    /// the window that it covers, in device pixels.
+    KeyboardInsets(SideOffsets2D<f32>),
     /// Sent when a new URL is to be loaded.
     LoadUrl(String),
     /// Sent when a mouse hit test is to be performed.
@@ -77,6 +84,9 @@ pub enum WindowEvent {
     Quit,
     /// Sent when a key input state changes
     KeyEvent(Key, KeyState, KeyModifiers),
+    /// Sent when the user enables (if true) or disables tracking protection for a site, given by
+    /// its host.
+    SetTrackingProtectionForSite(String, bool),
 }
 
 impl Debug for WindowEvent {
@@ -88,6 +98,7 @@ impl Debug for WindowEvent {
             WindowEvent::Resize(..) => write!(f, "Resize"),
             WindowEvent::TouchpadPressure(..) => write!(f, "TouchpadPressure"),
             WindowEvent::Viewport(..) => write!(f, "Viewport"),
+            WindowEvent::KeyboardInsets(..) => write!(f, "KeyboardInsets"),
             WindowEvent::KeyEvent(..) => write!(f, "Key"),
             WindowEvent::LoadUrl(..) => write!(f, "LoadUrl"),
             WindowEvent::MouseWindowEventClass(..) => write!(f, "Mouse"),
@@ -99,10 +110,23 @@ impl Debug for WindowEvent {
             WindowEvent::ResetZoom => write!(f, "ResetZoom"),
             WindowEvent::Navigation(..) => write!(f, "Navigation"),
             WindowEvent::Quit => write!(f, "Quit"),
+            WindowEvent::SetTrackingProtectionForSite(..) => {
+                write!(f, "SetTrackingProtectionForSite")
+            }
         }
     }
 }
 
+/// Answers a request of a page for access to a device, with the id of the device that the user
+/// chose, if any. Dropping it without answering denies access.
+pub struct DeviceChooserResponder(pub IpcSender<Option<String>>);
+
+impl DeviceChooserResponder {
+    pub fn respond(self, device_id: Option<String>) {
+        let _ = self.0.send(device_id);
+    }
+}
+
 pub trait WindowMethods {
     /// Returns the size of the window in hardware pixels.
     fn framebuffer_size(&self) -> TypedSize2D<DevicePixel, u32>;
@@ -124,6 +148,14 @@ pub trait WindowMethods {
     fn set_page_url(&self, url: Url);
     /// Called when the browser chrome should display a status message.
     fn status(&self, Option<String>);
+    /// Called when the browser chrome should display a tooltip with the given text, next to the
+    /// given rect of the window.
+    fn show_tooltip(&self, text: String, anchor: TypedRect<DevicePixel, f32>);
+    /// Called when the browser chrome should hide the tooltip.
+    fn hide_tooltip(&self);
+    /// Called when the page of the given pipeline reports an error, such as an uncaught
+    /// exception, a content security policy violation or a `console.error` call.
+    fn page_error(&self, pipeline_id: PipelineId, error: PageError);
     /// Called when the browser has started loading a frame.
     fn load_start(&self, back: bool, forward: bool);
     /// Called when the browser is done loading a frame.
@@ -154,6 +186,9 @@ pub trait WindowMethods {
 
     /// Sets the cursor to be used in the window.
     fn set_cursor(&self, cursor: Cursor);
+    /// Sets a custom cursor image to be used in the window. Returns false if the window does not
+    /// support custom cursors, in which case `set_cursor` is called instead.
+    fn set_custom_cursor(&self, cursor: &CustomCursor) -> bool;
 
     /// Process a key event.
     fn handle_key(&self, key: Key, mods: KeyModifiers);
@@ -163,4 +198,18 @@ pub trait WindowMethods {
 
     /// Add a favicon
     fn set_favicon(&self, url: Url);
+
+    /// Returns the embedder's URL classifier, if any, to be consulted before every load instead
+    /// of Servo's local list.
+    fn url_classifier(&self) -> Option<Arc<UrlClassifier>>;
+
+    /// Sets the number of requests of the current page blocked by tracking protection.
+    fn set_blocked_request_count(&self, count: usize);
+
+    /// Called when a page requests access to a device of the given kind, for the user to choose
+    /// one of `devices`. The answer may be given later, through the responder.
+    fn choose_device(&self,
+                     kind: DeviceKind,
+                     devices: Vec<DeviceChoice>,
+                     responder: DeviceChooserResponder);
 }
@@ -17,6 +17,7 @@ use compositing::SendableFrameTree;
 use compositing::compositor_thread::CompositorProxy;
 use compositing::compositor_thread::Msg as ToCompositorMsg;
 use devtools_traits::{ChromeToDevtoolsControlMsg, DevtoolsControlMsg};
+use euclid::rect::Rect;
 use euclid::scale_factor::ScaleFactor;
 use euclid::size::{Size2D, TypedSize2D};
 use gfx::font_cache_thread::FontCacheThread;
@@ -25,28 +26,32 @@ use ipc_channel::ipc::{self, IpcSender};
 use ipc_channel::router::ROUTER;
 use layout_traits::LayoutThreadFactory;
 use msg::constellation_msg::WebDriverCommandMsg;
-use msg::constellation_msg::{FrameId, FrameType, PipelineId};
+use msg::constellation_msg::{CustomCursor, FrameId, FrameType, PipelineId};
 use msg::constellation_msg::{Key, KeyModifiers, KeyState, LoadData};
 use msg::constellation_msg::{PipelineNamespace, PipelineNamespaceId, NavigationDirection};
-use msg::constellation_msg::{SubpageId, WindowSizeData, WindowSizeType};
+use msg::constellation_msg::{Snapshot, SnapshotFormat, SubpageId, WindowSizeData, WindowSizeType};
 use msg::constellation_msg::{self, PanicMsg};
 use msg::webdriver_msg;
 use net_traits::bluetooth_thread::BluetoothMethodMsg;
 use net_traits::filemanager_thread::FileManagerThreadMsg;
 use net_traits::image_cache_thread::ImageCacheThread;
+use net_traits::reporting::Report;
 use net_traits::storage_thread::StorageThreadMsg;
+use net_traits::usb_thread::UsbMethodMsg;
 use net_traits::{self, ResourceThreads, IpcSend};
 use offscreen_gl_context::{GLContextAttributes, GLLimits};
 use pipeline::{ChildProcess, InitialPipelineState, Pipeline};
 use profile_traits::mem;
 use profile_traits::time;
 use rand::{random, Rng, SeedableRng, StdRng};
-use script_traits::{AnimationState, AnimationTickType, CompositorEvent};
+use script_traits::{AccessibilityAction, AccessibleNode, AnimationState, AnimationTickType};
+use script_traits::CompositorEvent;
 use script_traits::{ConstellationControlMsg, ConstellationMsg as FromCompositorMsg};
 use script_traits::{DocumentState, LayoutControlMsg};
 use script_traits::{IFrameLoadInfo, IFrameSandboxState, TimerEventRequest};
 use script_traits::{LayoutMsg as FromLayoutMsg, ScriptMsg as FromScriptMsg, ScriptThreadFactory};
-use script_traits::{MozBrowserEvent, MozBrowserErrorType};
+use script_traits::{MozBrowserEvent, MozBrowserErrorType, PageError};
+use script_traits::UsedFontFace;
 use std::borrow::ToOwned;
 use std::collections::HashMap;
 use std::io::Error as IOError;
@@ -55,6 +60,7 @@ use std::mem::replace;
 use std::process;
 use std::sync::mpsc::{Sender, channel, Receiver};
 use style_traits::cursor::Cursor;
+use style_traits::forced_colors::ForcedColors;
 use style_traits::viewport::ViewportConstraints;
 use timer_scheduler::TimerScheduler;
 use url::Url;
@@ -122,6 +128,9 @@ pub struct Constellation<Message, LTF, STF> {
     /// A channel through which messages can be sent to the bluetooth thread.
     bluetooth_thread: IpcSender<BluetoothMethodMsg>,
 
+    /// A channel through which messages can be sent to the USB thread.
+    usb_thread: IpcSender<UsbMethodMsg>,
+
     /// A list of all the pipelines. (See the `pipeline` module for more details.)
     pipelines: HashMap<PipelineId, Pipeline>,
 
@@ -196,6 +205,8 @@ pub struct InitialConstellationState {
     pub devtools_chan: Option<Sender<DevtoolsControlMsg>>,
     /// A channel to the bluetooth thread.
     pub bluetooth_thread: IpcSender<BluetoothMethodMsg>,
+    /// A channel to the USB thread.
+    pub usb_thread: IpcSender<UsbMethodMsg>,
     /// A channel to the image cache thread.
     pub image_cache_thread: ImageCacheThread,
     /// A channel to the font cache thread.
@@ -333,6 +344,7 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
                 compositor_proxy: state.compositor_proxy,
                 devtools_chan: state.devtools_chan,
                 bluetooth_thread: state.bluetooth_thread,
+                usb_thread: state.usb_thread,
                 resource_threads: state.resource_threads,
                 image_cache_thread: state.image_cache_thread,
                 font_cache_thread: state.font_cache_thread,
@@ -417,6 +429,7 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
             compositor_proxy: self.compositor_proxy.clone_compositor_proxy(),
             devtools_chan: self.devtools_chan.clone(),
             bluetooth_thread: self.bluetooth_thread.clone(),
+            usb_thread: self.usb_thread.clone(),
             image_cache_thread: self.image_cache_thread.clone(),
             font_cache_thread: self.font_cache_thread.clone(),
             resource_threads: self.resource_threads.clone(),
@@ -598,13 +611,48 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
                 debug!("constellation got window resize message");
                 self.handle_window_size_msg(new_size, size_type);
             }
-            FromCompositorMsg::TickAnimation(pipeline_id, tick_type) => {
-                self.handle_tick_animation(pipeline_id, tick_type)
+            FromCompositorMsg::TickAnimation(pipeline_id, tick_type, frame_time) => {
+                self.handle_tick_animation(pipeline_id, tick_type, frame_time)
             }
             FromCompositorMsg::WebDriverCommand(command) => {
                 debug!("constellation got webdriver command message");
                 self.handle_webdriver_msg(command);
             }
+            FromCompositorMsg::SetTrackingProtectionForSite(site, enabled) => {
+                debug!("constellation got tracking protection message for {}", site);
+                let msg = net_traits::CoreResourceMsg::SetTrackingProtectionForSite(site, enabled);
+                if let Err(e) = self.resource_threads.send(msg) {
+                    warn!("Sending tracking protection change to resource thread failed ({}).", e);
+                }
+            }
+            FromCompositorMsg::RenderToImage(pipeline_id, rect, format, reply) => {
+                debug!("constellation got render to image message");
+                self.handle_render_to_image_msg(pipeline_id, rect, format, reply);
+            }
+            FromCompositorMsg::GetAccessibilityTree(pipeline_id, reply) => {
+                debug!("constellation got accessibility tree message");
+                self.handle_get_accessibility_tree_msg(pipeline_id, reply);
+            }
+            FromCompositorMsg::AccessibilityAction(pipeline_id, action) => {
+                debug!("constellation got accessibility action message");
+                self.handle_accessibility_action_msg(pipeline_id, action);
+            }
+            FromCompositorMsg::SetAuthorStylesEnabled(pipeline_id, enabled) => {
+                debug!("constellation got set author styles enabled message");
+                self.handle_set_author_styles_enabled_msg(pipeline_id, enabled);
+            }
+            FromCompositorMsg::SetForcedColors(pipeline_id, forced_colors) => {
+                debug!("constellation got set forced colors message");
+                self.handle_set_forced_colors_msg(pipeline_id, forced_colors);
+            }
+            FromCompositorMsg::SetTextZoom(pipeline_id, text_zoom) => {
+                debug!("constellation got set text zoom message");
+                self.handle_set_text_zoom_msg(pipeline_id, text_zoom);
+            }
+            FromCompositorMsg::GetUsedFontFaces(pipeline_id, reply) => {
+                debug!("constellation got used font faces message");
+                self.handle_get_used_font_faces_msg(pipeline_id, reply);
+            }
         }
 
         true
@@ -738,9 +786,20 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
                 debug!("constellation got create-WebGL-paint-thread message");
                 self.handle_create_webgl_paint_thread_msg(&size, attributes, sender)
             }
-            FromScriptMsg::NodeStatus(message) => {
-                debug!("constellation got NodeStatus message");
-                self.compositor_proxy.send(ToCompositorMsg::Status(message));
+            FromScriptMsg::PageError(pipeline_id, error) => {
+                debug!("constellation got PageError message");
+                self.handle_page_error_msg(pipeline_id, error);
+            }
+            FromScriptMsg::ShowTooltip(pipeline_id, text, anchor) => {
+                debug!("constellation got ShowTooltip message");
+                // Tooltips of pipelines that are not displayed are never shown.
+                if self.pipeline_is_in_current_frame(pipeline_id) {
+                    self.handle_tooltip_msg(pipeline_id, Some((text, anchor)));
+                }
+            }
+            FromScriptMsg::HideTooltip(pipeline_id) => {
+                debug!("constellation got HideTooltip message");
+                self.handle_tooltip_msg(pipeline_id, None);
             }
             FromScriptMsg::SetDocumentState(pipeline_id, state) => {
                 debug!("constellation got SetDocumentState message");
@@ -789,6 +848,10 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
             FromScriptMsg::GetScrollOffset(pid, lid, send) => {
                 self.compositor_proxy.send(ToCompositorMsg::GetScrollOffset(pid, lid, send));
             }
+
+            FromScriptMsg::SetNodePicker(enabled) => {
+                self.compositor_proxy.send(ToCompositorMsg::SetNodePicker(enabled));
+            }
         }
     }
 
@@ -797,13 +860,21 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
             FromLayoutMsg::ChangeRunningAnimationsState(pipeline_id, animation_state) => {
                 self.handle_change_running_animations_state(pipeline_id, animation_state)
             }
-            FromLayoutMsg::SetCursor(cursor) => {
-                self.handle_set_cursor_msg(cursor)
+            FromLayoutMsg::SetCursor(cursor, custom_cursor) => {
+                self.handle_set_cursor_msg(cursor, custom_cursor)
             }
             FromLayoutMsg::ViewportConstrained(pipeline_id, constraints) => {
                 debug!("constellation got viewport-constrained event message");
                 self.handle_viewport_constrained_msg(pipeline_id, constraints);
             }
+            FromLayoutMsg::SetHitTestTree(pipeline_id, hit_test_tree) => {
+                self.compositor_proxy.send(ToCompositorMsg::SetHitTestTree(pipeline_id,
+                                                                           hit_test_tree))
+            }
+            FromLayoutMsg::SetLayerAnimations(pipeline_id, animations) => {
+                self.compositor_proxy.send(ToCompositorMsg::SetLayerAnimations(pipeline_id,
+                                                                               animations))
+            }
         }
     }
 
@@ -841,6 +912,9 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
         if let Err(e) = self.bluetooth_thread.send(BluetoothMethodMsg::Exit) {
             warn!("Exit bluetooth thread failed ({})", e);
         }
+        if let Err(e) = self.usb_thread.send(UsbMethodMsg::Exit) {
+            warn!("Exit USB thread failed ({})", e);
+        }
         self.font_cache_thread.exit();
         self.compositor_proxy.send(ToCompositorMsg::ShutdownComplete);
     }
@@ -873,6 +947,11 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
             let parent_info = self.pipelines.get(&pipeline_id).and_then(|pipeline| pipeline.parent_info);
             let window_size = self.pipelines.get(&pipeline_id).and_then(|pipeline| pipeline.size);
 
+            let msg = net_traits::CoreResourceMsg::QueueReport(pipeline_id, Report::crash(&reason));
+            if let Err(e) = self.resource_threads.send(msg) {
+                warn!("Sending crash report to resource thread failed ({}).", e);
+            }
+
             // Notify the browser chrome that the pipeline has failed
             self.trigger_mozbrowsererror(pipeline_id, reason, backtrace);
 
@@ -1034,8 +1113,49 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
         self.push_pending_frame(load_info.new_pipeline_id, old_pipeline_id);
     }
 
-    fn handle_set_cursor_msg(&mut self, cursor: Cursor) {
-        self.compositor_proxy.send(ToCompositorMsg::SetCursor(cursor))
+    /// Hands the tooltip of a pipeline to its parent, which knows where the pipeline is, or to
+    /// the compositor once it gets to the root pipeline.
+    fn handle_tooltip_msg(&mut self,
+                          pipeline_id: PipelineId,
+                          tooltip: Option<(String, Rect<f32>)>) {
+        let parent_info = self.pipelines.get(&pipeline_id)
+                                        .and_then(|pipeline| pipeline.parent_info);
+        match parent_info {
+            Some((parent_pipeline_id, subpage_id, _)) => {
+                let msg = match tooltip {
+                    Some((text, anchor)) => {
+                        ConstellationControlMsg::ShowFrameTooltip(parent_pipeline_id,
+                                                                  subpage_id,
+                                                                  text,
+                                                                  anchor)
+                    }
+                    None => ConstellationControlMsg::HideFrameTooltip(parent_pipeline_id),
+                };
+                if let Some(parent_pipeline) = self.pipelines.get(&parent_pipeline_id) {
+                    let _ = parent_pipeline.script_chan.send(msg);
+                }
+            }
+            None => {
+                let msg = match tooltip {
+                    Some((text, anchor)) => ToCompositorMsg::ShowTooltip(text, anchor),
+                    None => ToCompositorMsg::HideTooltip,
+                };
+                self.compositor_proxy.send(msg);
+            }
+        }
+    }
+
+    /// Hands an error of a page to the compositor, which reports it to the embedder.
+    fn handle_page_error_msg(&mut self, pipeline_id: PipelineId, error: PageError) {
+        if opts::get().fail_on_page_error {
+            error!("Pipeline {:?} reported a page error: {:?}.  Exiting!", pipeline_id, error);
+            process::exit(1);
+        }
+        self.compositor_proxy.send(ToCompositorMsg::PageError(pipeline_id, error));
+    }
+
+    fn handle_set_cursor_msg(&mut self, cursor: Cursor, custom_cursor: Option<CustomCursor>) {
+        self.compositor_proxy.send(ToCompositorMsg::SetCursor(cursor, custom_cursor))
     }
 
     fn handle_change_running_animations_state(&mut self,
@@ -1045,17 +1165,20 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
                                                                                animation_state))
     }
 
-    fn handle_tick_animation(&mut self, pipeline_id: PipelineId, tick_type: AnimationTickType) {
+    fn handle_tick_animation(&mut self,
+                             pipeline_id: PipelineId,
+                             tick_type: AnimationTickType,
+                             frame_time: f64) {
         let result = match tick_type {
             AnimationTickType::Script => {
-                let msg = ConstellationControlMsg::TickAllAnimations(pipeline_id);
+                let msg = ConstellationControlMsg::TickAllAnimations(pipeline_id, frame_time);
                 match self.pipelines.get(&pipeline_id) {
                     Some(pipeline) => pipeline.script_chan.send(msg),
                     None => return warn!("Pipeline {:?} got script tick after closure.", pipeline_id),
                 }
             }
             AnimationTickType::Layout => {
-                let msg = LayoutControlMsg::TickAnimations;
+                let msg = LayoutControlMsg::TickAnimations(frame_time);
                 match self.pipelines.get(&pipeline_id) {
                     Some(pipeline) => pipeline.layout_chan.send(msg),
                     None => return warn!("Pipeline {:?} got script tick after closure.", pipeline_id),
@@ -1511,18 +1634,122 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
                     }
                 }
             },
-            WebDriverCommandMsg::TakeScreenshot(pipeline_id, reply) => {
-                let current_pipeline_id = self.root_frame_id
-                    .and_then(|root_frame_id| self.frames.get(&root_frame_id))
-                    .map(|root_frame| root_frame.current);
-                if Some(pipeline_id) == current_pipeline_id {
-                    self.compositor_proxy.send(ToCompositorMsg::CreatePng(reply));
-                } else {
-                    if let Err(e) = reply.send(None) {
-                        warn!("Screenshot reply failed ({})", e);
-                    }
+        }
+    }
+
+    fn handle_render_to_image_msg(&mut self,
+                                  pipeline_id: PipelineId,
+                                  rect: Option<Rect<f32>>,
+                                  format: SnapshotFormat,
+                                  reply: IpcSender<Option<Snapshot>>) {
+        // Only the root pipeline is composited on its own.
+        let current_pipeline_id = self.root_frame_id
+            .and_then(|root_frame_id| self.frames.get(&root_frame_id))
+            .map(|root_frame| root_frame.current);
+        if Some(pipeline_id) == current_pipeline_id {
+            self.compositor_proxy.send(ToCompositorMsg::RenderToImage(rect, format, reply));
+        } else {
+            if let Err(e) = reply.send(None) {
+                warn!("Render to image reply failed ({})", e);
+            }
+        }
+    }
+
+    fn handle_get_accessibility_tree_msg(&mut self,
+                                         pipeline_id: PipelineId,
+                                         reply: IpcSender<Option<AccessibleNode>>) {
+        let result = match self.pipelines.get(&pipeline_id) {
+            None => {
+                if let Err(e) = reply.send(None) {
+                    warn!("Accessibility tree reply failed ({})", e);
                 }
-            },
+                return
+            }
+            Some(pipeline) => {
+                let msg = ConstellationControlMsg::GetAccessibilityTree(pipeline_id, reply);
+                pipeline.script_chan.send(msg)
+            }
+        };
+        if let Err(e) = result {
+            self.handle_send_error(pipeline_id, e);
+        }
+    }
+
+    fn handle_accessibility_action_msg(&mut self,
+                                       pipeline_id: PipelineId,
+                                       action: AccessibilityAction) {
+        // The pipeline may have been torn down since the tree that the action is about was
+        // sent, in which case the action is dropped.
+        let result = match self.pipelines.get(&pipeline_id) {
+            None => return,
+            Some(pipeline) => {
+                let msg = ConstellationControlMsg::AccessibilityAction(pipeline_id, action);
+                pipeline.script_chan.send(msg)
+            }
+        };
+        if let Err(e) = result {
+            self.handle_send_error(pipeline_id, e);
+        }
+    }
+
+    fn handle_set_author_styles_enabled_msg(&mut self, pipeline_id: PipelineId, enabled: bool) {
+        let result = match self.pipelines.get(&pipeline_id) {
+            None => return warn!("Pipeline {:?} toggled author styles after closure.", pipeline_id),
+            Some(pipeline) => {
+                let msg = ConstellationControlMsg::SetAuthorStylesEnabled(pipeline_id, enabled);
+                pipeline.script_chan.send(msg)
+            }
+        };
+        if let Err(e) = result {
+            self.handle_send_error(pipeline_id, e);
+        }
+    }
+
+    fn handle_set_forced_colors_msg(&mut self,
+                                    pipeline_id: PipelineId,
+                                    forced_colors: Option<ForcedColors>) {
+        let result = match self.pipelines.get(&pipeline_id) {
+            None => return warn!("Pipeline {:?} forced colors after closure.", pipeline_id),
+            Some(pipeline) => {
+                let msg = ConstellationControlMsg::SetForcedColors(pipeline_id, forced_colors);
+                pipeline.script_chan.send(msg)
+            }
+        };
+        if let Err(e) = result {
+            self.handle_send_error(pipeline_id, e);
+        }
+    }
+
+    fn handle_set_text_zoom_msg(&mut self, pipeline_id: PipelineId, text_zoom: f32) {
+        let result = match self.pipelines.get(&pipeline_id) {
+            None => return warn!("Pipeline {:?} zoomed text after closure.", pipeline_id),
+            Some(pipeline) => {
+                let msg = ConstellationControlMsg::SetTextZoom(pipeline_id, text_zoom);
+                pipeline.script_chan.send(msg)
+            }
+        };
+        if let Err(e) = result {
+            self.handle_send_error(pipeline_id, e);
+        }
+    }
+
+    fn handle_get_used_font_faces_msg(&mut self,
+                                      pipeline_id: PipelineId,
+                                      reply: IpcSender<Option<Vec<UsedFontFace>>>) {
+        let result = match self.pipelines.get(&pipeline_id) {
+            None => {
+                if let Err(e) = reply.send(None) {
+                    warn!("Used font faces reply failed ({})", e);
+                }
+                return
+            }
+            Some(pipeline) => {
+                let msg = ConstellationControlMsg::GetUsedFontFaces(pipeline_id, reply);
+                pipeline.script_chan.send(msg)
+            }
+        };
+        if let Err(e) = result {
+            self.handle_send_error(pipeline_id, e);
         }
     }
 
@@ -1798,9 +2025,13 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
                         }
                         match epoch_receiver.recv() {
                             Err(e) => warn!("Failed to receive current epoch ({}).", e),
-                            Ok(layout_thread_epoch) => if layout_thread_epoch != *compositor_epoch {
-                                return ReadyToSave::EpochMismatch;
-                            },
+                            Ok(token) => {
+                                if token.epoch != *compositor_epoch {
+                                    return ReadyToSave::EpochMismatch;
+                                }
+                                debug!("Pipeline {:?} shows DOM version {} at epoch {:?}.",
+                                       pipeline_id, token.dom_version, token.epoch);
+                            }
                         }
                     }
                     None => {
@@ -1908,6 +2139,11 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
         // Remove assocation between this pipeline and its holding frame
         self.pipeline_to_frame_map.remove(&pipeline_id);
 
+        let msg = net_traits::CoreResourceMsg::ClearReportingEndpoints(pipeline_id);
+        if let Err(e) = self.resource_threads.send(msg) {
+            warn!("Clearing reporting endpoints failed ({}).", e);
+        }
+
         // Remove this pipeline from pending frames if it hasn't loaded yet.
         let pending_index = self.pending_frames.iter().position(|frame_change| {
             frame_change.new_pipeline_id == pipeline_id
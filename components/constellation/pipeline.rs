@@ -21,6 +21,7 @@ use msg::constellation_msg::{PipelineNamespaceId, SubpageId, WindowSizeData};
 use net_traits::ResourceThreads;
 use net_traits::bluetooth_thread::BluetoothMethodMsg;
 use net_traits::image_cache_thread::ImageCacheThread;
+use net_traits::usb_thread::UsbMethodMsg;
 use profile_traits::mem as profile_mem;
 use profile_traits::time;
 use script_traits::{ConstellationControlMsg, InitialScriptState, MozBrowserEvent};
@@ -93,6 +94,8 @@ pub struct InitialPipelineState {
     pub devtools_chan: Option<Sender<DevtoolsControlMsg>>,
     /// A channel to the bluetooth thread.
     pub bluetooth_thread: IpcSender<BluetoothMethodMsg>,
+    /// A channel to the USB thread.
+    pub usb_thread: IpcSender<UsbMethodMsg>,
     /// A channel to the image cache thread.
     pub image_cache_thread: ImageCacheThread,
     /// A channel to the font cache thread.
@@ -217,6 +220,7 @@ impl Pipeline {
                 scheduler_chan: state.scheduler_chan,
                 devtools_chan: script_to_devtools_chan,
                 bluetooth_thread: state.bluetooth_thread,
+                usb_thread: state.usb_thread,
                 image_cache_thread: state.image_cache_thread,
                 font_cache_thread: state.font_cache_thread,
                 resource_threads: state.resource_threads,
@@ -395,6 +399,7 @@ pub struct UnprivilegedPipelineContent {
     scheduler_chan: IpcSender<TimerEventRequest>,
     devtools_chan: Option<IpcSender<ScriptToDevtoolsControlMsg>>,
     bluetooth_thread: IpcSender<BluetoothMethodMsg>,
+    usb_thread: IpcSender<UsbMethodMsg>,
     image_cache_thread: ImageCacheThread,
     font_cache_thread: FontCacheThread,
     resource_threads: ResourceThreads,
@@ -432,6 +437,7 @@ impl UnprivilegedPipelineContent {
             scheduler_chan: self.scheduler_chan,
             panic_chan: self.panic_chan.clone(),
             bluetooth_thread: self.bluetooth_thread,
+            usb_thread: self.usb_thread,
             resource_threads: self.resource_threads,
             image_cache_thread: self.image_cache_thread.clone(),
             time_profiler_chan: self.time_profiler_chan.clone(),
@@ -450,7 +456,7 @@ impl UnprivilegedPipelineContent {
                     self.layout_to_constellation_chan,
                     self.panic_chan,
                     self.script_chan,
-                    self.layout_to_paint_chan,
+                    Some(self.layout_to_paint_chan),
                     self.image_cache_thread,
                     self.font_cache_thread,
                     self.time_profiler_chan,
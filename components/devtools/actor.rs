@@ -166,6 +166,12 @@ impl ActorRegistry {
                 }
             }
         }
+        self.apply_later_changes();
+        Ok(())
+    }
+
+    /// Registers and drops the actors queued with `register_later` and `drop_actor_later`.
+    pub fn apply_later_changes(&mut self) {
         let new_actors = replace(&mut *self.new_actors.borrow_mut(), vec!());
         for actor in new_actors.into_iter() {
             self.actors.insert(actor.name().to_owned(), actor);
@@ -175,7 +181,6 @@ impl ActorRegistry {
         for name in old_actors {
             self.drop_actor(name);
         }
-        Ok(())
     }
 
     pub fn drop_actor(&mut self, name: String) {
@@ -0,0 +1,217 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Liberally derived from the [Firefox JS implementation]
+//! (http://mxr.mozilla.org/mozilla-central/source/devtools/server/actors/animation.js).
+//! Lists the running CSS transitions of a tab and lets them be paused, resumed and seeked.
+
+use actor::{Actor, ActorMessageStatus, ActorRegistry};
+use devtools_traits::DevtoolScriptControlMsg::{GetRunningAnimations, SeekAnimation, SetAnimationPaused};
+use devtools_traits::{AnimationInfo, DevtoolScriptControlMsg};
+use ipc_channel::ipc::{self, IpcSender};
+use msg::constellation_msg::PipelineId;
+use protocol::JsonPacketStream;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::net::TcpStream;
+
+#[derive(Serialize)]
+struct GetAnimationPlayersReply {
+    from: String,
+    players: Vec<AnimationPlayerMsg>,
+}
+
+#[derive(Serialize)]
+struct AnimationPlayerMsg {
+    actor: String,
+    state: AnimationStateMsg,
+}
+
+#[derive(Serialize)]
+struct AnimationStateMsg {
+    #[serde(rename = "type")]
+    type_: String,
+    name: String,
+    /// The node actor of the animated node, if the inspector has seen it.
+    node: Option<String>,
+    duration: f64,
+    currentTime: f64,
+    playState: String,
+}
+
+#[derive(Serialize)]
+struct GetCurrentStateReply {
+    from: String,
+    state: AnimationStateMsg,
+}
+
+#[derive(Serialize)]
+struct ActionReply {
+    from: String,
+}
+
+/// Queries the running animations of the given pipeline.
+fn running_animations(script_chan: &IpcSender<DevtoolScriptControlMsg>,
+                      pipeline: PipelineId)
+                      -> Vec<AnimationInfo> {
+    let (tx, rx) = ipc::channel().unwrap();
+    script_chan.send(GetRunningAnimations(pipeline, tx)).unwrap();
+    rx.recv().unwrap()
+}
+
+fn encode_state(registry: &ActorRegistry, animation: &AnimationInfo) -> AnimationStateMsg {
+    let node = if registry.script_actor_registered(animation.node.clone()) {
+        Some(registry.script_to_actor(animation.node.clone()))
+    } else {
+        None
+    };
+    AnimationStateMsg {
+        // Servo only supports CSS transitions so far.
+        type_: "csstransition".to_owned(),
+        name: animation.property.clone(),
+        node: node,
+        duration: animation.duration,
+        currentTime: animation.currentTime,
+        playState: if animation.paused { "paused" } else { "running" }.to_owned(),
+    }
+}
+
+pub struct AnimationsActor {
+    pub name: String,
+    pub script_chan: IpcSender<DevtoolScriptControlMsg>,
+    pub pipeline: PipelineId,
+}
+
+impl Actor for AnimationsActor {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn handle_message(&self,
+                      registry: &ActorRegistry,
+                      msg_type: &str,
+                      msg: &BTreeMap<String, Value>,
+                      stream: &mut TcpStream) -> Result<ActorMessageStatus, ()> {
+        Ok(match msg_type {
+            // Without a node, the players of every animation in the tab are returned.
+            "getAnimationPlayersForNode" => {
+                let node = msg.get("node").and_then(&Value::as_string).map(|node| {
+                    registry.actor_to_script(node.to_owned())
+                });
+
+                let players = running_animations(&self.script_chan, self.pipeline)
+                    .into_iter()
+                    .filter(|animation| node.as_ref().map_or(true, |node| *node == animation.node))
+                    .map(|animation| {
+                        let actor = AnimationPlayerActor {
+                            name: registry.new_name("animationplayer"),
+                            script_chan: self.script_chan.clone(),
+                            pipeline: self.pipeline,
+                            node: animation.node.clone(),
+                            property: animation.property.clone(),
+                        };
+                        let msg = AnimationPlayerMsg {
+                            actor: actor.name(),
+                            state: encode_state(registry, &animation),
+                        };
+                        registry.register_later(box actor);
+                        msg
+                    }).collect();
+
+                let msg = GetAnimationPlayersReply {
+                    from: self.name(),
+                    players: players,
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
+            "pauseAll" | "playAll" => {
+                for animation in running_animations(&self.script_chan, self.pipeline) {
+                    self.script_chan.send(SetAnimationPaused(self.pipeline,
+                                                             animation.node,
+                                                             animation.property,
+                                                             msg_type == "pauseAll")).unwrap();
+                }
+                stream.write_json_packet(&ActionReply { from: self.name() });
+                ActorMessageStatus::Processed
+            }
+
+            _ => ActorMessageStatus::Ignored,
+        })
+    }
+}
+
+/// An animation of one property of one node.
+pub struct AnimationPlayerActor {
+    pub name: String,
+    pub script_chan: IpcSender<DevtoolScriptControlMsg>,
+    pub pipeline: PipelineId,
+    /// The unique id of the animated node.
+    pub node: String,
+    pub property: String,
+}
+
+impl Actor for AnimationPlayerActor {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn handle_message(&self,
+                      registry: &ActorRegistry,
+                      msg_type: &str,
+                      msg: &BTreeMap<String, Value>,
+                      stream: &mut TcpStream) -> Result<ActorMessageStatus, ()> {
+        Ok(match msg_type {
+            "getCurrentState" => {
+                let animation = running_animations(&self.script_chan, self.pipeline)
+                    .into_iter()
+                    .find(|animation| animation.node == self.node &&
+                                      animation.property == self.property);
+
+                // Animations that are no longer running have finished.
+                let state = match animation {
+                    Some(ref animation) => encode_state(registry, animation),
+                    None => AnimationStateMsg {
+                        type_: "csstransition".to_owned(),
+                        name: self.property.clone(),
+                        node: None,
+                        duration: 0.,
+                        currentTime: 0.,
+                        playState: "finished".to_owned(),
+                    },
+                };
+
+                let msg = GetCurrentStateReply {
+                    from: self.name(),
+                    state: state,
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
+            "pause" | "play" => {
+                self.script_chan.send(SetAnimationPaused(self.pipeline,
+                                                         self.node.clone(),
+                                                         self.property.clone(),
+                                                         msg_type == "pause")).unwrap();
+                stream.write_json_packet(&ActionReply { from: self.name() });
+                ActorMessageStatus::Processed
+            }
+
+            // The time is in milliseconds since the start of the animation.
+            "setCurrentTime" => {
+                let time = try!(msg.get("currentTime").and_then(&Value::as_f64).ok_or(()));
+                self.script_chan.send(SeekAnimation(self.pipeline,
+                                                    self.node.clone(),
+                                                    self.property.clone(),
+                                                    time)).unwrap();
+                stream.write_json_packet(&ActionReply { from: self.name() });
+                ActorMessageStatus::Processed
+            }
+
+            _ => ActorMessageStatus::Ignored,
+        })
+    }
+}
@@ -6,9 +6,15 @@
 //! (http://mxr.mozilla.org/mozilla-central/source/toolkit/devtools/server/actors/inspector.js).
 
 use actor::{Actor, ActorMessageStatus, ActorRegistry};
+use devtools_traits::DevtoolScriptControlMsg::SetNodePicker;
 use devtools_traits::DevtoolScriptControlMsg::{GetChildren, GetDocumentElement, GetRootNode};
-use devtools_traits::DevtoolScriptControlMsg::{GetLayout, ModifyAttribute};
-use devtools_traits::{ComputedNodeLayout, DevtoolScriptControlMsg, NodeInfo};
+use devtools_traits::DevtoolScriptControlMsg::{GetBoxModel, GetFragmentTree, GetLayout};
+use devtools_traits::DevtoolScriptControlMsg::GetUsedFontFaces;
+use devtools_traits::DevtoolScriptControlMsg::GetMatchedRules;
+use devtools_traits::DevtoolScriptControlMsg::HighlightNode;
+use devtools_traits::DevtoolScriptControlMsg::ModifyAttribute;
+use devtools_traits::{BoxModelInfo, ComputedNodeLayout, DevtoolScriptControlMsg, MatchedRuleInfo};
+use devtools_traits::NodeInfo;
 use ipc_channel::ipc::{self, IpcSender};
 use msg::constellation_msg::PipelineId;
 use protocol::JsonPacketStream;
@@ -39,6 +45,8 @@ struct HighlighterMsg {
 
 struct HighlighterActor {
     name: String,
+    script_chan: IpcSender<DevtoolScriptControlMsg>,
+    pipeline: PipelineId,
 }
 
 pub struct NodeActor {
@@ -57,18 +65,27 @@ struct HideBoxModelReply {
     from: String,
 }
 
+#[derive(Serialize)]
+struct GetBoxModelReply {
+    from: String,
+    boxModel: Option<BoxModelInfo>,
+}
+
 impl Actor for HighlighterActor {
     fn name(&self) -> String {
         self.name.clone()
     }
 
     fn handle_message(&self,
-                      _registry: &ActorRegistry,
+                      registry: &ActorRegistry,
                       msg_type: &str,
-                      _msg: &BTreeMap<String, Value>,
+                      msg: &BTreeMap<String, Value>,
                       stream: &mut TcpStream) -> Result<ActorMessageStatus, ()> {
         Ok(match msg_type {
             "showBoxModel" => {
+                let node = try!(msg.get("node").and_then(&Value::as_string).ok_or(()));
+                let node_id = registry.actor_to_script(node.to_owned());
+                self.script_chan.send(HighlightNode(self.pipeline, Some(node_id))).unwrap();
                 let msg = ShowBoxModelReply {
                     from: self.name(),
                 };
@@ -77,6 +94,7 @@ impl Actor for HighlighterActor {
             }
 
             "hideBoxModel" => {
+                self.script_chan.send(HighlightNode(self.pipeline, None)).unwrap();
                 let msg = HideBoxModelReply {
                     from: self.name(),
                 };
@@ -84,6 +102,19 @@ impl Actor for HighlighterActor {
                 ActorMessageStatus::Processed
             }
 
+            "getBoxModel" => {
+                let node = try!(msg.get("node").and_then(&Value::as_string).ok_or(()));
+                let node_id = registry.actor_to_script(node.to_owned());
+                let (tx, rx) = ipc::channel().unwrap();
+                self.script_chan.send(GetBoxModel(self.pipeline, node_id, tx)).unwrap();
+                let msg = GetBoxModelReply {
+                    from: self.name(),
+                    boxModel: rx.recv().unwrap(),
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
             _ => ActorMessageStatus::Ignored,
         })
     }
@@ -238,10 +269,12 @@ impl NodeInfoToProtocol for NodeInfo {
     }
 }
 
-struct WalkerActor {
+pub struct WalkerActor {
     name: String,
     script_chan: IpcSender<DevtoolScriptControlMsg>,
     pipeline: PipelineId,
+    /// The stream of the client waiting for a node to be picked, if any.
+    picker_stream: RefCell<Option<TcpStream>>,
 }
 
 #[derive(Serialize)]
@@ -249,6 +282,19 @@ struct QuerySelectorReply {
     from: String,
 }
 
+#[derive(Serialize)]
+struct PickReply {
+    from: String,
+}
+
+#[derive(Serialize)]
+struct PickerNodePickedMsg {
+    from: String,
+    #[serde(rename = "type")]
+    type_: String,
+    node: NodeActorMsg,
+}
+
 #[derive(Serialize)]
 struct DocumentElementReply {
     from: String,
@@ -287,6 +333,21 @@ impl Actor for WalkerActor {
                 ActorMessageStatus::Processed
             }
 
+            // The picked node is sent later as a `pickerNodePicked` event.
+            "pick" => {
+                *self.picker_stream.borrow_mut() = Some(stream.try_clone().unwrap());
+                self.script_chan.send(SetNodePicker(self.pipeline, true)).unwrap();
+                stream.write_json_packet(&PickReply { from: self.name() });
+                ActorMessageStatus::Processed
+            }
+
+            "cancelPick" => {
+                *self.picker_stream.borrow_mut() = None;
+                self.script_chan.send(SetNodePicker(self.pipeline, false)).unwrap();
+                stream.write_json_packet(&PickReply { from: self.name() });
+                ActorMessageStatus::Processed
+            }
+
             "documentElement" => {
                 let (tx, rx) = ipc::channel().unwrap();
                 self.script_chan.send(GetDocumentElement(self.pipeline, tx)).unwrap();
@@ -360,12 +421,40 @@ struct GetAppliedReply {
     from: String,
 }
 
+#[derive(Serialize)]
+struct GetFragmentTreeReply {
+    from: String,
+    /// The flows of the last layout, with their fragments and the children flows, or null if
+    /// nothing has been laid out yet.
+    fragmentTree: Value,
+}
+
+#[derive(Serialize)]
+struct FontFaceForm {
+    name: String,
+    CSSFamilyName: String,
+    /// The URL of a web font, or an empty string for the fonts of the system.
+    URI: String,
+    glyphCount: usize,
+}
+
+#[derive(Serialize)]
+struct GetUsedFontFacesReply {
+    from: String,
+    fontFaces: Vec<FontFaceForm>,
+}
+
 #[derive(Serialize)]
 struct GetComputedReply {
     computed: Vec<u32>, //XXX all css props
     from: String,
 }
 
+/// The pseudo-elements that the rules panel lists the rules of along with those of a node, when
+/// they exist.
+const PSEUDO_ELEMENTS: &'static [&'static str] =
+    &["::before", "::after", "::first-line", "::marker"];
+
 #[derive(Serialize)]
 struct AppliedEntry {
     rule: String,
@@ -443,6 +532,16 @@ struct GetLayoutReply {
     height: f32,
 }
 
+/// Serializes the declarations of a matched rule as the body of a style rule.
+fn css_text(rule: &MatchedRuleInfo) -> String {
+    rule.declarations.iter().map(|declaration| {
+        format!("{}: {}{};",
+                declaration.name,
+                declaration.value,
+                if rule.important { " !important" } else { "" })
+    }).collect::<Vec<_>>().join(" ")
+}
+
 impl Actor for PageStyleActor {
     fn name(&self) -> String {
         self.name.clone()
@@ -455,10 +554,51 @@ impl Actor for PageStyleActor {
                       stream: &mut TcpStream) -> Result<ActorMessageStatus, ()> {
         Ok(match msg_type {
             "getApplied" => {
-                //TODO: query script for relevant applied styles to node (msg.node)
+                let target = msg.get("node").unwrap().as_string().unwrap();
+                let node = registry.actor_to_script(target.to_owned());
+
+                // The rules panel lists those of the pseudo-elements that exist first, then those
+                // of the node, and in each group the rules that win the cascade first.
+                let mut matched_rules = vec![];
+                for pseudo in PSEUDO_ELEMENTS.iter().map(|&pseudo| Some(pseudo)).chain(Some(None)) {
+                    let (tx, rx) = ipc::channel().unwrap();
+                    self.script_chan.send(GetMatchedRules(self.pipeline,
+                                                          node.clone(),
+                                                          pseudo.map(|pseudo| pseudo.to_owned()),
+                                                          tx))
+                                    .unwrap();
+                    if let Some(pseudo_rules) = rx.recv().unwrap() {
+                        matched_rules.extend(pseudo_rules.into_iter().rev().map(|rule| {
+                            (pseudo, rule)
+                        }));
+                    }
+                }
+
+                let mut entries = vec![];
+                let mut rules = vec![];
+                for (pseudo, rule) in matched_rules {
+                    let name = registry.new_name("rule");
+                    entries.push(AppliedEntry {
+                        rule: name.clone(),
+                        pseudoElement: pseudo.map_or(Value::Null, |pseudo| {
+                            Value::String(pseudo.to_owned())
+                        }),
+                        isSystem: rule.origin == "user-agent",
+                        matchedSelectors: vec![rule.selectorText.clone()],
+                    });
+                    rules.push(AppliedRule {
+                        actor: name,
+                        type_: "1".to_owned(),
+                        href: rule.href.clone(),
+                        cssText: css_text(&rule),
+                        line: rule.line as u32,
+                        column: rule.column as u32,
+                        parentStyleSheet: String::new(),
+                    });
+                }
                 let msg = GetAppliedReply {
-                    entries: vec!(),
-                    rules: vec!(),
+                    entries: entries,
+                    rules: rules,
                     sheets: vec!(),
                     from: self.name(),
                 };
@@ -534,11 +674,60 @@ impl Actor for PageStyleActor {
                 ActorMessageStatus::Processed
             }
 
+            "getFragmentTree" => {
+                let (tx, rx) = ipc::channel().unwrap();
+                self.script_chan.send(GetFragmentTree(self.pipeline, tx)).unwrap();
+                let fragment_tree = rx.recv().unwrap().map_or(Value::Null, |fragment_tree| {
+                    serde_json::from_str(&fragment_tree).unwrap()
+                });
+                let msg = GetFragmentTreeReply {
+                    from: self.name(),
+                    fragmentTree: fragment_tree,
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
+            "getUsedFontFaces" => {
+                let (tx, rx) = ipc::channel().unwrap();
+                self.script_chan.send(GetUsedFontFaces(self.pipeline, tx)).unwrap();
+                let font_faces = rx.recv().unwrap().into_iter().map(|font_face| {
+                    FontFaceForm {
+                        name: font_face.name,
+                        CSSFamilyName: font_face.familyName,
+                        URI: font_face.url,
+                        glyphCount: font_face.glyphCount,
+                    }
+                }).collect();
+                let msg = GetUsedFontFacesReply {
+                    from: self.name(),
+                    fontFaces: font_faces,
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
             _ => ActorMessageStatus::Ignored,
         })
     }
 }
 
+impl WalkerActor {
+    /// Notifies the client that started picking of the node it picked.
+    pub fn node_picked(&self, registry: &ActorRegistry, info: NodeInfo) {
+        let mut stream = match self.picker_stream.borrow_mut().take() {
+            Some(stream) => stream,
+            None => return,
+        };
+        let msg = PickerNodePickedMsg {
+            from: self.name(),
+            type_: "pickerNodePicked".to_owned(),
+            node: info.encode(registry, true, self.script_chan.clone(), self.pipeline),
+        };
+        stream.write_json_packet(&msg);
+    }
+}
+
 impl Actor for InspectorActor {
     fn name(&self) -> String {
         self.name.clone()
@@ -556,6 +745,7 @@ impl Actor for InspectorActor {
                         name: registry.new_name("walker"),
                         script_chan: self.script_chan.clone(),
                         pipeline: self.pipeline,
+                        picker_stream: RefCell::new(None),
                     };
                     let mut walker_name = self.walker.borrow_mut();
                     *walker_name = Some(walker.name());
@@ -608,6 +798,8 @@ impl Actor for InspectorActor {
                 if self.highlighter.borrow().is_none() {
                     let highlighter_actor = HighlighterActor {
                         name: registry.new_name("highlighter"),
+                        script_chan: self.script_chan.clone(),
+                        pipeline: self.pipeline,
                     };
                     let mut highlighter = self.highlighter.borrow_mut();
                     *highlighter = Some(highlighter_actor.name());
@@ -0,0 +1,285 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Liberally derived from the [Firefox JS implementation]
+//! (http://mxr.mozilla.org/mozilla-central/source/devtools/server/actors/stylesheets.js).
+//! Lists the stylesheets of a tab, and maps locations in them back to their original sources
+//! through source maps. Also reports which rules of each stylesheet have been used when CSS
+//! coverage tracking is enabled, and edits rules as they are changed in the rules panel.
+
+use actor::{Actor, ActorMessageStatus, ActorRegistry};
+use devtools_traits::DevtoolScriptControlMsg::{GetCSSCoverage, GetStyleSheets, ModifyStyleRule};
+use devtools_traits::{DevtoolScriptControlMsg, StyleRuleModification, StyleSheetCoverage};
+use devtools_traits::StyleSheetInfo;
+use ipc_channel::ipc::{self, IpcSender};
+use msg::constellation_msg::PipelineId;
+use protocol::JsonPacketStream;
+use serde_json::Value;
+use source_map::SourceMap;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::net::TcpStream;
+
+#[derive(Serialize)]
+struct GetStyleSheetsReply {
+    from: String,
+    styleSheets: Vec<StyleSheetMsg>,
+}
+
+#[derive(Serialize)]
+struct StyleSheetMsg {
+    actor: String,
+    href: Option<String>,
+    disabled: bool,
+    styleSheetIndex: usize,
+    ruleCount: usize,
+    sourceMapURL: Option<String>,
+}
+
+#[derive(Serialize)]
+struct GetCoverageReply {
+    from: String,
+    /// Whether the `layout.css.coverage.enabled` pref is set; no coverage is reported otherwise.
+    enabled: bool,
+    coverage: Vec<StyleSheetCoverage>,
+}
+
+#[derive(Serialize)]
+struct GetOriginalSourcesReply {
+    from: String,
+    originalSources: Vec<OriginalSourceMsg>,
+}
+
+#[derive(Serialize)]
+struct OriginalSourceMsg {
+    url: String,
+}
+
+#[derive(Serialize)]
+struct ModifyRuleReply {
+    from: String,
+    /// Whether the rule was found and its new selectors parsed.
+    modified: bool,
+}
+
+#[derive(Serialize)]
+struct GetOriginalLocationReply {
+    from: String,
+    fromSourceMap: bool,
+    source: Option<String>,
+    line: u32,
+    column: u32,
+}
+
+pub struct StyleSheetsActor {
+    pub name: String,
+    pub script_chan: IpcSender<DevtoolScriptControlMsg>,
+    pub pipeline: PipelineId,
+}
+
+impl Actor for StyleSheetsActor {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn handle_message(&self,
+                      registry: &ActorRegistry,
+                      msg_type: &str,
+                      _msg: &BTreeMap<String, Value>,
+                      stream: &mut TcpStream) -> Result<ActorMessageStatus, ()> {
+        Ok(match msg_type {
+            "getStyleSheets" => {
+                let (tx, rx) = ipc::channel().unwrap();
+                self.script_chan.send(GetStyleSheets(self.pipeline, tx)).unwrap();
+                let style_sheets = rx.recv().unwrap();
+
+                let msg = GetStyleSheetsReply {
+                    from: self.name(),
+                    styleSheets: style_sheets.into_iter().enumerate().map(|(index, info)| {
+                        let actor = StyleSheetActor::new(registry.new_name("stylesheet"),
+                                                         info,
+                                                         index,
+                                                         self.script_chan.clone(),
+                                                         self.pipeline);
+                        let msg = actor.encodable();
+                        registry.register_later(box actor);
+                        msg
+                    }).collect(),
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
+            // The stylesheets are listed in the same order as for `getStyleSheets`.
+            "getCoverage" => {
+                let (tx, rx) = ipc::channel().unwrap();
+                self.script_chan.send(GetCSSCoverage(self.pipeline, tx)).unwrap();
+                let coverage = rx.recv().unwrap();
+
+                let msg = GetCoverageReply {
+                    from: self.name(),
+                    enabled: coverage.is_some(),
+                    coverage: coverage.unwrap_or(vec![]),
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
+            _ => ActorMessageStatus::Ignored,
+        })
+    }
+}
+
+pub struct StyleSheetActor {
+    pub name: String,
+    pub info: StyleSheetInfo,
+    /// The index of this stylesheet in document order.
+    index: usize,
+    script_chan: IpcSender<DevtoolScriptControlMsg>,
+    pipeline: PipelineId,
+    /// The source map of this stylesheet, fetched when first needed.
+    source_map: RefCell<Option<Result<SourceMap, ()>>>,
+}
+
+impl Actor for StyleSheetActor {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn handle_message(&self,
+                      _registry: &ActorRegistry,
+                      msg_type: &str,
+                      msg: &BTreeMap<String, Value>,
+                      stream: &mut TcpStream) -> Result<ActorMessageStatus, ()> {
+        Ok(match msg_type {
+            "getOriginalSources" => {
+                let original_sources = self.with_source_map(|source_map| {
+                    source_map.sources.iter().map(|url| {
+                        OriginalSourceMsg {
+                            url: url.clone(),
+                        }
+                    }).collect()
+                }).unwrap_or(vec![]);
+
+                let msg = GetOriginalSourcesReply {
+                    from: self.name(),
+                    originalSources: original_sources,
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
+            // Lines are one-based and columns zero-based, as in the Firefox protocol.
+            "getOriginalLocation" => {
+                let line = msg.get("line").and_then(&Value::as_u64).unwrap_or(1) as u32;
+                let column = msg.get("column").and_then(&Value::as_u64).unwrap_or(0) as u32;
+
+                let original = self.with_source_map(|source_map| {
+                    source_map.original_location(line.saturating_sub(1), column)
+                }).and_then(|location| location);
+
+                let msg = match original {
+                    Some(location) => GetOriginalLocationReply {
+                        from: self.name(),
+                        fromSourceMap: true,
+                        source: Some(location.source),
+                        line: location.line + 1,
+                        column: location.column,
+                    },
+                    None => GetOriginalLocationReply {
+                        from: self.name(),
+                        fromSourceMap: false,
+                        source: self.info.href.clone(),
+                        line: line,
+                        column: column,
+                    },
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
+            // The rule is found by where its selectors start, as `getApplied` reports it, with
+            // both the line and the column one-based. Selectors or declarations that are left out
+            // are kept.
+            "modifyRule" => {
+                let line = try!(msg.get("line").and_then(&Value::as_u64).ok_or(())) as usize;
+                let column = try!(msg.get("column").and_then(&Value::as_u64).ok_or(())) as usize;
+                let modification = StyleRuleModification {
+                    selectorText: msg.get("selectorText").and_then(&Value::as_string)
+                                                         .map(|text| text.to_owned()),
+                    cssText: msg.get("cssText").and_then(&Value::as_string)
+                                               .map(|text| text.to_owned()),
+                };
+                let (tx, rx) = ipc::channel().unwrap();
+                self.script_chan.send(ModifyStyleRule(self.pipeline,
+                                                      self.index,
+                                                      line,
+                                                      column,
+                                                      modification,
+                                                      tx)).unwrap();
+
+                let msg = ModifyRuleReply {
+                    from: self.name(),
+                    modified: rx.recv().unwrap(),
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
+            _ => ActorMessageStatus::Ignored,
+        })
+    }
+}
+
+impl StyleSheetActor {
+    pub fn new(name: String,
+               info: StyleSheetInfo,
+               index: usize,
+               script_chan: IpcSender<DevtoolScriptControlMsg>,
+               pipeline: PipelineId)
+               -> StyleSheetActor {
+        StyleSheetActor {
+            name: name,
+            info: info,
+            index: index,
+            script_chan: script_chan,
+            pipeline: pipeline,
+            source_map: RefCell::new(None),
+        }
+    }
+
+    fn encodable(&self) -> StyleSheetMsg {
+        StyleSheetMsg {
+            actor: self.name(),
+            href: self.info.href.clone(),
+            disabled: false,
+            styleSheetIndex: self.index,
+            ruleCount: self.info.ruleCount,
+            sourceMapURL: self.info.sourceMapURL.clone(),
+        }
+    }
+
+    /// Runs the callback with this stylesheet's source map, fetching it first if this is the
+    /// first time it is needed. Returns `None` if there is no usable source map.
+    fn with_source_map<F, R>(&self, callback: F) -> Option<R> where F: FnOnce(&SourceMap) -> R {
+        let url = match self.info.sourceMapURL {
+            Some(ref url) => url,
+            None => return None,
+        };
+
+        let mut source_map = self.source_map.borrow_mut();
+        if source_map.is_none() {
+            let fetched = SourceMap::fetch(url);
+            if fetched.is_err() {
+                warn!("Failed to load source map at {}", url);
+            }
+            *source_map = Some(fetched);
+        }
+
+        match *source_map {
+            Some(Ok(ref source_map)) => Some(callback(source_map)),
+            _ => None,
+        }
+    }
+}
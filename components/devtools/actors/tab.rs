@@ -9,9 +9,10 @@
 
 use actor::{Actor, ActorMessageStatus, ActorRegistry};
 use actors::console::ConsoleActor;
-use devtools_traits::DevtoolScriptControlMsg::WantsLiveNotifications;
+use devtools_traits::DevtoolScriptControlMsg::{SetInvalidationOverlays, WantsLiveNotifications};
 use protocol::JsonPacketStream;
 use serde_json::Value;
+use std::cell::Cell;
 use std::collections::BTreeMap;
 use std::net::TcpStream;
 
@@ -63,6 +64,8 @@ pub struct TabActorMsg {
     outerWindowID: u32,
     consoleActor: String,
     inspectorActor: String,
+    styleSheetsActor: String,
+    animationsActor: String,
     timelineActor: String,
     profilerActor: String,
     performanceActor: String,
@@ -74,10 +77,15 @@ pub struct TabActor {
     pub url: String,
     pub console: String,
     pub inspector: String,
+    pub style_sheets: String,
+    pub animations: String,
     pub timeline: String,
     pub profiler: String,
     pub performance: String,
     pub thread: String,
+    pub paint_flashing: Cell<bool>,
+    pub reflow_outlines: Cell<bool>,
+    pub layer_borders: Cell<bool>,
 }
 
 impl Actor for TabActor {
@@ -88,10 +96,31 @@ impl Actor for TabActor {
     fn handle_message(&self,
                       registry: &ActorRegistry,
                       msg_type: &str,
-                      _msg: &BTreeMap<String, Value>,
+                      msg: &BTreeMap<String, Value>,
                       stream: &mut TcpStream) -> Result<ActorMessageStatus, ()> {
         Ok(match msg_type {
             "reconfigure" => {
+                if let Some(options) = msg.get("options").and_then(|o| o.as_object()) {
+                    let paint_flashing = options.get("paintFlashing")
+                        .and_then(&Value::as_boolean);
+                    let reflow_outlines = options.get("servoReflowOutlines")
+                        .and_then(&Value::as_boolean);
+                    let layer_borders = options.get("servoLayerBorders")
+                        .and_then(&Value::as_boolean);
+                    if paint_flashing.is_some() || reflow_outlines.is_some() ||
+                       layer_borders.is_some() {
+                        self.paint_flashing.set(paint_flashing.unwrap_or(self.paint_flashing.get()));
+                        self.reflow_outlines.set(
+                            reflow_outlines.unwrap_or(self.reflow_outlines.get()));
+                        self.layer_borders.set(layer_borders.unwrap_or(self.layer_borders.get()));
+                        let console_actor = registry.find::<ConsoleActor>(&self.console);
+                        console_actor.script_chan.send(
+                            SetInvalidationOverlays(console_actor.pipeline,
+                                                    self.paint_flashing.get(),
+                                                    self.reflow_outlines.get(),
+                                                    self.layer_borders.get())).unwrap();
+                    }
+                }
                 stream.write_json_packet(&ReconfigureReply { from: self.name() });
                 ActorMessageStatus::Processed
             }
@@ -153,6 +182,8 @@ impl TabActor {
             outerWindowID: 0, //FIXME: this should probably be the pipeline id
             consoleActor: self.console.clone(),
             inspectorActor: self.inspector.clone(),
+            styleSheetsActor: self.style_sheets.clone(),
+            animationsActor: self.animations.clone(),
             timelineActor: self.timeline.clone(),
             profilerActor: self.profiler.clone(),
             performanceActor: self.performance.clone(),
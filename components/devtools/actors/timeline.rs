@@ -68,6 +68,7 @@ struct TimelineMarkerReply {
     end: HighResolutionStamp,
     stack: Option<Vec<()>>,
     endStack: Option<Vec<()>>,
+    count: Option<usize>,
 }
 
 #[derive(Serialize)]
@@ -287,6 +288,7 @@ impl Emitter {
             end: HighResolutionStamp::new(self.start_stamp, payload.end_time),
             stack: payload.start_stack,
             endStack: payload.end_stack,
+            count: payload.count,
         }
     }
 
@@ -32,26 +32,28 @@ extern crate time;
 extern crate util;
 
 use actor::{Actor, ActorRegistry};
+use actors::animation::AnimationsActor;
 use actors::console::ConsoleActor;
 use actors::framerate::FramerateActor;
-use actors::inspector::InspectorActor;
+use actors::inspector::{InspectorActor, WalkerActor};
 use actors::network_event::{EventActor, NetworkEventActor, RequestCookiesMsg, ResponseCookiesMsg };
 use actors::network_event::{ResponseContentMsg, ResponseHeadersMsg, ResponseStartMsg };
 use actors::performance::PerformanceActor;
 use actors::profiler::ProfilerActor;
 use actors::root::RootActor;
+use actors::stylesheets::StyleSheetsActor;
 use actors::tab::TabActor;
 use actors::thread::ThreadActor;
 use actors::timeline::TimelineActor;
 use actors::worker::WorkerActor;
 use devtools_traits::{ChromeToDevtoolsControlMsg, ConsoleMessage, DevtoolsControlMsg};
-use devtools_traits::{DevtoolScriptControlMsg, DevtoolsPageInfo, LogLevel, NetworkEvent};
+use devtools_traits::{DevtoolScriptControlMsg, DevtoolsPageInfo, LogLevel, NetworkEvent, NodeInfo};
 use devtools_traits::{ScriptToDevtoolsControlMsg, WorkerId};
 use ipc_channel::ipc::IpcSender;
 use msg::constellation_msg::PipelineId;
 use protocol::JsonPacketStream;
 use std::borrow::ToOwned;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::net::{Shutdown, TcpListener, TcpStream};
@@ -63,6 +65,7 @@ use util::thread::spawn_named;
 mod actor;
 /// Corresponds to http://mxr.mozilla.org/mozilla-central/source/toolkit/devtools/server/actors/
 mod actors {
+    pub mod animation;
     pub mod console;
     pub mod framerate;
     pub mod inspector;
@@ -72,12 +75,14 @@ mod actors {
     pub mod performance;
     pub mod profiler;
     pub mod root;
+    pub mod stylesheets;
     pub mod tab;
     pub mod thread;
     pub mod timeline;
     pub mod worker;
 }
 mod protocol;
+mod source_map;
 
 #[derive(Serialize)]
 struct ConsoleAPICall {
@@ -258,7 +263,8 @@ fn run_server(sender: Sender<DevtoolsControlMsg>,
         let (pipeline, worker_id) = ids;
 
         //TODO: move all this actor creation into a constructor method on TabActor
-        let (tab, console, inspector, timeline, profiler, performance, thread) = {
+        let (tab, console, inspector, style_sheets, animations, timeline, profiler, performance,
+             thread) = {
             let console = ConsoleActor {
                 name: actors.new_name("console"),
                 script_chan: script_sender.clone(),
@@ -274,6 +280,18 @@ fn run_server(sender: Sender<DevtoolsControlMsg>,
                 pipeline: pipeline,
             };
 
+            let style_sheets = StyleSheetsActor {
+                name: actors.new_name("stylesheets"),
+                script_chan: script_sender.clone(),
+                pipeline: pipeline,
+            };
+
+            let animations = AnimationsActor {
+                name: actors.new_name("animations"),
+                script_chan: script_sender.clone(),
+                pipeline: pipeline,
+            };
+
             let timeline = TimelineActor::new(actors.new_name("timeline"),
                                               pipeline,
                                               script_sender);
@@ -290,16 +308,22 @@ fn run_server(sender: Sender<DevtoolsControlMsg>,
                 url: url.into_string(),
                 console: console.name(),
                 inspector: inspector.name(),
+                style_sheets: style_sheets.name(),
+                animations: animations.name(),
                 timeline: timeline.name(),
                 profiler: profiler.name(),
                 performance: performance.name(),
                 thread: thread.name(),
+                paint_flashing: Cell::new(false),
+                reflow_outlines: Cell::new(false),
+                layer_borders: Cell::new(false),
             };
 
             let root = actors.find_mut::<RootActor>("root");
             root.tabs.push(tab.name.clone());
 
-            (tab, console, inspector, timeline, profiler, performance, thread)
+            (tab, console, inspector, style_sheets, animations, timeline, profiler, performance,
+             thread)
         };
 
         if let Some(id) = worker_id {
@@ -316,6 +340,8 @@ fn run_server(sender: Sender<DevtoolsControlMsg>,
         actors.register(box tab);
         actors.register(box console);
         actors.register(box inspector);
+        actors.register(box style_sheets);
+        actors.register(box animations);
         actors.register(box timeline);
         actors.register(box profiler);
         actors.register(box performance);
@@ -358,6 +384,26 @@ fn run_server(sender: Sender<DevtoolsControlMsg>,
         }
     }
 
+    fn handle_node_picked(actors: Arc<Mutex<ActorRegistry>>,
+                          id: PipelineId,
+                          node_info: NodeInfo,
+                          actor_pipelines: &HashMap<PipelineId, String>) {
+        let mut actors = actors.lock().unwrap();
+        let walker = match actor_pipelines.get(&id) {
+            Some(tab) => {
+                let inspector = &actors.find::<TabActor>(tab).inspector;
+                actors.find::<InspectorActor>(inspector).walker.borrow().clone()
+            }
+            None => return,
+        };
+        if let Some(walker) = walker {
+            actors.find::<WalkerActor>(&walker).node_picked(&actors, node_info);
+        }
+        // The node actor created for the picked node has to be registered before the client
+        // refers to it.
+        actors.apply_later_changes();
+    }
+
     fn find_console_actor(actors: Arc<Mutex<ActorRegistry>>,
                           id: PipelineId,
                           worker_id: Option<WorkerId>,
@@ -562,6 +608,10 @@ fn run_server(sender: Sender<DevtoolsControlMsg>,
                 handle_console_message(actors.clone(), id, None, console_message,
                                        &actor_pipelines, &actor_workers)
             },
+            DevtoolsControlMsg::FromScript(ScriptToDevtoolsControlMsg::NodePicked(
+                        id,
+                        node_info)) =>
+                handle_node_picked(actors.clone(), id, node_info, &actor_pipelines),
             DevtoolsControlMsg::FromChrome(ChromeToDevtoolsControlMsg::NetworkEvent(
                         request_id, network_event)) => {
                 // copy the accepted_connections vector
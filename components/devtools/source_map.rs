@@ -0,0 +1,208 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Fetching and decoding of source maps, used to report locations in generated
+//! stylesheets in terms of the original (SCSS, LESS, etc.) sources.
+//!
+//! https://sourcemaps.info/spec.html
+
+use hyper::Client;
+use hyper::Url;
+use hyper::status::StatusCode;
+use serde_json::{self, Value};
+use std::fs::File;
+use std::io::Read;
+
+/// A position in one of the original sources of a source map. Lines and
+/// columns are zero-based.
+pub struct OriginalLocation {
+    pub source: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+struct Mapping {
+    generated_column: u32,
+    /// The source index, line and column this mapping points to, if any.
+    original: Option<(usize, u32, u32)>,
+}
+
+/// A decoded version 3 source map.
+pub struct SourceMap {
+    /// The absolute URLs of the original sources.
+    pub sources: Vec<String>,
+    /// The mappings for each generated line, sorted by generated column.
+    lines: Vec<Vec<Mapping>>,
+}
+
+impl SourceMap {
+    /// Fetches the source map at the given URL from the network or file system
+    /// and decodes it.
+    pub fn fetch(url: &str) -> Result<SourceMap, ()> {
+        let url = try!(Url::parse(url).map_err(|_| ()));
+        let mut json = String::new();
+        match url.scheme() {
+            "http" | "https" => {
+                let mut response = try!(Client::new().get(url.clone()).send().map_err(|_| ()));
+                if response.status != StatusCode::Ok {
+                    return Err(())
+                }
+                try!(response.read_to_string(&mut json).map_err(|_| ()));
+            }
+            "file" => {
+                let path = try!(url.to_file_path());
+                try!(File::open(path).and_then(|mut file| file.read_to_string(&mut json))
+                                     .map_err(|_| ()));
+            }
+            _ => return Err(()),
+        }
+        SourceMap::parse(&json, &url)
+    }
+
+    /// Decodes a source map, resolving the URLs of its sources against the URL
+    /// the map was loaded from.
+    pub fn parse(json: &str, map_url: &Url) -> Result<SourceMap, ()> {
+        // Source maps may be prefixed with a line starting with `)]}` to
+        // prevent them from being evaluated as script.
+        let json = if json.starts_with(")]}") {
+            json.find('\n').map_or("", |newline| &json[newline + 1..])
+        } else {
+            json
+        };
+
+        let json: Value = try!(serde_json::from_str(json).map_err(|_| ()));
+        if json.find("version").and_then(Value::as_u64) != Some(3) {
+            return Err(())
+        }
+
+        let mut source_root = json.find("sourceRoot")
+                                  .and_then(Value::as_string)
+                                  .unwrap_or("")
+                                  .to_owned();
+        if !source_root.is_empty() && !source_root.ends_with('/') {
+            source_root.push('/');
+        }
+        let sources = try!(json.find("sources").and_then(Value::as_array).ok_or(()));
+        let sources = try!(sources.iter().map(|source| {
+            let source = try!(source.as_string().ok_or(()));
+            let url = try!(map_url.join(&format!("{}{}", source_root, source)).map_err(|_| ()));
+            Ok(url.into_string())
+        }).collect::<Result<Vec<_>, ()>>());
+
+        let mappings = try!(json.find("mappings").and_then(Value::as_string).ok_or(()));
+        let lines = try!(decode_mappings(mappings, sources.len()));
+
+        Ok(SourceMap {
+            sources: sources,
+            lines: lines,
+        })
+    }
+
+    /// Returns the original location corresponding to the given zero-based
+    /// line and column of the generated source, if the source map covers it.
+    pub fn original_location(&self, line: u32, column: u32) -> Option<OriginalLocation> {
+        let mappings = match self.lines.get(line as usize) {
+            Some(mappings) => mappings,
+            None => return None,
+        };
+
+        // Find the last mapping starting at or before the column.
+        let index = match mappings.binary_search_by(|mapping| {
+            mapping.generated_column.cmp(&column)
+        }) {
+            Ok(index) => index,
+            Err(0) => return None,
+            Err(index) => index - 1,
+        };
+
+        mappings[index].original.map(|(source, line, column)| {
+            OriginalLocation {
+                source: self.sources[source].clone(),
+                line: line,
+                column: column,
+            }
+        })
+    }
+}
+
+/// Decodes the `mappings` field of a source map into per-line mappings.
+fn decode_mappings(mappings: &str, source_count: usize) -> Result<Vec<Vec<Mapping>>, ()> {
+    // Everything but the generated column is relative to the previous segment,
+    // even across lines.
+    let (mut source, mut original_line, mut original_column) = (0i64, 0i64, 0i64);
+
+    mappings.split(';').map(|line| {
+        let mut generated_column = 0i64;
+        let mut line_mappings = vec![];
+        for segment in line.split(',').filter(|segment| !segment.is_empty()) {
+            let fields = try!(decode_vlq_segment(segment));
+            generated_column += fields[0];
+            let original = match fields.len() {
+                1 => None,
+                4 | 5 => {
+                    source += fields[1];
+                    original_line += fields[2];
+                    original_column += fields[3];
+                    if source < 0 || source as usize >= source_count ||
+                            original_line < 0 || original_column < 0 {
+                        return Err(())
+                    }
+                    Some((source as usize, original_line as u32, original_column as u32))
+                }
+                _ => return Err(()),
+            };
+            if generated_column < 0 {
+                return Err(())
+            }
+            line_mappings.push(Mapping {
+                generated_column: generated_column as u32,
+                original: original,
+            });
+        }
+        line_mappings.sort_by(|a, b| a.generated_column.cmp(&b.generated_column));
+        Ok(line_mappings)
+    }).collect()
+}
+
+/// Decodes a segment of Base64 VLQ encoded numbers.
+fn decode_vlq_segment(segment: &str) -> Result<Vec<i64>, ()> {
+    const VLQ_CONTINUATION_BIT: i64 = 0x20;
+    const VLQ_VALUE_MASK: i64 = 0x1f;
+
+    let mut values = vec![];
+    let (mut value, mut shift) = (0i64, 0);
+    for byte in segment.bytes() {
+        let digit = try!(base64_digit(byte)) as i64;
+        value += (digit & VLQ_VALUE_MASK) << shift;
+        if digit & VLQ_CONTINUATION_BIT != 0 {
+            shift += 5;
+            if shift > 60 {
+                return Err(())
+            }
+            continue
+        }
+
+        // The lowest bit holds the sign.
+        let magnitude = value >> 1;
+        values.push(if value & 1 == 1 { -magnitude } else { magnitude });
+        value = 0;
+        shift = 0;
+    }
+
+    if shift != 0 || values.is_empty() {
+        return Err(())
+    }
+    Ok(values)
+}
+
+fn base64_digit(byte: u8) -> Result<u8, ()> {
+    match byte {
+        b'A'...b'Z' => Ok(byte - b'A'),
+        b'a'...b'z' => Ok(byte - b'a' + 26),
+        b'0'...b'9' => Ok(byte - b'0' + 52),
+        b'+' => Ok(62),
+        b'/' => Ok(63),
+        _ => Err(()),
+    }
+}
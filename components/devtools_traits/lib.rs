@@ -88,6 +88,8 @@ pub enum ScriptToDevtoolsControlMsg {
 
     /// Report a CSS parse error for the given pipeline
     ReportCSSError(PipelineId, CSSError),
+    /// The given node was picked by clicking on it while the node picker was enabled.
+    NodePicked(PipelineId, NodeInfo),
 }
 
 /// Serialized JS return values
@@ -131,6 +133,93 @@ pub struct NodeInfo {
     pub incompleteValue: bool,
 }
 
+/// The details of a stylesheet applying to a document.
+#[derive(Deserialize, Serialize)]
+pub struct StyleSheetInfo {
+    /// The URL the stylesheet was loaded from, or `None` for inline stylesheets.
+    pub href: Option<String>,
+    /// The absolute URL of the stylesheet's source map, if it declares one.
+    pub sourceMapURL: Option<String>,
+    pub ruleCount: usize,
+}
+
+/// The state of a running CSS animation or transition.
+#[derive(Deserialize, Serialize)]
+pub struct AnimationInfo {
+    /// The unique id of the animated node.
+    pub node: String,
+    /// The name of the animated property.
+    pub property: String,
+    /// The duration of the animation, in milliseconds.
+    pub duration: f64,
+    /// The number of milliseconds since the animation started. This is negative while the
+    /// animation is delayed.
+    pub currentTime: f64,
+    pub paused: bool,
+}
+
+/// A style rule that matches a node, as the rules panel shows it.
+#[derive(Deserialize, Serialize)]
+pub struct MatchedRuleInfo {
+    pub selectorText: String,
+    pub specificity: u32,
+    /// The origin of the stylesheet of the rule: "user-agent", "user" or "author".
+    pub origin: String,
+    /// The URL of the stylesheet of the rule, or that of its document if the stylesheet is
+    /// inline.
+    pub href: String,
+    /// The line and column where the selectors of the rule start, both counted from 1.
+    pub line: usize,
+    pub column: usize,
+    /// Whether these are the `!important` declarations of the rule, which are listed apart from
+    /// its normal ones.
+    pub important: bool,
+    pub declarations: Vec<MatchedDeclarationInfo>,
+}
+
+/// A declaration of a `MatchedRuleInfo`.
+#[derive(Deserialize, Serialize)]
+pub struct MatchedDeclarationInfo {
+    pub name: String,
+    pub value: String,
+    /// Whether a later declaration in the cascade sets the same property.
+    pub overridden: bool,
+}
+
+/// New selectors or declarations for a style rule, as edited in the rules panel.
+#[derive(Deserialize, Serialize)]
+pub struct StyleRuleModification {
+    /// The new selectors, or `None` to keep the current ones.
+    pub selectorText: Option<String>,
+    /// The new declarations, as the body of a style rule, or `None` to keep the current ones.
+    pub cssText: Option<String>,
+}
+
+/// How many of the style rules of a stylesheet have matched an element so far, and which have
+/// not.
+#[derive(Deserialize, Serialize)]
+pub struct StyleSheetCoverage {
+    /// The URL the stylesheet was loaded from, or `None` for inline stylesheets.
+    pub href: Option<String>,
+    pub usedRuleCount: usize,
+    pub unusedRuleCount: usize,
+    /// The style rules that have never matched an element, in source order.
+    pub unusedRules: Vec<UnusedRuleInfo>,
+}
+
+/// A style rule of a `StyleSheetCoverage` that has never matched an element.
+#[derive(Deserialize, Serialize)]
+pub struct UnusedRuleInfo {
+    pub selectorText: String,
+    /// The line and column where the selectors of the rule start, both counted from 1.
+    pub line: usize,
+    pub column: usize,
+    /// The byte offsets where the whole rule starts and ends in the source text of the
+    /// stylesheet, or in that of its `<style>` element if it is inline.
+    pub start: usize,
+    pub end: usize,
+}
+
 pub struct StartedTimelineMarker {
     name: String,
     start_time: PreciseTime,
@@ -144,6 +233,9 @@ pub struct TimelineMarker {
     pub start_stack: Option<Vec<()>>,
     pub end_time: PreciseTime,
     pub end_stack: Option<Vec<()>>,
+    /// The number of things that the marked work ran for, such as the nodes that a phase of
+    /// layout styled.
+    pub count: Option<usize>,
 }
 
 #[derive(PartialEq, Eq, Hash, Clone, Deserialize, Serialize)]
@@ -180,6 +272,28 @@ pub struct ComputedNodeLayout {
     pub height: f32,
 }
 
+/// The boxes of a DOM node in page coordinates, for drawing the box model overlay. Each box is
+/// given by its corners, as (x, y) pairs clockwise from its top left corner, since transforms can
+/// make it any quadrilateral.
+#[derive(Deserialize, Serialize)]
+pub struct BoxModelInfo {
+    pub margin: Vec<(f32, f32)>,
+    pub border: Vec<(f32, f32)>,
+    pub padding: Vec<(f32, f32)>,
+    pub content: Vec<(f32, f32)>,
+    pub position: String,
+    pub zIndex: String,
+}
+
+/// A font face that text is laid out in. The URL is empty for the fonts of the system.
+#[derive(Deserialize, Serialize)]
+pub struct UsedFontFaceInfo {
+    pub name: String,
+    pub familyName: String,
+    pub url: String,
+    pub glyphCount: usize,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct AutoMargins {
     pub top: bool,
@@ -201,6 +315,8 @@ pub enum DevtoolScriptControlMsg {
     GetChildren(PipelineId, String, IpcSender<Vec<NodeInfo>>),
     /// Retrieve the computed layout properties of the given node in the given pipeline.
     GetLayout(PipelineId, String, IpcSender<ComputedNodeLayout>),
+    /// Retrieve the boxes of the given node in the given pipeline, if it has any.
+    GetBoxModel(PipelineId, String, IpcSender<Option<BoxModelInfo>>),
     /// Retrieve all stored console messages for the given pipeline.
     GetCachedMessages(PipelineId, CachedConsoleMessageTypes, IpcSender<Vec<CachedConsoleMessage>>),
     /// Update a given node's attributes with a list of modifications.
@@ -214,6 +330,42 @@ pub enum DevtoolScriptControlMsg {
     /// Request a callback directed at the given actor name from the next animation frame
     /// executed in the given pipeline.
     RequestAnimationFrame(PipelineId, String),
+    /// Toggle the debugging overlays for the given pipeline that tint repainted areas (first
+    /// flag), outline reflowed flows (second flag) and outline layers (third flag).
+    SetInvalidationOverlays(PipelineId, bool, bool, bool),
+    /// Retrieve the details of the stylesheets of the document in the given pipeline, in
+    /// document order.
+    GetStyleSheets(PipelineId, IpcSender<Vec<StyleSheetInfo>>),
+    /// Retrieve the rule coverage of the stylesheets of the document in the given pipeline, in
+    /// document order. The reply is `None` if CSS coverage tracking is disabled.
+    GetCSSCoverage(PipelineId, IpcSender<Option<Vec<StyleSheetCoverage>>>),
+    /// Retrieve the style rules that match the node with the given unique id in the given
+    /// pipeline, or the pseudo-element of it with the given name, such as "::before", in the order
+    /// in which they are cascaded. The reply is `None` if the pseudo-element does not exist, such
+    /// as `::before` without `content`.
+    GetMatchedRules(PipelineId, String, Option<String>, IpcSender<Option<Vec<MatchedRuleInfo>>>),
+    /// Modify the style rule whose selectors start at the given line and column of the
+    /// stylesheet with the given index in document order, in the given pipeline. The reply is
+    /// whether there is such a rule and its new selectors parse.
+    ModifyStyleRule(PipelineId, usize, usize, usize, StyleRuleModification, IpcSender<bool>),
+    /// Retrieve the fragment tree of the last layout of the given pipeline as JSON, with the
+    /// fragments of each flow and the unique id of the node of each fragment. The reply is `None`
+    /// if nothing has been laid out yet.
+    GetFragmentTree(PipelineId, IpcSender<Option<String>>),
+    /// Retrieve the font faces that the text of the given pipeline is laid out in.
+    GetUsedFontFaces(PipelineId, IpcSender<Vec<UsedFontFaceInfo>>),
+    /// Retrieve the running animations of the document in the given pipeline.
+    GetRunningAnimations(PipelineId, IpcSender<Vec<AnimationInfo>>),
+    /// Pause (if true) or resume the animations of the given property of the node with the
+    /// given unique id.
+    SetAnimationPaused(PipelineId, String, String, bool),
+    /// Move the animations of the given property of the node with the given unique id to the
+    /// given number of milliseconds since their start.
+    SeekAnimation(PipelineId, String, String, f64),
+    /// Enable (if true) or disable picking a node of the given pipeline with the next click.
+    SetNodePicker(PipelineId, bool),
+    /// Highlight the box model of the node with the given unique id, or remove the highlight.
+    HighlightNode(PipelineId, Option<String>),
 }
 
 #[derive(Deserialize, Serialize)]
@@ -316,6 +468,20 @@ impl TimelineMarker {
             start_stack: None,
         }
     }
+
+    /// A marker of work that ran from `start_time` to `end_time`, as returned by
+    /// `time::precise_time_ns()`, for `count` things.
+    pub fn with_count(name: String, start_time: u64, end_time: u64, count: usize)
+                      -> TimelineMarker {
+        TimelineMarker {
+            name: name,
+            start_time: PreciseTime(start_time),
+            start_stack: None,
+            end_time: PreciseTime(end_time),
+            end_stack: None,
+            count: Some(count),
+        }
+    }
 }
 
 impl StartedTimelineMarker {
@@ -326,6 +492,7 @@ impl StartedTimelineMarker {
             start_stack: self.start_stack,
             end_time: PreciseTime::now(),
             end_stack: None,
+            count: None,
         }
     }
 }
@@ -24,7 +24,7 @@ use euclid::{Matrix2D, Matrix4D, Point2D, Rect, SideOffsets2D, Size2D};
 use fnv::FnvHasher;
 use gfx_traits::{LayerId, ScrollPolicy};
 use ipc_channel::ipc::IpcSharedMemory;
-use msg::constellation_msg::PipelineId;
+use msg::constellation_msg::{CustomCursor, PipelineId};
 use net_traits::image::base::{Image, PixelFormat};
 use paint_context::PaintContext;
 use range::Range;
@@ -32,7 +32,7 @@ use serde::de::{self, Deserialize, Deserializer, MapVisitor, Visitor};
 use serde::ser::impls::MapIteratorVisitor;
 use serde::ser::{Serialize, Serializer};
 use std::cmp::{self, Ordering};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::hash::{BuildHasherDefault, Hash};
 use std::marker::PhantomData;
@@ -40,6 +40,8 @@ use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 use style::computed_values::{border_style, filter, image_rendering, mix_blend_mode};
+use style::computed_values::{scroll_snap_align, scroll_snap_type_x, scroll_snap_type_y};
+use style::computed_values::touch_action;
 use style_traits::cursor::Cursor;
 use text::TextRun;
 use text::glyph::ByteIndex;
@@ -60,7 +62,7 @@ pub static BLUR_INFLATION_FACTOR: i32 = 3;
 /// LayerInfo is used to store PaintLayer metadata during DisplayList construction.
 /// It is also used for tracking LayerIds when creating layers to preserve ordering when
 /// layered DisplayItems should render underneath unlayered DisplayItems.
-#[derive(Clone, Copy, HeapSizeOf, Deserialize, Serialize)]
+#[derive(Clone, Copy, HeapSizeOf, Deserialize, PartialEq, Serialize)]
 pub struct LayerInfo {
     /// The base LayerId of this layer.
     pub layer_id: LayerId,
@@ -210,11 +212,15 @@ pub struct DisplayList {
     pub list: Vec<DisplayItem>,
     pub offsets: FnvHashMap<StackingContextId, StackingContextOffsets>,
     pub root_stacking_context: StackingContext,
+    /// The loaded `url()` cursor images of the nodes that have them, which are used instead of
+    /// the cursors of their display items.
+    pub custom_cursors: HashMap<OpaqueNode, CustomCursor>,
 }
 
 impl DisplayList {
     pub fn new(mut root_stacking_context: StackingContext,
-               items: &mut Option<Vec<DisplayItem>>)
+               items: &mut Option<Vec<DisplayItem>>,
+               custom_cursors: HashMap<OpaqueNode, CustomCursor>)
                -> DisplayList {
         let items = match items.take() {
             Some(items) => items,
@@ -228,6 +234,7 @@ impl DisplayList {
             list: items,
             offsets: offsets,
             root_stacking_context: root_stacking_context,
+            custom_cursors: custom_cursors,
         };
         display_list.sort();
         display_list
@@ -262,6 +269,49 @@ impl DisplayList {
         self.print_with_tree(&mut print_tree);
     }
 
+    /// Returns the ids of the stacking contexts whose properties, children or items differ from
+    /// those in an older display list, including the stacking contexts only one of the lists has.
+    pub fn changed_stacking_contexts(&self, old: &DisplayList) -> HashSet<StackingContextId> {
+        fn collect_stacking_contexts<'a>(
+                stacking_context: &'a StackingContext,
+                stacking_contexts: &mut HashMap<StackingContextId, &'a StackingContext>) {
+            stacking_contexts.insert(stacking_context.id, stacking_context);
+            for child in &stacking_context.children {
+                collect_stacking_contexts(child, stacking_contexts);
+            }
+        }
+
+        fn items_by_stacking_context(display_list: &DisplayList)
+                                     -> HashMap<StackingContextId, Vec<&DisplayItem>> {
+            let mut items = HashMap::new();
+            for item in &display_list.list {
+                items.entry(item.base().stacking_context_id).or_insert(vec![]).push(item);
+            }
+            items
+        }
+
+        let (mut new_stacking_contexts, mut old_stacking_contexts) =
+            (HashMap::new(), HashMap::new());
+        collect_stacking_contexts(&self.root_stacking_context, &mut new_stacking_contexts);
+        collect_stacking_contexts(&old.root_stacking_context, &mut old_stacking_contexts);
+        let new_items = items_by_stacking_context(self);
+        let old_items = items_by_stacking_context(old);
+
+        let mut changed: HashSet<StackingContextId> = old_stacking_contexts.keys().filter(|id| {
+            !new_stacking_contexts.contains_key(id)
+        }).cloned().collect();
+        for (id, stacking_context) in &new_stacking_contexts {
+            let unchanged = old_stacking_contexts.get(id).map_or(false, |old_stacking_context| {
+                stacking_context.has_same_properties_and_children(old_stacking_context) &&
+                    new_items.get(id) == old_items.get(id)
+            });
+            if !unchanged {
+                changed.insert(*id);
+            }
+        }
+        changed
+    }
+
     fn sort_and_count_stacking_contexts(
             stacking_context: &mut StackingContext,
             offsets: &mut HashMap<StackingContextId,
@@ -433,7 +483,8 @@ impl DisplayList {
 
         let draw_target = paint_context.get_or_create_temporary_draw_target(
             &stacking_context.filters,
-            stacking_context.blend_mode);
+            stacking_context.blend_mode,
+            stacking_context.mask.is_some());
 
         // If a layer is being used, the transform for this layer
         // will be handled by the compositor.
@@ -477,6 +528,32 @@ impl DisplayList {
 
             paint_subcontext.remove_transient_clip_if_applicable();
             paint_subcontext.pop_clip_if_applicable();
+
+            if let Some(ref mask) = stacking_context.mask {
+                let size = draw_target.get_size();
+                let mask_draw_target = draw_target.create_similar_draw_target(
+                    &Size2D::new(size.width, size.height),
+                    draw_target.get_format());
+                mask_draw_target.set_transform(&draw_target.get_transform());
+                {
+                    let mut mask_subcontext = PaintContext {
+                        draw_target: mask_draw_target.clone(),
+                        font_context: &mut *paint_subcontext.font_context,
+                        page_rect: paint_subcontext.page_rect,
+                        screen_rect: paint_subcontext.screen_rect,
+                        clip_rect: Some(stacking_context.overflow),
+                        transient_clip: None,
+                        layer_kind: paint_subcontext.layer_kind,
+                    };
+                    mask_subcontext.push_clip_if_applicable();
+                    for item in &mask.items {
+                        item.draw_into_context(&mut mask_subcontext);
+                    }
+                    mask_subcontext.remove_transient_clip_if_applicable();
+                    mask_subcontext.pop_clip_if_applicable();
+                }
+                paint_subcontext.draw_mask(&mask_draw_target, mask.luminance);
+            }
         }
 
         draw_target.set_transform(&old_transform);
@@ -532,6 +609,45 @@ pub enum StackingContextType {
     PseudoFloat,
 }
 
+/// The mask of a stacking context, painted from its `mask-image`.
+#[derive(Clone, Deserialize, HeapSizeOf, PartialEq, Serialize)]
+pub struct Mask {
+    /// The items that paint the mask, in the coordinates of the stacking context.
+    pub items: Vec<DisplayItem>,
+    /// Whether the luminance of the mask masks the contents, rather than its alpha.
+    pub luminance: bool,
+}
+
+/// How a scroll container snaps its scroll position to the snap areas in it, per CSS-SCROLL-SNAP.
+#[derive(Clone, Copy, Debug, Deserialize, HeapSizeOf, PartialEq, Serialize)]
+pub struct ScrollSnapContainer {
+    /// How strictly it snaps horizontally.
+    pub type_x: scroll_snap_type_x::T,
+    /// How strictly it snaps vertically.
+    pub type_y: scroll_snap_type_y::T,
+    /// The scrollport, in the coordinates of the stacking context.
+    pub scrollport: Rect<Au>,
+}
+
+/// The area of an element with a `scroll-snap-align` that its scroll container snaps to.
+#[derive(Clone, Copy, Debug, Deserialize, HeapSizeOf, PartialEq, Serialize)]
+pub struct ScrollSnapArea {
+    /// The border box of the element, in the coordinates of the stacking context.
+    pub rect: Rect<Au>,
+    /// How the area is aligned with the scrollport.
+    pub align: scroll_snap_align::T,
+}
+
+/// The area of an element with a `touch-action` other than `auto`, so that the compositor can
+/// decide how to handle the touches that start on it without waiting for script.
+#[derive(Clone, Copy, Debug, Deserialize, HeapSizeOf, PartialEq, Serialize)]
+pub struct TouchActionRegion {
+    /// The border box of the element, in the coordinates of the stacking context.
+    pub rect: Rect<Au>,
+    /// The touch behaviors that the element allows.
+    pub touch_action: touch_action::T,
+}
+
 #[derive(HeapSizeOf, Deserialize, Serialize)]
 /// Represents one CSS stacking context, which may or may not have a hardware layer.
 pub struct StackingContext {
@@ -571,6 +687,19 @@ pub struct StackingContext {
     /// The layer info for this stacking context, if there is any.
     pub layer_info: Option<LayerInfo>,
 
+    /// The mask that its contents are painted through, if there is any.
+    pub mask: Option<Mask>,
+
+    /// How it snaps its scroll position, if it is the stacking context of a scroll container
+    /// with a `scroll-snap-type`.
+    pub scroll_snap: Option<ScrollSnapContainer>,
+
+    /// The snap areas of the elements whose display items are in it.
+    pub scroll_snap_areas: Vec<ScrollSnapArea>,
+
+    /// The `touch-action` regions of the elements whose display items are in it.
+    pub touch_action_regions: Vec<TouchActionRegion>,
+
     /// Children of this StackingContext.
     pub children: Vec<Box<StackingContext>>,
 }
@@ -604,6 +733,10 @@ impl StackingContext {
             establishes_3d_context: establishes_3d_context,
             scrolls_overflow_area: scrolls_overflow_area,
             layer_info: layer_info,
+            mask: None,
+            scroll_snap: None,
+            scroll_snap_areas: Vec::new(),
+            touch_action_regions: Vec::new(),
             children: Vec::new(),
         }
     }
@@ -643,6 +776,31 @@ impl StackingContext {
         print_tree.end_level();
     }
 
+    /// Whether this stacking context looks the same as another, apart from its items and the
+    /// contents of its children. `PartialEq` only compares ids.
+    pub fn has_same_properties_and_children(&self, other: &StackingContext) -> bool {
+        self.id == other.id &&
+            self.context_type == other.context_type &&
+            self.bounds == other.bounds &&
+            self.overflow == other.overflow &&
+            self.z_index == other.z_index &&
+            self.filters == other.filters &&
+            self.blend_mode == other.blend_mode &&
+            self.transform == other.transform &&
+            self.perspective == other.perspective &&
+            self.establishes_3d_context == other.establishes_3d_context &&
+            self.scrolls_overflow_area == other.scrolls_overflow_area &&
+            self.layer_info == other.layer_info &&
+            self.mask == other.mask &&
+            self.scroll_snap == other.scroll_snap &&
+            self.scroll_snap_areas == other.scroll_snap_areas &&
+            self.touch_action_regions == other.touch_action_regions &&
+            self.children.len() == other.children.len() &&
+            self.children.iter().zip(other.children.iter()).all(|(child, other_child)| {
+                child.id == other_child.id
+            })
+    }
+
     pub fn intersects_rect_in_parent_context(&self, rect: Option<Rect<Au>>) -> bool {
         // We only do intersection checks for real stacking contexts, since
         // pseudo stacking contexts might not have proper position information.
@@ -730,7 +888,7 @@ impl fmt::Debug for StackingContext {
 }
 
 /// One drawing command in the list.
-#[derive(Clone, Deserialize, HeapSizeOf, Serialize)]
+#[derive(Clone, Deserialize, HeapSizeOf, PartialEq, Serialize)]
 pub enum DisplayItem {
     SolidColorClass(Box<SolidColorDisplayItem>),
     TextClass(Box<TextDisplayItem>),
@@ -742,10 +900,11 @@ pub enum DisplayItem {
     BoxShadowClass(Box<BoxShadowDisplayItem>),
     LayeredItemClass(Box<LayeredItem>),
     IframeClass(Box<IframeDisplayItem>),
+    CaretClass(Box<CaretDisplayItem>),
 }
 
 /// Information common to all display items.
-#[derive(Clone, Deserialize, HeapSizeOf, Serialize)]
+#[derive(Clone, Deserialize, HeapSizeOf, PartialEq, Serialize)]
 pub struct BaseDisplayItem {
     /// The boundaries of the display item, in layer coordinates.
     pub bounds: Rect<Au>,
@@ -873,6 +1032,14 @@ impl ClippingRegion {
             self.complex.iter().all(|complex| complex.rect.intersects(rect))
     }
 
+    /// Returns true if this clipping region contains the given point, taking the rounded corners
+    /// of its complex regions into account.
+    #[inline]
+    pub fn contains_point(&self, point: &Point2D<Au>) -> bool {
+        self.main.contains(point) &&
+            self.complex.iter().all(|complex| complex.contains_point(point))
+    }
+
     /// Returns true if this clipping region completely surrounds the given rect.
     #[inline]
     pub fn does_not_clip_rect(&self, rect: &Rect<Au>) -> bool {
@@ -951,12 +1118,17 @@ impl ComplexClippingRegion {
         interior.origin.x <= other.rect.origin.x && interior.origin.y <= other.rect.origin.y &&
             interior.max_x() >= other.rect.max_x() && interior.max_y() >= other.rect.max_y()
     }
+
+    /// Returns true if the rounded rectangle contains the given point.
+    pub fn contains_point(&self, point: &Point2D<Au>) -> bool {
+        geometry::rounded_rect_contains_point(&self.rect, &self.radii.to_array(), point)
+    }
 }
 
 /// Metadata attached to each display item. This is useful for performing auxiliary threads with
 /// the display list involving hit testing: finding the originating DOM node and determining the
 /// cursor to use when the element is hovered over.
-#[derive(Clone, Copy, HeapSizeOf, Deserialize, Serialize)]
+#[derive(Clone, Copy, HeapSizeOf, Deserialize, PartialEq, Serialize)]
 pub struct DisplayItemMetadata {
     /// The DOM node from which this display item originated.
     pub node: OpaqueNode,
@@ -966,7 +1138,7 @@ pub struct DisplayItemMetadata {
 }
 
 /// Paints a solid color.
-#[derive(Clone, HeapSizeOf, Deserialize, Serialize)]
+#[derive(Clone, HeapSizeOf, Deserialize, PartialEq, Serialize)]
 pub struct SolidColorDisplayItem {
     /// Fields common to all display items.
     pub base: BaseDisplayItem,
@@ -975,6 +1147,19 @@ pub struct SolidColorDisplayItem {
     pub color: Color,
 }
 
+/// Paints the insertion point of editable text.
+#[derive(Clone, HeapSizeOf, Deserialize, PartialEq, Serialize)]
+pub struct CaretDisplayItem {
+    /// Fields common to all display items.
+    pub base: BaseDisplayItem,
+
+    /// The color of the caret, given by `caret-color`.
+    pub color: Color,
+
+    /// Whether the caret is shown in the current phase of its blinking.
+    pub visible: bool,
+}
+
 /// Paints text.
 #[derive(Clone, HeapSizeOf, Deserialize, Serialize)]
 pub struct TextDisplayItem {
@@ -1001,6 +1186,21 @@ pub struct TextDisplayItem {
     pub blur_radius: Au,
 }
 
+// Text runs are shared between display lists built from the same fragments, so they are compared
+// by identity.
+impl PartialEq for TextDisplayItem {
+    fn eq(&self, other: &TextDisplayItem) -> bool {
+        self.base == other.base &&
+            &*self.text_run as *const TextRun == &*other.text_run as *const TextRun &&
+            self.range.begin() == other.range.begin() &&
+            self.range.length() == other.range.length() &&
+            self.text_color == other.text_color &&
+            self.baseline_origin == other.baseline_origin &&
+            self.orientation == other.orientation &&
+            self.blur_radius == other.blur_radius
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, HeapSizeOf, Deserialize, Serialize)]
 pub enum TextOrientation {
     Upright,
@@ -1026,9 +1226,31 @@ pub struct ImageDisplayItem {
     /// The algorithm we should use to stretch the image. See `image_rendering` in CSS-IMAGES-3 §
     /// 5.3.
     pub image_rendering: image_rendering::T,
+
+    /// The opacity to paint the image with, which is less than one for the images that
+    /// `cross-fade()` fades in.
+    pub opacity: f32,
 }
 
-#[derive(Clone, HeapSizeOf, Deserialize, Serialize)]
+impl PartialEq for ImageDisplayItem {
+    fn eq(&self, other: &ImageDisplayItem) -> bool {
+        let same_image_data = match (&self.image_data, &other.image_data) {
+            (&Some(ref data), &Some(ref other_data)) => {
+                &**data as *const IpcSharedMemory == &**other_data as *const IpcSharedMemory
+            }
+            (&None, &None) => true,
+            _ => false,
+        };
+        self.base == other.base &&
+            self.webrender_image == other.webrender_image &&
+            same_image_data &&
+            self.stretch_size == other.stretch_size &&
+            self.image_rendering == other.image_rendering &&
+            self.opacity == other.opacity
+    }
+}
+
+#[derive(Clone, HeapSizeOf, Deserialize, PartialEq, Serialize)]
 pub struct WebGLDisplayItem {
     pub base: BaseDisplayItem,
     #[ignore_heap_size_of = "Defined in webrender_traits"]
@@ -1037,7 +1259,7 @@ pub struct WebGLDisplayItem {
 
 
 /// Paints an iframe.
-#[derive(Clone, HeapSizeOf, Deserialize, Serialize)]
+#[derive(Clone, HeapSizeOf, Deserialize, PartialEq, Serialize)]
 pub struct IframeDisplayItem {
     pub base: BaseDisplayItem,
     pub iframe: PipelineId,
@@ -1060,7 +1282,19 @@ pub struct GradientDisplayItem {
 }
 
 /// Paints a border.
-#[derive(Clone, HeapSizeOf, Deserialize, Serialize)]
+impl PartialEq for GradientDisplayItem {
+    fn eq(&self, other: &GradientDisplayItem) -> bool {
+        self.base == other.base &&
+            self.start_point == other.start_point &&
+            self.end_point == other.end_point &&
+            self.stops.len() == other.stops.len() &&
+            self.stops.iter().zip(other.stops.iter()).all(|(stop, other_stop)| {
+                stop.offset == other_stop.offset && stop.color == other_stop.color
+            })
+    }
+}
+
+#[derive(Clone, HeapSizeOf, Deserialize, PartialEq, Serialize)]
 pub struct BorderDisplayItem {
     /// Fields common to all display items.
     pub base: BaseDisplayItem,
@@ -1109,6 +1343,20 @@ impl<T> Default for BorderRadii<T> where T: Default, T: Clone {
 }
 
 impl BorderRadii<Au> {
+    /// The radii of the corners of the edge inside borders of the given widths.
+    pub fn inner(&self, widths: &SideOffsets2D<Au>) -> BorderRadii<Au> {
+        fn inner_corner(corner: Size2D<Au>, width: Au, height: Au) -> Size2D<Au> {
+            Size2D::new(cmp::max(corner.width - width, Au(0)),
+                        cmp::max(corner.height - height, Au(0)))
+        }
+        BorderRadii {
+            top_left: inner_corner(self.top_left, widths.left, widths.top),
+            top_right: inner_corner(self.top_right, widths.right, widths.top),
+            bottom_right: inner_corner(self.bottom_right, widths.right, widths.bottom),
+            bottom_left: inner_corner(self.bottom_left, widths.left, widths.bottom),
+        }
+    }
+
     // Scale the border radii by the specified factor
     pub fn scale_by(&self, s: f32) -> BorderRadii<Au> {
         BorderRadii { top_left: BorderRadii::scale_corner_by(self.top_left, s),
@@ -1123,6 +1371,13 @@ impl BorderRadii<Au> {
     }
 }
 
+impl<T> BorderRadii<T> where T: Copy {
+    /// The radii of the top left, top right, bottom right and bottom left corners, in that order.
+    pub fn to_array(&self) -> [Size2D<T>; 4] {
+        [self.top_left, self.top_right, self.bottom_right, self.bottom_left]
+    }
+}
+
 impl<T> BorderRadii<T> where T: PartialEq + Zero {
     /// Returns true if all the radii are zero.
     pub fn is_square(&self) -> bool {
@@ -1145,7 +1400,7 @@ impl<T> BorderRadii<T> where T: PartialEq + Zero + Clone {
 }
 
 /// Paints a line segment.
-#[derive(Clone, HeapSizeOf, Deserialize, Serialize)]
+#[derive(Clone, HeapSizeOf, Deserialize, PartialEq, Serialize)]
 pub struct LineDisplayItem {
     pub base: BaseDisplayItem,
 
@@ -1157,7 +1412,7 @@ pub struct LineDisplayItem {
 }
 
 /// Paints a box shadow per CSS-BACKGROUNDS.
-#[derive(Clone, HeapSizeOf, Deserialize, Serialize)]
+#[derive(Clone, HeapSizeOf, Deserialize, PartialEq, Serialize)]
 pub struct BoxShadowDisplayItem {
     /// Fields common to all display items.
     pub base: BaseDisplayItem,
@@ -1187,7 +1442,7 @@ pub struct BoxShadowDisplayItem {
 }
 
 /// Contains an item that should get its own layer during layer creation.
-#[derive(Clone, HeapSizeOf, Deserialize, Serialize)]
+#[derive(Clone, HeapSizeOf, Deserialize, PartialEq, Serialize)]
 pub struct LayeredItem {
     /// Fields common to all display items.
     pub item: DisplayItem,
@@ -1239,7 +1494,8 @@ impl DisplayItem {
                     &image_item.image_data
                                .as_ref()
                                .expect("Non-WR painting needs image data!")[..],
-                    image_item.image_rendering.clone());
+                    image_item.image_rendering.clone(),
+                    image_item.opacity);
             }
 
             DisplayItem::WebGLClass(_) => {
@@ -1277,6 +1533,12 @@ impl DisplayItem {
             DisplayItem::LayeredItemClass(ref item) => item.item.draw_into_context(paint_context),
 
             DisplayItem::IframeClass(..) => {}
+
+            DisplayItem::CaretClass(ref caret) => {
+                if caret.visible && !caret.color.a.approx_eq(&0.0) {
+                    paint_context.draw_solid_color(&caret.base.bounds, caret.color)
+                }
+            }
         }
     }
 
@@ -1305,6 +1567,7 @@ impl DisplayItem {
             DisplayItem::BoxShadowClass(ref box_shadow) => &box_shadow.base,
             DisplayItem::LayeredItemClass(ref layered_item) => layered_item.item.base(),
             DisplayItem::IframeClass(ref iframe) => &iframe.base,
+            DisplayItem::CaretClass(ref caret) => &caret.base,
         }
     }
 
@@ -1321,10 +1584,8 @@ impl DisplayItem {
     }
 
     fn hit_test(&self, point: Point2D<Au>, result: &mut Vec<DisplayItemMetadata>) {
-        // TODO(pcwalton): Use a precise algorithm here. This will allow us to properly hit
-        // test elements with `border-radius`, for example.
         let base_item = self.base();
-        if !base_item.clip.might_intersect_point(&point) {
+        if !base_item.clip.contains_point(&point) {
             // Clipped out.
             return;
         }
@@ -1388,6 +1649,8 @@ impl fmt::Debug for DisplayItem {
                 DisplayItem::LayeredItemClass(ref layered_item) =>
                     format!("LayeredItem({:?})", layered_item.item),
                 DisplayItem::IframeClass(_) => "Iframe".to_owned(),
+                DisplayItem::CaretClass(ref caret) =>
+                    format!("Caret{}", if caret.visible { "" } else { " (hidden)" }),
             },
             self.bounds(),
             self.base().clip
@@ -1426,7 +1689,7 @@ impl StackingContextId {
     }
 }
 
-#[derive(Copy, Clone, HeapSizeOf, Deserialize, Serialize)]
+#[derive(Copy, Clone, HeapSizeOf, Deserialize, PartialEq, Serialize)]
 pub struct WebRenderImageInfo {
     pub width: u32,
     pub height: u32,
@@ -11,6 +11,7 @@ use azure::azure_hl::{FilterNode, FilterType, LinearTransferAttribute, LinearTra
 use azure::azure_hl::{GaussianBlurAttribute, GaussianBlurInput};
 use azure::azure_hl::{Matrix5x4, TableTransferAttribute, TableTransferInput};
 use style::computed_values::filter;
+use style::values::RGBA;
 
 /// Creates a filter pipeline from a set of CSS filters. Returns the destination end of the filter
 /// pipeline and the opacity.
@@ -100,6 +101,11 @@ pub fn create_filters(draw_target: &DrawTarget,
                 blur.set_input(GaussianBlurInput, &filter);
                 filter = blur
             }
+            filter::Filter::DropShadow(_, _, blur_radius, _) => {
+                // The shadow is drawn separately, beneath the output of the pipeline. See
+                // `create_drop_shadow`.
+                *accumulated_blur_radius = accumulated_blur_radius.clone() + blur_radius;
+            }
         }
     }
     (filter, opacity)
@@ -122,7 +128,8 @@ pub fn calculate_accumulated_blur(style_filters: &filter::T) -> Au {
     let mut accum_blur = Au::new(0);
     for style_filter in &style_filters.filters {
         match *style_filter {
-            filter::Filter::Blur(amount) => {
+            filter::Filter::Blur(amount) |
+            filter::Filter::DropShadow(_, _, amount, _) => {
                 accum_blur = accum_blur.clone() + amount;
             }
             _ => continue,
@@ -132,6 +139,62 @@ pub fn calculate_accumulated_blur(style_filters: &filter::T) -> Au {
     accum_blur
 }
 
+/// Creates the shadow that a `drop-shadow` filter draws beneath `input`, the output of a filter
+/// pipeline: its alpha mask in the given color, blurred per CSS-FILTERS § 5.1.
+pub fn create_drop_shadow(draw_target: &DrawTarget,
+                          input: &FilterNode,
+                          blur_radius: Au,
+                          color: RGBA)
+                          -> FilterNode {
+    let shadow = draw_target.create_filter(FilterType::ColorMatrix);
+    shadow.set_attribute(ColorMatrixAttribute::Matrix(self::shadow(color)));
+    shadow.set_input(ColorMatrixInput, input);
+    if blur_radius == Au(0) {
+        return shadow
+    }
+
+    // As for box shadows, the standard deviation is half the blur radius.
+    let blur = draw_target.create_filter(FilterType::GaussianBlur);
+    blur.set_attribute(GaussianBlurAttribute::StdDeviation(blur_radius.to_f32_px() / 2.0));
+    blur.set_input(GaussianBlurInput, &shadow);
+    blur
+}
+
+/// Creates the mask that `mask_draw_target` paints: by its alpha, or by its luminance.
+pub fn create_mask(draw_target: &DrawTarget, mask_draw_target: &DrawTarget, luminance: bool)
+                   -> FilterNode {
+    let mask = draw_target.create_filter(FilterType::Composite);
+    mask.set_input(CompositeInput, &mask_draw_target.snapshot());
+    if !luminance {
+        return mask
+    }
+
+    let luminance_to_alpha = draw_target.create_filter(FilterType::ColorMatrix);
+    luminance_to_alpha.set_attribute(ColorMatrixAttribute::Matrix(self::luminance_to_alpha()));
+    luminance_to_alpha.set_input(ColorMatrixInput, &mask);
+    luminance_to_alpha
+}
+
+/// Creates a 5x4 color matrix that turns the luminance of its input into alpha, per
+/// CSS-MASKING § 7.1. The alpha of the input is not taken into account.
+fn luminance_to_alpha() -> Matrix5x4 {
+    Matrix5x4 {
+        m11: 0.0, m21: 0.0, m31: 0.0, m41: 0.0, m51: 0.0,
+        m12: 0.0, m22: 0.0, m32: 0.0, m42: 0.0, m52: 0.0,
+        m13: 0.0, m23: 0.0, m33: 0.0, m43: 0.0, m53: 0.0,
+        m14: 0.2125, m24: 0.7154, m34: 0.0721, m44: 0.0, m54: 0.0,
+    }
+}
+
+/// Creates a 5x4 color matrix that paints the alpha mask of its input in the given color.
+fn shadow(color: RGBA) -> Matrix5x4 {
+    Matrix5x4 {
+        m11: 0.0, m21: 0.0, m31: 0.0, m41: 0.0, m51: color.red as AzFloat,
+        m12: 0.0, m22: 0.0, m32: 0.0, m42: 0.0, m52: color.green as AzFloat,
+        m13: 0.0, m23: 0.0, m33: 0.0, m43: 0.0, m53: color.blue as AzFloat,
+        m14: 0.0, m24: 0.0, m34: 0.0, m44: color.alpha as AzFloat, m54: 0.0,
+    }
+}
 
 /// Creates a grayscale 5x4 color matrix per CSS-FILTERS § 12.1.1.
 fn grayscale(amount: AzFloat) -> Matrix5x4 {
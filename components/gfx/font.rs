@@ -16,7 +16,9 @@ use std::rc::Rc;
 use std::str;
 use std::sync::Arc;
 use std::sync::atomic::{ATOMIC_USIZE_INIT, AtomicUsize, Ordering};
-use style::computed_values::{font_stretch, font_variant, font_weight};
+use style::computed_values::font_family::FontFamily;
+use style::computed_values::{font_stretch, font_variant, font_weight, tab_size};
+use style::font_face::UnicodeRange;
 use text::Shaper;
 use text::glyph::{ByteIndex, GlyphData, GlyphId, GlyphStore};
 use text::shaping::ShaperMethods;
@@ -112,6 +114,9 @@ pub struct Font {
     shape_cache: RefCell<HashCache<ShapeCacheEntry, Arc<GlyphStore>>>,
     glyph_advance_cache: RefCell<HashCache<u32, FractionalPixel>>,
     pub font_key: Option<webrender_traits::FontKey>,
+    /// The code points this font may be used for, from the `unicode-range` of its
+    /// `@font-face` rule.
+    pub unicode_range: Vec<UnicodeRange>,
 }
 
 impl Font {
@@ -120,7 +125,8 @@ impl Font {
                descriptor: FontTemplateDescriptor,
                requested_pt_size: Au,
                actual_pt_size: Au,
-               font_key: Option<webrender_traits::FontKey>) -> Font {
+               font_key: Option<webrender_traits::FontKey>,
+               unicode_range: Vec<UnicodeRange>) -> Font {
         let metrics = handle.metrics();
         Font {
             handle: handle,
@@ -133,8 +139,15 @@ impl Font {
             shape_cache: RefCell::new(HashCache::new()),
             glyph_advance_cache: RefCell::new(HashCache::new()),
             font_key: font_key,
+            unicode_range: unicode_range,
         }
     }
+
+    /// Whether this font may be used to display the character, regardless of whether it has a
+    /// glyph for it.
+    pub fn covers_character(&self, character: char) -> bool {
+        self.unicode_range.iter().any(|range| range.contains(character))
+    }
 }
 
 bitflags! {
@@ -150,16 +163,45 @@ bitflags! {
     }
 }
 
+/// An OpenType feature to set when shaping, such as small capitals (`smcp`) or tabular numerals
+/// (`tnum`).
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub struct FontFeature {
+    /// The OpenType feature tag.
+    pub tag: u32,
+    /// Zero disables the feature, one enables it, and larger values select an alternate glyph.
+    pub value: u32,
+}
+
+impl FontFeature {
+    /// Creates a feature from its four-character tag.
+    pub fn new(tag: &str, value: u32) -> FontFeature {
+        debug_assert!(tag.len() == 4);
+        FontFeature {
+            tag: tag.bytes().fold(0, |tag, byte| (tag << 8) | byte as u32),
+            value: value,
+        }
+    }
+}
+
 /// Various options that control text shaping.
-#[derive(Clone, Eq, PartialEq, Hash, Copy, Debug)]
+///
+/// These are part of the key of the shape cache, so everything that can affect the shaped glyphs
+/// must be included here.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
 pub struct ShapingOptions {
     /// Spacing to add between each letter. Corresponds to the CSS 2.1 `letter-spacing` property.
     /// NB: You will probably want to set the `IGNORE_LIGATURES_SHAPING_FLAG` if this is non-null.
     pub letter_spacing: Option<Au>,
     /// Spacing to add between each word. Corresponds to the CSS 2.1 `word-spacing` property.
     pub word_spacing: Au,
+    /// The width of a tab character. Corresponds to the CSS Text 3 `tab-size` property.
+    pub tab_size: tab_size::T,
     /// The Unicode script property of the characters in this run.
     pub script: Script,
+    /// OpenType features requested by the `font-variant-ligatures` and `font-feature-settings`
+    /// properties. Later features take precedence over earlier ones and over the flags.
+    pub features: Vec<FontFeature>,
     /// Various flags.
     pub flags: ShapingFlags,
 }
@@ -178,7 +220,7 @@ impl Font {
 
         let lookup_key = ShapeCacheEntry {
             text: text.to_owned(),
-            options: *options,
+            options: options.clone(),
         };
         let result = self.shape_cache.borrow_mut().find_or_create(lookup_key, || {
             let start_time = time::precise_time_ns();
@@ -209,6 +251,7 @@ impl Font {
     fn can_do_fast_shaping(&self, text: &str, options: &ShapingOptions) -> bool {
         options.script == Script::Latin &&
             !options.flags.contains(RTL_FLAG) &&
+            options.features.is_empty() &&
             self.handle.can_do_fast_shaping() &&
             text.is_ascii()
     }
@@ -218,6 +261,15 @@ impl Font {
         let mut prev_glyph_id = None;
         for (i, byte) in text.bytes().enumerate() {
             let character = byte as char;
+            if character == '\t' {
+                if let Some((space_glyph_id, advance)) = self.tab_glyph_and_advance(options) {
+                    let glyph = GlyphData::new(space_glyph_id, advance, None, true, true);
+                    glyphs.add_glyph_for_byte_index(ByteIndex(i as isize), character, &glyph);
+                }
+                prev_glyph_id = None;
+                continue
+            }
+
             let glyph_id = match self.glyph_index(character) {
                 Some(id) => id,
                 None => continue,
@@ -243,6 +295,27 @@ impl Font {
         glyphs.finalize_changes();
     }
 
+    /// Returns the glyph used to render a tab character (the space glyph) along with the advance
+    /// that a tab gets under the `tab-size` in the given shaping options.
+    ///
+    /// TODO: Proper tab stops, measured from the start of the line.
+    pub fn tab_glyph_and_advance(&self, options: &ShapingOptions) -> Option<(GlyphId, Au)> {
+        self.glyph_index(' ').map(|space_glyph_id| {
+            let advance = match options.tab_size {
+                tab_size::T::Spaces(count) => {
+                    let mut space_advance = Au::from_f64_px(self.glyph_h_advance(space_glyph_id)) +
+                        options.word_spacing;
+                    if let Some(letter_spacing) = options.letter_spacing {
+                        space_advance += letter_spacing;
+                    }
+                    space_advance * (count as i32)
+                }
+                tab_size::T::Length(length) => length,
+            };
+            (space_glyph_id, advance)
+        })
+    }
+
     pub fn table_for_tag(&self, tag: FontTableTag) -> Option<FontTable> {
         let result = self.handle.table_for_tag(tag);
         let status = if result.is_some() { "Found" } else { "Didn't find" };
@@ -281,14 +354,27 @@ impl Font {
 #[derive(Debug)]
 pub struct FontGroup {
     pub fonts: SmallVec<[Rc<RefCell<Font>>; 8]>,
+    /// The families with web fonts that are only downloaded once text needs a code point in
+    /// their `unicode-range`, along with the code points they cover.
+    pub deferred_web_fonts: Vec<(FontFamily, Vec<UnicodeRange>)>,
 }
 
 impl FontGroup {
-    pub fn new(fonts: SmallVec<[Rc<RefCell<Font>>; 8]>) -> FontGroup {
+    pub fn new(fonts: SmallVec<[Rc<RefCell<Font>>; 8]>,
+               deferred_web_fonts: Vec<(FontFamily, Vec<UnicodeRange>)>)
+               -> FontGroup {
         FontGroup {
             fonts: fonts,
+            deferred_web_fonts: deferred_web_fonts,
         }
     }
+
+    /// Returns the families with web fonts for the character that have not been downloaded.
+    pub fn deferred_web_fonts_for_character(&self, character: char) -> Vec<&FontFamily> {
+        self.deferred_web_fonts.iter().filter(|&&(_, ref unicode_range)| {
+            unicode_range.iter().any(|range| range.contains(character))
+        }).map(|&(ref family, _)| family).collect()
+    }
 }
 
 pub struct RunMetrics {
@@ -9,6 +9,7 @@ use mime::{TopLevel, SubLevel};
 use net_traits::{AsyncResponseTarget, LoadContext, PendingAsyncLoad, CoreResourceThread, ResponseAction, RequestSource};
 use platform::font_context::FontContextHandle;
 use platform::font_list::SANS_SERIF_FONT_FAMILY;
+use platform::font_list::fallback_font_families;
 use platform::font_list::for_each_available_family;
 use platform::font_list::for_each_variation;
 use platform::font_list::last_resort_font_families;
@@ -20,8 +21,10 @@ use std::mem;
 use std::sync::{Arc, Mutex};
 use std::u32;
 use string_cache::Atom;
-use style::font_face::Source;
+use style::font_face::{Source, UnicodeRange};
+use style::font_preferences;
 use style::properties::longhands::font_family::computed_value::FontFamily;
+use unicode_script::{Script, get_script};
 use url::Url;
 use util::prefs;
 use util::str::LowercaseString;
@@ -31,28 +34,57 @@ use webrender_traits;
 /// A list of font templates that make up a given font family.
 struct FontTemplates {
     templates: Vec<FontTemplate>,
+    /// Web fonts with a `unicode-range` that have not been downloaded, because no text has
+    /// needed any of their code points yet.
+    deferred: Vec<DeferredWebFont>,
+}
+
+/// A web font source whose download waits until a character in its `unicode-range` is used.
+struct DeferredWebFont {
+    source: Source,
+    unicode_range: Vec<UnicodeRange>,
+    sender: IpcSender<()>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FontTemplateInfo {
     pub font_template: Arc<FontTemplateData>,
     pub font_key: Option<webrender_traits::FontKey>,
+    pub unicode_range: Vec<UnicodeRange>,
+}
+
+/// The fonts of a family that match a descriptor.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FontFamilyInfo {
+    /// The best match for each distinct `unicode-range` of the family.
+    pub templates: Vec<FontTemplateInfo>,
+    /// The code points covered by web fonts of the family that have not been downloaded yet.
+    pub deferred_unicode_range: Vec<UnicodeRange>,
 }
 
 impl FontTemplates {
     fn new() -> FontTemplates {
         FontTemplates {
             templates: vec!(),
+            deferred: vec!(),
         }
     }
 
-    /// Find a font in this family that matches a given descriptor.
-    fn find_font_for_style(&mut self, desc: &FontTemplateDescriptor, fctx: &FontContextHandle)
-                               -> Option<Arc<FontTemplateData>> {
+    /// Find a font in this family that matches a given descriptor, among the fonts with the
+    /// given `unicode-range`.
+    fn find_font_for_style(&mut self,
+                           desc: &FontTemplateDescriptor,
+                           unicode_range: &[UnicodeRange],
+                           fctx: &FontContextHandle)
+                           -> Option<Arc<FontTemplateData>> {
+        let mut templates = self.templates.iter_mut().filter(|template| {
+            template.unicode_range() == unicode_range
+        }).collect::<Vec<_>>();
+
         // TODO(Issue #189): optimize lookup for
         // regular/bold/italic/bolditalic with fixed offsets and a
         // static decision table for fallback between these values.
-        for template in &mut self.templates {
+        for template in &mut templates {
             let maybe_template = template.data_for_descriptor(fctx, desc);
             if maybe_template.is_some() {
                 return maybe_template;
@@ -62,7 +94,7 @@ impl FontTemplates {
         // We didn't find an exact match. Do more expensive fuzzy matching.
         // TODO(#190): Do a better job.
         let (mut best_template_data, mut best_distance) = (None, u32::MAX);
-        for template in &mut self.templates {
+        for template in &mut templates {
             if let Some((template_data, distance)) =
                     template.data_for_approximate_descriptor(fctx, desc) {
                 if distance < best_distance {
@@ -78,7 +110,7 @@ impl FontTemplates {
         // If a request is made for a font family that exists,
         // pick the first valid font in the family if we failed
         // to find an exact match for the descriptor.
-        for template in &mut self.templates {
+        for template in &mut templates {
             let maybe_template = template.get();
             if maybe_template.is_some() {
                 return maybe_template;
@@ -88,31 +120,70 @@ impl FontTemplates {
         None
     }
 
-    fn add_template(&mut self, identifier: Atom, maybe_data: Option<Vec<u8>>) {
+    /// Find the fonts in this family that match a given descriptor, one for each distinct
+    /// `unicode-range`, so that a family split into several files covers all of its
+    /// characters.
+    fn find_fonts_for_style(&mut self, desc: &FontTemplateDescriptor, fctx: &FontContextHandle)
+                            -> Vec<(Arc<FontTemplateData>, Vec<UnicodeRange>)> {
+        let mut unicode_ranges: Vec<Vec<UnicodeRange>> = vec!();
+        for template in &self.templates {
+            if !unicode_ranges.iter().any(|range| &range[..] == template.unicode_range()) {
+                unicode_ranges.push(template.unicode_range().to_vec());
+            }
+        }
+
+        unicode_ranges.into_iter().filter_map(|unicode_range| {
+            self.find_font_for_style(desc, &unicode_range, fctx).map(|data| (data, unicode_range))
+        }).collect()
+    }
+
+    fn add_template(&mut self,
+                    identifier: Atom,
+                    maybe_data: Option<Vec<u8>>,
+                    unicode_range: Vec<UnicodeRange>) {
         for template in &self.templates {
             if *template.identifier() == identifier {
                 return;
             }
         }
 
-        let template = FontTemplate::new(identifier, maybe_data);
+        let template = FontTemplate::new(identifier, maybe_data, unicode_range);
         self.templates.push(template);
     }
+
+    /// Removes the deferred web fonts that cover the given character, so they can be
+    /// downloaded.
+    fn take_deferred_web_fonts(&mut self, character: char) -> Vec<DeferredWebFont> {
+        let (needed, deferred): (Vec<_>, Vec<_>) =
+            mem::replace(&mut self.deferred, vec!()).into_iter().partition(|font| {
+                font.unicode_range.iter().any(|range| range.contains(character))
+            });
+        self.deferred = deferred;
+        needed
+    }
+
+    fn deferred_unicode_range(&self) -> Vec<UnicodeRange> {
+        self.deferred.iter().flat_map(|font| font.unicode_range.iter().cloned()).collect()
+    }
 }
 
 /// Commands that the FontContext sends to the font cache thread.
 #[derive(Deserialize, Serialize, Debug)]
 pub enum Command {
-    GetFontTemplate(FontFamily, FontTemplateDescriptor, IpcSender<Reply>),
+    GetFontTemplates(FontFamily, FontTemplateDescriptor, IpcSender<Reply>),
     GetLastResortFontTemplate(FontTemplateDescriptor, IpcSender<Reply>),
-    AddWebFont(FontFamily, Source, IpcSender<()>),
-    AddDownloadedWebFont(FontFamily, Url, Vec<u8>, IpcSender<()>),
+    GetFallbackFontTemplates(char, FontTemplateDescriptor, IpcSender<Reply>),
+    AddWebFont(FontFamily, Source, Vec<UnicodeRange>, IpcSender<()>),
+    AddDeferredWebFont(FontFamily, Source, Vec<UnicodeRange>, IpcSender<()>),
+    AddDownloadedWebFont(FontFamily, Url, Vec<u8>, Vec<UnicodeRange>, IpcSender<()>),
+    LoadDeferredWebFonts(FontFamily, char),
     Exit(IpcSender<()>),
 }
 
 /// Reply messages sent from the font cache thread to the FontContext caller.
 #[derive(Deserialize, Serialize, Debug)]
 pub enum Reply {
+    GetFontTemplatesReply(FontFamilyInfo),
     GetFontTemplateReply(Option<FontTemplateInfo>),
 }
 
@@ -128,8 +199,12 @@ struct FontCache {
     core_resource_thread: CoreResourceThread,
     webrender_api: Option<webrender_traits::RenderApi>,
     webrender_fonts: HashMap<Atom, webrender_traits::FontKey>,
+    /// The installed fallback families for each script that text has needed so far.
+    fallback_families: Vec<(Script, Vec<LowercaseString>)>,
 }
 
+/// Maps generic families to the families that the user prefers for them in every language, or
+/// the defaults of the platform when they prefer none.
 fn populate_generic_fonts() -> HashMap<FontFamily, LowercaseString> {
     let mut generic_fonts = HashMap::with_capacity(5);
 
@@ -142,11 +217,14 @@ fn populate_generic_fonts() -> HashMap<FontFamily, LowercaseString> {
     fn append_map(generic_fonts: &mut HashMap<FontFamily, LowercaseString>,
                   font_family: FontFamily,
                   mapped_name: &str) {
-        let family_name = {
-            let opt_system_default = system_default_family(font_family.name());
-            match opt_system_default {
-                Some(system_default) => LowercaseString::new(&system_default),
-                None => LowercaseString::new(mapped_name)
+        let family_name = match font_preferences::preferred_family(font_family.name(), None) {
+            Some(preferred) => LowercaseString::new(&preferred),
+            None => {
+                let opt_system_default = system_default_family(font_family.name());
+                match opt_system_default {
+                    Some(system_default) => LowercaseString::new(&system_default),
+                    None => LowercaseString::new(mapped_name)
+                }
             }
         };
 
@@ -163,99 +241,51 @@ impl FontCache {
             let msg = self.port.recv().unwrap();
 
             match msg {
-                Command::GetFontTemplate(family, descriptor, result) => {
-                    let maybe_font_template = self.find_font_template(&family, &descriptor);
-                    result.send(Reply::GetFontTemplateReply(maybe_font_template)).unwrap();
+                Command::GetFontTemplates(family, descriptor, result) => {
+                    let family_info = self.find_font_templates(&family, &descriptor);
+                    result.send(Reply::GetFontTemplatesReply(family_info)).unwrap();
                 }
                 Command::GetLastResortFontTemplate(descriptor, result) => {
                     let font_template = self.last_resort_font_template(&descriptor);
                     result.send(Reply::GetFontTemplateReply(Some(font_template))).unwrap();
                 }
-                Command::AddWebFont(family, src, result) => {
+                Command::GetFallbackFontTemplates(character, descriptor, result) => {
+                    let family_info = self.fallback_font_templates(character, &descriptor);
+                    result.send(Reply::GetFontTemplatesReply(family_info)).unwrap();
+                }
+                Command::AddWebFont(family, src, unicode_range, result) => {
+                    self.add_web_font(family, src, unicode_range, result);
+                }
+                Command::AddDeferredWebFont(family, src, unicode_range, result) => {
                     let family_name = LowercaseString::new(family.name());
-                    if !self.web_families.contains_key(&family_name) {
-                        let templates = FontTemplates::new();
-                        self.web_families.insert(family_name.clone(), templates);
-                    }
-
-                    match src {
-                        Source::Url(ref url_source) => {
-                            let url = &url_source.url;
-                            let load = PendingAsyncLoad::new(LoadContext::Font,
-                                                             self.core_resource_thread.clone(),
-                                                             url.clone(),
-                                                             None,
-                                                             None,
-                                                             None,
-                                                             RequestSource::None);
-                            let (data_sender, data_receiver) = ipc::channel().unwrap();
-                            let data_target = AsyncResponseTarget {
-                                sender: data_sender,
-                            };
-                            load.load_async(data_target);
-                            let channel_to_self = self.channel_to_self.clone();
-                            let url = (*url).clone();
-                            let bytes = Mutex::new(Vec::new());
-                            let response_valid = Mutex::new(false);
-                            ROUTER.add_route(data_receiver.to_opaque(), box move |message| {
-                                let response: ResponseAction = message.to().unwrap();
-                                match response {
-                                    ResponseAction::HeadersAvailable(meta_result) => {
-                                        let is_response_valid = match meta_result {
-                                            Ok(ref metadata) => {
-                                                metadata.content_type.as_ref().map_or(false, |content_type| {
-                                                    let mime = &content_type.0;
-                                                    is_supported_font_type(&mime.0, &mime.1)
-                                                })
-                                            }
-                                            Err(_) => false,
-                                        };
-
-                                        info!("{} font with MIME type {}",
-                                              if is_response_valid { "Loading" } else { "Ignoring" },
-                                              meta_result.map(|ref meta| format!("{:?}", meta.content_type))
-                                                         .unwrap_or(format!("<Network Error>")));
-                                        *response_valid.lock().unwrap() = is_response_valid;
-                                    }
-                                    ResponseAction::DataAvailable(new_bytes) => {
-                                        if *response_valid.lock().unwrap() {
-                                            bytes.lock().unwrap().extend(new_bytes.into_iter())
-                                        }
-                                    }
-                                    ResponseAction::ResponseComplete(response) => {
-                                        if response.is_err() || !*response_valid.lock().unwrap() {
-                                            drop(result.send(()));
-                                            return;
-                                        }
-                                        let mut bytes = bytes.lock().unwrap();
-                                        let bytes = mem::replace(&mut *bytes, Vec::new());
-                                        let command =
-                                            Command::AddDownloadedWebFont(family.clone(),
-                                                                          url.clone(),
-                                                                          bytes,
-                                                                          result.clone());
-                                        channel_to_self.send(command).unwrap();
-                                    }
-                                }
-                            });
-                        }
-                        Source::Local(ref font) => {
-                            let font_face_name = LowercaseString::new(font.name());
-                            let templates = &mut self.web_families.get_mut(&family_name).unwrap();
-                            for_each_variation(&font_face_name, |path| {
-                                templates.add_template(Atom::from(&*path), None);
-                            });
-                            result.send(()).unwrap();
-                        }
-                    }
+                    let templates =
+                        self.web_families.entry(family_name).or_insert_with(FontTemplates::new);
+                    templates.deferred.push(DeferredWebFont {
+                        source: src,
+                        unicode_range: unicode_range,
+                        sender: result,
+                    });
                 }
-                Command::AddDownloadedWebFont(family, url, bytes, result) => {
+                Command::AddDownloadedWebFont(family, url, bytes, unicode_range, result) => {
                     let family_name = LowercaseString::new(family.name());
 
                     let templates = &mut self.web_families.get_mut(&family_name).unwrap();
-                    templates.add_template(Atom::from(url.to_string()), Some(bytes));
+                    templates.add_template(Atom::from(url.to_string()), Some(bytes), unicode_range);
                     drop(result.send(()));
                 }
+                Command::LoadDeferredWebFonts(family, character) => {
+                    let family_name = LowercaseString::new(family.name());
+                    let needed = match self.web_families.get_mut(&family_name) {
+                        Some(templates) => templates.take_deferred_web_fonts(character),
+                        None => vec![],
+                    };
+                    for font in needed {
+                        self.add_web_font(family.clone(),
+                                          font.source,
+                                          font.unicode_range,
+                                          font.sender);
+                    }
+                }
                 Command::Exit(result) => {
                     result.send(()).unwrap();
                     break;
@@ -264,6 +294,90 @@ impl FontCache {
         }
     }
 
+    fn add_web_font(&mut self,
+                    family: FontFamily,
+                    src: Source,
+                    unicode_range: Vec<UnicodeRange>,
+                    result: IpcSender<()>) {
+        let family_name = LowercaseString::new(family.name());
+        if !self.web_families.contains_key(&family_name) {
+            let templates = FontTemplates::new();
+            self.web_families.insert(family_name.clone(), templates);
+        }
+
+        match src {
+            Source::Url(ref url_source) => {
+                let url = &url_source.url;
+                let load = PendingAsyncLoad::new(LoadContext::Font,
+                                                 self.core_resource_thread.clone(),
+                                                 url.clone(),
+                                                 None,
+                                                 None,
+                                                 None,
+                                                 RequestSource::None);
+                let (data_sender, data_receiver) = ipc::channel().unwrap();
+                let data_target = AsyncResponseTarget {
+                    sender: data_sender,
+                };
+                load.load_async(data_target);
+                let channel_to_self = self.channel_to_self.clone();
+                let url = (*url).clone();
+                let bytes = Mutex::new(Vec::new());
+                let response_valid = Mutex::new(false);
+                ROUTER.add_route(data_receiver.to_opaque(), box move |message| {
+                    let response: ResponseAction = message.to().unwrap();
+                    match response {
+                        ResponseAction::HeadersAvailable(meta_result) => {
+                            let is_response_valid = match meta_result {
+                                Ok(ref metadata) => {
+                                    metadata.content_type.as_ref().map_or(false, |content_type| {
+                                        let mime = &content_type.0;
+                                        is_supported_font_type(&mime.0, &mime.1)
+                                    })
+                                }
+                                Err(_) => false,
+                            };
+
+                            info!("{} font with MIME type {}",
+                                  if is_response_valid { "Loading" } else { "Ignoring" },
+                                  meta_result.map(|ref meta| format!("{:?}", meta.content_type))
+                                             .unwrap_or(format!("<Network Error>")));
+                            *response_valid.lock().unwrap() = is_response_valid;
+                        }
+                        ResponseAction::DataAvailable(new_bytes) => {
+                            if *response_valid.lock().unwrap() {
+                                bytes.lock().unwrap().extend(new_bytes.into_iter())
+                            }
+                        }
+                        ResponseAction::ResponseComplete(response) => {
+                            if response.is_err() || !*response_valid.lock().unwrap() {
+                                drop(result.send(()));
+                                return;
+                            }
+                            let mut bytes = bytes.lock().unwrap();
+                            let bytes = mem::replace(&mut *bytes, Vec::new());
+                            let command =
+                                Command::AddDownloadedWebFont(family.clone(),
+                                                              url.clone(),
+                                                              bytes,
+                                                              unicode_range.clone(),
+                                                              result.clone());
+                            channel_to_self.send(command).unwrap();
+                        }
+                    }
+                });
+            }
+            Source::Local(ref font) => {
+                let font_face_name = LowercaseString::new(font.name());
+                let templates = &mut self.web_families.get_mut(&family_name).unwrap();
+                for_each_variation(&font_face_name, |path| {
+                    templates.add_template(Atom::from(&*path), None, unicode_range.clone());
+                });
+                result.send(()).unwrap();
+            }
+        }
+    }
+
     fn refresh_local_families(&mut self) {
         self.local_families.clear();
         for_each_available_family(|family_name| {
@@ -292,33 +406,36 @@ impl FontCache {
 
             if s.templates.is_empty() {
                 for_each_variation(family_name, |path| {
-                    s.add_template(Atom::from(&*path), None);
+                    s.add_template(Atom::from(&*path), None, vec![UnicodeRange::all()]);
                 });
             }
 
             // TODO(Issue #192: handle generic font families, like 'serif' and 'sans-serif'.
             // if such family exists, try to match style to a font
 
-            s.find_font_for_style(desc, &self.font_context)
+            s.find_font_for_style(desc, &[UnicodeRange::all()], &self.font_context)
         } else {
             debug!("FontList: Couldn't find font family with name={}", &**family_name);
             None
         }
     }
 
-    fn find_font_in_web_family(&mut self, family: &FontFamily, desc: &FontTemplateDescriptor)
-                                -> Option<Arc<FontTemplateData>> {
+    fn find_fonts_in_web_family(&mut self, family: &FontFamily, desc: &FontTemplateDescriptor)
+                                -> Vec<(Arc<FontTemplateData>, Vec<UnicodeRange>)> {
         let family_name = LowercaseString::new(family.name());
 
         if self.web_families.contains_key(&family_name) {
             let templates = self.web_families.get_mut(&family_name).unwrap();
-            templates.find_font_for_style(desc, &self.font_context)
+            templates.find_fonts_for_style(desc, &self.font_context)
         } else {
-            None
+            vec!()
         }
     }
 
-    fn get_font_template_info(&mut self, template: Arc<FontTemplateData>) -> FontTemplateInfo {
+    fn get_font_template_info(&mut self,
+                              template: Arc<FontTemplateData>,
+                              unicode_range: Vec<UnicodeRange>)
+                              -> FontTemplateInfo {
         let webrender_fonts = &mut self.webrender_fonts;
         let font_key = self.webrender_api.as_ref().map(|webrender_api| {
             *webrender_fonts.entry(template.identifier.clone()).or_insert_with(|| {
@@ -333,20 +450,30 @@ impl FontCache {
         FontTemplateInfo {
             font_template: template,
             font_key: font_key,
+            unicode_range: unicode_range,
         }
     }
 
-    fn find_font_template(&mut self, family: &FontFamily, desc: &FontTemplateDescriptor)
-                            -> Option<FontTemplateInfo> {
-        let template = self.find_font_in_web_family(family, desc)
-            .or_else(|| {
-                let transformed_family = self.transform_family(family);
-                self.find_font_in_local_family(&transformed_family, desc)
-            });
+    fn find_font_templates(&mut self, family: &FontFamily, desc: &FontTemplateDescriptor)
+                           -> FontFamilyInfo {
+        let mut templates = self.find_fonts_in_web_family(family, desc);
+        if templates.is_empty() {
+            let transformed_family = self.transform_family(family);
+            templates.extend(self.find_font_in_local_family(&transformed_family, desc).map(|data| {
+                (data, vec![UnicodeRange::all()])
+            }));
+        }
+
+        let deferred_unicode_range = self.web_families
+                                         .get(&LowercaseString::new(family.name()))
+                                         .map_or(vec!(), FontTemplates::deferred_unicode_range);
 
-        template.map(|template| {
-            self.get_font_template_info(template)
-        })
+        FontFamilyInfo {
+            templates: templates.into_iter().map(|(template, unicode_range)| {
+                self.get_font_template_info(template, unicode_range)
+            }).collect(),
+            deferred_unicode_range: deferred_unicode_range,
+        }
     }
 
     fn last_resort_font_template(&mut self, desc: &FontTemplateDescriptor)
@@ -357,12 +484,44 @@ impl FontCache {
             let family = LowercaseString::new(family);
             let maybe_font_in_family = self.find_font_in_local_family(&family, desc);
             if let Some(family) = maybe_font_in_family {
-                return self.get_font_template_info(family)
+                return self.get_font_template_info(family, vec![UnicodeRange::all()])
             }
         }
 
         panic!("Unable to find any fonts that match (do you have fallback fonts installed?)");
     }
+
+    /// Returns the installed families that may have glyphs for the given script.
+    fn fallback_families_for_script(&mut self, script: Script) -> Vec<LowercaseString> {
+        if let Some(&(_, ref families)) = self.fallback_families.iter().find(|&&(cached, _)| {
+            cached == script
+        }) {
+            return families.clone()
+        }
+
+        let families = fallback_font_families(script).iter().map(|family| {
+            LowercaseString::new(family)
+        }).filter(|family| self.local_families.contains_key(family)).collect::<Vec<_>>();
+        self.fallback_families.push((script, families.clone()));
+        families
+    }
+
+    /// Finds a font in each fallback family for the script of the character, for when none of
+    /// the fonts of the style have a glyph for it.
+    fn fallback_font_templates(&mut self, character: char, desc: &FontTemplateDescriptor)
+                               -> FontFamilyInfo {
+        let families = self.fallback_families_for_script(get_script(character));
+        let templates = families.iter().filter_map(|family| {
+            self.find_font_in_local_family(family, desc)
+        }).collect::<Vec<_>>();
+
+        FontFamilyInfo {
+            templates: templates.into_iter().map(|template| {
+                self.get_font_template_info(template, vec![UnicodeRange::all()])
+            }).collect(),
+            deferred_unicode_range: vec!(),
+        }
+    }
 }
 
 /// The public interface to the font cache thread, used exclusively by
@@ -392,6 +551,7 @@ impl FontCacheThread {
                 core_resource_thread: core_resource_thread,
                 webrender_api: webrender_api,
                 webrender_fonts: HashMap::new(),
+                fallback_families: vec!(),
             };
 
             cache.refresh_local_families();
@@ -403,17 +563,18 @@ impl FontCacheThread {
         }
     }
 
-    pub fn find_font_template(&self, family: FontFamily, desc: FontTemplateDescriptor)
-                                                -> Option<FontTemplateInfo> {
+    pub fn find_font_templates(&self, family: FontFamily, desc: FontTemplateDescriptor)
+                                                -> FontFamilyInfo {
         let (response_chan, response_port) = ipc::channel().unwrap();
-        self.chan.send(Command::GetFontTemplate(family, desc, response_chan)).unwrap();
+        self.chan.send(Command::GetFontTemplates(family, desc, response_chan)).unwrap();
 
         let reply = response_port.recv().unwrap();
 
         match reply {
-            Reply::GetFontTemplateReply(data) => {
-                data
+            Reply::GetFontTemplatesReply(family_info) => {
+                family_info
             }
+            Reply::GetFontTemplateReply(_) => unreachable!(),
         }
     }
 
@@ -428,11 +589,48 @@ impl FontCacheThread {
             Reply::GetFontTemplateReply(data) => {
                 data.unwrap()
             }
+            Reply::GetFontTemplatesReply(_) => unreachable!(),
+        }
+    }
+
+    /// Returns a font of each system family that may have a glyph for the character, based on
+    /// its Unicode script.
+    pub fn fallback_font_templates(&self, character: char, desc: FontTemplateDescriptor)
+                                   -> FontFamilyInfo {
+        let (response_chan, response_port) = ipc::channel().unwrap();
+        self.chan.send(Command::GetFallbackFontTemplates(character, desc, response_chan)).unwrap();
+
+        let reply = response_port.recv().unwrap();
+
+        match reply {
+            Reply::GetFontTemplatesReply(family_info) => {
+                family_info
+            }
+            Reply::GetFontTemplateReply(_) => unreachable!(),
         }
     }
 
-    pub fn add_web_font(&self, family: FontFamily, src: Source, sender: IpcSender<()>) {
-        self.chan.send(Command::AddWebFont(family, src, sender)).unwrap();
+    pub fn add_web_font(&self,
+                        family: FontFamily,
+                        src: Source,
+                        unicode_range: Vec<UnicodeRange>,
+                        sender: IpcSender<()>) {
+        self.chan.send(Command::AddWebFont(family, src, unicode_range, sender)).unwrap();
+    }
+
+    /// Adds a web font that is only downloaded once `load_deferred_web_fonts` is called for a
+    /// character in its `unicode-range`. The sender is notified once that download is done.
+    pub fn add_deferred_web_font(&self,
+                                 family: FontFamily,
+                                 src: Source,
+                                 unicode_range: Vec<UnicodeRange>,
+                                 sender: IpcSender<()>) {
+        self.chan.send(Command::AddDeferredWebFont(family, src, unicode_range, sender)).unwrap();
+    }
+
+    /// Starts downloading the deferred web fonts of the family that cover the character.
+    pub fn load_deferred_web_fonts(&self, family: FontFamily, character: char) {
+        self.chan.send(Command::LoadDeferredWebFonts(family, character)).unwrap();
     }
 
     pub fn exit(&self) {
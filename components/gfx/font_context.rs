@@ -17,15 +17,19 @@ use platform::font_context::FontContextHandle;
 use platform::font_template::FontTemplateData;
 use smallvec::SmallVec;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::default::Default;
 use std::hash::{BuildHasherDefault, Hash, Hasher};
 use std::rc::Rc;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
 use string_cache::Atom;
+use style::computed_values::font_family::FontFamily;
 use style::computed_values::{font_style, font_variant};
+use style::font_face::UnicodeRange;
+use style::font_preferences;
 use style::properties::style_structs::ServoFont;
+use unicode_script::{Script, get_script};
 use webrender_traits;
 
 #[cfg(any(target_os = "linux", target_os = "android", target_os = "windows"))]
@@ -45,7 +49,8 @@ static SMALL_CAPS_SCALE_FACTOR: f32 = 0.8;      // Matches FireFox (see gfxFont.
 #[derive(Debug)]
 struct LayoutFontCacheEntry {
     family: String,
-    font: Option<Rc<RefCell<Font>>>,
+    fonts: Vec<Rc<RefCell<Font>>>,
+    deferred_unicode_range: Vec<UnicodeRange>,
 }
 
 #[derive(Debug)]
@@ -53,6 +58,16 @@ struct FallbackFontCacheEntry {
     font: Rc<RefCell<Font>>,
 }
 
+/// The fonts of the system fallback families of a script, at a given style.
+#[derive(Debug)]
+struct ScriptFallbackFontCacheEntry {
+    script: Script,
+    descriptor: FontTemplateDescriptor,
+    pt_size: Au,
+    variant: font_variant::T,
+    fonts: Vec<Rc<RefCell<Font>>>,
+}
+
 /// A cached azure font (per paint thread) that
 /// can be shared by multiple text runs.
 #[derive(Debug)]
@@ -78,6 +93,7 @@ pub struct FontContext {
     /// TODO: See bug https://github.com/servo/servo/issues/3300.
     layout_font_cache: Vec<LayoutFontCacheEntry>,
     fallback_font_cache: Vec<FallbackFontCacheEntry>,
+    script_fallback_font_cache: Vec<ScriptFallbackFontCacheEntry>,
 
     /// Strong reference as the paint FontContext is (for now) recycled
     /// per frame. TODO: Make this weak when incremental redraw is done.
@@ -86,6 +102,9 @@ pub struct FontContext {
     layout_font_group_cache:
         HashMap<LayoutFontGroupCacheKey, Rc<FontGroup>, BuildHasherDefault<FnvHasher>>,
 
+    /// The characters for which the deferred web fonts of a family have been requested.
+    requested_deferred_web_fonts: HashSet<(String, char)>,
+
     epoch: usize,
 }
 
@@ -97,8 +116,10 @@ impl FontContext {
             font_cache_thread: font_cache_thread,
             layout_font_cache: vec!(),
             fallback_font_cache: vec!(),
+            script_fallback_font_cache: vec!(),
             paint_font_cache: vec!(),
             layout_font_group_cache: HashMap::with_hasher(Default::default()),
+            requested_deferred_web_fonts: HashSet::new(),
             epoch: 0,
         }
     }
@@ -109,7 +130,8 @@ impl FontContext {
                           descriptor: FontTemplateDescriptor,
                           pt_size: Au,
                           variant: font_variant::T,
-                          font_key: Option<webrender_traits::FontKey>) -> Result<Font, ()> {
+                          font_key: Option<webrender_traits::FontKey>,
+                          unicode_range: Vec<UnicodeRange>) -> Result<Font, ()> {
         // TODO: (Bug #3463): Currently we only support fake small-caps
         // painting. We should also support true small-caps (where the
         // font supports it) in the future.
@@ -122,7 +144,7 @@ impl FontContext {
                                                         template,
                                                         Some(actual_pt_size)));
 
-        Ok(Font::new(handle, variant, descriptor, pt_size, actual_pt_size, font_key))
+        Ok(Font::new(handle, variant, descriptor, pt_size, actual_pt_size, font_key, unicode_range))
     }
 
     fn expire_font_caches_if_necessary(&mut self) {
@@ -133,6 +155,7 @@ impl FontContext {
 
         self.layout_font_cache.clear();
         self.fallback_font_cache.clear();
+        self.script_fallback_font_cache.clear();
         self.paint_font_cache.clear();
         self.layout_font_group_cache.clear();
         self.epoch = current_epoch
@@ -163,63 +186,81 @@ impl FontContext {
                                                 style.font_style == font_style::T::oblique);
 
         let mut fonts: SmallVec<[Rc<RefCell<Font>>; 8]> = SmallVec::new();
+        let mut deferred_web_fonts = vec![];
 
+        // Generic families map to the families that the user prefers for the language of the
+        // text, if they set any, before those that the font cache maps them to.
+        let language_tag = style._servo_lang.0.as_ref().map(|language_tag| &**language_tag);
+        let mut families = Vec::with_capacity(style.font_family.0.len());
         for family in &style.font_family.0 {
+            if let FontFamily::Generic(ref generic) = *family {
+                if let Some(preferred) = font_preferences::preferred_family(generic, language_tag) {
+                    families.push(FontFamily::FamilyName(preferred));
+                }
+            }
+            families.push(family.clone());
+        }
+
+        for family in &families {
             // GWTODO: Check on real pages if this is faster as Vec() or HashMap().
             let mut cache_hit = false;
             for cached_font_entry in &self.layout_font_cache {
                 if cached_font_entry.family == family.name() {
-                    match cached_font_entry.font {
-                        None => {
-                            cache_hit = true;
-                            break;
-                        }
-                        Some(ref cached_font_ref) => {
+                    match cached_font_entry.fonts.first() {
+                        None => cache_hit = true,
+                        Some(cached_font_ref) => {
                             let cached_font = (*cached_font_ref).borrow();
                             if cached_font.descriptor == desc &&
                                cached_font.requested_pt_size == style.font_size &&
                                cached_font.variant == style.font_variant {
-                                fonts.push((*cached_font_ref).clone());
+                                for cached_font_ref in &cached_font_entry.fonts {
+                                    fonts.push((*cached_font_ref).clone());
+                                }
                                 cache_hit = true;
-                                break;
                             }
                         }
                     }
+
+                    if cache_hit {
+                        if !cached_font_entry.deferred_unicode_range.is_empty() {
+                            deferred_web_fonts.push((family.clone(),
+                                                     cached_font_entry.deferred_unicode_range
+                                                                      .clone()));
+                        }
+                        break;
+                    }
                 }
             }
 
             if !cache_hit {
-                let template_info = self.font_cache_thread.find_font_template(family.clone(),
+                // A family with several `unicode-range`s is made up of one font for each.
+                let family_info = self.font_cache_thread.find_font_templates(family.clone(),
                                                                              desc.clone());
-                match template_info {
-                    Some(template_info) => {
-                        let layout_font = self.create_layout_font(template_info.font_template,
-                                                                  desc.clone(),
-                                                                  style.font_size,
-                                                                  style.font_variant,
-                                                                  template_info.font_key);
-                        let font = match layout_font {
-                            Ok(layout_font) => {
-                                let layout_font = Rc::new(RefCell::new(layout_font));
-                                fonts.push(layout_font.clone());
-
-                                Some(layout_font)
-                            }
-                            Err(_) => None
-                        };
-
-                        self.layout_font_cache.push(LayoutFontCacheEntry {
-                            family: family.name().to_owned(),
-                            font: font
-                        });
-                    }
-                    None => {
-                        self.layout_font_cache.push(LayoutFontCacheEntry {
-                            family: family.name().to_owned(),
-                            font: None,
-                        });
+                let mut family_fonts = vec![];
+                for template_info in family_info.templates {
+                    let layout_font = self.create_layout_font(template_info.font_template,
+                                                              desc.clone(),
+                                                              style.font_size,
+                                                              style.font_variant,
+                                                              template_info.font_key,
+                                                              template_info.unicode_range);
+                    if let Ok(layout_font) = layout_font {
+                        let layout_font = Rc::new(RefCell::new(layout_font));
+                        fonts.push(layout_font.clone());
+                        family_fonts.push(layout_font);
                     }
                 }
+
+                if !family_info.deferred_unicode_range.is_empty() {
+                    deferred_web_fonts.push((family.clone(),
+                                             family_info.deferred_unicode_range.clone()));
+                }
+
+                self.layout_font_cache.push(LayoutFontCacheEntry {
+                    family: family.name().to_owned(),
+                    fonts: family_fonts,
+                    deferred_unicode_range: family_info.deferred_unicode_range,
+                });
             }
         }
 
@@ -242,7 +283,8 @@ impl FontContext {
                                                       desc.clone(),
                                                       style.font_size,
                                                       style.font_variant,
-                                                      template_info.font_key);
+                                                      template_info.font_key,
+                                                      template_info.unicode_range);
             match layout_font {
                 Ok(layout_font) => {
                     let layout_font = Rc::new(RefCell::new(layout_font));
@@ -255,11 +297,58 @@ impl FontContext {
             }
         }
 
-        let font_group = Rc::new(FontGroup::new(fonts));
+        let font_group = Rc::new(FontGroup::new(fonts, deferred_web_fonts));
         self.layout_font_group_cache.insert(layout_font_group_cache_key, font_group.clone());
         font_group
     }
 
+    /// Returns a system font with a glyph for the character, chosen among the fallback families
+    /// of its Unicode script. Used when none of the fonts of the style have such a glyph.
+    pub fn fallback_font_for_character(&mut self, character: char, style: &ServoFont)
+                                       -> Option<Rc<RefCell<Font>>> {
+        self.expire_font_caches_if_necessary();
+
+        let script = get_script(character);
+        let desc = FontTemplateDescriptor::new(style.font_weight,
+                                               style.font_stretch,
+                                               style.font_style == font_style::T::italic ||
+                                                style.font_style == font_style::T::oblique);
+
+        let cached_index = self.script_fallback_font_cache.iter().position(|entry| {
+            entry.script == script &&
+                entry.descriptor == desc &&
+                entry.pt_size == style.font_size &&
+                entry.variant == style.font_variant
+        });
+        let index = match cached_index {
+            Some(index) => index,
+            None => {
+                let family_info = self.font_cache_thread.fallback_font_templates(character,
+                                                                                 desc.clone());
+                let fonts = family_info.templates.into_iter().filter_map(|template_info| {
+                    self.create_layout_font(template_info.font_template,
+                                            desc.clone(),
+                                            style.font_size,
+                                            style.font_variant,
+                                            template_info.font_key,
+                                            template_info.unicode_range).ok()
+                }).map(|font| Rc::new(RefCell::new(font))).collect();
+                self.script_fallback_font_cache.push(ScriptFallbackFontCacheEntry {
+                    script: script,
+                    descriptor: desc,
+                    pt_size: style.font_size,
+                    variant: style.font_variant,
+                    fonts: fonts,
+                });
+                self.script_fallback_font_cache.len() - 1
+            }
+        };
+
+        self.script_fallback_font_cache[index].fonts.iter().find(|font| {
+            font.borrow().glyph_index(character).is_some()
+        }).cloned()
+    }
+
     /// Create a paint font for use with azure. May return a cached
     /// reference if already used by this font context.
     pub fn paint_font_from_template(&mut self,
@@ -282,6 +371,14 @@ impl FontContext {
         paint_font
     }
 
+    /// Asks the font cache thread to download the web fonts of the family whose `unicode-range`
+    /// covers the character. The fonts are used once layout is notified that they have loaded.
+    pub fn load_deferred_web_fonts(&mut self, family: &FontFamily, character: char) {
+        if self.requested_deferred_web_fonts.insert((family.name().to_owned(), character)) {
+            self.font_cache_thread.load_deferred_web_fonts(family.clone(), character);
+        }
+    }
+
     /// Returns a reference to the font cache thread.
     pub fn font_cache_thread(&self) -> FontCacheThread {
         self.font_cache_thread.clone()
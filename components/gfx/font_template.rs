@@ -11,6 +11,7 @@ use std::sync::{Arc, Weak};
 use std::u32;
 use string_cache::Atom;
 use style::computed_values::{font_stretch, font_weight};
+use style::font_face::UnicodeRange;
 
 /// Describes how to select a font from a given family. This is very basic at the moment and needs
 /// to be expanded or refactored when we support more of the font styling parameters.
@@ -65,6 +66,9 @@ pub struct FontTemplate {
     // GWTODO: Add code path to unset the strong_ref for web fonts!
     strong_ref: Option<Arc<FontTemplateData>>,
     is_valid: bool,
+    /// The code points this font may be used for, from the `unicode-range` of its
+    /// `@font-face` rule.
+    unicode_range: Vec<UnicodeRange>,
 }
 
 impl Debug for FontTemplate {
@@ -77,7 +81,8 @@ impl Debug for FontTemplate {
 /// is common, regardless of the number of instances of
 /// this font handle per thread.
 impl FontTemplate {
-    pub fn new(identifier: Atom, maybe_bytes: Option<Vec<u8>>) -> FontTemplate {
+    pub fn new(identifier: Atom, maybe_bytes: Option<Vec<u8>>, unicode_range: Vec<UnicodeRange>)
+               -> FontTemplate {
         let maybe_data = match maybe_bytes {
             Some(_) => Some(FontTemplateData::new(identifier.clone(), maybe_bytes)),
             None => None,
@@ -99,6 +104,7 @@ impl FontTemplate {
             weak_ref: maybe_weak_ref,
             strong_ref: maybe_strong_ref,
             is_valid: true,
+            unicode_range: unicode_range,
         }
     }
 
@@ -106,6 +112,10 @@ impl FontTemplate {
         &self.identifier
     }
 
+    pub fn unicode_range(&self) -> &[UnicodeRange] {
+        &self.unicode_range
+    }
+
     /// Get the data for creating a font if it matches a given descriptor.
     pub fn data_for_descriptor(&mut self,
                                fctx: &FontContextHandle,
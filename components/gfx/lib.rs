@@ -103,6 +103,9 @@ pub mod font_template;
 
 pub mod paint_thread;
 
+// Widgets
+pub mod theme;
+
 // Platform-specific implementations.
 #[allow(unsafe_code)]
 pub mod platform;
@@ -182,7 +182,8 @@ impl<'a> PaintContext<'a> {
                       stretch_size: &Size2D<Au>,
                       image_info: &WebRenderImageInfo,
                       image_data: &[u8],
-                      image_rendering: image_rendering::T) {
+                      image_rendering: image_rendering::T,
+                      opacity: AzFloat) {
         let size = Size2D::new(image_info.width as i32, image_info.height as i32);
         let (pixel_width, source_format) = match image_info.format {
             PixelFormat::RGBA8 => (4, SurfaceFormat::B8G8R8A8),
@@ -217,7 +218,7 @@ impl<'a> PaintContext<'a> {
         };
 
         let draw_surface_options = DrawSurfaceOptions::new(draw_surface_filter, true);
-        let draw_options = DrawOptions::new(1.0, CompositionOp::Over, AntialiasMode::None);
+        let draw_options = DrawOptions::new(opacity, CompositionOp::Over, AntialiasMode::None);
 
         // Fast path: No need to create a pattern.
         if bounds.size == *stretch_size {
@@ -264,7 +265,8 @@ impl<'a> PaintContext<'a> {
                                           true,
                                           true,
                                           &matrix);
-        draw_target_ref.fill_rect(&dest_rect, PatternRef::Surface(&pattern), None);
+        let draw_options = DrawOptions::new(opacity, CompositionOp::Over, AntialiasMode::None);
+        draw_target_ref.fill_rect(&dest_rect, PatternRef::Surface(&pattern), Some(&draw_options));
     }
 
     pub fn clear(&self) {
@@ -1409,11 +1411,13 @@ impl<'a> PaintContext<'a> {
 
     pub fn get_or_create_temporary_draw_target(&mut self,
                                                filters: &filter::T,
-                                               blend_mode: mix_blend_mode::T)
+                                               blend_mode: mix_blend_mode::T,
+                                               masked: bool)
                                                -> DrawTarget {
-        // Determine if we need a temporary draw target.
+        // Determine if we need a temporary draw target. Masks are applied to what has been
+        // painted so far, so masked contents are painted on their own.
         if !filters::temporary_draw_target_needed_for_style_filters(filters) &&
-                blend_mode == mix_blend_mode::T::normal {
+                blend_mode == mix_blend_mode::T::normal && !masked {
             // Reuse the draw target, but remove the transient clip. If we don't do the latter,
             // we'll be in a state whereby the paint subcontext thinks it has no transient clip
             // (see `StackingContext::optimize_and_draw_into_context`) but it actually does,
@@ -1481,27 +1485,67 @@ impl<'a> PaintContext<'a> {
                                                              &mut accum_blur);
 
         // Perform the blit operation.
-        let mut draw_options = DrawOptions::new(opacity, CompositionOp::Over, AntialiasMode::None);
-        draw_options.set_composition_op(blend_mode.to_azure_composition_op());
+        let draw_options = || {
+            let mut draw_options = DrawOptions::new(opacity,
+                                                    CompositionOp::Over,
+                                                    AntialiasMode::None);
+            draw_options.set_composition_op(blend_mode.to_azure_composition_op());
+            draw_options
+        };
 
        // If there is a blur expansion, shift the transform and update the size.
-        if accum_blur > Au(0) {
+        let source_rect = if accum_blur > Au(0) {
             // Remove both the transient clip and the stacking context clip, because we may need to
             // draw outside the stacking context's clip.
             self.remove_transient_clip_if_applicable();
             self.pop_clip_if_applicable();
 
             debug!("######### use expanded Rect.");
-            self.draw_target.draw_filter(&filter_node,
-                                         &rect_temporary,
-                                         &rect_temporary.origin,
-                                         draw_options);
-            self.push_clip_if_applicable();
+            rect_temporary
         } else {
             debug!("######### use regular Rect.");
-            self.draw_target.draw_filter(&filter_node, &rect, &rect.origin, draw_options);
+            rect
+        };
+
+        // Draw the shadows of `drop-shadow` filters beneath the filtered contents.
+        let pixels_per_px = self.screen_pixels_per_px().get();
+        for style_filter in &filters.filters {
+            if let filter::Filter::DropShadow(offset_x, offset_y, blur_radius, color) =
+                    *style_filter {
+                let shadow = filters::create_drop_shadow(&self.draw_target,
+                                                         &filter_node,
+                                                         blur_radius,
+                                                         color);
+                let origin = Point2D::new(
+                    source_rect.origin.x + offset_x.to_f32_px() * pixels_per_px,
+                    source_rect.origin.y + offset_y.to_f32_px() * pixels_per_px);
+                self.draw_target.draw_filter(&shadow, &source_rect, &origin, draw_options());
+            }
         }
+        self.draw_target.draw_filter(&filter_node,
+                                     &source_rect,
+                                     &source_rect.origin,
+                                     draw_options());
+        if accum_blur > Au(0) {
+            self.push_clip_if_applicable();
+        }
+
+        self.draw_target.set_transform(&old_transform);
+    }
 
+    /// Masks what has been painted with what has been painted to `mask_draw_target`, which has
+    /// the size of the draw target, by its luminance or by its alpha.
+    pub fn draw_mask(&mut self, mask_draw_target: &DrawTarget, luminance: bool) {
+        let old_transform = self.draw_target.get_transform();
+        self.draw_target.set_transform(&Matrix2D::identity());
+        let rect = Rect::new(Point2D::new(0.0, 0.0), mask_draw_target.get_size().to_azure_size());
+        let filter_node = filters::create_mask(&self.draw_target, mask_draw_target, luminance);
+        self.draw_target.draw_filter(&filter_node,
+                                     &rect,
+                                     &rect.origin,
+                                     DrawOptions::new(1.0,
+                                                      CompositionOp::DestIn,
+                                                      AntialiasMode::None));
         self.draw_target.set_transform(&old_transform);
     }
 
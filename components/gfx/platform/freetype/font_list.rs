@@ -18,6 +18,7 @@ use libc::{c_char, c_int};
 use std::borrow::ToOwned;
 use std::ffi::CString;
 use std::ptr;
+use unicode_script::Script;
 use util::str::c_str_to_string;
 
 static FC_FAMILY: &'static [u8] = b"family\0";
@@ -158,6 +159,29 @@ pub fn last_resort_font_families() -> Vec<String> {
     )
 }
 
+/// The families that may have glyphs for characters of the given script, in order of
+/// preference. Families that are not installed are skipped.
+pub fn fallback_font_families(script: Script) -> &'static [&'static str] {
+    match script {
+        Script::Han => &["Noto Sans CJK SC", "WenQuanYi Micro Hei", "Droid Sans Fallback",
+                         "Microsoft YaHei", "SimSun"],
+        Script::Hiragana | Script::Katakana => &["Noto Sans CJK JP", "TakaoPGothic", "IPAGothic",
+                                                 "Droid Sans Fallback", "Meiryo", "MS Gothic"],
+        Script::Hangul => &["Noto Sans CJK KR", "NanumGothic", "UnDotum", "Malgun Gothic"],
+        Script::Arabic => &["Noto Naskh Arabic", "DejaVu Sans", "Arial"],
+        Script::Hebrew => &["Noto Sans Hebrew", "DejaVu Sans", "Arial"],
+        Script::Devanagari => &["Noto Sans Devanagari", "Lohit Devanagari", "Mangal"],
+        Script::Bengali => &["Noto Sans Bengali", "Lohit Bengali", "Vrinda"],
+        Script::Tamil => &["Noto Sans Tamil", "Lohit Tamil", "Latha"],
+        Script::Thai => &["Noto Sans Thai", "Garuda", "Tahoma"],
+        Script::Armenian => &["Noto Sans Armenian", "DejaVu Sans", "Sylfaen"],
+        Script::Georgian => &["Noto Sans Georgian", "DejaVu Sans", "Sylfaen"],
+        Script::Ethiopic => &["Noto Sans Ethiopic", "Abyssinica SIL", "Nyala"],
+        Script::Greek | Script::Cyrillic => &["DejaVu Sans", "Noto Sans", "Arial"],
+        _ => &["Noto Color Emoji", "Symbola", "DejaVu Sans", "Segoe UI Symbol"],
+    }
+}
+
 #[cfg(target_os = "android")]
 pub static SANS_SERIF_FONT_FAMILY: &'static str = "Roboto";
 
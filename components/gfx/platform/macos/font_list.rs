@@ -8,6 +8,7 @@ use core_text;
 use core_text::font_descriptor::{CTFontDescriptor, CTFontDescriptorRef};
 use std::borrow::ToOwned;
 use std::mem;
+use unicode_script::Script;
 
 pub fn for_each_available_family<F>(mut callback: F) where F: FnMut(String) {
     let family_names = core_text::font_collection::get_family_names();
@@ -42,6 +43,27 @@ pub fn last_resort_font_families() -> Vec<String> {
     vec!("Arial Unicode MS".to_owned(), "Arial".to_owned())
 }
 
+/// The families that may have glyphs for characters of the given script, in order of
+/// preference. Families that are not installed are skipped.
+pub fn fallback_font_families(script: Script) -> &'static [&'static str] {
+    match script {
+        Script::Han => &["PingFang SC", "Hiragino Sans GB", "STHeiti"],
+        Script::Hiragana | Script::Katakana => &["Hiragino Sans", "Hiragino Kaku Gothic ProN"],
+        Script::Hangul => &["Apple SD Gothic Neo", "AppleGothic"],
+        Script::Arabic => &["Geeza Pro", "Arial Unicode MS"],
+        Script::Hebrew => &["Arial Hebrew", "Lucida Grande"],
+        Script::Devanagari => &["Kohinoor Devanagari", "Devanagari Sangam MN"],
+        Script::Bengali => &["Kohinoor Bangla", "Bangla Sangam MN"],
+        Script::Tamil => &["Tamil Sangam MN", "InaiMathi"],
+        Script::Thai => &["Thonburi", "Ayuthaya"],
+        Script::Armenian => &["Mshtakan", "Arial Unicode MS"],
+        Script::Georgian => &["Helvetica", "Arial Unicode MS"],
+        Script::Ethiopic => &["Kefa", "Arial Unicode MS"],
+        Script::Greek | Script::Cyrillic => &["Helvetica", "Lucida Grande"],
+        _ => &["Apple Color Emoji", "Apple Symbols", "Arial Unicode MS"],
+    }
+}
+
 #[cfg(target_os = "macos")]
 pub static SANS_SERIF_FONT_FAMILY: &'static str = "Helvetica";
 
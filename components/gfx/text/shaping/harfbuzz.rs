@@ -218,6 +218,14 @@ impl ShaperMethods for Shaper {
                     end: hb_buffer_get_length(hb_buffer),
                 })
             }
+            for feature in &options.features {
+                features.push(hb_feature_t {
+                    tag: feature.tag,
+                    value: feature.value,
+                    start: 0,
+                    end: hb_buffer_get_length(hb_buffer),
+                })
+            }
 
             hb_shape(self.hb_font, hb_buffer, features.as_mut_ptr(), features.len() as u32);
             self.save_glyph_results(text, options, glyphs, hb_buffer);
@@ -341,12 +349,10 @@ impl Shaper {
                 if is_bidi_control(character) {
                     // Don't add any glyphs for bidi control chars
                 } else if character == '\t' {
-                    // Treat tabs in pre-formatted text as a fixed number of spaces.
-                    //
-                    // TODO: Proper tab stops.
-                    const TAB_COLS: i32 = 8;
-                    let (space_glyph_id, space_advance) = glyph_space_advance(self.font);
-                    let advance = Au::from_f64_px(space_advance) * TAB_COLS;
+                    // Treat tabs in pre-formatted text as whitespace as wide as `tab-size`.
+                    let (space_glyph_id, advance) = unsafe {
+                        (*self.font).tab_glyph_and_advance(options).expect("No space info")
+                    };
                     let data = GlyphData::new(space_glyph_id,
                                               advance,
                                               Default::default(),
@@ -457,19 +463,6 @@ extern fn glyph_h_advance_func(_: *mut hb_font_t,
     }
 }
 
-fn glyph_space_advance(font: *const Font) -> (hb_codepoint_t, f64) {
-    let space_unicode = ' ';
-    let space_glyph: hb_codepoint_t;
-    match unsafe { (*font).glyph_index(space_unicode) } {
-        Some(g) => {
-            space_glyph = g as hb_codepoint_t;
-        }
-        None => panic!("No space info")
-    }
-    let space_advance = unsafe { (*font).glyph_h_advance(space_glyph as GlyphId) };
-    (space_glyph, space_advance)
-}
-
 extern fn glyph_h_kerning_func(_: *mut hb_font_t,
                                font_data: *mut c_void,
                                first_glyph: hb_codepoint_t,
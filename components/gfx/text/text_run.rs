@@ -270,6 +270,11 @@ impl<'a> TextRun {
         debug!("iterating outer range {:?}", range);
         self.natural_word_slices_in_range(range).fold(Au(0), |max_piece_width, slice| {
             debug!("iterated on {:?}[{:?}]", slice.offset, slice.range);
+            // Whitespace can always hang off the end of a line, so it never forces the line to
+            // be wider.
+            if slice.glyphs.is_whitespace() {
+                return max_piece_width
+            }
             max(max_piece_width, self.advance_for_range(&slice.range))
         })
     }
@@ -0,0 +1,251 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The native look of form widgets and scrollbars. Layout asks the theme for the sizes of the
+//! widgets that are left to it with `appearance: auto`, and for the display items that paint
+//! them instead of their backgrounds and borders.
+
+use app_units::Au;
+use azure::azure_hl::Color;
+use display_list::{BaseDisplayItem, BorderDisplayItem, BorderRadii, DisplayItem};
+use display_list::SolidColorDisplayItem;
+use euclid::{Point2D, Rect, SideOffsets2D, Size2D};
+use gfx_traits::color;
+use style::computed_values::border_style;
+
+/// A widget that the theme paints.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Widget {
+    /// A push button, such as `<button>` or `<input type=submit>`.
+    Button,
+    /// `<input type=checkbox>`.
+    Checkbox,
+    /// `<input type=radio>`.
+    Radio,
+    /// The scrollbar along the bottom of a scroll container.
+    HorizontalScrollbar,
+    /// The scrollbar along the end of a scroll container.
+    VerticalScrollbar,
+}
+
+/// The state of a widget, which changes how it looks.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WidgetState {
+    /// Whether a checkbox or radio button is checked.
+    pub checked: bool,
+    /// Whether a checkbox is indeterminate.
+    pub indeterminate: bool,
+    /// Whether the widget is being pressed.
+    pub active: bool,
+    /// Whether the widget is disabled.
+    pub disabled: bool,
+}
+
+/// How widgets look and how big they are.
+pub trait Theme: Send + Sync {
+    /// The size of a widget whose style leaves its size `auto`, if it has one of its own rather
+    /// than that of its contents.
+    fn intrinsic_size(&self, widget: Widget) -> Option<Size2D<Au>>;
+
+    /// The thickness of scrollbars, which are laid over the contents of scroll containers.
+    fn scrollbar_thickness(&self) -> Au;
+
+    /// Appends the display items that paint `widget` inside the bounds of `base`, with the same
+    /// metadata and clip, to `items`. For scrollbars, `thumb` is the part of the track that the
+    /// thumb covers.
+    fn build_display_items(&self,
+                           widget: Widget,
+                           state: WidgetState,
+                           thumb: Option<Rect<Au>>,
+                           base: &BaseDisplayItem,
+                           items: &mut Vec<DisplayItem>);
+}
+
+/// The theme of the platform that Servo runs on.
+pub fn platform_theme() -> &'static Theme {
+    static PLATFORM_THEME: PlatformTheme = PlatformTheme;
+    &PLATFORM_THEME
+}
+
+/// The radius of the corners of buttons and checkboxes, in pixels.
+#[cfg(target_os = "macos")]
+const CONTROL_RADIUS: i32 = 4;
+#[cfg(not(target_os = "macos"))]
+const CONTROL_RADIUS: i32 = 2;
+
+/// The side of checkboxes and the diameter of radio buttons, in pixels.
+#[cfg(target_os = "macos")]
+const CHECK_SIZE: i32 = 14;
+#[cfg(not(target_os = "macos"))]
+const CHECK_SIZE: i32 = 13;
+
+/// The thickness of scrollbars, in pixels.
+#[cfg(target_os = "macos")]
+const SCROLLBAR_THICKNESS: i32 = 7;
+#[cfg(not(target_os = "macos"))]
+const SCROLLBAR_THICKNESS: i32 = 10;
+
+/// The color of checked checkboxes and radio buttons.
+#[cfg(target_os = "macos")]
+fn accent_color() -> Color {
+    color::rgb(0, 122, 255)
+}
+#[cfg(not(target_os = "macos"))]
+fn accent_color() -> Color {
+    color::rgb(51, 102, 204)
+}
+
+/// A theme that draws widgets with plain rounded rects, in the colors and sizes of the platform.
+struct PlatformTheme;
+
+impl Theme for PlatformTheme {
+    fn intrinsic_size(&self, widget: Widget) -> Option<Size2D<Au>> {
+        match widget {
+            Widget::Checkbox | Widget::Radio => {
+                Some(Size2D::new(Au::from_px(CHECK_SIZE), Au::from_px(CHECK_SIZE)))
+            }
+            Widget::Button | Widget::HorizontalScrollbar | Widget::VerticalScrollbar => None,
+        }
+    }
+
+    fn scrollbar_thickness(&self) -> Au {
+        Au::from_px(SCROLLBAR_THICKNESS)
+    }
+
+    fn build_display_items(&self,
+                           widget: Widget,
+                           state: WidgetState,
+                           thumb: Option<Rect<Au>>,
+                           base: &BaseDisplayItem,
+                           items: &mut Vec<DisplayItem>) {
+        let bounds = base.bounds;
+        let frame_color = if state.disabled {
+            color::rgb(200, 200, 200)
+        } else {
+            color::rgb(142, 142, 142)
+        };
+        let face_color = if state.active {
+            color::rgb(220, 220, 220)
+        } else if state.disabled {
+            color::rgb(245, 245, 245)
+        } else {
+            color::white()
+        };
+
+        match widget {
+            Widget::Button => {
+                push_rounded_rect(base, &bounds, face_color, frame_color, Au::from_px(1),
+                                  Au::from_px(CONTROL_RADIUS), items);
+            }
+            Widget::Checkbox => {
+                let checked = state.checked || state.indeterminate;
+                let (face_color, frame_color) = if checked && !state.disabled {
+                    (accent_color(), accent_color())
+                } else {
+                    (face_color, frame_color)
+                };
+                push_rounded_rect(base, &bounds, face_color, frame_color, Au::from_px(1),
+                                  Au::from_px(CONTROL_RADIUS), items);
+                if state.indeterminate {
+                    // A dash across the middle.
+                    let mark = Rect::new(
+                        Point2D::new(bounds.origin.x + bounds.size.width.scale_by(0.25),
+                                     bounds.origin.y + bounds.size.height.scale_by(0.4)),
+                        Size2D::new(bounds.size.width.scale_by(0.5),
+                                    bounds.size.height.scale_by(0.2)));
+                    push_solid_rect(base, &mark, color::white(), items);
+                } else if state.checked {
+                    let mark = inset(&bounds, 0.3);
+                    push_rounded_rect(base, &mark, color::white(), color::white(), Au(0),
+                                      Au::from_px(1), items);
+                }
+            }
+            Widget::Radio => {
+                let (face_color, frame_color) = if state.checked && !state.disabled {
+                    (accent_color(), accent_color())
+                } else {
+                    (face_color, frame_color)
+                };
+                let radius = bounds.size.width.scale_by(0.5);
+                push_rounded_rect(base, &bounds, face_color, frame_color, Au::from_px(1), radius,
+                                  items);
+                if state.checked {
+                    let dot = inset(&bounds, 0.3);
+                    push_rounded_rect(base, &dot, color::white(), color::white(), Au(0),
+                                      dot.size.width.scale_by(0.5), items);
+                }
+            }
+            Widget::HorizontalScrollbar | Widget::VerticalScrollbar => {
+                // Scrollbars are laid over the contents, so the track is translucent.
+                push_solid_rect(base, &bounds, color::rgba(0.0, 0.0, 0.0, 0.05), items);
+                if let Some(thumb) = thumb {
+                    let thickness = if widget == Widget::HorizontalScrollbar {
+                        thumb.size.height
+                    } else {
+                        thumb.size.width
+                    };
+                    let thumb_color = color::rgba(0.0, 0.0, 0.0, 0.45);
+                    push_rounded_rect(base, &inset(&thumb, 0.1), thumb_color, thumb_color,
+                                      Au(0), thickness.scale_by(0.4), items);
+                }
+            }
+        }
+    }
+}
+
+/// `rect`, shrunk on each side by `fraction` of its size.
+fn inset(rect: &Rect<Au>, fraction: f32) -> Rect<Au> {
+    let (horizontal, vertical) = (rect.size.width.scale_by(fraction),
+                                  rect.size.height.scale_by(fraction));
+    Rect::new(Point2D::new(rect.origin.x + horizontal, rect.origin.y + vertical),
+              Size2D::new(rect.size.width - horizontal - horizontal,
+                          rect.size.height - vertical - vertical))
+}
+
+fn push_solid_rect(base: &BaseDisplayItem,
+                   rect: &Rect<Au>,
+                   color: Color,
+                   items: &mut Vec<DisplayItem>) {
+    items.push(DisplayItem::SolidColorClass(box SolidColorDisplayItem {
+        base: BaseDisplayItem { bounds: *rect, ..base.clone() },
+        color: color,
+    }));
+}
+
+/// Pushes a rect with rounded corners, filled with `fill_color` and framed by a border of
+/// `frame_color`. The inside is a border too, as wide as half of the rect, so that its corners
+/// are rounded as well.
+fn push_rounded_rect(base: &BaseDisplayItem,
+                     rect: &Rect<Au>,
+                     fill_color: Color,
+                     frame_color: Color,
+                     frame_width: Au,
+                     radius: Au,
+                     items: &mut Vec<DisplayItem>) {
+    let radii = BorderRadii {
+        top_left: Size2D::new(radius, radius),
+        top_right: Size2D::new(radius, radius),
+        bottom_right: Size2D::new(radius, radius),
+        bottom_left: Size2D::new(radius, radius),
+    };
+    let half_width = rect.size.width.scale_by(0.5);
+    let half_height = rect.size.height.scale_by(0.5);
+    items.push(DisplayItem::BorderClass(box BorderDisplayItem {
+        base: BaseDisplayItem { bounds: *rect, ..base.clone() },
+        border_widths: SideOffsets2D::new(half_height, half_width, half_height, half_width),
+        color: SideOffsets2D::new_all_same(fill_color),
+        style: SideOffsets2D::new_all_same(border_style::T::solid),
+        radius: radii,
+    }));
+    if frame_width == Au(0) || frame_color == fill_color {
+        return
+    }
+    items.push(DisplayItem::BorderClass(box BorderDisplayItem {
+        base: BaseDisplayItem { bounds: *rect, ..base.clone() },
+        border_widths: SideOffsets2D::new_all_same(frame_width),
+        color: SideOffsets2D::new_all_same(frame_color),
+        style: SideOffsets2D::new_all_same(border_style::T::solid),
+        radius: radii,
+    }));
+}
@@ -43,6 +43,8 @@ pub enum LayerType {
     BeforePseudoContent,
     /// A layer created to contain ::after pseudo-element content.
     AfterPseudoContent,
+    /// A layer created for a `background-attachment: fixed` background image.
+    FixedBackground,
 }
 
 /// The scrolling policy of a layer.
@@ -73,6 +75,7 @@ impl Debug for LayerId {
             LayerType::OverflowScroll => "-OverflowScroll",
             LayerType::BeforePseudoContent => "-BeforePseudoContent",
             LayerType::AfterPseudoContent => "-AfterPseudoContent",
+            LayerType::FixedBackground => "-FixedBackground",
         };
 
         write!(f, "{}{}-{}", id, type_string, companion)
@@ -141,6 +144,46 @@ impl Epoch {
     }
 }
 
+/// A point in the history of a pipeline that script, layout and the compositor can agree on: the
+/// epoch of the display list that layout last sent, with the version of the DOM that it reflects.
+///
+/// The constellation compares the epoch with the one that the compositor has painted to tell
+/// whether the DOM mutations up to `dom_version` are on screen.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Deserialize, Serialize)]
+pub struct ConsistencyToken {
+    /// The epoch of the display list.
+    pub epoch: Epoch,
+    /// The version of the document, as counted by the DOM mutations of its nodes, that the
+    /// display list was built from.
+    pub dom_version: u64,
+}
+
+/// A transition of the transform of a layer that the compositor runs without involving layout.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct LayerAnimation {
+    pub layer_id: LayerId,
+    /// When the animation starts, as returned by `time::precise_time_s()`.
+    pub start_time: f64,
+    /// The duration of the animation in seconds.
+    pub duration: f64,
+    /// The transform of the layer at evenly spaced points over the duration of the animation,
+    /// from its start to its end.
+    pub keyframes: Vec<Matrix4D<f32>>,
+}
+
+impl LayerAnimation {
+    /// Returns the keyframe closest to the given time.
+    pub fn transform_at(&self, time: f64) -> Matrix4D<f32> {
+        let progress = ((time - self.start_time) / self.duration).max(0.0).min(1.0);
+        let last = self.keyframes.len() - 1;
+        self.keyframes[(progress * last as f64).round() as usize]
+    }
+
+    pub fn is_finished(&self, time: f64) -> bool {
+        time >= self.start_time + self.duration
+    }
+}
+
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
 pub struct FrameTreeId(pub u32);
 
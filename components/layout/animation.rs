@@ -4,47 +4,101 @@
 
 //! CSS transitions and animations.
 
+use app_units::Au;
+use cssparser::ToCss;
+use display_list_builder::transform_matrix;
+use euclid::Size2D;
 use flow::{self, Flow};
+use fragment::HAS_LAYER;
 use gfx::display_list::OpaqueNode;
+use gfx_traits::LayerAnimation;
 use incremental::RestyleDamage;
 use ipc_channel::ipc::IpcSender;
 use msg::constellation_msg::PipelineId;
-use script_traits::{AnimationState, LayoutMsg as ConstellationMsg};
-use std::collections::HashMap;
+use script_traits::{AnimationState, ConstellationControlMsg, LayoutMsg as ConstellationMsg};
+use std::cmp::{max, min};
 use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use std::sync::mpsc::Receiver;
-use style::animation::{Animation, update_style_for_animation};
-use time;
+use style::animation::{Animation, AnimationTarget, KeyframesAnimation};
+use style::animation::update_style_for_animation;
+use style::animation::update_style_for_keyframes_animation;
+use style::computed_values::transition_property::TransitionProperty;
+use style::selector_impl::PseudoElement;
+
+/// How many keyframes are handed off to the compositor for each second of an animation.
+const KEYFRAMES_PER_SECOND: f64 = 60.0;
+
+/// The most keyframes handed off to the compositor for a single animation.
+const MAX_KEYFRAMES: usize = 600;
 
 /// Processes any new animations that were discovered after style recalculation.
-/// Also expire any old animations that have completed, inserting them into `expired_animations`.
+/// Also expire any old animations that have completed, inserting them into `expired_animations`
+/// and telling script to fire `transitionend` for them. The animations that script created are
+/// dropped once they finish, and script is told so that it restyles their nodes without them.
+/// Animations are expired as of `now`, the time that they were last sampled at.
 pub fn update_animation_state(constellation_chan: &IpcSender<ConstellationMsg>,
-                              running_animations: &mut HashMap<OpaqueNode, Vec<Animation>>,
-                              expired_animations: &mut HashMap<OpaqueNode, Vec<Animation>>,
+                              script_chan: &IpcSender<ConstellationControlMsg>,
+                              running_animations: &mut HashMap<AnimationTarget, Vec<Animation>>,
+                              expired_animations: &mut HashMap<AnimationTarget, Vec<Animation>>,
+                              keyframes_animations: &mut HashMap<OpaqueNode,
+                                                                 Vec<KeyframesAnimation>>,
                               new_animations_receiver: &Receiver<Animation>,
-                              pipeline_id: PipelineId) {
+                              handed_off_targets: &HashSet<AnimationTarget>,
+                              caret_present: bool,
+                              pipeline_id: PipelineId,
+                              now: f64) {
     let mut new_running_animations = Vec::new();
     while let Ok(animation) = new_animations_receiver.try_recv() {
         new_running_animations.push(animation)
     }
 
-    if running_animations.is_empty() && new_running_animations.is_empty() {
+    if running_animations.is_empty() && new_running_animations.is_empty() &&
+            keyframes_animations.is_empty() {
         // Nothing to do. Return early so we don't flood the compositor with
         // `ChangeRunningAnimationsState` messages.
         return
     }
 
+    let mut finished_nodes = vec![];
+    for (node, animations) in keyframes_animations.iter_mut() {
+        for animation in animations.iter().filter(|animation| animation.has_finished(now)) {
+            let msg = ConstellationControlMsg::AnimationFinished(pipeline_id, animation.id);
+            script_chan.send(msg).unwrap();
+        }
+        animations.retain(|animation| !animation.has_finished(now));
+        if animations.is_empty() {
+            finished_nodes.push(*node)
+        }
+    }
+    for node in finished_nodes {
+        keyframes_animations.remove(&node);
+    }
+
     // Expire old running animations.
-    let now = time::precise_time_s();
     let mut keys_to_remove = Vec::new();
     for (key, running_animations) in running_animations.iter_mut() {
         let mut animations_still_running = vec![];
         for running_animation in running_animations.drain(..) {
-            if now < running_animation.end_time {
+            // Paused animations never finish.
+            if now < running_animation.end_time || running_animation.paused_at.is_some() {
                 animations_still_running.push(running_animation);
                 continue
             }
-            match expired_animations.entry(*key) {
+            let pseudo_element = match key.pseudo {
+                Some(PseudoElement::Before) => "::before",
+                Some(PseudoElement::After) => "::after",
+                _ => "",
+            };
+            let msg = ConstellationControlMsg::TransitionEnd(
+                pipeline_id,
+                key.node.0,
+                running_animation.property_animation.property().to_css_string(),
+                running_animation.duration(),
+                pseudo_element.to_owned());
+            script_chan.send(msg).unwrap();
+            match expired_animations.entry(key.clone()) {
                 Entry::Vacant(entry) => {
                     entry.insert(vec![running_animation]);
                 }
@@ -52,7 +106,7 @@ pub fn update_animation_state(constellation_chan: &IpcSender<ConstellationMsg>,
             }
         }
         if animations_still_running.len() == 0 {
-            keys_to_remove.push(*key);
+            keys_to_remove.push(key.clone());
         } else {
             *running_animations = animations_still_running
         }
@@ -63,7 +117,7 @@ pub fn update_animation_state(constellation_chan: &IpcSender<ConstellationMsg>,
 
     // Add new running animations.
     for new_running_animation in new_running_animations {
-        match running_animations.entry(new_running_animation.node) {
+        match running_animations.entry(new_running_animation.target.clone()) {
             Entry::Vacant(entry) => {
                 entry.insert(vec![new_running_animation]);
             }
@@ -71,25 +125,109 @@ pub fn update_animation_state(constellation_chan: &IpcSender<ConstellationMsg>,
         }
     }
 
-    let animation_state;
-    if running_animations.is_empty() {
-        animation_state = AnimationState::NoAnimationsPresent;
+    let animation_state = animation_state(running_animations,
+                                          keyframes_animations,
+                                          handed_off_targets,
+                                          caret_present);
+    constellation_chan.send(ConstellationMsg::ChangeRunningAnimationsState(pipeline_id, animation_state))
+                      .unwrap();
+}
+
+/// Returns whether layout needs to be ticked for the running animations, which it does not for
+/// those the compositor runs, or for carets to blink if `caret_present`.
+pub fn animation_state(running_animations: &HashMap<AnimationTarget, Vec<Animation>>,
+                       keyframes_animations: &HashMap<OpaqueNode, Vec<KeyframesAnimation>>,
+                       handed_off_targets: &HashSet<AnimationTarget>,
+                       caret_present: bool)
+                       -> AnimationState {
+    if !caret_present && keyframes_animations.is_empty() &&
+            running_animations.keys().all(|target| handed_off_targets.contains(target)) {
+        AnimationState::NoAnimationsPresent
     } else {
-        animation_state = AnimationState::AnimationsPresent;
+        AnimationState::AnimationsPresent
     }
+}
 
-    constellation_chan.send(ConstellationMsg::ChangeRunningAnimationsState(pipeline_id, animation_state))
-                      .unwrap();
+/// Returns the animations the compositor can run by itself: the transitions of `transform` of
+/// fragments that have layers of their own. The nodes and pseudo-elements of those fragments are
+/// added to `handed_off_targets`.
+pub fn layer_animations(flow: &mut Flow,
+                        animations: &HashMap<AnimationTarget, Vec<Animation>>,
+                        handed_off_targets: &mut HashSet<AnimationTarget>)
+                        -> Vec<LayerAnimation> {
+    let mut result = vec![];
+    flow.mutate_fragments(&mut |fragment| {
+        if !fragment.flags.contains(HAS_LAYER) {
+            return
+        }
+        let target = match fragment.animation_target() {
+            Some(target) => target,
+            None => return,
+        };
+        let animation = match animations.get(&target) {
+            Some(animations) if animations.len() == 1 => &animations[0],
+            _ => return,
+        };
+        if animation.property_animation.property() != TransitionProperty::Transform ||
+                animation.paused_at.is_some() {
+            return
+        }
+
+        let border_box_size = fragment.border_box.size.to_physical(fragment.style.writing_mode);
+        let keyframe_count = (animation.duration() * KEYFRAMES_PER_SECOND) as usize + 1;
+        let keyframe_count = max(2, min(keyframe_count, MAX_KEYFRAMES));
+        let keyframes = (0..keyframe_count).map(|index| {
+            let mut style = fragment.style.clone();
+            let progress = index as f64 / (keyframe_count - 1) as f64;
+            animation.property_animation.update(Arc::make_mut(&mut style), progress);
+            transform_matrix(&*style, &border_box_size)
+        }).collect();
+
+        result.push(LayerAnimation {
+            layer_id: fragment.layer_id(),
+            start_time: animation.start_time,
+            duration: animation.duration(),
+            keyframes: keyframes,
+        });
+        handed_off_targets.insert(target);
+    });
+
+    for kid in flow::mut_base(flow).children.iter_mut() {
+        result.extend(layer_animations(kid, animations, handed_off_targets))
+    }
+    result
 }
 
-/// Recalculates style for a set of animations. This does *not* run with the DOM lock held.
+/// Recalculates style for a set of animations at the time `now`. This does *not* run with the
+/// DOM lock held.
 pub fn recalc_style_for_animations(flow: &mut Flow,
-                                   animations: &HashMap<OpaqueNode, Vec<Animation>>) {
+                                   animations: &HashMap<AnimationTarget, Vec<Animation>>,
+                                   keyframes_animations: &HashMap<OpaqueNode,
+                                                                  Vec<KeyframesAnimation>>,
+                                   viewport_size: Size2D<Au>,
+                                   now: f64) {
     let mut damage = RestyleDamage::empty();
     flow.mutate_fragments(&mut |fragment| {
-        if let Some(ref animations) = animations.get(&fragment.node) {
+        let target = match fragment.animation_target() {
+            Some(target) => target,
+            None => return,
+        };
+        if let Some(ref animations) = animations.get(&target) {
+            for animation in *animations {
+                update_style_for_animation(animation, &mut fragment.style, now, Some(&mut damage));
+            }
+        }
+        // The animations that script created apply on top of the transitions, to elements only.
+        if target.pseudo.is_some() {
+            return
+        }
+        if let Some(ref animations) = keyframes_animations.get(&fragment.node) {
             for animation in *animations {
-                update_style_for_animation(animation, &mut fragment.style, Some(&mut damage));
+                update_style_for_keyframes_animation(animation,
+                                                     &mut fragment.style,
+                                                     viewport_size,
+                                                     now,
+                                                     Some(&mut damage));
             }
         }
     });
@@ -97,6 +235,6 @@ pub fn recalc_style_for_animations(flow: &mut Flow,
     let base = flow::mut_base(flow);
     base.restyle_damage.insert(damage);
     for kid in base.children.iter_mut() {
-        recalc_style_for_animations(kid, animations)
+        recalc_style_for_animations(kid, animations, keyframes_animations, viewport_size, now)
     }
 }
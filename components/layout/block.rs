@@ -32,7 +32,7 @@ use context::LayoutContext;
 use display_list_builder::BlockFlowDisplayListBuilding;
 use display_list_builder::{BorderPaintingMode, DisplayListBuildState, FragmentDisplayListBuilding};
 use euclid::{Point2D, Rect, Size2D};
-use floats::{ClearType, FloatKind, Floats, PlacementInfo};
+use floats::{ClearType, FloatKind, FloatShape, Floats, PlacementInfo};
 use flow::IS_ABSOLUTELY_POSITIONED;
 use flow::{BLOCK_POSITION_IS_STATIC, CLEARS_LEFT, CLEARS_RIGHT};
 use flow::{CONTAINS_TEXT_OR_REPLACED_FRAGMENTS, INLINE_POSITION_IS_STATIC};
@@ -528,6 +528,9 @@ bitflags! {
     flags BlockFlowFlags: u8 {
         #[doc = "If this is set, then this block flow is the root flow."]
         const IS_ROOT = 0x01,
+        #[doc = "If this is set, then this block flow is a flex item whose block size is"]
+        #[doc = "stretched to the cross size of its flex container."]
+        const IS_STRETCHED_FLEX_ITEM = 0x02,
     }
 }
 
@@ -958,6 +961,11 @@ impl BlockFlow {
                 block_size = block_size + floats.clearance(ClearType::Both);
             }
 
+            if self.fragment.style().get_box().contain.size {
+                // Under size containment, the block is laid out as though it had no contents.
+                block_size = Au(0)
+            }
+
             if self.base.flags.contains(IS_ABSOLUTELY_POSITIONED) {
                 // FIXME(#2003, pcwalton): The max is taken here so that you can scroll the page,
                 // but this is not correct behavior according to CSS 2.1 § 10.5. Instead I think we
@@ -1108,7 +1116,10 @@ impl BlockFlow {
 
         // Place the float and return the `Floats` back to the parent flow.
         // After, grab the position and use that to set our position.
-        self.base.floats.add_float(&info);
+        let shape = FloatShape::from_fragment(&self.fragment,
+                                              info.size,
+                                              self.base.floats.writing_mode);
+        self.base.floats.add_float(&info, shape);
 
         // FIXME (mbrubeck) Get the correct container size for self.base.floats;
         let container_size = Size2D::new(self.base.block_container_inline_size, Au(0));
@@ -1138,14 +1149,59 @@ impl BlockFlow {
             let viewport_size = LogicalSize::from_physical(self.fragment.style.writing_mode,
                                                            layout_context.shared_context().viewport_size);
             Some(viewport_size.block)
-        } else if self.base.flags.contains(IS_ABSOLUTELY_POSITIONED) &&
-                  self.base.block_container_explicit_block_size.is_none() {
+        } else if self.base.flags.contains(IS_ABSOLUTELY_POSITIONED) {
+            // Percentages refer to the padding box of the containing block, not to the block
+            // that the flow would be in if it were not positioned (CSS 2.1 § 10.1).
             self.base.absolute_cb.explicit_block_containing_size(layout_context)
         } else {
             self.base.block_container_explicit_block_size
         }
     }
 
+    /// The block size of the content box of this flow, if it is definite: that is, if it can be
+    /// known before the contents are laid out, so that percentages of it can be resolved.
+    pub fn explicit_content_block_size(&self, layout_context: &LayoutContext) -> Option<Au> {
+        let box_border = match self.fragment.style().get_position().box_sizing {
+            box_sizing::T::border_box => self.fragment.border_padding.block_start_end(),
+            box_sizing::T::content_box => Au(0),
+        };
+        let parent_container_size = self.explicit_block_containing_size(layout_context);
+        // https://drafts.csswg.org/css-ui-3/#box-sizing
+        self.explicit_block_size(parent_container_size)
+            .map(|x| if x < box_border { Au(0) } else { x - box_border })
+    }
+
+    /// The block size of the padding box of this flow, if it is definite. This is what the
+    /// percentages of the absolutely-positioned flows that it contains refer to.
+    pub fn explicit_padding_box_block_size(&self, layout_context: &LayoutContext) -> Option<Au> {
+        if self.is_root() {
+            // The root is laid out as the initial containing block.
+            return self.explicit_block_containing_size(layout_context)
+        }
+        let padding = self.fragment.border_padding.block_start_end() -
+            self.fragment.border_width().block_start_end();
+        self.explicit_content_block_size(layout_context).map(|size| size + padding)
+    }
+
+    /// Marks this flow as a flex item whose block size is stretched to the cross size of its
+    /// flex container, or clears the mark.
+    pub fn set_stretched_flex_item(&mut self, stretched: bool) {
+        if stretched {
+            self.flags.insert(IS_STRETCHED_FLEX_ITEM)
+        } else {
+            self.flags.remove(IS_STRETCHED_FLEX_ITEM)
+        }
+    }
+
+    /// Whether an `auto` block size of this flow is the block size of its container, which
+    /// makes it as definite as that of the container.
+    fn block_size_is_stretched(&self) -> bool {
+        match self.fragment.specific {
+            SpecificFragmentInfo::TableCell => true,
+            _ => self.flags.contains(IS_STRETCHED_FLEX_ITEM),
+        }
+    }
+
     fn explicit_block_size(&self, containing_block_size: Option<Au>) -> Option<Au> {
         let content_block_size = self.fragment.style().content_block_size();
 
@@ -1162,6 +1218,18 @@ impl BlockFlow {
             (LengthOrPercentageOrAuto::Auto, None) => {
                 None
             }
+            (LengthOrPercentageOrAuto::Auto, Some(container_size))
+                    if self.block_size_is_stretched() => {
+                // Table cells are as tall as their rows, and stretched flex items are as tall
+                // as the lines of their flex containers (css-flexbox § 9.8).
+                match self.fragment.style().get_position().box_sizing {
+                    box_sizing::T::border_box => Some(container_size),
+                    box_sizing::T::content_box => {
+                        let border_padding = self.fragment.border_padding.block_start_end();
+                        Some(max(container_size - border_padding, Au(0)))
+                    }
+                }
+            }
             (LengthOrPercentageOrAuto::Auto, Some(container_size)) => {
                 let (block_start, block_end) = {
                     let position = self.fragment.style().logical_position();
@@ -1324,15 +1392,7 @@ impl BlockFlow {
         let opaque_self = OpaqueFlow::from_flow(self);
 
         // Calculate non-auto block size to pass to children.
-        let box_border = match self.fragment.style().get_position().box_sizing {
-            box_sizing::T::border_box => self.fragment.border_padding.block_start_end(),
-            box_sizing::T::content_box => Au(0),
-        };
-        let parent_container_size = self.explicit_block_containing_size(layout_context);
-        // https://drafts.csswg.org/css-ui-3/#box-sizing
-        let explicit_content_size = self
-                                    .explicit_block_size(parent_container_size)
-                                    .map(|x| if x < box_border { Au(0) } else { x - box_border });
+        let explicit_content_size = self.explicit_content_block_size(layout_context);
 
         // Calculate containing block inline size.
         let containing_block_size = if flags.contains(IS_ABSOLUTELY_POSITIONED) {
@@ -1406,11 +1466,13 @@ impl BlockFlow {
             display::T::table_caption |
             display::T::table_row_group |
             display::T::table |
-            display::T::inline_block => {
+            display::T::inline_block |
+            display::T::ruby => {
                 FormattingContextType::Other
             }
             _ if style.get_box().overflow_x != overflow_x::T::visible ||
                     style.get_box().overflow_y != overflow_y::T(overflow_x::T::visible) ||
+                    style.get_box().contain.layout ||
                     style.is_multicol() => {
                 FormattingContextType::Block
             }
@@ -1457,8 +1519,12 @@ impl BlockFlow {
         self.fragment.restyle_damage.remove(REFLOW_OUT_OF_FLOW | REFLOW);
     }
 
+    /// Ruby containers are sized as inline-blocks are.
     fn is_inline_block(&self) -> bool {
-        self.fragment.style().get_box().display == display::T::inline_block
+        match self.fragment.style().get_box().display {
+            display::T::inline_block | display::T::ruby => true,
+            _ => false,
+        }
     }
 
     /// Computes the content portion (only) of the intrinsic inline sizes of this flow. This is
@@ -1580,6 +1646,13 @@ impl BlockFlow {
             return
         }
 
+        // Elements about to be animated get a layer ahead of time, so that animating them does
+        // not repaint their surroundings.
+        if self.fragment.style().will_change_requires_layer() {
+            self.base.flags.insert(NEEDS_LAYER);
+            return
+        }
+
         match (self.fragment.style().get_box().overflow_x,
                self.fragment.style().get_box().overflow_y.0) {
             (overflow_x::T::auto, _) | (overflow_x::T::scroll, _) |
@@ -1705,7 +1778,7 @@ impl Flow for BlockFlow {
         // rather than bubbling up children inline width.
         let consult_children = match self.fragment.style().get_position().width {
             LengthOrPercentageOrAuto::Length(_) => false,
-            _ => true,
+            _ => !self.fragment.style().get_box().contain.size,
         };
         self.bubble_inline_sizes_for_block(consult_children);
         self.fragment.restyle_damage.remove(BUBBLE_ISIZES);
@@ -1821,8 +1894,11 @@ impl Flow for BlockFlow {
     }
 
     fn compute_absolute_position(&mut self, layout_context: &LayoutContext) {
-        if self.base.flags.contains(NEEDS_LAYER) {
+        if self.base.flags.contains(NEEDS_LAYER) ||
+                self.fragment.has_running_layer_animation(layout_context) {
             self.fragment.flags.insert(HAS_LAYER)
+        } else {
+            self.fragment.flags.remove(HAS_LAYER)
         }
 
         // FIXME (mbrubeck): Get the real container size, taking the container writing mode into
@@ -1981,9 +2057,28 @@ impl Flow for BlockFlow {
                                                   .early_absolute_position_info
                                                   .relative_containing_block_mode,
                                               CoordinateSystem::Own);
+        // Blocks that scroll establish stacking contexts, in which their border box is at the
+        // origin, while their overflow is relative to the flow.
+        let scroll_overflow = if self.has_scrolling_overflow() {
+            let border_box_offset =
+                self.fragment
+                    .stacking_relative_border_box(&self.base.stacking_relative_position,
+                                                  &self.base
+                                                       .early_absolute_position_info
+                                                       .relative_containing_block_size,
+                                                  self.base
+                                                      .early_absolute_position_info
+                                                      .relative_containing_block_mode,
+                                                  CoordinateSystem::Parent)
+                    .translate(&-self.base.stacking_relative_position).origin;
+            Some(self.base.overflow.scroll.translate(&-border_box_offset))
+        } else {
+            None
+        };
         self.fragment.adjust_clipping_region_for_children(
             &mut clip,
-            &stacking_relative_border_box);
+            &stacking_relative_border_box,
+            scroll_overflow.as_ref());
 
         // Process children.
         for kid in self.base.child_iter_mut() {
@@ -2105,6 +2200,10 @@ impl Flow for BlockFlow {
         self.contains_positioned_fragments()
     }
 
+    fn is_containing_block_for_fixed_descendants(&self) -> bool {
+        self.fragment.style().is_containing_block_for_fixed_descendants()
+    }
+
     fn update_late_computed_inline_position_if_necessary(&mut self, inline_position: Au) {
         if self.base.flags.contains(IS_ABSOLUTELY_POSITIONED) &&
                 self.fragment.style().logical_position().inline_start ==
@@ -2843,7 +2942,9 @@ impl ISizeAndMarginsComputer for AbsoluteReplaced {
         let containing_block_inline_size =
             block.containing_block_size(&layout_context.shared_context().viewport_size, opaque_block).inline;
         let fragment = block.fragment();
-        fragment.assign_replaced_inline_size_if_necessary(containing_block_inline_size);
+        // The block size of the containing block is not known until the block sizes are
+        // assigned, so percentage block sizes keep the intrinsic ratio only from then on.
+        fragment.assign_replaced_inline_size_if_necessary(containing_block_inline_size, None);
         // For replaced absolute flow, the rest of the constraint solving will
         // take inline-size to be specified as the value computed here.
         MaybeAuto::Specified(fragment.content_inline_size())
@@ -2900,8 +3001,10 @@ impl ISizeAndMarginsComputer for BlockReplaced {
                                     parent_flow_inline_size: Au,
                                     _: &LayoutContext)
                                     -> MaybeAuto {
+        let container_block_size = block.base.block_container_explicit_block_size;
         let fragment = block.fragment();
-        fragment.assign_replaced_inline_size_if_necessary(parent_flow_inline_size);
+        fragment.assign_replaced_inline_size_if_necessary(parent_flow_inline_size,
+                                                          container_block_size);
         // For replaced block flow, the rest of the constraint solving will
         // take inline-size to be specified as the value computed here.
         MaybeAuto::Specified(fragment.content_inline_size())
@@ -2957,8 +3060,10 @@ impl ISizeAndMarginsComputer for FloatReplaced {
                                     parent_flow_inline_size: Au,
                                     _: &LayoutContext)
                                     -> MaybeAuto {
+        let container_block_size = block.base.block_container_explicit_block_size;
         let fragment = block.fragment();
-        fragment.assign_replaced_inline_size_if_necessary(parent_flow_inline_size);
+        fragment.assign_replaced_inline_size_if_necessary(parent_flow_inline_size,
+                                                          container_block_size);
         // For replaced block flow, the rest of the constraint solving will
         // take inline-size to be specified as the value computed here.
         MaybeAuto::Specified(fragment.content_inline_size())
@@ -3044,8 +3149,10 @@ impl ISizeAndMarginsComputer for InlineBlockReplaced {
                                     parent_flow_inline_size: Au,
                                     _: &LayoutContext)
                                     -> MaybeAuto {
+        let container_block_size = block.base.block_container_explicit_block_size;
         let fragment = block.fragment();
-        fragment.assign_replaced_inline_size_if_necessary(parent_flow_inline_size);
+        fragment.assign_replaced_inline_size_if_necessary(parent_flow_inline_size,
+                                                          container_block_size);
         // For replaced block flow, the rest of the constraint solving will
         // take inline-size to be specified as the value computed here.
         MaybeAuto::Specified(fragment.content_inline_size())
@@ -20,7 +20,8 @@ use data::{HAS_NEWLY_CONSTRUCTED_FLOW, PrivateLayoutData};
 use flex::FlexFlow;
 use floats::FloatKind;
 use flow::{MutableFlowUtils, MutableOwnedFlowUtils, CAN_BE_FRAGMENTED};
-use flow::{self, AbsoluteDescendants, IS_ABSOLUTELY_POSITIONED, ImmutableFlowUtils};
+use flow::{self, AbsoluteDescendants, Flow, FlowClass, IS_ABSOLUTELY_POSITIONED};
+use flow::ImmutableFlowUtils;
 use flow_ref::{self, FlowRef};
 use fragment::WhitespaceStrippingResult;
 use fragment::{CanvasFragmentInfo, ImageFragmentInfo, InlineAbsoluteFragmentInfo};
@@ -30,10 +31,11 @@ use fragment::{InlineBlockFragmentInfo, SpecificFragmentInfo, UnscannedTextFragm
 use gfx::display_list::OpaqueNode;
 use incremental::{BUBBLE_ISIZES, RECONSTRUCT_FLOW, RestyleDamage};
 use inline::{FIRST_FRAGMENT_OF_ELEMENT, InlineFlow, InlineFragmentNodeFlags};
-use inline::{InlineFragmentNodeInfo, LAST_FRAGMENT_OF_ELEMENT};
+use inline::{InlineFragmentNodeInfo, LAST_FRAGMENT_OF_ELEMENT, ParentFontMetrics};
 use list_item::{ListItemFlow, ListStyleTypeContent};
 use multicol::{MulticolFlow, MulticolColumnFlow};
 use parallel;
+use ruby::RubyFlow;
 use script::dom::bindings::inheritance::{CharacterDataTypeId, ElementTypeId};
 use script::dom::bindings::inheritance::{HTMLElementTypeId, NodeTypeId};
 use script::dom::htmlobjectelement::is_image_data;
@@ -300,6 +302,32 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
         node.set_flow_construction_result(result);
     }
 
+    /// Hands the lines of the inline flow of the flow that `node` had before it was
+    /// reconstructed over to the inline flow of `flow`, its replacement, so that only the lines
+    /// around what changed need to be broken again.
+    fn hand_over_previous_lines(&self,
+                                flow: &mut FlowRef,
+                                node: &ConcreteThreadSafeLayoutNode) {
+        let previous_lines = {
+            let mut layout_data = match node.mutate_layout_data() {
+                Some(layout_data) => layout_data,
+                None => return,
+            };
+            match *node.construction_result_mut(&mut *layout_data) {
+                ConstructionResult::Flow(ref mut old_flow, _) => {
+                    match sole_inline_child(flow_ref::deref_mut(old_flow)) {
+                        Some(old_inline_flow) => old_inline_flow.take_previous_lines(),
+                        None => None,
+                    }
+                }
+                _ => None,
+            }
+        };
+        if let Some(new_inline_flow) = sole_inline_child(flow_ref::deref_mut(flow)) {
+            new_inline_flow.previous_lines = previous_lines
+        }
+    }
+
     /// Builds the fragment for the given block or subclass thereof.
     fn build_fragment_for_block(&mut self, node: &ConcreteThreadSafeLayoutNode) -> Fragment {
         let specific_fragment_info = match node.type_id() {
@@ -489,6 +517,9 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
                                                                &**node.style(self.style_context()));
             inline_flow.minimum_block_size_above_baseline = ascent;
             inline_flow.minimum_depth_below_baseline = descent;
+            inline_flow.block_font_metrics =
+                ParentFontMetrics::for_style(&mut self.layout_context.font_context(),
+                                             &**node.style(self.style_context()));
         }
 
         inline_flow_ref.finish();
@@ -656,15 +687,14 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
 
         // The flow is done.
         flow.finish();
+        self.hand_over_previous_lines(&mut flow, node);
 
         // Set up the absolute descendants.
         let contains_positioned_fragments = flow.contains_positioned_fragments();
         let is_absolutely_positioned = flow::base(&*flow).flags.contains(IS_ABSOLUTELY_POSITIONED);
         if contains_positioned_fragments {
-            // This is the containing block for all the absolute descendants.
-            flow.set_absolute_descendants(abs_descendants);
-
-            abs_descendants = AbsoluteDescendants::new();
+            // This is the containing block for the absolute descendants.
+            abs_descendants = flow.contain_absolute_descendants(abs_descendants);
             if is_absolutely_positioned {
                 // This is now the only absolute flow in the subtree which hasn't yet
                 // reached its CB.
@@ -751,6 +781,17 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
                             let info = box UnscannedTextFragmentInfo::new(string, None);
                             SpecificFragmentInfo::UnscannedText(info)
                         }
+                        ContentItem::Url(url) => {
+                            let info = box ImageFragmentInfo::new(node, Some(url),
+                                                                  &self.layout_context);
+                            if info.metadata.is_none() {
+                                self.layout_context
+                                    .shared
+                                    .generated_content_images_pending
+                                    .store(true, Ordering::SeqCst);
+                            }
+                            SpecificFragmentInfo::Image(info)
+                        }
                         content_item => {
                             let content_item = box GeneratedContentInfo::ContentItem(content_item);
                             SpecificFragmentInfo::GeneratedContent(content_item)
@@ -773,7 +814,10 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
     /// to happen.
     fn build_flow_for_block(&mut self, node: &ConcreteThreadSafeLayoutNode, float_kind: Option<FloatKind>)
                             -> ConstructionResult {
-        if node.style(self.style_context()).is_multicol() {
+        // Paginated documents are fragmented into pages the way multicol elements are into
+        // columns.
+        if node.style(self.style_context()).is_multicol() ||
+                (opts::get().paginate && node.is_root_element()) {
             return self.build_flow_for_multicol(node, float_kind)
         }
 
@@ -991,6 +1035,24 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
     fn build_fragment_for_inline_block(&mut self, node: &ConcreteThreadSafeLayoutNode)
                                        -> ConstructionResult {
         let block_flow_result = self.build_flow_for_block(node, None);
+        self.build_inline_block_fragment_for_flow(node, block_flow_result)
+    }
+
+    /// Builds a `RubyFlow` for a node with `display: ruby`. The ruby container is an atomic
+    /// inline, so the flow is wrapped in an inline-block fragment.
+    fn build_fragment_for_ruby(&mut self, node: &ConcreteThreadSafeLayoutNode)
+                               -> ConstructionResult {
+        let fragment = self.build_fragment_for_block(node);
+        let flow: FlowRef = Arc::new(RubyFlow::from_fragment(fragment));
+        let ruby_flow_result = self.build_flow_for_block_like(flow, node);
+        self.build_inline_block_fragment_for_flow(node, ruby_flow_result)
+    }
+
+    /// Wraps the flow built for an atomic inline node in an inline-block fragment.
+    fn build_inline_block_fragment_for_flow(&mut self,
+                                            node: &ConcreteThreadSafeLayoutNode,
+                                            block_flow_result: ConstructionResult)
+                                            -> ConstructionResult {
         let (block_flow, abs_descendants) = match block_flow_result {
             ConstructionResult::Flow(block_flow, abs_descendants) => (block_flow, abs_descendants),
             _ => unreachable!()
@@ -1122,13 +1184,15 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
         flow.add_new_child(anonymous_flow);
     }
 
-    /// Builds a flow for a node with `column-count` or `column-width` non-`auto`.
+    /// Builds a flow for a node with `column-count` or `column-width` non-`auto`, or for the
+    /// root element of a paginated document.
     /// This yields a `MulticolFlow` with a single `MulticolColumnFlow` underneath it.
     fn build_flow_for_multicol(&mut self, node: &ConcreteThreadSafeLayoutNode,
                                     float_kind: Option<FloatKind>)
                                     -> ConstructionResult {
         let fragment = Fragment::new(node, SpecificFragmentInfo::Multicol, self.layout_context);
-        let mut flow: FlowRef = Arc::new(MulticolFlow::from_fragment(fragment, float_kind));
+        let paged = opts::get().paginate && node.is_root_element();
+        let mut flow: FlowRef = Arc::new(MulticolFlow::from_fragment(fragment, float_kind, paged));
 
         let column_fragment = Fragment::new(node, SpecificFragmentInfo::MulticolColumn, self.layout_context);
         let column_flow = Arc::new(MulticolColumnFlow::from_fragment(column_fragment));
@@ -1147,10 +1211,8 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
         flow.finish();
         let contains_positioned_fragments = flow.contains_positioned_fragments();
         if contains_positioned_fragments {
-            // This is the containing block for all the absolute descendants.
-            flow.set_absolute_descendants(abs_descendants);
-
-            abs_descendants = AbsoluteDescendants::new();
+            // This is the containing block for the absolute descendants.
+            abs_descendants = flow.contain_absolute_descendants(abs_descendants);
 
             let is_absolutely_positioned =
                 flow::base(&*flow).flags.contains(IS_ABSOLUTELY_POSITIONED);
@@ -1201,10 +1263,8 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
         wrapper_flow.finish();
         let contains_positioned_fragments = wrapper_flow.contains_positioned_fragments();
         if contains_positioned_fragments {
-            // This is the containing block for all the absolute descendants.
-            wrapper_flow.set_absolute_descendants(abs_descendants);
-
-            abs_descendants = AbsoluteDescendants::new();
+            // This is the containing block for the absolute descendants.
+            abs_descendants = wrapper_flow.contain_absolute_descendants(abs_descendants);
 
             let is_absolutely_positioned =
                 flow::base(&*wrapper_flow).flags.contains(IS_ABSOLUTELY_POSITIONED);
@@ -1269,23 +1329,27 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
     fn build_flow_for_list_item(&mut self, node: &ConcreteThreadSafeLayoutNode, flotation: float::T)
                                 -> ConstructionResult {
         let flotation = FloatKind::from_property(flotation);
-        let marker_fragments = match node.style(self.style_context()).get_list().list_style_image.0 {
+        // The marker is a box of its own, which `::marker` rules style.
+        let marker = node.get_marker_pseudo().unwrap_or(*node);
+        let marker_style = marker.style(self.style_context()).clone();
+        let marker_fragments = match marker_style.get_list().list_style_image.0 {
             Some(ref url) => {
-                let image_info = box ImageFragmentInfo::new(node,
+                let image_info = box ImageFragmentInfo::new(&marker,
                                                             Some((*url).clone()),
                                                             &self.layout_context);
-                vec![Fragment::new(node, SpecificFragmentInfo::Image(image_info), self.layout_context)]
+                vec![Fragment::new(&marker,
+                                   SpecificFragmentInfo::Image(image_info),
+                                   self.layout_context)]
             }
             None => {
-                match ListStyleTypeContent::from_list_style_type(node.style(self.style_context())
-                                                                     .get_list()
-                                                                     .list_style_type) {
+                match ListStyleTypeContent::from_list_style_type(&marker_style.get_list()
+                                                                              .list_style_type) {
                     ListStyleTypeContent::None => Vec::new(),
                     ListStyleTypeContent::StaticText(ch) => {
                         let text = format!("{}\u{a0}", ch);
                         let mut unscanned_marker_fragments = LinkedList::new();
                         unscanned_marker_fragments.push_back(Fragment::new(
-                            node,
+                            &marker,
                             SpecificFragmentInfo::UnscannedText(
                                 box UnscannedTextFragmentInfo::new(text, None)),
                             self.layout_context));
@@ -1295,7 +1359,9 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
                         marker_fragments.fragments
                     }
                     ListStyleTypeContent::GeneratedContent(info) => {
-                        vec![Fragment::new(node, SpecificFragmentInfo::GeneratedContent(info), self.layout_context)]
+                        vec![Fragment::new(&marker,
+                                           SpecificFragmentInfo::GeneratedContent(info),
+                                           self.layout_context)]
                     }
                 }
             }
@@ -1520,6 +1586,7 @@ impl<'a, ConcreteThreadSafeLayoutNode> PostorderNodeMutTraversal<ConcreteThreadS
                     PseudoElementType::Before(maybe_display) |
                     PseudoElementType::After(maybe_display) |
                     PseudoElementType::DetailsContent(maybe_display) |
+                    PseudoElementType::Marker(maybe_display) |
                     PseudoElementType::DetailsSummary(maybe_display)
                         => maybe_display.unwrap_or(style.get_box().display),
                 };
@@ -1601,6 +1668,31 @@ impl<'a, ConcreteThreadSafeLayoutNode> PostorderNodeMutTraversal<ConcreteThreadS
                 self.set_flow_construction_result(node, construction_result)
             }
 
+            // Ruby containers contribute inline fragment construction results, like
+            // inline-blocks. Floated and absolutely-positioned ones are blocks.
+            (display::T::ruby, _, _) => {
+                let construction_result = self.build_fragment_for_ruby(node);
+                self.set_flow_construction_result(node, construction_result)
+            }
+
+            // Ruby annotations are laid out as blocks, which splits the inline content of their
+            // ruby container into the bases between them.
+            (display::T::ruby_text, _, _) => {
+                let construction_result = self.build_flow_for_block(node, None);
+                self.set_flow_construction_result(node, construction_result)
+            }
+
+            // The other ruby boxes are inline content of their ruby container.
+            //
+            // TODO: Pair up the bases and annotations of ruby-base-container and
+            // ruby-text-container boxes rather than treating annotations as following each base.
+            (display::T::ruby_base, _, _) |
+            (display::T::ruby_base_container, _, _) |
+            (display::T::ruby_text_container, _, _) => {
+                let construction_result = self.build_fragments_for_inline(node);
+                self.set_flow_construction_result(node, construction_result)
+            }
+
             // Table items contribute table flow construction results.
             (display::T::table_caption, _, _) => {
                 let construction_result = self.build_flow_for_table_caption(node);
@@ -1702,6 +1794,7 @@ impl<ConcreteThreadSafeLayoutNode> NodeUtils for ConcreteThreadSafeLayoutNode
             PseudoElementType::After (_) => &mut data.after_flow_construction_result,
             PseudoElementType::DetailsSummary(_) => &mut data.details_summary_flow_construction_result,
             PseudoElementType::DetailsContent(_) => &mut data.details_content_flow_construction_result,
+            PseudoElementType::Marker(_) => unreachable!("markers are built with their list items"),
             PseudoElementType::Normal    => &mut data.flow_construction_result,
         }
     }
@@ -1912,3 +2005,17 @@ impl ComputedValueUtils for ServoComputedValues {
            border.border_left_width != Au(0)
     }
 }
+
+/// Returns the inline flow of `flow`, if that is its only child.
+fn sole_inline_child(flow: &mut Flow) -> Option<&mut InlineFlow> {
+    let children = &mut flow::mut_base(flow).children;
+    if children.len() != 1 {
+        return None
+    }
+    let kid = children.iter_mut().next().unwrap();
+    if kid.class() == FlowClass::Inline {
+        Some(kid.as_mut_inline())
+    } else {
+        None
+    }
+}
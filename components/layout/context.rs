@@ -10,7 +10,7 @@
 use app_units::Au;
 use euclid::Rect;
 use fnv::FnvHasher;
-use gfx::display_list::WebRenderImageInfo;
+use gfx::display_list::{OpaqueNode, WebRenderImageInfo};
 use gfx::font_cache_thread::FontCacheThread;
 use gfx::font_context::FontContext;
 use gfx_traits::LayerId;
@@ -19,10 +19,12 @@ use ipc_channel::ipc::{self, IpcSharedMemory};
 use net_traits::image::base::Image;
 use net_traits::image_cache_thread::{ImageCacheChan, ImageCacheThread, ImageResponse, ImageState};
 use net_traits::image_cache_thread::{ImageOrMetadataAvailable, UsePlaceholder};
+use script::layout_interface::InvalidationOverlays;
 use std::cell::{RefCell, RefMut};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::hash::BuildHasherDefault;
 use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex, RwLock};
 use style::context::{LocalStyleContext, StyleContext};
 use style::matching::{ApplicableDeclarationsCache, StyleSharingCandidateCache};
@@ -98,6 +100,25 @@ pub struct SharedLayoutContext {
     pub webrender_image_cache: Arc<RwLock<HashMap<(Url, UsePlaceholder),
                                                   WebRenderImageInfo,
                                                   BuildHasherDefault<FnvHasher>>>>,
+
+    /// The debugging overlays to draw while building display lists.
+    pub invalidation_overlays: InvalidationOverlays,
+
+    /// The node whose box model devtools is highlighting, if any.
+    pub highlighted_node: Option<OpaqueNode>,
+
+    /// The lowercased names of the families whose web font is in its block period.
+    pub blocked_font_families: HashSet<String>,
+
+    /// Whether carets are shown in the current phase of their blinking.
+    pub caret_visible: bool,
+
+    /// The number of device pixels per `px`, which `image-set()` picks its images for.
+    pub device_pixel_ratio: f32,
+
+    /// Set when generated content is waiting for an image, so that its boxes are built again
+    /// once the image arrives.
+    pub generated_content_images_pending: Arc<AtomicBool>,
 }
 
 pub struct LayoutContext<'a> {
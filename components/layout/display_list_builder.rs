@@ -17,38 +17,49 @@ use canvas_traits::{CanvasMsg, CanvasData, FromLayoutMsg};
 use context::LayoutContext;
 use euclid::{Matrix4D, Point2D, Point3D, Rect, SideOffsets2D, Size2D};
 use flex::FlexFlow;
-use flow::{BaseFlow, Flow, IS_ABSOLUTELY_POSITIONED};
+use flow::{BaseFlow, Flow, IS_ABSOLUTELY_POSITIONED, WAS_REFLOWED};
 use flow_ref;
 use fragment::SpecificFragmentInfo;
 use fragment::{CoordinateSystem, Fragment, HAS_LAYER, ImageFragmentInfo, ScannedTextFragmentInfo};
 use gfx::display_list::GradientDisplayItem;
+use gfx::display_list::StackingContextType;
 use gfx::display_list::{BLUR_INFLATION_FACTOR, BaseDisplayItem, BorderDisplayItem};
-use gfx::display_list::{BorderRadii, BoxShadowClipMode, BoxShadowDisplayItem, ClippingRegion};
-use gfx::display_list::{DisplayItem, DisplayItemMetadata, DisplayListSection};
+use gfx::display_list::{BorderRadii, BoxShadowClipMode, BoxShadowDisplayItem, CaretDisplayItem};
+use gfx::display_list::{ClippingRegion, DisplayItem, DisplayItemMetadata, DisplayListSection};
 use gfx::display_list::{GradientStop, IframeDisplayItem, ImageDisplayItem, WebGLDisplayItem, LayeredItem, LayerInfo};
-use gfx::display_list::{LineDisplayItem, OpaqueNode, SolidColorDisplayItem};
-use gfx::display_list::{StackingContext, StackingContextId, StackingContextType};
-use gfx::display_list::{TextDisplayItem, TextOrientation, WebRenderImageInfo};
+use gfx::display_list::{LineDisplayItem, Mask, OpaqueNode, SolidColorDisplayItem};
+use gfx::display_list::{ScrollSnapArea, ScrollSnapContainer, StackingContext, StackingContextId};
+use gfx::display_list::{TextDisplayItem, TextOrientation, TouchActionRegion, WebRenderImageInfo};
 use gfx::paint_thread::THREAD_TINT_COLORS;
 use gfx::text::glyph::ByteIndex;
-use gfx_traits::{color, ScrollPolicy};
+use gfx::theme::{self, Widget, WidgetState};
+use gfx_traits::{color, LayerId, LayerType, ScrollPolicy};
+use incremental::REPAINT;
 use inline::{FIRST_FRAGMENT_OF_ELEMENT, InlineFlow, LAST_FRAGMENT_OF_ELEMENT};
 use ipc_channel::ipc;
 use list_item::ListItemFlow;
 use model::{self, MaybeAuto, ToGfxMatrix};
 use net_traits::image::base::PixelFormat;
-use net_traits::image_cache_thread::UsePlaceholder;
+use net_traits::image_cache_thread::{ImageOrMetadataAvailable, UsePlaceholder};
 use range::Range;
+use msg::constellation_msg::CustomCursor;
+use std::collections::HashMap;
 use std::default::Default;
 use std::sync::Arc;
-use std::{cmp, f32};
+use std::{cmp, f32, mem};
 use style::computed_values::filter::Filter;
 use style::computed_values::{_servo_overflow_clip_box as overflow_clip_box};
 use style::computed_values::{background_attachment, background_clip, background_origin};
-use style::computed_values::{background_repeat, background_size, border_style};
-use style::computed_values::{cursor, image_rendering, overflow_x, pointer_events, position};
-use style::computed_values::{transform, transform_style, visibility};
-use style::logical_geometry::{LogicalPoint, LogicalRect, LogicalSize, WritingMode};
+use style::computed_values::{background_repeat, background_size, border_image_outset};
+use style::computed_values::{border_image_repeat, border_image_slice, border_image_width};
+use style::computed_values::border_style;
+use style::computed_values::{cursor, image_rendering, mask_mode, object_fit, overflow_x};
+use style::computed_values::pointer_events;
+use style::computed_values::{position, scroll_snap_align, scroll_snap_type_x, scroll_snap_type_y};
+use style::computed_values::{text_decoration_style, touch_action, transform, transform_style};
+use style::computed_values::visibility;
+use style::logical_geometry::{LogicalPoint, LogicalRect, LogicalSize, PhysicalSide, WritingMode};
+use style::properties::longhands::_servo_text_decorations_in_effect::TextDecoration;
 use style::properties::style_structs::ServoBorder;
 use style::properties::{self, ComputedValues, ServoComputedValues};
 use style::values::RGBA;
@@ -64,6 +75,69 @@ pub struct DisplayListBuildState<'a> {
     pub layout_context: &'a LayoutContext<'a>,
     pub items: Vec<DisplayItem>,
     pub stacking_context_id_stack: Vec<StackingContextId>,
+    pub custom_cursors: HashMap<OpaqueNode, CustomCursor>,
+    pub stacking_context_positions: HashMap<StackingContextId, StackingContextPosition>,
+    pub masks: HashMap<StackingContextId, Mask>,
+    pub scroll_snap_containers: HashMap<StackingContextId, ScrollSnapContainer>,
+    pub scroll_snap_areas: HashMap<StackingContextId, Vec<ScrollSnapArea>>,
+    pub touch_action_regions: HashMap<StackingContextId, Vec<TouchActionRegion>>,
+}
+
+/// Where a stacking context is on the page, which `background-attachment: fixed` backgrounds
+/// are positioned against the viewport with.
+#[derive(Clone, Copy)]
+pub struct StackingContextPosition {
+    /// The origin of the stacking context, relative to the origin of the page.
+    pub origin: Point2D<Au>,
+    /// Whether the stacking context is affected by a transform other than that of the root
+    /// element, in which case fixed backgrounds are treated as `scroll` ones.
+    pub transformed: bool,
+}
+
+/// Records where `stacking_context` and its descendants are on the page, given the position of
+/// `stacking_context` itself.
+pub fn collect_stacking_context_positions(
+        stacking_context: &StackingContext,
+        position: StackingContextPosition,
+        root_element_id: StackingContextId,
+        positions: &mut HashMap<StackingContextId, StackingContextPosition>) {
+    positions.insert(stacking_context.id, position);
+    let has_perspective = stacking_context.perspective != Matrix4D::identity();
+    for child in &stacking_context.children {
+        // The items of pseudo-stacking contexts are in the coordinates of their parent.
+        let child_position = if child.context_type == StackingContextType::Real {
+            StackingContextPosition {
+                origin: position.origin + child.bounds.origin,
+                transformed: position.transformed || has_perspective ||
+                    (child.id != root_element_id && child.transform != Matrix4D::identity()),
+            }
+        } else {
+            position
+        };
+        collect_stacking_context_positions(child, child_position, root_element_id, positions);
+    }
+}
+
+/// Gives the stacking contexts in `stacking_context` and its descendants the masks, the scroll
+/// snap containers and areas and the `touch-action` regions that were built with their display
+/// items.
+pub fn attach_to_stacking_contexts(stacking_context: &mut StackingContext,
+                                   state: &mut DisplayListBuildState) {
+    if let Some(mask) = state.masks.remove(&stacking_context.id) {
+        stacking_context.mask = Some(mask);
+    }
+    if let Some(scroll_snap) = state.scroll_snap_containers.remove(&stacking_context.id) {
+        stacking_context.scroll_snap = Some(scroll_snap);
+    }
+    if let Some(scroll_snap_areas) = state.scroll_snap_areas.remove(&stacking_context.id) {
+        stacking_context.scroll_snap_areas = scroll_snap_areas;
+    }
+    if let Some(touch_action_regions) = state.touch_action_regions.remove(&stacking_context.id) {
+        stacking_context.touch_action_regions = touch_action_regions;
+    }
+    for child in &mut stacking_context.children {
+        attach_to_stacking_contexts(child, state);
+    }
 }
 
 impl<'a> DisplayListBuildState<'a> {
@@ -73,6 +147,12 @@ impl<'a> DisplayListBuildState<'a> {
             layout_context: layout_context,
             items: Vec::new(),
             stacking_context_id_stack: vec!(stacking_context_id),
+            custom_cursors: HashMap::new(),
+            stacking_context_positions: HashMap::new(),
+            masks: HashMap::new(),
+            scroll_snap_containers: HashMap::new(),
+            scroll_snap_areas: HashMap::new(),
+            touch_action_regions: HashMap::new(),
         }
     }
 
@@ -80,10 +160,46 @@ impl<'a> DisplayListBuildState<'a> {
         self.items.push(display_item);
     }
 
+    /// Uses the first `url()` cursor image of `style` that has loaded as the cursor of `node`.
+    fn add_custom_cursor(&mut self, node: OpaqueNode, style: &ServoComputedValues) {
+        if self.custom_cursors.contains_key(&node) {
+            return
+        }
+        for cursor_image in &style.get_pointing().cursor.images {
+            let image = match self.layout_context.get_or_request_image_or_meta(
+                    cursor_image.url.clone(), UsePlaceholder::No) {
+                Some(ImageOrMetadataAvailable::ImageAvailable(image)) => image,
+                _ => continue,
+            };
+            // The hotspot defaults to the top left corner, and is clamped to the image.
+            let (x, y) = cursor_image.hotspot.unwrap_or((0., 0.));
+            let hotspot = Point2D::new(
+                (x.max(0.) as u32).min(image.width.saturating_sub(1)),
+                (y.max(0.) as u32).min(image.height.saturating_sub(1)));
+            self.custom_cursors.insert(node, CustomCursor {
+                image: image,
+                hotspot: hotspot,
+            });
+            return
+        }
+    }
+
     fn stacking_context_id(&self) -> StackingContextId {
         self.stacking_context_id_stack.last().unwrap().clone()
     }
 
+    /// The viewport in the coordinates of the current stacking context, or `None` if
+    /// `background-attachment: fixed` backgrounds are treated as `scroll` ones in it.
+    fn fixed_background_viewport(&self) -> Option<Rect<Au>> {
+        match self.stacking_context_positions.get(&self.stacking_context_id()) {
+            Some(position) if !position.transformed => {
+                Some(Rect::new(Point2D::new(-position.origin.x, -position.origin.y),
+                               self.layout_context.shared_context().viewport_size))
+            }
+            _ => None,
+        }
+    }
+
     pub fn push_stacking_context_id(&mut self, stacking_context_id: StackingContextId) {
         self.stacking_context_id_stack.push(stacking_context_id);
     }
@@ -131,6 +247,91 @@ fn create_perspective_matrix(d: Au) -> Matrix4D<f32> {
     }
 }
 
+/// Returns the matrix of the `transform` of the given style, for a border box of the given size.
+pub fn transform_matrix(style: &ServoComputedValues, border_box_size: &Size2D<Au>)
+                        -> Matrix4D<f32> {
+    let mut transform = Matrix4D::identity();
+    if let Some(ref operations) = style.get_effects().transform.0 {
+        let transform_origin = style.get_effects().transform_origin;
+        let transform_origin =
+            Point3D::new(model::specified(transform_origin.horizontal,
+                                          border_box_size.width).to_f32_px(),
+                         model::specified(transform_origin.vertical,
+                                          border_box_size.height).to_f32_px(),
+                         transform_origin.depth.to_f32_px());
+
+        let pre_transform = Matrix4D::create_translation(transform_origin.x,
+                                                         transform_origin.y,
+                                                         transform_origin.z);
+        let post_transform = Matrix4D::create_translation(-transform_origin.x,
+                                                          -transform_origin.y,
+                                                          -transform_origin.z);
+
+        for operation in operations {
+            let matrix = match *operation {
+                transform::ComputedOperation::Rotate(ax, ay, az, theta) => {
+                    let theta = 2.0f32 * f32::consts::PI - theta.radians();
+                    Matrix4D::create_rotation(ax, ay, az, theta)
+                }
+                transform::ComputedOperation::Perspective(d) => {
+                    create_perspective_matrix(d)
+                }
+                transform::ComputedOperation::Scale(sx, sy, sz) => {
+                    Matrix4D::create_scale(sx, sy, sz)
+                }
+                transform::ComputedOperation::Translate(tx, ty, tz) => {
+                    let tx = model::specified(tx, border_box_size.width).to_f32_px();
+                    let ty = model::specified(ty, border_box_size.height).to_f32_px();
+                    let tz = tz.to_f32_px();
+                    Matrix4D::create_translation(tx, ty, tz)
+                }
+                transform::ComputedOperation::Matrix(m) => {
+                    m.to_gfx_matrix()
+                }
+                transform::ComputedOperation::Skew(theta_x, theta_y) => {
+                    Matrix4D::create_skew(theta_x.radians(), theta_y.radians())
+                }
+            };
+
+            transform = transform.mul(&matrix);
+        }
+
+        transform = pre_transform.mul(&transform).mul(&post_transform);
+    }
+
+    transform
+}
+
+/// Returns the matrix that the `perspective` of the given style applies to the children of a
+/// border box of the given size.
+pub fn perspective_matrix(style: &ServoComputedValues, border_box_size: &Size2D<Au>)
+                          -> Matrix4D<f32> {
+    match style.get_effects().perspective {
+        LengthOrNone::Length(d) => {
+            let perspective_origin = style.get_effects().perspective_origin;
+            let perspective_origin =
+                Point2D::new(model::specified(perspective_origin.horizontal,
+                                              border_box_size.width).to_f32_px(),
+                             model::specified(perspective_origin.vertical,
+                                              border_box_size.height).to_f32_px());
+
+            let pre_transform = Matrix4D::create_translation(perspective_origin.x,
+                                                             perspective_origin.y,
+                                                             0.0);
+            let post_transform = Matrix4D::create_translation(-perspective_origin.x,
+                                                              -perspective_origin.y,
+                                                              0.0);
+
+            let perspective_matrix = create_perspective_matrix(d);
+
+            pre_transform.mul(&perspective_matrix).mul(&post_transform)
+        }
+        LengthOrNone::None => {
+            Matrix4D::identity()
+        }
+    }
+}
+
 pub trait FragmentDisplayListBuilding {
     /// Adds the display items necessary to paint the background of this fragment to the display
     /// list if necessary.
@@ -141,6 +342,24 @@ pub trait FragmentDisplayListBuilding {
                                                        absolute_bounds: &Rect<Au>,
                                                        clip: &ClippingRegion);
 
+    /// Builds the mask of the stacking context that this fragment establishes from its
+    /// `mask-image`, if it has one.
+    fn build_mask_if_applicable(&self,
+                                state: &mut DisplayListBuildState,
+                                stacking_relative_border_box: &Rect<Au>);
+
+    /// Records the snap area of this fragment if it has a `scroll-snap-align`, and how the
+    /// stacking context that it establishes snaps if it is a scroll container with a
+    /// `scroll-snap-type`.
+    fn build_scroll_snap_data_if_applicable(&self,
+                                            state: &mut DisplayListBuildState,
+                                            stacking_relative_border_box: &Rect<Au>);
+
+    /// Records the `touch-action` region of this fragment if it restricts what touches do.
+    fn build_touch_action_region_if_applicable(&self,
+                                               state: &mut DisplayListBuildState,
+                                               stacking_relative_border_box: &Rect<Au>);
+
     /// Computes the background size for an image with the given background area according to the
     /// rules in CSS-BACKGROUNDS § 3.9.
     fn compute_background_image_size(&self,
@@ -149,15 +368,22 @@ pub trait FragmentDisplayListBuilding {
                                      image: &WebRenderImageInfo)
                                      -> Size2D<Au>;
 
+    /// Computes where the content of this replaced fragment goes in its content box, given the
+    /// intrinsic size of the content, according to `object-fit` and `object-position`.
+    fn object_rect(&self, content_box: &Rect<Au>, intrinsic_size: &Size2D<Au>) -> Rect<Au>;
+
     /// Adds the display items necessary to paint the background image of this fragment to the
-    /// appropriate section of the display list.
+    /// appropriate section of the display list. `resolution` is the number of image pixels per
+    /// `px`.
     fn build_display_list_for_background_image(&self,
                                                state: &mut DisplayListBuildState,
                                                style: &ServoComputedValues,
                                                display_list_section: DisplayListSection,
                                                absolute_bounds: &Rect<Au>,
                                                clip: &ClippingRegion,
-                                               image_url: &Url);
+                                               image_url: &Url,
+                                               resolution: f32,
+                                               opacity: f32);
 
     /// Adds the display items necessary to paint the background linear gradient of this fragment
     /// to the appropriate section of the display list.
@@ -167,7 +393,8 @@ pub trait FragmentDisplayListBuilding {
                                                          absolute_bounds: &Rect<Au>,
                                                          clip: &ClippingRegion,
                                                          gradient: &LinearGradient,
-                                                         style: &ServoComputedValues);
+                                                         style: &ServoComputedValues,
+                                                         opacity: f32);
 
     /// Adds the display items necessary to paint the borders of this fragment to a display list if
     /// necessary.
@@ -180,13 +407,38 @@ pub trait FragmentDisplayListBuilding {
             display_list_section: DisplayListSection,
             clip: &ClippingRegion);
 
+    /// Adds the display items necessary to paint the border image of this fragment, sliced into
+    /// nine regions according to the `border-image-*` properties, to the display list. Returns
+    /// false if the image is not available yet, in which case the borders are painted instead.
+    fn build_display_list_for_border_image(&self,
+                                           state: &mut DisplayListBuildState,
+                                           style: &ServoComputedValues,
+                                           display_list_section: DisplayListSection,
+                                           bounds: &Rect<Au>,
+                                           clip: &ClippingRegion,
+                                           image_url: &Url)
+                                           -> bool;
+
+    /// Adds the display items with which the theme paints `widget` in place of the background and
+    /// borders of this fragment.
+    fn build_display_list_for_widget(&self,
+                                     state: &mut DisplayListBuildState,
+                                     widget: Widget,
+                                     widget_state: WidgetState,
+                                     display_list_section: DisplayListSection,
+                                     bounds: &Rect<Au>,
+                                     clip: &ClippingRegion);
+
     /// Adds the display items necessary to paint the outline of this fragment to the display list
-    /// if necessary.
+    /// if necessary. The outline of an inline box broken across lines is open where the box is
+    /// broken, so that the outlines of its fragments join into one shape.
     fn build_display_list_for_outline_if_applicable(&self,
                                                     state: &mut DisplayListBuildState,
                                                     style: &ServoComputedValues,
                                                     bounds: &Rect<Au>,
-                                                    clip: &ClippingRegion);
+                                                    clip: &ClippingRegion,
+                                                    is_first_fragment_of_element: bool,
+                                                    is_last_fragment_of_element: bool);
 
     /// Adds the display items necessary to paint the box shadow of this fragment to the display
     /// list if necessary.
@@ -212,6 +464,17 @@ pub trait FragmentDisplayListBuilding {
                                            stacking_relative_border_box: &Rect<Au>,
                                            clip: &ClippingRegion);
 
+    /// Adds the devtools highlight overlay, which tints the margin, border, padding and content
+    /// areas of this fragment, if it belongs to the node being inspected.
+    fn build_display_items_for_highlight_if_necessary(&self,
+                                                      state: &mut DisplayListBuildState,
+                                                      stacking_relative_border_box: &Rect<Au>,
+                                                      clip: &ClippingRegion);
+
+    /// Returns true if the text of this fragment should not be painted yet, because the first
+    /// font family of its style is a web font in its block period.
+    fn waits_for_web_font(&self, state: &DisplayListBuildState) -> bool;
+
     /// Adds the display items for this fragment to the given display list.
     ///
     /// Arguments:
@@ -237,9 +500,12 @@ pub trait FragmentDisplayListBuilding {
                           stacking_relative_display_port: &Rect<Au>);
 
     /// Adjusts the clipping region for descendants of this fragment as appropriate.
+    /// `scroll_overflow` is the area that the descendants can be scrolled over, if this fragment
+    /// scrolls them.
     fn adjust_clipping_region_for_children(&self,
                                            current_clip: &mut ClippingRegion,
-                                           stacking_relative_border_box: &Rect<Au>);
+                                           stacking_relative_border_box: &Rect<Au>,
+                                           scroll_overflow: Option<&Rect<Au>>);
 
     /// Adjusts the clipping rectangle for a fragment to take the `clip` property into account
     /// per CSS 2.1 § 11.1.2.
@@ -268,14 +534,23 @@ pub trait FragmentDisplayListBuilding {
                                             offset: &Point2D<Au>,
                                             clip: &ClippingRegion);
 
-    /// Creates the display item for a text decoration: underline, overline, or line-through.
+    /// Creates the display items for a text decoration: underline, overline, or line-through.
+    /// `stacking_relative_box` is the box of a solid line; the other styles are painted around it.
     fn build_display_list_for_text_decoration(&self,
                                               state: &mut DisplayListBuildState,
-                                              color: &RGBA,
+                                              decoration: &TextDecoration,
                                               stacking_relative_box: &LogicalRect<Au>,
                                               clip: &ClippingRegion,
                                               blur_radius: Au);
 
+    /// Creates the display item for a solid piece of a text decoration.
+    fn build_display_list_for_text_decoration_line(&self,
+                                                   state: &mut DisplayListBuildState,
+                                                   color: &RGBA,
+                                                   stacking_relative_box: &LogicalRect<Au>,
+                                                   clip: &ClippingRegion,
+                                                   blur_radius: Au);
+
     /// A helper method that `build_display_list` calls to create per-fragment-type display items.
     fn build_fragment_type_specific_display_items(&mut self,
                                                   state: &mut DisplayListBuildState,
@@ -333,6 +608,32 @@ fn build_border_radius(abs_bounds: &Rect<Au>, border_style: &ServoBorder) -> Bor
     })
 }
 
+/// Sets the width and style of `side` of a border display item.
+fn set_border_side(widths: &mut SideOffsets2D<Au>,
+                   styles: &mut SideOffsets2D<border_style::T>,
+                   side: PhysicalSide,
+                   width: Au,
+                   style: border_style::T) {
+    match side {
+        PhysicalSide::Top => {
+            widths.top = width;
+            styles.top = style;
+        }
+        PhysicalSide::Right => {
+            widths.right = width;
+            styles.right = style;
+        }
+        PhysicalSide::Bottom => {
+            widths.bottom = width;
+            styles.bottom = style;
+        }
+        PhysicalSide::Left => {
+            widths.left = width;
+            styles.left = style;
+        }
+    }
+}
+
 impl FragmentDisplayListBuilding for Fragment {
     fn build_display_list_for_background_if_applicable(&self,
                                                        state: &mut DisplayListBuildState,
@@ -390,25 +691,129 @@ impl FragmentDisplayListBuilding for Fragment {
         // Implements background image, per spec:
         // http://www.w3.org/TR/CSS21/colors.html#background
         let background = style.get_background();
-        match background.background_image.0 {
-            None => {}
-            Some(computed::Image::LinearGradient(ref gradient)) => {
-                self.build_display_list_for_background_linear_gradient(state,
-                                                                       display_list_section,
-                                                                       &bounds,
-                                                                       &clip,
-                                                                       gradient,
-                                                                       style);
+        let image = match background.background_image.0 {
+            Some(ref image) => image,
+            None => return,
+        };
+        let device_pixel_ratio = state.layout_context.shared.device_pixel_ratio;
+        for layer in image_layers(image, device_pixel_ratio) {
+            match *layer.image {
+                computed::Image::LinearGradient(ref gradient) => {
+                    self.build_display_list_for_background_linear_gradient(state,
+                                                                           display_list_section,
+                                                                           &bounds,
+                                                                           &clip,
+                                                                           gradient,
+                                                                           style,
+                                                                           layer.opacity);
+                }
+                computed::Image::Url(ref image_url) => {
+                    self.build_display_list_for_background_image(state,
+                                                                 style,
+                                                                 display_list_section,
+                                                                 &bounds,
+                                                                 &clip,
+                                                                 image_url,
+                                                                 layer.resolution,
+                                                                 layer.opacity);
+                }
+                computed::Image::ImageSet(_) | computed::Image::CrossFade(_) => {
+                    unreachable!("image_layers only returns plain images")
+                }
             }
-            Some(computed::Image::Url(ref image_url)) => {
-                self.build_display_list_for_background_image(state,
-                                                             style,
-                                                             display_list_section,
-                                                             &bounds,
-                                                             &clip,
-                                                             image_url);
+        }
+    }
+
+    fn build_mask_if_applicable(&self,
+                                state: &mut DisplayListBuildState,
+                                stacking_relative_border_box: &Rect<Au>) {
+        let svg = self.style.get_svg();
+        if svg.mask_image.0.is_none() {
+            return
+        }
+
+        // Mask images are laid out like background images of the border box, so the mask is
+        // painted as the backgrounds of a style that has them.
+        let mut style = self.style.clone();
+        {
+            let background = Arc::make_mut(&mut style).mutate_background();
+            background.background_image = svg.mask_image.clone();
+            background.background_position = svg.mask_position;
+            background.background_repeat = svg.mask_repeat;
+            background.background_size = svg.mask_size.clone();
+            background.background_attachment = background_attachment::T::scroll;
+            background.background_origin = background_origin::T::border_box;
+            background.background_clip = background_clip::T::border_box;
+        }
+        let items = mem::replace(&mut state.items, vec![]);
+        self.build_display_list_for_background_if_applicable(state,
+                                                             &*style,
+                                                             DisplayListSection::Content,
+                                                             stacking_relative_border_box,
+                                                             &ClippingRegion::max());
+        // The background color is left out, as it is not part of the mask.
+        let mask_items = mem::replace(&mut state.items, items).into_iter().filter(|item| {
+            match *item {
+                DisplayItem::SolidColorClass(_) => false,
+                _ => true,
             }
+        }).collect();
+        let stacking_context_id = state.stacking_context_id();
+        state.masks.insert(stacking_context_id, Mask {
+            items: mask_items,
+            // Images are alpha masks unless they are told to be luminance ones.
+            luminance: svg.mask_mode == mask_mode::T::luminance,
+        });
+    }
+
+    fn build_scroll_snap_data_if_applicable(&self,
+                                            state: &mut DisplayListBuildState,
+                                            stacking_relative_border_box: &Rect<Au>) {
+        let stacking_context_id = state.stacking_context_id();
+        let box_style = self.style.get_box();
+        if box_style.scroll_snap_align != scroll_snap_align::T::none {
+            state.scroll_snap_areas.entry(stacking_context_id).or_insert_with(Vec::new).push(
+                ScrollSnapArea {
+                    rect: *stacking_relative_border_box,
+                    align: box_style.scroll_snap_align,
+                });
+        }
+
+        // Scroll containers establish stacking contexts, which scroll their overflow area if
+        // there is any.
+        if !self.establishes_stacking_context() ||
+                (box_style.overflow_x == overflow_x::T::visible &&
+                 box_style.overflow_y.0 == overflow_x::T::visible) ||
+                (box_style.scroll_snap_type_x == scroll_snap_type_x::T::none &&
+                 box_style.scroll_snap_type_y == scroll_snap_type_y::T::none) {
+            return
         }
+        let border = self.border_width().to_physical(self.style.writing_mode);
+        let scrollport =
+            Rect::new(Point2D::new(stacking_relative_border_box.origin.x + border.left,
+                                   stacking_relative_border_box.origin.y + border.top),
+                      Size2D::new(stacking_relative_border_box.size.width - border.horizontal(),
+                                  stacking_relative_border_box.size.height - border.vertical()));
+        state.scroll_snap_containers.insert(stacking_context_id, ScrollSnapContainer {
+            type_x: box_style.scroll_snap_type_x,
+            type_y: box_style.scroll_snap_type_y,
+            scrollport: scrollport,
+        });
+    }
+
+    fn build_touch_action_region_if_applicable(&self,
+                                               state: &mut DisplayListBuildState,
+                                               stacking_relative_border_box: &Rect<Au>) {
+        let touch_action = self.style.get_box().touch_action;
+        if touch_action == touch_action::T::auto {
+            return
+        }
+        let stacking_context_id = state.stacking_context_id();
+        state.touch_action_regions.entry(stacking_context_id).or_insert_with(Vec::new).push(
+            TouchActionRegion {
+                rect: *stacking_relative_border_box,
+                touch_action: touch_action,
+            });
     }
 
     fn compute_background_image_size(&self,
@@ -464,13 +869,59 @@ impl FragmentDisplayListBuilding for Fragment {
         }
     }
 
+    fn object_rect(&self, content_box: &Rect<Au>, intrinsic_size: &Size2D<Au>) -> Rect<Au> {
+        // Content without an intrinsic size, or with an empty one, fills the content box.
+        if intrinsic_size.width <= Au(0) || intrinsic_size.height <= Au(0) {
+            return *content_box
+        }
+
+        let position = self.style.get_position();
+        let scale_to_content_box = |cover: bool| {
+            let width_ratio = content_box.size.width.to_f32_px() /
+                              intrinsic_size.width.to_f32_px();
+            let height_ratio = content_box.size.height.to_f32_px() /
+                               intrinsic_size.height.to_f32_px();
+            let ratio = if cover {
+                width_ratio.max(height_ratio)
+            } else {
+                width_ratio.min(height_ratio)
+            };
+            Size2D::new(intrinsic_size.width.scale_by(ratio),
+                        intrinsic_size.height.scale_by(ratio))
+        };
+        let size = match position.object_fit {
+            object_fit::T::fill => content_box.size,
+            object_fit::T::contain => scale_to_content_box(false),
+            object_fit::T::cover => scale_to_content_box(true),
+            object_fit::T::none => *intrinsic_size,
+            object_fit::T::scale_down => {
+                if intrinsic_size.width <= content_box.size.width &&
+                        intrinsic_size.height <= content_box.size.height {
+                    *intrinsic_size
+                } else {
+                    scale_to_content_box(false)
+                }
+            }
+        };
+
+        let horizontal_position = model::specified(position.object_position.horizontal,
+                                                   content_box.size.width - size.width);
+        let vertical_position = model::specified(position.object_position.vertical,
+                                                 content_box.size.height - size.height);
+        Rect::new(Point2D::new(content_box.origin.x + horizontal_position,
+                               content_box.origin.y + vertical_position),
+                  size)
+    }
+
     fn build_display_list_for_background_image(&self,
                                                state: &mut DisplayListBuildState,
                                                style: &ServoComputedValues,
                                                display_list_section: DisplayListSection,
                                                absolute_bounds: &Rect<Au>,
                                                clip: &ClippingRegion,
-                                               image_url: &Url) {
+                                               image_url: &Url,
+                                               resolution: f32,
+                                               opacity: f32) {
         let background = style.get_background();
         let fetch_image_data_as_well = !opts::get().use_webrender;
         let webrender_image =
@@ -480,9 +931,32 @@ impl FragmentDisplayListBuilding for Fragment {
         if let Some((webrender_image, image_data)) = webrender_image {
             debug!("(building display list) building background image");
 
+            // Fixed backgrounds are positioned against the viewport, unless a transform affects
+            // them and they are treated as `scroll` backgrounds.
+            let fixed_viewport = match background.background_attachment {
+                background_attachment::T::scroll => None,
+                background_attachment::T::fixed => state.fixed_background_viewport(),
+            };
+
             // Use `background-size` to get the size.
             let mut bounds = *absolute_bounds;
-            let image_size = self.compute_background_image_size(style, &bounds, &webrender_image);
+            let positioning_area = fixed_viewport.unwrap_or(bounds);
+            // Images of higher resolutions have more pixels for the same size.
+            let intrinsic_image = WebRenderImageInfo {
+                width: (webrender_image.width as f32 / resolution).round() as u32,
+                height: (webrender_image.height as f32 / resolution).round() as u32,
+                ..webrender_image
+            };
+            let image_size =
+                self.compute_background_image_size(style, &positioning_area, &intrinsic_image);
+
+            // A fixed background is only visible where the element and the viewport overlap.
+            if let Some(ref viewport) = fixed_viewport {
+                bounds = match bounds.intersection(viewport) {
+                    Some(visible_bounds) => visible_bounds,
+                    None => return,
+                };
+            }
 
             // Clip.
             //
@@ -490,45 +964,50 @@ impl FragmentDisplayListBuilding for Fragment {
             let mut clip = clip.clone();
             clip.intersect_rect(&bounds);
 
-            // Background image should be positioned on the padding box basis.
-            let border = style.logical_border_width().to_physical(style.writing_mode);
-
-            // Use 'background-origin' to get the origin value.
-            let (mut origin_x, mut origin_y) = match background.background_origin {
-                background_origin::T::padding_box => {
-                    (Au(0), Au(0))
-                }
-                background_origin::T::border_box => {
-                    (-border.left, -border.top)
-                }
-                background_origin::T::content_box => {
-                    let border_padding = self.border_padding.to_physical(self.style.writing_mode);
-                    (border_padding.left - border.left, border_padding.top - border.top)
-                }
-            };
-
-            // Use `background-attachment` to get the initial virtual origin
-            let (virtual_origin_x, virtual_origin_y) = match background.background_attachment {
-                background_attachment::T::scroll => {
-                    (absolute_bounds.origin.x, absolute_bounds.origin.y)
-                }
-                background_attachment::T::fixed => {
+            let (abs_x, abs_y) = match fixed_viewport {
+                Some(ref viewport) => {
                     // If the ‘background-attachment’ value for this image is ‘fixed’, then
                     // 'background-origin' has no effect.
-                    origin_x = Au(0);
-                    origin_y = Au(0);
-                    (Au(0), Au(0))
+                    let horizontal_position =
+                        model::specified(background.background_position.horizontal,
+                                         viewport.size.width - image_size.width);
+                    let vertical_position =
+                        model::specified(background.background_position.vertical,
+                                         viewport.size.height - image_size.height);
+                    (viewport.origin.x + horizontal_position,
+                     viewport.origin.y + vertical_position)
                 }
-            };
+                None => {
+                    // Background image should be positioned on the padding box basis.
+                    let border = style.logical_border_width().to_physical(style.writing_mode);
+
+                    // Use 'background-origin' to get the origin value.
+                    let (origin_x, origin_y) = match background.background_origin {
+                        background_origin::T::padding_box => {
+                            (Au(0), Au(0))
+                        }
+                        background_origin::T::border_box => {
+                            (-border.left, -border.top)
+                        }
+                        background_origin::T::content_box => {
+                            let border_padding =
+                                self.border_padding.to_physical(self.style.writing_mode);
+                            (border_padding.left - border.left, border_padding.top - border.top)
+                        }
+                    };
 
-            // Use `background-position` to get the offset.
-            let horizontal_position = model::specified(background.background_position.horizontal,
-                                                       bounds.size.width - image_size.width);
-            let vertical_position = model::specified(background.background_position.vertical,
-                                                     bounds.size.height - image_size.height);
+                    // Use `background-position` to get the offset.
+                    let horizontal_position =
+                        model::specified(background.background_position.horizontal,
+                                         bounds.size.width - image_size.width);
+                    let vertical_position =
+                        model::specified(background.background_position.vertical,
+                                         bounds.size.height - image_size.height);
 
-            let abs_x = border.left + virtual_origin_x + horizontal_position + origin_x;
-            let abs_y = border.top + virtual_origin_y + vertical_position + origin_y;
+                    (border.left + absolute_bounds.origin.x + horizontal_position + origin_x,
+                     border.top + absolute_bounds.origin.y + vertical_position + origin_y)
+                }
+            };
 
             // Adjust origin and size based on background-repeat
             match background.background_repeat {
@@ -572,13 +1051,30 @@ impl FragmentDisplayListBuilding for Fragment {
                                                       self.node,
                                                       style.get_cursor(Cursor::DefaultCursor),
                                                       display_list_section);
-            state.add_display_item(DisplayItem::ImageClass(box ImageDisplayItem {
+            let item = DisplayItem::ImageClass(box ImageDisplayItem {
                 base: base,
                 webrender_image: webrender_image,
                 image_data: image_data.map(Arc::new),
                 stretch_size: Size2D::new(image_size.width, image_size.height),
                 image_rendering: style.get_inheritedbox().image_rendering.clone(),
-            }));
+                opacity: opacity,
+            });
+
+            if fixed_viewport.is_none() || opts::get().use_webrender {
+                state.add_display_item(item);
+            } else {
+                // Give the image a layer of its own, which the compositor keeps in place while
+                // scrolling.
+                let layer_id = LayerId::new_of_type(LayerType::FixedBackground,
+                                                    self.node.id() as usize);
+                state.add_display_item(DisplayItem::LayeredItemClass(box LayeredItem {
+                    item: item,
+                    layer_info: LayerInfo::new(layer_id,
+                                               ScrollPolicy::FixedPosition,
+                                               None,
+                                               color::transparent()),
+                }));
+            }
         }
     }
 
@@ -588,7 +1084,8 @@ impl FragmentDisplayListBuilding for Fragment {
                                                          absolute_bounds: &Rect<Au>,
                                                          clip: &ClippingRegion,
                                                          gradient: &LinearGradient,
-                                                         style: &ServoComputedValues) {
+                                                         style: &ServoComputedValues,
+                                                         opacity: f32) {
         let mut clip = clip.clone();
         clip.intersect_rect(absolute_bounds);
 
@@ -686,9 +1183,11 @@ impl FragmentDisplayListBuilding for Fragment {
                     position_to_offset(position, length)
                 }
             };
+            let mut color = style.resolve_color(stop.color).to_gfx_color();
+            color.a *= opacity;
             stops.push(GradientStop {
                 offset: offset,
-                color: style.resolve_color(stop.color).to_gfx_color()
+                color: color,
             })
         }
 
@@ -766,6 +1265,29 @@ impl FragmentDisplayListBuilding for Fragment {
             }
             BorderPaintingMode::Hidden => return,
         }
+
+        // Border images do not apply to collapsed borders.
+        if let BorderPaintingMode::Separate = border_painting_mode {
+            // Border images are not faded: only the topmost image of a `cross-fade()` is used.
+            let device_pixel_ratio = state.layout_context.shared.device_pixel_ratio;
+            let url = style.get_border().border_image_source.0.as_ref().and_then(|image| {
+                match image_layers(image, device_pixel_ratio).pop() {
+                    Some(ImageLayer { image: &computed::Image::Url(ref url), .. }) => Some(url),
+                    _ => None,
+                }
+            });
+            if let Some(url) = url {
+                if self.build_display_list_for_border_image(state,
+                                                            style,
+                                                            display_list_section,
+                                                            bounds,
+                                                            clip,
+                                                            url) {
+                    return
+                }
+            }
+        }
+
         if border.is_zero() {
             return
         }
@@ -814,11 +1336,223 @@ impl FragmentDisplayListBuilding for Fragment {
         }));
     }
 
+    fn build_display_list_for_border_image(&self,
+                                           state: &mut DisplayListBuildState,
+                                           style: &ServoComputedValues,
+                                           display_list_section: DisplayListSection,
+                                           bounds: &Rect<Au>,
+                                           clip: &ClippingRegion,
+                                           image_url: &Url)
+                                           -> bool {
+        let fetch_image_data_as_well = !opts::get().use_webrender;
+        let (webrender_image, image_data) =
+            match state.layout_context.get_webrender_image_for_url(image_url,
+                                                                   UsePlaceholder::No,
+                                                                   fetch_image_data_as_well) {
+                Some(image) => image,
+                None => return false,
+            };
+        debug!("(building display list) building border image");
+
+        let border_style_struct = style.get_border();
+        let border = style.logical_border_width().to_physical(style.writing_mode);
+        let image_size = Size2D::new(webrender_image.width as f32,
+                                     webrender_image.height as f32);
+
+        // Use `border-image-slice` to find the edges of the regions of the image, in image
+        // pixels.
+        let slice = border_style_struct.border_image_slice;
+        let slice_offset = |offset: border_image_slice::Offset, size: f32| {
+            match offset {
+                border_image_slice::Offset::Number(number) => number.min(size),
+                border_image_slice::Offset::Percentage(percentage) => {
+                    (percentage * size).min(size)
+                }
+            }
+        };
+        let slice = SideOffsets2D::new(slice_offset(slice.top, image_size.height),
+                                       slice_offset(slice.right, image_size.width),
+                                       slice_offset(slice.bottom, image_size.height),
+                                       slice_offset(slice.left, image_size.width));
+
+        // Use `border-image-outset` to get the border image area.
+        let outset = border_style_struct.border_image_outset;
+        let outset_length = |outset: border_image_outset::Outset, border_width: Au| {
+            match outset {
+                border_image_outset::Outset::Length(length) => length,
+                border_image_outset::Outset::Number(number) => border_width.scale_by(number),
+            }
+        };
+        let outset = SideOffsets2D::new(outset_length(outset.top, border.top),
+                                        outset_length(outset.right, border.right),
+                                        outset_length(outset.bottom, border.bottom),
+                                        outset_length(outset.left, border.left));
+        let area = Rect::new(Point2D::new(bounds.origin.x - outset.left,
+                                          bounds.origin.y - outset.top),
+                             Size2D::new(bounds.size.width + outset.horizontal(),
+                                         bounds.size.height + outset.vertical()));
+
+        // Use `border-image-width` to get the widths of the regions of the area, scaling them
+        // down together if those of opposite sides overlap.
+        let widths = border_style_struct.border_image_width;
+        let region_width = |width: border_image_width::Width,
+                            border_width: Au,
+                            slice: f32,
+                            area_length: Au| {
+            match width {
+                border_image_width::Width::LengthOrPercentage(length) => {
+                    model::specified(length, area_length)
+                }
+                border_image_width::Width::Number(number) => border_width.scale_by(number),
+                border_image_width::Width::Auto => Au::from_f32_px(slice),
+            }
+        };
+        let mut widths = SideOffsets2D::new(
+            region_width(widths.top, border.top, slice.top, area.size.height),
+            region_width(widths.right, border.right, slice.right, area.size.width),
+            region_width(widths.bottom, border.bottom, slice.bottom, area.size.height),
+            region_width(widths.left, border.left, slice.left, area.size.width));
+        let scale = (area.size.width.to_f32_px() / widths.horizontal().to_f32_px())
+            .min(area.size.height.to_f32_px() / widths.vertical().to_f32_px());
+        if scale < 1.0 {
+            widths = SideOffsets2D::new(widths.top.scale_by(scale),
+                                        widths.right.scale_by(scale),
+                                        widths.bottom.scale_by(scale),
+                                        widths.left.scale_by(scale));
+        }
+
+        // The columns and rows of the nine regions, as offsets and lengths in the image and in
+        // the area.
+        let image_columns = [(0.0, slice.left),
+                             (slice.left, image_size.width - slice.left - slice.right),
+                             (image_size.width - slice.right, slice.right)];
+        let image_rows = [(0.0, slice.top),
+                          (slice.top, image_size.height - slice.top - slice.bottom),
+                          (image_size.height - slice.bottom, slice.bottom)];
+        let area_columns = [(area.origin.x, widths.left),
+                            (area.origin.x + widths.left, area.size.width - widths.horizontal()),
+                            (area.max_x() - widths.right, widths.right)];
+        let area_rows = [(area.origin.y, widths.top),
+                         (area.origin.y + widths.top, area.size.height - widths.vertical()),
+                         (area.max_y() - widths.bottom, widths.bottom)];
+
+        // The edges and the middle are scaled like the corners next to them before being tiled:
+        // the top and bottom edges to the height of their regions, the left and right edges to
+        // the width of theirs, and the middle like the top and left edges.
+        let scale_to = |area_length: Au, image_length: f32| {
+            if image_length > 0.0 {
+                area_length.to_f32_px() / image_length
+            } else {
+                1.0
+            }
+        };
+        let horizontal_scales = [scale_to(widths.top, slice.top),
+                                 scale_to(widths.top, slice.top),
+                                 scale_to(widths.bottom, slice.bottom)];
+        let vertical_scales = [scale_to(widths.left, slice.left),
+                               scale_to(widths.left, slice.left),
+                               scale_to(widths.right, slice.right)];
+
+        let repeat = border_style_struct.border_image_repeat;
+        let image_data = image_data.map(Arc::new);
+        for row in 0..3 {
+            for column in 0..3 {
+                if row == 1 && column == 1 && !border_style_struct.border_image_slice.fill {
+                    continue
+                }
+                let (image_x, image_width) = image_columns[column];
+                let (image_y, image_height) = image_rows[row];
+                let (area_x, area_width) = area_columns[column];
+                let (area_y, area_height) = area_rows[row];
+                if image_width <= 0.0 || image_height <= 0.0 || area_width <= Au(0) ||
+                        area_height <= Au(0) {
+                    continue
+                }
+
+                // Corners are stretched to their regions, edges only along their sides.
+                let horizontal_tiles = if column == 1 {
+                    border_image_tiles(repeat.0,
+                                       area_x,
+                                       area_width,
+                                       Au::from_f32_px(image_width * horizontal_scales[row]))
+                } else {
+                    vec![(area_x, area_width)]
+                };
+                let vertical_tiles = if row == 1 {
+                    border_image_tiles(repeat.1,
+                                       area_y,
+                                       area_height,
+                                       Au::from_f32_px(image_height * vertical_scales[column]))
+                } else {
+                    vec![(area_y, area_height)]
+                };
+
+                let region = Rect::new(Point2D::new(area_x, area_y),
+                                       Size2D::new(area_width, area_height));
+                for &(tile_x, tile_width) in &horizontal_tiles {
+                    for &(tile_y, tile_height) in &vertical_tiles {
+                        // Draw the whole image, scaled and positioned so that the slice
+                        // covers the tile, clipped to the tile.
+                        let scale_x = tile_width.to_f32_px() / image_width;
+                        let scale_y = tile_height.to_f32_px() / image_height;
+                        let image_bounds = Rect::new(
+                            Point2D::new(tile_x - Au::from_f32_px(image_x * scale_x),
+                                         tile_y - Au::from_f32_px(image_y * scale_y)),
+                            Size2D::new(Au::from_f32_px(image_size.width * scale_x),
+                                        Au::from_f32_px(image_size.height * scale_y)));
+                        let tile = Rect::new(Point2D::new(tile_x, tile_y),
+                                             Size2D::new(tile_width, tile_height));
+                        let mut clip = clip.clone();
+                        clip.intersect_rect(&region);
+                        clip.intersect_rect(&tile);
+
+                        let base = state.create_base_display_item(
+                            &image_bounds,
+                            &clip,
+                            self.node,
+                            style.get_cursor(Cursor::DefaultCursor),
+                            display_list_section);
+                        state.add_display_item(DisplayItem::ImageClass(box ImageDisplayItem {
+                            base: base,
+                            webrender_image: webrender_image,
+                            image_data: image_data.clone(),
+                            stretch_size: image_bounds.size,
+                            image_rendering: style.get_inheritedbox().image_rendering.clone(),
+                            opacity: 1.0,
+                        }));
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    fn build_display_list_for_widget(&self,
+                                     state: &mut DisplayListBuildState,
+                                     widget: Widget,
+                                     widget_state: WidgetState,
+                                     display_list_section: DisplayListSection,
+                                     bounds: &Rect<Au>,
+                                     clip: &ClippingRegion) {
+        let base = state.create_base_display_item(bounds,
+                                                  clip,
+                                                  self.node,
+                                                  self.style.get_cursor(Cursor::DefaultCursor),
+                                                  display_list_section);
+        let mut items = vec![];
+        theme::platform_theme().build_display_items(widget, widget_state, None, &base, &mut items);
+        for item in items {
+            state.add_display_item(item);
+        }
+    }
+
     fn build_display_list_for_outline_if_applicable(&self,
                                                     state: &mut DisplayListBuildState,
                                                     style: &ServoComputedValues,
                                                     bounds: &Rect<Au>,
-                                                    clip: &ClippingRegion) {
+                                                    clip: &ClippingRegion,
+                                                    is_first_fragment_of_element: bool,
+                                                    is_last_fragment_of_element: bool) {
         let width = style.get_outline().outline_width;
         if width == Au(0) {
             return
@@ -829,14 +1563,27 @@ impl FragmentDisplayListBuilding for Fragment {
             return
         }
 
+        let mut widths = SideOffsets2D::new_all_same(width);
+        let mut styles = SideOffsets2D::new_all_same(outline_style);
+        if !is_first_fragment_of_element {
+            let side = style.writing_mode.inline_start_physical_side();
+            set_border_side(&mut widths, &mut styles, side, Au(0), border_style::T::none);
+        }
+        if !is_last_fragment_of_element {
+            let side = style.writing_mode.inline_end_physical_side();
+            set_border_side(&mut widths, &mut styles, side, Au(0), border_style::T::none);
+        }
+
         // Outlines are not accounted for in the dimensions of the border box, so adjust the
-        // absolute bounds.
-        let mut bounds = *bounds;
+        // absolute bounds. The open sides of broken inline boxes stay at their border box.
         let offset = width + style.get_outline().outline_offset;
-        bounds.origin.x = bounds.origin.x - offset;
-        bounds.origin.y = bounds.origin.y - offset;
-        bounds.size.width = bounds.size.width + offset + offset;
-        bounds.size.height = bounds.size.height + offset + offset;
+        let outset = |side_width: Au| if side_width == Au(0) { Au(0) } else { offset };
+        let (top, right) = (outset(widths.top), outset(widths.right));
+        let (bottom, left) = (outset(widths.bottom), outset(widths.left));
+        let bounds = Rect::new(
+            Point2D::new(bounds.origin.x - left, bounds.origin.y - top),
+            Size2D::new(cmp::max(bounds.size.width + left + right, Au(0)),
+                        cmp::max(bounds.size.height + top + bottom, Au(0))));
 
         // Append the outline to the display list.
         let color = style.resolve_color(style.get_outline().outline_color).to_gfx_color();
@@ -847,9 +1594,9 @@ impl FragmentDisplayListBuilding for Fragment {
                                                   DisplayListSection::Outlines);
         state.add_display_item(DisplayItem::BorderClass(box BorderDisplayItem {
             base: base,
-            border_widths: SideOffsets2D::new_all_same(width),
+            border_widths: widths,
             color: SideOffsets2D::new_all_same(color),
-            style: SideOffsets2D::new_all_same(outline_style),
+            style: styles,
             radius: Default::default(),
         }));
     }
@@ -917,6 +1664,78 @@ impl FragmentDisplayListBuilding for Fragment {
         }));
     }
 
+    fn waits_for_web_font(&self, state: &DisplayListBuildState) -> bool {
+        let blocked_families = &state.layout_context.shared.blocked_font_families;
+        !blocked_families.is_empty() &&
+            self.style.get_font().font_family.0.first().map_or(false, |family| {
+                blocked_families.contains(&family.name().to_lowercase())
+            })
+    }
+
+    fn build_display_items_for_highlight_if_necessary(&self,
+                                                      state: &mut DisplayListBuildState,
+                                                      stacking_relative_border_box: &Rect<Au>,
+                                                      clip: &ClippingRegion) {
+        let highlighted_node = match state.layout_context.shared.highlighted_node {
+            Some(highlighted_node) => highlighted_node,
+            None => return,
+        };
+
+        let content_color = color::rgba(0.44, 0.66, 0.86, 0.66);
+        if self.node != highlighted_node {
+            // Inline elements have no fragments of their own, so tint the fragments of their
+            // contents instead.
+            let in_highlighted_inline = self.inline_context.as_ref().map_or(false, |context| {
+                context.nodes.iter().any(|node| node.address == highlighted_node)
+            });
+            if in_highlighted_inline {
+                add_highlight_area(state,
+                                   stacking_relative_border_box,
+                                   None,
+                                   content_color,
+                                   self.node,
+                                   clip);
+            }
+            return
+        }
+
+        let writing_mode = self.style.writing_mode;
+        let margin = self.margin.to_physical(writing_mode);
+        let border = self.border_width().to_physical(writing_mode);
+        let border_padding = self.border_padding.to_physical(writing_mode);
+        let padding = SideOffsets2D::new(border_padding.top - border.top,
+                                         border_padding.right - border.right,
+                                         border_padding.bottom - border.bottom,
+                                         border_padding.left - border.left);
+
+        let border_box = *stacking_relative_border_box;
+        let margin_box = self.stacking_relative_margin_box(&border_box);
+        let padding_box = self.stacking_relative_padding_box(&border_box);
+        let content_box = self.stacking_relative_content_box(&border_box);
+
+        // Every area but the content is drawn as a ring around the next one in, so that the
+        // tints do not overlap.
+        add_highlight_area(state,
+                           &margin_box,
+                           Some(margin),
+                           color::rgba(0.96, 0.7, 0.42, 0.66),
+                           self.node,
+                           clip);
+        add_highlight_area(state,
+                           &border_box,
+                           Some(border),
+                           color::rgba(1.0, 0.9, 0.6, 0.66),
+                           self.node,
+                           clip);
+        add_highlight_area(state,
+                           &padding_box,
+                           Some(padding),
+                           color::rgba(0.58, 0.77, 0.49, 0.55),
+                           self.node,
+                           clip);
+        add_highlight_area(state, &content_box, None, content_color, self.node, clip);
+    }
+
     fn adjust_clip_for_style(&self,
                              parent_clip: &mut ClippingRegion,
                              stacking_relative_border_box: &Rect<Au>) {
@@ -966,7 +1785,8 @@ impl FragmentDisplayListBuilding for Fragment {
             }));
         }
 
-        // Draw a caret at the insertion point.
+        // Draw a caret at the insertion point. Layout blinks it by rebuilding the display list
+        // with the caret hidden every other phase.
         let insertion_point_index = match scanned_text_fragment_info.insertion_point {
             Some(insertion_point_index) => insertion_point_index,
             None => return,
@@ -997,9 +1817,14 @@ impl FragmentDisplayListBuilding for Fragment {
                                                   self.node,
                                                   self.style.get_cursor(cursor),
                                                   display_list_section);
-        state.add_display_item(DisplayItem::SolidColorClass(box SolidColorDisplayItem {
+        let color = match self.style().get_pointing().caret_color.0 {
+            Some(color) => self.style().resolve_color(color),
+            None => self.style().get_color().color,
+        };
+        state.add_display_item(DisplayItem::CaretClass(box CaretDisplayItem {
             base: base,
-            color: self.style().get_color().color.to_gfx_color(),
+            color: color.to_gfx_color(),
+            visible: state.layout_context.shared.caret_visible,
         }));
     }
 
@@ -1016,6 +1841,8 @@ impl FragmentDisplayListBuilding for Fragment {
             return
         }
 
+        state.add_custom_cursor(self.node, &*self.style);
+
         // Compute the fragment position relative to the parent stacking context. If the fragment
         // itself establishes a stacking context, then the origin of its position will be (0, 0)
         // for the purposes of this computation.
@@ -1031,6 +1858,13 @@ impl FragmentDisplayListBuilding for Fragment {
                stacking_relative_flow_origin,
                self);
 
+        // Snap areas and touch-action regions are needed even where nothing is painted, such as
+        // outside the display port.
+        if self.is_primary_fragment() {
+            self.build_scroll_snap_data_if_applicable(state, &stacking_relative_border_box);
+            self.build_touch_action_region_if_applicable(state, &stacking_relative_border_box);
+        }
+
         // webrender deals with all culling via aabb
         if !opts::get().use_webrender {
             if !stacking_relative_border_box.intersects(stacking_relative_display_port) {
@@ -1079,11 +1913,31 @@ impl FragmentDisplayListBuilding for Fragment {
                         state,
                         &*node.style,
                         &stacking_relative_border_box,
-                        &clip);
+                        &clip,
+                        node.flags.contains(FIRST_FRAGMENT_OF_ELEMENT),
+                        node.flags.contains(LAST_FRAGMENT_OF_ELEMENT));
                 }
             }
 
-            if !self.is_scanned_text_fragment() {
+            if let Some((widget, widget_state)) = self.widget() {
+                self.build_display_list_for_box_shadow_if_applicable(state,
+                                                                     &*self.style,
+                                                                     display_list_section,
+                                                                     &stacking_relative_border_box,
+                                                                     &clip);
+                self.build_display_list_for_widget(state,
+                                                   widget,
+                                                   widget_state,
+                                                   display_list_section,
+                                                   &stacking_relative_border_box,
+                                                   &clip);
+                self.build_display_list_for_outline_if_applicable(state,
+                                                                  &*self.style,
+                                                                  &stacking_relative_border_box,
+                                                                  &clip,
+                                                                  true,
+                                                                  true);
+            } else if !self.is_scanned_text_fragment() {
                 self.build_display_list_for_background_if_applicable(state,
                                                                      &*self.style,
                                                                      display_list_section,
@@ -1103,7 +1957,13 @@ impl FragmentDisplayListBuilding for Fragment {
                 self.build_display_list_for_outline_if_applicable(state,
                                                                   &*self.style,
                                                                   &stacking_relative_border_box,
-                                                                  &clip);
+                                                                  &clip,
+                                                                  true,
+                                                                  true);
+            }
+
+            if self.establishes_stacking_context() {
+                self.build_mask_if_applicable(state, &stacking_relative_border_box);
             }
         }
 
@@ -1132,6 +1992,10 @@ impl FragmentDisplayListBuilding for Fragment {
                                                     &stacking_relative_border_box,
                                                     &clip);
         }
+
+        self.build_display_items_for_highlight_if_necessary(state,
+                                                            &stacking_relative_border_box,
+                                                            &clip);
     }
 
     fn build_fragment_type_specific_display_items(&mut self,
@@ -1143,6 +2007,10 @@ impl FragmentDisplayListBuilding for Fragment {
             self.stacking_relative_content_box(stacking_relative_border_box);
 
         match self.specific {
+            SpecificFragmentInfo::ScannedText(_) if self.waits_for_web_font(state) => {
+                // The text is laid out with a fallback font, but invisible until the web font
+                // is loaded or its block period ends.
+            }
             SpecificFragmentInfo::ScannedText(ref text_fragment) => {
                 // Create items for shadows.
                 //
@@ -1232,9 +2100,15 @@ impl FragmentDisplayListBuilding for Fragment {
             SpecificFragmentInfo::Image(ref mut image_fragment) => {
                 // Place the image into the display list.
                 if let Some(ref image) = image_fragment.image {
+                    let intrinsic_size = Size2D::new(Au::from_px(image.width as i32),
+                                                     Au::from_px(image.height as i32));
+                    let bounds = self.object_rect(&stacking_relative_content_box,
+                                                  &intrinsic_size);
+                    let mut clip = clip.clone();
+                    clip.intersect_rect(&stacking_relative_content_box);
                     let base = state.create_base_display_item(
-                        &stacking_relative_content_box,
-                        clip,
+                        &bounds,
+                        &clip,
                         self.node,
                         self.style.get_cursor(Cursor::DefaultCursor),
                         DisplayListSection::Content);
@@ -1242,8 +2116,9 @@ impl FragmentDisplayListBuilding for Fragment {
                         base: base,
                         webrender_image: WebRenderImageInfo::from_image(image),
                         image_data: Some(Arc::new(image.bytes.clone())),
-                        stretch_size: stacking_relative_content_box.size,
+                        stretch_size: bounds.size,
                         image_rendering: self.style.get_inheritedbox().image_rendering.clone(),
+                        opacity: 1.0,
                     }));
                 }
             }
@@ -1268,9 +2143,15 @@ impl FragmentDisplayListBuilding for Fragment {
                         None => return,
                     };
 
+                    let intrinsic_size = Size2D::new(canvas_fragment_info.dom_width,
+                                                     canvas_fragment_info.dom_height);
+                    let bounds = self.object_rect(&stacking_relative_content_box,
+                                                  &intrinsic_size);
+                    let mut clip = clip.clone();
+                    clip.intersect_rect(&stacking_relative_content_box);
                     let base = state.create_base_display_item(
-                        &stacking_relative_content_box,
-                        clip,
+                        &bounds,
+                        &clip,
                         self.node,
                         self.style.get_cursor(Cursor::DefaultCursor),
                         DisplayListSection::Content);
@@ -1285,8 +2166,9 @@ impl FragmentDisplayListBuilding for Fragment {
                                     format: PixelFormat::RGBA8,
                                     key: canvas_data.image_key,
                                 },
-                                stretch_size: stacking_relative_content_box.size,
+                                stretch_size: bounds.size,
                                 image_rendering: image_rendering::T::Auto,
+                                opacity: 1.0,
                             })
                         }
                         CanvasData::WebGL(context_id) => {
@@ -1359,79 +2241,9 @@ impl FragmentDisplayListBuilding for Fragment {
             }
         };
 
-        let mut transform = Matrix4D::identity();
-        if let Some(ref operations) = self.style().get_effects().transform.0 {
-            let transform_origin = self.style().get_effects().transform_origin;
-            let transform_origin =
-                Point3D::new(model::specified(transform_origin.horizontal,
-                                              border_box.size.width).to_f32_px(),
-                             model::specified(transform_origin.vertical,
-                                              border_box.size.height).to_f32_px(),
-                             transform_origin.depth.to_f32_px());
-
-            let pre_transform = Matrix4D::create_translation(transform_origin.x,
-                                                             transform_origin.y,
-                                                             transform_origin.z);
-            let post_transform = Matrix4D::create_translation(-transform_origin.x,
-                                                              -transform_origin.y,
-                                                              -transform_origin.z);
-
-            for operation in operations {
-                let matrix = match *operation {
-                    transform::ComputedOperation::Rotate(ax, ay, az, theta) => {
-                        let theta = 2.0f32 * f32::consts::PI - theta.radians();
-                        Matrix4D::create_rotation(ax, ay, az, theta)
-                    }
-                    transform::ComputedOperation::Perspective(d) => {
-                        create_perspective_matrix(d)
-                    }
-                    transform::ComputedOperation::Scale(sx, sy, sz) => {
-                        Matrix4D::create_scale(sx, sy, sz)
-                    }
-                    transform::ComputedOperation::Translate(tx, ty, tz) => {
-                        let tx = model::specified(tx, border_box.size.width).to_f32_px();
-                        let ty = model::specified(ty, border_box.size.height).to_f32_px();
-                        let tz = tz.to_f32_px();
-                        Matrix4D::create_translation(tx, ty, tz)
-                    }
-                    transform::ComputedOperation::Matrix(m) => {
-                        m.to_gfx_matrix()
-                    }
-                    transform::ComputedOperation::Skew(theta_x, theta_y) => {
-                        Matrix4D::create_skew(theta_x.radians(), theta_y.radians())
-                    }
-                };
-
-                transform = transform.mul(&matrix);
-            }
-
-            transform = pre_transform.mul(&transform).mul(&post_transform);
-        }
+        let transform = transform_matrix(self.style(), &border_box.size);
 
-        let perspective = match self.style().get_effects().perspective {
-            LengthOrNone::Length(d) => {
-                let perspective_origin = self.style().get_effects().perspective_origin;
-                let perspective_origin =
-                    Point2D::new(model::specified(perspective_origin.horizontal,
-                                                  border_box.size.width).to_f32_px(),
-                                 model::specified(perspective_origin.vertical,
-                                                  border_box.size.height).to_f32_px());
-
-                let pre_transform = Matrix4D::create_translation(perspective_origin.x,
-                                                                 perspective_origin.y,
-                                                                 0.0);
-                let post_transform = Matrix4D::create_translation(-perspective_origin.x,
-                                                                  -perspective_origin.y,
-                                                                  0.0);
-
-                let perspective_matrix = create_perspective_matrix(d);
-
-                pre_transform.mul(&perspective_matrix).mul(&post_transform)
-            }
-            LengthOrNone::None => {
-                Matrix4D::identity()
-            }
-        };
+        let perspective = perspective_matrix(self.style(), &border_box.size);
 
         // Create the filter pipeline.
         let effects = self.style().get_effects();
@@ -1480,7 +2292,8 @@ impl FragmentDisplayListBuilding for Fragment {
 
     fn adjust_clipping_region_for_children(&self,
                                            current_clip: &mut ClippingRegion,
-                                           stacking_relative_border_box: &Rect<Au>) {
+                                           stacking_relative_border_box: &Rect<Au>,
+                                           scroll_overflow: Option<&Rect<Au>>) {
         // Don't clip if we're text.
         if self.is_scanned_text_fragment() {
             return
@@ -1489,22 +2302,22 @@ impl FragmentDisplayListBuilding for Fragment {
         // Account for style-specified `clip`.
         self.adjust_clip_for_style(current_clip, stacking_relative_border_box);
 
-        let overflow_x = self.style.get_box().overflow_x;
-        let overflow_y = self.style.get_box().overflow_y.0;
+        let (overflow_x, overflow_y) = self.style.clipped_overflow();
 
         if let (overflow_x::T::visible, overflow_x::T::visible) = (overflow_x, overflow_y) {
             return
         }
 
-        let overflow_clip_rect_owner;
+        let border = self.border_width().to_physical(self.style.writing_mode);
+        let padding_box =
+            Rect::new(Point2D::new(stacking_relative_border_box.origin.x + border.left,
+                                   stacking_relative_border_box.origin.y + border.top),
+                      Size2D::new(stacking_relative_border_box.size.width - border.horizontal(),
+                                  stacking_relative_border_box.size.height - border.vertical()));
         let overflow_clip_rect = match self.style.get_box()._servo_overflow_clip_box {
-            overflow_clip_box::T::padding_box => {
-                // FIXME(SimonSapin): should be the padding box, not border box.
-                stacking_relative_border_box
-            }
+            overflow_clip_box::T::padding_box => padding_box,
             overflow_clip_box::T::content_box => {
-                overflow_clip_rect_owner = self.stacking_relative_content_box(stacking_relative_border_box);
-                &overflow_clip_rect_owner
+                self.stacking_relative_content_box(stacking_relative_border_box)
             }
         };
 
@@ -1513,14 +2326,14 @@ impl FragmentDisplayListBuilding for Fragment {
         // FIXME(pcwalton): This may be more complex than it needs to be, since it seems to be
         // impossible with the computed value rules as they are to have `overflow-x: visible` with
         // `overflow-y: <scrolling>` or vice versa!
-        if let overflow_x::T::hidden = self.style.get_box().overflow_x {
+        if let overflow_x::T::hidden = overflow_x {
             let mut bounds = current_clip.bounding_rect();
             let max_x = cmp::min(bounds.max_x(), overflow_clip_rect.max_x());
             bounds.origin.x = cmp::max(bounds.origin.x, overflow_clip_rect.origin.x);
             bounds.size.width = max_x - bounds.origin.x;
             current_clip.intersect_rect(&bounds)
         }
-        if let overflow_x::T::hidden = self.style.get_box().overflow_y.0 {
+        if let overflow_x::T::hidden = overflow_y {
             let mut bounds = current_clip.bounding_rect();
             let max_y = cmp::min(bounds.max_y(), overflow_clip_rect.max_y());
             bounds.origin.y = cmp::max(bounds.origin.y, overflow_clip_rect.origin.y);
@@ -1528,10 +2341,23 @@ impl FragmentDisplayListBuilding for Fragment {
             current_clip.intersect_rect(&bounds)
         }
 
+        // The overflow is clipped to the curve of the padding edge, inside the rounded borders.
         let border_radii = build_border_radius(stacking_relative_border_box,
                                                self.style.get_border());
         if !border_radii.is_square() {
-            current_clip.intersect_with_rounded_rect(stacking_relative_border_box, &border_radii)
+            let border_widths = SideOffsets2D::new(border.top,
+                                                   border.right,
+                                                   border.bottom,
+                                                   border.left);
+            // Scrolled descendants are clipped in the coordinates of what they are scrolled
+            // over, so round the corners of all of it rather than of what is in view before
+            // scrolling, which would cut off what scrolls into view.
+            let rounded_rect = match scroll_overflow {
+                Some(scroll_overflow) => padding_box.union(scroll_overflow),
+                None => padding_box,
+            };
+            current_clip.intersect_with_rounded_rect(&rounded_rect,
+                                                     &border_radii.inner(&border_widths))
         }
     }
 
@@ -1588,44 +2414,50 @@ impl FragmentDisplayListBuilding for Fragment {
                                        ._servo_text_decorations_in_effect;
         if shadow_blur_radius.is_some() {
             // If we're painting a shadow, paint the decorations the same color as the shadow.
-            text_decorations.underline = text_decorations.underline.map(|_| text_color);
-            text_decorations.overline = text_decorations.overline.map(|_| text_color);
-            text_decorations.line_through = text_decorations.line_through.map(|_| text_color);
+            let shadow_color = |decoration: TextDecoration| {
+                TextDecoration {
+                    color: text_color,
+                    ..decoration
+                }
+            };
+            text_decorations.underline = text_decorations.underline.map(&shadow_color);
+            text_decorations.overline = text_decorations.overline.map(&shadow_color);
+            text_decorations.line_through = text_decorations.line_through.map(&shadow_color);
         }
 
         let stacking_relative_content_box =
             LogicalRect::from_physical(self.style.writing_mode,
                                        stacking_relative_content_box,
                                        container_size);
-        if let Some(ref underline_color) = text_decorations.underline {
+        if let Some(ref underline) = text_decorations.underline {
             let mut stacking_relative_box = stacking_relative_content_box;
             stacking_relative_box.start.b = stacking_relative_content_box.start.b +
                 metrics.ascent - metrics.underline_offset;
             stacking_relative_box.size.block = metrics.underline_size;
             self.build_display_list_for_text_decoration(state,
-                                                        underline_color,
+                                                        underline,
                                                         &stacking_relative_box,
                                                         clip,
                                                         shadow_blur_radius.unwrap_or(Au(0)));
         }
 
-        if let Some(ref overline_color) = text_decorations.overline {
+        if let Some(ref overline) = text_decorations.overline {
             let mut stacking_relative_box = stacking_relative_content_box;
             stacking_relative_box.size.block = metrics.underline_size;
             self.build_display_list_for_text_decoration(state,
-                                                        overline_color,
+                                                        overline,
                                                         &stacking_relative_box,
                                                         clip,
                                                         shadow_blur_radius.unwrap_or(Au(0)));
         }
 
-        if let Some(ref line_through_color) = text_decorations.line_through {
+        if let Some(ref line_through) = text_decorations.line_through {
             let mut stacking_relative_box = stacking_relative_content_box;
             stacking_relative_box.start.b = stacking_relative_box.start.b + metrics.ascent -
                 metrics.strikeout_offset;
             stacking_relative_box.size.block = metrics.strikeout_size;
             self.build_display_list_for_text_decoration(state,
-                                                        line_through_color,
+                                                        line_through,
                                                         &stacking_relative_box,
                                                         clip,
                                                         shadow_blur_radius.unwrap_or(Au(0)));
@@ -1634,10 +2466,94 @@ impl FragmentDisplayListBuilding for Fragment {
 
     fn build_display_list_for_text_decoration(&self,
                                               state: &mut DisplayListBuildState,
-                                              color: &RGBA,
+                                              decoration: &TextDecoration,
                                               stacking_relative_box: &LogicalRect<Au>,
                                               clip: &ClippingRegion,
                                               blur_radius: Au) {
+        let thickness = stacking_relative_box.size.block;
+        if thickness <= Au(0) {
+            return
+        }
+
+        let border_style = match decoration.style {
+            text_decoration_style::T::solid => {
+                return self.build_display_list_for_text_decoration_line(state,
+                                                                        &decoration.color,
+                                                                        stacking_relative_box,
+                                                                        clip,
+                                                                        blur_radius)
+            }
+            text_decoration_style::T::double => {
+                // The second line is as thick as the first, and one thickness away from it.
+                let mut second_box = *stacking_relative_box;
+                second_box.start.b = second_box.start.b + thickness * 2;
+                for line_box in &[*stacking_relative_box, second_box] {
+                    self.build_display_list_for_text_decoration_line(state,
+                                                                     &decoration.color,
+                                                                     line_box,
+                                                                     clip,
+                                                                     blur_radius)
+                }
+                return
+            }
+            text_decoration_style::T::wavy => {
+                // Wavy lines are approximated by pieces as long as the line is thick, following
+                // a sine wave with that amplitude.
+                let wavelength = (thickness * 8).to_f32_px();
+                let mut inline_offset = Au(0);
+                while inline_offset < stacking_relative_box.size.inline {
+                    let phase = inline_offset.to_f32_px() / wavelength * 2.0 * f32::consts::PI;
+                    let mut piece_box = *stacking_relative_box;
+                    piece_box.start.i = piece_box.start.i + inline_offset;
+                    piece_box.start.b = piece_box.start.b + thickness.scale_by(phase.sin());
+                    piece_box.size.inline = cmp::min(thickness,
+                                                     piece_box.size.inline - inline_offset);
+                    self.build_display_list_for_text_decoration_line(state,
+                                                                     &decoration.color,
+                                                                     &piece_box,
+                                                                     clip,
+                                                                     blur_radius);
+                    inline_offset = inline_offset + thickness;
+                }
+                return
+            }
+            text_decoration_style::T::dotted => border_style::T::dotted,
+            text_decoration_style::T::dashed => border_style::T::dashed,
+        };
+
+        // Dotted and dashed lines are painted as the block-start side of a border, untouched by
+        // `text-shadow` blur.
+        //
+        // FIXME(pcwalton, #2795): Get the real container size.
+        let container_size = Size2D::zero();
+        let bounds = stacking_relative_box.to_physical(self.style.writing_mode, container_size);
+        let mut widths = SideOffsets2D::new_all_same(Au(0));
+        let mut styles = SideOffsets2D::new_all_same(border_style::T::none);
+        set_border_side(&mut widths,
+                        &mut styles,
+                        self.style.writing_mode.block_start_physical_side(),
+                        thickness,
+                        border_style);
+        let base = state.create_base_display_item(&bounds,
+                                                  clip,
+                                                  self.node,
+                                                  self.style.get_cursor(Cursor::DefaultCursor),
+                                                  DisplayListSection::Content);
+        state.add_display_item(DisplayItem::BorderClass(box BorderDisplayItem {
+            base: base,
+            border_widths: widths,
+            color: SideOffsets2D::new_all_same(decoration.color.to_gfx_color()),
+            style: styles,
+            radius: Default::default(),
+        }));
+    }
+
+    fn build_display_list_for_text_decoration_line(&self,
+                                                   state: &mut DisplayListBuildState,
+                                                   color: &RGBA,
+                                                   stacking_relative_box: &LogicalRect<Au>,
+                                                   clip: &ClippingRegion,
+                                                   blur_radius: Au) {
         // Perhaps surprisingly, text decorations are box shadows. This is because they may need
         // to have blur in the case of `text-shadow`, and this doesn't hurt performance because box
         // shadows are optimized into essentially solid colors if there is no need for the blur.
@@ -1673,6 +2589,12 @@ pub trait BlockFlowDisplayListBuilding {
     fn build_display_list_for_block(&mut self,
                                     state: &mut DisplayListBuildState,
                                     border_painting_mode: BorderPaintingMode);
+
+    /// Adds the display items with which the theme paints the scrollbars of this scroll
+    /// container, laid over its padding box.
+    fn build_display_list_for_scrollbars(&self,
+                                         state: &mut DisplayListBuildState,
+                                         clip: &ClippingRegion);
 }
 
 impl BlockFlowDisplayListBuilding for BlockFlow {
@@ -1806,7 +2728,115 @@ impl BlockFlowDisplayListBuilding for BlockFlow {
                                 clip,
                                 &self.base.stacking_relative_position_of_display_port);
 
+        if self.has_scrolling_overflow() {
+            self.build_display_list_for_scrollbars(state, clip);
+        }
+
         self.base.build_display_items_for_debugging_tint(state, self.fragment.node);
+        self.base.build_display_items_for_invalidation_overlays(state, self.fragment.node);
+        if self.fragment.flags.contains(HAS_LAYER) || self.has_scrolling_overflow() {
+            self.base.build_display_items_for_layer_border(state, self.fragment.node);
+        }
+    }
+
+    fn build_display_list_for_scrollbars(&self,
+                                         state: &mut DisplayListBuildState,
+                                         clip: &ClippingRegion) {
+        let position_info = &self.base.early_absolute_position_info;
+        let containing_block_size = &position_info.relative_containing_block_size;
+        let containing_block_mode = position_info.relative_containing_block_mode;
+        let border_box =
+            self.fragment.stacking_relative_border_box(&self.base.stacking_relative_position,
+                                                       containing_block_size,
+                                                       containing_block_mode,
+                                                       CoordinateSystem::Own);
+        let writing_mode = self.fragment.style.writing_mode;
+        let border = self.fragment.border_width().to_physical(writing_mode);
+        let padding_box = Rect::new(
+            Point2D::new(border_box.origin.x + border.left, border_box.origin.y + border.top),
+            Size2D::new(border_box.size.width - (border.left + border.right),
+                        border_box.size.height - (border.top + border.bottom)));
+
+        // The scrollable overflow is in the coordinates of the flow, so measure the contents from
+        // the border box in those.
+        let container_size = containing_block_size.to_physical(containing_block_mode);
+        let flow_border_box = self.fragment.border_box.to_physical(writing_mode, container_size);
+        let scroll_overflow = &self.base.overflow.scroll;
+        let contents_size =
+            Size2D::new(cmp::max(scroll_overflow.max_x() - flow_border_box.origin.x - border.left,
+                                 padding_box.size.width),
+                        cmp::max(scroll_overflow.max_y() - flow_border_box.origin.y - border.top,
+                                 padding_box.size.height));
+
+        let style = self.fragment.style();
+        let shows_scrollbar = |overflow: overflow_x::T, visible: Au, contents: Au| {
+            match overflow {
+                overflow_x::T::scroll => true,
+                overflow_x::T::auto => contents > visible,
+                overflow_x::T::visible | overflow_x::T::hidden => false,
+            }
+        };
+        let horizontal = shows_scrollbar(style.get_box().overflow_x,
+                                         padding_box.size.width,
+                                         contents_size.width);
+        let vertical = shows_scrollbar(style.get_box().overflow_y.0,
+                                       padding_box.size.height,
+                                       contents_size.height);
+
+        // FIXME: Scrolling moves the contents in the compositor, without telling layout, so the
+        // thumbs stay where they are for the scroll position at the start.
+        let theme = theme::platform_theme();
+        let thickness = theme.scrollbar_thickness();
+        let mut items = vec![];
+        if horizontal {
+            let track_length = if vertical {
+                padding_box.size.width - thickness
+            } else {
+                padding_box.size.width
+            };
+            let track = Rect::new(Point2D::new(padding_box.origin.x,
+                                               padding_box.max_y() - thickness),
+                                  Size2D::new(track_length, thickness));
+            let thumb_length = track_length.scale_by(padding_box.size.width.to_f32_px() /
+                                                     contents_size.width.to_f32_px());
+            let thumb = Rect::new(track.origin, Size2D::new(thumb_length, thickness));
+            let base = state.create_base_display_item(&track,
+                                                      clip,
+                                                      self.fragment.node,
+                                                      Some(Cursor::DefaultCursor),
+                                                      DisplayListSection::Outlines);
+            theme.build_display_items(Widget::HorizontalScrollbar,
+                                      WidgetState::default(),
+                                      Some(thumb),
+                                      &base,
+                                      &mut items);
+        }
+        if vertical {
+            let track_length = if horizontal {
+                padding_box.size.height - thickness
+            } else {
+                padding_box.size.height
+            };
+            let track = Rect::new(Point2D::new(padding_box.max_x() - thickness,
+                                               padding_box.origin.y),
+                                  Size2D::new(thickness, track_length));
+            let thumb_length = track_length.scale_by(padding_box.size.height.to_f32_px() /
+                                                     contents_size.height.to_f32_px());
+            let thumb = Rect::new(track.origin, Size2D::new(thickness, thumb_length));
+            let base = state.create_base_display_item(&track,
+                                                      clip,
+                                                      self.fragment.node,
+                                                      Some(Cursor::DefaultCursor),
+                                                      DisplayListSection::Outlines);
+            theme.build_display_items(Widget::VerticalScrollbar,
+                                      WidgetState::default(),
+                                      Some(thumb),
+                                      &base,
+                                      &mut items);
+        }
+        for item in items {
+            state.add_display_item(item);
+        }
     }
 }
 
@@ -1901,6 +2931,9 @@ impl InlineFlowDisplayListBuilding for InlineFlow {
         if !self.fragments.fragments.is_empty() {
             self.base.build_display_items_for_debugging_tint(state,
                                                              self.fragments.fragments[0].node);
+            self.base.build_display_items_for_invalidation_overlays(
+                state,
+                self.fragments.fragments[0].node);
         }
     }
 }
@@ -1951,6 +2984,18 @@ trait BaseFlowDisplayListBuilding {
     fn build_display_items_for_debugging_tint(&self,
                                               state: &mut DisplayListBuildState,
                                               node: OpaqueNode);
+
+    /// Adds the paint flashing tint and reflow outline for this flow, if those overlays are
+    /// enabled and the flow was damaged by the most recent restyle.
+    fn build_display_items_for_invalidation_overlays(&self,
+                                                     state: &mut DisplayListBuildState,
+                                                     node: OpaqueNode);
+
+    /// Adds the layer border for this flow, if that overlay is enabled. The caller checks that
+    /// the flow is painted into a layer of its own.
+    fn build_display_items_for_layer_border(&self,
+                                            state: &mut DisplayListBuildState,
+                                            node: OpaqueNode);
 }
 
 impl BaseFlowDisplayListBuilding for BaseFlow {
@@ -1982,6 +3027,102 @@ impl BaseFlowDisplayListBuilding for BaseFlow {
             radius: BorderRadii::all_same(Au(0)),
         }));
     }
+
+    fn build_display_items_for_invalidation_overlays(&self,
+                                                     state: &mut DisplayListBuildState,
+                                                     node: OpaqueNode) {
+        let overlays = state.layout_context.shared.invalidation_overlays;
+        if !overlays.any() {
+            return
+        }
+
+        let stacking_context_relative_bounds =
+            Rect::new(self.stacking_relative_position,
+                      self.position.size.to_physical(self.writing_mode));
+
+        if overlays.paint_flashing && self.restyle_damage.contains(REPAINT) {
+            // Cycle through the tint colors from one layout to the next so that consecutive
+            // repaints of the same area are distinguishable.
+            let generation = state.layout_context.shared.style_context.generation as usize;
+            let color = THREAD_TINT_COLORS[generation % THREAD_TINT_COLORS.len()];
+            let base = state.create_base_display_item(&stacking_context_relative_bounds,
+                                                      &self.clip,
+                                                      node,
+                                                      None,
+                                                      DisplayListSection::Outlines);
+            state.add_display_item(DisplayItem::SolidColorClass(box SolidColorDisplayItem {
+                base: base,
+                color: color,
+            }));
+        }
+
+        if overlays.reflow_outlines && self.flags.contains(WAS_REFLOWED) {
+            let base = state.create_base_display_item(&stacking_context_relative_bounds,
+                                                      &self.clip,
+                                                      node,
+                                                      None,
+                                                      DisplayListSection::Outlines);
+            state.add_display_item(DisplayItem::BorderClass(box BorderDisplayItem {
+                base: base,
+                border_widths: SideOffsets2D::new_all_same(Au::from_px(1)),
+                color: SideOffsets2D::new_all_same(color::rgb(255, 0, 0)),
+                style: SideOffsets2D::new_all_same(border_style::T::dashed),
+                radius: BorderRadii::all_same(Au(0)),
+            }));
+        }
+    }
+
+    fn build_display_items_for_layer_border(&self,
+                                            state: &mut DisplayListBuildState,
+                                            node: OpaqueNode) {
+        if !state.layout_context.shared.invalidation_overlays.layer_borders {
+            return
+        }
+
+        let stacking_context_relative_bounds =
+            Rect::new(self.stacking_relative_position,
+                      self.position.size.to_physical(self.writing_mode));
+        let base = state.create_base_display_item(&stacking_context_relative_bounds,
+                                                  &self.clip,
+                                                  node,
+                                                  None,
+                                                  DisplayListSection::Outlines);
+        state.add_display_item(DisplayItem::BorderClass(box BorderDisplayItem {
+            base: base,
+            border_widths: SideOffsets2D::new_all_same(Au::from_px(2)),
+            color: SideOffsets2D::new_all_same(color::rgb(255, 165, 0)),
+            style: SideOffsets2D::new_all_same(border_style::T::solid),
+            radius: BorderRadii::all_same(Au(0)),
+        }));
+    }
+}
+
+/// Adds one area of the devtools highlight overlay: the whole of `bounds` if `widths` is `None`,
+/// or else a ring of the given widths along its inside edges.
+fn add_highlight_area(state: &mut DisplayListBuildState,
+                      bounds: &Rect<Au>,
+                      widths: Option<SideOffsets2D<Au>>,
+                      color: Color,
+                      node: OpaqueNode,
+                      clip: &ClippingRegion) {
+    let base = state.create_base_display_item(bounds,
+                                              clip,
+                                              node,
+                                              None,
+                                              DisplayListSection::Outlines);
+    state.add_display_item(match widths {
+        Some(widths) => DisplayItem::BorderClass(box BorderDisplayItem {
+            base: base,
+            border_widths: widths,
+            color: SideOffsets2D::new_all_same(color),
+            style: SideOffsets2D::new_all_same(border_style::T::solid),
+            radius: BorderRadii::all_same(Au(0)),
+        }),
+        None => DisplayItem::SolidColorClass(box SolidColorDisplayItem {
+            base: base,
+            color: color,
+        }),
+    });
 }
 
 trait ServoComputedValuesCursorUtility {
@@ -1994,10 +3135,11 @@ impl ServoComputedValuesCursorUtility for ServoComputedValues {
     /// text display items it may be `TextCursor` or `VerticalTextCursor`.
     #[inline]
     fn get_cursor(&self, default_cursor: Cursor) -> Option<Cursor> {
-        match (self.get_pointing().pointer_events, self.get_pointing().cursor) {
+        match (self.get_pointing().pointer_events, self.get_pointing().cursor.keyword) {
             (pointer_events::T::none, _) => None,
-            (pointer_events::T::auto, cursor::T::AutoCursor) => Some(default_cursor),
-            (pointer_events::T::auto, cursor::T::SpecifiedCursor(cursor)) => Some(cursor),
+            // There is no SVG content yet, and the other values act as `auto` outside of it.
+            (_, cursor::Keyword::AutoCursor) => Some(default_cursor),
+            (_, cursor::Keyword::SpecifiedCursor(cursor)) => Some(cursor),
         }
     }
 }
@@ -2011,6 +3153,69 @@ struct StopRun {
     stop_count: usize,
 }
 
+/// One of the plain images, URLs or gradients, that an image value is painted with.
+struct ImageLayer<'a> {
+    image: &'a computed::Image,
+    /// The number of image pixels per `px`.
+    resolution: f32,
+    opacity: f32,
+}
+
+/// Returns the images to paint for `image` on a device with `device_pixel_ratio` device pixels
+/// per `px`, bottommost first. An `image-set()` is painted with the candidate of the lowest
+/// resolution that is at least that of the device, or of the highest one if there is none. A
+/// `cross-fade()` is painted as its final image, with the image it fades in on top of it.
+fn image_layers(image: &computed::Image, device_pixel_ratio: f32) -> Vec<ImageLayer> {
+    fn add_layers<'a>(image: &'a computed::Image,
+                      device_pixel_ratio: f32,
+                      resolution: f32,
+                      opacity: f32,
+                      layers: &mut Vec<ImageLayer<'a>>) {
+        match *image {
+            computed::Image::Url(_) | computed::Image::LinearGradient(_) => {
+                layers.push(ImageLayer {
+                    image: image,
+                    resolution: resolution,
+                    opacity: opacity,
+                })
+            }
+            computed::Image::ImageSet(ref options) => {
+                let is_better = |option: &computed::ImageSetOption,
+                                 best: &computed::ImageSetOption| {
+                    let enough = option.resolution >= device_pixel_ratio;
+                    if enough != (best.resolution >= device_pixel_ratio) {
+                        enough
+                    } else if enough {
+                        option.resolution < best.resolution
+                    } else {
+                        option.resolution > best.resolution
+                    }
+                };
+                let mut best: Option<&computed::ImageSetOption> = None;
+                for option in options {
+                    if best.map_or(true, |best| is_better(option, best)) {
+                        best = Some(option)
+                    }
+                }
+                if let Some(option) = best {
+                    add_layers(&option.image, device_pixel_ratio, option.resolution, opacity,
+                               layers)
+                }
+            }
+            computed::Image::CrossFade(ref cross_fade) => {
+                add_layers(&cross_fade.final_image, device_pixel_ratio, resolution, opacity,
+                           layers);
+                add_layers(&cross_fade.image, device_pixel_ratio, resolution,
+                           opacity * cross_fade.percentage, layers);
+            }
+        }
+    }
+
+    let mut layers = vec![];
+    add_layers(image, device_pixel_ratio, 1.0, 1.0, &mut layers);
+    layers
+}
+
 fn position_to_offset(position: LengthOrPercentage, Au(total_length): Au) -> f32 {
     match position {
         LengthOrPercentage::Length(Au(length)) => {
@@ -2022,6 +3227,47 @@ fn position_to_offset(position: LengthOrPercentage, Au(total_length): Au) -> f32
     }
 }
 
+/// Returns the offsets and lengths of the tiles that cover the given span of a border image area
+/// according to `border-image-repeat`, given the length of the tiles once scaled.
+fn border_image_tiles(repeat: border_image_repeat::RepeatKeyword,
+                      start: Au,
+                      length: Au,
+                      tile_length: Au)
+                      -> Vec<(Au, Au)> {
+    if tile_length <= Au(0) {
+        return vec![(start, length)]
+    }
+    match repeat {
+        border_image_repeat::RepeatKeyword::Stretch => vec![(start, length)],
+        border_image_repeat::RepeatKeyword::Repeat => {
+            // The tiles are centered, and the ones at both ends get clipped.
+            let first_offset = (length - tile_length) / 2;
+            let tiles_before = (first_offset.0 + tile_length.0 - 1) / tile_length.0;
+            let first = start + first_offset - tile_length * tiles_before;
+            let count = (start + length - first + tile_length - Au(1)).0 / tile_length.0;
+            (0..count).map(|index| (first + tile_length * index, tile_length)).collect()
+        }
+        border_image_repeat::RepeatKeyword::Round => {
+            // The tiles are scaled so that a whole number of them fits.
+            let count = cmp::max(1,
+                                 (length.to_f32_px() / tile_length.to_f32_px()).round() as i32);
+            let tile_length = length / count;
+            (0..count).map(|index| (start + tile_length * index, tile_length)).collect()
+        }
+        border_image_repeat::RepeatKeyword::Space => {
+            // As many whole tiles as fit, with the remaining space spread evenly around them.
+            let count = length.0 / tile_length.0;
+            if count == 0 {
+                return vec![]
+            }
+            let gap = (length - tile_length * count) / (count + 1);
+            (0..count).map(|index| {
+                (start + gap + (tile_length + gap) * index, tile_length)
+            }).collect()
+        }
+    }
+}
+
 /// Adjusts `content_rect` as necessary for the given spread, and blur so that the resulting
 /// bounding rect contains all of a shadow's ink.
 fn shadow_bounds(content_rect: &Rect<Au>, blur_radius: Au, spread_radius: Au) -> Rect<Au> {
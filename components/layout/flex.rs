@@ -52,7 +52,9 @@ impl AxisSize {
             },
             LengthOrPercentageOrAuto::Calc(calc) => {
                 match content_size {
-                    Some(size) => AxisSize::Definite(size.scale_by(calc.percentage())),
+                    Some(size) => {
+                        AxisSize::Definite(size.scale_by(calc.percentage()) + calc.length())
+                    }
                     None => AxisSize::Infinite
                 }
             },
@@ -204,6 +206,8 @@ impl FlexFlow {
         };
         for kid in &mut self.items {
             {
+                // Items are only stretched along the cross axis, which is the inline axis here.
+                flow_ref::deref_mut(&mut kid.flow).as_mut_block().set_stretched_flex_item(false);
                 let kid_base = flow::mut_base(flow_ref::deref_mut(&mut kid.flow));
                 kid_base.block_container_explicit_block_size = container_block_size;
                 if kid_base.flags.contains(INLINE_POSITION_IS_STATIC) {
@@ -228,7 +232,7 @@ impl FlexFlow {
     // Currently, this is the core of InlineFlow::propagate_assigned_inline_size_to_children() with
     // fragment logic stripped out.
     fn inline_mode_assign_inline_sizes(&mut self,
-                                       _layout_context: &LayoutContext,
+                                       layout_context: &LayoutContext,
                                        inline_start_content_edge: Au,
                                        _inline_end_content_edge: Au,
                                        content_inline_size: Au) {
@@ -254,13 +258,18 @@ impl FlexFlow {
         let container_mode = self.block_flow.base.block_container_writing_mode;
         self.block_flow.base.position.size.inline = inline_size;
 
-        let block_container_explicit_block_size = self.block_flow.base.block_container_explicit_block_size;
+        // The items are in a single line that is as tall as the container, so their
+        // percentages refer to its block size, if it is definite.
+        let block_container_explicit_block_size =
+            self.block_flow.explicit_content_block_size(layout_context);
         let mut inline_child_start = if !self.is_reverse {
             inline_start_content_edge
         } else {
             self.block_flow.fragment.border_box.size.inline
         };
         for kid in &mut self.items {
+            // `align-self` is not supported yet, so all the items are stretched.
+            flow_ref::deref_mut(&mut kid.flow).as_mut_block().set_stretched_flex_item(true);
             let base = flow::mut_base(flow_ref::deref_mut(&mut kid.flow));
 
             base.block_container_inline_size = even_content_inline_size;
@@ -308,11 +317,11 @@ impl FlexFlow {
             kid.assign_block_size_for_inorder_child_if_necessary(layout_context, thread_id);
 
             {
+                let container_block_size = flow::base(kid).block_container_explicit_block_size;
                 let child_fragment = &mut kid.as_mut_block().fragment;
-                // TODO: Percentage block-size
                 let child_specified_block_size =
-                    MaybeAuto::from_style(child_fragment.style().content_block_size(),
-                                          Au(0)).specified_or_zero();
+                    MaybeAuto::from_option_style(child_fragment.style().content_block_size(),
+                                                 container_block_size).specified_or_zero();
                 max_block_size =
                     max(max_block_size,
                         child_specified_block_size +
@@ -324,13 +333,10 @@ impl FlexFlow {
         }
 
         let mut block_size = max_block_size;
-        // TODO: Percentage block-size
 
-        block_size = match MaybeAuto::from_style(self.block_flow
-                                                     .fragment
-                                                     .style()
-                                                     .content_block_size(),
-                                                 Au(0)) {
+        block_size = match MaybeAuto::from_option_style(
+                self.block_flow.fragment.style().content_block_size(),
+                self.block_flow.base.block_container_explicit_block_size) {
             MaybeAuto::Auto => block_size,
             MaybeAuto::Specified(value) => max(value, block_size),
         };
@@ -4,14 +4,21 @@
 
 use app_units::Au;
 use block::FormattingContextType;
+use euclid::{Point2D, Rect, Size2D};
 use flow::{self, CLEARS_LEFT, CLEARS_RIGHT, Flow, ImmutableFlowUtils};
+use fragment::Fragment;
+use model;
 use persistent_list::PersistentList;
 use std::cmp::{max, min};
 use std::fmt;
 use std::i32;
+use std::sync::Arc;
 use style::computed_values::float;
-use style::logical_geometry::{LogicalRect, LogicalSize, WritingMode};
-use style::values::computed::LengthOrPercentageOrAuto;
+use style::logical_geometry::{LogicalPoint, LogicalRect, LogicalSize, WritingMode};
+use style::properties::ComputedValues;
+use style::properties::longhands::shape_outside::computed_value::{BasicShape, ShapeBox};
+use style::properties::longhands::shape_outside::computed_value::{ShapeOutside, ShapeRadius};
+use style::values::computed::{LengthOrPercentage, LengthOrPercentageOrAuto};
 
 /// The kind of float: left or right.
 #[derive(Clone, RustcEncodable, Debug, Copy)]
@@ -39,20 +46,259 @@ pub enum ClearType {
 }
 
 /// Information about a single float.
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 struct Float {
     /// The boundaries of this float.
     bounds: LogicalRect<Au>,
     /// The kind of float: left or right.
     kind: FloatKind,
+    /// The float area that `shape-outside` gives this float, if any.
+    shape: Option<Arc<FloatShape>>,
 }
 
 impl fmt::Debug for Float {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "bounds={:?} kind={:?}", self.bounds, self.kind)
+        write!(f, "bounds={:?} kind={:?} shape={:?}", self.bounds, self.kind, self.shape)
+    }
+}
+
+impl Float {
+    /// Returns the part of this float that lines between the given block positions avoid, or
+    /// `None` if they can ignore it. That is the margin box, unless lines wrap around the float
+    /// area that `shape-outside` gives the float, in which case it is the part of the margin box
+    /// in that range that is as wide as the float area there.
+    fn area_avoided_by_lines(&self,
+                             block_start: Au,
+                             block_size: Au,
+                             around_shapes: bool,
+                             writing_mode: WritingMode)
+                             -> Option<LogicalRect<Au>> {
+        let shape = match self.shape {
+            Some(ref shape) if around_shapes => shape,
+            _ => return Some(self.bounds),
+        };
+        let start = self.bounds.start;
+        let block_end = block_start + shape_band_size(block_size);
+        let band_start = max(block_start, start.b);
+        let band_end = min(block_end, start.b + self.bounds.size.block);
+        if band_start >= band_end {
+            return None
+        }
+        let (inline_start, inline_end) = match shape.inline_extent(block_start - start.b,
+                                                                   block_end - start.b) {
+            Some(extent) => extent,
+            None => return None,
+        };
+        // The float area is clipped to the margin box.
+        let inline_start = min(max(inline_start, Au(0)), self.bounds.size.inline);
+        let inline_end = min(max(inline_end, inline_start), self.bounds.size.inline);
+        Some(LogicalRect::new(writing_mode,
+                              start.i + inline_start,
+                              band_start,
+                              inline_end - inline_start,
+                              band_end - band_start))
     }
 }
 
+/// The block size of the bands that lines wrapping around shapes look for room in, which must
+/// not be empty so that lines that do not fit can move down.
+fn shape_band_size(block_size: Au) -> Au {
+    max(block_size, Au::from_px(1))
+}
+
+/// The float area that `shape-outside` gives a float, which line boxes wrap around instead of
+/// the margin box of the float. Other floats and block formatting contexts still avoid the margin
+/// box. The coordinates are logical ones in the writing mode of the `Floats`, relative to the
+/// block-start inline-start corner of the margin box of the float.
+///
+/// https://drafts.csswg.org/css-shapes/#relation-to-box-model-and-float-behavior
+#[derive(Clone, Debug)]
+pub enum FloatShape {
+    Rect(LogicalRect<Au>),
+    /// An ellipse with the given center and inline and block radii.
+    Ellipse(LogicalPoint<Au>, Au, Au),
+    Polygon(Vec<LogicalPoint<Au>>),
+}
+
+impl FloatShape {
+    /// Computes the float area of `fragment`, whose margin box has the given size in the given
+    /// writing mode of the `Floats`, or returns `None` if it has no `shape-outside`.
+    pub fn from_fragment(fragment: &Fragment,
+                         margin_box_size: LogicalSize<Au>,
+                         writing_mode: WritingMode)
+                         -> Option<FloatShape> {
+        let (shape, reference_box) = match fragment.style.get_box().shape_outside {
+            ShapeOutside::None => return None,
+            ShapeOutside::Box(reference_box) => (None, reference_box),
+            ShapeOutside::Shape(ref shape, reference_box) => (Some(shape), reference_box),
+        };
+
+        // Find the reference box, in physical coordinates relative to the margin box.
+        let container_size = margin_box_size.to_physical(writing_mode);
+        let style_writing_mode = fragment.style.writing_mode;
+        let margin = fragment.margin.to_physical(style_writing_mode);
+        let border = fragment.style.logical_border_width().to_physical(style_writing_mode);
+        let border_padding = fragment.border_padding.to_physical(style_writing_mode);
+        let (top, right, bottom, left) = match reference_box {
+            ShapeBox::MarginBox => (Au(0), Au(0), Au(0), Au(0)),
+            ShapeBox::BorderBox => (margin.top, margin.right, margin.bottom, margin.left),
+            ShapeBox::PaddingBox => (margin.top + border.top,
+                                     margin.right + border.right,
+                                     margin.bottom + border.bottom,
+                                     margin.left + border.left),
+            ShapeBox::ContentBox => (margin.top + border_padding.top,
+                                     margin.right + border_padding.right,
+                                     margin.bottom + border_padding.bottom,
+                                     margin.left + border_padding.left),
+        };
+        let (top, right, bottom, left) = (max(top, Au(0)), max(right, Au(0)),
+                                          max(bottom, Au(0)), max(left, Au(0)));
+        let reference_box = Rect::new(
+            Point2D::new(left, top),
+            Size2D::new(max(container_size.width - left - right, Au(0)),
+                        max(container_size.height - top - bottom, Au(0))));
+        let (x, y) = (reference_box.origin.x, reference_box.origin.y);
+        let (width, height) = (reference_box.size.width, reference_box.size.height);
+        let logical_point = |x: Au, y: Au| {
+            LogicalPoint::from_physical(writing_mode, Point2D::new(x, y), container_size)
+        };
+
+        let shape = match shape {
+            Some(shape) => shape,
+            None => {
+                return Some(FloatShape::Rect(LogicalRect::from_physical(writing_mode,
+                                                                        reference_box,
+                                                                        container_size)))
+            }
+        };
+        Some(match *shape {
+            BasicShape::Inset(top, right, bottom, left) => {
+                let (top, bottom) = (model::specified(top, height),
+                                     model::specified(bottom, height));
+                let (right, left) = (model::specified(right, width),
+                                     model::specified(left, width));
+                let rect = Rect::new(Point2D::new(x + left, y + top),
+                                     Size2D::new(max(width - left - right, Au(0)),
+                                                 max(height - top - bottom, Au(0))));
+                FloatShape::Rect(LogicalRect::from_physical(writing_mode, rect, container_size))
+            }
+            BasicShape::Circle(radius, ref position) => {
+                let center_x = x + model::specified(position.horizontal, width);
+                let center_y = y + model::specified(position.vertical, height);
+                let sides = [distance(center_x, x), distance(center_x, x + width),
+                             distance(center_y, y), distance(center_y, y + height)];
+                let radius = match radius {
+                    ShapeRadius::Length(length) => {
+                        // Percentages refer to the diagonal of the box, divided by the square
+                        // root of two.
+                        let diagonal = (width.to_f32_px().powi(2) + height.to_f32_px().powi(2))
+                                           .sqrt() / 2f32.sqrt();
+                        model::specified(length, Au::from_f32_px(diagonal))
+                    }
+                    ShapeRadius::ClosestSide => *sides.iter().min().unwrap(),
+                    ShapeRadius::FarthestSide => *sides.iter().max().unwrap(),
+                };
+                FloatShape::Ellipse(logical_point(center_x, center_y), radius, radius)
+            }
+            BasicShape::Ellipse(radius_x, radius_y, ref position) => {
+                let center_x = x + model::specified(position.horizontal, width);
+                let center_y = y + model::specified(position.vertical, height);
+                let radius = |radius: ShapeRadius<LengthOrPercentage>,
+                              start: Au,
+                              center: Au,
+                              size: Au| {
+                    match radius {
+                        ShapeRadius::Length(length) => model::specified(length, size),
+                        ShapeRadius::ClosestSide => {
+                            min(distance(center, start), distance(center, start + size))
+                        }
+                        ShapeRadius::FarthestSide => {
+                            max(distance(center, start), distance(center, start + size))
+                        }
+                    }
+                };
+                let radius_x = radius(radius_x, x, center_x, width);
+                let radius_y = radius(radius_y, y, center_y, height);
+                let (radius_inline, radius_block) = if writing_mode.is_vertical() {
+                    (radius_y, radius_x)
+                } else {
+                    (radius_x, radius_y)
+                };
+                FloatShape::Ellipse(logical_point(center_x, center_y), radius_inline, radius_block)
+            }
+            BasicShape::Polygon(_, ref points) => {
+                FloatShape::Polygon(points.iter().map(|&(point_x, point_y)| {
+                    logical_point(x + model::specified(point_x, width),
+                                  y + model::specified(point_y, height))
+                }).collect())
+            }
+        })
+    }
+
+    /// Returns the inline-start and inline-end edges of the parts of this shape that lie between
+    /// the given block positions, or `None` if there are none.
+    fn inline_extent(&self, block_start: Au, block_end: Au) -> Option<(Au, Au)> {
+        match *self {
+            FloatShape::Rect(ref rect) => {
+                if rect.start.b < block_end && rect.start.b + rect.size.block > block_start {
+                    Some((rect.start.i, rect.start.i + rect.size.inline))
+                } else {
+                    None
+                }
+            }
+            FloatShape::Ellipse(ref center, radius_inline, radius_block) => {
+                if radius_block <= Au(0) || center.b - radius_block >= block_end ||
+                        center.b + radius_block <= block_start {
+                    return None
+                }
+                // The ellipse is widest at the block position that is closest to its center.
+                let offset = if block_start <= center.b && center.b <= block_end {
+                    Au(0)
+                } else {
+                    min(distance(block_start, center.b), distance(block_end, center.b))
+                };
+                let ratio = offset.to_f32_px() / radius_block.to_f32_px();
+                let half_size = radius_inline.scale_by((1.0 - ratio * ratio).max(0.0).sqrt());
+                Some((center.i - half_size, center.i + half_size))
+            }
+            FloatShape::Polygon(ref points) => {
+                // The polygon is widest at one of its vertices or where one of its edges crosses
+                // the start or the end of the range.
+                let mut extent = None;
+                for (index, start) in points.iter().enumerate() {
+                    let end = &points[(index + 1) % points.len()];
+                    let edge_start = max(min(start.b, end.b), block_start);
+                    let edge_end = min(max(start.b, end.b), block_end);
+                    if edge_start > edge_end {
+                        continue
+                    }
+                    if start.b == end.b {
+                        extend_extent(&mut extent, start.i);
+                        extend_extent(&mut extent, end.i);
+                        continue
+                    }
+                    for &b in &[edge_start, edge_end] {
+                        let ratio = (b - start.b).to_f32_px() / (end.b - start.b).to_f32_px();
+                        extend_extent(&mut extent, start.i + (end.i - start.i).scale_by(ratio));
+                    }
+                }
+                extent
+            }
+        }
+    }
+}
+
+fn distance(a: Au, b: Au) -> Au {
+    max(a - b, b - a)
+}
+
+fn extend_extent(extent: &mut Option<(Au, Au)>, inline_position: Au) {
+    *extent = Some(match *extent {
+        None => (inline_position, inline_position),
+        Some((start, end)) => (min(start, inline_position), max(end, inline_position)),
+    })
+}
+
 /// Information about the floats next to a flow.
 #[derive(Clone)]
 struct FloatList {
@@ -162,8 +408,9 @@ impl Floats {
     /// Returns a rectangle that encloses the region from block-start to block-start + block-size,
     /// with inline-size small enough that it doesn't collide with any floats. max_x is the
     /// inline-size beyond which floats have no effect. (Generally this is the containing block
-    /// inline-size.)
-    pub fn available_rect(&self, block_start: Au, block_size: Au, max_x: Au)
+    /// inline-size.) If `around_shapes` is set, the region is that of a line, which only has to
+    /// avoid the float areas of floats with `shape-outside`.
+    pub fn available_rect(&self, block_start: Au, block_size: Au, max_x: Au, around_shapes: bool)
                           -> Option<LogicalRect<Au>> {
         let list = &self.list;
         let block_start = block_start - self.offset.block;
@@ -182,8 +429,15 @@ impl Floats {
         // Find the float collisions for the given range in the block direction.
         for float in list.floats.iter() {
             debug!("available_rect: Checking for collision against float");
-            let float_pos = float.bounds.start;
-            let float_size = float.bounds.size;
+            let area = match float.area_avoided_by_lines(block_start,
+                                                         block_size,
+                                                         around_shapes,
+                                                         self.writing_mode) {
+                Some(area) => area,
+                None => continue,
+            };
+            let float_pos = area.start;
+            let float_size = area.size;
 
             debug!("float_pos: {:?}, float_size: {:?}", float_pos, float_size);
             match float.kind {
@@ -252,8 +506,8 @@ impl Floats {
                               block_end - block_start))
     }
 
-    /// Adds a new float to the list.
-    pub fn add_float(&mut self, info: &PlacementInfo) {
+    /// Adds a new float to the list, with the float area that `shape-outside` gives it if any.
+    pub fn add_float(&mut self, info: &PlacementInfo, shape: Option<FloatShape>) {
         let new_info = PlacementInfo {
             size: info.size,
             ceiling: match self.list.max_block_start {
@@ -272,20 +526,28 @@ impl Floats {
                 self.place_between_floats(&new_info).start - self.offset,
                 info.size,
             ),
-            kind: info.kind
+            kind: info.kind,
+            shape: shape.map(Arc::new),
         };
 
-        self.list.floats = self.list.floats.prepend_elem(new_float);
         self.list.max_block_start = match self.list.max_block_start {
             None => Some(new_float.bounds.start.b),
             Some(max_block_start) => Some(max(max_block_start, new_float.bounds.start.b)),
-        }
+        };
+        self.list.floats = self.list.floats.prepend_elem(new_float);
     }
 
     /// Given the three sides of the bounding rectangle in the block-start direction, finds the
     /// largest block-size that will result in the rectangle not colliding with any floats. Returns
-    /// `None` if that block-size is infinite.
-    fn max_block_size_for_bounds(&self, inline_start: Au, block_start: Au, inline_size: Au)
+    /// `None` if that block-size is infinite. Lines that wrap around shapes may collide with the
+    /// float areas of floats that they overlap beyond the `block_size` that they were placed
+    /// with, so they stop there.
+    fn max_block_size_for_bounds(&self,
+                                 inline_start: Au,
+                                 block_start: Au,
+                                 inline_size: Au,
+                                 block_size: Au,
+                                 around_shapes: bool)
                                  -> Option<Au> {
         let list = &self.list;
 
@@ -297,7 +559,11 @@ impl Floats {
             if float.bounds.start.b + float.bounds.size.block > block_start &&
                    float.bounds.start.i + float.bounds.size.inline > inline_start &&
                    float.bounds.start.i < inline_start + inline_size {
-               let new_y = float.bounds.start.b;
+               let new_y = if around_shapes && float.shape.is_some() {
+                   max(float.bounds.start.b, block_start + shape_band_size(block_size))
+               } else {
+                   float.bounds.start.b
+               };
                max_block_size = Some(min(max_block_size.unwrap_or(new_y), new_y));
             }
         }
@@ -308,6 +574,16 @@ impl Floats {
     /// Given placement information, finds the closest place a fragment can be positioned without
     /// colliding with any floats.
     pub fn place_between_floats(&self, info: &PlacementInfo) -> LogicalRect<Au> {
+        self.place(info, false)
+    }
+
+    /// Like `place_between_floats`, but for a line, which wraps around the float areas that
+    /// `shape-outside` gives floats instead of their margin boxes.
+    pub fn place_line_between_floats(&self, info: &PlacementInfo) -> LogicalRect<Au> {
+        self.place(info, true)
+    }
+
+    fn place(&self, info: &PlacementInfo, around_shapes: bool) -> LogicalRect<Au> {
         debug!("place_between_floats: Placing object with {:?}", info.size);
 
         // If no floats, use this fast path.
@@ -337,7 +613,8 @@ impl Floats {
         loop {
             let maybe_location = self.available_rect(float_b,
                                                      info.size.block,
-                                                     info.max_inline_size);
+                                                     info.max_inline_size,
+                                                     around_shapes);
             debug!("place_float: got available rect: {:?} for block-pos: {:?}",
                    maybe_location,
                    float_b);
@@ -372,7 +649,9 @@ impl Floats {
                     if rect.size.inline >= info.size.inline {
                         let block_size = self.max_block_size_for_bounds(rect.start.i,
                                                                         rect.start.b,
-                                                                        rect.size.inline);
+                                                                        rect.size.inline,
+                                                                        info.size.block,
+                                                                        around_shapes);
                         let block_size = block_size.unwrap_or(Au(i32::MAX));
                         return match info.kind {
                             FloatKind::Left => {
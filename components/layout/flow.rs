@@ -41,6 +41,7 @@ use inline::InlineFlow;
 use model::{CollapsibleMargins, IntrinsicISizes, MarginCollapseInfo};
 use multicol::MulticolFlow;
 use parallel::FlowParallelInfo;
+use ruby::RubyFlow;
 use rustc_serialize::{Encodable, Encoder};
 use std::iter::Zip;
 use std::slice::IterMut;
@@ -163,6 +164,11 @@ pub trait Flow: fmt::Debug + Sync + Send + 'static {
         panic!("called as_mut_multicol() on a non-multicol flow")
     }
 
+    /// If this is a ruby flow, returns the underlying object. Fails otherwise.
+    fn as_ruby(&self) -> &RubyFlow {
+        panic!("called as_ruby() on a non-ruby flow")
+    }
+
     /// If this is a table cell flow, returns the underlying object. Fails otherwise.
     fn as_table_cell(&self) -> &TableCellFlow {
         panic!("called as_table_cell() on a non-tablecell flow")
@@ -267,13 +273,13 @@ pub trait Flow: fmt::Debug + Sync + Send + 'static {
         let mut overflow = self.compute_overflow();
         match self.class() {
             FlowClass::Block |
+            FlowClass::Ruby |
             FlowClass::TableCaption |
             FlowClass::TableCell => {
                 // FIXME(#2795): Get the real container size.
                 let container_size = Size2D::zero();
 
-                let overflow_x = self.as_block().fragment.style.get_box().overflow_x;
-                let overflow_y = self.as_block().fragment.style.get_box().overflow_y;
+                let (overflow_x, overflow_y) = self.as_block().fragment.style.clipped_overflow();
 
                 for kid in mut_base(self).children.iter_mut() {
                     let mut kid_overflow = base(kid).overflow;
@@ -296,7 +302,7 @@ pub trait Flow: fmt::Debug + Sync + Send + 'static {
                         overflow_x::T::visible => {}
                     }
 
-                    match overflow_y.0 {
+                    match overflow_y {
                         overflow_x::T::hidden => {
                             kid_overflow.paint.origin.y = overflow.paint.origin.y;
                             kid_overflow.paint.size.height = overflow.paint.size.height;
@@ -364,14 +370,23 @@ pub trait Flow: fmt::Debug + Sync + Send + 'static {
         position::T::static_
     }
 
-    /// Return true if this flow has position 'fixed'.
+    /// Return true if this flow has position 'fixed' and is positioned relative to the viewport,
+    /// rather than to an ancestor that is the containing block of its fixed descendants.
     fn is_fixed(&self) -> bool {
-        self.positioning() == position::T::fixed
+        self.positioning() == position::T::fixed &&
+            !base(self).flags.contains(IS_FIXED_WITHIN_ANCESTOR)
     }
 
     fn contains_positioned_fragments(&self) -> bool {
         self.contains_relatively_positioned_fragments() ||
-            base(self).flags.contains(IS_ABSOLUTELY_POSITIONED)
+            base(self).flags.contains(IS_ABSOLUTELY_POSITIONED) ||
+            self.is_containing_block_for_fixed_descendants()
+    }
+
+    /// Returns true if this flow is the containing block of its fixed-position descendants, as
+    /// well as its absolutely-positioned ones, as flows with a filter are.
+    fn is_containing_block_for_fixed_descendants(&self) -> bool {
+        false
     }
 
     fn contains_relatively_positioned_fragments(&self) -> bool {
@@ -563,6 +578,13 @@ pub trait MutableOwnedFlowUtils {
     ///     </div>
     fn take_applicable_absolute_descendants(&mut self,
                                             absolute_descendants: &mut AbsoluteDescendants);
+
+    /// Set this flow as the containing block for the given absolute descendants, when it is
+    /// positioned. Fixed-position descendants only reach their containing block here if this
+    /// flow is the containing block of its fixed descendants; otherwise they are returned, to go
+    /// on to an ancestor that is, or the root.
+    fn contain_absolute_descendants(&mut self, abs_descendants: AbsoluteDescendants)
+                                    -> AbsoluteDescendants;
 }
 
 #[derive(Copy, Clone, RustcEncodable, PartialEq, Debug)]
@@ -580,6 +602,7 @@ pub enum FlowClass {
     Multicol,
     MulticolColumn,
     Flex,
+    Ruby,
 }
 
 impl FlowClass {
@@ -678,6 +701,18 @@ bitflags! {
 
         /// Whether this flow contains any text and/or replaced fragments.
         const CONTAINS_TEXT_OR_REPLACED_FRAGMENTS = 0b0001_0000_0000_0000_0000_0000,
+
+        /// Whether this flow had reflow damage at the start of the most recent layout. Only kept
+        /// up to date while the reflow outline debugging overlay is enabled.
+        const WAS_REFLOWED = 0b0010_0000_0000_0000_0000_0000,
+
+        /// Whether this flow has position `fixed`, but an ancestor rather than the viewport for
+        /// its containing block.
+        const IS_FIXED_WITHIN_ANCESTOR = 0b0100_0000_0000_0000_0000_0000,
+
+        /// Whether this flow is a reflow root: nothing that happens in its subtree can change
+        /// the layout of the rest of the flow tree, so the subtree can be laid out on its own.
+        const IS_REFLOW_ROOT = 0b1000_0000_0000_0000_0000_0000,
     }
 }
 
@@ -1093,6 +1128,10 @@ impl BaseFlow {
                         !style.get_counters().counter_increment.0.is_empty() {
                     flags.insert(AFFECTS_COUNTERS)
                 }
+
+                if style.is_reflow_root() {
+                    flags.insert(IS_REFLOW_ROOT)
+                }
             }
             None => flags.insert(BLOCK_POSITION_IS_STATIC | INLINE_POSITION_IS_STATIC),
         }
@@ -1396,6 +1435,11 @@ impl<'a> ImmutableFlowUtils for &'a Flow {
     }
 
     fn baseline_offset_of_last_line_box_in_flow(self) -> Option<Au> {
+        // The baseline of a ruby container is that of its bases, not of its last kid, which may
+        // be an annotation.
+        if self.class() == FlowClass::Ruby {
+            return self.as_ruby().baseline_offset()
+        }
         for kid in base(self).children.iter().rev() {
             if kid.is_inline_flow() {
                 return kid.as_inline().baseline_offset_of_last_line()
@@ -1487,6 +1531,7 @@ impl MutableOwnedFlowUtils for FlowRef {
             debug_assert!(!descendant_link.has_reached_containing_block);
             let descendant_base = mut_base(flow_ref::deref_mut(&mut descendant_link.flow));
             descendant_base.absolute_cb.set(this.clone());
+            descendant_base.flags.remove(IS_FIXED_WITHIN_ANCESTOR);
         }
     }
 
@@ -1518,6 +1563,30 @@ impl MutableOwnedFlowUtils for FlowRef {
             descendant_base.absolute_cb.set(this.clone());
         }
     }
+
+    fn contain_absolute_descendants(&mut self, mut abs_descendants: AbsoluteDescendants)
+                                    -> AbsoluteDescendants {
+        let contains_fixed_descendants = self.is_containing_block_for_fixed_descendants();
+        let mut passed_on_descendants = AbsoluteDescendants::new();
+        if !contains_fixed_descendants {
+            let (fixed, others): (Vec<_>, Vec<_>) =
+                abs_descendants.descendant_links.into_iter().partition(|descendant_link| {
+                    descendant_link.flow.positioning() == position::T::fixed
+                });
+            abs_descendants.descendant_links = others;
+            passed_on_descendants.descendant_links = fixed;
+        }
+
+        self.set_absolute_descendants(abs_descendants);
+        if contains_fixed_descendants {
+            for descendant in mut_base(flow_ref::deref_mut(self)).abs_descendants.iter() {
+                if descendant.positioning() == position::T::fixed {
+                    mut_base(descendant).flags.insert(IS_FIXED_WITHIN_ANCESTOR);
+                }
+            }
+        }
+        passed_on_descendants
+    }
 }
 
 /// A link to a flow's containing block.
@@ -1569,7 +1638,7 @@ impl ContainingBlockLink {
             Some(ref link) => {
                 let flow = link.upgrade().unwrap();
                 if flow.is_block_like() {
-                    flow.as_block().explicit_block_containing_size(layout_context)
+                    flow.as_block().explicit_padding_box_block_size(layout_context)
                 } else if flow.is_inline_flow() {
                     Some(flow.as_inline().minimum_block_size_above_baseline)
                 } else {
@@ -17,6 +17,7 @@ use gfx;
 use gfx::display_list::{BLUR_INFLATION_FACTOR, FragmentType, OpaqueNode, StackingContextId};
 use gfx::text::glyph::ByteIndex;
 use gfx::text::text_run::{TextRun, TextRunSlice};
+use gfx::theme::{self, Widget, WidgetState};
 use gfx_traits::{LayerId, LayerType};
 use incremental::{RECONSTRUCT_FLOW, RestyleDamage};
 use inline::{FIRST_FRAGMENT_OF_ELEMENT, InlineFragmentContext, InlineFragmentNodeInfo};
@@ -29,19 +30,28 @@ use net_traits::image::base::{Image, ImageMetadata};
 use net_traits::image_cache_thread::{ImageOrMetadataAvailable, UsePlaceholder};
 use range::*;
 use rustc_serialize::{Encodable, Encoder};
+use script::dom::bindings::inheritance::{ElementTypeId, HTMLElementTypeId, NodeTypeId};
 use script::dom::htmlcanvaselement::HTMLCanvasData;
+use std::ascii::AsciiExt;
 use std::borrow::ToOwned;
 use std::cmp::{max, min};
 use std::collections::LinkedList;
 use std::fmt;
+use std::iter;
 use std::sync::{Arc, Mutex};
+use style::animation::AnimationTarget;
 use style::computed_values::content::ContentItem;
-use style::computed_values::{border_collapse, clear, display, mix_blend_mode, overflow_wrap};
-use style::computed_values::{overflow_x, position, text_decoration, transform_style};
-use style::computed_values::{vertical_align, white_space, word_break, z_index};
+use style::computed_values::transition_property::TransitionProperty;
+use style::computed_values::{appearance, border_collapse, border_style, clear, display};
+use style::computed_values::mix_blend_mode;
+use style::computed_values::{overflow_wrap, overflow_x, position, text_decoration_line};
+use style::computed_values::{transform_style, vertical_align, white_space, word_break, z_index};
 use style::dom::TRestyleDamage;
+use style::element_state::{IN_ACTIVE_STATE, IN_CHECKED_STATE, IN_DISABLED_STATE};
+use style::element_state::IN_INDETERMINATE_STATE;
 use style::logical_geometry::{LogicalMargin, LogicalRect, LogicalSize, WritingMode};
-use style::properties::{ComputedValues, ServoComputedValues};
+use style::properties::{self, ComputedValues, ServoComputedValues};
+use style::selector_impl::PseudoElement;
 use style::values::computed::LengthOrPercentageOrNone;
 use style::values::computed::{LengthOrPercentage, LengthOrPercentageOrAuto};
 use text;
@@ -437,6 +447,8 @@ impl ImageFragmentInfo {
 pub struct ReplacedImageFragmentInfo {
     pub computed_inline_size: Option<Au>,
     pub computed_block_size: Option<Au>,
+    /// The inline size of the containing block that the inline size was computed against.
+    pub container_inline_size: Au,
     pub writing_mode_is_vertical: bool,
 }
 
@@ -447,6 +459,7 @@ impl ReplacedImageFragmentInfo {
         ReplacedImageFragmentInfo {
             computed_inline_size: None,
             computed_block_size: None,
+            container_inline_size: Au(0),
             writing_mode_is_vertical: is_vertical,
         }
     }
@@ -481,58 +494,151 @@ impl ReplacedImageFragmentInfo {
         }
     }
 
+    /// Returns the used inline and block sizes of the content of a replaced element with the given
+    /// intrinsic size, per CSS 2.1 § 10.3.2 and § 10.6.2, with the `min-*` and `max-*` constraints
+    /// of § 10.4. When both sizes are `auto`, the constraints keep the intrinsic ratio where they
+    /// can, so that `max-inline-size: 100%` scales the element down rather than squashing it.
+    ///
+    /// Percentages of the block size of the containing block compute to `auto` (or, for
+    /// `max-block-size`, `none`) if that block size is not definite.
+    fn used_size(style: &ServoComputedValues,
+                 container_inline_size: Au,
+                 container_block_size: Option<Au>,
+                 intrinsic_inline_size: Au,
+                 intrinsic_block_size: Au)
+                 -> (Au, Au) {
+        let inline_size = ReplacedImageFragmentInfo::style_length(style.content_inline_size(),
+                                                                  Some(container_inline_size));
+        let block_size = ReplacedImageFragmentInfo::style_length(style.content_block_size(),
+                                                                 container_block_size);
+
+        let min_inline_size = model::specified(style.min_inline_size(), container_inline_size);
+        let max_inline_size =
+            model::specified_or_none(style.max_inline_size(), container_inline_size)
+                .map(|max_inline_size| max(max_inline_size, min_inline_size));
+        let (min_block_size, max_block_size) = match container_block_size {
+            Some(container_block_size) => {
+                (model::specified(style.min_block_size(), container_block_size),
+                 model::specified_or_none(style.max_block_size(), container_block_size))
+            }
+            None => {
+                let min_block_size = match style.min_block_size() {
+                    LengthOrPercentage::Length(length) => length,
+                    LengthOrPercentage::Percentage(_) | LengthOrPercentage::Calc(_) => Au(0),
+                };
+                let max_block_size = match style.max_block_size() {
+                    LengthOrPercentageOrNone::Length(length) => Some(length),
+                    LengthOrPercentageOrNone::Percentage(_) |
+                    LengthOrPercentageOrNone::Calc(_) |
+                    LengthOrPercentageOrNone::None => None,
+                };
+                (min_block_size, max_block_size)
+            }
+        };
+        let max_block_size =
+            max_block_size.map(|max_block_size| max(max_block_size, min_block_size));
+
+        let clamp_inline_size = |size: Au| {
+            let size = max(size, min_inline_size);
+            max_inline_size.map_or(size, |max_inline_size| min(size, max_inline_size))
+        };
+        let clamp_block_size = |size: Au| {
+            let size = max(size, min_block_size);
+            max_block_size.map_or(size, |max_block_size| min(size, max_block_size))
+        };
+
+        // The intrinsic ratio, as the block size per inline size.
+        let ratio = if intrinsic_inline_size > Au(0) && intrinsic_block_size > Au(0) {
+            Some(intrinsic_block_size.to_f32_px() / intrinsic_inline_size.to_f32_px())
+        } else {
+            None
+        };
+
+        match (inline_size, block_size, ratio) {
+            (MaybeAuto::Specified(inline_size), MaybeAuto::Specified(block_size), _) => {
+                (clamp_inline_size(inline_size), clamp_block_size(block_size))
+            }
+            (MaybeAuto::Specified(inline_size), MaybeAuto::Auto, Some(ratio)) => {
+                let inline_size = clamp_inline_size(inline_size);
+                (inline_size, clamp_block_size(inline_size.scale_by(ratio)))
+            }
+            (MaybeAuto::Auto, MaybeAuto::Specified(block_size), Some(ratio)) => {
+                let block_size = clamp_block_size(block_size);
+                (clamp_inline_size(block_size.scale_by(1.0 / ratio)), block_size)
+            }
+            (MaybeAuto::Auto, MaybeAuto::Auto, Some(ratio)) => {
+                // The table of constraint violations of § 10.4, with the intrinsic size as the
+                // tentative size.
+                let (inline_size, block_size) = (intrinsic_inline_size, intrinsic_block_size);
+                let inline_size_for = |block_size: Au| block_size.scale_by(1.0 / ratio);
+                let block_size_for = |inline_size: Au| inline_size.scale_by(ratio);
+                let too_wide = max_inline_size.map_or(false, |max_size| inline_size > max_size);
+                let too_tall = max_block_size.map_or(false, |max_size| block_size > max_size);
+                let too_narrow = inline_size < min_inline_size;
+                let too_short = block_size < min_block_size;
+                match (too_wide, too_narrow, too_tall, too_short) {
+                    (true, _, true, _) => {
+                        let (max_inline_size, max_block_size) =
+                            (max_inline_size.unwrap(), max_block_size.unwrap());
+                        if max_inline_size.to_f32_px() / inline_size.to_f32_px() <=
+                                max_block_size.to_f32_px() / block_size.to_f32_px() {
+                            (max_inline_size,
+                             max(min_block_size, block_size_for(max_inline_size)))
+                        } else {
+                            (max(min_inline_size, inline_size_for(max_block_size)),
+                             max_block_size)
+                        }
+                    }
+                    (_, true, _, true) => {
+                        if min_inline_size.to_f32_px() / inline_size.to_f32_px() <=
+                                min_block_size.to_f32_px() / block_size.to_f32_px() {
+                            (clamp_inline_size(inline_size_for(min_block_size)), min_block_size)
+                        } else {
+                            (min_inline_size, clamp_block_size(block_size_for(min_inline_size)))
+                        }
+                    }
+                    (_, true, true, _) => (min_inline_size, max_block_size.unwrap()),
+                    (true, _, _, true) => (max_inline_size.unwrap(), min_block_size),
+                    (true, _, _, _) => {
+                        let max_inline_size = max_inline_size.unwrap();
+                        (max_inline_size, max(block_size_for(max_inline_size), min_block_size))
+                    }
+                    (_, true, _, _) => {
+                        (min_inline_size, clamp_block_size(block_size_for(min_inline_size)))
+                    }
+                    (_, _, true, _) => {
+                        let max_block_size = max_block_size.unwrap();
+                        (max(inline_size_for(max_block_size), min_inline_size), max_block_size)
+                    }
+                    (_, _, _, true) => {
+                        (clamp_inline_size(inline_size_for(min_block_size)), min_block_size)
+                    }
+                    (false, false, false, false) => (inline_size, block_size),
+                }
+            }
+            (inline_size, block_size, None) => {
+                (clamp_inline_size(inline_size.specified_or_default(intrinsic_inline_size)),
+                 clamp_block_size(block_size.specified_or_default(intrinsic_block_size)))
+            }
+        }
+    }
+
     pub fn calculate_replaced_inline_size(&mut self,
                                           style: &ServoComputedValues,
                                           noncontent_inline_size: Au,
                                           container_inline_size: Au,
+                                          container_block_size: Option<Au>,
                                           fragment_inline_size: Au,
                                           fragment_block_size: Au)
                                           -> Au {
-        let style_inline_size = style.content_inline_size();
-        let style_block_size = style.content_block_size();
-        let style_min_inline_size = style.min_inline_size();
-        let style_max_inline_size = style.max_inline_size();
-        let style_min_block_size = style.min_block_size();
-        let style_max_block_size = style.max_block_size();
-
         // TODO(ksh8281): compute border,margin
-        let inline_size = ReplacedImageFragmentInfo::style_length(
-            style_inline_size,
-            Some(container_inline_size));
-
-        let inline_size = match inline_size {
-            MaybeAuto::Auto => {
-                let intrinsic_width = fragment_inline_size;
-                let intrinsic_height = fragment_block_size;
-                if intrinsic_height == Au(0) {
-                    intrinsic_width
-                } else {
-                    let ratio = intrinsic_width.to_f32_px() /
-                                intrinsic_height.to_f32_px();
-
-                    let specified_height = ReplacedImageFragmentInfo::style_length(
-                        style_block_size,
-                        None);
-                    let specified_height = match specified_height {
-                        MaybeAuto::Auto => intrinsic_height,
-                        MaybeAuto::Specified(h) => h,
-                    };
-                    let specified_height = clamp_size(specified_height,
-                                                      style_min_block_size,
-                                                      style_max_block_size,
-                                                      Au(0));
-                    Au::from_f32_px(specified_height.to_f32_px() * ratio)
-                }
-            },
-            MaybeAuto::Specified(w) => w,
-        };
-
-        let inline_size = clamp_size(inline_size,
-                                     style_min_inline_size,
-                                     style_max_inline_size,
-                                     container_inline_size);
-
+        let (inline_size, _) = ReplacedImageFragmentInfo::used_size(style,
+                                                                    container_inline_size,
+                                                                    container_block_size,
+                                                                    fragment_inline_size,
+                                                                    fragment_block_size);
         self.computed_inline_size = Some(inline_size);
+        self.container_inline_size = container_inline_size;
         inline_size + noncontent_inline_size
     }
 
@@ -544,32 +650,14 @@ impl ReplacedImageFragmentInfo {
                                          fragment_block_size: Au)
                                          -> Au {
         // TODO(ksh8281): compute border,margin,padding
-        let style_block_size = style.content_block_size();
-        let style_min_block_size = style.min_block_size();
-        let style_max_block_size = style.max_block_size();
-
-        let inline_size = self.computed_inline_size();
-        let block_size = ReplacedImageFragmentInfo::style_length(
-            style_block_size,
-            containing_block_block_size);
-
-        let block_size = match block_size {
-            MaybeAuto::Auto => {
-                let intrinsic_width = fragment_inline_size;
-                let intrinsic_height = fragment_block_size;
-                let scale = intrinsic_width.to_f32_px() / inline_size.to_f32_px();
-                Au::from_f32_px(intrinsic_height.to_f32_px() / scale)
-            },
-            MaybeAuto::Specified(h) => {
-                h
-            }
-        };
-
-        let block_size = clamp_size(block_size,
-                                    style_min_block_size,
-                                    style_max_block_size,
-                                    Au(0));
-
+        //
+        // The inline size has been assigned already, but the block size of the containing block
+        // may only be known now, so the block size is worked out anew.
+        let (_, block_size) = ReplacedImageFragmentInfo::used_size(style,
+                                                                   self.container_inline_size,
+                                                                   containing_block_block_size,
+                                                                   fragment_inline_size,
+                                                                   fragment_block_size);
         self.computed_block_size = Some(block_size);
         block_size + noncontent_block_size
     }
@@ -663,6 +751,10 @@ pub struct ScannedTextFragmentInfo {
     pub range_end_including_stripped_whitespace: ByteIndex,
 
     pub flags: ScannedTextFlags,
+
+    /// The generated content that this text was rendered from, such as a counter, so that it can
+    /// be rendered again when the counter changes.
+    pub generated_content: Option<Box<GeneratedContentInfo>>,
 }
 
 bitflags! {
@@ -691,6 +783,7 @@ impl ScannedTextFragmentInfo {
             content_size: content_size,
             range_end_including_stripped_whitespace: range.end(),
             flags: flags,
+            generated_content: None,
         }
     }
 
@@ -797,7 +890,8 @@ impl Fragment {
         let mut restyle_damage = node.restyle_damage();
         restyle_damage.remove(RECONSTRUCT_FLOW);
 
-        Fragment {
+        let flags = widget_flags(node, &specific, &*style);
+        let mut fragment = Fragment {
             node: node.opaque(),
             style: style,
             selected_style: node.selected_style(style_context).clone(),
@@ -808,10 +902,18 @@ impl Fragment {
             specific: specific,
             inline_context: None,
             pseudo: node.get_pseudo_element_type().strip(),
-            flags: FragmentFlags::empty(),
+            flags: flags,
             debug_id: layout_debug::generate_unique_debug_id(),
             stacking_context_id: StackingContextId::new(0),
+        };
+
+        // Widgets such as checkboxes have no contents to size them.
+        if let Some((widget, _)) = fragment.widget() {
+            if let Some(size) = theme::platform_theme().intrinsic_size(widget) {
+                properties::modify_style_for_widget(&mut fragment.style, size.width, size.height);
+            }
         }
+        fragment
     }
 
     /// Constructs a new `Fragment` instance from an opaque node.
@@ -843,6 +945,26 @@ impl Fragment {
         }
     }
 
+    /// The widget that the theme paints in place of the background and borders of this fragment,
+    /// if any, with its state.
+    pub fn widget(&self) -> Option<(Widget, WidgetState)> {
+        let widget = if self.flags.contains(WIDGET_BUTTON) {
+            Widget::Button
+        } else if self.flags.contains(WIDGET_CHECKBOX) {
+            Widget::Checkbox
+        } else if self.flags.contains(WIDGET_RADIO) {
+            Widget::Radio
+        } else {
+            return None
+        };
+        Some((widget, WidgetState {
+            checked: self.flags.contains(WIDGET_CHECKED),
+            indeterminate: self.flags.contains(WIDGET_INDETERMINATE),
+            active: self.flags.contains(WIDGET_ACTIVE),
+            disabled: self.flags.contains(WIDGET_DISABLED),
+        }))
+    }
+
     /// Returns a debug ID of this fragment. This ID should not be considered stable across
     /// multiple layouts or fragment manipulations.
     pub fn debug_id(&self) -> u16 {
@@ -1313,8 +1435,8 @@ impl Fragment {
     /// CSS 2.1 § 16.3.1. Unfortunately, computing this properly doesn't really fit into Servo's
     /// model. Therefore, this is a best lower bound approximation, but the end result may actually
     /// have the various decoration flags turned on afterward.
-    pub fn text_decoration(&self) -> text_decoration::T {
-        self.style().get_text().text_decoration
+    pub fn text_decoration_line(&self) -> text_decoration_line::T {
+        self.style().get_text().text_decoration_line
     }
 
     /// Returns the inline-start offset from margin edge to content edge.
@@ -1769,8 +1891,11 @@ impl Fragment {
     }
 
     /// Assigns replaced inline-size, padding, and margins for this fragment only if it is replaced
-    /// content per CSS 2.1 § 10.3.2.
-    pub fn assign_replaced_inline_size_if_necessary(&mut self, container_inline_size: Au) {
+    /// content per CSS 2.1 § 10.3.2. `container_block_size` is the block size of the containing
+    /// block, if it is definite, which percentage block sizes need to keep the intrinsic ratio.
+    pub fn assign_replaced_inline_size_if_necessary(&mut self,
+                                                    container_inline_size: Au,
+                                                    container_block_size: Option<Au>) {
         match self.specific {
             SpecificFragmentInfo::Generic |
             SpecificFragmentInfo::GeneratedContent(_) |
@@ -1836,6 +1961,7 @@ impl Fragment {
                                        .calculate_replaced_inline_size(style,
                                                                        noncontent_inline_size,
                                                                        container_inline_size,
+                                                                       container_block_size,
                                                                        fragment_inline_size,
                                                                        fragment_block_size);
             }
@@ -1847,6 +1973,7 @@ impl Fragment {
                                         .calculate_replaced_inline_size(style,
                                                                         noncontent_inline_size,
                                                                         container_inline_size,
+                                                                        container_block_size,
                                                                         fragment_inline_size,
                                                                         fragment_block_size);
             }
@@ -1989,12 +2116,19 @@ impl Fragment {
                 }
             }
             SpecificFragmentInfo::InlineBlock(ref info) => {
-                // See CSS 2.1 § 10.8.1.
+                // The baseline is that of the last line box in the flow, unless it has none or
+                // its overflow is not `visible`, in which case it is the bottom margin edge. See
+                // CSS 2.1 § 10.8.1.
                 let flow = &info.flow_ref;
                 let block_flow = flow.as_block();
+                let overflows_visibly = {
+                    let box_style = block_flow.fragment.style.get_box();
+                    box_style.overflow_x == overflow_x::T::visible &&
+                        box_style.overflow_y.0 == overflow_x::T::visible
+                };
                 let baseline_offset = match flow.baseline_offset_of_last_line_box_in_flow() {
-                    Some(baseline_offset) => baseline_offset,
-                    None => block_flow.fragment.border_box.size.block,
+                    Some(baseline_offset) if overflows_visibly => baseline_offset,
+                    _ => block_flow.fragment.border_box.size.block,
                 };
                 let start_margin = block_flow.fragment.margin.block_start;
                 let end_margin = block_flow.fragment.margin.block_end;
@@ -2039,7 +2173,7 @@ impl Fragment {
              &SpecificFragmentInfo::UnscannedText(_)) => {
                 // FIXME: Should probably use a whitelist of styles that can safely differ (#3165)
                 if self.style().get_font() != other.style().get_font() ||
-                        self.text_decoration() != other.text_decoration() ||
+                        self.text_decoration_line() != other.text_decoration_line() ||
                         self.white_space() != other.white_space() {
                     return false
                 }
@@ -2205,6 +2339,28 @@ impl Fragment {
                               stacking_relative_border_box.size.height - border_padding.vertical()))
     }
 
+    /// Given the stacking-context-relative border box, returns the stacking-context-relative
+    /// margin box.
+    pub fn stacking_relative_margin_box(&self, stacking_relative_border_box: &Rect<Au>)
+                                        -> Rect<Au> {
+        let margin = self.margin.to_physical(self.style.writing_mode);
+        Rect::new(Point2D::new(stacking_relative_border_box.origin.x - margin.left,
+                               stacking_relative_border_box.origin.y - margin.top),
+                  Size2D::new(stacking_relative_border_box.size.width + margin.horizontal(),
+                              stacking_relative_border_box.size.height + margin.vertical()))
+    }
+
+    /// Given the stacking-context-relative border box, returns the stacking-context-relative
+    /// padding box.
+    pub fn stacking_relative_padding_box(&self, stacking_relative_border_box: &Rect<Au>)
+                                         -> Rect<Au> {
+        let border = self.border_width().to_physical(self.style.writing_mode);
+        Rect::new(Point2D::new(stacking_relative_border_box.origin.x + border.left,
+                               stacking_relative_border_box.origin.y + border.top),
+                  Size2D::new(stacking_relative_border_box.size.width - border.horizontal(),
+                              stacking_relative_border_box.size.height - border.vertical()))
+    }
+
     /// Returns true if this fragment establishes a new stacking context and false otherwise.
     pub fn establishes_stacking_context(&self) -> bool {
         if self.flags.contains(HAS_LAYER) {
@@ -2216,9 +2372,16 @@ impl Fragment {
         if !self.style().get_effects().filter.is_empty() {
             return true
         }
+        let contain = self.style().get_box().contain;
+        if contain.layout || contain.paint {
+            return true
+        }
         if self.style().get_effects().mix_blend_mode != mix_blend_mode::T::normal {
             return true
         }
+        if self.style().get_svg().mask_image.0.is_some() {
+            return true
+        }
         if self.style().get_effects().transform.0.is_some() {
             return true
         }
@@ -2302,11 +2465,20 @@ impl Fragment {
                                                              .inflate(inflation, inflation))
         }
 
-        // Outlines cause us to draw outside our border box.
-        let outline_width = self.style.get_outline().outline_width;
-        if outline_width != Au(0) {
-            overflow.paint = overflow.paint.union(&border_box.inflate(outline_width,
-                                                                      outline_width))
+        // Outlines cause us to draw outside our border box, including those of the inline boxes
+        // that the fragment is in.
+        let inline_styles = self.inline_context.iter().flat_map(|inline_context| {
+            inline_context.nodes.iter().map(|node| &*node.style)
+        });
+        for style in iter::once(&*self.style).chain(inline_styles) {
+            let outline = style.get_outline();
+            if outline.outline_width == Au(0) || outline.outline_style == border_style::T::none {
+                continue
+            }
+            let outset = outline.outline_width + outline.outline_offset;
+            if outset > Au(0) {
+                overflow.paint = overflow.paint.union(&border_box.inflate(outset, outset))
+            }
         }
 
         // Include the overflow of the block flow, if any.
@@ -2389,6 +2561,30 @@ impl Fragment {
         }
     }
 
+    /// Returns the inline-size of the spaces and tabs at the end of this fragment that hang
+    /// because of `white-space: pre-wrap`, ignoring any trailing preserved newline.
+    ///
+    /// https://drafts.csswg.org/css-text-3/#white-space-phase-2
+    pub fn hanging_whitespace_inline_size(&self) -> Au {
+        if self.white_space() != white_space::T::pre_wrap {
+            return Au(0)
+        }
+
+        match self.specific {
+            SpecificFragmentInfo::ScannedText(ref scanned_text_fragment_info) => {
+                let text = scanned_text_fragment_info.text().trim_right_matches('\n');
+                let whitespace_start =
+                    text.trim_right_matches(|c| c == ' ' || c == '\t').len();
+                let mut whitespace_range =
+                    Range::new(ByteIndex(whitespace_start as isize),
+                               ByteIndex((text.len() - whitespace_start) as isize));
+                whitespace_range.shift_by(scanned_text_fragment_info.range.begin());
+                scanned_text_fragment_info.run.metrics_for_range(&whitespace_range).advance_width
+            }
+            _ => Au(0),
+        }
+    }
+
     /// Returns true if the entire fragment was stripped.
     pub fn strip_trailing_whitespace_if_necessary(&mut self) -> WhitespaceStrippingResult {
         if self.white_space().preserve_spaces() {
@@ -2543,9 +2739,42 @@ impl Fragment {
             PseudoElementType::After(_) => FragmentType::AfterPseudoContent,
             PseudoElementType::DetailsSummary(_) => FragmentType::FragmentBody,
             PseudoElementType::DetailsContent(_) => FragmentType::FragmentBody,
+            PseudoElementType::Marker(_) => FragmentType::FragmentBody,
         }
     }
 
+    /// Returns the node or pseudo-element that the transitions of this fragment run on, or `None`
+    /// if it is generated for a pseudo-element that is not animated.
+    pub fn animation_target(&self) -> Option<AnimationTarget> {
+        let pseudo = match self.pseudo {
+            PseudoElementType::Normal => None,
+            PseudoElementType::Before(_) => Some(PseudoElement::Before),
+            PseudoElementType::After(_) => Some(PseudoElement::After),
+            PseudoElementType::DetailsSummary(_) |
+            PseudoElementType::DetailsContent(_) |
+            PseudoElementType::Marker(_) => return None,
+        };
+        Some(AnimationTarget::new(self.node, pseudo))
+    }
+
+    /// Returns true if a transition of `transform` or `opacity` is running on this fragment's
+    /// node or pseudo-element. Those are animated on a layer of their own.
+    pub fn has_running_layer_animation(&self, layout_context: &LayoutContext) -> bool {
+        let target = match self.animation_target() {
+            Some(target) => target,
+            None => return false,
+        };
+        let running_animations = layout_context.style_context().running_animations.read().unwrap();
+        running_animations.get(&target).map_or(false, |animations| {
+            animations.iter().any(|animation| {
+                match animation.property_animation.property() {
+                    TransitionProperty::Transform | TransitionProperty::Opacity => true,
+                    _ => false,
+                }
+            })
+        })
+    }
+
     pub fn layer_id(&self) -> LayerId {
         let layer_type = match self.pseudo {
             PseudoElementType::Normal => LayerType::FragmentBody,
@@ -2553,6 +2782,7 @@ impl Fragment {
             PseudoElementType::After(_) => LayerType::AfterPseudoContent,
             PseudoElementType::DetailsSummary(_) => LayerType::FragmentBody,
             PseudoElementType::DetailsContent(_) => LayerType::FragmentBody,
+            PseudoElementType::Marker(_) => LayerType::FragmentBody,
         };
         LayerId::new_of_type(layer_type, self.node.id() as usize)
     }
@@ -2769,7 +2999,73 @@ bitflags! {
     pub flags FragmentFlags: u8 {
         /// Whether this fragment has a layer.
         const HAS_LAYER = 0x01,
+        /// Whether the theme paints this fragment as a button.
+        const WIDGET_BUTTON = 0x02,
+        /// Whether the theme paints this fragment as a checkbox.
+        const WIDGET_CHECKBOX = 0x04,
+        /// Whether the theme paints this fragment as a radio button.
+        const WIDGET_RADIO = 0x08,
+        /// Whether the widget of this fragment is checked.
+        const WIDGET_CHECKED = 0x10,
+        /// Whether the widget of this fragment is indeterminate.
+        const WIDGET_INDETERMINATE = 0x20,
+        /// Whether the widget of this fragment is being pressed.
+        const WIDGET_ACTIVE = 0x40,
+        /// Whether the widget of this fragment is disabled.
+        const WIDGET_DISABLED = 0x80,
+    }
+}
+
+/// The flags that make the theme paint the fragment of `node` as a widget, if the element is one
+/// and the author left its `appearance` to the theme.
+fn widget_flags<N: ThreadSafeLayoutNode>(node: &N,
+                                         specific: &SpecificFragmentInfo,
+                                         style: &ServoComputedValues)
+                                         -> FragmentFlags {
+    match (specific, node.get_pseudo_element_type()) {
+        (&SpecificFragmentInfo::Generic, PseudoElementType::Normal) => {}
+        _ => return FragmentFlags::empty(),
+    }
+    if style.get_box().appearance != appearance::T::auto {
+        return FragmentFlags::empty()
+    }
+
+    let element = node.as_element();
+    let mut flags = match node.type_id() {
+        Some(NodeTypeId::Element(ElementTypeId::HTMLElement(
+                HTMLElementTypeId::HTMLButtonElement))) => WIDGET_BUTTON,
+        Some(NodeTypeId::Element(ElementTypeId::HTMLElement(
+                HTMLElementTypeId::HTMLInputElement))) => {
+            let type_ = element.get_attr(&ns!(), &atom!("type")).unwrap_or("text");
+            if type_.eq_ignore_ascii_case("checkbox") {
+                WIDGET_CHECKBOX
+            } else if type_.eq_ignore_ascii_case("radio") {
+                WIDGET_RADIO
+            } else if type_.eq_ignore_ascii_case("submit") ||
+                    type_.eq_ignore_ascii_case("reset") ||
+                    type_.eq_ignore_ascii_case("button") {
+                WIDGET_BUTTON
+            } else {
+                return FragmentFlags::empty()
+            }
+        }
+        _ => return FragmentFlags::empty(),
+    };
+
+    let state = element.get_state();
+    if state.contains(IN_CHECKED_STATE) {
+        flags.insert(WIDGET_CHECKED)
+    }
+    if state.contains(IN_INDETERMINATE_STATE) {
+        flags.insert(WIDGET_INDETERMINATE)
+    }
+    if state.contains(IN_ACTIVE_STATE) {
+        flags.insert(WIDGET_ACTIVE)
+    }
+    if state.contains(IN_DISABLED_STATE) {
+        flags.insert(WIDGET_DISABLED)
     }
+    flags
 }
 
 /// Specified distances from the margin edge of a block to its content in the inline direction.
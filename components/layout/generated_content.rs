@@ -10,15 +10,22 @@
 
 use context::LayoutContext;
 use flow::InorderFlowTraversal;
-use flow::{self, AFFECTS_COUNTERS, Flow, HAS_COUNTER_AFFECTING_CHILDREN, ImmutableFlowUtils};
+use flow::{self, AFFECTS_COUNTERS, Flow, FlowClass, HAS_COUNTER_AFFECTING_CHILDREN};
+use flow::ImmutableFlowUtils;
 use fragment::{Fragment, GeneratedContentInfo, SpecificFragmentInfo, UnscannedTextFragmentInfo};
 use gfx::display_list::OpaqueNode;
+use incremental::{BUBBLE_ISIZES, REFLOW, REFLOW_OUT_OF_FLOW, REPAINT};
 use incremental::{RESOLVE_GENERATED_CONTENT, RestyleDamage};
+use inline::FIRST_FRAGMENT_OF_ELEMENT;
 use smallvec::SmallVec;
 use std::collections::{HashMap, LinkedList};
+use std::iter;
+use std::mem;
 use std::sync::Arc;
+use string_cache::Atom;
 use style::computed_values::content::ContentItem;
 use style::computed_values::{display, list_style_type};
+use style::counter_style::{CounterStyleRule, CounterStyles, RangeBound, System};
 use style::dom::TRestyleDamage;
 use style::properties::{ComputedValues, ServoComputedValues};
 use text::TextRunScanner;
@@ -104,6 +111,9 @@ pub struct ResolveGeneratedContent<'a> {
     counters: HashMap<String, Counter>,
     /// The level of quote nesting.
     quote: u32,
+    /// Whether generated content has been rendered anew since `take_regenerated_content` was last
+    /// called.
+    regenerated_content: bool,
 }
 
 impl<'a> ResolveGeneratedContent<'a> {
@@ -114,20 +124,49 @@ impl<'a> ResolveGeneratedContent<'a> {
             list_item: Counter::new(),
             counters: HashMap::new(),
             quote: 0,
+            regenerated_content: false,
         }
     }
+
+    /// Returns whether generated content has been rendered anew since the last call, which
+    /// changes the size of the flows that it is in.
+    pub fn take_regenerated_content(&mut self) -> bool {
+        mem::replace(&mut self.regenerated_content, false)
+    }
+}
+
+/// The damage of a flow whose generated content got rendered anew, or that contains such a flow.
+pub fn regenerated_content_damage() -> RestyleDamage {
+    BUBBLE_ISIZES | REFLOW_OUT_OF_FLOW | REFLOW | REPAINT
 }
 
 impl<'a> InorderFlowTraversal for ResolveGeneratedContent<'a> {
     #[inline]
     fn process(&mut self, flow: &mut Flow, level: u32) {
-        let mut mutator = ResolveGeneratedContentFragmentMutator {
-            traversal: self,
-            level: level,
-            is_block: flow.is_block_like(),
-            incremented: false,
+        let (affects_counters, regenerated_content) = {
+            let mut mutator = ResolveGeneratedContentFragmentMutator {
+                traversal: self,
+                level: level,
+                is_block: flow.is_block_like(),
+                is_inline: flow.class() == FlowClass::Inline,
+                incremented: false,
+                affects_counters: false,
+                regenerated_content: false,
+            };
+            flow.mutate_fragments(&mut |fragment| mutator.mutate_fragment(fragment));
+            (mutator.affects_counters, mutator.regenerated_content)
         };
-        flow.mutate_fragments(&mut |fragment| mutator.mutate_fragment(fragment))
+
+        // Inline flows have no style of their own, so they only get to know that they affect
+        // counters here. They must be visited again when the counters before them change.
+        let base = flow::mut_base(flow);
+        if affects_counters {
+            base.flags.insert(AFFECTS_COUNTERS)
+        }
+        if regenerated_content {
+            base.restyle_damage.insert(regenerated_content_damage());
+            self.regenerated_content = true
+        }
     }
 
     #[inline]
@@ -145,8 +184,17 @@ struct ResolveGeneratedContentFragmentMutator<'a,'b:'a> {
     level: u32,
     /// Whether this flow is a block flow.
     is_block: bool,
+    /// Whether this flow is an inline flow, whose inline elements reset and increment counters
+    /// as their first fragments come along.
+    is_inline: bool,
     /// Whether we've incremented the counter yet.
     incremented: bool,
+    /// Whether a fragment of this flow resets, increments, or renders a counter, or changes the
+    /// nesting level of quotes, so that the flow has to be traversed again whenever the content
+    /// before it changes.
+    affects_counters: bool,
+    /// Whether a fragment of this flow got its generated content rendered anew.
+    regenerated_content: bool,
 }
 
 impl<'a,'b> ResolveGeneratedContentFragmentMutator<'a,'b> {
@@ -157,19 +205,53 @@ impl<'a,'b> ResolveGeneratedContentFragmentMutator<'a,'b> {
             self.reset_and_increment_counters_as_necessary(fragment);
         }
 
-        let mut list_style_type = fragment.style().get_list().list_style_type;
+        // The inline elements of an inline formatting context all share its flow, so their
+        // counters are reset and incremented where they start.
+        if self.is_inline {
+            if let Some(ref inline_context) = fragment.inline_context {
+                for node in inline_context.nodes.iter().rev() {
+                    if node.flags.contains(FIRST_FRAGMENT_OF_ELEMENT) {
+                        self.reset_and_increment_counters(&*node.style);
+                    }
+                }
+            }
+        }
+
+        let mut list_style_type = fragment.style().get_list().list_style_type.clone();
         if fragment.style().get_box().display != display::T::list_item {
             list_style_type = list_style_type::T::none
         }
 
         let mut new_info = None;
-        {
-            let info =
-                if let SpecificFragmentInfo::GeneratedContent(ref mut info) = fragment.specific {
-                    info
-                } else {
-                    return
-                };
+        let generated_content = {
+            // Text that was rendered from generated content is rendered again, in case the
+            // counters or quotes before it changed.
+            let info = match fragment.specific {
+                SpecificFragmentInfo::GeneratedContent(ref info) => info,
+                SpecificFragmentInfo::ScannedText(ref text_info) => {
+                    match text_info.generated_content {
+                        // Text split across lines is left as it is, since rendering it again
+                        // would repeat it on every line.
+                        Some(ref info) if text_info.range.length().to_usize() ==
+                                text_info.run.text.len() => info,
+                        _ => return,
+                    }
+                }
+                _ => return,
+            };
+
+            match **info {
+                GeneratedContentInfo::ListItem |
+                GeneratedContentInfo::ContentItem(ContentItem::Counter(..)) |
+                GeneratedContentInfo::ContentItem(ContentItem::Counters(..)) |
+                GeneratedContentInfo::ContentItem(ContentItem::OpenQuote) |
+                GeneratedContentInfo::ContentItem(ContentItem::CloseQuote) |
+                GeneratedContentInfo::ContentItem(ContentItem::NoOpenQuote) |
+                GeneratedContentInfo::ContentItem(ContentItem::NoCloseQuote) => {
+                    self.affects_counters = true
+                }
+                _ => {}
+            }
 
             match **info {
                 GeneratedContentInfo::ListItem => {
@@ -177,15 +259,17 @@ impl<'a,'b> ResolveGeneratedContentFragmentMutator<'a,'b> {
                                                                fragment.node,
                                                                fragment.pseudo.clone(),
                                                                fragment.style.clone(),
-                                                               list_style_type,
-                                                               RenderingMode::Suffix(".\u{00a0}"))
+                                                               &list_style_type,
+                                                               RenderingMode::Marker)
                 }
                 GeneratedContentInfo::Empty |
-                GeneratedContentInfo::ContentItem(ContentItem::String(_)) => {
+                GeneratedContentInfo::ContentItem(ContentItem::String(_)) |
+                GeneratedContentInfo::ContentItem(ContentItem::Url(_)) |
+                GeneratedContentInfo::ContentItem(ContentItem::Attr(..)) => {
                     // Nothing to do here.
                 }
                 GeneratedContentInfo::ContentItem(ContentItem::Counter(ref counter_name,
-                                                                       counter_style)) => {
+                                                                       ref counter_style)) => {
                     let temporary_counter = Counter::new();
                     let counter = self.traversal
                                       .counters
@@ -200,7 +284,7 @@ impl<'a,'b> ResolveGeneratedContentFragmentMutator<'a,'b> {
                 }
                 GeneratedContentInfo::ContentItem(ContentItem::Counters(ref counter_name,
                                                                         ref separator,
-                                                                        counter_style)) => {
+                                                                        ref counter_style)) => {
                     let temporary_counter = Counter::new();
                     let counter = self.traversal
                                       .counters
@@ -222,15 +306,18 @@ impl<'a,'b> ResolveGeneratedContentFragmentMutator<'a,'b> {
                     self.traversal.quote += 1
                 }
                 GeneratedContentInfo::ContentItem(ContentItem::CloseQuote) => {
-                    if self.traversal.quote >= 1 {
-                        self.traversal.quote -= 1
-                    }
-
+                    // A close quote that closes no open quote is not rendered (CSS 2.1 § 12.3.2).
+                    let quote = if self.traversal.quote >= 1 {
+                        self.traversal.quote -= 1;
+                        self.quote(&*fragment.style, true)
+                    } else {
+                        String::new()
+                    };
                     new_info = render_text(self.traversal.layout_context,
                                            fragment.node,
                                            fragment.pseudo,
                                            fragment.style.clone(),
-                                           self.quote(&*fragment.style, true));
+                                           quote);
                 }
                 GeneratedContentInfo::ContentItem(ContentItem::NoOpenQuote) => {
                     self.traversal.quote += 1
@@ -241,20 +328,36 @@ impl<'a,'b> ResolveGeneratedContentFragmentMutator<'a,'b> {
                     }
                 }
             }
+            (**info).clone()
         };
 
         fragment.specific = match new_info {
+            Some(SpecificFragmentInfo::ScannedText(mut text_info)) => {
+                if let SpecificFragmentInfo::ScannedText(ref old_text_info) = fragment.specific {
+                    if old_text_info.run.text == text_info.run.text {
+                        return
+                    }
+                }
+                text_info.generated_content = Some(box generated_content);
+                SpecificFragmentInfo::ScannedText(text_info)
+            }
             Some(new_info) => new_info,
-            // If the fragment did not generate any content, replace it with a no-op placeholder
-            // so that it isn't processed again on the next layout.  FIXME (mbrubeck): When
+            // If the fragment did not generate any content, it is left as generated content that
+            // takes up no space, to be rendered again on the next layout.  FIXME (mbrubeck): When
             // processing an inline flow, this traversal should be allowed to insert or remove
             // fragments.  Then we can just remove these fragments rather than adding placeholders.
-            None => SpecificFragmentInfo::GeneratedContent(box GeneratedContentInfo::Empty)
+            None => {
+                if let SpecificFragmentInfo::GeneratedContent(_) = fragment.specific {
+                    return
+                }
+                SpecificFragmentInfo::GeneratedContent(box generated_content)
+            }
         };
+        self.regenerated_content = true
     }
 
     fn reset_and_increment_counters_as_necessary(&mut self, fragment: &mut Fragment) {
-        let mut list_style_type = fragment.style().get_list().list_style_type;
+        let mut list_style_type = fragment.style().get_list().list_style_type.clone();
         if !self.is_block || fragment.style().get_box().display != display::T::list_item {
             list_style_type = list_style_type::T::none
         }
@@ -272,7 +375,20 @@ impl<'a,'b> ResolveGeneratedContentFragmentMutator<'a,'b> {
         }
         self.traversal.list_item.truncate_to_level(self.level);
 
-        for &(ref counter_name, value) in &fragment.style().get_counters().counter_reset.0 {
+        if !self.is_inline {
+            self.reset_and_increment_counters(&*fragment.style);
+        }
+        self.incremented = true
+    }
+
+    /// Resets and then increments the counters that `style` names, per CSS 2.1 § 12.4.
+    fn reset_and_increment_counters(&mut self, style: &ServoComputedValues) {
+        let counters = style.get_counters();
+        if !counters.counter_reset.0.is_empty() || !counters.counter_increment.0.is_empty() {
+            self.affects_counters = true
+        }
+
+        for &(ref counter_name, value) in &counters.counter_reset.0 {
             if let Some(ref mut counter) = self.traversal.counters.get_mut(counter_name) {
                  counter.reset(self.level, value);
                  continue
@@ -283,7 +399,7 @@ impl<'a,'b> ResolveGeneratedContentFragmentMutator<'a,'b> {
             self.traversal.counters.insert((*counter_name).clone(), counter);
         }
 
-        for &(ref counter_name, value) in &fragment.style().get_counters().counter_increment.0 {
+        for &(ref counter_name, value) in &counters.counter_increment.0 {
             if let Some(ref mut counter) = self.traversal.counters.get_mut(counter_name) {
                 counter.increment(self.level, value);
                 continue
@@ -293,8 +409,6 @@ impl<'a,'b> ResolveGeneratedContentFragmentMutator<'a,'b> {
             counter.increment(self.level, value);
             self.traversal.counters.insert((*counter_name).clone(), counter);
         }
-
-        self.incremented = true
     }
 
     fn quote(&self, style: &ServoComputedValues, close: bool) -> String {
@@ -368,9 +482,10 @@ impl Counter {
               node: OpaqueNode,
               pseudo: PseudoElementType<()>,
               style: Arc<ServoComputedValues>,
-              list_style_type: list_style_type::T,
+              list_style_type: &list_style_type::T,
               mode: RenderingMode)
               -> Option<SpecificFragmentInfo> {
+        let counter_styles = layout_context.style_context().stylist.counter_styles();
         let mut string = String::new();
         match mode {
             RenderingMode::Plain => {
@@ -378,14 +493,16 @@ impl Counter {
                     Some(ref value) => value.value,
                     None => 0,
                 };
-                push_representation(value, list_style_type, &mut string)
+                push_representation(value, list_style_type, counter_styles, &mut string)
             }
-            RenderingMode::Suffix(suffix) => {
+            RenderingMode::Marker => {
                 let value = match self.values.last() {
                     Some(ref value) => value.value,
                     None => 0,
                 };
-                push_representation(value, list_style_type, &mut string);
+                let (prefix, suffix) = marker_affixes(list_style_type, counter_styles);
+                string.push_str(prefix);
+                push_representation(value, list_style_type, counter_styles, &mut string);
                 string.push_str(suffix)
             }
             RenderingMode::All(separator) => {
@@ -395,7 +512,7 @@ impl Counter {
                         string.push_str(separator)
                     }
                     first = false;
-                    push_representation(value.value, list_style_type, &mut string)
+                    push_representation(value.value, list_style_type, counter_styles, &mut string)
                 }
             }
         }
@@ -412,8 +529,9 @@ impl Counter {
 enum RenderingMode<'a> {
     /// The innermost counter value is rendered with no extra decoration.
     Plain,
-    /// The innermost counter value is rendered with the given string suffix.
-    Suffix(&'a str),
+    /// The innermost counter value is rendered between the prefix and the suffix of the counter
+    /// style, as the markers of list items are.
+    Marker,
     /// All values of the counter are rendered with the given separator string between them.
     All(&'a str),
 }
@@ -455,8 +573,43 @@ fn render_text(layout_context: &LayoutContext,
 
 /// Appends string that represents the value rendered using the system appropriate for the given
 /// `list-style-type` onto the given string.
-fn push_representation(value: i32, list_style_type: list_style_type::T, accumulator: &mut String) {
-    match list_style_type {
+fn push_representation(value: i32,
+                       list_style_type: &list_style_type::T,
+                       counter_styles: &CounterStyles,
+                       accumulator: &mut String) {
+    match *list_style_type {
+        list_style_type::T::Custom(ref name) => {
+            push_custom_representation(value, name, counter_styles, &mut vec![], accumulator)
+        }
+        ref list_style_type => push_predefined_representation(value, list_style_type, accumulator),
+    }
+}
+
+/// The prefix and the suffix of the markers of list items whose `list-style-type` is
+/// `list_style_type`.
+fn marker_affixes<'a>(list_style_type: &list_style_type::T, counter_styles: &'a CounterStyles)
+                      -> (&'a str, &'a str) {
+    // Just to keep things simple, the predefined styles use a nonbreaking space to separate the
+    // marker from the contents.
+    let name = match *list_style_type {
+        list_style_type::T::Custom(ref name) => name,
+        _ => return ("", ".\u{00a0}"),
+    };
+    match counter_styles.get(name) {
+        Some(rule) => {
+            let style = ResolvedCounterStyle::new(rule, counter_styles);
+            (style.prefix.map_or("", |prefix| &**prefix),
+             style.suffix.map_or(". ", |suffix| &**suffix))
+        }
+        None => ("", ".\u{00a0}"),
+    }
+}
+
+/// Appends the representation of `value` in the predefined counter style `list_style_type`.
+fn push_predefined_representation(value: i32,
+                                  list_style_type: &list_style_type::T,
+                                  accumulator: &mut String) {
+    match *list_style_type {
         list_style_type::T::none => {}
         list_style_type::T::disc |
         list_style_type::T::circle |
@@ -522,13 +675,291 @@ fn push_representation(value: i32, list_style_type: list_style_type::T, accumula
         list_style_type::T::katakana_iroha => {
             push_alphabetic_representation(value, &KATAKANA_IROHA, accumulator)
         }
+        // Names that no `@counter-style` rule defines stand for `decimal`.
+        list_style_type::T::Custom(_) => {
+            push_numeric_representation(value, &DECIMAL, accumulator)
+        }
+    }
+}
+
+/// Whether the predefined counter style `list_style_type` is one of the alphabetic ones, which
+/// cannot represent values below one.
+fn is_predefined_alphabetic(list_style_type: &list_style_type::T) -> bool {
+    match *list_style_type {
+        list_style_type::T::lower_alpha | list_style_type::T::upper_alpha |
+        list_style_type::T::cjk_earthly_branch | list_style_type::T::cjk_heavenly_stem |
+        list_style_type::T::lower_greek | list_style_type::T::hiragana |
+        list_style_type::T::hiragana_iroha | list_style_type::T::katakana |
+        list_style_type::T::katakana_iroha => true,
+        _ => false,
+    }
+}
+
+/// Whether the predefined counter style `list_style_type` shows the same symbol for every
+/// value, and so has no use for a negative sign.
+fn is_predefined_static(list_style_type: &list_style_type::T) -> bool {
+    match *list_style_type {
+        list_style_type::T::none | list_style_type::T::disc | list_style_type::T::circle |
+        list_style_type::T::square | list_style_type::T::disclosure_open |
+        list_style_type::T::disclosure_closed => true,
+        _ => false,
+    }
+}
+
+/// How a counter style that a `@counter-style` rule defines generates representations.
+enum Algorithm<'a> {
+    /// One of the systems of CSS-COUNTER-STYLES § 3.1, other than `extends`, with the symbols
+    /// and the additive symbols of the rule.
+    System(&'a System, &'a [String], &'a [(u32, String)]),
+    /// The algorithm of a predefined counter style, which the rule extends.
+    Predefined(list_style_type::T),
+}
+
+/// A counter style that a `@counter-style` rule defines, with the descriptors that it leaves
+/// unspecified taken from the counter styles that it extends (CSS-COUNTER-STYLES § 3.1.7).
+struct ResolvedCounterStyle<'a> {
+    algorithm: Algorithm<'a>,
+    negative: Option<&'a (String, String)>,
+    prefix: Option<&'a String>,
+    suffix: Option<&'a String>,
+    range: Option<&'a Vec<(RangeBound, RangeBound)>>,
+    pad: Option<&'a (u32, String)>,
+    fallback: Option<&'a Atom>,
+}
+
+impl<'a> ResolvedCounterStyle<'a> {
+    fn new(rule: &'a CounterStyleRule, counter_styles: &'a CounterStyles)
+           -> ResolvedCounterStyle<'a> {
+        let mut style = ResolvedCounterStyle {
+            algorithm: Algorithm::Predefined(list_style_type::T::decimal),
+            negative: None,
+            prefix: None,
+            suffix: None,
+            range: None,
+            pad: None,
+            fallback: None,
+        };
+        let mut chain = vec![];
+        let mut current = rule;
+        loop {
+            style.negative = style.negative.or(current.negative.as_ref());
+            style.prefix = style.prefix.or(current.prefix.as_ref());
+            style.suffix = style.suffix.or(current.suffix.as_ref());
+            style.range = style.range.or(current.range.as_ref());
+            style.pad = style.pad.or(current.pad.as_ref());
+            style.fallback = style.fallback.or(current.fallback.as_ref());
+            chain.push(&current.name);
+
+            let name = match current.system {
+                System::Extends(ref name) => name,
+                ref system => {
+                    style.algorithm = Algorithm::System(
+                        system,
+                        current.symbols.as_ref().map_or(&[][..], |symbols| &symbols[..]),
+                        current.additive_symbols.as_ref().map_or(&[][..], |symbols| &symbols[..]));
+                    return style
+                }
+            };
+            match counter_styles.get(name) {
+                // Styles that extend each other in a cycle extend `decimal` instead.
+                Some(_) if chain.contains(&name) => return style,
+                Some(extended) => current = extended,
+                None => {
+                    let predefined = list_style_type::T::from_ident(name);
+                    style.algorithm =
+                        Algorithm::Predefined(predefined.unwrap_or(list_style_type::T::decimal));
+                    return style
+                }
+            }
+        }
+    }
+
+    /// Whether the style can represent `value`.
+    fn in_range(&self, value: i32) -> bool {
+        if let Some(ranges) = self.range {
+            if !ranges.is_empty() {
+                return ranges.iter().any(|&(lower, upper)| {
+                    lower.map_or(true, |lower| lower <= value) &&
+                    upper.map_or(true, |upper| value <= upper)
+                })
+            }
+        }
+        match self.algorithm {
+            Algorithm::System(&System::Alphabetic, _, _) |
+            Algorithm::System(&System::Symbolic, _, _) => value >= 1,
+            Algorithm::System(&System::Additive, _, _) => value >= 0,
+            Algorithm::Predefined(ref predefined) if is_predefined_alphabetic(predefined) => {
+                value >= 1
+            }
+            _ => true,
+        }
+    }
+
+    /// Whether negative values are represented by their absolute value and a negative sign.
+    fn uses_negative_sign(&self) -> bool {
+        match self.algorithm {
+            Algorithm::System(&System::Cyclic, _, _) |
+            Algorithm::System(&System::Fixed(_), _, _) => false,
+            Algorithm::Predefined(ref predefined) => !is_predefined_static(predefined),
+            _ => true,
+        }
+    }
+
+    /// Generates the initial representation of `value`, which is not negative if the style uses
+    /// a negative sign, per CSS-COUNTER-STYLES § 3.1. Returns `None` if the algorithm cannot
+    /// represent it.
+    fn initial_representation(&self, value: i32) -> Option<String> {
+        let (system, symbols, additive_symbols) = match self.algorithm {
+            Algorithm::System(system, symbols, additive_symbols) => {
+                (system, symbols, additive_symbols)
+            }
+            Algorithm::Predefined(ref predefined) => {
+                let mut string = String::new();
+                push_predefined_representation(value, predefined, &mut string);
+                return Some(string)
+            }
+        };
+        let count = symbols.len() as i64;
+        let value = value as i64;
+        match *system {
+            System::Cyclic => {
+                Some(symbols[((value - 1) % count + count) as usize % symbols.len()].clone())
+            }
+            System::Fixed(first) => {
+                let index = value - first as i64;
+                if index >= 0 && index < count {
+                    Some(symbols[index as usize].clone())
+                } else {
+                    None
+                }
+            }
+            System::Symbolic => {
+                // Representations of huge values are not worth their length.
+                let repetitions = (value + count - 1) / count;
+                if value < 1 || repetitions > 60 {
+                    return None
+                }
+                let symbol = &symbols[((value - 1) % count) as usize];
+                Some(iter::repeat(&**symbol).take(repetitions as usize).collect())
+            }
+            System::Alphabetic => {
+                if value < 1 {
+                    return None
+                }
+                let mut parts = vec![];
+                let mut value = value;
+                while value != 0 {
+                    value -= 1;
+                    parts.push(&*symbols[(value % count) as usize]);
+                    value /= count;
+                }
+                parts.reverse();
+                Some(parts.concat())
+            }
+            System::Numeric => {
+                let mut parts = vec![];
+                let mut value = value;
+                loop {
+                    parts.push(&*symbols[(value % count) as usize]);
+                    value /= count;
+                    if value == 0 {
+                        break
+                    }
+                }
+                parts.reverse();
+                Some(parts.concat())
+            }
+            System::Additive => {
+                if value == 0 {
+                    return additive_symbols.iter()
+                                           .find(|&&(weight, _)| weight == 0)
+                                           .map(|&(_, ref symbol)| symbol.clone())
+                }
+                let mut string = String::new();
+                let mut value = value;
+                for &(weight, ref symbol) in additive_symbols {
+                    if weight == 0 || weight as i64 > value {
+                        continue
+                    }
+                    let repetitions = value / weight as i64;
+                    if repetitions > 60 {
+                        return None
+                    }
+                    string.extend(iter::repeat(&**symbol).take(repetitions as usize));
+                    value -= repetitions * weight as i64;
+                    if value == 0 {
+                        return Some(string)
+                    }
+                }
+                None
+            }
+            System::Extends(_) => None,
+        }
+    }
+}
+
+/// Appends the representation of `value` in the counter style that the `@counter-style` rule by
+/// the name `name` defines, per CSS-COUNTER-STYLES § 1.2. `visited` holds the styles that fell
+/// back to this one, so that cycles of fallbacks end in `decimal`.
+fn push_custom_representation(value: i32,
+                              name: &Atom,
+                              counter_styles: &CounterStyles,
+                              visited: &mut Vec<Atom>,
+                              accumulator: &mut String) {
+    let rule = match counter_styles.get(name) {
+        Some(rule) if !visited.contains(name) => rule,
+        _ => return push_numeric_representation(value, &DECIMAL, accumulator),
+    };
+    visited.push(name.clone());
+    let style = ResolvedCounterStyle::new(rule, counter_styles);
+
+    let negative = value < 0 && style.uses_negative_sign();
+    let absolute_value = if negative {
+        value.checked_abs().unwrap_or(i32::max_value())
+    } else {
+        value
+    };
+    let representation = if style.in_range(value) {
+        style.initial_representation(absolute_value)
+    } else {
+        None
+    };
+    let representation = match representation {
+        Some(representation) => representation,
+        None => {
+            // Fall back to another style.
+            let fallback = style.fallback.cloned().unwrap_or_else(|| Atom::from("decimal"));
+            if counter_styles.contains_key(&fallback) {
+                return push_custom_representation(value, &fallback, counter_styles, visited,
+                                                  accumulator)
+            }
+            let fallback = list_style_type::T::from_ident(&fallback)
+                .unwrap_or(list_style_type::T::decimal);
+            return push_predefined_representation(value, &fallback, accumulator)
+        }
+    };
+
+    let (negative_before, negative_after) = match (negative, style.negative) {
+        (false, _) => ("", ""),
+        (true, Some(&(ref before, ref after))) => (&**before, &**after),
+        (true, None) => ("-", ""),
+    };
+    accumulator.push_str(negative_before);
+    if let Some(&(length, ref symbol)) = style.pad {
+        let used = representation.chars().count() + negative_before.chars().count() +
+            negative_after.chars().count();
+        for _ in used..(length as usize) {
+            accumulator.push_str(symbol)
+        }
     }
+    accumulator.push_str(&representation);
+    accumulator.push_str(negative_after);
 }
 
 /// Returns the static character that represents the value rendered using the given list-style, if
 /// possible.
-pub fn static_representation(list_style_type: list_style_type::T) -> char {
-    match list_style_type {
+pub fn static_representation(list_style_type: &list_style_type::T) -> char {
+    match *list_style_type {
         list_style_type::T::disc => '•',
         list_style_type::T::circle => '◦',
         list_style_type::T::square => '▪',
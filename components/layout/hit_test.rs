@@ -0,0 +1,270 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The hit-test trees that layout hands to the compositor along with display lists.
+
+use app_units::Au;
+use euclid::{Matrix2D, Matrix4D, Point2D, Rect, Size2D};
+use gfx::display_list::{DisplayItem, DisplayList, DisplayListTraversal};
+use gfx::display_list::{ScrollSnapArea, ScrollSnapContainer, StackingContext, StackingContextType};
+use gfx::display_list::TouchActionRegion;
+use script_traits::{AllowedTouchActions, HitTestItem, HitTestRoundedClip, HitTestScrollFrame};
+use script_traits::{HitTestTouchActionRegion, HitTestTree, ScrollSnap, ScrollSnapStrictness};
+use std::collections::HashMap;
+use string_cache::Atom;
+use style::computed_values::{scroll_snap_align, scroll_snap_type_x, scroll_snap_type_y};
+use style::computed_values::touch_action;
+use style::dom::{TElement, TNode};
+use url::Url;
+use util::geometry::{self, MAX_RECT};
+use wrapper::{ServoLayoutElement, ServoLayoutNode};
+
+/// Summarizes what pointer events can hit in `display_list`, hit testing its items the way
+/// `DisplayList::hit_test` does. `links` are the URLs of the links that nodes are in.
+pub fn hit_test_tree(display_list: &DisplayList, links: &HashMap<usize, String>) -> HitTestTree {
+    let mut tree = HitTestTree::default();
+    tree.custom_cursors = display_list.custom_cursors.iter().map(|(node, custom_cursor)| {
+        (node.0, custom_cursor.clone())
+    }).collect();
+    tree.links = links.clone();
+    tree.viewport_snap = display_list.root_stacking_context.scroll_snap.as_ref().map(|container| {
+        scroll_snap(container, &Matrix4D::identity())
+    });
+    let mut traversal = DisplayListTraversal {
+        display_list: display_list,
+        current_item_index: 0,
+        last_item_index: display_list.list.len().saturating_sub(1),
+    };
+    add_stacking_context(&display_list.root_stacking_context,
+                         &mut traversal,
+                         &Matrix4D::identity(),
+                         None,
+                         &mut tree);
+    // The items were added in painting order.
+    tree.items.reverse();
+    tree
+}
+
+fn add_stacking_context<'a>(stacking_context: &StackingContext,
+                            traversal: &mut DisplayListTraversal<'a>,
+                            transform: &Matrix4D<f32>,
+                            scroll_frame: Option<usize>,
+                            tree: &mut HitTestTree) {
+    let transform = if stacking_context.context_type == StackingContextType::Real {
+        let origin = &stacking_context.bounds.origin;
+        transform.translate(origin.x.to_f32_px(), origin.y.to_f32_px(), 0.0)
+                 .mul(&stacking_context.transform)
+    } else {
+        *transform
+    };
+
+    let scroll_frame = match stacking_context.layer_info {
+        Some(ref layer_info) if stacking_context.scrolls_overflow_area => {
+            tree.scroll_frames.push(HitTestScrollFrame {
+                layer_id: layer_info.layer_id,
+                parent: scroll_frame,
+                snap: stacking_context.scroll_snap.as_ref().map(|container| {
+                    scroll_snap(container, &transform)
+                }),
+            });
+            Some(tree.scroll_frames.len() - 1)
+        }
+        _ => scroll_frame,
+    };
+
+    for area in &stacking_context.scroll_snap_areas {
+        add_scroll_snap_area(area, &transform, scroll_frame, tree);
+    }
+    for region in &stacking_context.touch_action_regions {
+        tree.touch_action_regions.push(touch_action_region(region, &transform));
+    }
+
+    for child in &stacking_context.children {
+        while let Some(item) = traversal.advance(stacking_context) {
+            add_item(item, &transform, scroll_frame, tree);
+        }
+        add_stacking_context(child, traversal, &transform, scroll_frame, tree);
+    }
+
+    while let Some(item) = traversal.advance(stacking_context) {
+        add_item(item, &transform, scroll_frame, tree);
+    }
+}
+
+fn add_item(item: &DisplayItem,
+            transform: &Matrix4D<f32>,
+            scroll_frame: Option<usize>,
+            tree: &mut HitTestTree) {
+    let base = item.base();
+    let cursor = match base.metadata.pointing {
+        Some(cursor) => cursor,
+        // `pointer-events` is `none`.
+        None => return,
+    };
+
+    let (interior, subpage) = match *item {
+        DisplayItem::BorderClass(ref border) => {
+            let widths = &border.border_widths;
+            let bounds = &border.base.bounds;
+            let interior = Rect::new(
+                Point2D::new(bounds.origin.x + widths.left, bounds.origin.y + widths.top),
+                Size2D::new(bounds.size.width - (widths.left + widths.right),
+                            bounds.size.height - (widths.top + widths.bottom)));
+            (Some(transform_rect(transform, &interior)), None)
+        }
+        // Box shadows can never be hit.
+        DisplayItem::BoxShadowClass(_) => return,
+        DisplayItem::IframeClass(ref iframe) => (None, Some(iframe.iframe)),
+        _ => (None, None),
+    };
+
+    tree.items.push(HitTestItem {
+        bounds: transform_rect(transform, &base.bounds),
+        clip: transform_rect(transform, &base.clip.main),
+        // The radii of transformed clips are left as they are.
+        rounded_clips: base.clip.complex.iter().map(|complex| {
+            HitTestRoundedClip {
+                rect: transform_rect(transform, &complex.rect),
+                radii: complex.radii.to_array(),
+            }
+        }).collect(),
+        interior: interior,
+        node: base.metadata.node.0,
+        cursor: cursor,
+        subpage: subpage,
+        scroll_frame: scroll_frame,
+    });
+}
+
+fn scroll_snap(container: &ScrollSnapContainer, transform: &Matrix4D<f32>) -> ScrollSnap {
+    fn strictness(type_: scroll_snap_type_x::T) -> Option<ScrollSnapStrictness> {
+        match type_ {
+            scroll_snap_type_x::T::none => None,
+            scroll_snap_type_x::T::mandatory => Some(ScrollSnapStrictness::Mandatory),
+            scroll_snap_type_x::T::proximity => Some(ScrollSnapStrictness::Proximity),
+        }
+    }
+
+    ScrollSnap {
+        x: strictness(container.type_x),
+        // Both axes have the same keywords.
+        y: strictness(match container.type_y {
+            scroll_snap_type_y::T::none => scroll_snap_type_x::T::none,
+            scroll_snap_type_y::T::mandatory => scroll_snap_type_x::T::mandatory,
+            scroll_snap_type_y::T::proximity => scroll_snap_type_x::T::proximity,
+        }),
+        scrollport: transform_rect(transform, &container.scrollport),
+        positions: vec![],
+    }
+}
+
+/// Adds the scroll position that aligns `area` with the scrollport of the scroll frame that it
+/// is in, if that snaps.
+fn add_scroll_snap_area(area: &ScrollSnapArea,
+                        transform: &Matrix4D<f32>,
+                        scroll_frame: Option<usize>,
+                        tree: &mut HitTestTree) {
+    let snap = match scroll_frame {
+        Some(index) => tree.scroll_frames[index].snap.as_mut(),
+        None => tree.viewport_snap.as_mut(),
+    };
+    let snap = match snap {
+        Some(snap) => snap,
+        None => return,
+    };
+
+    let rect = transform_rect(transform, &area.rect);
+    let scrollport = snap.scrollport;
+    let position = match area.align {
+        scroll_snap_align::T::none => return,
+        scroll_snap_align::T::start => {
+            Point2D::new(rect.origin.x - scrollport.origin.x, rect.origin.y - scrollport.origin.y)
+        }
+        scroll_snap_align::T::end => {
+            Point2D::new(rect.max_x() - scrollport.max_x(), rect.max_y() - scrollport.max_y())
+        }
+        scroll_snap_align::T::center => {
+            Point2D::new((rect.origin.x + rect.max_x() -
+                          (scrollport.origin.x + scrollport.max_x())).scale_by(0.5),
+                         (rect.origin.y + rect.max_y() -
+                          (scrollport.origin.y + scrollport.max_y())).scale_by(0.5))
+        }
+    };
+    snap.positions.push(position);
+}
+
+fn touch_action_region(region: &TouchActionRegion, transform: &Matrix4D<f32>)
+                       -> HitTestTouchActionRegion {
+    let (pan_x, pan_y, zoom) = match region.touch_action {
+        touch_action::T::auto | touch_action::T::manipulation => (true, true, true),
+        touch_action::T::none => (false, false, false),
+        touch_action::T::pan_x => (true, false, false),
+        touch_action::T::pan_y => (false, true, false),
+        touch_action::T::pan_x_pan_y => (true, true, false),
+    };
+    HitTestTouchActionRegion {
+        rect: transform_rect(transform, &region.rect),
+        allowed: AllowedTouchActions {
+            pan_x: pan_x,
+            pan_y: pan_y,
+            zoom: zoom,
+        },
+    }
+}
+
+/// Finds the `a` and `area` elements with an `href` of the document rooted at `root`, and maps
+/// the opaque addresses of the nodes inside them to the URLs that they link to.
+pub fn collect_links(root: ServoLayoutNode, document_url: &Url) -> HashMap<usize, String> {
+    // The base URL is given by the first `base` element with an `href`.
+    let base_url = root.traverse_preorder()
+                       .filter_map(|node| node.as_element())
+                       .filter(|element| is_html_element(element, &atom!("base")))
+                       .filter_map(|element| element.get_attr(&ns!(), &atom!("href"))
+                                                    .map(|href| href.to_owned()))
+                       .next()
+                       .and_then(|href| document_url.join(&href).ok())
+                       .unwrap_or_else(|| document_url.clone());
+    let mut links = HashMap::new();
+    add_links(root, None, &base_url, &mut links);
+    links
+}
+
+fn add_links(node: ServoLayoutNode,
+             link: Option<&String>,
+             base_url: &Url,
+             links: &mut HashMap<usize, String>) {
+    let own_link = node.as_element().and_then(|element| {
+        if !is_html_element(&element, &atom!("a")) && !is_html_element(&element, &atom!("area")) {
+            return None
+        }
+        element.get_attr(&ns!(), &atom!("href"))
+               .and_then(|href| base_url.join(href).ok())
+               .map(|url| url.to_string())
+    });
+    // Links cannot be nested, but if they are, the innermost one is followed.
+    let link = own_link.as_ref().or(link);
+    if let Some(link) = link {
+        links.insert(node.opaque().0, link.clone());
+    }
+    for child in node.children() {
+        add_links(child, link, base_url, links);
+    }
+}
+
+fn is_html_element(element: &ServoLayoutElement, name: &Atom) -> bool {
+    element.get_local_name() == *name && element.get_namespace() == ns!(html)
+}
+
+/// The bounding box of `rect` transformed into the coordinates of the page.
+fn transform_rect(transform: &Matrix4D<f32>, rect: &Rect<Au>) -> Rect<Au> {
+    // Unclipped items are clipped to a rect that would overflow if it were transformed.
+    if *rect == MAX_RECT {
+        return MAX_RECT
+    }
+    let transform_2d = Matrix2D::new(transform.m11, transform.m12,
+                                     transform.m21, transform.m22,
+                                     transform.m41, transform.m42);
+    let rect = transform_2d.transform_rect(&geometry::au_rect_to_f32_rect(*rect));
+    geometry::f32_rect_to_au_rect(rect)
+}
@@ -3,6 +3,7 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use flow::{self, AFFECTS_COUNTERS, Flow, HAS_COUNTER_AFFECTING_CHILDREN, IS_ABSOLUTELY_POSITIONED};
+use flow::IS_REFLOW_ROOT;
 use std::fmt;
 use std::sync::Arc;
 use style::computed_values::{display, float};
@@ -49,6 +50,10 @@ bitflags! {
         #[doc = "If this flag is set, we need to reflow the entire document. This is more or less a \
                  temporary hack to deal with cases that we don't handle incrementally yet."]
         const REFLOW_ENTIRE_DOCUMENT = 0x01,
+
+        #[doc = "If this flag is set, a reflow root needs to be reflowed, even if the root of the \
+                 flow tree does not."]
+        const REFLOW_REFLOW_ROOTS = 0x02,
     }
 }
 
@@ -187,11 +192,13 @@ pub fn compute_damage(old: Option<&Arc<ServoComputedValues>>, new: &ServoCompute
         get_inheritedtext.text_transform, get_inheritedtext.word_spacing,
         get_inheritedtext.overflow_wrap, get_inheritedtext.text_justify,
         get_inheritedtext.white_space, get_inheritedtext.word_break, get_text.text_overflow,
+        get_inheritedtext.tab_size,
         get_font.font_family, get_font.font_style, get_font.font_variant, get_font.font_weight,
-        get_font.font_size, get_font.font_stretch,
+        get_font.font_size, get_font.font_stretch, get_font.font_variant_ligatures,
+        get_font.font_feature_settings, get_font._servo_lang,
         get_inheritedbox.direction, get_inheritedbox.writing_mode,
         get_inheritedbox.text_orientation,
-        get_text.text_decoration, get_text.unicode_bidi,
+        get_text.text_decoration_line, get_text.unicode_bidi,
         get_inheritedtable.empty_cells, get_inheritedtable.caption_side,
         get_column.column_width, get_column.column_count
     ]) || (new.get_box().display == display::T::inline &&
@@ -219,6 +226,7 @@ pub fn compute_damage(old: Option<&Arc<ServoComputedValues>>, new: &ServoCompute
         get_inheritedtable.border_collapse,
         get_inheritedtable.border_spacing,
         get_column.column_gap,
+        get_box.shape_outside, get_box.contain,
         get_position.flex_direction,
         get_position.flex_basis,
         get_position.order
@@ -239,6 +247,9 @@ pub fn compute_damage(old: Option<&Arc<ServoComputedValues>>, new: &ServoCompute
         get_border.border_bottom_style, get_border.border_left_style,
         get_border.border_top_left_radius, get_border.border_top_right_radius,
         get_border.border_bottom_left_radius, get_border.border_bottom_right_radius,
+        get_border.border_image_source, get_border.border_image_slice,
+        get_border.border_image_width, get_border.border_image_outset,
+        get_border.border_image_repeat,
         get_position.z_index, get_box._servo_overflow_clip_box,
         get_inheritedtext._servo_text_decorations_in_effect,
         get_pointing.cursor, get_pointing.pointer_events,
@@ -253,7 +264,16 @@ pub fn compute_damage(old: Option<&Arc<ServoComputedValues>>, new: &ServoCompute
 
     // If the layer requirements of this flow have changed due to the value
     // of the transform, then reflow is required to rebuild the layers.
-    if old.transform_requires_layer() != new.transform_requires_layer() {
+    if old.transform_requires_layer() != new.transform_requires_layer() ||
+            old.will_change_requires_layer() != new.will_change_requires_layer() {
+        damage.insert(RestyleDamage::rebuild_and_reflow());
+    }
+
+    // Fixed-position descendants are attached to their containing block, and reflow roots
+    // flagged, when the flow tree is built.
+    if old.is_containing_block_for_fixed_descendants() !=
+            new.is_containing_block_for_fixed_descendants() ||
+            old.is_reflow_root() != new.is_reflow_root() {
         damage.insert(RestyleDamage::rebuild_and_reflow());
     }
 
@@ -284,13 +304,26 @@ impl<'a> LayoutDamageComputation for &'a mut Flow {
                 flow::mut_base(kid).restyle_damage.insert(
                     parent_damage.damage_for_child(is_absolutely_positioned,
                                                    child_is_absolutely_positioned));
+                let kid_damage = flow::base(kid).restyle_damage;
                 {
                     let kid: &mut Flow = kid;
                     special_damage.insert(kid.compute_layout_damage());
                 }
+
+                // The layout of a reflow root only depends on its own style, not on that of its
+                // descendants, so only the rest of the damage of its subtree goes on up.
+                let kid_base = flow::base(kid);
+                let damage_from_kid = if kid_base.flags.contains(IS_REFLOW_ROOT) {
+                    if kid_base.restyle_damage.intersects(REFLOW_OUT_OF_FLOW | REFLOW) {
+                        special_damage.insert(REFLOW_REFLOW_ROOTS)
+                    }
+                    kid_damage | (kid_base.restyle_damage & (REPAINT | STORE_OVERFLOW |
+                                                             RESOLVE_GENERATED_CONTENT))
+                } else {
+                    kid_base.restyle_damage
+                };
                 self_base.restyle_damage
-                         .insert(flow::base(kid).restyle_damage.damage_for_parent(
-                                 child_is_absolutely_positioned));
+                         .insert(damage_from_kid.damage_for_parent(child_is_absolutely_positioned));
 
                 has_counter_affecting_children = has_counter_affecting_children ||
                     flow::base(kid).flags.intersects(AFFECTS_COUNTERS |
@@ -15,16 +15,18 @@ use flow::OpaqueFlow;
 use flow::{CONTAINS_TEXT_OR_REPLACED_FRAGMENTS, EarlyAbsolutePositionInfo, MutableFlowUtils};
 use flow::{self, BaseFlow, Flow, FlowClass, ForceNonfloatedFlag, IS_ABSOLUTELY_POSITIONED};
 use flow_ref;
-use fragment::SpecificFragmentInfo;
+use fragment::{ScannedTextFragmentInfo, SpecificFragmentInfo};
 use fragment::{CoordinateSystem, Fragment, FragmentBorderBoxIterator, Overflow};
 use gfx::display_list::{OpaqueNode, StackingContext, StackingContextId};
 use gfx::font::FontMetrics;
 use gfx::font_context::FontContext;
+use gfx::text::glyph::ByteIndex;
+use gfx::text::text_run::TextRun;
 use incremental::{BUBBLE_ISIZES, REFLOW, REFLOW_OUT_OF_FLOW, REPAINT, RESOLVE_GENERATED_CONTENT};
 use layout_debug;
 use model::IntrinsicISizesContribution;
 use range::{Range, RangeIndex};
-use std::cmp::max;
+use std::cmp::{max, min};
 use std::collections::VecDeque;
 use std::sync::Arc;
 use std::{fmt, i32, isize, mem};
@@ -183,6 +185,218 @@ int_range_index! {
     struct FragmentIndex(isize)
 }
 
+/// The lines of an inline flow that flow construction replaced, which the flow that replaced it
+/// can reuse, where it has the same fragments. This makes changes to the text of a block, like
+/// typing in it, only break the lines around the change.
+#[derive(RustcEncodable)]
+pub struct PreviousLines {
+    /// The fragments of the flow, as split up by line breaking.
+    fragments: Vec<Fragment>,
+    /// The lines of the flow.
+    lines: Vec<Line>,
+    /// The inline size of the flow.
+    inline_size: Au,
+    /// The indentation of the first line.
+    first_line_indentation: Au,
+    /// The minimum block-size above the baseline for each line.
+    minimum_block_size_above_baseline: Au,
+    /// The minimum depth below the baseline for each line.
+    minimum_depth_below_baseline: Au,
+}
+
+impl PreviousLines {
+    /// Works out which of these lines `flow` can reuse.
+    fn line_reuse_for(self, flow: &InlineFlow) -> Option<LineReuse> {
+        // The lines only stay the same if nothing they depend on outside of the fragments has
+        // changed.
+        if flow.base.floats.is_present() || !flow.base.children.is_empty() ||
+                flow.base.position.size.inline != self.inline_size ||
+                flow.first_line_indentation != self.first_line_indentation ||
+                flow.minimum_block_size_above_baseline != self.minimum_block_size_above_baseline ||
+                flow.minimum_depth_below_baseline != self.minimum_depth_below_baseline ||
+                !flow.fragments.fragments.iter().all(Fragment::is_scanned_text_fragment) {
+            return None
+        }
+
+        // Gather the pieces that line breaking split each old fragment into, and find the
+        // fragments that changed.
+        let mut old_units: Vec<(usize, usize)> = vec![];
+        for (index, fragment) in self.fragments.iter().enumerate() {
+            match old_units.last_mut() {
+                Some(&mut (start, ref mut end)) if is_piece_of(&self.fragments[start],
+                                                               fragment) => {
+                    *end = index + 1;
+                    continue
+                }
+                _ => {}
+            }
+            old_units.push((index, index + 1))
+        }
+        let new_fragments = &flow.fragments.fragments;
+        let (first_changed_unit, unchanged_units_at_end) = {
+            let old_fragments = &self.fragments;
+            let unit_is_unchanged = |&(start, end): &(usize, usize), new_fragment: &Fragment| {
+                is_unchanged(&old_fragments[start..end], new_fragment)
+            };
+            let unit_count = min(old_units.len(), new_fragments.len());
+            let first_changed_unit = (0..unit_count).find(|&index| {
+                !unit_is_unchanged(&old_units[index], &new_fragments[index])
+            }).unwrap_or(unit_count);
+            let unchanged_units_at_end =
+                (0..unit_count - first_changed_unit).take_while(|&index| {
+                    unit_is_unchanged(&old_units[old_units.len() - 1 - index],
+                                      &new_fragments[new_fragments.len() - 1 - index])
+                }).count();
+            (first_changed_unit, unchanged_units_at_end)
+        };
+
+        // The line that holds the last unchanged fragment before the change may now hold some
+        // of the changed ones too, so line breaking starts over at its start.
+        let first_changed_piece = old_units.get(first_changed_unit)
+                                           .map_or(self.fragments.len(), |unit| unit.0);
+        let first_reflowed_line = if first_changed_piece == 0 {
+            0
+        } else {
+            self.lines.iter().position(|line| {
+                line.range.end().to_usize() >= first_changed_piece
+            }).unwrap_or(self.lines.len() - 1)
+        };
+        let first_reflowed_piece = self.lines[first_reflowed_line].range.begin().to_usize();
+
+        // Line breaking can stop at the start of any later line that is made of unchanged
+        // fragments only, as the lines from there on stay the same.
+        let first_unchanged_piece_at_end = if unchanged_units_at_end == 0 {
+            self.fragments.len()
+        } else {
+            old_units[old_units.len() - unchanged_units_at_end].0
+        };
+        let mut resync_points = vec![];
+        for (line_index, line) in self.lines.iter().enumerate().skip(first_reflowed_line + 1) {
+            let first_piece = line.range.begin().to_usize();
+            if first_piece < first_unchanged_piece_at_end || first_piece >= self.fragments.len() {
+                continue
+            }
+            let unit_index = old_units.iter().position(|&(_, end)| first_piece < end).unwrap();
+            let new_fragment = &new_fragments[new_fragments.len() - (old_units.len() - unit_index)];
+            let old_unit_end = text_end(&self.fragments[old_units[unit_index].1 - 1]);
+            if let (&SpecificFragmentInfo::ScannedText(ref old_info),
+                    &SpecificFragmentInfo::ScannedText(ref new_info)) =
+                    (&self.fragments[first_piece].specific, &new_fragment.specific) {
+                resync_points.push(ResyncPoint {
+                    run: new_info.run.clone(),
+                    offset: new_info.range.end() - (old_unit_end - old_info.range.begin()),
+                    old_line_index: line_index - first_reflowed_line,
+                })
+            }
+        }
+
+        let mut lines_before = self.lines;
+        let mut lines_after = lines_before.split_off(first_reflowed_line);
+        for line in &mut lines_after {
+            line.range.shift_by(FragmentIndex(-(first_reflowed_piece as isize)));
+        }
+        let mut fragments_after = self.fragments;
+        let fragments_before = fragments_after.drain(..first_reflowed_piece).collect();
+        let fragments_to_reflow =
+            fragments_after[..(first_changed_piece - first_reflowed_piece)].to_vec();
+        Some(LineReuse {
+            fragments_before: fragments_before,
+            lines_before: lines_before,
+            fragments_to_reflow: fragments_to_reflow,
+            first_changed_fragment: first_changed_unit,
+            resync_points: resync_points,
+            fragments_after: fragments_after,
+            lines_after: lines_after,
+        })
+    }
+}
+
+/// Returns true if `fragment` is a piece of the same fragment as `first_piece`, which line
+/// breaking split up.
+fn is_piece_of(first_piece: &Fragment, fragment: &Fragment) -> bool {
+    match (&first_piece.specific, &fragment.specific) {
+        (&SpecificFragmentInfo::ScannedText(ref first_info),
+         &SpecificFragmentInfo::ScannedText(ref info)) => {
+            first_piece.node == fragment.node && first_piece.pseudo == fragment.pseudo &&
+                util::arc_ptr_eq(&first_info.run, &info.run)
+        }
+        _ => false,
+    }
+}
+
+/// The end of the text of a text fragment in its run, including stripped whitespace.
+fn text_end(fragment: &Fragment) -> ByteIndex {
+    match fragment.specific {
+        SpecificFragmentInfo::ScannedText(ref info) => info.range_end_including_stripped_whitespace,
+        _ => ByteIndex(0),
+    }
+}
+
+/// Returns true if `new_fragment` has the same text and styles as the old fragment that line
+/// breaking split into `old_pieces`.
+fn is_unchanged(old_pieces: &[Fragment], new_fragment: &Fragment) -> bool {
+    let old_fragment = &old_pieces[0];
+    if old_fragment.node != new_fragment.node || old_fragment.pseudo != new_fragment.pseudo ||
+            !util::arc_ptr_eq(&old_fragment.style, &new_fragment.style) ||
+            !inline_contexts_are_equal(&old_fragment.inline_context,
+                                       &new_fragment.inline_context) {
+        return false
+    }
+    match (&old_fragment.specific, &new_fragment.specific) {
+        (&SpecificFragmentInfo::ScannedText(ref old_info),
+         &SpecificFragmentInfo::ScannedText(ref new_info)) => {
+            // Fragments with the insertion point are laid out again, as it moves.
+            let old_text = &old_info.run.text[old_info.range.begin().to_usize()..
+                                              text_end(&old_pieces[old_pieces.len() - 1])
+                                                  .to_usize()];
+            let new_text = &new_info.run.text[new_info.range.begin().to_usize()..
+                                              new_info.range.end().to_usize()];
+            old_text == new_text && old_info.selected() == new_info.selected() &&
+                new_info.insertion_point.is_none() &&
+                old_pieces.iter().all(|piece| match piece.specific {
+                    SpecificFragmentInfo::ScannedText(ref info) => info.insertion_point.is_none(),
+                    _ => false,
+                })
+        }
+        _ => false,
+    }
+}
+
+/// The lines of an inline flow that can be reused from the flow that it replaced.
+struct LineReuse {
+    /// The fragments of the lines before the changed fragments.
+    fragments_before: Vec<Fragment>,
+    /// The lines before the changed fragments.
+    lines_before: Vec<Line>,
+    /// The unchanged fragments to break into lines again before the changed ones.
+    fragments_to_reflow: Vec<Fragment>,
+    /// The index of the first changed fragment of the new flow.
+    first_changed_fragment: usize,
+    /// Where the lines that may be reused after the changed fragments start.
+    resync_points: Vec<ResyncPoint>,
+    /// The fragments of the old flow from the first line that was broken again.
+    fragments_after: Vec<Fragment>,
+    /// The lines of the old flow from the first line that was broken again.
+    lines_after: Vec<Line>,
+}
+
+/// The start of a line of a previous layout, in the text of the fragments of this one.
+struct ResyncPoint {
+    /// The text run of the new fragment the line starts in.
+    run: Arc<TextRun>,
+    /// The offset in that run at which the line starts.
+    offset: ByteIndex,
+    /// The index of the line in `LineReuse::lines_after`.
+    old_line_index: usize,
+}
+
+impl ResyncPoint {
+    /// Returns true if a text fragment starts where this line started.
+    fn is_start_of(&self, info: &ScannedTextFragmentInfo) -> bool {
+        util::arc_ptr_eq(&self.run, &info.run) && info.range.begin() == self.offset
+    }
+}
+
 /// Arranges fragments into lines, splitting them up as necessary.
 struct LineBreaker {
     /// The floats we need to flow around.
@@ -208,6 +422,19 @@ struct LineBreaker {
     /// The minimum depth below the baseline for each line, as specified by the line height and
     /// font style.
     minimum_depth_below_baseline: Au,
+    /// The metrics of the font of the block, which `vertical-align` values are relative to.
+    block_font_metrics: ParentFontMetrics,
+    /// The starts of the lines of the previous layout that can be reused as they are, if a line
+    /// of this layout starts at one of them.
+    resync_points: Vec<ResyncPoint>,
+    /// The line of the previous layout at which a line of this layout started, if any. Line
+    /// breaking stops there, as the rest of the lines are the same as in the previous layout.
+    resynced_at: Option<usize>,
+    /// The index of the first line that was not reused from the previous layout.
+    first_new_line: usize,
+    /// The index of the first line that was reused from the end of the previous layout, or the
+    /// number of lines if none was.
+    first_reused_line_at_end: usize,
 }
 
 impl LineBreaker {
@@ -215,7 +442,8 @@ impl LineBreaker {
     fn new(float_context: Floats,
            first_line_indentation: Au,
            minimum_block_size_above_baseline: Au,
-           minimum_depth_below_baseline: Au)
+           minimum_depth_below_baseline: Au,
+           block_font_metrics: ParentFontMetrics)
            -> LineBreaker {
         LineBreaker {
             new_fragments: Vec::new(),
@@ -230,6 +458,11 @@ impl LineBreaker {
             first_line_indentation: first_line_indentation,
             minimum_block_size_above_baseline: minimum_block_size_above_baseline,
             minimum_depth_below_baseline: minimum_depth_below_baseline,
+            block_font_metrics: block_font_metrics,
+            resync_points: Vec::new(),
+            resynced_at: None,
+            first_new_line: 0,
+            first_reused_line_at_end: 0,
         }
     }
 
@@ -249,23 +482,65 @@ impl LineBreaker {
                                                        self.minimum_depth_below_baseline))
     }
 
-    /// Reflows fragments for the given inline flow.
-    fn scan_for_lines(&mut self, flow: &mut InlineFlow, layout_context: &LayoutContext) {
+    /// Reflows fragments for the given inline flow, reusing the lines of the flow it replaced
+    /// that `line_reuse` describes, if any.
+    fn scan_for_lines(&mut self,
+                      flow: &mut InlineFlow,
+                      line_reuse: Option<LineReuse>,
+                      layout_context: &LayoutContext) {
         self.reset_scanner();
 
         // Create our fragment iterator.
         debug!("LineBreaker: scanning for lines, {} fragments", flow.fragments.len());
         let mut old_fragments = mem::replace(&mut flow.fragments, InlineFragments::new());
-        let old_fragment_iter = old_fragments.fragments.into_iter();
-
-        // TODO(pcwalton): This would likely be better as a list of dirty line indices. That way we
-        // could resynchronize if we discover during reflow that all subsequent fragments must have
-        // the same position as they had in the previous reflow. I don't know how common this case
-        // really is in practice, but it's probably worth handling.
-        self.lines = Vec::new();
+        let fragments = mem::replace(&mut old_fragments.fragments, vec![]);
+
+        let mut reused_lines_at_end = None;
+        let fragments_to_reflow = match line_reuse {
+            None => fragments,
+            Some(line_reuse) => {
+                debug!("LineBreaker: reusing {} lines before the changed fragments",
+                       line_reuse.lines_before.len());
+                self.new_fragments = line_reuse.fragments_before;
+                self.lines = line_reuse.lines_before;
+                if let Some(cur_b) = self.lines.last().map(|last_line| {
+                    last_line.bounds.start.b + last_line.bounds.size.block
+                }) {
+                    self.cur_b = cur_b
+                }
+                self.resync_points = line_reuse.resync_points;
+                reused_lines_at_end = Some((line_reuse.fragments_after, line_reuse.lines_after));
+                line_reuse.fragments_to_reflow
+                          .into_iter()
+                          .chain(fragments.into_iter().skip(line_reuse.first_changed_fragment))
+                          .collect()
+            }
+        };
+        self.first_new_line = self.lines.len();
 
         // Do the reflow.
-        self.reflow_fragments(old_fragment_iter, flow, layout_context);
+        self.reflow_fragments(fragments_to_reflow.into_iter(), flow, layout_context);
+
+        // If a line started where a line of the previous layout did, the rest of the lines are
+        // those of the previous layout, moved in the block direction.
+        self.first_reused_line_at_end = self.lines.len();
+        if let (Some(old_line_index), Some((mut old_fragments, mut old_lines))) =
+                (self.resynced_at, reused_lines_at_end) {
+            let first_old_fragment = old_lines[old_line_index].range.begin();
+            let delta = self.cur_b - old_lines[old_line_index].bounds.start.b;
+            debug!("LineBreaker: reusing {} lines after the changed fragments",
+                   old_lines.len() - old_line_index);
+            let first_new_fragment = FragmentIndex(self.new_fragments.len() as isize);
+            for mut line in old_lines.drain(old_line_index..) {
+                line.range.shift_by(first_new_fragment - first_old_fragment);
+                line.bounds.start.b = line.bounds.start.b + delta;
+                self.lines.push(line);
+            }
+            for mut fragment in old_fragments.drain(first_old_fragment.to_usize()..) {
+                fragment.border_box.start.b = fragment.border_box.start.b + delta;
+                self.new_fragments.push(fragment);
+            }
+        }
 
         // Perform unicode bidirectional layout.
         let para_level = flow.base.writing_mode.to_bidi_level();
@@ -318,6 +593,9 @@ impl LineBreaker {
 
             // Try to append the fragment.
             self.reflow_fragment(fragment, flow, layout_context);
+            if self.resynced_at.is_some() {
+                return
+            }
         }
 
         if !self.pending_line_is_empty() {
@@ -398,6 +676,10 @@ impl LineBreaker {
 
         self.pending_line.bounds.size.inline +=
             fragment.border_box.size.inline - old_fragment_inline_size;
+
+        // Preserved spaces at the end of a `white-space: pre-wrap` line hang: they stay in the
+        // fragment, but neither overflow the line nor take part in alignment.
+        self.pending_line.bounds.size.inline -= fragment.hanging_whitespace_inline_size();
     }
 
     // FIXME(eatkinson): this assumes that the tallest fragment in the line determines the line
@@ -406,7 +688,11 @@ impl LineBreaker {
                                    -> InlineMetrics {
         if !new_fragment.is_vertically_aligned_to_top_or_bottom() {
             let fragment_inline_metrics = new_fragment.inline_metrics(layout_context);
-            self.pending_line.inline_metrics.max(&fragment_inline_metrics)
+            let baseline_shift = baseline_shift_for_fragment(new_fragment,
+                                                             &fragment_inline_metrics,
+                                                             &self.block_font_metrics,
+                                                             layout_context);
+            self.pending_line.inline_metrics.max(&fragment_inline_metrics.raised_by(baseline_shift))
         } else {
             self.pending_line.inline_metrics
         }
@@ -446,7 +732,7 @@ impl LineBreaker {
         };
 
         // Try to place the fragment between floats.
-        let line_bounds = self.floats.place_between_floats(&PlacementInfo {
+        let line_bounds = self.floats.place_line_between_floats(&PlacementInfo {
             size: LogicalSize::new(self.floats.writing_mode,
                                    placement_inline_size,
                                    first_fragment.border_box.size.block),
@@ -531,6 +817,23 @@ impl LineBreaker {
         // Also, determine whether we can legally break the line before, or inside, this fragment.
         let fragment_is_line_break_opportunity = if self.pending_line_is_empty() {
             fragment.strip_leading_whitespace_if_necessary();
+            if !self.lines.is_empty() {
+                let old_line_index = match fragment.specific {
+                    SpecificFragmentInfo::ScannedText(ref info) => {
+                        self.resync_points.iter().find(|resync_point| {
+                            resync_point.is_start_of(info)
+                        }).map(|resync_point| resync_point.old_line_index)
+                    }
+                    _ => None,
+                };
+                if let Some(old_line_index) = old_line_index {
+                    debug!("LineBreaker: line {} starts like line {} of the previous layout",
+                           self.lines.len(),
+                           old_line_index);
+                    self.resynced_at = Some(old_line_index);
+                    return
+                }
+            }
             let (line_bounds, _) = self.initial_line_placement(flow, &fragment, self.cur_b);
             self.pending_line.bounds.start = line_bounds.start;
             self.pending_line.green_zone = line_bounds.size;
@@ -827,10 +1130,18 @@ pub struct InlineFlow {
     /// font style.
     pub minimum_depth_below_baseline: Au,
 
+    /// The metrics of the font of the block that this flow is in, which the `vertical-align`
+    /// values of its fragments are relative to.
+    pub block_font_metrics: ParentFontMetrics,
+
     /// The amount of indentation to use on the first line. This is determined by our block parent
     /// (because percentages are relative to the containing block, and we aren't in a position to
     /// compute things relative to our parent's containing block).
     pub first_line_indentation: Au,
+
+    /// The lines of the flow that this one replaced, if this flow may be able to reuse some of
+    /// them in its next layout.
+    pub previous_lines: Option<PreviousLines>,
 }
 
 impl InlineFlow {
@@ -841,7 +1152,9 @@ impl InlineFlow {
             lines: Vec::new(),
             minimum_block_size_above_baseline: Au(0),
             minimum_depth_below_baseline: Au(0),
+            block_font_metrics: ParentFontMetrics::new(Au(0), Au(0), Au(0)),
             first_line_indentation: Au(0),
+            previous_lines: None,
         };
 
         if flow.fragments.fragments.iter().any(Fragment::is_unscanned_generated_content) {
@@ -851,6 +1164,25 @@ impl InlineFlow {
         flow
     }
 
+    /// Takes the lines of this flow, for the flow that replaces it to reuse where it has the same
+    /// fragments. Only the lines of text that were laid out without floats around can be reused.
+    pub fn take_previous_lines(&mut self) -> Option<PreviousLines> {
+        if self.lines.is_empty() || self.base.floats.is_present() ||
+                !self.base.children.is_empty() ||
+                self.base.restyle_damage.intersects(REFLOW_OUT_OF_FLOW | REFLOW) ||
+                !self.fragments.fragments.iter().all(Fragment::is_scanned_text_fragment) {
+            return None
+        }
+        Some(PreviousLines {
+            fragments: mem::replace(&mut self.fragments.fragments, vec![]),
+            lines: mem::replace(&mut self.lines, vec![]),
+            inline_size: self.base.position.size.inline,
+            first_line_indentation: self.first_line_indentation,
+            minimum_block_size_above_baseline: self.minimum_block_size_above_baseline,
+            minimum_depth_below_baseline: self.minimum_depth_below_baseline,
+        })
+    }
+
     /// Sets fragment positions in the inline direction based on alignment for one line. This
     /// performs text justification if mandated by the style.
     fn set_inline_fragment_positions(fragments: &mut InlineFragments,
@@ -1005,73 +1337,37 @@ impl InlineFlow {
     /// Sets final fragment positions in the block direction for one line.
     fn set_block_fragment_positions(fragments: &mut InlineFragments,
                                     line: &Line,
-                                    minimum_block_size_above_baseline: Au,
-                                    minimum_depth_below_baseline: Au,
+                                    block_font_metrics: &ParentFontMetrics,
                                     layout_context: &LayoutContext) {
+        let line_baseline = line.bounds.start.b + line.inline_metrics.block_size_above_baseline;
         for fragment_index in line.range.each_index() {
-            // If any of the inline styles say `top` or `bottom`, adjust the vertical align
-            // appropriately.
-            //
-            // FIXME(#5624, pcwalton): This passes our current reftests but isn't the right thing
-            // to do.
             let fragment = fragments.get_mut(fragment_index.to_usize());
             let fragment_inline_metrics = fragment.inline_metrics(layout_context);
-            let mut block_start = line.bounds.start.b +
-                line.inline_metrics.block_size_above_baseline -
-                fragment_inline_metrics.ascent;
-
-            for style in fragment.inline_styles() {
-                match style.get_box().vertical_align {
-                    vertical_align::T::baseline => {}
-                    vertical_align::T::middle => {}
-                    vertical_align::T::sub => {
-                        let sub_offset =
-                            (minimum_block_size_above_baseline +
-                             minimum_depth_below_baseline).scale_by(FONT_SUBSCRIPT_OFFSET_RATIO);
-                        block_start = block_start + sub_offset
-                    }
-                    vertical_align::T::super_ => {
-                        let super_offset =
-                            (minimum_block_size_above_baseline +
-                             minimum_depth_below_baseline).scale_by(FONT_SUPERSCRIPT_OFFSET_RATIO);
-                        block_start = block_start - super_offset
-                    }
-                    vertical_align::T::text_top => {
-                        block_start = line.bounds.start.b +
-                            line.inline_metrics.block_size_above_baseline -
-                            minimum_block_size_above_baseline
-                    }
-                    vertical_align::T::text_bottom => {
-                        block_start = line.bounds.start.b +
-                            line.inline_metrics.block_size_above_baseline +
-                            minimum_depth_below_baseline -
-                            fragment.border_box.size.block
-                    }
-                    vertical_align::T::top => {
-                        block_start = line.bounds.start.b
-                    }
-                    vertical_align::T::bottom => {
-                        block_start = line.bounds.start.b + line.bounds.size.block -
-                            fragment.border_box.size.block
-                    }
-                    vertical_align::T::LengthOrPercentage(LengthOrPercentage::Length(length)) => {
-                        block_start = block_start - length
-                    }
-                    vertical_align::T::LengthOrPercentage(LengthOrPercentage::Percentage(
-                            percentage)) => {
-                        let line_height = fragment.calculate_line_height(layout_context);
-                        let length = line_height.scale_by(percentage);
-                        block_start = block_start - length
-                    }
-                    vertical_align::T::LengthOrPercentage(LengthOrPercentage::Calc(calc)) => {
-                        let line_height = fragment.calculate_line_height(layout_context);
-                        let percentage_length = line_height.scale_by(calc.percentage());
-                        block_start = block_start - percentage_length - calc.length()
-                    }
+
+            // FIXME(#5624, pcwalton): `top` and `bottom` align the whole fragment with the line,
+            // even if they are set on an inline box that it is in that other values shift.
+            let baseline = match fragment.inline_styles().map(|style| {
+                style.get_box().vertical_align
+            }).find(|vertical_align| {
+                *vertical_align == vertical_align::T::top ||
+                    *vertical_align == vertical_align::T::bottom
+            }) {
+                Some(vertical_align::T::top) => {
+                    line.bounds.start.b + fragment_inline_metrics.block_size_above_baseline
                 }
-            }
+                Some(_) => {
+                    line.bounds.start.b + line.bounds.size.block -
+                        fragment_inline_metrics.depth_below_baseline
+                }
+                None => {
+                    line_baseline - baseline_shift_for_fragment(fragment,
+                                                                &fragment_inline_metrics,
+                                                                block_font_metrics,
+                                                                layout_context)
+                }
+            };
 
-            fragment.border_box.start.b = block_start;
+            fragment.border_box.start.b = baseline - fragment_inline_metrics.ascent;
             fragment.update_late_computed_block_position_if_necessary();
         }
     }
@@ -1152,7 +1448,8 @@ impl InlineFlow {
             match (display_value, vertical_align_value) {
                 (display::T::inline, vertical_align::T::top) |
                 (display::T::block, vertical_align::T::top) |
-                (display::T::inline_block, vertical_align::T::top) if
+                (display::T::inline_block, vertical_align::T::top) |
+                (display::T::ruby, vertical_align::T::top) if
                         inline_metrics.block_size_above_baseline >= Au(0) => {
                     *largest_block_size_for_top_fragments =
                         max(*largest_block_size_for_top_fragments,
@@ -1161,7 +1458,8 @@ impl InlineFlow {
                 }
                 (display::T::inline, vertical_align::T::bottom) |
                 (display::T::block, vertical_align::T::bottom) |
-                (display::T::inline_block, vertical_align::T::bottom) if
+                (display::T::inline_block, vertical_align::T::bottom) |
+                (display::T::ruby, vertical_align::T::bottom) if
                         inline_metrics.depth_below_baseline >= Au(0) => {
                     *largest_block_size_for_bottom_fragments =
                         max(*largest_block_size_for_bottom_fragments,
@@ -1362,12 +1660,13 @@ impl Flow for InlineFlow {
 
         {
             let this = &mut *self;
+            let block_size = this.base.block_container_explicit_block_size;
             for fragment in this.fragments.fragments.iter_mut() {
                 let border_collapse = fragment.style.get_inheritedtable().border_collapse;
                 fragment.compute_border_and_padding(inline_size, border_collapse);
                 fragment.compute_block_direction_margins(inline_size);
                 fragment.compute_inline_direction_margins(inline_size);
-                fragment.assign_replaced_inline_size_if_necessary(inline_size);
+                fragment.assign_replaced_inline_size_if_necessary(inline_size, block_size);
             }
         }
 
@@ -1406,10 +1705,13 @@ impl Flow for InlineFlow {
             fragment.assign_replaced_block_size_if_necessary(containing_block_block_size);
         }
 
-        // Reset our state, so that we handle incremental reflow correctly.
-        //
-        // TODO(pcwalton): Do something smarter, like Gecko and WebKit?
+        // Reset our state, so that we handle incremental reflow correctly, reusing what we can
+        // of the lines of the flow that this one replaced.
         self.lines.clear();
+        let previous_lines = self.previous_lines.take();
+        let line_reuse = previous_lines.and_then(|previous_lines| {
+            previous_lines.line_reuse_for(self)
+        });
 
         // Determine how much indentation the first line wants.
         let mut indentation = if self.fragments.is_empty() {
@@ -1422,13 +1724,21 @@ impl Flow for InlineFlow {
         let mut scanner = LineBreaker::new(self.base.floats.clone(),
                                            indentation,
                                            self.minimum_block_size_above_baseline,
-                                           self.minimum_depth_below_baseline);
-        scanner.scan_for_lines(self, layout_context);
-
+                                           self.minimum_depth_below_baseline,
+                                           self.block_font_metrics);
+        scanner.scan_for_lines(self, line_reuse, layout_context);
 
-        // Now, go through each line and lay out the fragments inside.
+        // Now, go through each line and lay out the fragments inside. The fragments of the reused
+        // lines are already in place.
         let line_count = self.lines.len();
-        for (line_index, line) in self.lines.iter_mut().enumerate() {
+        if scanner.first_new_line != 0 {
+            indentation = Au(0)
+        }
+        for (line_index, line) in self.lines
+                                      .iter_mut()
+                                      .enumerate()
+                                      .take(scanner.first_reused_line_at_end)
+                                      .skip(scanner.first_new_line) {
             // Lay out fragments in the inline direction, and justify them if necessary.
             InlineFlow::set_inline_fragment_positions(&mut self.fragments,
                                                       line,
@@ -1439,8 +1749,7 @@ impl Flow for InlineFlow {
             // Compute the final positions in the block direction of each fragment.
             InlineFlow::set_block_fragment_positions(&mut self.fragments,
                                                      line,
-                                                     self.minimum_block_size_above_baseline,
-                                                     self.minimum_depth_below_baseline,
+                                                     &self.block_font_metrics,
                                                      layout_context);
 
             // This is used to set the block-start position of the next line in the next iteration
@@ -1564,7 +1873,8 @@ impl Flow for InlineFlow {
                 fragment.stacking_relative_content_box(&stacking_relative_border_box);
             let mut clip = self.base.clip.clone();
             fragment.adjust_clipping_region_for_children(&mut clip,
-                                                         &stacking_relative_border_box);
+                                                         &stacking_relative_border_box,
+                                                         None);
             let is_positioned = fragment.is_positioned();
             match fragment.specific {
                 SpecificFragmentInfo::InlineBlock(ref mut info) => {
@@ -1837,6 +2147,16 @@ impl InlineMetrics {
         self.block_size_above_baseline + self.depth_below_baseline
     }
 
+    /// Returns these metrics for a fragment whose baseline is raised by `shift`, relative to the
+    /// baseline that it would otherwise have.
+    pub fn raised_by(&self, shift: Au) -> InlineMetrics {
+        InlineMetrics {
+            block_size_above_baseline: self.block_size_above_baseline + shift,
+            depth_below_baseline: self.depth_below_baseline - shift,
+            ascent: self.ascent + shift,
+        }
+    }
+
     pub fn max(&self, other: &InlineMetrics) -> InlineMetrics {
         InlineMetrics {
             block_size_above_baseline: max(self.block_size_above_baseline,
@@ -1847,6 +2167,97 @@ impl InlineMetrics {
     }
 }
 
+/// The metrics of the font of an element that the `vertical-align` values of the boxes inside it
+/// are relative to. See CSS 2.1 § 10.8.1.
+#[derive(Clone, Copy, Debug, RustcEncodable)]
+pub struct ParentFontMetrics {
+    pub ascent: Au,
+    pub descent: Au,
+    pub x_height: Au,
+}
+
+impl ParentFontMetrics {
+    pub fn new(ascent: Au, descent: Au, x_height: Au) -> ParentFontMetrics {
+        ParentFontMetrics {
+            ascent: ascent,
+            descent: descent,
+            x_height: x_height,
+        }
+    }
+
+    #[inline]
+    pub fn from_font_metrics(font_metrics: &FontMetrics) -> ParentFontMetrics {
+        ParentFontMetrics::new(font_metrics.ascent, font_metrics.descent, font_metrics.x_height)
+    }
+
+    pub fn for_style(font_context: &mut FontContext, style: &ServoComputedValues)
+                     -> ParentFontMetrics {
+        let font_metrics = text::font_metrics_for_style(font_context, style.get_font_arc());
+        ParentFontMetrics::from_font_metrics(&font_metrics)
+    }
+}
+
+/// Returns how far the `vertical-align` values of `fragment` and of the inline boxes that it is
+/// in raise its baseline above the baseline of its line, given the inline metrics of the
+/// fragment and the font metrics of the block. `top` and `bottom`, which align with the line
+/// itself rather than with the parent box, are left to the caller.
+fn baseline_shift_for_fragment(fragment: &Fragment,
+                               fragment_inline_metrics: &InlineMetrics,
+                               block_font_metrics: &ParentFontMetrics,
+                               layout_context: &LayoutContext)
+                               -> Au {
+    // The styles are those of the fragment and then those of the inline boxes that it is in,
+    // from the innermost one out.
+    let styles: Vec<&ServoComputedValues> = fragment.inline_styles().collect();
+    if styles.iter().all(|style| style.get_box().vertical_align == vertical_align::T::baseline) {
+        return Au(0)
+    }
+
+    let mut shift = Au(0);
+    let mut parent_font_metrics = *block_font_metrics;
+    for (index, style) in styles.iter().enumerate().rev() {
+        let font_metrics = text::font_metrics_for_style(&mut layout_context.font_context(),
+                                                        style.get_font_arc());
+        let line_height = text::line_height_from_style(style, &font_metrics);
+        let inline_metrics = if index == 0 {
+            *fragment_inline_metrics
+        } else {
+            InlineMetrics::from_font_metrics(&font_metrics, line_height)
+        };
+        let parent_font_size = parent_font_metrics.ascent + parent_font_metrics.descent;
+
+        shift = shift + match style.get_box().vertical_align {
+            vertical_align::T::baseline |
+            vertical_align::T::top |
+            vertical_align::T::bottom => Au(0),
+            // The midpoint of the box goes half the x-height above the baseline of the parent.
+            vertical_align::T::middle => {
+                parent_font_metrics.x_height.scale_by(0.5) +
+                    inline_metrics.block_size().scale_by(0.5) -
+                    inline_metrics.block_size_above_baseline
+            }
+            vertical_align::T::sub => -parent_font_size.scale_by(FONT_SUBSCRIPT_OFFSET_RATIO),
+            vertical_align::T::super_ => parent_font_size.scale_by(FONT_SUPERSCRIPT_OFFSET_RATIO),
+            vertical_align::T::text_top => {
+                parent_font_metrics.ascent - inline_metrics.block_size_above_baseline
+            }
+            vertical_align::T::text_bottom => {
+                inline_metrics.depth_below_baseline - parent_font_metrics.descent
+            }
+            vertical_align::T::LengthOrPercentage(LengthOrPercentage::Length(length)) => length,
+            vertical_align::T::LengthOrPercentage(LengthOrPercentage::Percentage(percentage)) => {
+                line_height.scale_by(percentage)
+            }
+            vertical_align::T::LengthOrPercentage(LengthOrPercentage::Calc(calc)) => {
+                line_height.scale_by(calc.percentage()) + calc.length()
+            }
+        };
+
+        parent_font_metrics = ParentFontMetrics::from_font_metrics(&font_metrics);
+    }
+    shift
+}
+
 #[derive(Copy, Clone, PartialEq)]
 enum LineFlushMode {
     No,
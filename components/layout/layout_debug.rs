@@ -4,18 +4,26 @@
 
 //! Supports writing a trace file created during each layout scope
 //! that can be viewed by an external tool to make layout debugging easier.
+//! Also serializes the fragment tree as JSON, for external tools to read.
 
 // for thread_local
 #![allow(unsafe_code)]
 
-use flow;
-use flow_ref::FlowRef;
+use app_units::Au;
+use cssparser::ToCss;
+use euclid::point::Point2D;
+use euclid::rect::Rect;
+use flow::{self, Flow, ImmutableFlowUtils};
+use flow_ref::{self, FlowRef};
+use fragment::{Fragment, FragmentBorderBoxIterator, SpecificFragmentInfo};
 use rustc_serialize::json;
 use std::borrow::ToOwned;
 use std::cell::RefCell;
 use std::fs::File;
 use std::io::Write;
 use std::sync::atomic::{ATOMIC_USIZE_INIT, AtomicUsize, Ordering};
+use style::properties::ComputedValues;
+use wrapper::PseudoElementType;
 
 thread_local!(static STATE_KEY: RefCell<Option<State>> = RefCell::new(None));
 
@@ -128,3 +136,161 @@ pub fn end_trace() {
     let mut file = File::create("layout_trace.json").unwrap();
     file.write_all(result.as_bytes()).unwrap();
 }
+
+/// A flow in the JSON form of the fragment tree.
+#[derive(RustcEncodable)]
+struct FlowData {
+    id: usize,
+    class: String,
+    fragments: Vec<FragmentData>,
+    children: Vec<FlowData>,
+}
+
+/// A fragment in the JSON form of the fragment tree. Lengths are in CSS pixels.
+#[derive(RustcEncodable)]
+struct FragmentData {
+    id: u16,
+    kind: &'static str,
+    /// The address of the DOM node that the fragment was generated for.
+    node: usize,
+    /// The pseudo-element of the node that the fragment was generated for, if any.
+    pseudo: Option<&'static str>,
+    /// The border box, relative to the origin of the page.
+    border_box: RectData,
+    margin: SidesData,
+    border_padding: SidesData,
+    /// The text that the fragment shows, if it is a text fragment.
+    text: Option<String>,
+    style: StyleSummary,
+}
+
+#[derive(RustcEncodable)]
+struct RectData {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+#[derive(RustcEncodable)]
+struct SidesData {
+    top: f32,
+    right: f32,
+    bottom: f32,
+    left: f32,
+}
+
+/// The computed values that most decide how a fragment is laid out and looks.
+#[derive(RustcEncodable)]
+struct StyleSummary {
+    display: String,
+    position: String,
+    float: String,
+    font_size: f32,
+    color: String,
+    visibility: String,
+}
+
+impl FragmentData {
+    fn new(fragment: &Fragment, border_box: &Rect<Au>) -> FragmentData {
+        let writing_mode = fragment.style.writing_mode;
+        let margin = fragment.margin.to_physical(writing_mode);
+        let border_padding = fragment.border_padding.to_physical(writing_mode);
+        let style = &*fragment.style;
+        FragmentData {
+            id: fragment.debug_id(),
+            kind: fragment.specific.get_type(),
+            node: fragment.node.id(),
+            pseudo: match fragment.pseudo {
+                PseudoElementType::Normal => None,
+                PseudoElementType::Before(()) => Some("::before"),
+                PseudoElementType::After(()) => Some("::after"),
+                PseudoElementType::DetailsSummary(()) => Some("::-servo-details-summary"),
+                PseudoElementType::DetailsContent(()) => Some("::-servo-details-content"),
+                PseudoElementType::Marker(()) => Some("::marker"),
+            },
+            border_box: RectData {
+                x: border_box.origin.x.to_f32_px(),
+                y: border_box.origin.y.to_f32_px(),
+                width: border_box.size.width.to_f32_px(),
+                height: border_box.size.height.to_f32_px(),
+            },
+            margin: SidesData {
+                top: margin.top.to_f32_px(),
+                right: margin.right.to_f32_px(),
+                bottom: margin.bottom.to_f32_px(),
+                left: margin.left.to_f32_px(),
+            },
+            border_padding: SidesData {
+                top: border_padding.top.to_f32_px(),
+                right: border_padding.right.to_f32_px(),
+                bottom: border_padding.bottom.to_f32_px(),
+                left: border_padding.left.to_f32_px(),
+            },
+            text: match fragment.specific {
+                SpecificFragmentInfo::ScannedText(ref info) => Some(info.text().to_owned()),
+                SpecificFragmentInfo::UnscannedText(ref info) => Some(info.text.clone()),
+                _ => None,
+            },
+            style: StyleSummary {
+                display: style.get_box().display.to_css_string(),
+                position: style.get_box().position.to_css_string(),
+                float: style.get_box().float.to_css_string(),
+                font_size: style.get_font().font_size.to_f32_px(),
+                color: style.get_color().color.to_css_string(),
+                visibility: style.get_inheritedbox().visibility.to_css_string(),
+            },
+        }
+    }
+}
+
+/// Collects the fragments of a single flow.
+struct FragmentCollector {
+    fragments: Vec<FragmentData>,
+}
+
+impl FragmentBorderBoxIterator for FragmentCollector {
+    fn process(&mut self, fragment: &Fragment, _: i32, border_box: &Rect<Au>) {
+        self.fragments.push(FragmentData::new(fragment, border_box))
+    }
+
+    fn should_process(&mut self, _: &Fragment) -> bool {
+        true
+    }
+}
+
+/// Serializes the flows under `root`, with the fragments of each, as JSON. The border boxes of
+/// fragments are positioned in the same way as for script queries, so layout must have run.
+pub fn fragment_tree_json(root: &mut FlowRef) -> String {
+    fn flow_data(flow: &mut Flow, level: i32, stacking_context_position: &Point2D<Au>)
+                 -> FlowData {
+        let mut collector = FragmentCollector {
+            fragments: vec![],
+        };
+        flow.iterate_through_fragment_border_boxes(&mut collector,
+                                                   level,
+                                                   stacking_context_position);
+
+        let mut children = vec![];
+        for kid in flow::mut_base(flow).child_iter_mut() {
+            let stacking_context_position =
+                if kid.is_block_flow() && kid.as_block().fragment.establishes_stacking_context() {
+                    let margin = Point2D::new(kid.as_block().fragment.margin.inline_start, Au(0));
+                    *stacking_context_position + flow::base(kid).stacking_relative_position + margin
+                } else {
+                    *stacking_context_position
+                };
+            children.push(flow_data(kid, level + 1, &stacking_context_position));
+        }
+
+        FlowData {
+            id: flow::base(flow).debug_id(),
+            class: format!("{:?}", flow.class()),
+            fragments: collector.fragments,
+            children: children,
+        }
+    }
+
+    let root = flow_data(flow_ref::deref_mut(root), 0, &Point2D::zero());
+    json::encode(&root).unwrap()
+}
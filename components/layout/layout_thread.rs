@@ -12,26 +12,30 @@ use app_units::Au;
 use azure::azure::AzColor;
 use construct::ConstructionResult;
 use context::{LayoutContext, SharedLayoutContext, heap_size_of_local_context};
+use cssparser::ToCss;
 use display_list_builder::ToGfxColor;
 use euclid::Matrix4D;
 use euclid::point::Point2D;
 use euclid::rect::Rect;
 use euclid::scale_factor::ScaleFactor;
 use euclid::size::Size2D;
-use flow::{self, Flow, ImmutableFlowUtils, MutableOwnedFlowUtils};
+use flow::{self, Flow, ImmutableFlowUtils, MutableOwnedFlowUtils, WAS_REFLOWED};
 use flow_ref::{self, FlowRef};
 use fnv::FnvHasher;
+use gfx::display_list::StackingContextType;
 use gfx::display_list::WebRenderImageInfo;
-use gfx::display_list::{ClippingRegion, DisplayItemMetadata, DisplayList, LayerInfo};
-use gfx::display_list::{OpaqueNode, StackingContext, StackingContextId, StackingContextType};
+use gfx::display_list::{ClippingRegion, DisplayItem, DisplayItemMetadata, DisplayList, LayerInfo};
+use gfx::display_list::{OpaqueNode, ScrollSnapContainer, StackingContext, StackingContextId};
 use gfx::font;
 use gfx::font_cache_thread::FontCacheThread;
 use gfx::font_context;
 use gfx::paint_thread::LayoutToPaintMsg;
-use gfx_traits::{color, Epoch, LayerId, ScrollPolicy};
+use gfx_traits::{color, ConsistencyToken, Epoch, LayerAnimation, LayerId, ScrollPolicy};
 use heapsize::HeapSizeOf;
+use hit_test;
 use incremental::LayoutDamageComputation;
 use incremental::{REPAINT, STORE_OVERFLOW, REFLOW_OUT_OF_FLOW, REFLOW, REFLOW_ENTIRE_DOCUMENT};
+use incremental::{REFLOW_REFLOW_ROOTS, RestyleDamage};
 use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
 use ipc_channel::router::ROUTER;
 use layout_debug;
@@ -40,6 +44,7 @@ use log;
 use msg::constellation_msg::{PanicMsg, PipelineId};
 use net_traits::image_cache_thread::UsePlaceholder;
 use net_traits::image_cache_thread::{ImageCacheChan, ImageCacheResult, ImageCacheThread};
+use opaque_node::OpaqueNodeMethods;
 use parallel;
 use profile_traits::mem::{self, Report, ReportKind, ReportsChan};
 use profile_traits::time::{TimerMetadataFrameType, TimerMetadataReflowType};
@@ -48,44 +53,65 @@ use query::process_offset_parent_query;
 use query::{LayoutRPCImpl, process_content_box_request, process_content_boxes_request};
 use query::{process_node_geometry_request, process_node_layer_id_request, process_node_scroll_area_request};
 use query::{process_node_overflow_request, process_resolved_style_request, process_margin_style_query};
+use query::{process_animation_state_query, process_box_model_query, process_matched_rules_query};
+use query::process_used_font_faces_query;
 use script::dom::node::OpaqueStyleAndLayoutData;
+use script::layout_interface::{BoxModel, TrustedNodeAddress};
 use script::layout_interface::{LayoutRPC, OffsetParentResponse, NodeOverflowResponse, MarginStyleResponse};
-use script::layout_interface::{Msg, NewLayoutThreadInfo, Reflow, ReflowQueryType, ScriptReflow};
+use script::layout_interface::{AnimationControl, InvalidationOverlays, Msg, NewLayoutThreadInfo};
+use script::layout_interface::{NodeAnimation, Reflow, ReflowQueryType, RunningAnimation};
+use script::layout_interface::{LayoutPhaseMarker, ScriptReflow};
 use script::reporter::CSSErrorReporter;
-use script_traits::ConstellationControlMsg;
+use script_traits::{AnimationState, ConstellationControlMsg};
 use script_traits::{LayoutControlMsg, LayoutMsg as ConstellationMsg};
+use script_traits::UsedFontFace;
 use sequential;
+use rustc_serialize::json;
 use serde_json;
 use std::borrow::ToOwned;
-use std::collections::HashMap;
+use std::cmp::min;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::hash::BuildHasherDefault;
 use std::ops::{Deref, DerefMut};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc::{channel, Sender, Receiver};
 use std::sync::{Arc, Mutex, MutexGuard, RwLock};
-use style::animation::Animation;
-use style::computed_values::{filter, mix_blend_mode};
+use std::time::Duration;
+use string_cache::Atom;
+use style::animation::{Animation, AnimationTarget, AnimationTiming, KeyframeEffect};
+use style::animation::KeyframesAnimation;
+use style::computed_values::font_family::FontFamily;
+use style::computed_values::content::{self, ContentItem};
+use style::computed_values::{display, filter, mix_blend_mode};
+use style::computed_values::{scroll_snap_type_x, scroll_snap_type_y};
 use style::context::ReflowGoal;
-use style::dom::{TDocument, TElement, TNode};
+use style::dom::{TDocument, TElement, TNode, TRestyleDamage};
 use style::error_reporting::ParseErrorReporter;
+use style::font_face::FontDisplay;
 use style::logical_geometry::LogicalPoint;
 use style::media_queries::{Device, MediaType};
 use style::parallel::WorkQueueData;
-use style::properties::ComputedValues;
-use style::selector_matching::USER_OR_USER_AGENT_STYLESHEETS;
-use style::servo::{SharedStyleContext, Stylesheet, Stylist};
+use style::properties::{self, ComputedValues, ServoComputedValues};
+use style::restyle_hints::{ElementSnapshot, RESTYLE_SELF};
+use style::selector_impl::PseudoElement;
+use style::selector_matching::{MatchedRule, USER_OR_USER_AGENT_STYLESHEETS};
+use style::servo::{CascadeSnapshot, SharedCascadeSnapshot, SharedStyleContext, Stylesheet};
+use style::servo::Stylist;
 use style::stylesheets::CSSRuleIteratorExt;
-use traversal::RecalcStyleAndConstructFlows;
+use style::traversal::get_and_reset_phase_totals;
+use traversal::{RecalcStyleAndConstructFlows, get_and_reset_flow_construction_count};
 use url::Url;
 use util::geometry::MAX_RECT;
 use util::ipc::OptionalIpcSender;
 use util::opts;
+use util::prefs::get_pref;
 use util::thread;
 use util::thread_state;
 use util::workqueue::WorkQueue;
 use webrender_helpers::{WebRenderDisplayListConverter, WebRenderFrameBuilder};
 use webrender_traits;
-use wrapper::{LayoutNode, NonOpaqueStyleAndLayoutData, ServoLayoutNode};
+use wrapper::{LayoutNode, NonOpaqueStyleAndLayoutData, ServoLayoutElement, ServoLayoutNode};
+use wrapper::ThreadSafeLayoutNode;
 
 /// The number of screens of data we're allowed to generate display lists for in each direction.
 pub const DISPLAY_PORT_SIZE_FACTOR: i32 = 8;
@@ -93,6 +119,56 @@ pub const DISPLAY_PORT_SIZE_FACTOR: i32 = 8;
 /// The number of screens we have to traverse before we decide to generate new display lists.
 const DISPLAY_PORT_THRESHOLD_SIZE_FACTOR: i32 = 4;
 
+/// How long carets are shown, and then hidden, while they blink, in nanoseconds.
+const CARET_BLINK_INTERVAL_NS: u64 = 500 * 1000000;
+
+/// How long a frame lasts, in nanoseconds. What a reflow for display leaves of it is spent on
+/// computing the styles of lazily cascaded pseudo-elements ahead of time.
+const FRAME_DURATION_NS: u64 = 1000000000 / 60;
+
+/// The number of nodes that are looked at between two checks of the time while styles are
+/// computed ahead of time.
+const PRECOMPUTE_NODES_PER_TIME_CHECK: usize = 64;
+
+/// The number of other requests that the layout thread handles at most while a low-priority
+/// request waits.
+const MAX_REQUESTS_BEFORE_LOW_PRIORITY_REQUEST: usize = 16;
+
+/// The number of low-priority requests that wait at most, beyond which the oldest is handled.
+const MAX_LOW_PRIORITY_REQUESTS: usize = 256;
+
+/// A request that the layout thread received from one of the threads that send it requests.
+enum Request {
+    FromPipeline(LayoutControlMsg),
+    FromScript(Msg),
+    FromImageCache,
+    FromFontCache(usize),
+    FromDeferredFontCache,
+    FromWebFontTimeout,
+}
+
+impl Request {
+    /// Whether the request can wait behind the others, such as script queries: freeing the data
+    /// of dead nodes, and updating the visible rects, which only affect the next display list.
+    fn is_low_priority(&self) -> bool {
+        match *self {
+            Request::FromScript(Msg::ReapStyleAndLayoutData(_)) |
+            Request::FromScript(Msg::SetVisibleRects(_)) |
+            Request::FromPipeline(LayoutControlMsg::SetVisibleRects(_)) => true,
+            _ => false,
+        }
+    }
+
+    fn is_exit(&self) -> bool {
+        match *self {
+            Request::FromScript(Msg::PrepareToExit(_)) |
+            Request::FromScript(Msg::ExitNow) |
+            Request::FromPipeline(LayoutControlMsg::ExitNow) => true,
+            _ => false,
+        }
+    }
+}
+
 /// Mutable data belonging to the LayoutThread.
 ///
 /// This needs to be protected by a mutex so we can do fast RPCs.
@@ -134,6 +210,27 @@ pub struct LayoutThreadData {
 
     /// A queued response for the offset parent/rect of a node.
     pub margin_style_response: MarginStyleResponse,
+
+    /// A queued response for the animations running on a node.
+    pub animation_state_response: Vec<NodeAnimation>,
+
+    /// A queued response for the style rules that match a node.
+    pub matched_rules_response: (Vec<MatchedRule>, bool),
+
+    /// A queued response for the boxes of a node.
+    pub box_model_response: Option<BoxModel>,
+
+    /// A queued response for the font faces that text is laid out in.
+    pub used_font_faces_response: Vec<UsedFontFace>,
+
+    /// The phases that the last script reflow went through, if it asked for them.
+    pub phase_markers: Option<Vec<LayoutPhaseMarker>>,
+
+    /// The snapshot of what styles are cascaded against that other threads resolve styles with.
+    pub cascade_snapshot: SharedCascadeSnapshot,
+
+    /// The version of the DOM that the last reflow that script asked for reflected.
+    pub dom_version: u64,
 }
 
 /// Information needed by the layout thread.
@@ -159,11 +256,23 @@ pub struct LayoutThread {
     /// The channel on which the image cache can send messages to ourself.
     image_cache_sender: ImageCacheChan,
 
-    /// The port on which we receive messages from the font cache thread.
-    font_cache_receiver: Receiver<()>,
+    /// The port on which we receive the index in `web_font_loads` of each web font that the
+    /// font cache thread finished loading.
+    font_cache_receiver: Receiver<usize>,
+
+    /// The web fonts that are being loaded, and where they are in their `font-display`
+    /// timeline.
+    web_font_loads: WebFontLoads,
+
+    /// The port on which we are notified that the block period of a web font ended.
+    web_font_timeout_receiver: Receiver<()>,
+
+    /// The port on which we are notified of web fonts that were downloaded once text needed a
+    /// character in their `unicode-range`.
+    deferred_font_cache_receiver: Receiver<()>,
 
-    /// The channel on which the font cache can send messages to us.
-    font_cache_sender: IpcSender<()>,
+    /// The channel on which the font cache notifies us of such web fonts.
+    deferred_font_cache_sender: IpcSender<()>,
 
     /// The channel on which messages can be sent to the constellation.
     constellation_chan: IpcSender<ConstellationMsg>,
@@ -171,8 +280,9 @@ pub struct LayoutThread {
     /// The channel on which messages can be sent to the script thread.
     script_chan: IpcSender<ConstellationControlMsg>,
 
-    /// The channel on which messages can be sent to the painting thread.
-    paint_chan: OptionalIpcSender<LayoutToPaintMsg>,
+    /// The channel on which messages can be sent to the painting thread, unless the layout thread
+    /// is headless, in which case display lists are built for queries but never painted.
+    paint_chan: Option<OptionalIpcSender<LayoutToPaintMsg>>,
 
     /// The channel on which messages can be sent to the time profiler.
     time_profiler_chan: time::ProfilerChan,
@@ -206,6 +316,15 @@ pub struct LayoutThread {
     /// The number of Web fonts that have been requested but not yet loaded.
     outstanding_web_fonts: Arc<AtomicUsize>,
 
+    /// Whether generated content is waiting for an image to be able to size itself.
+    generated_content_images_pending: Arc<AtomicBool>,
+
+    /// The requests that wait until no other request is pending, oldest first.
+    low_priority_requests: VecDeque<Request>,
+
+    /// The number of other requests handled since a low-priority request was last handled.
+    requests_since_low_priority_request: usize,
+
     /// The root of the flow tree.
     root_flow: Option<FlowRef>,
 
@@ -213,15 +332,32 @@ pub struct LayoutThread {
     /// for any areas more than `DISPLAY_PORT_SIZE_FACTOR` screens away from this area.
     visible_rects: Arc<HashMap<LayerId, Rect<Au>, BuildHasherDefault<FnvHasher>>>,
 
-    /// The list of currently-running animations.
-    running_animations: Arc<RwLock<HashMap<OpaqueNode, Vec<Animation>>>>,
+    /// The list of currently-running animations, by the node or pseudo-element they run on.
+    running_animations: Arc<RwLock<HashMap<AnimationTarget, Vec<Animation>>>>,
 
     /// The list of animations that have expired since the last style recalculation.
-    expired_animations: Arc<RwLock<HashMap<OpaqueNode, Vec<Animation>>>>,
+    expired_animations: Arc<RwLock<HashMap<AnimationTarget, Vec<Animation>>>>,
+
+    /// The animations that script created with `Element.animate()`.
+    keyframes_animations: Arc<RwLock<HashMap<OpaqueNode, Vec<KeyframesAnimation>>>>,
+
+    /// The time that animations were last sampled at, as returned by `time::precise_time_s()`.
+    /// It is that of the animation tick that layout last rendered, or that of the start of the
+    /// last reflow that script asked for outside of animation ticks.
+    animation_time: f64,
+
+    /// The animations last handed off to the compositor, which does not need layout to tick them.
+    layer_animations: Vec<LayerAnimation>,
+
+    /// The nodes and pseudo-elements whose animations are handed off to the compositor.
+    handed_off_targets: HashSet<AnimationTarget>,
 
     /// A counter for epoch messages
     epoch: Epoch,
 
+    /// The version of the DOM that the display list of the current epoch reflects.
+    displayed_dom_version: u64,
+
     /// The size of the viewport. This may be different from the size of the screen due to viewport
     /// constraints.
     viewport_size: Size2D<Au>,
@@ -241,6 +377,31 @@ pub struct LayoutThread {
 
     // Webrender interface, if enabled.
     webrender_api: Option<webrender_traits::RenderApi>,
+
+    /// The debugging overlays that devtools has enabled for this pipeline.
+    invalidation_overlays: InvalidationOverlays,
+
+    /// The node whose box model devtools is highlighting, if any.
+    highlighted_node: Option<OpaqueNode>,
+
+    /// The display list last sent to WebRender and the viewport size it was sent for. Frames with
+    /// the same display list are not sent again.
+    retained_display_list: Option<(Arc<DisplayList>, Size2D<Au>)>,
+
+    /// The URLs of the links that the nodes of the document are in, by opaque node address.
+    links: HashMap<usize, String>,
+
+    /// Whether the display list has carets, which keep animations ticking while they blink.
+    caret_present: bool,
+
+    /// When carets were last shown without blinking: moving them restarts their blinking.
+    caret_blink_start: u64,
+
+    /// Whether carets are shown in the current phase of their blinking.
+    caret_visible: bool,
+
+    /// The number of device pixels per `px` of the window, as of the last reflow from script.
+    device_pixel_ratio: f32,
 }
 
 impl LayoutThreadFactory for LayoutThread {
@@ -255,7 +416,7 @@ impl LayoutThreadFactory for LayoutThread {
               constellation_chan: IpcSender<ConstellationMsg>,
               panic_chan: IpcSender<PanicMsg>,
               script_chan: IpcSender<ConstellationControlMsg>,
-              paint_chan: OptionalIpcSender<LayoutToPaintMsg>,
+              paint_chan: Option<OptionalIpcSender<LayoutToPaintMsg>>,
               image_cache_thread: ImageCacheThread,
               font_cache_thread: FontCacheThread,
               time_profiler_chan: time::ProfilerChan,
@@ -353,10 +514,96 @@ impl<'a, 'b: 'a> RwData<'a, 'b> {
     }
 }
 
+/// A web font download started by layout.
+struct WebFontLoad {
+    /// The lowercased name of the family of the font.
+    family: String,
+    display: FontDisplay,
+    /// When the download started, in nanoseconds.
+    start_time: u64,
+    loaded: bool,
+}
+
+impl WebFontLoad {
+    fn elapsed_ms(&self) -> u64 {
+        (::time::precise_time_ns() - self.start_time) / 1000000
+    }
+}
+
+/// Keeps track of the block and swap periods of the web fonts being loaded.
+/// https://drafts.csswg.org/css-fonts-4/#font-display-timeline
+struct WebFontLoads {
+    loads: Vec<WebFontLoad>,
+    /// Notified with the index of a load once the font cache thread is done with it.
+    loaded_sender: Sender<usize>,
+    /// Notified when the block period of a load ends.
+    timeout_sender: Sender<()>,
+}
+
+impl WebFontLoads {
+    fn new(loaded_sender: Sender<usize>, timeout_sender: Sender<()>) -> WebFontLoads {
+        WebFontLoads {
+            loads: vec![],
+            loaded_sender: loaded_sender,
+            timeout_sender: timeout_sender,
+        }
+    }
+
+    /// Starts the timeline of a font of the given family, and returns the channel on which the
+    /// font cache thread should report that the font is loaded.
+    fn start(&mut self, family: &FontFamily, display: FontDisplay) -> IpcSender<()> {
+        let index = self.loads.len();
+        self.loads.push(WebFontLoad {
+            family: family.name().to_lowercase(),
+            display: display,
+            start_time: ::time::precise_time_ns(),
+            loaded: false,
+        });
+
+        let (sender, receiver) = ipc::channel().unwrap();
+        let loaded_sender = self.loaded_sender.clone();
+        ROUTER.add_route(receiver.to_opaque(), box move |_| {
+            let _ = loaded_sender.send(index);
+        });
+
+        // Text waiting for the font has to be painted with the fallback font once the block
+        // period is over.
+        let block_period = display.block_period();
+        if block_period != 0 {
+            let timeout_sender = self.timeout_sender.clone();
+            thread::spawn_named("WebFontBlockPeriod".to_owned(), move || {
+                ::std::thread::sleep(Duration::from_millis(block_period));
+                let _ = timeout_sender.send(());
+            });
+        }
+        sender
+    }
+
+    /// Marks the font of the given load as loaded, and returns whether it arrived early enough
+    /// to replace the fallback font.
+    fn finish(&mut self, index: usize) -> bool {
+        let load = &mut self.loads[index];
+        load.loaded = true;
+        match load.display.swap_period() {
+            Some(swap_period) => load.elapsed_ms() <= load.display.block_period() + swap_period,
+            None => true,
+        }
+    }
+
+    /// The families of the fonts in their block period. Text using them is laid out with a
+    /// fallback font but not painted.
+    fn blocked_families(&self) -> HashSet<String> {
+        self.loads.iter().filter(|load| {
+            !load.loaded && load.elapsed_ms() < load.display.block_period()
+        }).map(|load| load.family.clone()).collect()
+    }
+}
+
 fn add_font_face_rules(stylesheet: &Stylesheet,
                        device: &Device,
                        font_cache_thread: &FontCacheThread,
-                       font_cache_sender: &IpcSender<()>,
+                       web_font_loads: &mut WebFontLoads,
+                       deferred_font_cache_sender: &IpcSender<()>,
                        outstanding_web_fonts_counter: &Arc<AtomicUsize>) {
     for font_face in stylesheet.effective_rules(&device).font_face() {
         for source in &font_face.sources {
@@ -364,13 +611,22 @@ fn add_font_face_rules(stylesheet: &Stylesheet,
                 let (sender, receiver) = ipc::channel().unwrap();
                 font_cache_thread.add_web_font(font_face.family.clone(),
                                              (*source).clone(),
+                                             font_face.unicode_range.clone(),
                                              sender);
                 receiver.recv().unwrap();
+            } else if !font_face.covers_all_code_points() {
+                // Only downloaded once some text needs it, so it does not count as loading.
+                font_cache_thread.add_deferred_web_font(font_face.family.clone(),
+                                                        (*source).clone(),
+                                                        font_face.unicode_range.clone(),
+                                                        (*deferred_font_cache_sender).clone());
             } else {
                 outstanding_web_fonts_counter.fetch_add(1, Ordering::SeqCst);
+                let sender = web_font_loads.start(&font_face.family, font_face.display);
                 font_cache_thread.add_web_font(font_face.family.clone(),
                                              (*source).clone(),
-                                             (*font_cache_sender).clone());
+                                             font_face.unicode_range.clone(),
+                                             sender);
             }
         }
     }
@@ -385,7 +641,7 @@ impl LayoutThread {
            pipeline_port: IpcReceiver<LayoutControlMsg>,
            constellation_chan: IpcSender<ConstellationMsg>,
            script_chan: IpcSender<ConstellationControlMsg>,
-           paint_chan: OptionalIpcSender<LayoutToPaintMsg>,
+           paint_chan: Option<OptionalIpcSender<LayoutToPaintMsg>>,
            image_cache_thread: ImageCacheThread,
            font_cache_thread: FontCacheThread,
            time_profiler_chan: time::ProfilerChan,
@@ -393,7 +649,7 @@ impl LayoutThread {
            webrender_api_sender: Option<webrender_traits::RenderApiSender>)
            -> LayoutThread {
         let device = Device::new(
-            MediaType::Screen,
+            media_type(),
             opts::get().initial_window_size.as_f32() * ScaleFactor::new(1.0));
         let parallel_traversal = if opts::get().layout_threads != 1 {
             Some(WorkQueue::new("LayoutWorker", thread_state::LAYOUT,
@@ -414,17 +670,24 @@ impl LayoutThread {
             ROUTER.route_ipc_receiver_to_new_mpsc_receiver(ipc_image_cache_receiver);
 
         // Ask the router to proxy IPC messages from the font cache thread to the layout thread.
-        let (ipc_font_cache_sender, ipc_font_cache_receiver) = ipc::channel().unwrap();
-        let font_cache_receiver =
-            ROUTER.route_ipc_receiver_to_new_mpsc_receiver(ipc_font_cache_receiver);
+        let (font_cache_sender, font_cache_receiver) = channel();
+        let (web_font_timeout_sender, web_font_timeout_receiver) = channel();
+        let mut web_font_loads = WebFontLoads::new(font_cache_sender, web_font_timeout_sender);
+        let (ipc_deferred_font_cache_sender, ipc_deferred_font_cache_receiver) =
+            ipc::channel().unwrap();
+        let deferred_font_cache_receiver =
+            ROUTER.route_ipc_receiver_to_new_mpsc_receiver(ipc_deferred_font_cache_receiver);
 
         let stylist = Arc::new(Stylist::new(device));
+        let cascade_snapshot = Arc::new(RwLock::new(Arc::new(CascadeSnapshot::new(&*stylist,
+                                                                                  None))));
         let outstanding_web_fonts_counter = Arc::new(AtomicUsize::new(0));
         for stylesheet in &*USER_OR_USER_AGENT_STYLESHEETS {
             add_font_face_rules(stylesheet,
                                 &stylist.device,
                                 &font_cache_thread,
-                                &ipc_font_cache_sender,
+                                &mut web_font_loads,
+                                &ipc_deferred_font_cache_sender,
                                 &outstanding_web_fonts_counter);
         }
 
@@ -445,19 +708,38 @@ impl LayoutThread {
             image_cache_receiver: image_cache_receiver,
             image_cache_sender: ImageCacheChan(ipc_image_cache_sender),
             font_cache_receiver: font_cache_receiver,
-            font_cache_sender: ipc_font_cache_sender,
+            web_font_loads: web_font_loads,
+            web_font_timeout_receiver: web_font_timeout_receiver,
+            deferred_font_cache_receiver: deferred_font_cache_receiver,
+            deferred_font_cache_sender: ipc_deferred_font_cache_sender,
             parallel_traversal: parallel_traversal,
             generation: 0,
             new_animations_sender: new_animations_sender,
             new_animations_receiver: new_animations_receiver,
             outstanding_web_fonts: outstanding_web_fonts_counter,
+            generated_content_images_pending: Arc::new(AtomicBool::new(false)),
+            low_priority_requests: VecDeque::new(),
+            requests_since_low_priority_request: 0,
             root_flow: None,
             visible_rects: Arc::new(HashMap::with_hasher(Default::default())),
             running_animations: Arc::new(RwLock::new(HashMap::new())),
             expired_animations: Arc::new(RwLock::new(HashMap::new())),
+            keyframes_animations: Arc::new(RwLock::new(HashMap::new())),
+            animation_time: ::time::precise_time_s(),
+            layer_animations: vec![],
+            handed_off_targets: HashSet::new(),
             epoch: Epoch(0),
+            displayed_dom_version: 0,
             viewport_size: Size2D::new(Au(0), Au(0)),
             webrender_api: webrender_api_sender.map(|wr| wr.create_api()),
+            invalidation_overlays: Default::default(),
+            highlighted_node: None,
+            retained_display_list: None,
+            links: HashMap::new(),
+            caret_present: false,
+            caret_blink_start: 0,
+            caret_visible: true,
+            device_pixel_ratio: 1.0,
             rw_data: Arc::new(Mutex::new(
                 LayoutThreadData {
                     constellation_chan: constellation_chan,
@@ -473,6 +755,13 @@ impl LayoutThread {
                     resolved_style_response: None,
                     offset_parent_response: OffsetParentResponse::empty(),
                     margin_style_response: MarginStyleResponse::empty(),
+                    animation_state_response: vec![],
+                    matched_rules_response: (vec![], false),
+                    box_model_response: None,
+                    used_font_faces_response: vec![],
+                    phase_markers: None,
+                    cascade_snapshot: cascade_snapshot,
+                    dom_version: 0,
               })),
               error_reporter: CSSErrorReporter {
                   pipelineid: id,
@@ -512,6 +801,10 @@ impl LayoutThread {
                 new_animations_sender: Mutex::new(self.new_animations_sender.clone()),
                 running_animations: self.running_animations.clone(),
                 expired_animations: self.expired_animations.clone(),
+                keyframes_animations: self.keyframes_animations.clone(),
+                animation_time: self.animation_time,
+                record_phase_times: rw_data.phase_markers.is_some() ||
+                                    opts::get().time_profiling.is_some(),
                 error_reporter: self.error_reporter.clone(),
             },
             image_cache_thread: self.image_cache_thread.clone(),
@@ -519,48 +812,130 @@ impl LayoutThread {
             font_cache_thread: Mutex::new(self.font_cache_thread.clone()),
             visible_rects: self.visible_rects.clone(),
             webrender_image_cache: self.webrender_image_cache.clone(),
+            invalidation_overlays: self.invalidation_overlays,
+            highlighted_node: self.highlighted_node,
+            blocked_font_families: self.web_font_loads.blocked_families(),
+            caret_visible: self.caret_visible,
+            device_pixel_ratio: self.device_pixel_ratio,
+            generated_content_images_pending: self.generated_content_images_pending.clone(),
         }
     }
 
-    /// Receives and dispatches messages from the script and constellation threads
+    /// Receives and dispatches messages from the script and constellation threads. Requests that
+    /// can wait wait until no other request is pending, so that script queries are answered first,
+    /// or until too many other requests have gone first or too many of them are waiting.
     fn handle_request<'a, 'b>(&mut self, possibly_locked_rw_data: &mut RwData<'a, 'b>) -> bool {
-        enum Request {
-            FromPipeline(LayoutControlMsg),
-            FromScript(Msg),
-            FromImageCache,
-            FromFontCache,
-        }
-
-        let request = {
-            let port_from_script = &self.port;
-            let port_from_pipeline = &self.pipeline_port;
-            let port_from_image_cache = &self.image_cache_receiver;
-            let port_from_font_cache = &self.font_cache_receiver;
-            select! {
-                msg = port_from_pipeline.recv() => {
-                    Request::FromPipeline(msg.unwrap())
-                },
-                msg = port_from_script.recv() => {
-                    Request::FromScript(msg.unwrap())
-                },
-                msg = port_from_image_cache.recv() => {
-                    msg.unwrap();
-                    Request::FromImageCache
-                },
-                msg = port_from_font_cache.recv() => {
-                    msg.unwrap();
-                    Request::FromFontCache
+        loop {
+            let request = if self.low_priority_requests.is_empty() {
+                self.receive_request()
+            } else {
+                match self.try_receive_request() {
+                    Some(request) => request,
+                    None => {
+                        let request = self.low_priority_requests.pop_front().unwrap();
+                        self.requests_since_low_priority_request = 0;
+                        return self.dispatch_request(request, possibly_locked_rw_data)
+                    }
+                }
+            };
+
+            if request.is_low_priority() {
+                self.low_priority_requests.push_back(request);
+                if self.low_priority_requests.len() < MAX_LOW_PRIORITY_REQUESTS {
+                    continue
                 }
+                let request = self.low_priority_requests.pop_front().unwrap();
+                self.requests_since_low_priority_request = 0;
+                return self.dispatch_request(request, possibly_locked_rw_data)
             }
-        };
 
+            // Dead nodes must be reaped before exiting, and waiting requests must not starve.
+            self.requests_since_low_priority_request += 1;
+            if request.is_exit() {
+                while let Some(low_priority_request) = self.low_priority_requests.pop_front() {
+                    self.dispatch_request(low_priority_request, possibly_locked_rw_data);
+                }
+            } else if self.requests_since_low_priority_request >=
+                    MAX_REQUESTS_BEFORE_LOW_PRIORITY_REQUEST {
+                if let Some(low_priority_request) = self.low_priority_requests.pop_front() {
+                    self.requests_since_low_priority_request = 0;
+                    if !self.dispatch_request(low_priority_request, possibly_locked_rw_data) {
+                        return false
+                    }
+                }
+            }
+            return self.dispatch_request(request, possibly_locked_rw_data)
+        }
+    }
+
+    /// Waits for the next request from any of the threads that send them.
+    fn receive_request(&self) -> Request {
+        let port_from_script = &self.port;
+        let port_from_pipeline = &self.pipeline_port;
+        let port_from_image_cache = &self.image_cache_receiver;
+        let port_from_font_cache = &self.font_cache_receiver;
+        let port_from_deferred_font_cache = &self.deferred_font_cache_receiver;
+        let port_from_web_font_timeout = &self.web_font_timeout_receiver;
+        select! {
+            msg = port_from_pipeline.recv() => {
+                Request::FromPipeline(msg.unwrap())
+            },
+            msg = port_from_script.recv() => {
+                Request::FromScript(msg.unwrap())
+            },
+            msg = port_from_image_cache.recv() => {
+                msg.unwrap();
+                Request::FromImageCache
+            },
+            msg = port_from_font_cache.recv() => {
+                Request::FromFontCache(msg.unwrap())
+            },
+            msg = port_from_deferred_font_cache.recv() => {
+                msg.unwrap();
+                Request::FromDeferredFontCache
+            },
+            msg = port_from_web_font_timeout.recv() => {
+                msg.unwrap();
+                Request::FromWebFontTimeout
+            }
+        }
+    }
+
+    /// Returns the next request if one is pending, preferring those from script and the
+    /// constellation.
+    fn try_receive_request(&self) -> Option<Request> {
+        if let Ok(msg) = self.port.try_recv() {
+            return Some(Request::FromScript(msg))
+        }
+        if let Ok(msg) = self.pipeline_port.try_recv() {
+            return Some(Request::FromPipeline(msg))
+        }
+        if self.image_cache_receiver.try_recv().is_ok() {
+            return Some(Request::FromImageCache)
+        }
+        if let Ok(index) = self.font_cache_receiver.try_recv() {
+            return Some(Request::FromFontCache(index))
+        }
+        if self.deferred_font_cache_receiver.try_recv().is_ok() {
+            return Some(Request::FromDeferredFontCache)
+        }
+        if self.web_font_timeout_receiver.try_recv().is_ok() {
+            return Some(Request::FromWebFontTimeout)
+        }
+        None
+    }
+
+    fn dispatch_request<'a, 'b>(&mut self,
+                                request: Request,
+                                possibly_locked_rw_data: &mut RwData<'a, 'b>)
+                                -> bool {
         match request {
             Request::FromPipeline(LayoutControlMsg::SetVisibleRects(new_visible_rects)) => {
                 self.handle_request_helper(Msg::SetVisibleRects(new_visible_rects),
                                            possibly_locked_rw_data)
             },
-            Request::FromPipeline(LayoutControlMsg::TickAnimations) => {
-                self.handle_request_helper(Msg::TickAnimations, possibly_locked_rw_data)
+            Request::FromPipeline(LayoutControlMsg::TickAnimations(frame_time)) => {
+                self.handle_request_helper(Msg::TickAnimations(frame_time), possibly_locked_rw_data)
             },
             Request::FromPipeline(LayoutControlMsg::GetCurrentEpoch(sender)) => {
                 self.handle_request_helper(Msg::GetCurrentEpoch(sender), possibly_locked_rw_data)
@@ -576,15 +951,34 @@ impl LayoutThread {
                 self.handle_request_helper(msg, possibly_locked_rw_data)
             },
             Request::FromImageCache => {
+                // Generated images are sized when their fragments are built, so their boxes have
+                // to be built again.
+                if self.generated_content_images_pending.swap(false, Ordering::SeqCst) {
+                    let msg = ConstellationControlMsg::GeneratedContentImageLoaded(self.id);
+                    self.script_chan.send(msg).unwrap();
+                }
                 self.repaint(possibly_locked_rw_data)
             },
-            Request::FromFontCache => {
-                let _rw_data = possibly_locked_rw_data.lock();
+            Request::FromFontCache(index) => {
                 self.outstanding_web_fonts.fetch_sub(1, Ordering::SeqCst);
+                // A font that arrives after its swap period is over is not swapped in.
+                if self.web_font_loads.finish(index) {
+                    self.handle_request_helper(Msg::ReflowWithNewlyLoadedWebFont,
+                                               possibly_locked_rw_data)
+                } else {
+                    true
+                }
+            },
+            Request::FromDeferredFontCache => {
+                let _rw_data = possibly_locked_rw_data.lock();
                 font_context::invalidate_font_caches();
                 self.script_chan.send(ConstellationControlMsg::WebFontLoaded(self.id)).unwrap();
                 true
             },
+            Request::FromWebFontTimeout => {
+                // Paint the text that was waiting for the font.
+                self.repaint(possibly_locked_rw_data)
+            },
         }
     }
 
@@ -627,19 +1021,45 @@ impl LayoutThread {
                 self.handle_add_stylesheet(style_info, possibly_locked_rw_data)
             }
             Msg::SetQuirksMode => self.handle_set_quirks_mode(possibly_locked_rw_data),
+            Msg::SetAuthorStylesEnabled(enabled) => {
+                let mut rw_data = possibly_locked_rw_data.lock();
+                Arc::get_mut(&mut rw_data.stylist).unwrap().set_author_styles_enabled(enabled);
+                possibly_locked_rw_data.block(rw_data);
+            }
+            Msg::SetForcedColors(forced_colors) => {
+                let mut rw_data = possibly_locked_rw_data.lock();
+                Arc::get_mut(&mut rw_data.stylist).unwrap().set_forced_colors(forced_colors);
+                possibly_locked_rw_data.block(rw_data);
+            }
+            Msg::SetTextZoom(text_zoom) => {
+                let mut rw_data = possibly_locked_rw_data.lock();
+                Arc::get_mut(&mut rw_data.stylist).unwrap().set_text_zoom(text_zoom);
+                possibly_locked_rw_data.block(rw_data);
+            }
             Msg::GetRPC(response_chan) => {
                 response_chan.send(box LayoutRPCImpl(self.rw_data.clone()) as
                                    Box<LayoutRPC + Send>).unwrap();
             },
             Msg::Reflow(data) => {
-                profile(time::ProfilerCategory::LayoutPerform,
-                        self.profiler_metadata(),
-                        self.time_profiler_chan.clone(),
-                        || self.handle_reflow(&data, possibly_locked_rw_data));
+                let start_time = ::time::precise_time_ns();
+                let dirt = profile(time::ProfilerCategory::LayoutPerform,
+                                   self.profiler_metadata(),
+                                   self.time_profiler_chan.clone(),
+                                   || self.handle_reflow(&data, possibly_locked_rw_data));
+                self.log_reflow("Reflow", Some(&data), dirt, start_time);
             },
-            Msg::TickAnimations => self.tick_all_animations(possibly_locked_rw_data),
+            Msg::TickAnimations(frame_time) => {
+                let start_time = ::time::precise_time_ns();
+                self.tick_all_animations(frame_time, possibly_locked_rw_data);
+                self.log_reflow("TickAnimations", None, ReflowDirt::default(), start_time);
+            }
             Msg::ReflowWithNewlyLoadedWebFont => {
-                self.reflow_with_newly_loaded_web_font(possibly_locked_rw_data)
+                let start_time = ::time::precise_time_ns();
+                self.reflow_with_newly_loaded_web_font(possibly_locked_rw_data);
+                self.log_reflow("ReflowWithNewlyLoadedWebFont",
+                                None,
+                                ReflowDirt::default(),
+                                start_time);
             }
             Msg::SetVisibleRects(new_visible_rects) => {
                 self.set_visible_rects(new_visible_rects, possibly_locked_rw_data);
@@ -654,7 +1074,10 @@ impl LayoutThread {
             },
             Msg::GetCurrentEpoch(sender) => {
                 let _rw_data = possibly_locked_rw_data.lock();
-                sender.send(self.epoch).unwrap();
+                sender.send(ConsistencyToken {
+                    epoch: self.epoch,
+                    dom_version: self.displayed_dom_version,
+                }).unwrap();
             },
             Msg::GetWebFontLoadState(sender) => {
                 let _rw_data = possibly_locked_rw_data.lock();
@@ -667,6 +1090,51 @@ impl LayoutThread {
             Msg::SetFinalUrl(final_url) => {
                 self.url = final_url;
             },
+            Msg::SetInvalidationOverlays(overlays) => {
+                self.set_invalidation_overlays(overlays, possibly_locked_rw_data)
+            }
+            Msg::SetHighlightedNode(node) => {
+                let node = node.map(OpaqueNodeMethods::from_script_node);
+                self.set_highlighted_node(node, possibly_locked_rw_data)
+            }
+            Msg::GetRuleCoverage(stylesheets, sender) => {
+                let rw_data = possibly_locked_rw_data.lock();
+                let coverage = stylesheets.iter().map(|stylesheet| {
+                    rw_data.stylist.rule_coverage(stylesheet)
+                }).collect();
+                possibly_locked_rw_data.block(rw_data);
+                sender.send(coverage).unwrap();
+            }
+            Msg::GetSelectorCosts(reset, sender) => {
+                let rw_data = possibly_locked_rw_data.lock();
+                let costs = rw_data.stylist.selector_costs(reset);
+                possibly_locked_rw_data.block(rw_data);
+                sender.send(costs).unwrap();
+            }
+            Msg::EditStyleRule(stylesheet, line, column, edit, sender) => {
+                let mut rw_data = possibly_locked_rw_data.lock();
+                let previous_selectors = Arc::get_mut(&mut rw_data.stylist).unwrap()
+                    .edit_style_rule(&stylesheet, line, column, edit);
+                possibly_locked_rw_data.block(rw_data);
+                sender.send(previous_selectors).unwrap();
+            }
+            Msg::GetFragmentTree(sender) => {
+                let fragment_tree = self.root_flow.as_mut().map(layout_debug::fragment_tree_json);
+                sender.send(fragment_tree).unwrap();
+            }
+            Msg::GetRunningAnimations(sender) => {
+                sender.send(self.running_animations_for_devtools()).unwrap();
+            }
+            Msg::ControlAnimation(node, property, control) => {
+                self.control_animation(node, property, control)
+            }
+            Msg::AddKeyframesAnimation(node, id, effect, timing) => {
+                self.add_keyframes_animation(node, id, effect, timing)
+            }
+            Msg::ControlKeyframesAnimation(id, control) => {
+                self.control_keyframes_animation(id, control)
+            }
+            Msg::CancelKeyframesAnimation(id) => self.cancel_keyframes_animation(id),
             Msg::PrepareToExit(response_chan) => {
                 self.prepare_to_exit(response_chan);
                 return false
@@ -735,7 +1203,7 @@ impl LayoutThread {
                              info.constellation_chan,
                              info.panic_chan,
                              info.script_chan.clone(),
-                             info.paint_chan.to::<LayoutToPaintMsg>(),
+                             Some(info.paint_chan.to::<LayoutToPaintMsg>()),
                              self.image_cache_thread.clone(),
                              self.font_cache_thread.clone(),
                              self.time_profiler_chan.clone(),
@@ -778,10 +1246,12 @@ impl LayoutThread {
             traversal.shutdown()
         }
 
-        self.paint_chan.send(LayoutToPaintMsg::Exit).unwrap();
+        if let Some(ref paint_chan) = self.paint_chan {
+            paint_chan.send(LayoutToPaintMsg::Exit).unwrap();
+        }
     }
 
-    fn handle_add_stylesheet<'a, 'b>(&self,
+    fn handle_add_stylesheet<'a, 'b>(&mut self,
                                      stylesheet: Arc<Stylesheet>,
                                      possibly_locked_rw_data: &mut RwData<'a, 'b>) {
         // Find all font-face rules and notify the font cache of them.
@@ -792,7 +1262,8 @@ impl LayoutThread {
             add_font_face_rules(&*stylesheet,
                                 &rw_data.stylist.device,
                                 &self.font_cache_thread,
-                                &self.font_cache_sender,
+                                &mut self.web_font_loads,
+                                &self.deferred_font_cache_sender,
                                 &self.outstanding_web_fonts);
         }
 
@@ -806,6 +1277,131 @@ impl LayoutThread {
         possibly_locked_rw_data.block(rw_data);
     }
 
+    /// Describes the running animations for the devtools animation inspector. Those of
+    /// pseudo-elements are left out, since the inspector only knows about nodes.
+    fn running_animations_for_devtools(&self) -> Vec<RunningAnimation> {
+        let now = ::time::precise_time_s();
+        let running_animations = self.running_animations.read().unwrap();
+        running_animations.iter().filter(|&(target, _)| {
+            target.pseudo.is_none()
+        }).flat_map(|(_, animations)| animations.iter()).map(|animation| {
+            RunningAnimation {
+                node: animation.target.node.to_untrusted_node_address(),
+                property: animation.property_animation.property().to_css_string(),
+                duration: animation.duration(),
+                elapsed_time: animation.elapsed_time(now),
+                paused: animation.paused_at.is_some(),
+            }
+        }).collect()
+    }
+
+    /// Pauses, resumes or seeks the running animations of the given property of a node on behalf
+    /// of devtools. The change shows up on the next animation tick.
+    fn control_animation(&mut self,
+                         node: TrustedNodeAddress,
+                         property: String,
+                         control: AnimationControl) {
+        let node: OpaqueNode = OpaqueNodeMethods::from_script_node(node);
+        let now = ::time::precise_time_s();
+        let mut running_animations = self.running_animations.write().unwrap();
+        let animations = match running_animations.get_mut(&AnimationTarget::new(node, None)) {
+            Some(animations) => animations,
+            None => return,
+        };
+        for animation in animations.iter_mut() {
+            if animation.property_animation.property().to_css_string() != property {
+                continue
+            }
+            match control {
+                AnimationControl::Pause => animation.pause(now),
+                AnimationControl::Play => animation.play(now),
+                AnimationControl::Seek(elapsed_time) => animation.seek(now, elapsed_time),
+            }
+        }
+    }
+
+    /// Starts an animation that script created. Script restyles the node afterwards, which
+    /// applies the animation to it.
+    fn add_keyframes_animation(&mut self,
+                               node: TrustedNodeAddress,
+                               id: u32,
+                               effect: KeyframeEffect,
+                               timing: AnimationTiming) {
+        let node: OpaqueNode = OpaqueNodeMethods::from_script_node(node);
+        let animation = KeyframesAnimation {
+            id: id,
+            node: node,
+            effect: effect,
+            timing: timing,
+            paused_at: None,
+        };
+        let mut keyframes_animations = self.keyframes_animations.write().unwrap();
+        keyframes_animations.entry(node).or_insert_with(Vec::new).push(animation)
+    }
+
+    /// Pauses, resumes or seeks an animation that script created.
+    fn control_keyframes_animation(&mut self, id: u32, control: AnimationControl) {
+        let now = ::time::precise_time_s();
+        let mut keyframes_animations = self.keyframes_animations.write().unwrap();
+        let animations = keyframes_animations.values_mut()
+                                             .flat_map(|animations| animations.iter_mut());
+        for animation in animations {
+            if animation.id != id {
+                continue
+            }
+            match control {
+                AnimationControl::Pause => animation.pause(now),
+                AnimationControl::Play => animation.play(now),
+                AnimationControl::Seek(elapsed_time) => animation.seek(now, elapsed_time),
+            }
+        }
+    }
+
+    /// Drops an animation that script created, which script restyles the node without.
+    fn cancel_keyframes_animation(&mut self, id: u32) {
+        let mut keyframes_animations = self.keyframes_animations.write().unwrap();
+        let mut cancelled_nodes = vec![];
+        for (node, animations) in keyframes_animations.iter_mut() {
+            animations.retain(|animation| animation.id != id);
+            if animations.is_empty() {
+                cancelled_nodes.push(*node)
+            }
+        }
+        for node in cancelled_nodes {
+            keyframes_animations.remove(&node);
+        }
+    }
+
+    fn set_invalidation_overlays<'a, 'b>(&mut self,
+                                         overlays: InvalidationOverlays,
+                                         possibly_locked_rw_data: &mut RwData<'a, 'b>) {
+        if self.invalidation_overlays == overlays {
+            return
+        }
+        self.invalidation_overlays = overlays;
+
+        // Throw away the old display list so that the next reflow rebuilds it with (or without)
+        // the overlays, even if nothing on the page has changed.
+        let mut rw_data = possibly_locked_rw_data.lock();
+        rw_data.display_list = None;
+        possibly_locked_rw_data.block(rw_data);
+    }
+
+    fn set_highlighted_node<'a, 'b>(&mut self,
+                                    node: Option<OpaqueNode>,
+                                    possibly_locked_rw_data: &mut RwData<'a, 'b>) {
+        if self.highlighted_node == node {
+            return
+        }
+        self.highlighted_node = node;
+
+        // As for the invalidation overlays, the display list has to be rebuilt to show the
+        // highlight on an unchanged page.
+        let mut rw_data = possibly_locked_rw_data.lock();
+        rw_data.display_list = None;
+        possibly_locked_rw_data.block(rw_data);
+    }
+
     fn try_get_layout_root<N: LayoutNode>(&self, node: N) -> Option<FlowRef> {
         let mut data = match node.mutate_layout_data() {
             Some(x) => x,
@@ -883,6 +1479,11 @@ impl LayoutThread {
 
             if flow::base(&**layout_root).restyle_damage.contains(REPAINT) ||
                     rw_data.display_list.is_none() {
+                let build_start = if rw_data.phase_markers.is_some() {
+                    ::time::precise_time_ns()
+                } else {
+                    0
+                };
                 let mut root_stacking_context =
                     StackingContext::new(StackingContextId::new(0),
                                          StackingContextType::Real,
@@ -897,7 +1498,7 @@ impl LayoutThread {
                                          false,
                                          None);
 
-                let display_list_entries =
+                let (display_list_entries, custom_cursors) =
                     sequential::build_display_list_for_subtree(layout_root,
                                                                &mut root_stacking_context,
                                                                shared_layout_context);
@@ -923,10 +1524,20 @@ impl LayoutThread {
                                         ScrollPolicy::Scrollable,
                                         None,
                                         root_background_color));
-
-                rw_data.display_list =
-                    Some(Arc::new(DisplayList::new(root_stacking_context,
-                                                   &mut Some(display_list_entries))))
+                root_stacking_context.scroll_snap =
+                    get_viewport_scroll_snap(flow_ref::deref_mut(layout_root), self.viewport_size);
+
+                let display_list = DisplayList::new(root_stacking_context,
+                                                    &mut Some(display_list_entries),
+                                                    custom_cursors);
+                self.update_caret_blinking(&display_list);
+                let display_items = display_list.list.len();
+                rw_data.display_list = Some(Arc::new(display_list));
+                push_phase_marker(rw_data,
+                                  "DisplayList",
+                                  build_start,
+                                  ::time::precise_time_ns(),
+                                  display_items);
             }
 
             if data.goal == ReflowGoal::ForDisplay {
@@ -941,6 +1552,28 @@ impl LayoutThread {
 
                 debug!("Layout done!");
 
+                // The custom cursors may have changed even if the display list did not.
+                let hit_test_tree = hit_test::hit_test_tree(&display_list, &self.links);
+                let msg = ConstellationMsg::SetHitTestTree(self.id, hit_test_tree);
+                self.constellation_chan.send(msg).unwrap();
+
+                // What is on screen reflects the DOM that was last reflowed, whether or not the
+                // display list changed.
+                self.displayed_dom_version = rw_data.dom_version;
+
+                let paint_chan = match self.paint_chan {
+                    Some(ref paint_chan) => paint_chan,
+                    None => {
+                        debug!("Headless layout thread, not painting the display list");
+                        return
+                    }
+                };
+
+                if opts::get().use_webrender && !self.display_list_changed(&display_list) {
+                    debug!("Display list unchanged, not sending it to WebRender");
+                    return
+                }
+
                 self.epoch.next();
 
                 if opts::get().use_webrender {
@@ -979,25 +1612,52 @@ impl LayoutThread {
                                                   frame_builder.display_lists,
                                                   frame_builder.auxiliary_lists_builder
                                                                .finalize());
+                    self.retained_display_list = Some((display_list, self.viewport_size));
                 } else {
-                    self.paint_chan
-                        .send(LayoutToPaintMsg::PaintInit(self.epoch, display_list))
-                        .unwrap();
+                    paint_chan.send(LayoutToPaintMsg::PaintInit(self.epoch, display_list)).unwrap();
                 }
             }
         });
     }
 
+    /// Whether a display list differs from the one last sent to WebRender.
+    ///
+    /// WebRender replaces the whole scene of a pipeline with each display list it is sent, so a
+    /// display list in which only a few stacking contexts changed is still sent in full.
+    fn display_list_changed(&self, display_list: &Arc<DisplayList>) -> bool {
+        let (old_display_list, old_viewport_size) = match self.retained_display_list {
+            Some((ref display_list, viewport_size)) => (display_list, viewport_size),
+            None => return true,
+        };
+        if old_viewport_size != self.viewport_size {
+            return true
+        }
+        // Display lists are only rebuilt when there is something to repaint.
+        if &**old_display_list as *const DisplayList == &**display_list as *const DisplayList {
+            return false
+        }
+        let changed = display_list.changed_stacking_contexts(old_display_list);
+        debug!("{} stacking contexts changed since the last frame", changed.len());
+        !changed.is_empty()
+    }
+
     /// The high-level routine that performs layout threads.
+    /// Performs a reflow that script asked for, and returns how much of the document was dirty
+    /// before it, if reflows are logged.
     fn handle_reflow<'a, 'b>(&mut self,
                              data: &ScriptReflow,
-                             possibly_locked_rw_data: &mut RwData<'a, 'b>) {
+                             possibly_locked_rw_data: &mut RwData<'a, 'b>)
+                             -> ReflowDirt {
+        let reflow_start = ::time::precise_time_ns();
         let document = unsafe { ServoLayoutNode::new(&data.document) };
         let document = document.as_document().unwrap();
 
         debug!("layout: received layout request for: {}", self.url);
 
         let mut rw_data = possibly_locked_rw_data.lock();
+        rw_data.dom_version = data.dom_version;
+        rw_data.phase_markers = if data.record_phase_markers { Some(vec![]) } else { None };
+        self.animation_time = data.animation_tick.unwrap_or_else(::time::precise_time_s);
 
         let node: ServoLayoutNode = match document.root_node() {
             None => {
@@ -1034,9 +1694,21 @@ impl LayoutThread {
                     ReflowQueryType::MarginStyleQuery(_) => {
                         rw_data.margin_style_response = MarginStyleResponse::empty();
                     },
+                    ReflowQueryType::AnimationStateQuery(_) => {
+                        rw_data.animation_state_response = vec![];
+                    },
+                    ReflowQueryType::MatchedRulesQuery(_, _) => {
+                        rw_data.matched_rules_response = (vec![], false);
+                    },
+                    ReflowQueryType::BoxModelQuery(_) => {
+                        rw_data.box_model_response = None;
+                    },
+                    ReflowQueryType::UsedFontFacesQuery => {
+                        rw_data.used_font_faces_response = vec![];
+                    },
                     ReflowQueryType::NoQuery => {}
                 }
-                return;
+                return ReflowDirt::default();
             },
             Some(x) => x,
         };
@@ -1048,11 +1720,12 @@ impl LayoutThread {
 
         let initial_viewport = data.window_size.initial_viewport;
         let old_viewport_size = self.viewport_size;
+        self.device_pixel_ratio = data.window_size.device_pixel_ratio.get();
         let current_screen_size = Size2D::new(Au::from_f32_px(initial_viewport.width.get()),
                                               Au::from_f32_px(initial_viewport.height.get()));
 
         // Calculate the actual viewport as per DEVICE-ADAPT § 6
-        let device = Device::new(MediaType::Screen, initial_viewport);
+        let device = Device::new(media_type(), initial_viewport);
         Arc::get_mut(&mut rw_data.stylist).unwrap().set_device(device, &data.document_stylesheets);
 
         let constraints = rw_data.stylist.viewport_constraints().clone();
@@ -1100,36 +1773,80 @@ impl LayoutThread {
         }
 
         let modified_elements = document.drain_modified_elements();
+        let mut dirt = ReflowDirt::default();
+        if opts::get().log_reflows {
+            dirt.modified_elements = modified_elements.len();
+        }
         if !needs_dirtying {
             for (el, snapshot) in modified_elements {
                 let hint = rw_data.stylist.compute_restyle_hint(&el, &snapshot, el.get_state());
                 el.note_restyle_hint(hint);
+                if !hint.contains(RESTYLE_SELF) &&
+                        generated_content_reads_changed_attributes(&el, &snapshot) {
+                    // Only the boxes of the element have to be built again, with the new
+                    // values of the attributes: its style and those of its descendants stand.
+                    let node = el.as_node();
+                    node.set_restyle_damage(node.restyle_damage() |
+                                            RestyleDamage::rebuild_and_reflow());
+                    let mut curr = Some(node);
+                    while let Some(node) = curr {
+                        if node.has_dirty_descendants() { break }
+                        unsafe { node.set_dirty_descendants(true); }
+                        curr = node.parent_node();
+                    }
+                }
             }
         }
 
-        // Create a layout context for use throughout the following passes.
-        let mut shared_layout_context = self.build_shared_layout_context(&*rw_data,
-                                                                         viewport_size_changed,
-                                                                         data.reflow_info.goal);
+        // Script reflows for display when the insertion point may have moved, and carets are
+        // shown steadily while they move.
+        if data.reflow_info.goal == ReflowGoal::ForDisplay {
+            self.caret_blink_start = ::time::precise_time_ns();
+            if !self.caret_visible {
+                self.caret_visible = true;
+                rw_data.display_list = None;
+            }
+        }
+
+        // Create a layout context for use throughout the following passes. Styles that were
+        // cascaded for another viewport or device, such as one in another forced colors mode, are
+        // evicted from the caches.
+        let mut shared_layout_context =
+            self.build_shared_layout_context(&*rw_data,
+                                             viewport_size_changed || needs_dirtying,
+                                             data.reflow_info.goal);
+
+        if opts::get().log_reflows {
+            dirt.dirty_nodes = node.traverse_preorder().filter(|node| node.is_dirty()).count();
+        }
 
         if node.is_dirty() || node.has_dirty_descendants() {
             // Recalculate CSS styles and rebuild flows and fragments.
+            let traversal_start = ::time::precise_time_ns();
             profile(time::ProfilerCategory::LayoutStyleRecalc,
                     self.profiler_metadata(),
                     self.time_profiler_chan.clone(),
                     || {
-                // Perform CSS selector matching and flow construction.
+                // Perform CSS selector matching and flow construction. The traversal can be
+                // made sequential on its own to debug it, keeping the flow passes parallel.
+                let parallel_dom_traversal = get_pref("layout.parallel_dom_traversal.enabled")
+                    .as_boolean().unwrap_or(true);
                 match self.parallel_traversal {
-                    None => {
-                        sequential::traverse_dom::<ServoLayoutNode, RecalcStyleAndConstructFlows>(
-                            node, &shared_layout_context);
-                    }
-                    Some(ref mut traversal) => {
+                    Some(ref mut traversal) if parallel_dom_traversal => {
                         parallel::traverse_dom::<ServoLayoutNode, RecalcStyleAndConstructFlows>(
                             node, &shared_layout_context, traversal);
                     }
+                    _ => {
+                        sequential::traverse_dom::<ServoLayoutNode, RecalcStyleAndConstructFlows>(
+                            node, &shared_layout_context);
+                    }
                 }
             });
+            if shared_layout_context.style_context.record_phase_times {
+                self.note_style_traversal_phases(&mut *rw_data,
+                                                 traversal_start,
+                                                 ::time::precise_time_ns());
+            }
 
             // TODO(pcwalton): Measure energy usage of text shaping, perhaps?
             let text_shaping_time =
@@ -1143,10 +1860,28 @@ impl LayoutThread {
                                     0,
                                     0);
 
+            if opts::get().profile_cascade {
+                let timings = properties::get_and_reset_cascade_timings();
+                let timings = timings.into_iter().map(|(name, declarations, time)| {
+                    (name.to_owned(), declarations, time)
+                }).collect();
+                self.time_profiler_chan.send(time::ProfilerMsg::PropertyCascade(timings));
+            }
+
             // Retrieve the (possibly rebuilt) root flow.
             self.root_flow = self.try_get_layout_root(node);
+
+            self.links = hit_test::collect_links(node, &self.url);
+        }
+
+        // This reflow renders an animation tick, so the animations of nodes that script left
+        // alone are sampled too, rather than in a layout of their own.
+        if data.animation_tick.is_some() {
+            self.recalc_style_for_animations();
         }
 
+        LayoutThread::update_cascade_snapshot(&*rw_data, node);
+
         // Perform post-style recalculation layout passes.
         self.perform_post_style_recalc_layout_passes(&data.reflow_info,
                                                      &mut rw_data,
@@ -1193,7 +1928,13 @@ impl LayoutThread {
                 ReflowQueryType::ResolvedStyleQuery(node, ref pseudo, ref property) => {
                     let node = unsafe { ServoLayoutNode::new(&node) };
                     rw_data.resolved_style_response =
-                        process_resolved_style_request(node, pseudo, property, &mut root_flow);
+                        process_resolved_style_request(node,
+                                                       pseudo,
+                                                       property,
+                                                       &mut root_flow,
+                                                       &*self.running_animations.read().unwrap(),
+                                                       self.animation_time,
+                                                       &*rw_data.stylist);
                 },
                 ReflowQueryType::OffsetParentQuery(node) => {
                     let node = unsafe { ServoLayoutNode::new(&node) };
@@ -1203,9 +1944,111 @@ impl LayoutThread {
                     let node = unsafe { ServoLayoutNode::new(&node) };
                     rw_data.margin_style_response = process_margin_style_query(node);
                 },
+                ReflowQueryType::AnimationStateQuery(node) => {
+                    let node = unsafe { ServoLayoutNode::new(&node) };
+                    rw_data.animation_state_response =
+                        process_animation_state_query(node,
+                                                      &*self.running_animations.read().unwrap(),
+                                                      &*self.keyframes_animations.read().unwrap(),
+                                                      self.animation_time);
+                },
+                ReflowQueryType::MatchedRulesQuery(node, ref pseudo) => {
+                    let node = unsafe { ServoLayoutNode::new(&node) };
+                    let matched_rules =
+                        process_matched_rules_query(node, pseudo, &*rw_data.stylist);
+                    rw_data.matched_rules_response = matched_rules;
+                },
+                ReflowQueryType::BoxModelQuery(node) => {
+                    let node = unsafe { ServoLayoutNode::new(&node) };
+                    rw_data.box_model_response = process_box_model_query(node, &mut root_flow);
+                },
+                ReflowQueryType::UsedFontFacesQuery => {
+                    rw_data.used_font_faces_response =
+                        process_used_font_faces_query(&mut root_flow);
+                },
                 ReflowQueryType::NoQuery => {}
             }
         }
+
+        // The DOM can only be read while script waits for the reflow, so the layout thread is
+        // idle for the rest of the frame. Queries are answered as soon as possible instead.
+        if data.query_type == ReflowQueryType::NoQuery &&
+                data.reflow_info.goal == ReflowGoal::ForDisplay &&
+                get_pref("layout.lazy_pseudo_element_precompute.enabled").as_boolean()
+                                                                        .unwrap_or(false) {
+            LayoutThread::precompute_lazy_pseudo_element_styles(node,
+                                                                &*rw_data.stylist,
+                                                                reflow_start + FRAME_DURATION_NS);
+        }
+        dirt
+    }
+
+    /// Takes a new snapshot of what styles are cascaded against if the device or the style of the
+    /// root element changed since the last one was taken.
+    fn update_cascade_snapshot(rw_data: &LayoutThreadData, root: ServoLayoutNode) {
+        let root_style = root.borrow_data().and_then(|data| data.style.clone());
+        let is_stale = {
+            let snapshot = rw_data.cascade_snapshot.read().unwrap();
+            snapshot.device.media_type != rw_data.stylist.device.media_type ||
+                snapshot.device.viewport_size != rw_data.stylist.device.viewport_size ||
+                match (&snapshot.root_style, &root_style) {
+                    (&Some(ref old_style), &Some(ref new_style)) => {
+                        !::util::arc_ptr_eq(old_style, new_style)
+                    }
+                    (&None, &None) => false,
+                    _ => true,
+                }
+        };
+        if is_stale {
+            *rw_data.cascade_snapshot.write().unwrap() =
+                Arc::new(CascadeSnapshot::new(&*rw_data.stylist, root_style));
+        }
+    }
+
+    /// Computes ahead of time the styles of the lazily cascaded pseudo-elements of the elements
+    /// that their rules may match, so that the boxes that are built for them later, such as after
+    /// a class is toggled, do not wait for their cascade. Stops at `deadline`.
+    fn precompute_lazy_pseudo_element_styles(root: ServoLayoutNode,
+                                             stylist: &Stylist,
+                                             deadline: u64) {
+        for (index, node) in root.traverse_preorder().enumerate() {
+            if index % PRECOMPUTE_NODES_PER_TIME_CHECK == 0 &&
+                    ::time::precise_time_ns() >= deadline {
+                return
+            }
+            let element = match node.as_element() {
+                Some(element) => element,
+                None => continue,
+            };
+            let pseudos = stylist.lazy_pseudo_elements_that_may_match(&element);
+            if pseudos.is_empty() {
+                continue
+            }
+            let mut data = match node.mutate_data() {
+                Some(data) => data,
+                None => continue,
+            };
+            let style = match data.style {
+                Some(ref style) if style.get_box().display != display::T::none => style.clone(),
+                _ => continue,
+            };
+            for pseudo in pseudos {
+                // No boxes are built for first lines, whose styles only queries compute.
+                if pseudo == PseudoElement::FirstLine || data.per_pseudo.contains_key(&pseudo) {
+                    continue
+                }
+                // Without rules of their own, they just inherit.
+                let pseudo_style =
+                    stylist.lazily_compute_pseudo_element_style(&node.to_threadsafe().as_element(),
+                                                                &pseudo,
+                                                                &style)
+                           .unwrap_or_else(|| {
+                               ServoComputedValues::style_for_child_text_node(&style)
+                           });
+                data.per_pseudo.insert(pseudo.clone(), pseudo_style);
+                data.speculative_pseudos.push(pseudo);
+            }
+        }
     }
 
     fn set_visible_rects<'a, 'b>(&mut self,
@@ -1264,12 +2107,26 @@ impl LayoutThread {
         true
     }
 
-    fn tick_all_animations<'a, 'b>(&mut self, possibly_locked_rw_data: &mut RwData<'a, 'b>) {
+    fn tick_all_animations<'a, 'b>(&mut self,
+                                   frame_time: f64,
+                                   possibly_locked_rw_data: &mut RwData<'a, 'b>) {
         let mut rw_data = possibly_locked_rw_data.lock();
+        self.animation_time = frame_time;
         self.tick_animations(&mut rw_data);
     }
 
     pub fn tick_animations(&mut self, rw_data: &mut LayoutThreadData) {
+        // Carets blink by rebuilding the display list with them shown or hidden.
+        if self.update_caret_visibility() {
+            rw_data.display_list = None;
+        } else if animation::animation_state(&*self.running_animations.read().unwrap(),
+                                             &*self.keyframes_animations.read().unwrap(),
+                                             &self.handed_off_targets,
+                                             false) == AnimationState::NoAnimationsPresent {
+            // Only carets keep animations ticking, and they have not blinked yet.
+            return
+        }
+
         let reflow_info = Reflow {
             goal: ReflowGoal::ForDisplay,
             page_clip_rect: MAX_RECT,
@@ -1279,27 +2136,41 @@ impl LayoutThread {
                                                                   false,
                                                                   reflow_info.goal);
 
+        self.recalc_style_for_animations();
+
+        self.perform_post_style_recalc_layout_passes(&reflow_info,
+                                                     &mut *rw_data,
+                                                     &mut layout_context);
+    }
+
+    /// Applies the running animations at `animation_time` to the styles of the fragments of
+    /// their nodes, in an abbreviated style recalc that operates without access to the DOM.
+    fn recalc_style_for_animations(&self) {
         if let Some(mut root_flow) = self.root_flow.clone() {
-            // Perform an abbreviated style recalc that operates without access to the DOM.
             let animations = self.running_animations.read().unwrap();
+            let keyframes_animations = self.keyframes_animations.read().unwrap();
             profile(time::ProfilerCategory::LayoutStyleRecalc,
                     self.profiler_metadata(),
                     self.time_profiler_chan.clone(),
                     || {
                         animation::recalc_style_for_animations(flow_ref::deref_mut(&mut root_flow),
-                                                               &*animations)
+                                                               &*animations,
+                                                               &*keyframes_animations,
+                                                               self.viewport_size,
+                                                               self.animation_time)
                     });
         }
-
-        self.perform_post_style_recalc_layout_passes(&reflow_info,
-                                                     &mut *rw_data,
-                                                     &mut layout_context);
     }
 
     fn reflow_with_newly_loaded_web_font<'a, 'b>(&mut self, possibly_locked_rw_data: &mut RwData<'a, 'b>) {
         let mut rw_data = possibly_locked_rw_data.lock();
         font_context::invalidate_font_caches();
 
+        // Text runs are only shaped during flow construction, so the font is only used once
+        // script restyles the document. Until then, text that waited for it is painted with the
+        // fallback font.
+        self.script_chan.send(ConstellationControlMsg::WebFontLoaded(self.id)).unwrap();
+
         let reflow_info = Reflow {
             goal: ReflowGoal::ForDisplay,
             page_clip_rect: MAX_RECT,
@@ -1325,15 +2196,20 @@ impl LayoutThread {
         if let Some(mut root_flow) = self.root_flow.clone() {
             // Kick off animations if any were triggered, expire completed ones.
             animation::update_animation_state(&self.constellation_chan,
+                                              &self.script_chan,
                                               &mut *self.running_animations.write().unwrap(),
                                               &mut *self.expired_animations.write().unwrap(),
+                                              &mut *self.keyframes_animations.write().unwrap(),
                                               &self.new_animations_receiver,
-                                              self.id);
-
-            profile(time::ProfilerCategory::LayoutRestyleDamagePropagation,
-                    self.profiler_metadata(),
-                    self.time_profiler_chan.clone(),
-                    || {
+                                              &self.handed_off_targets,
+                                              self.caret_present,
+                                              self.id,
+                                              self.animation_time);
+
+            let special_damage = profile(time::ProfilerCategory::LayoutRestyleDamagePropagation,
+                                         self.profiler_metadata(),
+                                         self.time_profiler_chan.clone(),
+                                         || {
                 // Call `compute_layout_damage` even in non-incremental mode, because it sets flags
                 // that are needed in both incremental and non-incremental traversals.
                 let damage = flow_ref::deref_mut(&mut root_flow).compute_layout_damage();
@@ -1341,8 +2217,15 @@ impl LayoutThread {
                 if opts::get().nonincremental_layout || damage.contains(REFLOW_ENTIRE_DOCUMENT) {
                     flow_ref::deref_mut(&mut root_flow).reflow_entire_document()
                 }
+                damage
             });
 
+            if self.invalidation_overlays.reflow_outlines {
+                // Remember which flows are about to be reflowed, since the layout passes clear
+                // their reflow damage before display list construction gets to see it.
+                LayoutThread::mark_reflowed_flows(flow_ref::deref_mut(&mut root_flow));
+            }
+
             if opts::get().trace_layout {
                 layout_debug::begin_trace(root_flow.clone());
             }
@@ -1360,8 +2243,15 @@ impl LayoutThread {
                     || sequential::guess_float_placement(flow_ref::deref_mut(&mut root_flow)));
 
             // Perform the primary layout passes over the flow tree to compute the locations of all
-            // the boxes.
-            if flow::base(&*root_flow).restyle_damage.intersects(REFLOW | REFLOW_OUT_OF_FLOW) {
+            // the boxes. Only the damaged flows are laid out again, which may be just the
+            // subtrees of some reflow roots.
+            if flow::base(&*root_flow).restyle_damage.intersects(REFLOW | REFLOW_OUT_OF_FLOW) ||
+                    special_damage.contains(REFLOW_REFLOW_ROOTS) {
+                let (layout_start, reflowed_flows) = if rw_data.phase_markers.is_some() {
+                    (::time::precise_time_ns(), LayoutThread::count_reflowed_flows(&*root_flow))
+                } else {
+                    (0, 0)
+                };
                 profile(time::ProfilerCategory::LayoutMain,
                         self.profiler_metadata(),
                         self.time_profiler_chan.clone(),
@@ -1382,6 +2272,11 @@ impl LayoutThread {
                         }
                     }
                 });
+                push_phase_marker(rw_data,
+                                  "Layout",
+                                  layout_start,
+                                  ::time::precise_time_ns(),
+                                  reflowed_flows);
             }
 
             profile(time::ProfilerCategory::LayoutStoreOverflow,
@@ -1407,6 +2302,7 @@ impl LayoutThread {
                                                         &mut root_flow,
                                                         &mut *layout_context,
                                                         rw_data);
+            self.hand_off_layer_animations(&mut root_flow);
             self.first_reflow = false;
 
             if opts::get().trace_layout {
@@ -1416,11 +2312,82 @@ impl LayoutThread {
             if opts::get().dump_flow_tree {
                 root_flow.print("Post layout flow tree".to_owned());
             }
+            if opts::get().dump_fragment_tree_json {
+                println!("{}", layout_debug::fragment_tree_json(&mut root_flow));
+            }
 
             self.generation += 1;
         }
     }
 
+    /// Shows or hides carets for the current phase of their blinking. Returns whether that
+    /// changed.
+    fn update_caret_visibility(&mut self) -> bool {
+        if !self.caret_present {
+            return false
+        }
+        let elapsed = ::time::precise_time_ns() - self.caret_blink_start;
+        let visible = (elapsed / CARET_BLINK_INTERVAL_NS) % 2 == 0;
+        if visible == self.caret_visible {
+            return false
+        }
+        self.caret_visible = visible;
+        true
+    }
+
+    /// Starts or stops ticking animations for carets to blink, if `display_list` gained its
+    /// first caret or lost its last one.
+    fn update_caret_blinking(&mut self, display_list: &DisplayList) {
+        let caret_present = display_list.list.iter().any(|item| {
+            match *item {
+                DisplayItem::CaretClass(_) => true,
+                _ => false,
+            }
+        });
+        if caret_present == self.caret_present {
+            return
+        }
+        self.caret_present = caret_present;
+        let keyframes_animations = self.keyframes_animations.read().unwrap();
+        let animation_state = animation::animation_state(&*self.running_animations.read().unwrap(),
+                                                         &*keyframes_animations,
+                                                         &self.handed_off_targets,
+                                                         caret_present);
+        let msg = ConstellationMsg::ChangeRunningAnimationsState(self.id, animation_state);
+        self.constellation_chan.send(msg).unwrap();
+    }
+
+    /// Hands the animations the compositor can run by itself off to it, so that they do not need
+    /// a reflow on every tick. WebRender has no way of animating parts of a display list by
+    /// itself, so layout keeps ticking all animations there.
+    fn hand_off_layer_animations(&mut self, root_flow: &mut FlowRef) {
+        if opts::get().use_webrender {
+            return
+        }
+
+        let running_animations = self.running_animations.read().unwrap();
+        let keyframes_animations = self.keyframes_animations.read().unwrap();
+        let mut handed_off_targets = HashSet::new();
+        let layer_animations = animation::layer_animations(flow_ref::deref_mut(root_flow),
+                                                           &*running_animations,
+                                                           &mut handed_off_targets);
+        if layer_animations == self.layer_animations {
+            return
+        }
+
+        let msg = ConstellationMsg::SetLayerAnimations(self.id, layer_animations.clone());
+        self.constellation_chan.send(msg).unwrap();
+        let animation_state = animation::animation_state(&*running_animations,
+                                                         &*keyframes_animations,
+                                                         &handed_off_targets,
+                                                         self.caret_present);
+        let msg = ConstellationMsg::ChangeRunningAnimationsState(self.id, animation_state);
+        self.constellation_chan.send(msg).unwrap();
+
+        self.layer_animations = layer_animations;
+        self.handed_off_targets = handed_off_targets;
+    }
+
     unsafe fn dirty_all_nodes<N: LayoutNode>(node: N) {
         for node in node.traverse_preorder() {
             // TODO(cgaebel): mark nodes which are sensitive to media queries as
@@ -1440,6 +2407,76 @@ impl LayoutThread {
         }
     }
 
+    /// Returns the number of flows in the tree of `flow` that the primary layout passes are to
+    /// lay out again.
+    fn count_reflowed_flows(flow: &Flow) -> usize {
+        let mut count = if flow::base(flow).restyle_damage.intersects(REFLOW | REFLOW_OUT_OF_FLOW) {
+            1
+        } else {
+            0
+        };
+        for child in flow::child_iter(flow) {
+            count += LayoutThread::count_reflowed_flows(child);
+        }
+        count
+    }
+
+    /// Reports how long selector matching, the cascade and flow construction took in the style
+    /// traversal that ran from `start_time` to `end_time`, to the time profiler and to the phase
+    /// markers. Only the time of the whole traversal is known, so selector matching and the
+    /// cascade are given their share of it, and flow construction the rest.
+    fn note_style_traversal_phases(&self,
+                                   rw_data: &mut LayoutThreadData,
+                                   start_time: u64,
+                                   end_time: u64) {
+        let (selector_matching, cascade) = get_and_reset_phase_totals();
+        let flow_construction_count = get_and_reset_flow_construction_count();
+        let layout_threads = opts::get().layout_threads as u64;
+        let matching_end = min(start_time + selector_matching.time / layout_threads, end_time);
+        let cascade_end = min(matching_end + cascade.time / layout_threads, end_time);
+
+        for &(category, start, end) in &[
+            (time::ProfilerCategory::LayoutSelectorMatch, start_time, matching_end),
+            (time::ProfilerCategory::LayoutCascade, matching_end, cascade_end),
+            (time::ProfilerCategory::LayoutTreeBuilder, cascade_end, end_time),
+        ] {
+            time::send_profile_data(category,
+                                    self.profiler_metadata(),
+                                    self.time_profiler_chan.clone(),
+                                    start,
+                                    end,
+                                    0,
+                                    0);
+        }
+
+        push_phase_marker(rw_data,
+                          "SelectorMatching",
+                          start_time,
+                          matching_end,
+                          selector_matching.count);
+        push_phase_marker(rw_data, "Cascade", matching_end, cascade_end, cascade.count);
+        push_phase_marker(rw_data,
+                          "FlowConstruction",
+                          cascade_end,
+                          end_time,
+                          flow_construction_count);
+    }
+
+    fn mark_reflowed_flows(flow: &mut Flow) {
+        {
+            let base = flow::mut_base(flow);
+            if base.restyle_damage.intersects(REFLOW | REFLOW_OUT_OF_FLOW) {
+                base.flags.insert(WAS_REFLOWED)
+            } else {
+                base.flags.remove(WAS_REFLOWED)
+            }
+        }
+
+        for child in flow::child_iter_mut(flow) {
+            LayoutThread::mark_reflowed_flows(child);
+        }
+    }
+
     /// Handles a message to destroy layout data. Layout data must be destroyed on *this* thread
     /// because the struct type is transmuted to a different type on the script side.
     unsafe fn handle_reap_style_and_layout_data(&self, data: OpaqueStyleAndLayoutData) {
@@ -1448,6 +2485,36 @@ impl LayoutThread {
         let _ = Box::from_raw(non_opaque);
     }
 
+    /// Prints a line of JSON about a reflow that a message triggered, along with the script
+    /// reflow it was for if any, if the `log-reflows` debug option is set.
+    fn log_reflow(&self,
+                  trigger: &'static str,
+                  data: Option<&ScriptReflow>,
+                  dirt: ReflowDirt,
+                  start_time: u64) {
+        if !opts::get().log_reflows {
+            return
+        }
+        let end_time = ::time::precise_time_ns();
+        let entry = ReflowLogEntry {
+            pipeline: self.id.to_string(),
+            url: self.url.to_string(),
+            trigger: trigger,
+            goal: match data.map(|data| data.reflow_info.goal) {
+                Some(ReflowGoal::ForScriptQuery) => "ForScriptQuery",
+                _ => "ForDisplay",
+            },
+            query: data.map(|data| data.query_type.name()),
+            reason: data.map(|data| data.reason),
+            stylesheets_changed: data.map_or(false, |data| data.stylesheets_changed),
+            dirty_nodes: dirt.dirty_nodes,
+            modified_elements: dirt.modified_elements,
+            start_time: start_time,
+            duration: end_time - start_time,
+        };
+        println!("{}", json::encode(&entry).unwrap());
+    }
+
     /// Returns profiling information which is passed to the time profiler.
     fn profiler_metadata(&self) -> Option<TimerMetadata> {
         Some(TimerMetadata {
@@ -1466,6 +2533,110 @@ impl LayoutThread {
     }
 }
 
+/// How much of the document was dirty before a script reflow, when reflows are logged.
+#[derive(Clone, Copy, Default)]
+struct ReflowDirt {
+    /// The number of nodes that needed to be restyled.
+    dirty_nodes: usize,
+    /// The number of elements whose state or attributes changed, for which restyle hints were
+    /// computed.
+    modified_elements: usize,
+}
+
+/// A line of the reflow log of the `log-reflows` debug option.
+#[derive(RustcEncodable)]
+struct ReflowLogEntry {
+    pipeline: String,
+    url: String,
+    /// The message that the reflow was for.
+    trigger: &'static str,
+    goal: &'static str,
+    /// The type of the query of the script reflow, and why script asked for it, if the reflow
+    /// was for script.
+    query: Option<&'static str>,
+    reason: Option<&'static str>,
+    stylesheets_changed: bool,
+    dirty_nodes: usize,
+    modified_elements: usize,
+    /// When the reflow started and how long it took, in nanoseconds.
+    start_time: u64,
+    duration: u64,
+}
+
+/// Records a phase of the current script reflow, if it asked for them.
+fn push_phase_marker(rw_data: &mut LayoutThreadData,
+                     name: &'static str,
+                     start_time: u64,
+                     end_time: u64,
+                     count: usize) {
+    if let Some(ref mut markers) = rw_data.phase_markers {
+        markers.push(LayoutPhaseMarker {
+            name: name,
+            start_time: start_time,
+            end_time: end_time,
+            count: count,
+        })
+    }
+}
+
+/// The media type that style is matched against: paginated documents are printed.
+fn media_type() -> MediaType {
+    if opts::get().paginate {
+        MediaType::Print
+    } else {
+        MediaType::Screen
+    }
+}
+
+/// Whether the `content` of a pseudo-element of `element` reads an attribute with `attr()`
+/// whose value is not the one in `snapshot`, which was taken before the attributes changed.
+fn generated_content_reads_changed_attributes(element: &ServoLayoutElement,
+                                              snapshot: &ElementSnapshot)
+                                              -> bool {
+    if snapshot.attrs.is_none() {
+        return false
+    }
+    let data = match element.as_node().borrow_data() {
+        Some(data) => data,
+        None => return false,
+    };
+    data.per_pseudo.values().any(|style| {
+        let items = match style.get_counters().content {
+            content::T::Content(ref items) => items,
+            _ => return false,
+        };
+        items.iter().any(|item| {
+            match *item {
+                ContentItem::Attr(ref name, _, _) => {
+                    let name = Atom::from(&**name);
+                    element.get_attr(&ns!(), &name) !=
+                        snapshot.get_attr(&ns!(), &name).map(|value| &**value)
+                }
+                _ => false,
+            }
+        })
+    })
+}
+
+/// How the viewport snaps its scroll position, which the `scroll-snap-type` of the root element
+/// determines.
+fn get_viewport_scroll_snap(flow: &mut Flow, viewport_size: Size2D<Au>)
+                            -> Option<ScrollSnapContainer> {
+    if !flow.is_block_like() {
+        return None
+    }
+
+    let box_style = flow.as_block().fragment.style.get_box();
+    if box_style.scroll_snap_type_x == scroll_snap_type_x::T::none &&
+            box_style.scroll_snap_type_y == scroll_snap_type_y::T::none {
+        return None
+    }
+    Some(ScrollSnapContainer {
+        type_x: box_style.scroll_snap_type_x,
+        type_y: box_style.scroll_snap_type_y,
+        scrollport: Rect::new(Point2D::zero(), viewport_size),
+    })
+}
 
 // The default computed value for background-color is transparent (see
 // http://dev.w3.org/csswg/css-backgrounds/#background-color). However, we
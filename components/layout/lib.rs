@@ -77,6 +77,7 @@ mod flow_list;
 mod flow_ref;
 mod fragment;
 mod generated_content;
+mod hit_test;
 mod incremental;
 mod inline;
 pub mod layout_thread;
@@ -87,6 +88,7 @@ mod opaque_node;
 mod parallel;
 mod persistent_list;
 mod query;
+mod ruby;
 mod sequential;
 mod table;
 mod table_caption;
@@ -21,7 +21,7 @@ use gfx::display_list::{StackingContext, StackingContextId};
 use incremental::RESOLVE_GENERATED_CONTENT;
 use inline::InlineMetrics;
 use std::sync::Arc;
-use style::computed_values::{list_style_type, position};
+use style::computed_values::{border_collapse, list_style_type, position};
 use style::logical_geometry::LogicalSize;
 use style::properties::{ComputedValues, ServoComputedValues};
 use text;
@@ -87,16 +87,26 @@ impl Flow for ListItemFlow {
 
         for marker in self.marker_fragments.iter_mut().rev() {
             let containing_block_inline_size = self.block_flow.base.block_container_inline_size;
-            marker.assign_replaced_inline_size_if_necessary(containing_block_inline_size);
+            let containing_block_block_size =
+                self.block_flow.base.block_container_explicit_block_size;
+            marker.assign_replaced_inline_size_if_necessary(containing_block_inline_size,
+                                                            containing_block_block_size);
 
             // Do this now. There's no need to do this in bubble-widths, since markers do not
             // contribute to the inline size of this flow.
             let intrinsic_inline_sizes = marker.compute_intrinsic_inline_sizes();
 
+            // `::marker` rules can give markers borders, padding and margins.
+            marker.compute_border_and_padding(containing_block_inline_size,
+                                              border_collapse::T::separate);
+            marker.compute_inline_direction_margins(containing_block_inline_size);
             marker.border_box.size.inline =
-                intrinsic_inline_sizes.content_intrinsic_sizes.preferred_inline_size;
-            marker_inline_start = marker_inline_start - marker.border_box.size.inline;
+                intrinsic_inline_sizes.content_intrinsic_sizes.preferred_inline_size +
+                marker.border_padding.inline_start_end();
+            marker_inline_start = marker_inline_start - marker.margin.inline_end -
+                marker.border_box.size.inline;
             marker.border_box.start.i = marker_inline_start;
+            marker_inline_start = marker_inline_start - marker.margin.inline_start;
         }
     }
 
@@ -133,6 +143,10 @@ impl Flow for ListItemFlow {
         self.block_flow.is_absolute_containing_block()
     }
 
+    fn is_containing_block_for_fixed_descendants(&self) -> bool {
+        self.block_flow.is_containing_block_for_fixed_descendants()
+    }
+
     fn update_late_computed_inline_position_if_necessary(&mut self, inline_position: Au) {
         self.block_flow.update_late_computed_inline_position_if_necessary(inline_position)
     }
@@ -225,10 +239,10 @@ pub enum ListStyleTypeContent {
 
 impl ListStyleTypeContent {
     /// Returns the content to be used for the given value of the `list-style-type` property.
-    pub fn from_list_style_type(list_style_type: list_style_type::T) -> ListStyleTypeContent {
+    pub fn from_list_style_type(list_style_type: &list_style_type::T) -> ListStyleTypeContent {
         // Just to keep things simple, use a nonbreaking space (Unicode 0xa0) to provide the marker
         // separation.
-        match list_style_type {
+        match *list_style_type {
             list_style_type::T::none => ListStyleTypeContent::None,
             list_style_type::T::disc | list_style_type::T::circle | list_style_type::T::square |
             list_style_type::T::disclosure_open | list_style_type::T::disclosure_closed => {
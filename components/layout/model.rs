@@ -414,6 +414,19 @@ impl MaybeAuto {
         }
     }
 
+    /// Like `from_style`, but percentages of an indefinite containing length are `auto`.
+    #[inline]
+    pub fn from_option_style(length: LengthOrPercentageOrAuto, containing_length: Option<Au>)
+                             -> MaybeAuto {
+        match (length, containing_length) {
+            (LengthOrPercentageOrAuto::Percentage(_), None) |
+            (LengthOrPercentageOrAuto::Calc(_), None) => MaybeAuto::Auto,
+            (length, containing_length) => {
+                MaybeAuto::from_style(length, containing_length.unwrap_or(Au(0)))
+            }
+        }
+    }
+
     #[inline]
     pub fn specified_or_default(&self, default: Au) -> Au {
         match *self {
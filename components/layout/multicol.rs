@@ -20,7 +20,7 @@ use std::cmp::{min, max};
 use std::fmt;
 use std::sync::Arc;
 use style::context::StyleContext;
-use style::logical_geometry::LogicalSize;
+use style::logical_geometry::{LogicalMargin, LogicalSize};
 use style::properties::{ComputedValues, ServoComputedValues};
 use style::values::computed::{LengthOrPercentageOrAuto, LengthOrPercentageOrNone};
 use util::print_tree::PrintTree;
@@ -31,6 +31,10 @@ pub struct MulticolFlow {
     /// Length between the inline-start edge of a column and that of the next.
     /// That is, the used column-width + used column-gap.
     pub column_pitch: Au,
+
+    /// Whether this is the root of a paginated document, whose columns are its pages, as set
+    /// up by `@page` rules.
+    pub paged: bool,
 }
 
 pub struct MulticolColumnFlow {
@@ -38,12 +42,57 @@ pub struct MulticolColumnFlow {
 }
 
 impl MulticolFlow {
-    pub fn from_fragment(fragment: Fragment, float_kind: Option<FloatKind>) -> MulticolFlow {
+    pub fn from_fragment(fragment: Fragment, float_kind: Option<FloatKind>, paged: bool)
+                         -> MulticolFlow {
         MulticolFlow {
             block_flow: BlockFlow::from_fragment(fragment, float_kind),
             column_pitch: Au(0),
+            paged: paged,
         }
     }
+
+    /// Fragments the contents of a paginated document into the page areas of its pages, and
+    /// stacks the pages along the block axis.
+    fn assign_block_size_for_pages<'a>(&mut self, ctx: &'a LayoutContext<'a>) {
+        let writing_mode = self.block_flow.base.writing_mode;
+        let shared_context = ctx.shared_context();
+
+        assert!(self.block_flow.base.children.len() == 1);
+        let mut page = self.block_flow.base.children.pop_front().unwrap();
+        self.block_flow.assign_block_size(ctx);
+
+        // Every page starts where the first one does, before it is moved to its place.
+        let start = mut_base(flow_ref::deref_mut(&mut page)).position.start;
+        let mut block_offset = Au(0);
+        let mut index = 0;
+        loop {
+            let page_box = shared_context.stylist.page_box(index, shared_context.viewport_size);
+            let margins = LogicalMargin::from_physical(writing_mode, page_box.margins);
+            let page_area_size = LogicalSize::from_physical(writing_mode,
+                                                            page_box.page_area_size());
+            let remaining = flow_ref::deref_mut(&mut page).fragment(ctx, Some(FragmentationContext {
+                available_block_size: page_area_size.block,
+                this_fragment_is_empty: true,
+            }));
+            {
+                let position = &mut mut_base(flow_ref::deref_mut(&mut page)).position;
+                position.start.i = start.i + margins.inline_start;
+                position.start.b = start.b + block_offset + margins.block_start;
+            }
+            block_offset = block_offset +
+                LogicalSize::from_physical(writing_mode, page_box.size).block;
+            self.block_flow.base.children.push_back(page);
+            page = match remaining {
+                Some(remaining) => remaining,
+                None => break
+            };
+            index += 1;
+        }
+
+        // The document is as long as its pages.
+        self.block_flow.fragment.border_box.size.block = block_offset;
+        self.block_flow.base.position.size.block = block_offset;
+    }
 }
 
 impl MulticolColumnFlow {
@@ -71,6 +120,14 @@ impl Flow for MulticolFlow {
         self
     }
 
+    fn mark_as_root(&mut self) {
+        self.block_flow.mark_as_root()
+    }
+
+    fn is_root(&self) -> bool {
+        self.block_flow.is_root()
+    }
+
     fn bubble_inline_sizes(&mut self) {
         // FIXME(SimonSapin) http://dev.w3.org/csswg/css-sizing/#multicol-intrinsic
         self.block_flow.bubble_inline_sizes();
@@ -94,7 +151,14 @@ impl Flow for MulticolFlow {
         let content_inline_size =
             self.block_flow.fragment.border_box.size.inline - padding_and_borders;
         let column_width;
-        {
+        if self.paged {
+            // All pages are as wide as the page area of the first one.
+            let shared_context = layout_context.shared_context();
+            let page_box = shared_context.stylist.page_box(0, shared_context.viewport_size);
+            column_width = LogicalSize::from_physical(self.block_flow.base.writing_mode,
+                                                      page_box.page_area_size()).inline;
+            self.column_pitch = Au(0);
+        } else {
             let column_style = self.block_flow.fragment.style.get_column();
 
             // `None` is 'normal': "UA-specified length. A value of 1em is suggested."
@@ -124,6 +188,9 @@ impl Flow for MulticolFlow {
 
     fn assign_block_size<'a>(&mut self, ctx: &'a LayoutContext<'a>) {
         debug!("assign_block_size: assigning block_size for multicol");
+        if self.paged {
+            return self.assign_block_size_for_pages(ctx)
+        }
 
         let fragmentation_context = Some(FragmentationContext {
             this_fragment_is_empty: true,
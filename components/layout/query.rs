@@ -6,35 +6,53 @@
 
 use app_units::Au;
 use construct::ConstructionResult;
-use euclid::point::Point2D;
+use cssparser::ToCss;
+use display_list_builder::{perspective_matrix, transform_matrix};
+use euclid::Matrix4D;
+use euclid::point::{Point2D, Point3D};
 use euclid::rect::Rect;
 use euclid::size::Size2D;
-use flow;
+use flow::{self, Flow, FlowClass, IS_ABSOLUTELY_POSITIONED, ImmutableFlowUtils};
 use flow_ref::FlowRef;
 use fragment::{Fragment, FragmentBorderBoxIterator, SpecificFragmentInfo};
 use gfx::display_list::OpaqueNode;
+use gfx::font::FontHandleMethods;
+use gfx::platform::font::FontHandle;
+use gfx::platform::font_context::FontContextHandle;
+use gfx::platform::font_template::FontTemplateData;
 use gfx_traits::LayerId;
+use incremental::RestyleDamage;
 use layout_thread::LayoutThreadData;
 use opaque_node::OpaqueNodeMethods;
 use script::layout_interface::{ContentBoxResponse, NodeOverflowResponse, ContentBoxesResponse, NodeGeometryResponse};
 use script::layout_interface::{HitTestResponse, LayoutRPC, OffsetParentResponse, NodeLayerIdResponse};
 use script::layout_interface::{ResolvedStyleResponse, MarginStyleResponse};
+use script::layout_interface::{AnimationStateResponse, NodeAnimation, NodeAnimationSource};
+use script::layout_interface::{BoxModel, BoxModelResponse, BoxQuad, MatchedRulesResponse};
+use script::layout_interface::{PhaseMarkersResponse, UsedFontFacesResponse};
 use script_traits::LayoutMsg as ConstellationMsg;
-use script_traits::UntrustedNodeAddress;
+use script_traits::{UntrustedNodeAddress, UsedFontFace};
 use sequential;
-use std::cmp::{min, max};
+use std::cmp::{Ordering, min, max};
+use std::collections::HashMap;
 use std::ops::Deref;
 use std::sync::{Arc, Mutex};
 use string_cache::Atom;
+use style::animation::{Animation, AnimationTarget, KeyframesAnimation, update_style_for_animation};
 use style::computed_values;
+use style::dom::{TElement, TNode};
 use style::logical_geometry::{WritingMode, BlockFlowDirection, InlineBaseDirection};
-use style::properties::ComputedValues;
-use style::properties::longhands::{display, position};
+use style::properties::{ComputedValues, ServoComputedValues};
+use style::properties::longhands::{display, position, z_index};
 use style::properties::style_structs;
 use style::selector_impl::PseudoElement;
+use style::selector_matching::MatchedRule;
+use style::servo::{SharedCascadeSnapshot, Stylist};
 use style::values::AuExtensionMethods;
+use style::values::computed::LengthOrNone;
 use style_traits::cursor::Cursor;
-use wrapper::{LayoutNode, ThreadSafeLayoutNode};
+use url::Url;
+use wrapper::{LayoutNode, ServoLayoutNode, ThreadSafeLayoutNode};
 
 pub struct LayoutRPCImpl(pub Arc<Mutex<LayoutThreadData>>);
 
@@ -73,11 +91,17 @@ impl LayoutRPC for LayoutRPCImpl {
         let &(ref result, update_cursor) = &rw_data.hit_test_response;
         if update_cursor {
             // Compute the new cursor.
-            let cursor = match *result {
-                None => Cursor::DefaultCursor,
-                Some(dim) => dim.pointing.unwrap(),
+            let (cursor, custom_cursor) = match *result {
+                None => (Cursor::DefaultCursor, None),
+                Some(dim) => {
+                    let custom_cursor = rw_data.display_list.as_ref().and_then(|display_list| {
+                        display_list.custom_cursors.get(&dim.node).cloned()
+                    });
+                    (dim.pointing.unwrap(), custom_cursor)
+                }
             };
-            rw_data.constellation_chan.send(ConstellationMsg::SetCursor(cursor)).unwrap();
+            let msg = ConstellationMsg::SetCursor(cursor, custom_cursor);
+            rw_data.constellation_chan.send(msg).unwrap();
         }
         HitTestResponse {
             node_address: result.map(|dim| dim.node.to_untrusted_node_address()),
@@ -145,6 +169,49 @@ impl LayoutRPC for LayoutRPCImpl {
         let rw_data = rw_data.lock().unwrap();
         rw_data.margin_style_response.clone()
     }
+
+    fn animation_state(&self) -> AnimationStateResponse {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        AnimationStateResponse(rw_data.animation_state_response.clone())
+    }
+
+    fn matched_rules(&self) -> MatchedRulesResponse {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        let (ref rules, exists) = rw_data.matched_rules_response;
+        MatchedRulesResponse(rules.clone(), exists)
+    }
+
+    fn box_model(&self) -> BoxModelResponse {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        BoxModelResponse(rw_data.box_model_response.clone())
+    }
+
+    fn used_font_faces(&self) -> UsedFontFacesResponse {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        UsedFontFacesResponse(rw_data.used_font_faces_response.clone())
+    }
+
+    fn phase_markers(&self) -> PhaseMarkersResponse {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        PhaseMarkersResponse(rw_data.phase_markers.clone().unwrap_or(vec![]))
+    }
+
+    fn cascade_snapshot(&self) -> SharedCascadeSnapshot {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        rw_data.cascade_snapshot.clone()
+    }
+
+    fn dom_version(&self) -> u64 {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        rw_data.dom_version
+    }
 }
 
 struct UnioningFragmentBorderBoxIterator {
@@ -566,11 +633,23 @@ pub fn process_node_scroll_area_request< N: LayoutNode>(requested_node: N, layou
 /// https://drafts.csswg.org/cssom/#resolved-value
 pub fn process_resolved_style_request<N: LayoutNode>(
             requested_node: N, pseudo: &Option<PseudoElement>,
-            property: &Atom, layout_root: &mut FlowRef) -> Option<String> {
+            property: &Atom, layout_root: &mut FlowRef,
+            animations: &HashMap<AnimationTarget, Vec<Animation>>,
+            animation_time: f64,
+            stylist: &Stylist) -> Option<String> {
+    if !pseudo_element_exists(requested_node, pseudo.as_ref()) {
+        return None
+    }
     let layout_node = requested_node.to_threadsafe();
     let layout_node = match *pseudo {
         Some(PseudoElement::Before) => layout_node.get_before_pseudo(),
         Some(PseudoElement::After) => layout_node.get_after_pseudo(),
+        Some(PseudoElement::Marker) => layout_node.get_marker_pseudo(),
+        // The first line has no node of its own, and no box that properties resolve against.
+        Some(PseudoElement::FirstLine) => {
+            return first_line_style(requested_node, stylist).computed_value_to_string(&*property)
+                                                             .ok()
+        }
         Some(PseudoElement::DetailsSummary) |
         Some(PseudoElement::DetailsContent) |
         Some(PseudoElement::Selection) => None,
@@ -587,7 +666,20 @@ pub fn process_resolved_style_request<N: LayoutNode>(
         Some(layout_node) => layout_node
     };
 
-    let style = &*layout_node.resolved_style();
+    let mut style = layout_node.resolved_style().clone();
+    // Running animations are not reflected in the style of the node, and those handed off to the
+    // compositor do not even update the style of its fragments, so sample them now. Those of
+    // `::before` and `::after` run separately from those of the element.
+    let target = AnimationTarget::new(requested_node.opaque(), pseudo.clone());
+    if let Some(animations) = animations.get(&target) {
+        for animation in animations {
+            update_style_for_animation::<_, RestyleDamage>(animation,
+                                                           &mut style,
+                                                           animation_time,
+                                                           None);
+        }
+    }
+    let style = &*style;
 
     let positioned = match style.get_box().position {
         position::computed_value::T::relative |
@@ -727,3 +819,347 @@ pub fn process_margin_style_query<N: LayoutNode>(requested_node: N)
         left: margin.margin_left,
     }
 }
+
+/// Describes the animations running on a node as of `now`: its transitions, then the animations
+/// that script created on it, each in the order that they started. Those of its pseudo-elements
+/// are left out.
+/// https://w3c.github.io/web-animations/#dom-animatable-getanimations
+pub fn process_animation_state_query<N: LayoutNode>(
+            requested_node: N,
+            running_animations: &HashMap<AnimationTarget, Vec<Animation>>,
+            keyframes_animations: &HashMap<OpaqueNode, Vec<KeyframesAnimation>>,
+            now: f64) -> Vec<NodeAnimation> {
+    let node = requested_node.opaque();
+    let mut transitions = vec![];
+    if let Some(animations) = running_animations.get(&AnimationTarget::new(node, None)) {
+        for animation in animations {
+            let property = animation.property_animation.property().to_css_string();
+            transitions.push(NodeAnimation {
+                source: NodeAnimationSource::Transition(property),
+                start_time: animation.start_time,
+                duration: animation.duration(),
+                paused: animation.paused_at.is_some(),
+                progress: animation.progress(now),
+            })
+        }
+    }
+    let mut script_animations = vec![];
+    if let Some(animations) = keyframes_animations.get(&node) {
+        for animation in animations {
+            let duration = animation.end_time() - animation.timing.start_time;
+            let elapsed_time = animation.elapsed_time(now);
+            let progress = if duration > 0.0 {
+                (elapsed_time / duration).max(0.0).min(1.0)
+            } else if elapsed_time < 0.0 {
+                0.0
+            } else {
+                1.0
+            };
+            script_animations.push(NodeAnimation {
+                source: NodeAnimationSource::Script(animation.id),
+                start_time: animation.timing.start_time,
+                duration: duration,
+                paused: animation.paused_at.is_some(),
+                progress: progress,
+            })
+        }
+    }
+    fn by_start_time(a: &NodeAnimation, b: &NodeAnimation) -> Ordering {
+        a.start_time.partial_cmp(&b.start_time).unwrap_or(Ordering::Equal)
+    }
+    transitions.sort_by(by_start_time);
+    script_animations.sort_by(by_start_time);
+    transitions.extend(script_animations);
+    transitions
+}
+
+/// Returns the style rules that match the given node, or the given pseudo-element of it, in the
+/// order in which they are cascaded, with where they come from, and whether that pseudo-element
+/// exists. This is what the rules panel of developer tools shows.
+pub fn process_matched_rules_query(requested_node: ServoLayoutNode,
+                                   pseudo: &Option<PseudoElement>,
+                                   stylist: &Stylist)
+                                   -> (Vec<MatchedRule>, bool) {
+    match requested_node.as_element() {
+        Some(element) => {
+            let rules = stylist.matched_rules(&element,
+                                              element.style_attribute().as_ref(),
+                                              pseudo.as_ref());
+            (rules, pseudo_element_exists(requested_node, pseudo.as_ref()))
+        }
+        None => (vec![], false),
+    }
+}
+
+/// Whether the given pseudo-element of a node exists, which the node itself always does.
+/// `::before` and `::after` exist when their `content` generates boxes, `::marker` when the node
+/// is a list item with a marker, and `::first-line` when the node is a block container whose
+/// first formatted line is laid out in it.
+pub fn pseudo_element_exists<N: LayoutNode>(node: N, pseudo: Option<&PseudoElement>) -> bool {
+    let layout_node = node.to_threadsafe();
+    match pseudo {
+        None => true,
+        Some(&PseudoElement::Before) => layout_node.get_before_pseudo().is_some(),
+        Some(&PseudoElement::After) => layout_node.get_after_pseudo().is_some(),
+        // Markers are only styled when their list items are laid out.
+        Some(&PseudoElement::Marker) => layout_node.get_marker_pseudo().map_or(false, |marker| {
+            marker.borrow_layout_data().unwrap().style_data.per_pseudo
+                  .contains_key(&PseudoElement::Marker)
+        }),
+        Some(&PseudoElement::FirstLine) => {
+            let data = match layout_node.borrow_layout_data() {
+                Some(data) => data,
+                None => return false,
+            };
+            match data.flow_construction_result {
+                ConstructionResult::Flow(ref flow_ref, _) => has_first_line(&**flow_ref),
+                _ => false,
+            }
+        }
+        Some(&PseudoElement::DetailsSummary) |
+        Some(&PseudoElement::DetailsContent) |
+        Some(&PseudoElement::Selection) => false,
+    }
+}
+
+/// Whether the first formatted line of a block container is in it: whether it has lines before
+/// any block in it, or the first block in it has. Floats and absolutely positioned flows are out
+/// of the flow, and other flows, such as tables, have no first line for them to share.
+/// https://drafts.csswg.org/css-pseudo/#first-formatted-line
+fn has_first_line(flow: &Flow) -> bool {
+    match flow.class() {
+        FlowClass::Block |
+        FlowClass::ListItem |
+        FlowClass::TableCell |
+        FlowClass::TableCaption => {}
+        _ => return false,
+    }
+    for kid in flow::child_iter(flow) {
+        let flags = flow::base(kid).flags;
+        if flags.is_float() || flags.contains(IS_ABSOLUTELY_POSITIONED) {
+            continue
+        }
+        return kid.is_inline_flow() || has_first_line(kid)
+    }
+    false
+}
+
+/// The style of the first line of a node, cascaded from the rules that match its `::first-line`
+/// on top of the style of the node, which it otherwise inherits.
+fn first_line_style<N: LayoutNode>(node: N, stylist: &Stylist) -> Arc<ServoComputedValues> {
+    let layout_node = node.to_threadsafe();
+    let style = layout_node.resolved_style();
+    stylist.lazily_compute_pseudo_element_style(&layout_node.as_element(),
+                                                &PseudoElement::FirstLine,
+                                                &*style)
+           .unwrap_or_else(|| ServoComputedValues::style_for_child_text_node(&*style))
+}
+
+/// The transforms that a fragment applies to the fragments of its descendants.
+struct AncestorTransform {
+    level: i32,
+    /// The origin of the border box of the fragment, which its transforms are relative to.
+    origin: Point2D<Au>,
+    perspective: Matrix4D<f32>,
+    transform: Matrix4D<f32>,
+}
+
+struct BoxModelIterator {
+    node_address: OpaqueNode,
+    /// The transforms of the ancestors of the fragment being processed, outermost first.
+    ancestors: Vec<AncestorTransform>,
+    box_model: Option<BoxModel>,
+    /// Inline nodes have no fragments of their own, so their boxes are approximated by the union
+    /// of the border boxes of their contents. This is that union, the transforms that apply to
+    /// it, and the `position` and `z-index` of the node.
+    inline_contents: Option<(Rect<Au>, Vec<(Point2D<Au>, Matrix4D<f32>)>, position::T, z_index::T)>,
+}
+
+impl BoxModelIterator {
+    fn new(node_address: OpaqueNode) -> BoxModelIterator {
+        BoxModelIterator {
+            node_address: node_address,
+            ancestors: vec![],
+            box_model: None,
+            inline_contents: None,
+        }
+    }
+
+    /// Returns the transforms that map points of the fragment being processed to page
+    /// coordinates, in the order in which they apply, starting with its own transform.
+    fn page_transforms(&self, own_transform: Option<(Point2D<Au>, Matrix4D<f32>)>)
+                       -> Vec<(Point2D<Au>, Matrix4D<f32>)> {
+        let mut transforms: Vec<_> = own_transform.into_iter().collect();
+        for ancestor in self.ancestors.iter().rev() {
+            transforms.push((ancestor.origin, ancestor.perspective));
+            transforms.push((ancestor.origin, ancestor.transform));
+        }
+        transforms
+    }
+}
+
+impl FragmentBorderBoxIterator for BoxModelIterator {
+    fn process(&mut self, fragment: &Fragment, level: i32, border_box: &Rect<Au>) {
+        while self.ancestors.last().map_or(false, |ancestor| ancestor.level >= level) {
+            self.ancestors.pop();
+        }
+
+        if fragment.node == self.node_address {
+            if self.box_model.is_none() {
+                let own_transform = transform_matrix(&*fragment.style, &border_box.size);
+                let transforms = self.page_transforms(Some((border_box.origin, own_transform)));
+                self.box_model = Some(BoxModel {
+                    margin: page_quad(&fragment.stacking_relative_margin_box(border_box),
+                                      &transforms),
+                    border: page_quad(border_box, &transforms),
+                    padding: page_quad(&fragment.stacking_relative_padding_box(border_box),
+                                       &transforms),
+                    content: page_quad(&fragment.stacking_relative_content_box(border_box),
+                                       &transforms),
+                    position: fragment.style.get_box().position,
+                    z_index: fragment.style.get_position().z_index,
+                });
+            }
+        } else {
+            let node_address = self.node_address;
+            let inline_node = fragment.inline_context.as_ref().and_then(|context| {
+                context.nodes.iter().find(|node| node.address == node_address)
+            });
+            if let Some(node) = inline_node {
+                if let Some(ref mut contents) = self.inline_contents {
+                    contents.0 = contents.0.union(border_box);
+                }
+                if self.inline_contents.is_none() {
+                    let transforms = self.page_transforms(None);
+                    self.inline_contents = Some((*border_box,
+                                                 transforms,
+                                                 node.style.get_box().position,
+                                                 node.style.get_position().z_index));
+                }
+            }
+        }
+
+        let has_perspective = match fragment.style.get_effects().perspective {
+            LengthOrNone::Length(_) => true,
+            LengthOrNone::None => false,
+        };
+        if fragment.style.get_effects().transform.0.is_some() || has_perspective {
+            self.ancestors.push(AncestorTransform {
+                level: level,
+                origin: border_box.origin,
+                perspective: perspective_matrix(&*fragment.style, &border_box.size),
+                transform: transform_matrix(&*fragment.style, &border_box.size),
+            });
+        }
+    }
+
+    fn should_process(&mut self, _: &Fragment) -> bool {
+        // Every fragment is processed to find the transforms of the ancestors of the node.
+        true
+    }
+}
+
+/// Returns the corners of the given rect in page coordinates once the given transforms, which are
+/// relative to the given origins, apply to it in order.
+fn page_quad(rect: &Rect<Au>, transforms: &[(Point2D<Au>, Matrix4D<f32>)]) -> BoxQuad {
+    let corner = |x: Au, y: Au| {
+        let point = Point3D::new(x.to_f32_px(), y.to_f32_px(), 0.0);
+        let point = transforms.iter().fold(point, |point, &(ref origin, ref matrix)| {
+            let (x, y, z) =
+                (point.x - origin.x.to_f32_px(), point.y - origin.y.to_f32_px(), point.z);
+            let w = x * matrix.m14 + y * matrix.m24 + z * matrix.m34 + matrix.m44;
+            let w = if w == 0.0 { 1.0 } else { w };
+            Point3D::new((x * matrix.m11 + y * matrix.m21 + z * matrix.m31 + matrix.m41) / w +
+                             origin.x.to_f32_px(),
+                         (x * matrix.m12 + y * matrix.m22 + z * matrix.m32 + matrix.m42) / w +
+                             origin.y.to_f32_px(),
+                         (x * matrix.m13 + y * matrix.m23 + z * matrix.m33 + matrix.m43) / w)
+        });
+        Point2D::new(point.x, point.y)
+    };
+    [corner(rect.origin.x, rect.origin.y),
+     corner(rect.max_x(), rect.origin.y),
+     corner(rect.max_x(), rect.max_y()),
+     corner(rect.origin.x, rect.max_y())]
+}
+
+pub fn process_box_model_query<N: LayoutNode>(requested_node: N, layout_root: &mut FlowRef)
+                                              -> Option<BoxModel> {
+    let mut iterator = BoxModelIterator::new(requested_node.opaque());
+    sequential::iterate_through_flow_tree_fragment_border_boxes(layout_root, &mut iterator);
+    let BoxModelIterator { box_model, inline_contents, .. } = iterator;
+    box_model.or_else(|| {
+        inline_contents.map(|(union, transforms, position, z_index)| {
+            let quad = page_quad(&union, &transforms);
+            BoxModel {
+                margin: quad,
+                border: quad,
+                padding: quad,
+                content: quad,
+                position: position,
+                z_index: z_index,
+            }
+        })
+    })
+}
+
+/// Counts the glyphs of the text of a flow tree by the font that they are shaped in, which the
+/// template of the font identifies.
+struct UsedFontFacesIterator {
+    /// The templates of the fonts in the order that they are first used, with their glyph counts.
+    fonts: Vec<(Arc<FontTemplateData>, usize)>,
+}
+
+impl FragmentBorderBoxIterator for UsedFontFacesIterator {
+    fn process(&mut self, fragment: &Fragment, _: i32, _: &Rect<Au>) {
+        let info = match fragment.specific {
+            SpecificFragmentInfo::ScannedText(ref info) => info,
+            _ => return,
+        };
+        let slices = info.run.natural_word_slices_in_range(&info.range);
+        let glyph_count = slices.fold(0, |count, slice| {
+            count + slice.glyphs.iter_glyphs_for_byte_range(&slice.range).count()
+        });
+        let template = &info.run.font_template;
+        match self.fonts.iter_mut().find(|font| font.0.identifier == template.identifier) {
+            Some(font) => font.1 += glyph_count,
+            None => self.fonts.push((template.clone(), glyph_count)),
+        }
+    }
+
+    fn should_process(&mut self, fragment: &Fragment) -> bool {
+        match fragment.specific {
+            SpecificFragmentInfo::ScannedText(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Returns the font faces that the text of a flow tree is laid out in.
+pub fn process_used_font_faces_query(layout_root: &mut FlowRef) -> Vec<UsedFontFace> {
+    let mut iterator = UsedFontFacesIterator { fonts: vec![] };
+    sequential::iterate_through_flow_tree_fragment_border_boxes(layout_root, &mut iterator);
+
+    // Text runs only keep the templates of their fonts, which the names are read from again.
+    let font_context = FontContextHandle::new();
+    iterator.fonts.into_iter().map(|(template, glyph_count)| {
+        // Web fonts are identified by the URL that they were downloaded from, and the fonts of
+        // the system by their path or their name.
+        let url = match Url::parse(&template.identifier) {
+            Ok(ref url) if ["http", "https", "file", "data", "blob"].contains(&url.scheme()) => {
+                Some(String::from(&*template.identifier))
+            }
+            _ => None,
+        };
+        let (family, name) = match FontHandle::new_from_template(&font_context, template, None) {
+            Ok(handle) => (handle.family_name(), handle.face_name()),
+            Err(()) => (String::new(), String::new()),
+        };
+        UsedFontFace {
+            family: family,
+            name: name,
+            url: url,
+            glyph_count: glyph_count,
+        }
+    }).collect()
+}
@@ -0,0 +1,286 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Layout for elements with a CSS `display` property of `ruby`.
+//!
+//! https://drafts.csswg.org/css-ruby-1/
+//!
+//! A ruby container is laid out as an atomic inline. Its children are paired up into segments of
+//! a base (the anonymous inline flows and `ruby-base` content) and the annotation that follows it
+//! (a `ruby-text` flow). Segments are placed next to each other in the inline direction, with each
+//! annotation centered over its base. The annotations make the container taller above its
+//! baseline, which is that of its bases, so the line box grows to make room for them.
+
+#![deny(unsafe_code)]
+
+use app_units::Au;
+use block::BlockFlow;
+use context::LayoutContext;
+use display_list_builder::DisplayListBuildState;
+use euclid::Point2D;
+use flow::{self, Flow, FlowClass, OpaqueFlow};
+use fragment::{Fragment, FragmentBorderBoxIterator, Overflow};
+use gfx::display_list::{StackingContext, StackingContextId};
+use layout_debug;
+use model::IntrinsicISizes;
+use std::cmp::max;
+use std::fmt;
+use std::sync::Arc;
+use style::computed_values::display;
+use style::logical_geometry::LogicalSize;
+use style::properties::{ComputedValues, ServoComputedValues};
+use style::values::computed::LengthOrPercentageOrAuto;
+use util::print_tree::PrintTree;
+
+/// A base and the annotation over it, as indices into the children of the ruby flow.
+#[derive(Clone, Copy, Debug)]
+struct RubySegment {
+    base: Option<usize>,
+    annotation: Option<usize>,
+}
+
+pub struct RubyFlow {
+    pub block_flow: BlockFlow,
+
+    /// The offset of the baseline of the bases from the block-start border edge, once block sizes
+    /// have been assigned.
+    baseline_offset: Option<Au>,
+}
+
+fn is_ruby_annotation(flow: &Flow) -> bool {
+    flow.class() == FlowClass::Block &&
+        flow.as_block().fragment.style.get_box().display == display::T::ruby_text
+}
+
+impl RubyFlow {
+    pub fn from_fragment(fragment: Fragment) -> RubyFlow {
+        RubyFlow {
+            block_flow: BlockFlow::from_fragment(fragment, None),
+            baseline_offset: None,
+        }
+    }
+
+    /// Returns the offset of the baseline of this ruby container from its block-start border
+    /// edge, if it has any base text.
+    pub fn baseline_offset(&self) -> Option<Au> {
+        self.baseline_offset
+    }
+
+    /// Pairs up each annotation with the base before it. Annotations without a base get a
+    /// segment of their own.
+    fn segments(&self) -> Vec<RubySegment> {
+        let mut segments: Vec<RubySegment> = vec![];
+        for (index, kid) in self.block_flow.base.children.iter().enumerate() {
+            if !is_ruby_annotation(kid) {
+                segments.push(RubySegment {
+                    base: Some(index),
+                    annotation: None,
+                });
+                continue
+            }
+            if let Some(segment) = segments.last_mut() {
+                if segment.annotation.is_none() {
+                    segment.annotation = Some(index);
+                    continue
+                }
+            }
+            segments.push(RubySegment {
+                base: None,
+                annotation: Some(index),
+            })
+        }
+        segments
+    }
+
+    fn kid_intrinsic_inline_sizes(&self, index: Option<usize>) -> IntrinsicISizes {
+        match index.and_then(|index| self.block_flow.base.children.iter().nth(index)) {
+            Some(kid) => flow::base(kid).intrinsic_inline_sizes,
+            None => IntrinsicISizes::new(),
+        }
+    }
+}
+
+impl Flow for RubyFlow {
+    fn class(&self) -> FlowClass {
+        FlowClass::Ruby
+    }
+
+    fn as_block(&self) -> &BlockFlow {
+        &self.block_flow
+    }
+
+    fn as_mut_block(&mut self) -> &mut BlockFlow {
+        &mut self.block_flow
+    }
+
+    fn as_ruby(&self) -> &RubyFlow {
+        self
+    }
+
+    fn bubble_inline_sizes(&mut self) {
+        let _scope = layout_debug_scope!("ruby::bubble_inline_sizes {:x}",
+                                         self.block_flow.base.debug_id());
+
+        self.block_flow.bubble_inline_sizes();
+        if let LengthOrPercentageOrAuto::Length(_) =
+                self.block_flow.fragment.style().get_position().width {
+            return
+        }
+
+        // Segments sit side by side, so their sizes add up rather than being the maximum of the
+        // kids' sizes, as for a block.
+        let mut content_sizes = IntrinsicISizes::new();
+        for segment in self.segments() {
+            let base_sizes = self.kid_intrinsic_inline_sizes(segment.base);
+            let annotation_sizes = self.kid_intrinsic_inline_sizes(segment.annotation);
+            content_sizes.minimum_inline_size = content_sizes.minimum_inline_size +
+                max(base_sizes.minimum_inline_size, annotation_sizes.minimum_inline_size);
+            content_sizes.preferred_inline_size = content_sizes.preferred_inline_size +
+                max(base_sizes.preferred_inline_size, annotation_sizes.preferred_inline_size);
+        }
+
+        let surrounding_inline_size = self.block_flow.fragment.surrounding_intrinsic_inline_size();
+        self.block_flow.base.intrinsic_inline_sizes = IntrinsicISizes {
+            minimum_inline_size: content_sizes.minimum_inline_size + surrounding_inline_size,
+            preferred_inline_size: content_sizes.preferred_inline_size + surrounding_inline_size,
+        };
+    }
+
+    fn assign_inline_sizes(&mut self, layout_context: &LayoutContext) {
+        let _scope = layout_debug_scope!("ruby::assign_inline_sizes {:x}",
+                                         self.block_flow.base.debug_id());
+        debug!("assign_inline_sizes({}): assigning inline_size for flow", "ruby");
+
+        // Let the block flow size us and hand its content inline size to the kids, then narrow
+        // each kid down to its segment.
+        self.block_flow.assign_inline_sizes(layout_context);
+
+        let segments = self.segments();
+        let mut segment_start = self.block_flow.fragment.border_box.start.i +
+            self.block_flow.fragment.border_padding.inline_start;
+        for segment in segments {
+            let base_sizes = self.kid_intrinsic_inline_sizes(segment.base);
+            let annotation_sizes = self.kid_intrinsic_inline_sizes(segment.annotation);
+            let segment_inline_size = max(base_sizes.preferred_inline_size,
+                                          annotation_sizes.preferred_inline_size);
+
+            for (index, sizes) in vec![(segment.base, base_sizes),
+                                       (segment.annotation, annotation_sizes)] {
+                let index = match index {
+                    Some(index) => index,
+                    None => continue,
+                };
+                let kid = self.block_flow.base.children.iter_mut().nth(index).unwrap();
+                let kid_base = flow::mut_base(kid);
+                let inline_size = sizes.preferred_inline_size;
+                kid_base.block_container_inline_size = inline_size;
+                kid_base.position.start.i = segment_start + (segment_inline_size - inline_size) / 2;
+            }
+            segment_start = segment_start + segment_inline_size;
+        }
+    }
+
+    fn assign_block_size<'a>(&mut self, layout_context: &'a LayoutContext<'a>) {
+        let _scope = layout_debug_scope!("ruby::assign_block_size {:x}",
+                                         self.block_flow.base.debug_id());
+        debug!("assign_block_size: assigning block_size for ruby");
+
+        self.block_flow.assign_block_size(layout_context);
+
+        // The annotations of all segments share a row above the row of the bases.
+        let (mut annotation_block_size, mut base_block_size) = (Au(0), Au(0));
+        for kid in self.block_flow.base.children.iter() {
+            let kid_block_size = flow::base(kid).position.size.block;
+            if is_ruby_annotation(kid) {
+                annotation_block_size = max(annotation_block_size, kid_block_size);
+            } else {
+                base_block_size = max(base_block_size, kid_block_size);
+            }
+        }
+
+        let content_block_start = self.block_flow.fragment.border_padding.block_start;
+        let base_block_start = content_block_start + annotation_block_size;
+        let mut baseline_offset = None;
+        for kid in self.block_flow.base.children.iter_mut() {
+            if is_ruby_annotation(kid) {
+                // Annotations sit right on top of their bases.
+                let kid_base = flow::mut_base(kid);
+                kid_base.position.start.b = base_block_start - kid_base.position.size.block;
+                continue
+            }
+
+            flow::mut_base(kid).position.start.b = base_block_start;
+            if baseline_offset.is_none() && kid.is_inline_flow() {
+                baseline_offset = kid.as_inline().baseline_offset_of_last_line().map(|offset| {
+                    base_block_start + offset
+                });
+            }
+        }
+        self.baseline_offset = baseline_offset;
+
+        let block_size = annotation_block_size + base_block_size +
+            self.block_flow.fragment.border_padding.block_start_end();
+        self.block_flow.fragment.border_box.size.block = block_size;
+        self.block_flow.base.position.size.block = block_size;
+    }
+
+    fn compute_absolute_position(&mut self, layout_context: &LayoutContext) {
+        self.block_flow.compute_absolute_position(layout_context)
+    }
+
+    fn update_late_computed_inline_position_if_necessary(&mut self, inline_position: Au) {
+        self.block_flow.update_late_computed_inline_position_if_necessary(inline_position)
+    }
+
+    fn update_late_computed_block_position_if_necessary(&mut self, block_position: Au) {
+        self.block_flow.update_late_computed_block_position_if_necessary(block_position)
+    }
+
+    fn build_display_list(&mut self, state: &mut DisplayListBuildState) {
+        debug!("build_display_list_ruby");
+        self.block_flow.build_display_list(state);
+    }
+
+    fn collect_stacking_contexts(&mut self,
+                                 parent_id: StackingContextId,
+                                 contexts: &mut Vec<Box<StackingContext>>)
+                                 -> StackingContextId {
+        self.block_flow.collect_stacking_contexts(parent_id, contexts)
+    }
+
+    fn repair_style(&mut self, new_style: &Arc<ServoComputedValues>) {
+        self.block_flow.repair_style(new_style)
+    }
+
+    fn compute_overflow(&self) -> Overflow {
+        self.block_flow.compute_overflow()
+    }
+
+    fn generated_containing_block_size(&self, flow: OpaqueFlow) -> LogicalSize<Au> {
+        self.block_flow.generated_containing_block_size(flow)
+    }
+
+    fn iterate_through_fragment_border_boxes(&self,
+                                             iterator: &mut FragmentBorderBoxIterator,
+                                             level: i32,
+                                             stacking_context_position: &Point2D<Au>) {
+        self.block_flow.iterate_through_fragment_border_boxes(iterator,
+                                                              level,
+                                                              stacking_context_position);
+    }
+
+    fn mutate_fragments(&mut self, mutator: &mut FnMut(&mut Fragment)) {
+        self.block_flow.mutate_fragments(mutator);
+    }
+
+    fn print_extra_flow_children(&self, print_tree: &mut PrintTree) {
+        self.block_flow.print_extra_flow_children(print_tree);
+    }
+}
+
+impl fmt::Debug for RubyFlow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RubyFlow: {:?}", self.block_flow)
+    }
+}
@@ -6,7 +6,8 @@
 
 use app_units::Au;
 use context::{LayoutContext, SharedLayoutContext};
-use display_list_builder::DisplayListBuildState;
+use display_list_builder::{attach_to_stacking_contexts, collect_stacking_context_positions};
+use display_list_builder::{DisplayListBuildState, StackingContextPosition};
 use euclid::point::Point2D;
 use floats::SpeculatedFloatPlacement;
 use flow::IS_ABSOLUTELY_POSITIONED;
@@ -14,30 +15,43 @@ use flow::{PostorderFlowTraversal, PreorderFlowTraversal};
 use flow::{self, Flow, ImmutableFlowUtils, InorderFlowTraversal, MutableFlowUtils};
 use flow_ref::{self, FlowRef};
 use fragment::FragmentBorderBoxIterator;
-use generated_content::ResolveGeneratedContent;
-use gfx::display_list::{DisplayItem, StackingContext};
+use generated_content::{ResolveGeneratedContent, regenerated_content_damage};
+use gfx::display_list::{DisplayItem, OpaqueNode, StackingContext};
 use incremental::{REFLOW, STORE_OVERFLOW};
+use msg::constellation_msg::CustomCursor;
+use std::collections::HashMap;
 use traversal::{AssignBSizes, AssignISizes, BubbleISizes, BuildDisplayList, ComputeAbsolutePositions};
 use util::opts;
 
 pub use style::sequential::traverse_dom;
 
 pub fn resolve_generated_content(root: &mut FlowRef, shared_layout_context: &SharedLayoutContext) {
-    fn doit(flow: &mut Flow, level: u32, traversal: &mut ResolveGeneratedContent) {
+    /// Returns whether generated content was rendered anew in the subtree of `flow`.
+    fn doit(flow: &mut Flow, level: u32, traversal: &mut ResolveGeneratedContent) -> bool {
         if !traversal.should_process(flow) {
-            return
+            return false
         }
 
         traversal.process(flow, level);
+        let mut regenerated_content = traversal.take_regenerated_content();
 
+        let mut kids_regenerated_content = false;
         for kid in flow::mut_base(flow).children.iter_mut() {
-            doit(kid, level + 1, traversal)
+            kids_regenerated_content = doit(kid, level + 1, traversal) || kids_regenerated_content
         }
+
+        // The damage of the ancestors has been computed already, so it is added to here for the
+        // flows that change size with the content.
+        if kids_regenerated_content {
+            flow::mut_base(flow).restyle_damage.insert(regenerated_content_damage());
+            regenerated_content = true
+        }
+        regenerated_content
     }
 
     let layout_context = LayoutContext::new(shared_layout_context);
     let mut traversal = ResolveGeneratedContent::new(&layout_context);
-    doit(flow_ref::deref_mut(root), 0, &mut traversal)
+    doit(flow_ref::deref_mut(root), 0, &mut traversal);
 }
 
 pub fn traverse_flow_tree_preorder(root: &mut FlowRef,
@@ -79,7 +93,7 @@ pub fn traverse_flow_tree_preorder(root: &mut FlowRef,
 pub fn build_display_list_for_subtree(root: &mut FlowRef,
                                       root_stacking_context: &mut StackingContext,
                                       shared_layout_context: &SharedLayoutContext)
-                                      -> Vec<DisplayItem> {
+                                      -> (Vec<DisplayItem>, HashMap<OpaqueNode, CustomCursor>) {
     let flow_root = flow_ref::deref_mut(root);
     let layout_context = LayoutContext::new(shared_layout_context);
     flow_root.traverse_preorder(&ComputeAbsolutePositions { layout_context: &layout_context });
@@ -89,8 +103,17 @@ pub fn build_display_list_for_subtree(root: &mut FlowRef,
         state: DisplayListBuildState::new(&layout_context,
                                           flow::base(&**root).stacking_context_id),
     };
+    let root_position = StackingContextPosition {
+        origin: Point2D::zero(),
+        transformed: false,
+    };
+    collect_stacking_context_positions(root_stacking_context,
+                                       root_position,
+                                       flow::base(&**root).stacking_context_id,
+                                       &mut build_display_list.state.stacking_context_positions);
     build_display_list.traverse(&mut *flow_root);
-    build_display_list.state.items
+    attach_to_stacking_contexts(root_stacking_context, &mut build_display_list.state);
+    (build_display_list.state.items, build_display_list.state.custom_cursors)
 }
 
 pub fn iterate_through_flow_tree_fragment_border_boxes(root: &mut FlowRef,
@@ -137,6 +160,11 @@ pub fn store_overflow(layout_context: &LayoutContext, flow: &mut Flow) {
 /// contexts. The speculation typically succeeds, but if it doesn't we have to lay it out again.
 pub fn guess_float_placement(flow: &mut Flow) {
     if !flow::base(flow).restyle_damage.intersects(REFLOW) {
+        // Reflow roots below this flow may still need to be laid out. They are independent
+        // formatting contexts, so the floats around them do not matter.
+        for kid in flow::mut_base(flow).child_iter_mut() {
+            guess_float_placement(kid);
+        }
         return
     }
 
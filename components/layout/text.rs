@@ -9,8 +9,9 @@
 use app_units::Au;
 use fragment::{Fragment, REQUIRES_LINE_BREAK_AFTERWARD_IF_WRAPPING_ON_NEWLINES, ScannedTextFlags};
 use fragment::{ScannedTextFragmentInfo, SELECTED, SpecificFragmentInfo, UnscannedTextFragmentInfo};
-use gfx::font::{DISABLE_KERNING_SHAPING_FLAG, FontMetrics, IGNORE_LIGATURES_SHAPING_FLAG};
-use gfx::font::{RTL_FLAG, RunMetrics, ShapingFlags, ShapingOptions};
+use gfx::font::ShapingOptions;
+use gfx::font::{DISABLE_KERNING_SHAPING_FLAG, Font, FontFeature, FontMetrics};
+use gfx::font::{IGNORE_LIGATURES_SHAPING_FLAG, RTL_FLAG, RunMetrics, ShapingFlags};
 use gfx::font_context::FontContext;
 use gfx::text::glyph::ByteIndex;
 use gfx::text::text_run::TextRun;
@@ -18,11 +19,14 @@ use gfx::text::util::{self, CompressionMode};
 use inline::{FIRST_FRAGMENT_OF_ELEMENT, InlineFragments, LAST_FRAGMENT_OF_ELEMENT};
 use range::Range;
 use std::borrow::ToOwned;
+use std::cell::RefCell;
 use std::collections::LinkedList;
 use std::mem;
+use std::rc::Rc;
 use std::sync::Arc;
 use style::computed_values::white_space;
-use style::computed_values::{line_height, text_orientation, text_rendering, text_transform};
+use style::computed_values::{font_feature_settings, font_variant_ligatures, line_height};
+use style::computed_values::{text_orientation, text_rendering, text_transform};
 use style::logical_geometry::{LogicalSize, WritingMode};
 use style::properties::style_structs::ServoFont;
 use style::properties::{ComputedValues, ServoComputedValues};
@@ -144,17 +148,21 @@ impl TextRunScanner {
         // Concatenate all of the transformed strings together, saving the new character indices.
         let mut mappings: Vec<RunMapping> = Vec::new();
         let runs = {
+            let font_style;
             let fontgroup;
             let compression;
             let text_transform;
             let letter_spacing;
             let word_spacing;
+            let tab_size;
             let text_rendering;
+            let font_features;
             {
                 let in_fragment = self.clump.front().unwrap();
-                let font_style = in_fragment.style().get_font_arc();
+                font_style = in_fragment.style().get_font_arc();
                 let inherited_text_style = in_fragment.style().get_inheritedtext();
-                fontgroup = font_context.layout_font_group_for_style(font_style);
+                font_features = font_features_for_style(&*font_style);
+                fontgroup = font_context.layout_font_group_for_style(font_style.clone());
                 compression = match in_fragment.white_space() {
                     white_space::T::normal |
                     white_space::T::nowrap => CompressionMode::CompressWhitespaceNewline,
@@ -165,6 +173,7 @@ impl TextRunScanner {
                 text_transform = inherited_text_style.text_transform;
                 letter_spacing = inherited_text_style.letter_spacing.0;
                 word_spacing = inherited_text_style.word_spacing.0.unwrap_or(Au(0));
+                tab_size = inherited_text_style.tab_size;
                 text_rendering = inherited_text_style.text_rendering;
             }
 
@@ -172,6 +181,10 @@ impl TextRunScanner {
             let (mut run_info_list, mut run_info) = (Vec::new(), RunInfo::new());
             let mut insertion_point = None;
 
+            // System fonts used for characters that no font of the group has a glyph for. Their
+            // font indices follow those of the fonts of the group.
+            let mut fallback_fonts: Vec<Rc<RefCell<Font>>> = vec![];
+
             for (fragment_index, in_fragment) in self.clump.iter().enumerate() {
                 debug!("  flushing {:?}", in_fragment);
                 let mut mapping = RunMapping::new(&run_info_list[..], fragment_index);
@@ -195,11 +208,40 @@ impl TextRunScanner {
 
                 let (mut start_position, mut end_position) = (0, 0);
                 for (byte_index, character) in text.char_indices() {
-                    // Search for the first font in this font group that contains a glyph for this
-                    // character.
-                    let font_index = fontgroup.fonts.iter().position(|font| {
-                        font.borrow().glyph_index(character).is_some()
-                    }).unwrap_or(0);
+                    // Search for the first font in this font group that may be used for this
+                    // character and contains a glyph for it.
+                    let mut font_index = fontgroup.fonts.iter().position(|font| {
+                        let font = font.borrow();
+                        font.covers_character(character) && font.glyph_index(character).is_some()
+                    });
+
+                    // Rather than drawing a missing glyph, look for a system font that has one,
+                    // based on the script of the character.
+                    if font_index.is_none() && !character.is_whitespace() {
+                        let fallback_font =
+                            font_context.fallback_font_for_character(character, &*font_style);
+                        if let Some(fallback_font) = fallback_font {
+                            let position = fallback_fonts.iter().position(|font| {
+                                &**font as *const RefCell<Font> ==
+                                    &*fallback_font as *const RefCell<Font>
+                            });
+                            let index = match position {
+                                Some(index) => index,
+                                None => {
+                                    fallback_fonts.push(fallback_font);
+                                    fallback_fonts.len() - 1
+                                }
+                            };
+                            font_index = Some(fontgroup.fonts.len() + index);
+                        }
+                    }
+                    let font_index = font_index.unwrap_or(0);
+
+                    // Web fonts with a `unicode-range` are only downloaded once some text needs
+                    // them; until then this character is displayed with a fallback font.
+                    for family in fontgroup.deferred_web_fonts_for_character(character) {
+                        font_context.load_deferred_web_fonts(family, character);
+                    }
 
                     // The following code panics one way or another if this condition isn't met.
                     assert!(fontgroup.fonts.len() > 0);
@@ -289,18 +331,25 @@ impl TextRunScanner {
             let options = ShapingOptions {
                 letter_spacing: letter_spacing,
                 word_spacing: word_spacing,
+                tab_size: tab_size,
                 script: Script::Common,
+                features: font_features,
                 flags: flags,
             };
 
             // FIXME(https://github.com/rust-lang/rust/issues/23338)
             run_info_list.into_iter().map(|run_info| {
-                let mut options = options;
+                let mut options = options.clone();
                 options.script = run_info.script;
                 if is_rtl(run_info.bidi_level) {
                     options.flags.insert(RTL_FLAG);
                 }
-                let mut font = fontgroup.fonts.get(run_info.font_index).unwrap().borrow_mut();
+                let font = if run_info.font_index < fontgroup.fonts.len() {
+                    &fontgroup.fonts[run_info.font_index]
+                } else {
+                    &fallback_fonts[run_info.font_index - fontgroup.fonts.len()]
+                };
+                let mut font = font.borrow_mut();
                 ScannedTextRun {
                     run: Arc::new(TextRun::new(&mut *font,
                                                run_info.text,
@@ -436,6 +485,40 @@ fn bounding_box_for_run_metrics(metrics: &RunMetrics, writing_mode: WritingMode)
 ///
 /// `#[inline]` because often the caller only needs a few fields from the font metrics.
 #[inline]
+/// Returns the OpenType features that the `font-variant-ligatures` and `font-feature-settings`
+/// properties of the given font style ask for, in order of increasing precedence.
+fn font_features_for_style(font_style: &ServoFont) -> Vec<FontFeature> {
+    let mut features = vec![];
+
+    let ligatures = match font_style.font_variant_ligatures {
+        font_variant_ligatures::T::Normal => None,
+        font_variant_ligatures::T::None => Some(font_variant_ligatures::Ligatures {
+            common: Some(false),
+            discretionary: Some(false),
+            historical: Some(false),
+            contextual: Some(false),
+        }),
+        font_variant_ligatures::T::Values(ligatures) => Some(ligatures),
+    };
+    if let Some(ligatures) = ligatures {
+        for &(enabled, tags) in &[(ligatures.common, &["liga", "clig"][..]),
+                                  (ligatures.discretionary, &["dlig"][..]),
+                                  (ligatures.historical, &["hlig"][..]),
+                                  (ligatures.contextual, &["calt"][..])] {
+            if let Some(enabled) = enabled {
+                features.extend(tags.iter().map(|tag| FontFeature::new(tag, enabled as u32)));
+            }
+        }
+    }
+
+    // Low-level feature settings override the higher-level properties.
+    if let font_feature_settings::T::Tags(ref tags) = font_style.font_feature_settings {
+        features.extend(tags.iter().map(|tag| FontFeature::new(&tag.tag, tag.value)));
+    }
+
+    features
+}
+
 pub fn font_metrics_for_style(font_context: &mut FontContext, font_style: Arc<ServoFont>)
                               -> FontMetrics {
     let fontgroup = font_context.layout_font_group_for_style(font_style);
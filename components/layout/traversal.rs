@@ -9,17 +9,30 @@ use context::{LayoutContext, SharedLayoutContext};
 use display_list_builder::DisplayListBuildState;
 use flow::{CAN_BE_FRAGMENTED, Flow, ImmutableFlowUtils, PostorderFlowTraversal};
 use flow::{PreorderFlowTraversal, self};
+use fragment::CoordinateSystem;
 use gfx::display_list::OpaqueNode;
 use incremental::{BUBBLE_ISIZES, REFLOW, REFLOW_OUT_OF_FLOW, REPAINT, RestyleDamage};
 use std::mem;
+use std::sync::atomic::{ATOMIC_USIZE_INIT, AtomicUsize, Ordering};
 use style::context::StyleContext;
 use style::matching::MatchMethods;
+use style::properties::ComputedValues;
 use style::traversal::{DomTraversalContext, STYLE_BLOOM};
 use style::traversal::{put_thread_local_bloom_filter, recalc_style_at};
 use util::opts;
 use util::tid::tid;
 use wrapper::{LayoutNode, ServoLayoutNode, ThreadSafeLayoutNode};
 
+/// The number of nodes that flows were constructed or repaired for, counted when the style
+/// context records phase times.
+static FLOW_CONSTRUCTION_COUNT: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// Returns the number of nodes that flows were constructed or repaired for since the last call,
+/// and starts counting again from zero.
+pub fn get_and_reset_flow_construction_count() -> usize {
+    FLOW_CONSTRUCTION_COUNT.swap(0, Ordering::SeqCst)
+}
+
 pub struct RecalcStyleAndConstructFlows<'lc> {
     context: LayoutContext<'lc>,
     root: OpaqueNode,
@@ -93,6 +106,9 @@ fn construct_flows_at<'a, N: LayoutNode>(context: &'a LayoutContext<'a>, root: O
                        tnode.debug_id(),
                        tnode.flow_debug_id());
             }
+            if context.shared_context().record_phase_times {
+                FLOW_CONSTRUCTION_COUNT.fetch_add(1, Ordering::Relaxed);
+            }
         }
 
         // Reset the layout damage in this node. It's been propagated to the
@@ -219,6 +235,10 @@ pub struct BuildDisplayList<'a> {
 impl<'a> BuildDisplayList<'a> {
     #[inline]
     pub fn traverse(&mut self, flow: &mut Flow) {
+        if is_paint_contained_outside_display_port(flow) {
+            return
+        }
+
         if self.should_process() {
             self.state.push_stacking_context_id(flow::base(flow).stacking_context_id);
             flow.build_display_list(&mut self.state);
@@ -236,3 +256,26 @@ impl<'a> BuildDisplayList<'a> {
         true
     }
 }
+
+/// Returns true if nothing in the subtree of `flow` can be painted inside the display port,
+/// because paint containment clips it to the overflow of `flow`, which is outside of it. The
+/// display lists are built again before the display port moves over it.
+fn is_paint_contained_outside_display_port(flow: &Flow) -> bool {
+    // WebRender culls display items itself.
+    if opts::get().use_webrender || !flow.is_block_like() {
+        return false
+    }
+    let block = flow.as_block();
+    let style = block.fragment.style();
+    if !style.get_box().contain.paint || style.get_effects().transform.0.is_some() {
+        return false
+    }
+    let base = &block.base;
+    let border_box = block.fragment.stacking_relative_border_box(
+        &base.stacking_relative_position,
+        &base.early_absolute_position_info.relative_containing_block_size,
+        base.early_absolute_position_info.relative_containing_block_mode,
+        CoordinateSystem::Parent);
+    !base.overflow.paint.translate(&border_box.origin)
+                        .intersects(&base.stacking_relative_position_of_display_port)
+}
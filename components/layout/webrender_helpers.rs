@@ -237,6 +237,9 @@ impl ToFilterOps for filter::T {
                 Filter::Blur(radius) => result.push(webrender_traits::FilterOp::Blur(radius)),
                 Filter::Brightness(amount) => result.push(webrender_traits::FilterOp::Brightness(amount)),
                 Filter::Contrast(amount) => result.push(webrender_traits::FilterOp::Contrast(amount)),
+                // TODO: WebRender has no drop shadow filter yet, so only the painting backend
+                // draws them.
+                Filter::DropShadow(..) => {}
                 Filter::Grayscale(amount) => result.push(webrender_traits::FilterOp::Grayscale(amount)),
                 Filter::HueRotate(angle) => result.push(webrender_traits::FilterOp::HueRotate(angle.0)),
                 Filter::Invert(amount) => result.push(webrender_traits::FilterOp::Invert(amount)),
@@ -315,6 +318,7 @@ impl WebRenderStackingContextConverter for StackingContext {
             ScrollPolicy::FixedPosition => webrender_traits::ScrollPolicy::Fixed,
         };
 
+        // FIXME: WebRender cannot mask stacking contexts yet, so `self.mask` is left out.
         let mut sc =
             webrender_traits::StackingContext::new(scroll_layer_id,
                                                    webrender_scroll_policy,
@@ -421,6 +425,11 @@ impl WebRenderDisplayItemConverter for DisplayItem {
                 }
             }
             DisplayItem::ImageClass(ref item) => {
+                // WebRender has no opacity for images, so the images that `cross-fade()` fades
+                // in are only painted once they make up most of the result.
+                if item.opacity < 0.5 {
+                    return
+                }
                 if let Some(id) = item.webrender_image.key {
                     if item.stretch_size.width > Au(0) &&
                        item.stretch_size.height > Au(0) {
@@ -509,6 +518,14 @@ impl WebRenderDisplayItemConverter for DisplayItem {
                                     item.base.clip.to_clip_region(frame_builder),
                                     pipeline_id);
             }
+            DisplayItem::CaretClass(ref item) => {
+                let color = item.color.to_colorf();
+                if item.visible && color.a > 0.0 {
+                    builder.push_rect(item.base.bounds.to_rectf(),
+                                      item.base.clip.to_clip_region(frame_builder),
+                                      color);
+                }
+            }
         }
     }
 }
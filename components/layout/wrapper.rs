@@ -59,6 +59,7 @@ use selectors::matching::{DeclarationBlock, ElementFlags};
 use selectors::parser::{AttrSelector, NamespaceConstraint};
 use smallvec::VecLike;
 use std::cell::{Ref, RefCell, RefMut};
+use std::cmp::min;
 use std::marker::PhantomData;
 use std::mem::{transmute, transmute_copy};
 use std::sync::Arc;
@@ -625,6 +626,7 @@ pub enum PseudoElementType<T> {
     After(T),
     DetailsSummary(T),
     DetailsContent(T),
+    Marker(T),
 }
 
 impl<T> PseudoElementType<T> {
@@ -649,6 +651,7 @@ impl<T> PseudoElementType<T> {
             PseudoElementType::After(_) => PseudoElementType::After(()),
             PseudoElementType::DetailsSummary(_) => PseudoElementType::DetailsSummary(()),
             PseudoElementType::DetailsContent(_) => PseudoElementType::DetailsContent(()),
+            PseudoElementType::Marker(_) => PseudoElementType::Marker(()),
         }
     }
 
@@ -659,6 +662,7 @@ impl<T> PseudoElementType<T> {
             PseudoElementType::After(_) => PseudoElement::After,
             PseudoElementType::DetailsSummary(_) => PseudoElement::DetailsSummary,
             PseudoElementType::DetailsContent(_) => PseudoElement::DetailsContent,
+            PseudoElementType::Marker(_) => PseudoElement::Marker,
         }
     }
 }
@@ -709,6 +713,10 @@ pub trait ThreadSafeLayoutNode: Clone + Copy + Sized + PartialEq {
     #[inline]
     fn as_element(&self) -> Self::ConcreteThreadSafeLayoutElement;
 
+    /// Returns true if this is the root element of the document, and not one of its
+    /// pseudo-elements.
+    fn is_root_element(&self) -> bool;
+
     #[inline]
     fn get_pseudo_element_type(&self) -> PseudoElementType<Option<display::T>>;
 
@@ -761,6 +769,21 @@ pub trait ThreadSafeLayoutNode: Clone + Copy + Sized + PartialEq {
         }
     }
 
+    /// The `::marker` of a list item. It is not one of the children of the list item, as the list
+    /// item builds it along with its own box.
+    #[inline]
+    fn get_marker_pseudo(&self) -> Option<Self> {
+        let is_list_item = self.is_element() &&
+            self.borrow_layout_data().unwrap().style_data.style.as_ref().map_or(false, |style| {
+                style.get_box().display == display::T::list_item
+            });
+        if is_list_item {
+            Some(self.with_pseudo(PseudoElementType::Marker(None)))
+        } else {
+            None
+        }
+    }
+
     /// Borrows the layout data immutably. Fails on a conflicting borrow.
     ///
     /// TODO(pcwalton): Make this private. It will let us avoid borrow flag checks in some cases.
@@ -807,18 +830,33 @@ pub trait ThreadSafeLayoutNode: Clone + Copy + Sized + PartialEq {
                     }
                     PseudoElementCascadeType::Lazy => {
                         debug_assert!(self.is_element_or_elements_pseudo());
+                        // A style computed ahead of time is used by boxes from now on, so they
+                        // have to be built again when it changes.
+                        if self.borrow_layout_data()
+                               .unwrap().style_data
+                               .speculative_pseudos.contains(&style_pseudo) {
+                            let mut data = self.mutate_layout_data().unwrap();
+                            data.style_data.speculative_pseudos.retain(|p| *p != style_pseudo);
+                        }
                         if !self.borrow_layout_data()
                                 .unwrap().style_data
                                 .per_pseudo.contains_key(&style_pseudo) {
                             let mut data = self.mutate_layout_data().unwrap();
-                            let new_style =
+                            let new_style = {
+                                let parent_style = data.style_data.style.as_ref().unwrap();
+                                // Without rules of their own, they just inherit.
                                 context.stylist
                                        .lazily_compute_pseudo_element_style(
                                            &self.as_element(),
                                            &style_pseudo,
-                                           data.style_data.style.as_ref().unwrap());
+                                           parent_style)
+                                       .unwrap_or_else(|| {
+                                           ServoComputedValues::style_for_child_text_node(
+                                               parent_style)
+                                       })
+                            };
                             data.style_data.per_pseudo
-                                .insert(style_pseudo.clone(), new_style.unwrap());
+                                .insert(style_pseudo.clone(), new_style);
                         }
                     }
                 }
@@ -933,6 +971,7 @@ pub trait ThreadSafeLayoutNode: Clone + Copy + Sized + PartialEq {
             PseudoElementType::After(_) => LayerType::AfterPseudoContent,
             PseudoElementType::DetailsSummary(_) => LayerType::FragmentBody,
             PseudoElementType::DetailsContent(_) => LayerType::FragmentBody,
+            PseudoElementType::Marker(_) => LayerType::FragmentBody,
         };
         LayerId::new_of_type(layer_type, self.opaque().id() as usize)
     }
@@ -962,6 +1001,8 @@ pub trait ThreadSafeLayoutElement: Clone + Copy + Sized +
 
     #[inline]
     fn get_namespace<'a>(&'a self) -> BorrowedNamespace<'a>;
+
+    fn get_state(&self) -> ElementState;
 }
 
 #[derive(Copy, Clone)]
@@ -1071,6 +1112,11 @@ impl<'ln> ThreadSafeLayoutNode for ServoThreadSafeLayoutNode<'ln> {
         }
     }
 
+    fn is_root_element(&self) -> bool {
+        self.pseudo == PseudoElementType::Normal && self.node.is_element() &&
+            self.node.parent_node().map_or(false, |parent| parent.as_document().is_some())
+    }
+
     fn get_pseudo_element_type(&self) -> PseudoElementType<Option<display::T>> {
         self.pseudo
     }
@@ -1128,7 +1174,20 @@ impl<'ln> ThreadSafeLayoutNode for ServoThreadSafeLayoutNode<'ln> {
 
             return match style.as_ref().get_counters().content {
                 content::T::Content(ref value) if !value.is_empty() => {
-                    TextContent::GeneratedContent((*value).clone())
+                    // The values of attributes are the same wherever the content ends up, so
+                    // `attr()` is looked up right away.
+                    let element = self.node.as_element();
+                    TextContent::GeneratedContent(value.iter().map(|item| {
+                        match *item {
+                            ContentItem::Attr(ref name, type_, ref fallback) => {
+                                let value = element.as_ref().and_then(|element| {
+                                    element.get_attr(&ns!(), &Atom::from(&**name))
+                                });
+                                ContentItem::String(type_.generate(value, fallback))
+                            }
+                            ref item => item.clone(),
+                        }
+                    }).collect())
                 }
                 _ => TextContent::GeneratedContent(vec![]),
             };
@@ -1145,6 +1204,13 @@ impl<'ln> ThreadSafeLayoutNode for ServoThreadSafeLayoutNode<'ln> {
             unsafe { area.selection_for_layout() }
         } else if let Some(input) = this.downcast::<HTMLInputElement>() {
             unsafe { input.selection_for_layout() }
+        } else if let Some(text) = this.downcast::<Text>() {
+            // The caret of caret browsing, which is drawn like that of a text control.
+            let offset = unsafe { this.owner_doc_for_layout().caret_offset_for_layout(text) };
+            return offset.map(|offset| {
+                let offset = min(offset, this.text_content().len());
+                Range::new(ByteIndex(offset as isize), ByteIndex(0))
+            });
         } else {
             return None;
         };
@@ -1214,7 +1280,9 @@ impl<ConcreteNode> Iterator for ThreadSafeLayoutNodeChildrenIterator<ConcreteNod
     type Item = ConcreteNode;
     fn next(&mut self) -> Option<ConcreteNode> {
         match self.parent_node.get_pseudo_element_type() {
-            PseudoElementType::Before(_) | PseudoElementType::After(_) => None,
+            PseudoElementType::Before(_) |
+            PseudoElementType::After(_) |
+            PseudoElementType::Marker(_) => None,
 
             PseudoElementType::DetailsSummary(_) => {
                 let mut current_node = self.current_node.clone();
@@ -1271,7 +1339,7 @@ impl<ConcreteNode> Iterator for ThreadSafeLayoutNodeChildrenIterator<ConcreteNod
                         },
                         PseudoElementType::DetailsSummary(_) => self.parent_node.get_details_content_pseudo(),
                         PseudoElementType::DetailsContent(_) => self.parent_node.get_after_pseudo(),
-                        PseudoElementType::After(_) => {
+                        PseudoElementType::After(_) | PseudoElementType::Marker(_) => {
                             None
                         },
                     };
@@ -1308,6 +1376,10 @@ impl<'le> ThreadSafeLayoutElement for ServoThreadSafeLayoutElement<'le> {
     fn get_namespace<'a>(&'a self) -> BorrowedNamespace<'a> {
         BorrowedNamespace(self.element.namespace())
     }
+
+    fn get_state(&self) -> ElementState {
+        self.element.get_state_for_layout()
+    }
 }
 
 pub enum TextContent {
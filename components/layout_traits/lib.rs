@@ -35,6 +35,9 @@ use util::ipc::OptionalIpcSender;
 // Here to remove the compositor -> layout dependency
 pub trait LayoutThreadFactory {
     type Message;
+    /// Spawns a layout thread. Without a channel to a paint thread, the layout thread is headless:
+    /// it styles and lays out documents and answers queries about them, but paints nothing, as
+    /// for tests of layout and tools that measure documents.
     fn create(id: PipelineId,
               url: Url,
               is_iframe: bool,
@@ -43,7 +46,7 @@ pub trait LayoutThreadFactory {
               constellation_chan: IpcSender<ConstellationMsg>,
               panic_chan: IpcSender<PanicMsg>,
               script_chan: IpcSender<ConstellationControlMsg>,
-              layout_to_paint_chan: OptionalIpcSender<LayoutToPaintMsg>,
+              layout_to_paint_chan: Option<OptionalIpcSender<LayoutToPaintMsg>>,
               image_cache_thread: ImageCacheThread,
               font_cache_thread: FontCacheThread,
               time_profiler_chan: time::ProfilerChan,
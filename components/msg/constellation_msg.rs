@@ -5,6 +5,7 @@
 //! The high-level interface from script to constellation. Using this abstract interface helps
 //! reduce coupling between these two components.
 
+use euclid::point::Point2D;
 use euclid::scale_factor::ScaleFactor;
 use euclid::size::{Size2D, TypedSize2D};
 use hyper::header::Headers;
@@ -13,6 +14,7 @@ use ipc_channel::ipc::{IpcSender, IpcSharedMemory};
 use layers::geometry::DevicePixel;
 use std::cell::Cell;
 use std::fmt;
+use std::sync::Arc;
 use url::Url;
 use util::geometry::{PagePx, ViewportPx};
 use webdriver_msg::{LoadStatus, WebDriverScriptCommand};
@@ -194,7 +196,6 @@ pub enum WebDriverCommandMsg {
     ScriptCommand(PipelineId, WebDriverScriptCommand),
     SendKeys(PipelineId, Vec<(Key, KeyModifiers, KeyState)>),
     SetWindowSize(PipelineId, Size2D<u32>, IpcSender<WindowSizeData>),
-    TakeScreenshot(PipelineId, IpcSender<Option<Image>>),
 }
 
 #[derive(Clone, Copy, Deserialize, Eq, PartialEq, Serialize, HeapSizeOf)]
@@ -205,6 +206,24 @@ pub enum PixelFormat {
     RGBA8,      // RGB + alpha, 8 bits per channel
 }
 
+/// The encoding of a snapshot of the rendering of a pipeline.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum SnapshotFormat {
+    /// A PNG file.
+    Png,
+    /// Raw pixels, RGB + alpha, 8 bits per channel.
+    Rgba,
+}
+
+/// A snapshot of the rendering of a pipeline.
+#[derive(Deserialize, Serialize)]
+pub struct Snapshot {
+    pub width: u32,
+    pub height: u32,
+    pub format: SnapshotFormat,
+    pub bytes: IpcSharedMemory,
+}
+
 #[derive(Clone, Deserialize, Serialize, HeapSizeOf)]
 pub struct Image {
     pub width: u32,
@@ -216,6 +235,21 @@ pub struct Image {
     pub id: Option<webrender_traits::ImageKey>,
 }
 
+/// A `url()` cursor image, decoded by the image cache.
+#[derive(Clone, Deserialize, Serialize, HeapSizeOf)]
+pub struct CustomCursor {
+    #[ignore_heap_size_of = "Because it is non-owning"]
+    pub image: Arc<Image>,
+    /// The point in the image that points.
+    pub hotspot: Point2D<u32>,
+}
+
+impl fmt::Debug for CustomCursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CustomCursor({}x{} @ {:?})", self.image.width, self.image.height, self.hotspot)
+    }
+}
+
 /// Similar to net::resource_thread::LoadData
 /// can be passed to LoadUrl to load a page with GET/POST
 /// parameters or headers
@@ -14,13 +14,12 @@ use net_traits::bluetooth_thread::{BluetoothCharacteristicMsg, BluetoothCharacte
 use net_traits::bluetooth_thread::{BluetoothDescriptorMsg, BluetoothDescriptorsMsg};
 use net_traits::bluetooth_thread::{BluetoothDeviceMsg, BluetoothMethodMsg};
 use net_traits::bluetooth_thread::{BluetoothResult, BluetoothServiceMsg, BluetoothServicesMsg};
+use net_traits::{DeviceChoice, DeviceChooser, DeviceKind, choose_device};
 use std::borrow::ToOwned;
 use std::collections::HashMap;
 use std::string::String;
 use std::thread;
 use std::time::Duration;
-#[cfg(target_os = "linux")]
-use tinyfiledialogs;
 use util::thread::spawn_named;
 
 const ADAPTER_ERROR: &'static str = "No adapter found";
@@ -33,12 +32,6 @@ const DESCRIPTOR_ERROR: &'static str = "No descriptor found";
 const VALUE_ERROR: &'static str = "No characteristic or descriptor found with that id";
 // The discovery session needs some time to find any nearby devices
 const DISCOVERY_TIMEOUT_MS: u64 = 1500;
-#[cfg(target_os = "linux")]
-const DIALOG_TITLE: &'static str = "Choose a device";
-#[cfg(target_os = "linux")]
-const DIALOG_COLUMN_ID: &'static str = "Id";
-#[cfg(target_os = "linux")]
-const DIALOG_COLUMN_NAME: &'static str = "Name";
 
 bitflags! {
     flags Flags: u32 {
@@ -72,15 +65,15 @@ macro_rules! get_adapter_or_return_error(
 );
 
 pub trait BluetoothThreadFactory {
-    fn new() -> Self;
+    fn new(chooser: DeviceChooser) -> Self;
 }
 
 impl BluetoothThreadFactory for IpcSender<BluetoothMethodMsg> {
-    fn new() -> IpcSender<BluetoothMethodMsg> {
+    fn new(chooser: DeviceChooser) -> IpcSender<BluetoothMethodMsg> {
         let (sender, receiver) = ipc::channel().unwrap();
         let adapter = BluetoothAdapter::init().ok();
         spawn_named("BluetoothThread".to_owned(), move || {
-            BluetoothManager::new(receiver, adapter).start();
+            BluetoothManager::new(receiver, adapter, chooser).start();
         });
         sender
     }
@@ -130,6 +123,7 @@ fn matches_filters(device: &BluetoothDevice, filters: &BluetoothScanfilterSequen
 pub struct BluetoothManager {
     receiver: IpcReceiver<BluetoothMethodMsg>,
     adapter: Option<BluetoothAdapter>,
+    chooser: DeviceChooser,
     service_to_device: HashMap<String, String>,
     characteristic_to_service: HashMap<String, String>,
     descriptor_to_characteristic: HashMap<String, String>,
@@ -140,10 +134,14 @@ pub struct BluetoothManager {
 }
 
 impl BluetoothManager {
-    pub fn new (receiver: IpcReceiver<BluetoothMethodMsg>, adapter: Option<BluetoothAdapter>) -> BluetoothManager {
+    pub fn new (receiver: IpcReceiver<BluetoothMethodMsg>,
+                adapter: Option<BluetoothAdapter>,
+                chooser: DeviceChooser)
+                -> BluetoothManager {
         BluetoothManager {
             receiver: receiver,
             adapter: adapter,
+            chooser: chooser,
             service_to_device: HashMap::new(),
             characteristic_to_service: HashMap::new(),
             descriptor_to_characteristic: HashMap::new(),
@@ -232,35 +230,17 @@ impl BluetoothManager {
         None
     }
 
-    #[cfg(target_os = "linux")]
+    // The embedder lets the user choose the device.
     fn select_device(&mut self, devices: Vec<BluetoothDevice>) -> Option<String> {
-        let mut dialog_rows: Vec<String> = vec!();
-        for device in devices {
-            dialog_rows.extend_from_slice(&[device.get_address().unwrap_or("".to_string()),
-                                            device.get_name().unwrap_or("".to_string())]);
-        }
-        let dialog_rows: Vec<&str> = dialog_rows.iter()
-                                                .map(|s| s.as_ref())
-                                                .collect();
-        let dialog_rows: &[&str] = dialog_rows.as_slice();
-
-        if let Some(device) = tinyfiledialogs::list_dialog(DIALOG_TITLE,
-                                                           &[DIALOG_COLUMN_ID, DIALOG_COLUMN_NAME],
-                                                           Some(dialog_rows)) {
-            // The device string format will be "Address|Name". We need the first part of it.
-            return device.split("|").next().map(|s| s.to_string());
-        }
-        None
-    }
-
-    #[cfg(not(target_os = "linux"))]
-    fn select_device(&mut self, devices: Vec<BluetoothDevice>) -> Option<String> {
-        for device in devices {
-            if let Ok(address) = device.get_address() {
-                return Some(address);
-            }
-        }
-        None
+        let choices = devices.iter().filter_map(|device| {
+            device.get_address().ok().map(|address| {
+                DeviceChoice {
+                    name: device.get_name().unwrap_or(address.clone()),
+                    id: address,
+                }
+            })
+        }).collect();
+        choose_device(&self.chooser, DeviceKind::Bluetooth, choices)
     }
 
     // Service
@@ -62,8 +62,12 @@ pub mod http_loader;
 pub mod image_cache_thread;
 pub mod mime_classifier;
 pub mod pub_domains;
+pub mod reporting;
 pub mod resource_thread;
 pub mod storage_thread;
+pub mod tracking_protection;
+pub mod url_classifier;
+pub mod usb_thread;
 pub mod websocket_loader;
 
 /// An implementation of the [Fetch specification](https://fetch.spec.whatwg.org/)
@@ -0,0 +1,299 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Delivery of the reports queued by documents to the endpoints named by their `Report-To`
+//! headers and the `report-uri` directives of their content security policies.
+//!
+//! https://w3c.github.io/reporting/
+//!
+//! Reports are batched per endpoint and delivered in the Reporting API format, including to
+//! `report-uri` endpoints. Failed deliveries are retried a few times, and the oldest reports are
+//! dropped when too many are waiting.
+
+use hyper::header::ContentType;
+use hyper::http::RawStatus;
+use hyper::method::Method;
+use ipc_channel::ipc;
+use msg::constellation_msg::{PipelineId, ReferrerPolicy};
+use net_traits::reporting::{Report, ReportType, ReportingHeaders};
+use net_traits::{CoreResourceMsg, CoreResourceThread, LoadConsumer, LoadContext, LoadData};
+use net_traits::{LoadOrigin, ProgressMsg, RequestSource};
+use rustc_serialize::json::Json;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+use std::time::Duration;
+use time::precise_time_ns;
+use url::Url;
+use util::thread::spawn_named;
+
+/// How long reports are collected before a batch is delivered.
+const BATCH_DELAY_MS: u64 = 1000;
+
+/// How many times the delivery of a report is attempted before it is dropped.
+const MAX_DELIVERY_ATTEMPTS: u32 = 3;
+
+/// How many reports may wait for delivery at once.
+const MAX_QUEUED_REPORTS: usize = 100;
+
+/// The group that reports not sent to a group of their own go to.
+const DEFAULT_GROUP: &'static str = "default";
+
+/// Where the reports of a document go.
+struct DocumentEndpoints {
+    url: Url,
+    /// The endpoints of each group named by the `Report-To` headers.
+    groups: HashMap<String, Vec<Url>>,
+    /// The `report-uri` endpoints of the content security policies.
+    report_uris: Vec<Url>,
+    /// The group named by the `report-to` directives of the content security policies.
+    csp_group: Option<String>,
+}
+
+struct QueuedReport {
+    endpoint: Url,
+    document_url: Url,
+    report: Report,
+    queued_at: u64,
+    attempts: u32,
+}
+
+pub struct ReportingManager {
+    user_agent: String,
+    documents: HashMap<PipelineId, DocumentEndpoints>,
+    /// The channel to the delivery thread, once it has been started.
+    delivery_chan: Option<Sender<QueuedReport>>,
+}
+
+impl ReportingManager {
+    pub fn new(user_agent: String) -> ReportingManager {
+        ReportingManager {
+            user_agent: user_agent,
+            documents: HashMap::new(),
+            delivery_chan: None,
+        }
+    }
+
+    pub fn set_endpoints(&mut self, pipeline_id: PipelineId, url: Url, headers: ReportingHeaders) {
+        let mut groups = HashMap::new();
+        for value in &headers.report_to {
+            for (group, endpoints) in parse_report_to(value, &url) {
+                groups.entry(group).or_insert(vec![]).extend(endpoints);
+            }
+        }
+
+        let mut report_uris = vec![];
+        let mut csp_group = None;
+        for policy in headers.content_security_policy.iter().flat_map(|value| value.split(',')) {
+            for directive in policy.split(';') {
+                let mut tokens = directive.split_whitespace();
+                match tokens.next().map(|name| name.to_lowercase()) {
+                    Some(ref name) if name == "report-uri" => {
+                        report_uris.extend(tokens.filter_map(|token| url.join(token).ok()));
+                    }
+                    Some(ref name) if name == "report-to" => {
+                        csp_group = tokens.next().map(|group| group.to_owned());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        self.documents.insert(pipeline_id, DocumentEndpoints {
+            url: url,
+            groups: groups,
+            report_uris: report_uris,
+            csp_group: csp_group,
+        });
+    }
+
+    pub fn clear_endpoints(&mut self, pipeline_id: PipelineId) {
+        self.documents.remove(&pipeline_id);
+    }
+
+    /// Returns the endpoints reports of the given type of the document of a pipeline go to.
+    pub fn endpoints_for(&self, pipeline_id: PipelineId, type_: ReportType) -> Vec<Url> {
+        let document = match self.documents.get(&pipeline_id) {
+            Some(document) => document,
+            None => return vec![],
+        };
+        let group = match type_ {
+            ReportType::CspViolation => document.csp_group.as_ref().map(|group| &**group),
+            ReportType::Deprecation | ReportType::Crash => Some(DEFAULT_GROUP),
+        };
+        let mut endpoints = group.and_then(|group| document.groups.get(group))
+                                 .cloned()
+                                 .unwrap_or(vec![]);
+        if type_ == ReportType::CspViolation {
+            endpoints.extend(document.report_uris.iter().cloned());
+        }
+        endpoints
+    }
+
+    pub fn queue_report(&mut self,
+                        pipeline_id: PipelineId,
+                        report: Report,
+                        resource_thread: &CoreResourceThread) {
+        let endpoints = self.endpoints_for(pipeline_id, report.type_);
+        if endpoints.is_empty() {
+            return debug!("Dropping {} report without endpoints", report.type_.as_str());
+        }
+        let document_url = self.documents[&pipeline_id].url.clone();
+
+        if self.delivery_chan.is_none() {
+            let (sender, receiver) = channel();
+            let user_agent = self.user_agent.clone();
+            let resource_thread = resource_thread.clone();
+            spawn_named("ReportDelivery".to_owned(), move || {
+                deliver_reports(receiver, user_agent, resource_thread)
+            });
+            self.delivery_chan = Some(sender);
+        }
+
+        let delivery_chan = self.delivery_chan.as_ref().unwrap();
+        for endpoint in endpoints {
+            let _ = delivery_chan.send(QueuedReport {
+                endpoint: endpoint,
+                document_url: document_url.clone(),
+                report: report.clone(),
+                queued_at: precise_time_ns(),
+                attempts: 0,
+            });
+        }
+    }
+}
+
+/// Parses the value of a `Report-To` header into the endpoints of each group it names.
+pub fn parse_report_to(value: &str, base_url: &Url) -> Vec<(String, Vec<Url>)> {
+    // A header can hold several comma-separated groups.
+    let groups = match Json::from_str(&format!("[{}]", value)) {
+        Ok(Json::Array(groups)) => groups,
+        _ => return vec![],
+    };
+
+    groups.iter().filter_map(|group| group.as_object()).map(|group| {
+        let name = group.get("group")
+                        .and_then(|name| name.as_string())
+                        .unwrap_or(DEFAULT_GROUP)
+                        .to_owned();
+        let endpoints = group.get("endpoints").and_then(|endpoints| endpoints.as_array());
+        let urls = endpoints.map_or(vec![], |endpoints| {
+            endpoints.iter()
+                     .filter_map(|endpoint| endpoint.find("url").and_then(|url| url.as_string()))
+                     .filter_map(|url| base_url.join(url).ok())
+                     .collect()
+        });
+        (name, urls)
+    }).collect()
+}
+
+struct ReportOrigin;
+
+impl LoadOrigin for ReportOrigin {
+    fn referrer_url(&self) -> Option<Url> {
+        None
+    }
+    fn referrer_policy(&self) -> Option<ReferrerPolicy> {
+        Some(ReferrerPolicy::NoReferrer)
+    }
+    fn request_source(&self) -> RequestSource {
+        RequestSource::None
+    }
+    fn pipeline_id(&self) -> Option<PipelineId> {
+        None
+    }
+}
+
+/// Runs on the delivery thread, until the resource thread drops its channel and all queued
+/// reports are handled.
+fn deliver_reports(receiver: Receiver<QueuedReport>,
+                   user_agent: String,
+                   resource_thread: CoreResourceThread) {
+    let mut queue: Vec<QueuedReport> = vec![];
+    loop {
+        if queue.is_empty() {
+            match receiver.recv() {
+                Ok(report) => queue.push(report),
+                Err(_) => return,
+            }
+        }
+        thread::sleep(Duration::from_millis(BATCH_DELAY_MS));
+        while let Ok(report) = receiver.try_recv() {
+            queue.push(report);
+        }
+        if queue.len() > MAX_QUEUED_REPORTS {
+            let excess = queue.len() - MAX_QUEUED_REPORTS;
+            warn!("Dropping {} reports over the queue limit", excess);
+            queue.drain(..excess);
+        }
+
+        let mut batches: Vec<(Url, Vec<QueuedReport>)> = vec![];
+        for report in queue.drain(..) {
+            match batches.iter().position(|&(ref endpoint, _)| *endpoint == report.endpoint) {
+                Some(index) => batches[index].1.push(report),
+                None => batches.push((report.endpoint.clone(), vec![report])),
+            }
+        }
+
+        for (endpoint, reports) in batches {
+            if post_reports(&endpoint, &reports, &user_agent, &resource_thread) {
+                continue
+            }
+            for mut report in reports {
+                report.attempts += 1;
+                if report.attempts < MAX_DELIVERY_ATTEMPTS {
+                    queue.push(report);
+                } else {
+                    warn!("Giving up on delivering a report to {}", endpoint);
+                }
+            }
+        }
+    }
+}
+
+/// Posts a batch of reports to an endpoint, returning whether the endpoint accepted them.
+fn post_reports(endpoint: &Url,
+                reports: &[QueuedReport],
+                user_agent: &str,
+                resource_thread: &CoreResourceThread)
+                -> bool {
+    let now = precise_time_ns();
+    let payload: Vec<Json> = reports.iter().map(|queued| {
+        let body = queued.report.body.iter().map(|(key, value)| {
+            (key.clone(), Json::String(value.clone()))
+        }).collect();
+        let mut report = BTreeMap::new();
+        report.insert("type".to_owned(), Json::String(queued.report.type_.as_str().to_owned()));
+        report.insert("age".to_owned(), Json::U64((now - queued.queued_at) / 1000000));
+        report.insert("url".to_owned(), Json::String(queued.document_url.to_string()));
+        report.insert("user_agent".to_owned(), Json::String(user_agent.to_owned()));
+        report.insert("body".to_owned(), Json::Object(body));
+        Json::Object(report)
+    }).collect();
+
+    // There is no load context for reports; they are never sniffed or rendered.
+    let mut load_data = LoadData::new(LoadContext::Browsing, endpoint.clone(), &ReportOrigin);
+    load_data.method = Method::Post;
+    load_data.credentials_flag = false;
+    load_data.headers.set(ContentType("application/reports+json".parse().unwrap()));
+    load_data.data = Some(Json::Array(payload).to_string().into_bytes());
+
+    let (sender, receiver) = ipc::channel().unwrap();
+    let msg = CoreResourceMsg::Load(load_data, LoadConsumer::Channel(sender), None);
+    if resource_thread.send(msg).is_err() {
+        return false
+    }
+    let response = match receiver.recv() {
+        Ok(response) => response,
+        Err(_) => return false,
+    };
+    loop {
+        match response.progress_port.recv() {
+            Ok(ProgressMsg::Payload(_)) => {}
+            Ok(ProgressMsg::Done(Ok(()))) => break,
+            Ok(ProgressMsg::Done(Err(_))) | Err(_) => return false,
+        }
+    }
+    response.metadata.status.map_or(false, |RawStatus(code, _)| code >= 200 && code < 300)
+}
@@ -22,10 +22,13 @@ use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
 use mime_classifier::{ApacheBugFlag, MIMEClassifier, NoSniffFlag};
 use net_traits::LoadContext;
 use net_traits::ProgressMsg::Done;
-use net_traits::{AsyncResponseTarget, Metadata, ProgressMsg, ResponseAction, CoreResourceThread};
+use net_traits::{AsyncResponseTarget, BlockedRequestsListener, Metadata, ProgressMsg};
+use net_traits::{ResponseAction, CoreResourceThread};
 use net_traits::{CoreResourceMsg, CookieSource, LoadConsumer, LoadData, LoadResponse, ResourceId};
 use net_traits::{NetworkError, WebSocketCommunicate, WebSocketConnectData, ResourceThreads};
+use net_traits::{UrlClassifier, UrlThreat};
 use profile_traits::time::ProfilerChan;
+use reporting::ReportingManager;
 use rustc_serialize::json;
 use rustc_serialize::{Decodable, Encodable};
 use std::borrow::ToOwned;
@@ -39,6 +42,7 @@ use std::path::Path;
 use std::sync::mpsc::{Receiver, Sender, channel};
 use std::sync::{Arc, RwLock};
 use storage_thread::StorageThreadFactory;
+use tracking_protection::{TrackingProtection, TrackingProtectionExceptions};
 use url::Url;
 use util::opts;
 use util::prefs;
@@ -141,8 +145,8 @@ fn start_sending_opt(start_chan: LoadConsumer, metadata: Metadata,
         }
         LoadConsumer::Listener(target) => {
             match network_error {
-                Some(NetworkError::SslValidation(url)) => {
-                    let error = NetworkError::SslValidation(url);
+                Some(error @ NetworkError::SslValidation(_)) |
+                Some(error @ NetworkError::UnsafeUrl(..)) => {
                     target.invoke_with_listener(ResponseAction::HeadersAvailable(Err(error)));
                 }
                 _ => target.invoke_with_listener(ResponseAction::HeadersAvailable(Ok(metadata))),
@@ -154,8 +158,15 @@ fn start_sending_opt(start_chan: LoadConsumer, metadata: Metadata,
 
 pub fn new_resource_threads(user_agent: String,
                             devtools_chan: Option<Sender<DevtoolsControlMsg>>,
-                            profiler_chan: ProfilerChan) -> ResourceThreads {
-    ResourceThreads::new(new_core_resource_thread(user_agent, devtools_chan, profiler_chan),
+                            profiler_chan: ProfilerChan,
+                            url_classifier: Option<Arc<UrlClassifier>>,
+                            blocked_requests_listener: Option<BlockedRequestsListener>)
+                            -> ResourceThreads {
+    ResourceThreads::new(new_core_resource_thread(user_agent,
+                                                  devtools_chan,
+                                                  profiler_chan,
+                                                  url_classifier,
+                                                  blocked_requests_listener),
                          StorageThreadFactory::new(),
                          FileManagerThreadFactory::new())
 }
@@ -164,13 +175,18 @@ pub fn new_resource_threads(user_agent: String,
 /// Create a CoreResourceThread
 pub fn new_core_resource_thread(user_agent: String,
                                 devtools_chan: Option<Sender<DevtoolsControlMsg>>,
-                                profiler_chan: ProfilerChan) -> CoreResourceThread {
+                                profiler_chan: ProfilerChan,
+                                url_classifier: Option<Arc<UrlClassifier>>,
+                                blocked_requests_listener: Option<BlockedRequestsListener>)
+                                -> CoreResourceThread {
     let hsts_preload = HstsList::from_servo_preload();
+    let tracking_protection = TrackingProtection::from_servo_list();
     let (setup_chan, setup_port) = ipc::channel().unwrap();
     let setup_chan_clone = setup_chan.clone();
     spawn_named("ResourceManager".to_owned(), move || {
         let resource_manager = CoreResourceManager::new(
-            user_agent, hsts_preload, devtools_chan, profiler_chan
+            user_agent, hsts_preload, devtools_chan, profiler_chan, url_classifier,
+            tracking_protection, blocked_requests_listener
         );
 
         let mut channel_manager = ResourceChannelManager {
@@ -211,6 +227,20 @@ impl ResourceChannelManager {
                 CoreResourceMsg::Synchronize(sender) => {
                     let _ = sender.send(());
                 }
+                CoreResourceMsg::SetTrackingProtectionForSite(site, enabled) => {
+                    let tracking_protection = &mut self.resource_manager.tracking_protection;
+                    if let Some(ref mut tracking_protection) = *tracking_protection {
+                        tracking_protection.set_enabled_for_site(site, enabled);
+                    }
+                }
+                CoreResourceMsg::SetReportingEndpoints(pipeline_id, url, headers) =>
+                    self.resource_manager.reporting.set_endpoints(pipeline_id, url, headers),
+                CoreResourceMsg::QueueReport(pipeline_id, report) => {
+                    let reporting = &mut self.resource_manager.reporting;
+                    reporting.queue_report(pipeline_id, report, &control_sender)
+                }
+                CoreResourceMsg::ClearReportingEndpoints(pipeline_id) =>
+                    self.resource_manager.reporting.clear_endpoints(pipeline_id),
                 CoreResourceMsg::Exit => {
                     if let Some(ref config_dir) = opts::get().config_dir {
                         match self.resource_manager.auth_cache.read() {
@@ -225,6 +255,12 @@ impl ResourceChannelManager {
                             Ok(hsts) => write_json_to_file(&*hsts, config_dir, "hsts_list.json"),
                             Err(_) => warn!("Error writing hsts list to disk"),
                         }
+                        let tracking_protection = &self.resource_manager.tracking_protection;
+                        if let Some(ref tracking_protection) = *tracking_protection {
+                            write_json_to_file(&tracking_protection.exceptions,
+                                               config_dir,
+                                               "tracking_protection_exceptions.json");
+                        }
                     }
                     break;
                 }
@@ -381,21 +417,38 @@ pub struct CoreResourceManager {
     connector: Arc<Pool<Connector>>,
     cancel_load_map: HashMap<ResourceId, Sender<()>>,
     next_resource_id: ResourceId,
+    /// Consulted before every load, if set.
+    url_classifier: Option<Arc<UrlClassifier>>,
+    /// Set if tracking protection is enabled.
+    tracking_protection: Option<TrackingProtection>,
+    blocked_requests_listener: Option<BlockedRequestsListener>,
+    reporting: ReportingManager,
 }
 
 impl CoreResourceManager {
     pub fn new(user_agent: String,
                mut hsts_list: HstsList,
                devtools_channel: Option<Sender<DevtoolsControlMsg>>,
-               profiler_chan: ProfilerChan) -> CoreResourceManager {
+               profiler_chan: ProfilerChan,
+               url_classifier: Option<Arc<UrlClassifier>>,
+               mut tracking_protection: Option<TrackingProtection>,
+               blocked_requests_listener: Option<BlockedRequestsListener>) -> CoreResourceManager {
         let mut auth_cache = AuthCache::new();
         let mut cookie_jar = CookieStorage::new();
         if let Some(ref config_dir) = opts::get().config_dir {
             read_json_from_file(&mut auth_cache, config_dir, "auth_cache.json");
             read_json_from_file(&mut hsts_list, config_dir, "hsts_list.json");
             read_json_from_file(&mut cookie_jar, config_dir, "cookie_jar.json");
+            if let Some(ref mut tracking_protection) = tracking_protection {
+                let mut exceptions = TrackingProtectionExceptions::new();
+                read_json_from_file(&mut exceptions,
+                                    config_dir,
+                                    "tracking_protection_exceptions.json");
+                tracking_protection.exceptions = exceptions;
+            }
         }
         CoreResourceManager {
+            reporting: ReportingManager::new(user_agent.clone()),
             user_agent: user_agent,
             cookie_jar: Arc::new(RwLock::new(cookie_jar)),
             auth_cache: Arc::new(RwLock::new(auth_cache)),
@@ -406,6 +459,9 @@ impl CoreResourceManager {
             connector: create_http_connector(),
             cancel_load_map: HashMap::new(),
             next_resource_id: ResourceId(0),
+            url_classifier: url_classifier,
+            tracking_protection: tracking_protection,
+            blocked_requests_listener: blocked_requests_listener,
         }
     }
 
@@ -447,6 +503,23 @@ impl CoreResourceManager {
         });
 
         let cancel_listener = CancellationListener::new(cancel_resource);
+
+        if let Some(ref mut tracking_protection) = self.tracking_protection {
+            if tracking_protection.should_block(&load_data) {
+                debug!("resource_thread: blocked tracker: {}", load_data.url);
+                if let Some(pipeline_id) = load_data.pipeline_id {
+                    let count = tracking_protection.record_blocked_request(pipeline_id);
+                    if let Some(ref listener) = self.blocked_requests_listener {
+                        listener(pipeline_id, count);
+                    }
+                }
+                send_error(load_data.url,
+                           NetworkError::Internal("Blocked by tracking protection".to_owned()),
+                           consumer);
+                return
+            }
+        }
+
         let loader = match load_data.url.scheme() {
             "chrome" => from_factory(chrome_loader::factory),
             "file" => from_factory(file_loader::factory),
@@ -471,12 +544,33 @@ impl CoreResourceManager {
                 return
             }
         };
-        debug!("resource_thread: loading url: {}", load_data.url);
+        let mime_classifier = self.mime_classifier.clone();
+        let url_classifier = match self.url_classifier {
+            Some(ref url_classifier) => url_classifier.clone(),
+            None => {
+                debug!("resource_thread: loading url: {}", load_data.url);
+                return loader.call_box((load_data, consumer, mime_classifier, cancel_listener))
+            }
+        };
 
-        loader.call_box((load_data,
-                         consumer,
-                         self.mime_classifier.clone(),
-                         cancel_listener));
+        // The load is held back until the URL has been classified, which may happen
+        // asynchronously on another thread.
+        let url = load_data.url.clone();
+        let context = load_data.context.clone();
+        url_classifier.classify(&url, &context, box move |threat: Option<UrlThreat>| {
+            match threat {
+                Some(threat) => {
+                    warn!("resource_thread: blocked load of {} flagged as {:?}",
+                          load_data.url, threat);
+                    let url = load_data.url.clone();
+                    send_error(url.clone(), NetworkError::UnsafeUrl(url, threat), consumer);
+                }
+                None => {
+                    debug!("resource_thread: loading url: {}", load_data.url);
+                    loader.call_box((load_data, consumer, mime_classifier, cancel_listener));
+                }
+            }
+        });
     }
 
     fn websocket_connect(&self,
@@ -0,0 +1,124 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Blocking of third-party requests to known trackers, enabled with the
+//! `network.tracking-protection.enabled` pref.
+
+use msg::constellation_msg::PipelineId;
+use net_traits::{LoadContext, LoadData};
+use rustc_serialize::json::decode;
+use std::collections::{HashMap, HashSet};
+use std::str::from_utf8;
+use util::prefs;
+use util::resource_files::read_resource_file;
+
+/// The sites tracking protection has been disabled for by the user.
+#[derive(RustcDecodable, RustcEncodable, Clone)]
+pub struct TrackingProtectionExceptions {
+    pub sites: HashSet<String>,
+}
+
+impl TrackingProtectionExceptions {
+    pub fn new() -> TrackingProtectionExceptions {
+        TrackingProtectionExceptions {
+            sites: HashSet::new(),
+        }
+    }
+}
+
+pub struct TrackingProtection {
+    /// The hosts of known trackers. Their subdomains are trackers as well.
+    trackers: HashSet<String>,
+    pub exceptions: TrackingProtectionExceptions,
+    /// The number of requests blocked so far for each page.
+    blocked_requests: HashMap<PipelineId, usize>,
+}
+
+impl TrackingProtection {
+    /// Create a `TrackingProtection` from the bytes of a JSON list of tracker hosts.
+    pub fn from_list(list_content: &[u8]) -> Option<TrackingProtection> {
+        let trackers: Vec<String> = match from_utf8(list_content).ok()
+                                                                 .and_then(|c| decode(c).ok()) {
+            Some(trackers) => trackers,
+            None => return None,
+        };
+        Some(TrackingProtection {
+            trackers: trackers.into_iter().map(|host| host.to_lowercase()).collect(),
+            exceptions: TrackingProtectionExceptions::new(),
+            blocked_requests: HashMap::new(),
+        })
+    }
+
+    /// Create a `TrackingProtection` from Servo's `tracking-protection.json` resource file, if
+    /// tracking protection is enabled and the file is valid.
+    pub fn from_servo_list() -> Option<TrackingProtection> {
+        if !prefs::get_pref("network.tracking-protection.enabled").as_boolean().unwrap_or(false) {
+            return None
+        }
+        let file_bytes = match read_resource_file("tracking-protection.json") {
+            Ok(file_bytes) => file_bytes,
+            Err(_) => {
+                warn!("Could not find Servo tracking protection list");
+                return None
+            }
+        };
+        let tracking_protection = TrackingProtection::from_list(&file_bytes);
+        if tracking_protection.is_none() {
+            warn!("Servo tracking protection list is invalid");
+        }
+        tracking_protection
+    }
+
+    /// Returns the listed tracker host the given host belongs to, if any.
+    fn tracker_domain(&self, host: &str) -> Option<String> {
+        let host = host.to_lowercase();
+        let mut domain = &*host;
+        loop {
+            if self.trackers.contains(domain) {
+                return Some(domain.to_owned())
+            }
+            match domain.find('.') {
+                Some(dot) => domain = &domain[dot + 1..],
+                None => return None,
+            }
+        }
+    }
+
+    /// Whether the load is a third-party subresource request to a tracker, made from a site
+    /// tracking protection has not been disabled for.
+    pub fn should_block(&self, load_data: &LoadData) -> bool {
+        if let LoadContext::Browsing = load_data.context {
+            return false
+        }
+        let site = match load_data.referrer_url.as_ref().and_then(|url| url.host_str()) {
+            Some(site) => site.to_lowercase(),
+            None => return false,
+        };
+        if self.exceptions.sites.contains(&site) {
+            return false
+        }
+        let tracker = match load_data.url.host_str().and_then(|host| self.tracker_domain(host)) {
+            Some(tracker) => tracker,
+            None => return false,
+        };
+        // Trackers are not blocked on their own sites.
+        site != tracker && !site.ends_with(&format!(".{}", tracker))
+    }
+
+    /// Counts a blocked request of the given page, returning the number blocked so far.
+    pub fn record_blocked_request(&mut self, pipeline_id: PipelineId) -> usize {
+        let count = self.blocked_requests.entry(pipeline_id).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    pub fn set_enabled_for_site(&mut self, site: String, enabled: bool) {
+        let site = site.to_lowercase();
+        if enabled {
+            self.exceptions.sites.remove(&site);
+        } else {
+            self.exceptions.sites.insert(site);
+        }
+    }
+}
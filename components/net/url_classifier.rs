@@ -0,0 +1,82 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! A `UrlClassifier` backed by a local list of flagged hosts, in the spirit of
+//! safe browsing lists.
+
+use net_traits::{LoadContext, UrlClassifier, UrlClassifierCallback, UrlThreat};
+use rustc_serialize::json::decode;
+use std::collections::HashMap;
+use std::str::from_utf8;
+use url::Url;
+use util::resource_files::read_resource_file;
+
+/// The format of the list file: the flagged hosts for each kind of threat. Subdomains of a
+/// flagged host are flagged as well.
+#[derive(RustcDecodable)]
+struct ClassificationList {
+    malware: Option<Vec<String>>,
+    phishing: Option<Vec<String>>,
+    unwanted: Option<Vec<String>>,
+}
+
+pub struct LocalListClassifier {
+    hosts: HashMap<String, UrlThreat>,
+}
+
+impl LocalListClassifier {
+    /// Create a `LocalListClassifier` from the bytes of a JSON list file.
+    pub fn from_list(list_content: &[u8]) -> Option<LocalListClassifier> {
+        let list: ClassificationList = match from_utf8(list_content).ok()
+                                                                    .and_then(|c| decode(c).ok()) {
+            Some(list) => list,
+            None => return None,
+        };
+
+        let mut hosts = HashMap::new();
+        for (entries, threat) in vec![(list.malware, UrlThreat::Malware),
+                                      (list.phishing, UrlThreat::Phishing),
+                                      (list.unwanted, UrlThreat::Unwanted)] {
+            for host in entries.unwrap_or(vec![]) {
+                hosts.insert(host.to_lowercase(), threat);
+            }
+        }
+        Some(LocalListClassifier {
+            hosts: hosts,
+        })
+    }
+
+    /// Create a `LocalListClassifier` from Servo's `url-classification.json` resource file,
+    /// if there is a valid one.
+    pub fn from_servo_list() -> Option<LocalListClassifier> {
+        read_resource_file("url-classification.json").ok().and_then(|bytes| {
+            let classifier = LocalListClassifier::from_list(&bytes);
+            if classifier.is_none() {
+                warn!("Servo URL classification file is invalid");
+            }
+            classifier
+        })
+    }
+
+    /// Returns the threat the host or one of its parent domains is flagged as, if any.
+    pub fn threat_for_host(&self, host: &str) -> Option<UrlThreat> {
+        let host = host.to_lowercase();
+        let mut domain = &*host;
+        loop {
+            if let Some(threat) = self.hosts.get(domain) {
+                return Some(*threat)
+            }
+            match domain.find('.') {
+                Some(dot) => domain = &domain[dot + 1..],
+                None => return None,
+            }
+        }
+    }
+}
+
+impl UrlClassifier for LocalListClassifier {
+    fn classify(&self, url: &Url, _context: &LoadContext, callback: UrlClassifierCallback) {
+        callback.call_box((url.host_str().and_then(|host| self.threat_for_host(host)),))
+    }
+}
@@ -0,0 +1,157 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The thread that brokers the access of pages to USB devices. It finds the devices that match
+//! the filters of a request, and hands the one that the user chooses to the page.
+
+use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
+use net_traits::usb_thread::{UsbDeviceFilter, UsbDeviceMsg, UsbMethodMsg, UsbResult};
+use net_traits::{DeviceChoice, DeviceChooser, DeviceKind, choose_device};
+use std::borrow::ToOwned;
+#[cfg(target_os = "linux")]
+use std::fs::{self, File};
+#[cfg(target_os = "linux")]
+use std::io::Read;
+#[cfg(target_os = "linux")]
+use std::path::Path;
+use util::thread::spawn_named;
+
+const DEVICE_MATCH_ERROR: &'static str = "No device found, that matches the given options";
+// The devices that the kernel knows of, with their descriptors as attributes.
+#[cfg(target_os = "linux")]
+const SYSFS_USB_DEVICES: &'static str = "/sys/bus/usb/devices";
+
+pub trait UsbThreadFactory {
+    fn new(chooser: DeviceChooser) -> Self;
+}
+
+impl UsbThreadFactory for IpcSender<UsbMethodMsg> {
+    fn new(chooser: DeviceChooser) -> IpcSender<UsbMethodMsg> {
+        let (sender, receiver) = ipc::channel().unwrap();
+        spawn_named("UsbThread".to_owned(), move || {
+            UsbManager::new(receiver, chooser).start();
+        });
+        sender
+    }
+}
+
+// https://wicg.github.io/webusb/#matches-a-device-filter
+fn matches_filter(device: &UsbDeviceMsg, filter: &UsbDeviceFilter) -> bool {
+    fn matches<T: PartialEq>(filter: &Option<T>, value: &T) -> bool {
+        filter.as_ref().map_or(true, |filter| filter == value)
+    }
+
+    matches(&filter.vendor_id, &device.vendor_id) &&
+    matches(&filter.product_id, &device.product_id) &&
+    matches(&filter.class_code, &device.device_class) &&
+    matches(&filter.subclass_code, &device.device_subclass) &&
+    matches(&filter.protocol_code, &device.device_protocol) &&
+    filter.serial_number.as_ref().map_or(true, |serial_number| {
+        device.serial_number.as_ref() == Some(serial_number)
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn read_attribute(device_path: &Path, name: &str) -> Option<String> {
+    let mut value = String::new();
+    match File::open(device_path.join(name)).and_then(|mut file| file.read_to_string(&mut value)) {
+        Ok(_) => Some(value.trim().to_owned()),
+        Err(_) => None,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_hex_attribute(device_path: &Path, name: &str) -> Option<u16> {
+    read_attribute(device_path, name).and_then(|value| u16::from_str_radix(&value, 16).ok())
+}
+
+#[cfg(target_os = "linux")]
+fn get_device(device_path: &Path) -> Option<UsbDeviceMsg> {
+    // Interfaces are listed next to devices, but have no device descriptor.
+    let vendor_id = match read_hex_attribute(device_path, "idVendor") {
+        Some(vendor_id) => vendor_id,
+        None => return None,
+    };
+    let id = match device_path.file_name().and_then(|name| name.to_str()) {
+        Some(id) => id.to_owned(),
+        None => return None,
+    };
+    Some(UsbDeviceMsg {
+        id: id,
+        vendor_id: vendor_id,
+        product_id: read_hex_attribute(device_path, "idProduct").unwrap_or(0),
+        device_class: read_hex_attribute(device_path, "bDeviceClass").unwrap_or(0) as u8,
+        device_subclass: read_hex_attribute(device_path, "bDeviceSubClass").unwrap_or(0) as u8,
+        device_protocol: read_hex_attribute(device_path, "bDeviceProtocol").unwrap_or(0) as u8,
+        manufacturer_name: read_attribute(device_path, "manufacturer"),
+        product_name: read_attribute(device_path, "product"),
+        serial_number: read_attribute(device_path, "serial"),
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn get_devices() -> Vec<UsbDeviceMsg> {
+    match fs::read_dir(SYSFS_USB_DEVICES) {
+        Ok(entries) => {
+            entries.filter_map(|entry| entry.ok())
+                   .filter_map(|entry| get_device(&entry.path()))
+                   .collect()
+        }
+        Err(_) => vec!(),
+    }
+}
+
+// Devices are only found through sysfs so far.
+#[cfg(not(target_os = "linux"))]
+fn get_devices() -> Vec<UsbDeviceMsg> {
+    vec!()
+}
+
+pub struct UsbManager {
+    receiver: IpcReceiver<UsbMethodMsg>,
+    chooser: DeviceChooser,
+}
+
+impl UsbManager {
+    pub fn new(receiver: IpcReceiver<UsbMethodMsg>, chooser: DeviceChooser) -> UsbManager {
+        UsbManager {
+            receiver: receiver,
+            chooser: chooser,
+        }
+    }
+
+    fn start(&mut self) {
+        while let Ok(msg) = self.receiver.recv() {
+            match msg {
+                UsbMethodMsg::RequestDevice(filters, sender) => {
+                    self.request_device(filters, sender)
+                },
+                UsbMethodMsg::Exit => {
+                    break
+                },
+            }
+        }
+    }
+
+    // https://wicg.github.io/webusb/#request-the-usb-device-permission
+    fn request_device(&mut self,
+                      filters: Vec<UsbDeviceFilter>,
+                      sender: IpcSender<UsbResult<UsbDeviceMsg>>) {
+        let devices: Vec<UsbDeviceMsg> = get_devices().into_iter().filter(|device| {
+            filters.is_empty() || filters.iter().any(|filter| matches_filter(device, filter))
+        }).collect();
+        let choices = devices.iter().map(|device| {
+            DeviceChoice {
+                id: device.id.clone(),
+                name: device.product_name.clone().unwrap_or_else(|| {
+                    format!("{:04x}:{:04x}", device.vendor_id, device.product_id)
+                }),
+            }
+        }).collect();
+        let chosen = choose_device(&self.chooser, DeviceKind::Usb, choices).and_then(|id| {
+            devices.into_iter().find(|device| device.id == id)
+        });
+        let _ = sender.send(chosen.ok_or_else(|| String::from(DEVICE_MATCH_ERROR)));
+    }
+}
@@ -5,6 +5,7 @@
 #![feature(box_syntax)]
 #![feature(custom_attribute)]
 #![feature(custom_derive)]
+#![feature(fnbox)]
 #![feature(plugin)]
 #![feature(slice_patterns)]
 #![feature(step_by)]
@@ -36,6 +37,8 @@ use hyper::method::Method;
 use hyper::mime::{Attr, Mime};
 use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
 use msg::constellation_msg::{PipelineId, ReferrerPolicy};
+use reporting::{Report, ReportingHeaders};
+use std::boxed::FnBox;
 use std::io::Error as IOError;
 use std::sync::mpsc::Sender;
 use std::thread;
@@ -49,9 +52,11 @@ pub mod filemanager_thread;
 pub mod hosts;
 pub mod image_cache_thread;
 pub mod net_error_list;
+pub mod reporting;
 pub mod request;
 pub mod response;
 pub mod storage_thread;
+pub mod usb_thread;
 
 /// Image handling.
 ///
@@ -340,6 +345,14 @@ pub enum CoreResourceMsg {
     Cancel(ResourceId),
     /// Synchronization message solely for knowing the state of the ResourceChannelManager loop
     Synchronize(IpcSender<()>),
+    /// Enable or disable tracking protection for the given site
+    SetTrackingProtectionForSite(String, bool),
+    /// Set where the reports of the document of a pipeline, at the given URL, are delivered
+    SetReportingEndpoints(PipelineId, Url, ReportingHeaders),
+    /// Queue a report of the document of a pipeline for delivery
+    QueueReport(PipelineId, Report),
+    /// Forget the reporting endpoints of a pipeline that has been closed
+    ClearReportingEndpoints(PipelineId),
     /// Break the load handler loop and exit
     Exit,
 }
@@ -571,4 +584,74 @@ pub enum NetworkError {
     LoadCancelled,
     /// SSL validation error that has to be handled in the HTML parser
     SslValidation(Url),
+    /// The URL was flagged by the URL classifier; navigations to it show a warning page
+    UnsafeUrl(Url, UrlThreat),
+}
+
+/// The kinds of threat a URL can be flagged as by a `UrlClassifier`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, HeapSizeOf)]
+pub enum UrlThreat {
+    Malware,
+    Phishing,
+    /// Deceptive or unwanted software
+    Unwanted,
+}
+
+impl UrlThreat {
+    pub fn description(&self) -> &'static str {
+        match *self {
+            UrlThreat::Malware => "This site may attempt to install malicious software.",
+            UrlThreat::Phishing => "This site may trick you into revealing personal information.",
+            UrlThreat::Unwanted => "This site may try to install unwanted software.",
+        }
+    }
+}
+
+/// Notified with the number of requests blocked so far by tracking protection, each time a
+/// request of the given page is blocked.
+pub type BlockedRequestsListener = Box<Fn(PipelineId, usize) + Send>;
+
+/// The kinds of devices that pages can request access to.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum DeviceKind {
+    Bluetooth,
+    Usb,
+}
+
+/// A device that the user can let a page access.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DeviceChoice {
+    /// The id of the device, as known to the thread that brokers access to it.
+    pub id: String,
+    /// The name of the device, to show to the user.
+    pub name: String,
+}
+
+/// Asks the embedder to let the user choose which of the given devices a page may access.
+/// The id of the chosen device, or `None` if the user chose none, is sent back on the channel.
+pub type DeviceChooser = Box<Fn(DeviceKind, Vec<DeviceChoice>, IpcSender<Option<String>>) + Send>;
+
+/// Let the user choose one of `devices` through `chooser`, waiting until they have.
+pub fn choose_device(chooser: &DeviceChooser,
+                     kind: DeviceKind,
+                     devices: Vec<DeviceChoice>)
+                     -> Option<String> {
+    if devices.is_empty() {
+        return None;
+    }
+    let (sender, receiver) = ipc::channel().unwrap();
+    chooser(kind, devices, sender);
+    receiver.recv().unwrap_or(None)
+}
+
+/// The callback a `UrlClassifier` calls with its verdict.
+pub type UrlClassifierCallback = Box<FnBox(Option<UrlThreat>) + Send>;
+
+/// A provider of URL classifications, such as a local block list or a check implemented by the
+/// embedder. It is consulted by the resource thread before every navigation and subresource
+/// load, which are held back until the callback is called.
+pub trait UrlClassifier: Send + Sync {
+    /// Classifies the URL, calling the callback with the threat it poses, if any. The callback
+    /// may be called from any thread, and must be called exactly once.
+    fn classify(&self, url: &Url, context: &LoadContext, callback: UrlClassifierCallback);
 }
@@ -0,0 +1,96 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Reports queued by documents for delivery to their reporting endpoints.
+//!
+//! https://w3c.github.io/reporting/
+
+use hyper::header::Headers;
+use std::collections::BTreeMap;
+use std::str::from_utf8;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum ReportType {
+    CspViolation,
+    Deprecation,
+    Crash,
+}
+
+impl ReportType {
+    /// The type of the report, as delivered to endpoints.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ReportType::CspViolation => "csp-violation",
+            ReportType::Deprecation => "deprecation",
+            ReportType::Crash => "crash",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Report {
+    pub type_: ReportType,
+    pub body: BTreeMap<String, String>,
+}
+
+impl Report {
+    /// A report of a violation of the given directive of the document's content security policy.
+    pub fn csp_violation(effective_directive: &str, blocked_url: &str) -> Report {
+        let mut body = BTreeMap::new();
+        body.insert("effectiveDirective".to_owned(), effective_directive.to_owned());
+        body.insert("blockedURL".to_owned(), blocked_url.to_owned());
+        Report {
+            type_: ReportType::CspViolation,
+            body: body,
+        }
+    }
+
+    /// A report of the use of a deprecated feature.
+    pub fn deprecation(id: &str, message: &str) -> Report {
+        let mut body = BTreeMap::new();
+        body.insert("id".to_owned(), id.to_owned());
+        body.insert("message".to_owned(), message.to_owned());
+        Report {
+            type_: ReportType::Deprecation,
+            body: body,
+        }
+    }
+
+    /// A report of the document's pipeline having crashed.
+    pub fn crash(reason: &str) -> Report {
+        let mut body = BTreeMap::new();
+        body.insert("reason".to_owned(), reason.to_owned());
+        Report {
+            type_: ReportType::Crash,
+            body: body,
+        }
+    }
+}
+
+/// The response headers of a document that configure where its reports are delivered.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ReportingHeaders {
+    /// The values of the `Report-To` headers.
+    pub report_to: Vec<String>,
+    /// The values of the `Content-Security-Policy` headers, for their `report-uri` and
+    /// `report-to` directives.
+    pub content_security_policy: Vec<String>,
+}
+
+impl ReportingHeaders {
+    pub fn from_headers(headers: &Headers) -> ReportingHeaders {
+        fn values(headers: &Headers, name: &str) -> Vec<String> {
+            headers.get_raw(name).map_or(vec![], |values| {
+                values.iter()
+                      .filter_map(|value| from_utf8(value).ok().map(|value| value.to_owned()))
+                      .collect()
+            })
+        }
+
+        ReportingHeaders {
+            report_to: values(headers, "Report-To"),
+            content_security_policy: values(headers, "Content-Security-Policy"),
+        }
+    }
+}
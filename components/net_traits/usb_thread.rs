@@ -0,0 +1,39 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use ipc_channel::ipc::IpcSender;
+
+/// A filter of the devices that a page requests, from a `USBDeviceFilter` dictionary.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct UsbDeviceFilter {
+    pub vendor_id: Option<u16>,
+    pub product_id: Option<u16>,
+    pub class_code: Option<u8>,
+    pub subclass_code: Option<u8>,
+    pub protocol_code: Option<u8>,
+    pub serial_number: Option<String>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct UsbDeviceMsg {
+    pub id: String,
+    // Device descriptor
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub device_class: u8,
+    pub device_subclass: u8,
+    pub device_protocol: u8,
+    // String descriptors
+    pub manufacturer_name: Option<String>,
+    pub product_name: Option<String>,
+    pub serial_number: Option<String>,
+}
+
+pub type UsbResult<T> = Result<T, String>;
+
+#[derive(Deserialize, Serialize)]
+pub enum UsbMethodMsg {
+    RequestDevice(Vec<UsbDeviceFilter>, IpcSender<UsbResult<UsbDeviceMsg>>),
+    Exit,
+}
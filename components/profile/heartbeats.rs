@@ -27,6 +27,7 @@ pub fn init() {
     maybe_create_heartbeat(&mut hbs, ProfilerCategory::LayoutRestyleDamagePropagation);
     maybe_create_heartbeat(&mut hbs, ProfilerCategory::LayoutNonIncrementalReset);
     maybe_create_heartbeat(&mut hbs, ProfilerCategory::LayoutSelectorMatch);
+    maybe_create_heartbeat(&mut hbs, ProfilerCategory::LayoutCascade);
     maybe_create_heartbeat(&mut hbs, ProfilerCategory::LayoutTreeBuilder);
     maybe_create_heartbeat(&mut hbs, ProfilerCategory::LayoutDamagePropagate);
     maybe_create_heartbeat(&mut hbs, ProfilerCategory::LayoutGeneratedContent);
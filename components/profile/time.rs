@@ -101,6 +101,7 @@ impl Formattable for ProfilerCategory {
             ProfilerCategory::PaintingPrepBuff => "+ ",
             ProfilerCategory::LayoutParallelWarmup |
             ProfilerCategory::LayoutSelectorMatch |
+            ProfilerCategory::LayoutCascade |
             ProfilerCategory::LayoutTreeBuilder |
             ProfilerCategory::LayoutTextShaping => "| + ",
             _ => ""
@@ -113,6 +114,7 @@ impl Formattable for ProfilerCategory {
             ProfilerCategory::LayoutRestyleDamagePropagation => "Restyle Damage Propagation",
             ProfilerCategory::LayoutNonIncrementalReset => "Non-incremental reset (temporary)",
             ProfilerCategory::LayoutSelectorMatch => "Selector Matching",
+            ProfilerCategory::LayoutCascade => "Cascade",
             ProfilerCategory::LayoutTreeBuilder => "Tree Building",
             ProfilerCategory::LayoutDamagePropagate => "Damage Propagation",
             ProfilerCategory::LayoutDisplayListSorting => "Sorting Display List",
@@ -156,10 +158,15 @@ impl Formattable for ProfilerCategory {
 
 type ProfilerBuckets = BTreeMap<(ProfilerCategory, Option<TimerMetadata>), Vec<f64>>;
 
+// for each CSS property, the number of its declarations that reflows cascaded, and the time in ms
+// that each of those reflows spent on them
+type PropertyCascadeBuckets = BTreeMap<String, (u64, Vec<f64>)>;
+
 // back end of the profiler that handles data aggregation and performance metrics
 pub struct Profiler {
     pub port: IpcReceiver<ProfilerMsg>,
     buckets: ProfilerBuckets,
+    property_cascade_buckets: PropertyCascadeBuckets,
     output: Option<OutputOptions>,
     pub last_msg: Option<ProfilerMsg>,
     trace: Option<TraceDump>,
@@ -278,6 +285,7 @@ impl Profiler {
         Profiler {
             port: port,
             buckets: BTreeMap::new(),
+            property_cascade_buckets: BTreeMap::new(),
             output: output,
             last_msg: None,
             trace: trace,
@@ -311,9 +319,19 @@ impl Profiler {
                 let ms = (t.1 - t.0) as f64 / 1000000f64;
                 self.find_or_insert(k, ms);
             },
-            ProfilerMsg::Print => if let Some(ProfilerMsg::Time(..)) = self.last_msg {
+            ProfilerMsg::PropertyCascade(properties) => {
+                for (name, declarations, ns) in properties {
+                    let bucket = self.property_cascade_buckets.entry(name).or_insert((0, vec![]));
+                    bucket.0 += declarations;
+                    bucket.1.push(ns as f64 / 1000000f64);
+                }
+            },
+            ProfilerMsg::Print => match self.last_msg {
                 // only print if more data has arrived since the last printout
-                self.print_buckets();
+                Some(ProfilerMsg::Time(..)) | Some(ProfilerMsg::PropertyCascade(..)) => {
+                    self.print_buckets()
+                }
+                _ => {}
             },
             ProfilerMsg::Exit(chan) => {
                 heartbeats::cleanup();
@@ -358,6 +376,7 @@ impl Profiler {
                             mean, median, min, max, data_len).unwrap();
                     }
                 }
+                self.write_property_cascade_buckets(&mut file);
             },
             Some(OutputOptions::Stdout(_)) => {
                 let stdout = io::stdout();
@@ -388,10 +407,44 @@ impl Profiler {
                     }
                 }
                 writeln!(&mut lock, "").unwrap();
+                self.write_property_cascade_buckets(&mut lock);
             },
             None => { /* Do nothing if not output option has been set */ },
         };
     }
+
+    // the properties are written from the most expensive to cascade to the least
+    fn write_property_cascade_buckets<W: Write>(&self, writer: &mut W) {
+        if self.property_cascade_buckets.is_empty() {
+            return
+        }
+        let mut properties: Vec<_> = self.property_cascade_buckets.iter().map(|(name, bucket)| {
+            (name, bucket.0, bucket.1.iter().sum::<f64>(), bucket.1.len())
+        }).collect();
+        properties.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(Ordering::Equal));
+        match self.output {
+            Some(OutputOptions::FileName(_)) => {
+                write!(writer, "\n_property_\t_declarations_\t_total (ms)_\t\
+                    _mean per reflow (ms)_\t_reflows_\n").unwrap();
+                for (name, declarations, total, reflows) in properties {
+                    write!(writer, "{}\t{}\t{:15.4}\t{:15.4}\t{:15}\n",
+                        name, declarations, total, total / (reflows as f64), reflows).unwrap();
+                }
+            },
+            Some(OutputOptions::Stdout(_)) => {
+                writeln!(writer, "{:35} {:15} {:15} {:21} {:-15}",
+                         "_property_", "  _declarations_", "   _total (ms)_",
+                         "_mean per reflow (ms)_", "     _reflows_").unwrap();
+                for (name, declarations, total, reflows) in properties {
+                    writeln!(writer, "{:-35} {:15} {:15.4} {:21.4} {:15}",
+                             name, declarations, total, total / (reflows as f64),
+                             reflows).unwrap();
+                }
+                writeln!(writer, "").unwrap();
+            },
+            None => {},
+        }
+    }
 }
 
 fn enforce_range<T>(min: T, max: T, value: T) -> T where T: Ord {
@@ -28,6 +28,9 @@ impl ProfilerChan {
 pub enum ProfilerMsg {
     /// Normal message used for reporting time
     Time((ProfilerCategory, Option<TimerMetadata>), (u64, u64), (u64, u64)),
+    /// Message used to report, for each CSS property that a reflow cascaded, the number of its
+    /// declarations and the time spent on them in nanoseconds
+    PropertyCascade(Vec<(String, u64, u64)>),
     /// Message used to force print the profiling metrics
     Print,
     /// Tells the profiler to shut down.
@@ -44,6 +47,7 @@ pub enum ProfilerCategory {
     LayoutRestyleDamagePropagation,
     LayoutNonIncrementalReset,
     LayoutSelectorMatch,
+    LayoutCascade,
     LayoutTreeBuilder,
     LayoutDamagePropagate,
     LayoutGeneratedContent,
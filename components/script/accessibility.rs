@@ -0,0 +1,684 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The accessibility tree that assistive technologies, such as screen readers, present instead of
+//! the rendering of a document. It is computed from the WAI-ARIA roles, states and properties of
+//! elements, and from the semantics of HTML elements where those are not given.
+//!
+//! https://w3c.github.io/aria/
+//! https://w3c.github.io/html-aam/
+//!
+//! The states that assistive technologies change are changed through the attributes that they
+//! come from, so that the element snapshots of the document record them like any other attribute
+//! change, and the style that depends on them through attribute selectors is updated.
+
+use dom::bindings::codegen::Bindings::CharacterDataBinding::CharacterDataMethods;
+use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
+use dom::bindings::codegen::Bindings::HTMLElementBinding::HTMLElementMethods;
+use dom::bindings::codegen::Bindings::HTMLInputElementBinding::HTMLInputElementMethods;
+use dom::bindings::codegen::Bindings::HTMLOptionElementBinding::HTMLOptionElementMethods;
+use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::js::Root;
+use dom::bindings::str::DOMString;
+use dom::characterdata::CharacterData;
+use dom::document::Document;
+use dom::element::Element;
+use dom::htmlelement::HTMLElement;
+use dom::htmlinputelement::HTMLInputElement;
+use dom::htmloptionelement::HTMLOptionElement;
+use dom::node::Node;
+use dom::text::Text;
+use script_traits::{AccessibilityAction, AccessibleNode, AccessibleRole, AccessibleStates};
+use script_traits::Tristate;
+use std::ascii::AsciiExt;
+use string_cache::Atom;
+use util::str::{split_html_space_chars, str_join};
+
+/// What the `role` attribute of an element makes of it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RoleAttribute {
+    /// The element has the given role.
+    Role(AccessibleRole),
+    /// `none` or `presentation`: the element has no role, but its contents are still in the
+    /// accessibility tree.
+    Presentation,
+}
+
+/// Parses the value of a `role` attribute, which is a list of roles of which the first that is
+/// known applies. Abstract roles, such as `widget`, are not known.
+/// https://w3c.github.io/aria/#document-handling_author-errors_roles
+pub fn parse_role(value: &str) -> Option<RoleAttribute> {
+    split_html_space_chars(value).filter_map(|token| {
+        let role = match &*token.to_ascii_lowercase() {
+            "none" | "presentation" => return Some(RoleAttribute::Presentation),
+            "alert" => AccessibleRole::Alert,
+            "alertdialog" => AccessibleRole::AlertDialog,
+            "application" => AccessibleRole::Application,
+            "article" => AccessibleRole::Article,
+            "banner" => AccessibleRole::Banner,
+            "button" => AccessibleRole::Button,
+            "cell" => AccessibleRole::Cell,
+            "checkbox" => AccessibleRole::Checkbox,
+            "columnheader" => AccessibleRole::ColumnHeader,
+            "combobox" => AccessibleRole::Combobox,
+            "complementary" => AccessibleRole::Complementary,
+            "contentinfo" => AccessibleRole::ContentInfo,
+            "definition" => AccessibleRole::Definition,
+            "dialog" => AccessibleRole::Dialog,
+            "directory" => AccessibleRole::Directory,
+            "document" => AccessibleRole::Document,
+            "feed" => AccessibleRole::Feed,
+            "figure" => AccessibleRole::Figure,
+            "form" => AccessibleRole::Form,
+            "grid" => AccessibleRole::Grid,
+            "gridcell" => AccessibleRole::GridCell,
+            "group" => AccessibleRole::Group,
+            "heading" => AccessibleRole::Heading,
+            "img" => AccessibleRole::Img,
+            "link" => AccessibleRole::Link,
+            "list" => AccessibleRole::List,
+            "listbox" => AccessibleRole::Listbox,
+            "listitem" => AccessibleRole::ListItem,
+            "log" => AccessibleRole::Log,
+            "main" => AccessibleRole::Main,
+            "marquee" => AccessibleRole::Marquee,
+            "math" => AccessibleRole::Math,
+            "menu" => AccessibleRole::Menu,
+            "menubar" => AccessibleRole::Menubar,
+            "menuitem" => AccessibleRole::MenuItem,
+            "menuitemcheckbox" => AccessibleRole::MenuItemCheckbox,
+            "menuitemradio" => AccessibleRole::MenuItemRadio,
+            "navigation" => AccessibleRole::Navigation,
+            "note" => AccessibleRole::Note,
+            "option" => AccessibleRole::Option,
+            "progressbar" => AccessibleRole::ProgressBar,
+            "radio" => AccessibleRole::Radio,
+            "radiogroup" => AccessibleRole::RadioGroup,
+            "region" => AccessibleRole::Region,
+            "row" => AccessibleRole::Row,
+            "rowgroup" => AccessibleRole::RowGroup,
+            "rowheader" => AccessibleRole::RowHeader,
+            "scrollbar" => AccessibleRole::Scrollbar,
+            "search" => AccessibleRole::Search,
+            "searchbox" => AccessibleRole::Searchbox,
+            "separator" => AccessibleRole::Separator,
+            "slider" => AccessibleRole::Slider,
+            "spinbutton" => AccessibleRole::SpinButton,
+            "status" => AccessibleRole::Status,
+            "switch" => AccessibleRole::Switch,
+            "tab" => AccessibleRole::Tab,
+            "table" => AccessibleRole::Table,
+            "tablist" => AccessibleRole::TabList,
+            "tabpanel" => AccessibleRole::TabPanel,
+            "term" => AccessibleRole::Term,
+            "textbox" => AccessibleRole::Textbox,
+            "timer" => AccessibleRole::Timer,
+            "toolbar" => AccessibleRole::Toolbar,
+            "tooltip" => AccessibleRole::Tooltip,
+            "tree" => AccessibleRole::Tree,
+            "treegrid" => AccessibleRole::TreeGrid,
+            "treeitem" => AccessibleRole::TreeItem,
+            _ => return None,
+        };
+        Some(RoleAttribute::Role(role))
+    }).next()
+}
+
+/// Parses the value of a true/false state, such as `aria-expanded`. `undefined` and invalid
+/// values leave the state undefined.
+/// https://w3c.github.io/aria/#valuetype_true-false-undefined
+pub fn parse_true_false(value: &str) -> Option<bool> {
+    let value = value.trim_matches(|c: char| c.is_whitespace());
+    if value.eq_ignore_ascii_case("true") {
+        Some(true)
+    } else if value.eq_ignore_ascii_case("false") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Parses the value of a tristate, such as `aria-checked`.
+/// https://w3c.github.io/aria/#valuetype_tristate
+pub fn parse_tristate(value: &str) -> Option<Tristate> {
+    if value.trim_matches(|c: char| c.is_whitespace()).eq_ignore_ascii_case("mixed") {
+        return Some(Tristate::Mixed)
+    }
+    parse_true_false(value).map(|value| if value { Tristate::True } else { Tristate::False })
+}
+
+/// Parses the value of a property that is a positive integer, such as `aria-level`.
+pub fn parse_positive_integer(value: &str) -> Option<u32> {
+    value.trim_matches(|c: char| c.is_whitespace()).parse::<u32>().ok().and_then(|value| {
+        if value >= 1 { Some(value) } else { None }
+    })
+}
+
+/// The accessibility tree of `document`, of which the root is the document itself.
+pub fn accessibility_tree(document: &Document) -> AccessibleNode {
+    let mut root = AccessibleNode {
+        id: document.upcast::<Node>().unique_id(),
+        role: AccessibleRole::Document,
+        name: collapse_whitespace(&document.Title()),
+        description: String::new(),
+        states: AccessibleStates::default(),
+        children: vec![],
+    };
+    if let Some(element) = document.GetDocumentElement() {
+        add_node(document, element.upcast::<Node>(), &mut root.children);
+    }
+    root
+}
+
+/// Performs `action`, if the node that it is about is still in `document`.
+pub fn perform_action(document: &Document, action: AccessibilityAction) {
+    let id = match action {
+        AccessibilityAction::Focus(ref id) |
+        AccessibilityAction::Activate(ref id) |
+        AccessibilityAction::SetExpanded(ref id, _) |
+        AccessibilityAction::SetChecked(ref id, _) |
+        AccessibilityAction::SetSelected(ref id, _) => id.clone(),
+    };
+    let element = match document.upcast::<Node>()
+                                .traverse_preorder()
+                                .find(|node| node.unique_id() == id)
+                                .and_then(Root::downcast::<Element>) {
+        Some(element) => element,
+        None => return,
+    };
+
+    match action {
+        AccessibilityAction::Focus(_) => {
+            if let Some(element) = element.downcast::<HTMLElement>() {
+                element.Focus();
+            }
+        }
+        AccessibilityAction::Activate(_) => {
+            if let Some(element) = element.downcast::<HTMLElement>() {
+                element.Click();
+            }
+        }
+        AccessibilityAction::SetExpanded(_, expanded) => {
+            // A summary expands and collapses its details.
+            match summarized_details(&element) {
+                Some(details) => details.set_bool_attribute(&Atom::from("open"), expanded),
+                None => set_aria_attribute(&element, "aria-expanded", bool_to_str(expanded)),
+            }
+        }
+        AccessibilityAction::SetChecked(_, checked) => {
+            match element.downcast::<HTMLInputElement>() {
+                Some(input) if is_checkable(input) => {
+                    input.SetIndeterminate(checked == Tristate::Mixed);
+                    input.SetChecked(checked == Tristate::True);
+                }
+                _ => {
+                    let value = match checked {
+                        Tristate::False => "false",
+                        Tristate::True => "true",
+                        Tristate::Mixed => "mixed",
+                    };
+                    set_aria_attribute(&element, "aria-checked", value);
+                }
+            }
+        }
+        AccessibilityAction::SetSelected(_, selected) => {
+            match element.downcast::<HTMLOptionElement>() {
+                Some(option) => option.SetSelected(selected),
+                None => set_aria_attribute(&element, "aria-selected", bool_to_str(selected)),
+            }
+        }
+    }
+}
+
+fn bool_to_str(value: bool) -> &'static str {
+    if value { "true" } else { "false" }
+}
+
+fn set_aria_attribute(element: &Element, name: &str, value: &str) {
+    element.set_string_attribute(&Atom::from(name), DOMString::from(value));
+}
+
+fn add_node(document: &Document, node: &Node, children: &mut Vec<AccessibleNode>) {
+    if let Some(text) = node.downcast::<Text>() {
+        let name = collapse_whitespace(&text.upcast::<CharacterData>().Data());
+        if !name.is_empty() {
+            children.push(AccessibleNode {
+                id: node.unique_id(),
+                role: AccessibleRole::StaticText,
+                name: name,
+                description: String::new(),
+                states: AccessibleStates::default(),
+                children: vec![],
+            });
+        }
+        return
+    }
+
+    let element = match node.downcast::<Element>() {
+        Some(element) => element,
+        None => return,
+    };
+    if is_hidden(element) {
+        return
+    }
+
+    let role = match attribute(element, "role").and_then(|role| parse_role(&role)) {
+        Some(RoleAttribute::Role(role)) => Some(role),
+        // Focusable elements keep the role of their element, so that users can tell what they
+        // are focusing.
+        // https://w3c.github.io/aria/#conflict_resolution_presentation_none
+        Some(RoleAttribute::Presentation) if !element.is_focusable_area() => None,
+        _ => implicit_role(element),
+    };
+
+    // Elements without roles are left out, but not their contents.
+    let role = match role {
+        Some(role) => role,
+        None => {
+            for child in node.children() {
+                add_node(document, &child, children);
+            }
+            return
+        }
+    };
+
+    let mut accessible = AccessibleNode {
+        id: node.unique_id(),
+        role: role,
+        name: accessible_name(document, element, role),
+        description: attribute(element, "aria-describedby").map_or(String::new(), |ids| {
+            text_of_elements(document, &ids)
+        }),
+        states: accessible_states(element, role),
+        children: vec![],
+    };
+    for child in node.children() {
+        add_node(document, &child, &mut accessible.children);
+    }
+    children.push(accessible);
+}
+
+/// The value of the attribute of `element` with the given name and no namespace, if it has one.
+fn attribute(element: &Element, name: &str) -> Option<String> {
+    element.get_attribute(&ns!(), &Atom::from(name)).map(|attr| String::from(&**attr.value()))
+}
+
+fn is_html_element(element: &Element, name: &str) -> bool {
+    *element.namespace() == ns!(html) && &**element.local_name() == name
+}
+
+/// Whether `element` and its contents are left out of the accessibility tree.
+/// https://w3c.github.io/aria/#tree_exclusion
+fn is_hidden(element: &Element) -> bool {
+    if attribute(element, "aria-hidden").and_then(|value| parse_true_false(&value)) == Some(true) {
+        return true
+    }
+    if *element.namespace() != ns!(html) {
+        return false
+    }
+    if element.has_attribute(&Atom::from("hidden")) {
+        return true
+    }
+    match &**element.local_name() {
+        "head" | "noscript" | "script" | "style" | "template" => true,
+        "input" => input_type(element) == "hidden",
+        _ => false,
+    }
+}
+
+/// The lowercase value of the `type` attribute of an `input` element.
+fn input_type(element: &Element) -> String {
+    attribute(element, "type").map_or(String::new(), |type_| type_.to_ascii_lowercase())
+}
+
+fn is_checkable(input: &HTMLInputElement) -> bool {
+    let type_ = input_type(input.upcast::<Element>());
+    type_ == "checkbox" || type_ == "radio"
+}
+
+/// The `details` element that `element` is the summary of, if it is one.
+fn summarized_details(element: &Element) -> Option<Root<Element>> {
+    if !is_html_element(element, "summary") {
+        return None
+    }
+    element.upcast::<Node>()
+           .GetParentElement()
+           .and_then(|parent| if is_html_element(&parent, "details") { Some(parent) } else { None })
+}
+
+/// The role of `element` when it has no `role` attribute.
+/// https://w3c.github.io/html-aam/#html-element-role-mappings
+fn implicit_role(element: &Element) -> Option<AccessibleRole> {
+    if *element.namespace() != ns!(html) {
+        return None
+    }
+    let has = |name: &str| element.has_attribute(&Atom::from(name));
+    let in_sectioning_content = || {
+        element.upcast::<Node>().ancestors().filter_map(Root::downcast::<Element>).any(|ancestor| {
+            ["article", "aside", "main", "nav", "section"].iter().any(|name| {
+                is_html_element(&ancestor, name)
+            })
+        })
+    };
+    Some(match &**element.local_name() {
+        "a" | "area" if has("href") => AccessibleRole::Link,
+        "article" => AccessibleRole::Article,
+        "aside" => AccessibleRole::Complementary,
+        "button" | "summary" => AccessibleRole::Button,
+        "datalist" => AccessibleRole::Listbox,
+        "dd" => AccessibleRole::Definition,
+        "details" | "fieldset" | "optgroup" => AccessibleRole::Group,
+        "dialog" => AccessibleRole::Dialog,
+        "dt" => AccessibleRole::Term,
+        "figure" => AccessibleRole::Figure,
+        "footer" if !in_sectioning_content() => AccessibleRole::ContentInfo,
+        "form" => AccessibleRole::Form,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => AccessibleRole::Heading,
+        "header" if !in_sectioning_content() => AccessibleRole::Banner,
+        "hr" => AccessibleRole::Separator,
+        // Images with an empty `alt` are decorative.
+        "img" if attribute(element, "alt").map_or(true, |alt| !alt.is_empty()) => {
+            AccessibleRole::Img
+        }
+        "input" => {
+            match &*input_type(element) {
+                "button" | "image" | "reset" | "submit" => AccessibleRole::Button,
+                "checkbox" => AccessibleRole::Checkbox,
+                "radio" => AccessibleRole::Radio,
+                "range" => AccessibleRole::Slider,
+                "number" => AccessibleRole::SpinButton,
+                "color" | "date" | "datetime-local" | "file" | "month" | "time" | "week" => {
+                    return None
+                }
+                _ if has("list") => AccessibleRole::Combobox,
+                "search" => AccessibleRole::Searchbox,
+                // Invalid types are the text type.
+                _ => AccessibleRole::Textbox,
+            }
+        }
+        "li" => AccessibleRole::ListItem,
+        "main" => AccessibleRole::Main,
+        "menu" | "ol" | "ul" => AccessibleRole::List,
+        "nav" => AccessibleRole::Navigation,
+        "option" => AccessibleRole::Option,
+        "output" => AccessibleRole::Status,
+        "progress" => AccessibleRole::ProgressBar,
+        // Sections are only regions if they are named.
+        "section" if has("aria-label") || has("aria-labelledby") || has("title") => {
+            AccessibleRole::Region
+        }
+        "select" => {
+            let size = attribute(element, "size").and_then(|size| parse_positive_integer(&size));
+            if has("multiple") || size.map_or(false, |size| size > 1) {
+                AccessibleRole::Listbox
+            } else {
+                AccessibleRole::Combobox
+            }
+        }
+        "table" => AccessibleRole::Table,
+        "tbody" | "tfoot" | "thead" => AccessibleRole::RowGroup,
+        "td" => AccessibleRole::Cell,
+        "textarea" => AccessibleRole::Textbox,
+        "th" => {
+            match attribute(element, "scope") {
+                Some(ref scope) if scope.eq_ignore_ascii_case("row") => AccessibleRole::RowHeader,
+                _ => AccessibleRole::ColumnHeader,
+            }
+        }
+        "tr" => AccessibleRole::Row,
+        _ => return None,
+    })
+}
+
+/// The states of `element`, which has the accessible role `role`. The native states of HTML
+/// elements win over their WAI-ARIA attributes, which should not contradict them anyway.
+/// https://w3c.github.io/html-aam/#html-attribute-state-and-property-mappings
+fn accessible_states(element: &Element, role: AccessibleRole) -> AccessibleStates {
+    let aria_true_false = |name: &str| {
+        attribute(element, name).and_then(|value| parse_true_false(&value))
+    };
+    let aria_tristate = |name: &str| {
+        attribute(element, name).and_then(|value| parse_tristate(&value))
+    };
+    let has = |name: &str| element.has_attribute(&Atom::from(name));
+
+    let mut states = AccessibleStates::default();
+    states.disabled = element.is_actually_disabled();
+    states.focusable = element.is_focusable_area();
+    states.focused = element.focus_state();
+
+    if let Some(input) = element.downcast::<HTMLInputElement>() {
+        if is_checkable(input) {
+            states.checked = Some(if input.Indeterminate() && role == AccessibleRole::Checkbox {
+                Tristate::Mixed
+            } else if input.Checked() {
+                Tristate::True
+            } else {
+                Tristate::False
+            });
+        } else if role == AccessibleRole::Slider || role == AccessibleRole::SpinButton {
+            states.value = Some(String::from(input.Value()));
+        }
+    }
+    if let Some(option) = element.downcast::<HTMLOptionElement>() {
+        states.selected = Some(option.Selected());
+    }
+    if let Some(details) = summarized_details(element) {
+        states.expanded = Some(details.has_attribute(&Atom::from("open")));
+    }
+    if is_html_element(element, "input") || is_html_element(element, "select") ||
+       is_html_element(element, "textarea") {
+        states.required = has("required");
+        states.readonly = has("readonly") && !is_html_element(element, "select");
+        states.multiselectable = has("multiple") && is_html_element(element, "select");
+    }
+    if role == AccessibleRole::Heading {
+        let name = &**element.local_name();
+        if name.len() == 2 && name.starts_with("h") {
+            states.level = name[1..].parse().ok();
+        }
+    }
+
+    if states.checked.is_none() {
+        states.checked = aria_tristate("aria-checked");
+    }
+    states.pressed = aria_tristate("aria-pressed");
+    if states.expanded.is_none() {
+        states.expanded = aria_true_false("aria-expanded");
+    }
+    if states.selected.is_none() {
+        states.selected = aria_true_false("aria-selected");
+    }
+    states.disabled = states.disabled || aria_true_false("aria-disabled") == Some(true);
+    states.required = states.required || aria_true_false("aria-required") == Some(true);
+    states.readonly = states.readonly || aria_true_false("aria-readonly") == Some(true);
+    states.multiselectable = states.multiselectable ||
+                             aria_true_false("aria-multiselectable") == Some(true);
+    states.busy = aria_true_false("aria-busy") == Some(true);
+    // Any value of `aria-invalid` but `false` means that the value is invalid in some way.
+    // https://w3c.github.io/aria/#aria-invalid
+    states.invalid = attribute(element, "aria-invalid").map_or(false, |value| {
+        let value = value.trim_matches(|c: char| c.is_whitespace());
+        !value.is_empty() && !value.eq_ignore_ascii_case("false")
+    });
+    // `aria-level` wins over the level of heading elements.
+    if let Some(level) = attribute(element, "aria-level").and_then(|value| {
+        parse_positive_integer(&value)
+    }) {
+        states.level = Some(level);
+    }
+    if let Some(value) = attribute(element, "aria-valuetext").or_else(|| {
+        attribute(element, "aria-valuenow")
+    }) {
+        states.value = Some(collapse_whitespace(&value));
+    }
+    states
+}
+
+/// Whether elements with the given role are named by their contents when nothing else names
+/// them.
+/// https://w3c.github.io/aria/#namefromcontent
+fn is_named_from_content(role: AccessibleRole) -> bool {
+    match role {
+        AccessibleRole::Button | AccessibleRole::Cell | AccessibleRole::Checkbox |
+        AccessibleRole::ColumnHeader | AccessibleRole::GridCell | AccessibleRole::Heading |
+        AccessibleRole::Link | AccessibleRole::MenuItem | AccessibleRole::MenuItemCheckbox |
+        AccessibleRole::MenuItemRadio | AccessibleRole::Option | AccessibleRole::Radio |
+        AccessibleRole::Row | AccessibleRole::RowHeader | AccessibleRole::Switch |
+        AccessibleRole::Tab | AccessibleRole::Tooltip | AccessibleRole::TreeItem => true,
+        _ => false,
+    }
+}
+
+/// The accessible name of `element`, which has the accessible role `role`. This follows the
+/// steps of the computation for the element itself, but not for the elements that name it,
+/// which are only named by their contents.
+/// https://w3c.github.io/accname/#mapping_additional_nd_te
+fn accessible_name(document: &Document, element: &Element, role: AccessibleRole) -> String {
+    if let Some(ids) = attribute(element, "aria-labelledby") {
+        let name = text_of_elements(document, &ids);
+        if !name.is_empty() {
+            return name
+        }
+    }
+    if let Some(label) = attribute(element, "aria-label") {
+        let name = collapse_whitespace(&label);
+        if !name.is_empty() {
+            return name
+        }
+    }
+    let name = native_name(document, element);
+    if !name.is_empty() {
+        return name
+    }
+    if is_named_from_content(role) {
+        let name = collapse_whitespace(&text_alternative(element.upcast()));
+        if !name.is_empty() {
+            return name
+        }
+    }
+    attribute(element, "title").map_or(String::new(), |title| collapse_whitespace(&title))
+}
+
+/// The name that the HTML semantics of `element` give to it.
+/// https://w3c.github.io/html-aam/#accessible-name-and-description-computation
+fn native_name(document: &Document, element: &Element) -> String {
+    if *element.namespace() != ns!(html) {
+        return String::new()
+    }
+    let child_text = |name: &str| {
+        element.upcast::<Node>()
+               .children()
+               .filter_map(Root::downcast::<Element>)
+               .find(|child| is_html_element(child, name))
+               .map_or(String::new(), |child| {
+                   collapse_whitespace(&text_alternative(child.upcast()))
+               })
+    };
+    let name = match &**element.local_name() {
+        "area" | "img" => attribute(element, "alt").unwrap_or(String::new()),
+        "fieldset" => child_text("legend"),
+        "figure" => child_text("figcaption"),
+        "table" => child_text("caption"),
+        "input" => {
+            match &*input_type(element) {
+                "image" => attribute(element, "alt").unwrap_or(String::new()),
+                type_ @ "button" | type_ @ "reset" | type_ @ "submit" => {
+                    // Submit and reset buttons have default labels.
+                    attribute(element, "value").unwrap_or_else(|| match type_ {
+                        "reset" => String::from("Reset"),
+                        "submit" => String::from("Submit"),
+                        _ => String::new(),
+                    })
+                }
+                _ => {
+                    let label = label_text(document, element);
+                    if label.is_empty() {
+                        attribute(element, "placeholder").unwrap_or(String::new())
+                    } else {
+                        label
+                    }
+                }
+            }
+        }
+        "button" | "meter" | "output" | "progress" | "select" | "textarea" => {
+            let label = label_text(document, element);
+            if label.is_empty() && is_html_element(element, "textarea") {
+                attribute(element, "placeholder").unwrap_or(String::new())
+            } else {
+                label
+            }
+        }
+        _ => String::new(),
+    };
+    collapse_whitespace(&name)
+}
+
+/// The text of the `label` elements of the labelable element `element`: the one that it is in,
+/// and those that are for its id.
+fn label_text(document: &Document, element: &Element) -> String {
+    let mut labels: Vec<Root<Element>> =
+        element.upcast::<Node>()
+               .ancestors()
+               .filter_map(Root::downcast::<Element>)
+               .filter(|ancestor| is_html_element(ancestor, "label"))
+               .take(1)
+               .collect();
+    if let Some(id) = attribute(element, "id") {
+        labels.extend(document.upcast::<Node>()
+                              .traverse_preorder()
+                              .filter_map(Root::downcast::<Element>)
+                              .filter(|label| {
+                                  is_html_element(label, "label") &&
+                                  attribute(label, "for").as_ref() == Some(&id)
+                              }));
+    }
+    labels.dedup();
+    collapse_whitespace(&str_join(labels.iter().map(|label| {
+        text_alternative(label.upcast())
+    }), " "))
+}
+
+/// The text of the elements whose ids are listed in `ids`, as in `aria-labelledby`.
+fn text_of_elements(document: &Document, ids: &str) -> String {
+    let texts = split_html_space_chars(ids).filter_map(|id| {
+        document.get_element_by_id(&Atom::from(id))
+    }).map(|element| text_alternative(element.upcast()));
+    collapse_whitespace(&str_join(texts, " "))
+}
+
+/// The text that the contents of `node` contribute to the names of the elements that they are
+/// in, leaving out the hidden ones, and taking the names that the others are given instead of
+/// their contents.
+/// https://w3c.github.io/accname/#step2F
+fn text_alternative(node: &Node) -> String {
+    let mut text = String::new();
+    for child in node.children() {
+        if let Some(data) = child.downcast::<Text>() {
+            text.push_str(&data.upcast::<CharacterData>().Data());
+            continue
+        }
+        let element = match child.downcast::<Element>() {
+            Some(element) => element,
+            None => continue,
+        };
+        if is_hidden(element) {
+            continue
+        }
+        let name = attribute(element, "aria-label")
+            .map(|label| collapse_whitespace(&label))
+            .and_then(|label| if label.is_empty() { None } else { Some(label) })
+            .or_else(|| {
+                if is_html_element(element, "img") { attribute(element, "alt") } else { None }
+            })
+            .unwrap_or_else(|| text_alternative(element.upcast()));
+        text.push_str(&name);
+    }
+    text
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    str_join(split_html_space_chars(text), " ")
+}
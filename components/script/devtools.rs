@@ -2,32 +2,57 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use cssparser::ToCss;
+use devtools_traits::StyleSheetInfo;
+use devtools_traits::TimelineMarker;
 use devtools_traits::TimelineMarkerType;
-use devtools_traits::{AutoMargins, CONSOLE_API, CachedConsoleMessage, CachedConsoleMessageTypes};
+use devtools_traits::{AnimationInfo, AutoMargins, CONSOLE_API, CachedConsoleMessage, CachedConsoleMessageTypes};
+use devtools_traits::BoxModelInfo;
 use devtools_traits::{ComputedNodeLayout, ConsoleAPI, PageError, ScriptToDevtoolsControlMsg};
-use devtools_traits::{EvaluateJSReply, Modification, NodeInfo, PAGE_ERROR, TimelineMarker};
+use devtools_traits::{EvaluateJSReply, Modification, NodeInfo, PAGE_ERROR, StyleSheetCoverage};
+use devtools_traits::{MatchedDeclarationInfo, MatchedRuleInfo, StyleRuleModification};
+use devtools_traits::{UnusedRuleInfo, UsedFontFaceInfo};
 use dom::bindings::codegen::Bindings::CSSStyleDeclarationBinding::CSSStyleDeclarationMethods;
 use dom::bindings::codegen::Bindings::DOMRectBinding::DOMRectMethods;
 use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
 use dom::bindings::codegen::Bindings::ElementBinding::ElementMethods;
+use dom::bindings::codegen::Bindings::HTMLLinkElementBinding::HTMLLinkElementMethods;
 use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use dom::bindings::conversions::{FromJSValConvertible, jsstring_to_str};
 use dom::bindings::global::GlobalRef;
 use dom::bindings::inheritance::Castable;
 use dom::bindings::js::Root;
+use dom::bindings::reflector::Reflectable;
 use dom::bindings::str::DOMString;
 use dom::browsingcontext::BrowsingContext;
 use dom::element::Element;
-use dom::node::Node;
-use dom::window::Window;
+use dom::htmllinkelement::HTMLLinkElement;
+use dom::htmlstyleelement::HTMLStyleElement;
+use dom::node::{self, Node, NodeDamage};
+use dom::window::{ReflowReason, Window};
+use euclid::point::Point2D;
 use ipc_channel::ipc::IpcSender;
-use js::jsapi::{JSAutoCompartment, ObjectClassName, RootedObject, RootedValue};
+use js::jsapi::{JSAutoCompartment, JSRuntime, ObjectClassName, RootedObject, RootedValue};
 use js::jsval::UndefinedValue;
+use rustc_serialize::json::Json;
+use layout_interface::{AnimationControl, BoxQuad, InvalidationOverlays, Msg, ReflowQueryType};
 use msg::constellation_msg::PipelineId;
 use script_thread::get_browsing_context;
+use script_traits::ScriptMsg as ConstellationMsg;
+use selectors::matching::matches;
+use selectors::parser::{Selector, parse_author_origin_selector_list_from_str};
+use std::collections::HashMap;
 use std::ffi::CStr;
 use std::str;
+use std::sync::Arc;
+use std::sync::mpsc::channel;
+use style::context::ReflowGoal;
+use style::parser::ParserContextExtraData;
+use style::properties::parse_style_attribute;
 use style::properties::longhands::{margin_top, margin_right, margin_bottom, margin_left};
+use style::selector_impl::{PseudoElement, ServoSelectorImpl};
+use style::servo::{StyleRuleEdit, Stylesheet};
+use style::stylesheets::Origin;
 use uuid::Uuid;
 
 #[allow(unsafe_code)]
@@ -250,3 +275,366 @@ pub fn handle_request_animation_frame(context: &BrowsingContext,
         devtools_sender.send(msg).unwrap();
     });
 }
+
+pub fn handle_set_invalidation_overlays(context: &BrowsingContext,
+                                        id: PipelineId,
+                                        paint_flashing: bool,
+                                        reflow_outlines: bool,
+                                        layer_borders: bool) {
+    let context = context.find(id).expect("There is no such context");
+    let window = context.active_window();
+    let overlays = InvalidationOverlays {
+        paint_flashing: paint_flashing,
+        reflow_outlines: reflow_outlines,
+        layer_borders: layer_borders,
+    };
+    window.layout_chan().send(Msg::SetInvalidationOverlays(overlays)).unwrap();
+
+    // Reflow so that the overlays appear or disappear without waiting for the page to change.
+    window.force_reflow(ReflowGoal::ForDisplay,
+                        ReflowQueryType::NoQuery,
+                        ReflowReason::InvalidationOverlaysChanged);
+}
+
+/// Returns the stylesheets of the document's `<link>` and `<style>` elements in document order,
+/// with the URLs of those loaded from a `<link>`.
+fn document_style_sheets(context: &BrowsingContext,
+                         pipeline: PipelineId)
+                         -> Vec<(Option<String>, Arc<Stylesheet>)> {
+    let context = get_browsing_context(context, pipeline);
+    let document = context.active_document();
+
+    document.upcast::<Node>().traverse_preorder().filter_map(|node| {
+        if let Some(link) = node.downcast::<HTMLLinkElement>() {
+            link.get_stylesheet().map(|sheet| (Some(String::from(link.Href())), sheet))
+        } else if let Some(style) = node.downcast::<HTMLStyleElement>() {
+            style.get_stylesheet().map(|sheet| (None, sheet))
+        } else {
+            None
+        }
+    }).collect()
+}
+
+pub fn handle_get_style_sheets(context: &BrowsingContext,
+                               pipeline: PipelineId,
+                               reply: IpcSender<Vec<StyleSheetInfo>>) {
+    let style_sheets = document_style_sheets(context, pipeline).into_iter().map(|(href, sheet)| {
+        StyleSheetInfo {
+            href: href,
+            sourceMapURL: sheet.source_map_url.as_ref().map(|url| url.as_str().to_owned()),
+            ruleCount: sheet.rules.len(),
+        }
+    }).collect();
+    reply.send(style_sheets).unwrap();
+}
+
+pub fn handle_get_css_coverage(context: &BrowsingContext,
+                               pipeline: PipelineId,
+                               reply: IpcSender<Option<Vec<StyleSheetCoverage>>>) {
+    let (hrefs, sheets): (Vec<_>, Vec<_>) =
+        document_style_sheets(context, pipeline).into_iter().unzip();
+
+    let window = get_browsing_context(context, pipeline).active_window();
+    let (sender, receiver) = channel();
+    window.layout_chan().send(Msg::GetRuleCoverage(sheets, sender)).unwrap();
+
+    let coverage = receiver.recv().unwrap().map(|coverage| {
+        hrefs.into_iter().zip(coverage).map(|(href, coverage)| {
+            StyleSheetCoverage {
+                href: href,
+                usedRuleCount: coverage.used_rules,
+                unusedRuleCount: coverage.unused_rules,
+                unusedRules: coverage.unused.into_iter().map(|rule| {
+                    UnusedRuleInfo {
+                        selectorText: rule.selector_text,
+                        line: rule.line,
+                        column: rule.column,
+                        start: rule.source_range.start,
+                        end: rule.source_range.end,
+                    }
+                }).collect(),
+            }
+        }).collect()
+    });
+    reply.send(coverage).unwrap();
+}
+
+pub fn handle_get_matched_rules(context: &BrowsingContext,
+                                pipeline: PipelineId,
+                                node_id: String,
+                                pseudo: Option<String>,
+                                reply: IpcSender<Option<Vec<MatchedRuleInfo>>>) {
+    let node = find_node_by_unique_id(context, pipeline, node_id);
+    let pseudo = match pseudo.as_ref().map(|pseudo| &**pseudo) {
+        None => None,
+        Some("::before") => Some(PseudoElement::Before),
+        Some("::after") => Some(PseudoElement::After),
+        Some("::first-line") => Some(PseudoElement::FirstLine),
+        Some("::marker") => Some(PseudoElement::Marker),
+        // Other pseudo-elements are not exposed to authors.
+        Some(_) => return reply.send(None).unwrap(),
+    };
+
+    let window = get_browsing_context(context, pipeline).active_window();
+    let (rules, exists) = window.matched_rules_query(node.to_trusted_node_address(), pseudo);
+    if !exists {
+        return reply.send(None).unwrap();
+    }
+    reply.send(Some(rules.into_iter().map(|rule| {
+        MatchedRuleInfo {
+            selectorText: rule.selector_text,
+            specificity: rule.specificity,
+            origin: match rule.origin {
+                Origin::UserAgent => "user-agent",
+                Origin::User => "user",
+                Origin::Author => "author",
+            }.to_owned(),
+            href: rule.stylesheet_url.as_str().to_owned(),
+            line: rule.line,
+            column: rule.column,
+            important: rule.important,
+            declarations: rule.declarations.into_iter().map(|matched| {
+                MatchedDeclarationInfo {
+                    name: matched.declaration.name().to_string(),
+                    value: matched.declaration.value(),
+                    overridden: !matched.wins_cascade,
+                }
+            }).collect(),
+        }
+    }).collect())).unwrap();
+}
+
+pub fn handle_modify_style_rule(context: &BrowsingContext,
+                                pipeline: PipelineId,
+                                style_sheet_index: usize,
+                                line: usize,
+                                column: usize,
+                                modification: StyleRuleModification,
+                                reply: IpcSender<bool>) {
+    let style_sheet = match document_style_sheets(context, pipeline).into_iter()
+                                                                     .nth(style_sheet_index) {
+        Some((_, style_sheet)) => style_sheet,
+        None => return reply.send(false).unwrap(),
+    };
+    let selectors = match modification.selectorText {
+        Some(text) => match parse_author_origin_selector_list_from_str(&text) {
+            Ok(selectors) => Some((selectors, text.trim().to_owned())),
+            Err(()) => return reply.send(false).unwrap(),
+        },
+        None => None,
+    };
+    let new_selectors = selectors.as_ref().map(|&(ref selectors, _)| selectors.clone());
+
+    let window = get_browsing_context(context, pipeline).active_window();
+    let declarations = modification.cssText.map(|text| {
+        parse_style_attribute(&text,
+                              &style_sheet.url,
+                              window.css_error_reporter(),
+                              ParserContextExtraData::default())
+    });
+    let edit = StyleRuleEdit {
+        selectors: selectors,
+        declarations: declarations,
+    };
+    let (sender, receiver) = channel();
+    window.layout_chan().send(Msg::EditStyleRule(style_sheet, line, column, edit, sender)).unwrap();
+    let previous_selectors = match receiver.recv().unwrap() {
+        Some(previous_selectors) => previous_selectors,
+        None => return reply.send(false).unwrap(),
+    };
+
+    // Only the elements that the rule matched or now matches are restyled, along with their
+    // descendants.
+    let selectors: Vec<_> = previous_selectors.iter()
+                                              .chain(new_selectors.iter().flat_map(|s| s.iter()))
+                                              .map(originating_selector)
+                                              .collect();
+    let document = window.Document();
+    for element in document.upcast::<Node>()
+                           .traverse_preorder()
+                           .filter_map(Root::downcast::<Element>) {
+        if matches(&selectors, &element, None) {
+            element.upcast::<Node>().dirty(NodeDamage::NodeStyleDamaged);
+        }
+    }
+    window.reflow(ReflowGoal::ForDisplay, ReflowQueryType::NoQuery, ReflowReason::StyleRuleEdited);
+    reply.send(true).unwrap();
+}
+
+/// Returns a selector that matches the elements that the pseudo-elements the given selector
+/// matches belong to, or the selector itself if it matches elements.
+fn originating_selector(selector: &Selector<ServoSelectorImpl>) -> Selector<ServoSelectorImpl> {
+    Selector {
+        compound_selectors: selector.compound_selectors.clone(),
+        pseudo_element: None,
+        specificity: selector.specificity,
+    }
+}
+
+pub fn handle_get_used_font_faces(context: &BrowsingContext,
+                                  pipeline: PipelineId,
+                                  reply: IpcSender<Vec<UsedFontFaceInfo>>) {
+    let window = get_browsing_context(context, pipeline).active_window();
+    reply.send(window.used_font_faces_query().into_iter().map(|font_face| {
+        UsedFontFaceInfo {
+            name: font_face.name,
+            familyName: font_face.family,
+            url: font_face.url.unwrap_or(String::new()),
+            glyphCount: font_face.glyph_count,
+        }
+    }).collect()).unwrap();
+}
+
+pub fn handle_get_fragment_tree(context: &BrowsingContext,
+                                pipeline: PipelineId,
+                                reply: IpcSender<Option<String>>) {
+    let context = get_browsing_context(context, pipeline);
+    let (sender, receiver) = channel();
+    context.active_window().layout_chan().send(Msg::GetFragmentTree(sender)).unwrap();
+    let fragment_tree = match receiver.recv().unwrap() {
+        Some(fragment_tree) => fragment_tree,
+        None => return reply.send(None).unwrap(),
+    };
+
+    // Layout identifies nodes by the address of their reflector, which is replaced with the unique
+    // id of the node, or null if it is gone.
+    let document = context.active_document();
+    let nodes = document.upcast::<Node>().traverse_preorder();
+    let unique_ids: HashMap<u64, String> = nodes.map(|node| {
+        (node.reflector().get_jsobject().get() as usize as u64, node.unique_id())
+    }).collect();
+    fn replace_nodes(json: &mut Json, unique_ids: &HashMap<u64, String>) {
+        match *json {
+            Json::Object(ref mut object) => {
+                if let Some(node) = object.get_mut("node") {
+                    *node = match node.as_u64().and_then(|address| unique_ids.get(&address)) {
+                        Some(unique_id) => Json::String(unique_id.clone()),
+                        None => Json::Null,
+                    };
+                }
+                for (key, value) in object.iter_mut() {
+                    if *key != "node" {
+                        replace_nodes(value, unique_ids);
+                    }
+                }
+            }
+            Json::Array(ref mut array) => {
+                for value in array {
+                    replace_nodes(value, unique_ids);
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut fragment_tree = Json::from_str(&fragment_tree).unwrap();
+    replace_nodes(&mut fragment_tree, &unique_ids);
+    reply.send(Some(fragment_tree.to_string())).unwrap();
+}
+
+pub fn handle_get_running_animations(context: &BrowsingContext,
+                                     pipeline: PipelineId,
+                                     reply: IpcSender<Vec<AnimationInfo>>) {
+    let context = get_browsing_context(context, pipeline);
+    let window = context.active_window();
+    let (sender, receiver) = channel();
+    window.layout_chan().send(Msg::GetRunningAnimations(sender)).unwrap();
+    let running_animations = receiver.recv().unwrap();
+
+    // Layout identifies nodes by the address of their reflector. Look them up in the document
+    // rather than trusting the addresses, skipping animations of nodes that are gone.
+    let document = context.active_document();
+    let animations = document.upcast::<Node>().traverse_preorder().flat_map(|node| {
+        let address = node.reflector().get_jsobject().get() as usize;
+        running_animations.iter().filter(|animation| {
+            animation.node.0 as usize == address
+        }).map(|animation| {
+            AnimationInfo {
+                node: node.unique_id(),
+                property: animation.property.clone(),
+                duration: animation.duration * 1000.,
+                currentTime: animation.elapsed_time * 1000.,
+                paused: animation.paused,
+            }
+        }).collect::<Vec<_>>()
+    }).collect();
+    reply.send(animations).unwrap();
+}
+
+pub fn handle_control_animation(context: &BrowsingContext,
+                                pipeline: PipelineId,
+                                node_id: String,
+                                property: String,
+                                control: AnimationControl) {
+    let node = find_node_by_unique_id(context, pipeline, node_id);
+    let window = get_browsing_context(context, pipeline).active_window();
+    window.layout_chan()
+          .send(Msg::ControlAnimation(node.to_trusted_node_address(), property, control))
+          .unwrap();
+}
+
+pub fn handle_set_node_picker(context: &BrowsingContext, pipeline: PipelineId, enabled: bool) {
+    let window = get_browsing_context(context, pipeline).active_window();
+    window.constellation_chan().send(ConstellationMsg::SetNodePicker(enabled)).unwrap();
+}
+
+/// Reports the element under the point that was clicked while devtools was picking a node. No
+/// events are dispatched for the click.
+pub fn handle_pick_node(context: &BrowsingContext,
+                        pipeline: PipelineId,
+                        js_runtime: *mut JSRuntime,
+                        client_point: Point2D<f32>) {
+    let context = get_browsing_context(context, pipeline);
+    let window = context.active_window();
+    let devtools_chan = match window.devtools_chan() {
+        Some(devtools_chan) => devtools_chan,
+        None => return,
+    };
+
+    let page_point = Point2D::new(client_point.x + window.PageXOffset() as f32,
+                                  client_point.y + window.PageYOffset() as f32);
+    let element = window.hit_test_query(page_point, false).and_then(|address| {
+        node::from_untrusted_node_address(js_runtime, address)
+            .inclusive_ancestors()
+            .filter_map(Root::downcast::<Element>)
+            .next()
+    });
+    if let Some(element) = element {
+        let info = element.upcast::<Node>().summarize();
+        devtools_chan.send(ScriptToDevtoolsControlMsg::NodePicked(pipeline, info)).unwrap();
+    }
+}
+
+pub fn handle_highlight_node(context: &BrowsingContext,
+                             pipeline: PipelineId,
+                             node_id: Option<String>) {
+    let node = node_id.map(|node_id| find_node_by_unique_id(context, pipeline, node_id));
+    let window = get_browsing_context(context, pipeline).active_window();
+    let node = node.map(|node| node.to_trusted_node_address());
+    window.layout_chan().send(Msg::SetHighlightedNode(node)).unwrap();
+
+    window.force_reflow(ReflowGoal::ForDisplay,
+                        ReflowQueryType::NoQuery,
+                        ReflowReason::HighlightedNodeChanged);
+}
+
+pub fn handle_get_box_model(context: &BrowsingContext,
+                            pipeline: PipelineId,
+                            node_id: String,
+                            reply: IpcSender<Option<BoxModelInfo>>) {
+    let node = find_node_by_unique_id(context, pipeline, node_id);
+    let window = get_browsing_context(context, pipeline).active_window();
+    let box_model = window.box_model_query(node.to_trusted_node_address());
+    let corners = |quad: &BoxQuad| -> Vec<(f32, f32)> {
+        quad.iter().map(|point| (point.x, point.y)).collect()
+    };
+    reply.send(box_model.map(|box_model| {
+        BoxModelInfo {
+            margin: corners(&box_model.margin),
+            border: corners(&box_model.border),
+            padding: corners(&box_model.padding),
+            content: corners(&box_model.content),
+            position: box_model.position.to_css_string(),
+            zIndex: box_model.z_index.to_css_string(),
+        }
+    })).unwrap();
+}
@@ -0,0 +1,424 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use cssparser::Parser;
+use dom::bindings::cell::DOMRefCell;
+use dom::bindings::codegen::Bindings::AnimatableBinding::{Keyframe, KeyframeAnimationOptions};
+use dom::bindings::codegen::Bindings::AnimationBinding;
+use dom::bindings::codegen::Bindings::AnimationBinding::{AnimationMethods, AnimationPlayState};
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::global::GlobalRef;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::js::{JS, Root};
+use dom::bindings::num::Finite;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::str::DOMString;
+use dom::element::Element;
+use dom::node::{Node, NodeDamage, window_from_node};
+use dom::window::Window;
+use layout_interface::{AnimationControl, Msg, NodeAnimation, NodeAnimationSource};
+use std::cell::Cell;
+use std::sync::Arc;
+use style::animation::{self, AnimationTiming, KeyframeEffect};
+use style::parser::ParserContextExtraData;
+use style::properties::longhands::transition_timing_function::computed_value;
+use style::properties::longhands::transition_timing_function::parse_one as parse_timing_function;
+use style::properties::{PropertyDeclaration, parse_one_declaration};
+use time;
+
+/// What runs an animation, which layout controls it through.
+#[derive(JSTraceable, HeapSizeOf)]
+enum AnimationSource {
+    /// An animation that script created, with the number that layout knows it by.
+    Script(u32),
+    /// A transition of the property with the given name.
+    Transition(String),
+}
+
+// https://w3c.github.io/web-animations/#the-animation-interface
+#[dom_struct]
+pub struct Animation {
+    reflector_: Reflector,
+    element: JS<Element>,
+    source: AnimationSource,
+    id: DOMRefCell<DOMString>,
+    /// The time at which this animation started, before its delay, as returned by
+    /// `time::precise_time_s()`.
+    start_time: Cell<f64>,
+    /// The delay and the duration of all iterations, in seconds.
+    delay: f64,
+    active_duration: f64,
+    /// The time at which this animation was paused, if it is paused.
+    paused_at: Cell<Option<f64>>,
+    play_state: Cell<AnimationPlayState>,
+}
+
+impl Animation {
+    fn new_inherited(element: &Element,
+                     source: AnimationSource,
+                     id: DOMString,
+                     start_time: f64,
+                     delay: f64,
+                     active_duration: f64)
+                     -> Animation {
+        Animation {
+            reflector_: Reflector::new(),
+            element: JS::from_ref(element),
+            source: source,
+            id: DOMRefCell::new(id),
+            start_time: Cell::new(start_time),
+            delay: delay,
+            active_duration: active_duration,
+            paused_at: Cell::new(None),
+            play_state: Cell::new(AnimationPlayState::Running),
+        }
+    }
+
+    /// Starts animating `element` through `keyframes` and returns the animation, for
+    /// `Element.animate()`. Only the properties that transitions can animate are animated, and
+    /// each of them must be set at offsets 0 and 1.
+    /// https://w3c.github.io/web-animations/#dom-animatable-animate
+    pub fn animate(element: &Element,
+                   keyframes: Vec<Keyframe>,
+                   options: &KeyframeAnimationOptions)
+                   -> Fallible<Root<Animation>> {
+        let (duration, delay, iterations) = (options.duration / 1000.0,
+                                             *options.delay / 1000.0,
+                                             options.iterations);
+        if duration.is_nan() || duration < 0.0 {
+            return Err(Error::Type("The duration of an animation must not be negative".to_owned()));
+        }
+        if iterations.is_nan() || iterations < 0.0 {
+            return Err(Error::Type("The iterations of an animation must not be negative"
+                                   .to_owned()));
+        }
+        let timing_function = try!(parse_easing(&options.easing));
+
+        let offsets = try!(compute_offsets(&keyframes));
+        let window = window_from_node(element);
+        let mut effect_keyframes = vec![];
+        for (keyframe, offset) in keyframes.iter().zip(offsets) {
+            effect_keyframes.push(animation::Keyframe {
+                offset: offset,
+                timing_function: try!(parse_easing(&keyframe.easing)),
+                declarations: Arc::new(keyframe_declarations(keyframe, window.r())),
+            });
+        }
+        let effect = match KeyframeEffect::new(effect_keyframes) {
+            Ok(effect) => effect,
+            Err(()) => return Err(Error::NotSupported),
+        };
+
+        let now = time::precise_time_s();
+        let timing = AnimationTiming {
+            start_time: now + delay,
+            duration: duration,
+            iterations: iterations,
+            timing_function: timing_function,
+        };
+        let node = element.upcast::<Node>();
+        let document = node.owner_doc();
+        let animation_id = document.next_animation_id();
+        let msg = Msg::AddKeyframesAnimation(node.to_trusted_node_address(),
+                                             animation_id,
+                                             effect,
+                                             timing);
+        window.layout_chan().send(msg).unwrap();
+        node.dirty(NodeDamage::NodeStyleDamaged);
+
+        let active_duration = if duration == 0.0 { 0.0 } else { duration * iterations };
+        let animation = reflect_dom_object(box Animation::new_inherited(element,
+                                                                        AnimationSource::Script(
+                                                                            animation_id),
+                                                                        options.id.clone(),
+                                                                        now,
+                                                                        delay,
+                                                                        active_duration),
+                                           GlobalRef::Window(window.r()),
+                                           AnimationBinding::Wrap);
+        document.add_animation(animation_id, animation.r());
+        Ok(animation)
+    }
+
+    /// Returns the animations running on `element`, for `Element.getAnimations()`. Transitions
+    /// get new animations each time, which control them as devtools do.
+    /// https://w3c.github.io/web-animations/#dom-animatable-getanimations
+    pub fn get_animations(element: &Element) -> Vec<Root<Animation>> {
+        let window = window_from_node(element);
+        let node = element.upcast::<Node>();
+        let document = node.owner_doc();
+        let states = window.animation_state_query(node.to_trusted_node_address());
+        states.iter().filter_map(|state| {
+            match state.source {
+                NodeAnimationSource::Script(id) => document.get_animation(id),
+                NodeAnimationSource::Transition(ref property) => {
+                    Some(Animation::for_transition(element, property.clone(), state, window.r()))
+                }
+            }
+        }).collect()
+    }
+
+    fn for_transition(element: &Element,
+                      property: String,
+                      state: &NodeAnimation,
+                      window: &Window)
+                      -> Root<Animation> {
+        let animation = Animation::new_inherited(element,
+                                                 AnimationSource::Transition(property),
+                                                 DOMString::new(),
+                                                 state.start_time,
+                                                 0.0,
+                                                 state.duration);
+        if state.paused {
+            animation.paused_at.set(Some(state.start_time + state.progress * state.duration));
+            animation.play_state.set(AnimationPlayState::Paused);
+        }
+        reflect_dom_object(box animation, GlobalRef::Window(window), AnimationBinding::Wrap)
+    }
+
+    /// Marks this animation as finished, once layout has run it to its end and dropped it.
+    pub fn finish(&self) {
+        self.play_state.set(AnimationPlayState::Finished);
+        self.paused_at.set(None);
+        self.element.upcast::<Node>().dirty(NodeDamage::NodeStyleDamaged);
+    }
+
+    fn control(&self, control: AnimationControl) {
+        let window = window_from_node(&*self.element);
+        let msg = match self.source {
+            AnimationSource::Script(animation_id) => {
+                Msg::ControlKeyframesAnimation(animation_id, control)
+            }
+            AnimationSource::Transition(ref property) => {
+                let node = self.element.upcast::<Node>().to_trusted_node_address();
+                Msg::ControlAnimation(node, property.clone(), control)
+            }
+        };
+        window.layout_chan().send(msg).unwrap();
+        self.element.upcast::<Node>().dirty(NodeDamage::NodeStyleDamaged);
+    }
+}
+
+/// Parses the easing of an animation or of one of its keyframes, which is a timing function
+/// like those of transitions.
+/// https://w3c.github.io/web-animations/#dom-animationeffecttimingreadonly-easing
+fn parse_easing(easing: &str) -> Fallible<computed_value::TransitionTimingFunction> {
+    let mut parser = Parser::new(easing);
+    match parse_timing_function(&mut parser) {
+        Ok(timing_function) if parser.is_exhausted() => Ok(timing_function),
+        _ => Err(Error::Type(format!("Invalid easing: {}", easing))),
+    }
+}
+
+/// Fills in the offsets that `keyframes` leave out, by spacing them evenly between the offsets
+/// around them, with the first and last keyframes at 0 and 1 by default.
+/// https://w3c.github.io/web-animations/#compute-missing-keyframe-offsets
+fn compute_offsets(keyframes: &[Keyframe]) -> Fallible<Vec<f64>> {
+    let mut offsets: Vec<Option<f64>> = keyframes.iter().map(|keyframe| {
+        keyframe.offset.map(|offset| *offset)
+    }).collect();
+    let mut previous = 0.0;
+    for offset in offsets.iter().filter_map(|offset| *offset) {
+        if offset < previous || offset > 1.0 {
+            return Err(Error::Type("Keyframe offsets must be between 0 and 1, in order"
+                                   .to_owned()));
+        }
+        previous = offset;
+    }
+    if offsets.len() > 1 && offsets[0].is_none() {
+        offsets[0] = Some(0.0);
+    }
+    if let Some(last) = offsets.last_mut() {
+        if last.is_none() {
+            *last = Some(1.0);
+        }
+    }
+    let mut start = 0;
+    for end in 1..offsets.len() {
+        if let Some(end_offset) = offsets[end] {
+            let start_offset = offsets[start].unwrap();
+            for index in start + 1..end {
+                let fraction = (index - start) as f64 / (end - start) as f64;
+                offsets[index] = Some(start_offset + (end_offset - start_offset) * fraction);
+            }
+            start = end;
+        }
+    }
+    Ok(offsets.into_iter().map(Option::unwrap).collect())
+}
+
+/// Parses the values that `keyframe` gives properties, ignoring those that are invalid.
+fn keyframe_declarations(keyframe: &Keyframe, window: &Window) -> Vec<PropertyDeclaration> {
+    let members = [
+        (&keyframe.backgroundColor, "background-color"),
+        (&keyframe.backgroundPosition, "background-position"),
+        (&keyframe.borderBottomColor, "border-bottom-color"),
+        (&keyframe.borderBottomLeftRadius, "border-bottom-left-radius"),
+        (&keyframe.borderBottomRightRadius, "border-bottom-right-radius"),
+        (&keyframe.borderBottomWidth, "border-bottom-width"),
+        (&keyframe.borderLeftColor, "border-left-color"),
+        (&keyframe.borderLeftWidth, "border-left-width"),
+        (&keyframe.borderRightColor, "border-right-color"),
+        (&keyframe.borderRightWidth, "border-right-width"),
+        (&keyframe.borderSpacing, "border-spacing"),
+        (&keyframe.borderTopColor, "border-top-color"),
+        (&keyframe.borderTopLeftRadius, "border-top-left-radius"),
+        (&keyframe.borderTopRightRadius, "border-top-right-radius"),
+        (&keyframe.borderTopWidth, "border-top-width"),
+        (&keyframe.bottom, "bottom"),
+        (&keyframe.boxShadow, "box-shadow"),
+        (&keyframe.color, "color"),
+        (&keyframe.clip, "clip"),
+        (&keyframe.fontSize, "font-size"),
+        (&keyframe.fontWeight, "font-weight"),
+        (&keyframe.height, "height"),
+        (&keyframe.left, "left"),
+        (&keyframe.letterSpacing, "letter-spacing"),
+        (&keyframe.lineHeight, "line-height"),
+        (&keyframe.marginBottom, "margin-bottom"),
+        (&keyframe.marginLeft, "margin-left"),
+        (&keyframe.marginRight, "margin-right"),
+        (&keyframe.marginTop, "margin-top"),
+        (&keyframe.maxHeight, "max-height"),
+        (&keyframe.maxWidth, "max-width"),
+        (&keyframe.minHeight, "min-height"),
+        (&keyframe.minWidth, "min-width"),
+        (&keyframe.opacity, "opacity"),
+        (&keyframe.order, "order"),
+        (&keyframe.outlineColor, "outline-color"),
+        (&keyframe.outlineOffset, "outline-offset"),
+        (&keyframe.outlineWidth, "outline-width"),
+        (&keyframe.paddingBottom, "padding-bottom"),
+        (&keyframe.paddingLeft, "padding-left"),
+        (&keyframe.paddingRight, "padding-right"),
+        (&keyframe.paddingTop, "padding-top"),
+        (&keyframe.perspective, "perspective"),
+        (&keyframe.perspectiveOrigin, "perspective-origin"),
+        (&keyframe.right, "right"),
+        (&keyframe.textDecorationColor, "text-decoration-color"),
+        (&keyframe.textIndent, "text-indent"),
+        (&keyframe.textShadow, "text-shadow"),
+        (&keyframe.top, "top"),
+        (&keyframe.transform, "transform"),
+        (&keyframe.transformOrigin, "transform-origin"),
+        (&keyframe.verticalAlign, "vertical-align"),
+        (&keyframe.visibility, "visibility"),
+        (&keyframe.width, "width"),
+        (&keyframe.wordSpacing, "word-spacing"),
+        (&keyframe.zIndex, "z-index"),
+    ];
+    let mut declarations = vec![];
+    for &(value, name) in members.iter() {
+        let value = match *value {
+            Some(ref value) => value,
+            None => continue,
+        };
+        let parsed = parse_one_declaration(name,
+                                           value,
+                                           &window.get_url(),
+                                           window.css_error_reporter(),
+                                           ParserContextExtraData::default());
+        if let Ok(parsed) = parsed {
+            declarations.extend(parsed)
+        }
+    }
+    declarations
+}
+
+impl AnimationMethods for Animation {
+    // https://w3c.github.io/web-animations/#dom-animation-id
+    fn Id(&self) -> DOMString {
+        self.id.borrow().clone()
+    }
+
+    // https://w3c.github.io/web-animations/#dom-animation-id
+    fn SetId(&self, id: DOMString) {
+        *self.id.borrow_mut() = id;
+    }
+
+    // https://w3c.github.io/web-animations/#dom-animation-currenttime
+    fn GetCurrentTime(&self) -> Option<Finite<f64>> {
+        let current_time = match self.play_state.get() {
+            AnimationPlayState::Idle => return None,
+            AnimationPlayState::Finished => self.delay + self.active_duration,
+            AnimationPlayState::Running | AnimationPlayState::Paused => {
+                let now = self.paused_at.get().unwrap_or_else(time::precise_time_s);
+                now - self.start_time.get()
+            }
+        };
+        Finite::new(current_time * 1000.0)
+    }
+
+    // https://w3c.github.io/web-animations/#dom-animation-currenttime
+    fn SetCurrentTime(&self, current_time: Option<Finite<f64>>) -> ErrorResult {
+        let current_time = match current_time {
+            Some(current_time) => *current_time / 1000.0,
+            None if self.play_state.get() == AnimationPlayState::Idle => return Ok(()),
+            None => return Err(Error::Type("The current time cannot be unset".to_owned())),
+        };
+        // Layout has dropped idle and finished animations, so they cannot be seeked back.
+        match self.play_state.get() {
+            AnimationPlayState::Running | AnimationPlayState::Paused => {}
+            AnimationPlayState::Idle | AnimationPlayState::Finished => return Ok(()),
+        }
+        let now = self.paused_at.get().unwrap_or_else(time::precise_time_s);
+        self.start_time.set(now - current_time);
+        self.control(AnimationControl::Seek(current_time - self.delay));
+        Ok(())
+    }
+
+    // https://w3c.github.io/web-animations/#dom-animation-playstate
+    fn PlayState(&self) -> AnimationPlayState {
+        self.play_state.get()
+    }
+
+    // https://w3c.github.io/web-animations/#dom-animation-cancel
+    fn Cancel(&self) {
+        match self.play_state.get() {
+            AnimationPlayState::Idle => return,
+            AnimationPlayState::Finished => {}
+            AnimationPlayState::Running | AnimationPlayState::Paused => {
+                match self.source {
+                    AnimationSource::Script(animation_id) => {
+                        let window = window_from_node(&*self.element);
+                        let msg = Msg::CancelKeyframesAnimation(animation_id);
+                        window.layout_chan().send(msg).unwrap();
+                        self.element.upcast::<Node>().owner_doc().remove_animation(animation_id);
+                    }
+                    // Layout cannot drop transitions, so they are run to their end instead.
+                    AnimationSource::Transition(_) => {
+                        self.control(AnimationControl::Play);
+                        self.control(AnimationControl::Seek(self.active_duration));
+                    }
+                }
+            }
+        }
+        self.play_state.set(AnimationPlayState::Idle);
+        self.paused_at.set(None);
+        self.element.upcast::<Node>().dirty(NodeDamage::NodeStyleDamaged);
+    }
+
+    // https://w3c.github.io/web-animations/#dom-animation-play
+    fn Play(&self) {
+        if self.play_state.get() != AnimationPlayState::Paused {
+            return
+        }
+        if let Some(paused_at) = self.paused_at.get() {
+            self.start_time.set(self.start_time.get() + time::precise_time_s() - paused_at);
+        }
+        self.paused_at.set(None);
+        self.play_state.set(AnimationPlayState::Running);
+        self.control(AnimationControl::Play);
+    }
+
+    // https://w3c.github.io/web-animations/#dom-animation-pause
+    fn Pause(&self) {
+        if self.play_state.get() != AnimationPlayState::Running {
+            return
+        }
+        self.paused_at.set(Some(time::precise_time_s()));
+        self.play_state.set(AnimationPlayState::Paused);
+        self.control(AnimationControl::Pause);
+    }
+}
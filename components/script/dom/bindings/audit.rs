@@ -0,0 +1,200 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Runtime checks of the tracing of DOM objects, enabled in debug builds by
+//! `-Z audit-dom-tracing`.
+//!
+//! The `unrooted_must_root` lint catches most DOM objects that are held
+//! without being traced, but not all of them, such as those behind raw
+//! pointers or in `unsafe` code. While auditing:
+//!
+//! * the DOM objects whose reflectors the GC finalizes are not freed, but
+//!   remembered, and using one of them through a `JS<T>` or a new `Root<T>`
+//!   panics, as whatever held it was not traced;
+//! * when a pipeline exits, a GC runs that records the edges to DOM objects
+//!   traced from Rust, and the DOM objects of the pipeline that it does not
+//!   collect are reported with the edges that lead to them.
+
+use dom::bindings::conversions::{get_dom_class, private_from_object};
+use js::jsapi::{JSObject, JSRuntime, JS_GC, JS_GetClass};
+use libc;
+use msg::constellation_msg::PipelineId;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::mem;
+use util::opts;
+
+/// Whether auditing is enabled.
+pub fn enabled() -> bool {
+    cfg!(debug_assertions) && opts::get().audit_dom_tracing
+}
+
+/// A DOM object whose reflector has not been finalized.
+struct LiveObject {
+    /// The name of the class of its reflector.
+    name: String,
+    /// The pipeline of its global.
+    pipeline: PipelineId,
+}
+
+/// What traces an edge.
+#[derive(Clone, Copy)]
+pub enum Owner {
+    /// A set of roots, such as the stack roots.
+    Roots(&'static str),
+    /// The DOM object at this address.
+    Object(*const libc::c_void),
+}
+
+/// An edge to a DOM object, traced from Rust.
+struct Edge {
+    from: Owner,
+    description: String,
+    to: *const libc::c_void,
+}
+
+#[derive(Default)]
+struct Audit {
+    live: HashMap<*const libc::c_void, LiveObject>,
+    /// The DOM objects kept after their reflector was finalized, with their names.
+    finalized: HashMap<*const libc::c_void, String>,
+    /// What is being traced.
+    owner: Option<Owner>,
+    /// The edges traced so far, during a GC that records them.
+    edges: Option<Vec<Edge>>,
+}
+
+thread_local!(static AUDIT: RefCell<Audit> = RefCell::new(Audit::default()));
+
+/// Register `object`, the DOM object reflected by `reflector`, which was
+/// created in the global of `pipeline`.
+#[allow(unsafe_code)]
+pub fn register(object: *const libc::c_void, reflector: *mut JSObject, pipeline: PipelineId) {
+    if !enabled() {
+        return;
+    }
+    let name = unsafe { CStr::from_ptr((*JS_GetClass(reflector)).name) };
+    let live = LiveObject {
+        name: name.to_string_lossy().into_owned(),
+        pipeline: pipeline,
+    };
+    AUDIT.with(|audit| audit.borrow_mut().live.insert(object, live));
+}
+
+/// Called when the GC finalizes the reflector of `object`. Returns whether
+/// the object should be kept rather than freed, so that later uses of it are
+/// caught.
+pub fn keep_finalized(object: *const libc::c_void) -> bool {
+    if !enabled() {
+        return false;
+    }
+    AUDIT.with(|audit| {
+        let mut audit = audit.borrow_mut();
+        let name = audit.live.remove(&object).map_or_else(|| "DOM object".to_owned(), |live| {
+            live.name
+        });
+        audit.finalized.insert(object, name);
+    });
+    true
+}
+
+/// Panic if the reflector of `object` has been finalized.
+pub fn check_alive(object: *const libc::c_void) {
+    if !enabled() {
+        return;
+    }
+    AUDIT.with(|audit| {
+        if let Some(name) = audit.borrow().finalized.get(&object) {
+            panic!("Use of the {} at {:p} after the GC finalized its reflector: something that \
+                    holds it is not traced", name, object);
+        }
+    });
+}
+
+/// Makes the edges traced while it is alive come from the given owner.
+pub struct TracingOwner(Option<Owner>);
+
+impl TracingOwner {
+    pub fn new(owner: Owner) -> TracingOwner {
+        if !enabled() {
+            return TracingOwner(None);
+        }
+        TracingOwner(AUDIT.with(|audit| {
+            mem::replace(&mut audit.borrow_mut().owner, Some(owner))
+        }))
+    }
+}
+
+impl Drop for TracingOwner {
+    fn drop(&mut self) {
+        if enabled() {
+            AUDIT.with(|audit| audit.borrow_mut().owner = self.0.take());
+        }
+    }
+}
+
+/// Record that `target` is being traced, if it is a DOM object and the
+/// current GC records edges.
+#[allow(unsafe_code)]
+pub fn record_edge(description: &str, target: *mut JSObject) {
+    if !enabled() || target.is_null() {
+        return;
+    }
+    AUDIT.with(|audit| {
+        let mut audit = audit.borrow_mut();
+        let from = match (audit.owner, audit.edges.is_some()) {
+            (Some(owner), true) => owner,
+            _ => return,
+        };
+        let to = unsafe {
+            if get_dom_class(target).is_err() {
+                return;
+            }
+            private_from_object(target)
+        };
+        audit.edges.as_mut().unwrap().push(Edge {
+            from: from,
+            description: description.to_owned(),
+            to: to,
+        });
+    });
+}
+
+/// Run a GC, and report the DOM objects of `pipelines` that it does not
+/// collect, with the edges traced from Rust that lead to them. The objects
+/// without such edges are only held from JS.
+#[allow(unsafe_code)]
+pub fn report_leaks(rt: *mut JSRuntime, pipelines: &[PipelineId]) {
+    if !enabled() {
+        return;
+    }
+    AUDIT.with(|audit| audit.borrow_mut().edges = Some(vec![]));
+    unsafe {
+        JS_GC(rt);
+    }
+    AUDIT.with(|audit| {
+        let mut audit = audit.borrow_mut();
+        let edges = audit.edges.take().unwrap();
+        for (object, live) in audit.live.iter().filter(|&(_, live)| {
+            pipelines.contains(&live.pipeline)
+        }) {
+            println!("Leaked {} at {:p} of pipeline {:?}", live.name, *object, live.pipeline);
+            let mut edges = edges.iter().filter(|edge| edge.to == *object).peekable();
+            if edges.peek().is_none() {
+                println!("    held from JS");
+            }
+            for edge in edges {
+                let from = match edge.from {
+                    Owner::Roots(roots) => roots.to_owned(),
+                    Owner::Object(from) => {
+                        let name = audit.live.get(&from).map_or("DOM object", |live| &*live.name);
+                        format!("{} at {:p}", name, from)
+                    },
+                };
+                println!("    held by {} ({})", from, edge.description);
+            }
+        }
+    });
+}
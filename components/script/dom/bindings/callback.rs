@@ -4,15 +4,14 @@
 
 //! Base classes to work with IDL callbacks.
 
-use dom::bindings::error::{Error, Fallible};
+use dom::bindings::error::{Error, Fallible, report_pending_exception};
 use dom::bindings::global::global_root_from_object;
 use dom::bindings::reflector::Reflectable;
 use js::jsapi::GetGlobalForObjectCrossCompartment;
-use js::jsapi::JSAutoCompartment;
 use js::jsapi::{Heap, MutableHandleObject, RootedObject, RootedValue};
 use js::jsapi::{IsCallable, JSContext, JSObject, JS_WrapObject};
 use js::jsapi::{JSCompartment, JS_EnterCompartment, JS_LeaveCompartment};
-use js::jsapi::{JS_GetProperty, JS_IsExceptionPending, JS_ReportPendingException};
+use js::jsapi::{JS_GetProperty, JS_IsExceptionPending};
 use js::jsval::{JSVal, UndefinedValue};
 use std::default::Default;
 use std::ffi::CString;
@@ -194,10 +193,7 @@ impl Drop for CallSetup {
         let need_to_deal_with_exception = self.handling == ExceptionHandling::Report &&
                                           unsafe { JS_IsExceptionPending(self.cx) };
         if need_to_deal_with_exception {
-            unsafe {
-                let _ac = JSAutoCompartment::new(self.cx, self.exception_compartment.ptr);
-                JS_ReportPendingException(self.cx);
-            }
+            report_pending_exception(self.cx, self.exception_compartment.ptr);
         }
     }
 }
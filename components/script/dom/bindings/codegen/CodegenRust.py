@@ -757,6 +757,17 @@ def getJSToNativeConversionInfo(type, descriptorProvider, failureCode=None,
 
             return handleOptional(template, declType, handleDefaultNull("None"))
 
+        if type.isPromise():
+            assert not type.nullable()
+            # Any value is converted to a promise with %Promise.resolve%, see
+            # https://heycam.github.io/webidl/#es-promise
+            templateBody = ("match Promise::Resolve(cx, ${val}) {\n"
+                            "    Ok(value) => value,\n"
+                            "    Err(_) => { %s },\n"
+                            "}" % exceptionCode)
+            declType = descriptor.argumentType if isArgument else descriptor.returnType
+            return handleOptional(templateBody, CGGeneric(declType), handleDefaultNull("None"))
+
         conversionFunction = "root_from_handlevalue"
         descriptorType = descriptor.returnType
         if isMember == "Variadic":
@@ -1323,7 +1334,7 @@ def getRetvalDeclarationForType(returnType, descriptorProvider):
                     returnType)
 
 
-def MemberCondition(pref, func):
+def MemberCondition(pref, func, trial):
     """
     A string representing the condition for a member to actually be exposed.
     Any of the arguments can be None. If not None, they should have the
@@ -1331,14 +1342,18 @@ def MemberCondition(pref, func):
 
     pref: The name of the preference.
     func: The name of the function.
+    trial: The name of the origin trial feature.
     """
     assert pref is None or isinstance(pref, str)
     assert func is None or isinstance(func, str)
-    assert func is None or pref is None
+    assert trial is None or isinstance(trial, str)
+    assert len([c for c in (pref, func, trial) if c]) <= 1
     if pref:
         return 'Condition::Pref("%s")' % pref
     if func:
         return 'Condition::Func(%s)' % func
+    if trial:
+        return 'Condition::OriginTrial("%s")' % trial
     return "Condition::Satisfied"
 
 
@@ -1381,7 +1396,9 @@ class PropertyDefiner:
             PropertyDefiner.getStringAttr(interfaceMember,
                                           "Pref"),
             PropertyDefiner.getStringAttr(interfaceMember,
-                                          "Func"))
+                                          "Func"),
+            PropertyDefiner.getStringAttr(interfaceMember,
+                                          "OriginTrial"))
 
     def generateGuardedArray(self, array, name, specTemplate, specTerminator,
                              specType, getCondition, getDataTuple):
@@ -1467,8 +1484,39 @@ class MethodDefiner(PropertyDefiner):
                          "condition": PropertyDefiner.getControllingCondition(m, descriptor)}
                         for m in methods]
 
-        # FIXME Check for an existing iterator on the interface first.
-        if any(m.isGetter() and m.isIndexed() for m in methods):
+        # Static operations that return promises are called through the
+        # wrapper that rejects the promise with their exceptions.
+        for (i, m) in enumerate(methods):
+            if m.isStatic() and m.returnsPromise():
+                self.regular[i]["nativeName"] = CGMethodPromiseWrapper.makeName(m.identifier.name)
+
+        # The aliases of a method, such as the @@iterator alias of the entries
+        # method of maplike and pair iterable declarations, share its JSJitInfo.
+        for m in methods:
+            for alias in m.aliases:
+                self.regular.append({"name": alias,
+                                     "nativeName": m.identifier.name,
+                                     "length": methodLength(m),
+                                     "condition": PropertyDefiner.getControllingCondition(m, descriptor)})
+
+        if (not static and not unforgeable and descriptor.interface.isIterable() and
+                descriptor.interface.maplikeOrSetlikeOrIterable.isValueIterator()):
+            # Value iterables are indexed, so they use the methods of %ArrayPrototype%.
+            if not any(m.isGetter() and m.isIndexed() for m in methods):
+                raise TypeError("Value iterable %s needs an indexed getter" %
+                                descriptor.interface.identifier.name)
+            for (name, selfHostedName, length) in [("keys", "ArrayKeys", 0),
+                                                   ("values", "ArrayValues", 0),
+                                                   ("entries", "ArrayEntries", 0),
+                                                   ("forEach", "ArrayForEach", 1)]:
+                self.regular.append({"name": name,
+                                     "methodInfo": False,
+                                     "selfHostedName": selfHostedName,
+                                     "length": length,
+                                     "condition": "Condition::Satisfied"})
+
+        if (any(m.isGetter() and m.isIndexed() for m in methods) and
+                not any(method["name"] == "@@iterator" for method in self.regular)):
             self.regular.append({"name": '@@iterator',
                                  "methodInfo": False,
                                  "selfHostedName": "ArrayValues",
@@ -1756,7 +1804,15 @@ class CGImports(CGWrapper):
         for c in callbacks:
             types += relatedTypesForSignatures(c)
 
-        imports += ['dom::types::%s' % getIdentifier(t).name for t in types if isImportable(t)]
+        for t in types:
+            if not isImportable(t):
+                continue
+            iface = t.inner if t.isType() else t
+            if iface.isIteratorInterface():
+                imports += ['dom::bindings::iterable::IterableIterator',
+                            'dom::types::%s' % iface.iterableInterface.identifier.name]
+            else:
+                imports.append('dom::types::%s' % getIdentifier(t).name)
 
         statements = []
         if len(ignored_warnings) > 0:
@@ -1813,6 +1869,11 @@ def DOMClassTypeId(desc):
     return "::dom::bindings::codegen::InheritTypes::TopTypeId::%s%s" % (protochain[0], inner)
 
 
+def isMaplikeOrSetlike(interface):
+    declaration = interface.maplikeOrSetlikeOrIterable
+    return declaration is not None and not declaration.isIterable()
+
+
 def DOMClass(descriptor):
         protoList = ['PrototypeList::ID::' + proto for proto in descriptor.prototypeChain]
         # Pad out the list to the right length with ID::Last so we
@@ -1821,7 +1882,7 @@ def DOMClass(descriptor):
         # padding.
         protoList.extend(['PrototypeList::ID::Last'] * (descriptor.config.maxProtoChainLength - len(protoList)))
         prototypeChainString = ', '.join(protoList)
-        heapSizeOf = 'heap_size_of_raw_self_and_children::<%s>' % descriptor.interface.identifier.name
+        heapSizeOf = 'heap_size_of_raw_self_and_children::<%s>' % descriptor.concreteType
         return """\
 DOMClass {
     interface_chain: [ %s ],
@@ -1851,6 +1912,7 @@ class CGDOMJSClass(CGThing):
         }
         if self.descriptor.isGlobal():
             assert not self.descriptor.weakReferenceable
+            assert not isMaplikeOrSetlike(self.descriptor.interface)
             args["enumerateHook"] = "Some(enumerate_global)"
             args["flags"] = "JSCLASS_IS_GLOBAL | JSCLASS_DOM_GLOBAL"
             args["slots"] = "JSCLASS_GLOBAL_SLOT_COUNT + 1"
@@ -1858,6 +1920,8 @@ class CGDOMJSClass(CGThing):
             args["traceHook"] = "js::jsapi::JS_GlobalObjectTraceHook"
         elif self.descriptor.weakReferenceable:
             args["slots"] = "2"
+        elif isMaplikeOrSetlike(self.descriptor.interface):
+            args["slots"] = "2"
         return """\
 static Class: DOMJSClass = DOMJSClass {
     base: js::jsapi::Class {
@@ -1979,6 +2043,21 @@ static InterfaceObjectClass: NonCallbackInterfaceObjectClass =
 """ % args
 
 
+class CGNamespaceObjectClass(CGThing):
+    def __init__(self, descriptor):
+        assert descriptor.interface.isNamespace()
+        CGThing.__init__(self)
+        self.descriptor = descriptor
+
+    def define(self):
+        name = str_to_const_array(self.descriptor.interface.identifier.name)
+        return """\
+static NAMESPACE_OBJECT_CLASS: NamespaceObjectClass = unsafe {
+    NamespaceObjectClass::new(%s)
+};
+""" % name
+
+
 class CGList(CGThing):
     """
     Generate code for a list of GCThings.  Just concatenates them together, with
@@ -2237,6 +2316,10 @@ class CGConstructorEnabled(CGAbstractMethod):
         if func:
             assert isinstance(func, list) and len(func) == 1
             conditions.append("%s(aCx, aObj)" % func[0])
+        trial = iface.getExtendedAttribute("OriginTrial")
+        if trial:
+            assert isinstance(trial, list) and len(trial) == 1
+            conditions.append('origin_trials::is_enabled(aCx, aObj, "%s")' % trial[0])
         # We should really have some conditions
         assert len(body) or len(conditions)
 
@@ -2413,11 +2496,11 @@ class CGIDLInterface(CGThing):
 
     def define(self):
         interface = self.descriptor.interface
-        name = self.descriptor.name
+        name = self.descriptor.concreteType
         if (interface.getUserData("hasConcreteDescendant", False) or
                 interface.getUserData("hasProxyDescendant", False)):
             depth = self.descriptor.prototypeDepth
-            check = "class.interface_chain[%s] == PrototypeList::ID::%s" % (depth, name)
+            check = "class.interface_chain[%s] == PrototypeList::ID::%s" % (depth, self.descriptor.name)
         elif self.descriptor.proxy:
             check = "class as *const _ == &Class as *const _"
         else:
@@ -2516,6 +2599,25 @@ assert!((*cache)[PrototypeList::Constructor::%(id)s as usize].is_null());
                               interface.ptr);
 """ % {"id": name, "name": str_to_const_array(name)})
 
+        if self.descriptor.interface.isNamespace():
+            methods = self.properties.static_methods
+            attrs = self.properties.static_attrs
+            return CGGeneric("""\
+let proto = RootedObject::new(cx, JS_GetObjectPrototype(cx, global));
+assert!(!proto.ptr.is_null());
+let mut namespace = RootedObject::new(cx, ptr::null_mut());
+create_namespace_object(cx, global, proto.handle(), &NAMESPACE_OBJECT_CLASS,
+                        %(methods)s, %(attrs)s, %(name)s, namespace.handle_mut());
+assert!(!namespace.ptr.is_null());
+assert!((*cache)[PrototypeList::Constructor::%(id)s as usize].is_null());
+(*cache)[PrototypeList::Constructor::%(id)s as usize] = namespace.ptr;
+<*mut JSObject>::post_barrier((*cache).as_mut_ptr().offset(PrototypeList::Constructor::%(id)s as isize),
+                              ptr::null_mut(),
+                              namespace.ptr);
+""" % {"id": name, "name": str_to_const_array(name),
+       "methods": methods.variableName() if methods.length() else "&[]",
+       "attrs": attrs.variableName() if attrs.length() else "&[]"})
+
         if len(self.descriptor.prototypeChain) == 1:
             if self.descriptor.interface.getExtendedAttribute("ExceptionClass"):
                 getPrototypeProto = "prototype_proto.ptr = JS_GetErrorPrototype(cx)"
@@ -2796,7 +2898,7 @@ class CGDefineDOMInterfaceMethod(CGAbstractMethod):
                 return ""
             else:
                 return "if !ConstructorEnabled(cx, global) { return; }"
-        if self.descriptor.interface.isCallback():
+        if self.descriptor.interface.isCallback() or self.descriptor.interface.isNamespace():
             function = "GetConstructorObject"
         else:
             function = "GetProtoObject"
@@ -2866,20 +2968,97 @@ class CGCallGenerator(CGThing):
         ]))
 
         if isFallible:
-            if static:
-                glob = ""
+            self.cgRoot.append(CGUnwrapResult(errorResult, static))
+
+    def define(self):
+        return self.cgRoot.define()
+
+
+class CGUnwrapResult(CGGeneric):
+    """
+    Throw the error of a fallible call, whose result is in a variable named
+    |result|, or unwrap its value.
+    """
+    def __init__(self, errorResult, static):
+        if static:
+            glob = ""
+        else:
+            glob = "        let global = global_root_from_reflector(this);\n"
+
+        CGGeneric.__init__(self,
+                           "let result = match result {\n"
+                           "    Ok(result) => result,\n"
+                           "    Err(e) => {\n"
+                           "%s"
+                           "        throw_dom_exception(cx, global.r(), e);\n"
+                           "        return%s;\n"
+                           "    },\n"
+                           "};" % (glob, errorResult))
+
+
+class CGMaplikeOrSetlikeOrIterableCallGenerator(CGThing):
+    """
+    Generate the implementation of a member generated by a maplike, setlike
+    or pair iterable declaration, which is not implemented by the DOM object.
+
+    Maplike and setlike members use the backing Map or Set object of the
+    reflector, through the functions of dom::bindings::like. Pair iterable
+    members use the Iterable trait of the DOM object.
+    """
+    def __init__(self, descriptor, idlNode, arguments, returnType):
+        CGThing.__init__(self)
+
+        declaration = descriptor.interface.maplikeOrSetlikeOrIterable
+        name = idlNode.identifier.name
+        argNames = ["arg%d" % i for i in range(len(arguments))]
+
+        self.cgRoot = CGList([], "\n")
+        if declaration.isIterable():
+            assert declaration.isPairIterator()
+            if name == "forEach":
+                call = "iterable::for_each(cx, this, _obj, %s, %s)" % tuple(argNames)
+            else:
+                iteratorType = {
+                    "keys": "IteratorType::Keys",
+                    "values": "IteratorType::Values",
+                    "entries": "IteratorType::Entries",
+                }[name]
+                call = "Ok(IterableIterator::new(this, %s, %s::Wrap))" % (
+                    iteratorType, toBindingNamespace(descriptor.name + "Iterator"))
+        else:
+            like = "Like::Map" if declaration.isMaplike() else "Like::Set"
+            # The backing object holds JS values, so the converted arguments are
+            # converted back. This still checks that they are of the right type.
+            if name != "forEach":
+                for argName in argNames:
+                    self.cgRoot.append(CGGeneric(
+                        "let mut %(name)s_jsval = RootedValue::new(cx, UndefinedValue());\n"
+                        "%(name)s.to_jsval(cx, %(name)s_jsval.handle_mut());" % {"name": argName}))
+            args = ["%s_jsval.handle()" % argName for argName in argNames]
+            if name == "size":
+                call = "like::size(cx, _obj, %s)" % like
+            elif name in ["has", "delete", "clear"]:
+                call = "like::%s(cx, _obj, %s)" % (name, ", ".join([like] + args))
+            elif name in ["get", "set", "add"]:
+                call = "like::%s(cx, _obj, %s)" % (name, ", ".join(args))
+            elif name == "forEach":
+                call = "like::for_each(cx, _obj, %s, %s, %s)" % ((like,) + tuple(argNames))
             else:
-                glob = "        let global = global_root_from_reflector(this);\n"
-
-            self.cgRoot.append(CGGeneric(
-                "let result = match result {\n"
-                "    Ok(result) => result,\n"
-                "    Err(e) => {\n"
-                "%s"
-                "        throw_dom_exception(cx, global.r(), e);\n"
-                "        return%s;\n"
-                "    },\n"
-                "};" % (glob, errorResult)))
+                iteratorType = {
+                    "keys": "LikeIterator::Keys",
+                    "values": "LikeIterator::Values",
+                    "entries": "LikeIterator::Entries",
+                }[name]
+                call = "like::iterator(cx, _obj, %s, %s)" % (like, iteratorType)
+
+        result = getRetvalDeclarationForType(returnType, descriptor)
+        self.cgRoot.append(CGList([
+            CGGeneric("let result: Result<"),
+            result,
+            CGGeneric(", Error> = %s;" % call),
+        ]))
+        # The size attribute is infallible, but is implemented by fallible JSAPI calls too.
+        self.cgRoot.append(CGUnwrapResult(" false", False))
 
     def define(self):
         return self.cgRoot.define()
@@ -2931,11 +3110,16 @@ class CGPerSignatureCall(CGThing):
         if self.isFallible():
             errorResult = " false"
 
-        cgThings.append(CGCallGenerator(
-            errorResult,
-            self.getArguments(), self.argsPre, returnType,
-            self.extendedAttributes, descriptor, nativeMethodName,
-            static))
+        if ((idlNode.isMethod() and idlNode.isMaplikeOrSetlikeOrIterableMethod()) or
+                (idlNode.isAttr() and idlNode.isMaplikeOrSetlikeAttr())):
+            cgThings.append(CGMaplikeOrSetlikeOrIterableCallGenerator(
+                descriptor, idlNode, self.arguments, returnType))
+        else:
+            cgThings.append(CGCallGenerator(
+                errorResult,
+                self.getArguments(), self.argsPre, returnType,
+                self.extendedAttributes, descriptor, nativeMethodName,
+                static))
         self.cgRoot = CGList(cgThings, "\n")
 
     def getArgs(self):
@@ -2947,7 +3131,12 @@ class CGPerSignatureCall(CGThing):
     def getArguments(self):
         def process(arg, i):
             argVal = "arg" + str(i)
-            if arg.type.isGeckoInterface() and not arg.type.unroll().inner.isCallback():
+            if arg.type.isPromise():
+                if arg.optional and not arg.defaultValue:
+                    argVal += ".as_ref().map(|promise| &**promise)"
+                else:
+                    argVal = "&" + argVal
+            elif arg.type.isGeckoInterface() and not arg.type.unroll().inner.isCallback():
                 argVal += ".r()"
             return argVal
         return [(a, process(a, i)) for (i, a) in enumerate(self.arguments)]
@@ -3132,6 +3321,38 @@ class CGStaticMethod(CGAbstractStaticBindingMethod):
         return CGList([setupArgs, call])
 
 
+class CGMethodPromiseWrapper(CGAbstractExternMethod):
+    """
+    A class for generating a wrapper around the method of an operation that
+    returns a promise, which converts the exceptions the method throws into
+    a rejected promise, as per https://heycam.github.io/webidl/#es-operations.
+    """
+    def __init__(self, descriptor, methodToWrap):
+        self.method = methodToWrap
+        name = CGMethodPromiseWrapper.makeName(methodToWrap.name)
+        CGAbstractExternMethod.__init__(self, descriptor, name, "bool", methodToWrap.args)
+
+    def definition_body(self):
+        if isinstance(self.method, CGStaticMethod):
+            rval = "CallArgs::from_vp(vp, argc).rval()"
+        else:
+            rval = "(*args).rval()"
+        return CGGeneric(fill(
+            """
+            if ${method}(${args}) {
+                return true;
+            }
+            exception_to_promise(cx, ${rval})
+            """,
+            method=self.method.name,
+            args=", ".join(arg.name for arg in self.args),
+            rval=rval))
+
+    @staticmethod
+    def makeName(methodName):
+        return methodName + "_promise_wrapper"
+
+
 class CGSpecializedGetter(CGAbstractExternMethod):
     """
     A class for generating the code for a specialized attribute getter
@@ -3392,6 +3613,8 @@ class CGMemberJITInfo(CGThing):
         if self.member.isMethod():
             methodinfo = ("%s_methodinfo" % self.member.identifier.name)
             method = ("%s" % self.member.identifier.name)
+            if self.member.returnsPromise():
+                method = CGMethodPromiseWrapper.makeName(method)
 
             # Methods are infallible if they are infallible, have no arguments
             # to unwrap, and have a return type that's infallible to wrap up for
@@ -4880,8 +5103,9 @@ if !weak_box_ptr.is_null() {
 }
 """ % descriptor.concreteType
     release += """\
-if !this.is_null() {
+if !this.is_null() && !keep_finalized(this as *const libc::c_void) {
     // The pointer can be null if the object is the unforgeable holder of that interface.
+    // The object is kept instead when auditing the tracing of DOM objects.
     let _ = Box::from_raw(this as *mut %s);
 }
 debug!("%s finalize: {:p}", this);\
@@ -4901,6 +5125,7 @@ class CGClassTraceHook(CGAbstractClassHook):
 
     def generate_code(self):
         body = [CGGeneric("if this.is_null() { return; } // GC during obj creation\n"
+                          "let _owner = TracingOwner::new(Owner::Object(this as *const libc::c_void));\n"
                           "(*this).trace(%s);" % self.args[0].name)]
         if self.traceGlobal:
             body += [CGGeneric("trace_global(trc, obj);")]
@@ -4969,6 +5194,11 @@ class CGInterfaceTrait(CGThing):
 
         def members():
             for m in descriptor.interface.members:
+                # The members of maplike, setlike and iterable declarations are
+                # implemented by codegen.
+                if ((m.isMethod() and m.isMaplikeOrSetlikeOrIterableMethod()) or
+                        (m.isAttr() and m.isMaplikeOrSetlikeAttr())):
+                    continue
                 if (m.isMethod() and not m.isStatic() and
                         (not m.isIdentifierLess() or m.isStringifier())):
                     name = CGSpecializedMethod.makeNativeName(descriptor, m)
@@ -5051,8 +5281,16 @@ class CGDescriptor(CGThing):
 
         assert not descriptor.concrete or not descriptor.interface.isCallback()
 
+        declaration = descriptor.interface.maplikeOrSetlikeOrIterable
+        if declaration and declaration.isIterable() and declaration.isAsync():
+            raise TypeError("Async iterable declarations need Promise support, "
+                            "which Servo does not have yet.\n%s" % declaration.location)
+        if isMaplikeOrSetlike(descriptor.interface) and descriptor.weakReferenceable:
+            raise TypeError("Maplike and setlike interfaces can't be weak-referenceable, "
+                            "as both use the second reserved slot.\n%s" % declaration.location)
+
         cgThings = []
-        if not descriptor.interface.isCallback():
+        if not descriptor.interface.isCallback() and not descriptor.interface.isNamespace():
             cgThings.append(CGGetProtoObjectMethod(descriptor))
         if (descriptor.interface.hasInterfaceObject() and
                 descriptor.shouldHaveGetConstructorObjectMethod()):
@@ -5063,9 +5301,15 @@ class CGDescriptor(CGThing):
                     (not m.isIdentifierLess() or m == descriptor.operations["Stringifier"])):
                 if m.isStatic():
                     assert descriptor.interface.hasInterfaceObject()
-                    cgThings.append(CGStaticMethod(descriptor, m))
+                    method = CGStaticMethod(descriptor, m)
+                    cgThings.append(method)
+                    if m.returnsPromise():
+                        cgThings.append(CGMethodPromiseWrapper(descriptor, method))
                 elif not descriptor.interface.isCallback():
-                    cgThings.append(CGSpecializedMethod(descriptor, m))
+                    method = CGSpecializedMethod(descriptor, m)
+                    cgThings.append(method)
+                    if m.returnsPromise():
+                        cgThings.append(CGMethodPromiseWrapper(descriptor, method))
                     cgThings.append(CGMemberJITInfo(descriptor, m))
             elif m.isAttr():
                 if m.stringifier:
@@ -5100,10 +5344,12 @@ class CGDescriptor(CGThing):
                 cgThings.append(CGClassConstructHook(descriptor))
             for ctor in descriptor.interface.namedConstructors:
                 cgThings.append(CGClassConstructHook(descriptor, ctor))
-            if not descriptor.interface.isCallback():
+            if descriptor.interface.isNamespace():
+                cgThings.append(CGNamespaceObjectClass(descriptor))
+            elif not descriptor.interface.isCallback():
                 cgThings.append(CGInterfaceObjectJSClass(descriptor))
 
-        if not descriptor.interface.isCallback():
+        if not descriptor.interface.isCallback() and not descriptor.interface.isNamespace():
             cgThings.append(CGPrototypeJSClass(descriptor))
 
         properties = PropertyArrays(descriptor)
@@ -5159,7 +5405,9 @@ class CGDescriptor(CGThing):
         if not descriptor.interface.isCallback():
             if descriptor.concrete or descriptor.hasDescendants():
                 cgThings.append(CGIDLInterface(descriptor))
-            cgThings.append(CGInterfaceTrait(descriptor))
+            # The methods of iterator interfaces are implemented by IterableIterator.
+            if not descriptor.interface.isIteratorInterface():
+                cgThings.append(CGInterfaceTrait(descriptor))
             if descriptor.weakReferenceable:
                 cgThings.append(CGWeakReferenceableTrait(descriptor))
 
@@ -5441,7 +5689,13 @@ class CGBindingRoot(CGThing):
         # (hence hasInterfaceObject=False).
         descriptors.extend(config.getDescriptors(webIDLFile=webIDLFile,
                                                  hasInterfaceObject=False,
-                                                 isCallback=False))
+                                                 isCallback=False,
+                                                 isIteratorInterface=False,
+                                                 spiderMonkeyInterface=False))
+        # Iterator interfaces get a module of their own, as they would clash
+        # with the bindings of their iterable interface.
+        iteratorDescriptors = config.getDescriptors(webIDLFile=webIDLFile,
+                                                    isIteratorInterface=True)
 
         dictionaries = config.getDictionaries(webIDLFile=webIDLFile)
 
@@ -5487,11 +5741,7 @@ class CGBindingRoot(CGThing):
                                 CGCallbackFunctionImpl(x.interface)], "\n")
                         for x in callbackDescriptors)
 
-        # And make sure we have the right number of newlines at the end
-        curr = CGWrapper(CGList(cgthings, "\n\n"), post="\n\n")
-
-        # Add imports
-        curr = CGImports(curr, descriptors + callbackDescriptors, mainCallbacks, [
+        imports = [
             'js',
             'js::{JS_CALLEE, JSCLASS_GLOBAL_SLOT_COUNT}',
             'js::{JSCLASS_IS_DOMJSCLASS, JSCLASS_IS_GLOBAL, JSCLASS_RESERVED_SLOTS_MASK}',
@@ -5534,6 +5784,11 @@ class CGBindingRoot(CGThing):
             'dom::bindings::interface::{define_guarded_methods, define_guarded_properties}',
             'dom::bindings::interface::{ConstantSpec, NonNullJSNative}',
             'dom::bindings::interface::ConstantVal::{IntVal, UintVal}',
+            'dom::bindings::iterable',
+            'dom::bindings::iterable::IteratorType',
+            'dom::bindings::like',
+            'dom::bindings::like::{Like, LikeIterator}',
+            'dom::bindings::namespace::{NamespaceObjectClass, create_namespace_object}',
             'dom::bindings::js::{JS, Root, RootedReference}',
             'dom::bindings::js::{OptionalRootedReference}',
             'dom::bindings::reflector::{Reflectable}',
@@ -5561,6 +5816,8 @@ class CGBindingRoot(CGThing):
             'dom::bindings::error::{Fallible, Error, ErrorResult}',
             'dom::bindings::error::Error::JSFailed',
             'dom::bindings::error::throw_dom_exception',
+            'dom::bindings::error::exception_to_promise',
+            'dom::bindings::audit::{Owner, TracingOwner, keep_finalized}',
             'dom::bindings::guard::{Condition, Guard}',
             'dom::bindings::proxyhandler',
             'dom::bindings::proxyhandler::{ensure_expando_object, fill_property_descriptor}',
@@ -5570,6 +5827,7 @@ class CGBindingRoot(CGThing):
             'dom::bindings::trace::RootedVec',
             'dom::bindings::weakref::{DOM_WEAK_SLOT, WeakBox, WeakReferenceable}',
             'mem::heap_size_of_raw_self_and_children',
+            'origin_trials',
             'libc',
             'util::prefs',
             'std::borrow::ToOwned',
@@ -5583,7 +5841,19 @@ class CGBindingRoot(CGThing):
             'std::rc::Rc',
             'std::default::Default',
             'std::ffi::CString',
-        ])
+        ]
+
+        # Do codegen for all the iterator interfaces.
+        cgthings.extend(CGNamespace(toBindingNamespace(x.name),
+                                    CGImports(CGDescriptor(x), [x], [], list(imports)),
+                                    public=True)
+                        for x in iteratorDescriptors)
+
+        # And make sure we have the right number of newlines at the end
+        curr = CGWrapper(CGList(cgthings, "\n\n"), post="\n\n")
+
+        # Add imports
+        curr = CGImports(curr, descriptors + callbackDescriptors, mainCallbacks, imports)
 
         # Add the auto-generated comment.
         curr = CGWrapper(curr, pre=AUTOGENERATED_WARNING_COMMENT)
@@ -6223,7 +6493,8 @@ class GlobalGenRoots():
     @staticmethod
     def PrototypeList(config):
         # Prototype ID enum.
-        interfaces = config.getDescriptors(isCallback=False)
+        interfaces = config.getDescriptors(isCallback=False, isNamespace=False,
+                                           spiderMonkeyInterface=False)
         protos = [d.name for d in interfaces]
         constructors = [d.name for d in config.getDescriptors(hasInterfaceObject=True)
                         if d.shouldHaveGetConstructorObjectMethod()]
@@ -6264,6 +6535,9 @@ class GlobalGenRoots():
     @staticmethod
     def InterfaceTypes(config):
         descriptors = [d.name for d in config.getDescriptors(register=True, isCallback=False)]
+        # SpiderMonkey interfaces are not registered, but their native wrappers
+        # are used like DOM types.
+        descriptors += [d.name for d in config.getDescriptors(spiderMonkeyInterface=True)]
         curr = CGList([CGGeneric("pub use dom::%s::%s;\n" % (name.lower(), name)) for name in descriptors])
         curr = CGWrapper(curr, pre=AUTOGENERATED_WARNING_COMMENT)
         return curr
@@ -38,6 +38,10 @@ class Configuration:
 
             iface = thing
             self.interfaces[iface.identifier.name] = iface
+            # Mixins only contribute members to the interfaces that include
+            # them, and get no bindings of their own.
+            if iface.isMixin():
+                continue
             if iface.identifier.name not in config:
                 # Completely skip consequential interfaces with no descriptor
                 # if they have no interface object because chances are we
@@ -85,6 +89,10 @@ class Configuration:
                 getter = lambda x: x.interface.isCallback()
             elif key == 'isJSImplemented':
                 getter = lambda x: x.interface.isJSImplemented()
+            elif key == 'isNamespace':
+                getter = lambda x: x.interface.isNamespace()
+            elif key == 'isIteratorInterface':
+                getter = lambda x: x.interface.isIteratorInterface()
             else:
                 getter = lambda x: getattr(x, key)
             curr = filter(lambda x: getter(x) == val, curr)
@@ -174,6 +182,8 @@ class Descriptor(DescriptorProvider):
         # Read the desc, and fill in the relevant defaults.
         ifaceName = self.interface.identifier.name
 
+        self.spiderMonkeyInterface = desc.get('spiderMonkeyInterface', False)
+
         # Callback types do not use JS smart pointers, so we should not use the
         # built-in rooting mechanisms for them.
         if self.interface.isCallback():
@@ -182,14 +192,34 @@ class Descriptor(DescriptorProvider):
             self.returnType = "Rc<%s>" % ty
             self.argumentType = "???"
             self.nativeType = ty
+        elif self.spiderMonkeyInterface:
+            # SpiderMonkey objects, such as promises, are kept alive by their
+            # reference-counted native wrappers rather than by rooting.
+            self.needsRooting = False
+            self.returnType = "Rc<%s>" % ifaceName
+            self.argumentType = "&%s" % ifaceName
+            self.nativeType = ifaceName
         else:
             self.needsRooting = True
-            self.returnType = "Root<%s>" % ifaceName
-            self.argumentType = "&%s" % ifaceName
-            self.nativeType = "*const %s" % ifaceName
-
-        self.concreteType = ifaceName
-        self.register = desc.get('register', True)
+            if self.interface.isIteratorInterface():
+                # Iterator interfaces are all implemented by the generic
+                # IterableIterator, and have no type of their own.
+                ty = "IterableIterator<%s>" % self.interface.iterableInterface.identifier.name
+            else:
+                ty = ifaceName
+            self.returnType = "Root<%s>" % ty
+            self.argumentType = "&%s" % ty
+            self.nativeType = "*const %s" % ty
+
+        if self.interface.isIteratorInterface():
+            self.concreteType = ty
+            self.register = False
+        elif self.spiderMonkeyInterface:
+            self.concreteType = ifaceName
+            self.register = False
+        else:
+            self.concreteType = ifaceName
+            self.register = desc.get('register', True)
         self.outerObjectHook = desc.get('outerObjectHook', 'None')
         self.proxy = False
         self.weakReferenceable = desc.get('weakReferenceable', False)
@@ -197,6 +227,8 @@ class Descriptor(DescriptorProvider):
         # If we're concrete, we need to crawl our ancestor interfaces and mark
         # them as having a concrete descendant.
         self.concrete = (not self.interface.isCallback() and
+                         not self.interface.isNamespace() and
+                         not self.spiderMonkeyInterface and
                          not self.interface.getExtendedAttribute("Abstract"))
         self.hasUnforgeableMembers = (self.concrete and
                                       any(MemberIsUnforgeable(m, self) for m in
@@ -355,7 +387,8 @@ class Descriptor(DescriptorProvider):
 
     def shouldHaveGetConstructorObjectMethod(self):
         assert self.interface.hasInterfaceObject()
-        return self.interface.isCallback() or self.hasDescendants()
+        return (self.interface.isCallback() or self.interface.isNamespace() or
+                self.hasDescendants())
 
     def isExposedConditionally(self):
         return self.interface.isExposedConditionally()
@@ -684,6 +684,9 @@ class IDLInterface(IDLObjectWithScope, IDLExposureMixins):
         # If this is an iterator interface, we need to know what iterable
         # interface we're iterating for in order to get its nativeType.
         self.iterableInterface = None
+        self._mixin = False
+        # The interfaces that include this mixin.
+        self.interfacesIncludingSelf = set()
 
         IDLObjectWithScope.__init__(self, location, parentScope, name)
         IDLExposureMixins.__init__(self, location)
@@ -709,6 +712,17 @@ class IDLInterface(IDLObjectWithScope, IDLExposureMixins):
     def isIteratorInterface(self):
         return self.iterableInterface is not None
 
+    def isNamespace(self):
+        return False
+
+    def setMixin(self):
+        self._mixin = True
+        # Mixins are never exposed as interface objects of their own.
+        self._noInterfaceObject = True
+
+    def isMixin(self):
+        return self._mixin
+
     def resolveIdentifierConflict(self, scope, identifier, originalObject, newObject):
         assert isinstance(scope, IDLScope)
         assert isinstance(originalObject, IDLInterfaceMember)
@@ -733,6 +747,15 @@ class IDLInterface(IDLObjectWithScope, IDLExposureMixins):
                               "declaration" % self.identifier.name,
                               [self.location])
 
+        if self.isMixin() and not self._exposureGlobalNames:
+            # The members of a mixin are exposed wherever the interfaces that
+            # include it are.
+            for iface in self.interfacesIncludingSelf:
+                if iface._exposureGlobalNames:
+                    self._exposureGlobalNames.update(iface._exposureGlobalNames)
+                else:
+                    self._exposureGlobalNames.add(scope.primaryGlobalName)
+
         IDLExposureMixins.finish(self, scope)
 
         # Now go ahead and merge in our partial interfaces.
@@ -1210,6 +1233,7 @@ class IDLInterface(IDLObjectWithScope, IDLExposureMixins):
                     if (member.getExtendedAttribute("Exposed") or
                         member.getExtendedAttribute("ChromeOnly") or
                         member.getExtendedAttribute("Pref") or
+                        member.getExtendedAttribute("OriginTrial") or
                         member.getExtendedAttribute("Func") or
                         member.getExtendedAttribute("AvailableIn") or
                         member.getExtendedAttribute("CheckAnyPermissions") or
@@ -1264,8 +1288,8 @@ class IDLInterface(IDLObjectWithScope, IDLExposureMixins):
 
         # Value iterators are only allowed on interfaces with indexed getters,
         # and pair iterators are only allowed on interfaces without indexed
-        # getters.
-        if self.isIterable():
+        # getters. Asynchronous iterators are allowed on any interface.
+        if self.isIterable() and not self.maplikeOrSetlikeOrIterable.isAsync():
             iterableDecl = self.maplikeOrSetlikeOrIterable
             if iterableDecl.isValueIterator():
                 if not indexedGetter:
@@ -1493,6 +1517,7 @@ class IDLInterface(IDLObjectWithScope, IDLExposureMixins):
                 convertExposedAttrToGlobalNameSet(attr,
                                                   self._exposureGlobalNames)
             elif (identifier == "Pref" or
+                  identifier == "OriginTrial" or
                   identifier == "JSImplementation" or
                   identifier == "HeaderFile" or
                   identifier == "NavigatorProperty" or
@@ -1657,11 +1682,48 @@ class IDLInterface(IDLObjectWithScope, IDLExposureMixins):
 
     conditionExtendedAttributes = [ "Pref", "ChromeOnly", "Func", "AvailableIn",
                                     "CheckAnyPermissions",
-                                    "CheckAllPermissions" ]
+                                    "CheckAllPermissions", "OriginTrial" ]
     def isExposedConditionally(self):
         return any(self.getExtendedAttribute(a) for a in self.conditionExtendedAttributes)
 
 
+class IDLNamespace(IDLInterface):
+    def __init__(self, location, parentScope, name, members):
+        for member in members:
+            if member.isAttr() and not member.readonly:
+                raise WebIDLError("Namespace attributes must be readonly",
+                                  [member.location])
+            if not (member.isAttr() or member.isMethod()) or member.isStatic():
+                raise WebIDLError("Namespace members must be regular operations "
+                                  "or readonly attributes",
+                                  [member.location])
+            if member.isMethod() and member.isSpecial():
+                raise WebIDLError("Namespace operations must not be special",
+                                  [member.location])
+            member.forceStatic()
+        IDLInterface.__init__(self, location, parentScope, name, None,
+                              members, isKnownNonPartial=True)
+
+    def __str__(self):
+        return "Namespace '%s'" % self.identifier.name
+
+    def isNamespace(self):
+        return True
+
+    def hasInterfacePrototypeObject(self):
+        return False
+
+    def addExtendedAttributes(self, attrs):
+        for attr in attrs:
+            identifier = attr.identifier()
+            if identifier not in ["Exposed", "Pref", "OriginTrial", "Func",
+                                  "ChromeOnly"]:
+                raise WebIDLError("Unknown extended attribute %s on namespace" %
+                                  identifier,
+                                  [attr.location])
+        IDLInterface.addExtendedAttributes(self, attrs)
+
+
 class IDLDictionary(IDLObjectWithScope):
     def __init__(self, location, parentScope, name, parent, members):
         assert isinstance(parentScope, IDLScope)
@@ -3684,14 +3746,20 @@ class IDLMaplikeOrSetlikeOrIterableBase(IDLInterfaceMember):
 # (keys/values/entries/@@iterator) to an interface.
 class IDLIterable(IDLMaplikeOrSetlikeOrIterableBase):
 
-    def __init__(self, location, identifier, keyType, valueType=None, scope=None):
+    def __init__(self, location, identifier, keyType, valueType=None, scope=None,
+                 isAsync=False):
         IDLMaplikeOrSetlikeOrIterableBase.__init__(self, location, identifier,
                                                    "iterable", keyType, valueType,
                                                    IDLInterfaceMember.Tags.Iterable)
         self.iteratorType = None
+        self._async = isAsync
 
     def __str__(self):
-        return "declared iterable with key '%s' and value '%s'" % (self.keyType, self.valueType)
+        return "declared %siterable with key '%s' and value '%s'" % (
+            "async " if self._async else "", self.keyType, self.valueType)
+
+    def isAsync(self):
+        return self._async
 
     def expand(self, members, isJSImplemented):
         """
@@ -3701,7 +3769,9 @@ class IDLIterable(IDLMaplikeOrSetlikeOrIterableBase):
         """
         # We only need to add entries/keys/values here if we're a pair iterator.
         # Value iterators just copy these from %ArrayPrototype% instead.
-        if not self.isPairIterator():
+        # Asynchronous iterators only have an @@asyncIterator method, which
+        # bindings generate themselves.
+        if not self.isPairIterator() or self.isAsync():
             return
 
         # object entries()
@@ -3887,6 +3957,7 @@ class IDLConst(IDLInterfaceMember):
         if identifier == "Exposed":
             convertExposedAttrToGlobalNameSet(attr, self._exposureGlobalNames)
         elif (identifier == "Pref" or
+              identifier == "OriginTrial" or
               identifier == "ChromeOnly" or
               identifier == "Func" or
               identifier == "AvailableIn" or
@@ -3939,6 +4010,9 @@ class IDLAttribute(IDLInterfaceMember):
     def isStatic(self):
         return self.static
 
+    def forceStatic(self):
+        self.static = True
+
     def __str__(self):
         return "'%s' attribute '%s'" % (self.type, self.identifier)
 
@@ -4183,6 +4257,7 @@ class IDLAttribute(IDLInterfaceMember):
                                   "attributes and operations",
                                   [attr.location, self.location])
         elif (identifier == "Pref" or
+              identifier == "OriginTrial" or
               identifier == "Deprecated" or
               identifier == "SetterThrows" or
               identifier == "Throws" or
@@ -4549,6 +4624,9 @@ class IDLMethod(IDLInterfaceMember, IDLScope):
     def isStatic(self):
         return self._static
 
+    def forceStatic(self):
+        self._static = True
+
     def isGetter(self):
         return self._getter
 
@@ -4898,6 +4976,7 @@ class IDLMethod(IDLInterfaceMember, IDLScope):
               identifier == "ChromeOnly" or
               identifier == "UnsafeInPrerendering" or
               identifier == "Pref" or
+              identifier == "OriginTrial" or
               identifier == "Deprecated" or
               identifier == "Func" or
               identifier == "AvailableIn" or
@@ -4969,6 +5048,24 @@ class IDLImplementsStatement(IDLObject):
         assert len(attrs) == 0
 
 
+class IDLIncludesStatement(IDLImplementsStatement):
+    def finish(self, scope):
+        if self._finished:
+            return
+        implementor = self.implementor.finish(scope)
+        implementee = self.implementee.finish(scope)
+        if not isinstance(implementor, IDLInterface) or implementor.isMixin():
+            raise WebIDLError("Left-hand side of 'includes' is not an "
+                              "interface",
+                              [self.implementor.location])
+        if not isinstance(implementee, IDLInterface) or not implementee.isMixin():
+            raise WebIDLError("Right-hand side of 'includes' is not an "
+                              "interface mixin",
+                              [self.implementee.location])
+        implementee.interfacesIncludingSelf.add(implementor)
+        IDLImplementsStatement.finish(self, scope)
+
+
 class IDLExtendedAttribute(IDLObject):
     """
     A class to represent IDL extended attributes so we can give them locations
@@ -5070,6 +5167,10 @@ class Tokenizer(object):
         "callback": "CALLBACK",
         "typedef": "TYPEDEF",
         "implements": "IMPLEMENTS",
+        "namespace": "NAMESPACE",
+        "mixin": "MIXIN",
+        "includes": "INCLUDES",
+        "async": "ASYNC",
         "const": "CONST",
         "null": "NULL",
         "true": "TRUE",
@@ -5230,6 +5331,8 @@ class Parser(Tokenizer):
                        | Enum
                        | Typedef
                        | ImplementsStatement
+                       | IncludesStatement
+                       | Namespace
         """
         p[0] = p[1]
         assert p[1]  # We might not have implemented something ...
@@ -5286,6 +5389,17 @@ class Parser(Tokenizer):
                             members, isKnownNonPartial=True)
         p[0] = iface
 
+    def p_InterfaceMixin(self, p):
+        """
+            Interface : INTERFACE MIXIN IDENTIFIER LBRACE InterfaceMembers RBRACE SEMICOLON
+        """
+        location = self.getLocation(p, 1)
+        identifier = IDLUnresolvedIdentifier(self.getLocation(p, 3), p[3])
+        iface = IDLInterface(location, self.globalScope(), identifier, None,
+                             p[5], isKnownNonPartial=True)
+        iface.setMixin()
+        p[0] = iface
+
     def p_InterfaceForwardDecl(self, p):
         """
             Interface : INTERFACE IDENTIFIER SEMICOLON
@@ -5309,6 +5423,14 @@ class Parser(Tokenizer):
 
         p[0] = IDLExternalInterface(location, self.globalScope(), identifier)
 
+    def p_Namespace(self, p):
+        """
+            Namespace : NAMESPACE IDENTIFIER LBRACE InterfaceMembers RBRACE SEMICOLON
+        """
+        location = self.getLocation(p, 1)
+        identifier = IDLUnresolvedIdentifier(self.getLocation(p, 2), p[2])
+        p[0] = IDLNamespace(location, self.globalScope(), identifier, p[4])
+
     def p_PartialInterface(self, p):
         """
             PartialInterface : PARTIAL INTERFACE IDENTIFIER LBRACE InterfaceMembers RBRACE SEMICOLON
@@ -5520,6 +5642,15 @@ class Parser(Tokenizer):
         p[0] = IDLImplementsStatement(self.getLocation(p, 1), implementor,
                                       implementee)
 
+    def p_IncludesStatement(self, p):
+        """
+            IncludesStatement : ScopedName INCLUDES ScopedName SEMICOLON
+        """
+        assert(p[2] == "includes")
+        interface = IDLIdentifierPlaceholder(self.getLocation(p, 1), p[1])
+        mixin = IDLIdentifierPlaceholder(self.getLocation(p, 3), p[3])
+        p[0] = IDLIncludesStatement(self.getLocation(p, 1), interface, mixin)
+
     def p_Const(self, p):
         """
             Const : CONST ConstType IDENTIFIER EQUALS ConstValue SEMICOLON
@@ -5612,6 +5743,24 @@ class Parser(Tokenizer):
 
         p[0] = IDLIterable(location, identifier, keyType, valueType, self.globalScope())
 
+    def p_AsyncIterable(self, p):
+        """
+            Iterable : ASYNC ITERABLE LT Type GT SEMICOLON
+                     | ASYNC ITERABLE LT Type COMMA Type GT SEMICOLON
+        """
+        location = self.getLocation(p, 3)
+        identifier = IDLUnresolvedIdentifier(location, "__iterable",
+                                             allowDoubleUnderscore=True)
+        if (len(p) > 7):
+            keyType = p[4]
+            valueType = p[6]
+        else:
+            keyType = None
+            valueType = p[4]
+
+        p[0] = IDLIterable(location, identifier, keyType, valueType, self.globalScope(),
+                           isAsync=True)
+
     def p_Setlike(self, p):
         """
             Setlike : ReadOnly SETLIKE LT Type GT SEMICOLON
@@ -5972,6 +6121,7 @@ class Parser(Tokenizer):
     def p_ArgumentName(self, p):
         """
             ArgumentName : IDENTIFIER
+                         | ASYNC
                          | ATTRIBUTE
                          | CALLBACK
                          | CONST
@@ -5982,11 +6132,14 @@ class Parser(Tokenizer):
                          | EXCEPTION
                          | GETTER
                          | IMPLEMENTS
+                         | INCLUDES
                          | INHERIT
                          | INTERFACE
                          | ITERABLE
                          | LEGACYCALLER
                          | MAPLIKE
+                         | MIXIN
+                         | NAMESPACE
                          | PARTIAL
                          | REQUIRED
                          | SERIALIZER
@@ -6003,6 +6156,7 @@ class Parser(Tokenizer):
     def p_AttributeName(self, p):
         """
             AttributeName : IDENTIFIER
+                          | ASYNC
                           | REQUIRED
         """
         p[0] = p[1]
@@ -6695,7 +6849,7 @@ class Parser(Tokenizer):
                 if isinstance(m, IDLIterable):
                     iterable = m
                     break
-            if iterable and iterable.isPairIterator():
+            if iterable and iterable.isPairIterator() and not iterable.isAsync():
                 def simpleExtendedAttr(str):
                     return IDLExtendedAttribute(iface.location, (str, ))
                 nextMethod = IDLMethod(
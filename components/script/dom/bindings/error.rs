@@ -5,14 +5,17 @@
 //! Utilities to throw exceptions from Rust bindings.
 
 use dom::bindings::codegen::PrototypeList::proto_id_to_name;
-use dom::bindings::conversions::ToJSValConvertible;
-use dom::bindings::global::GlobalRef;
+use dom::bindings::conversions::{FromJSValConvertible, StringificationBehavior, ToJSValConvertible};
+use dom::bindings::global::{GlobalRef, global_root_from_object};
+use dom::bindings::str::DOMString;
 use dom::domexception::{DOMErrorName, DOMException};
 use js::error::{throw_range_error, throw_type_error};
-use js::jsapi::JSAutoCompartment;
-use js::jsapi::{JSContext, JSObject, RootedValue};
+use js::jsapi::{CallOriginalPromiseReject, HandleValue, JSAutoCompartment, JSContext, JSObject};
+use js::jsapi::{JS_ClearPendingException, JS_GetPendingException, JS_GetProperty};
 use js::jsapi::{JS_IsExceptionPending, JS_ReportPendingException, JS_SetPendingException};
-use js::jsval::UndefinedValue;
+use js::jsapi::{MutableHandleValue, RootedObject, RootedValue};
+use js::jsval::{ObjectValue, UndefinedValue};
+use script_traits::PageError;
 
 /// DOM exceptions that can be thrown by a native DOM method.
 #[derive(Debug, Clone, HeapSizeOf)]
@@ -125,11 +128,88 @@ pub fn report_pending_exception(cx: *mut JSContext, obj: *mut JSObject) {
     unsafe {
         if JS_IsExceptionPending(cx) {
             let _ac = JSAutoCompartment::new(cx, obj);
+            let mut exception = RootedValue::new(cx, UndefinedValue());
+            if !JS_GetPendingException(cx, exception.handle_mut()) {
+                JS_ReportPendingException(cx);
+                return;
+            }
+            // Reading the exception may run script, which needs the exception to be cleared.
+            JS_ClearPendingException(cx);
+            let error = page_error_from_exception(cx, exception.handle());
+            JS_SetPendingException(cx, exception.handle());
             JS_ReportPendingException(cx);
+            global_root_from_object(obj).r().report_page_error(error);
         }
     }
 }
 
+/// Describe an uncaught exception for the embedder, reading the message and the location of
+/// error objects from their properties.
+unsafe fn page_error_from_exception(cx: *mut JSContext, exception: HandleValue) -> PageError {
+    let (message, filename, line, column) = if exception.get().is_object() {
+        let exception = RootedObject::new(cx, exception.get().to_object());
+        let property = |name: &[u8]| {
+            let mut value = RootedValue::new(cx, UndefinedValue());
+            if !JS_GetProperty(cx, exception.handle(), name.as_ptr() as *const _,
+                               value.handle_mut()) {
+                JS_ClearPendingException(cx);
+            }
+            value.ptr
+        };
+        let message = RootedValue::new(cx, property(b"message\0"));
+        let filename = RootedValue::new(cx, property(b"fileName\0"));
+        let line = property(b"lineNumber\0");
+        let column = property(b"columnNumber\0");
+        (value_to_string(cx, message.handle()),
+         value_to_string(cx, filename.handle()),
+         if line.is_number() { line.to_number() as u32 } else { 0 },
+         if column.is_number() { column.to_number() as u32 } else { 0 })
+    } else {
+        (value_to_string(cx, exception), String::new(), 0, 0)
+    };
+    PageError::UncaughtException {
+        message: message,
+        filename: filename,
+        line: line,
+        column: column,
+    }
+}
+
+/// Convert `value` to a string, or to the empty string if it is undefined or cannot be
+/// converted.
+unsafe fn value_to_string(cx: *mut JSContext, value: HandleValue) -> String {
+    if value.get().is_undefined() {
+        return String::new();
+    }
+    match DOMString::from_jsval(cx, value, StringificationBehavior::Default) {
+        Ok(string) => String::from(string),
+        Err(()) => {
+            JS_ClearPendingException(cx);
+            String::new()
+        }
+    }
+}
+
+/// Convert the pending exception into a promise rejected with it, stored in `rval`.
+///
+/// Operations that return promises never throw: whatever they throw, be it an `Error` returned
+/// by the Rust implementation or an exception from converting their arguments, rejects the
+/// promise they return instead. Returns false if there is no exception to convert, as happens
+/// when the operation was terminated without one, or if the promise could not be created.
+pub unsafe fn exception_to_promise(cx: *mut JSContext, rval: MutableHandleValue) -> bool {
+    let mut exception = RootedValue::new(cx, UndefinedValue());
+    if !JS_GetPendingException(cx, exception.handle_mut()) {
+        return false;
+    }
+    JS_ClearPendingException(cx);
+    let promise = RootedObject::new(cx, CallOriginalPromiseReject(cx, exception.handle()));
+    if promise.ptr.is_null() {
+        return false;
+    }
+    rval.set(ObjectValue(&*promise.ptr));
+    true
+}
+
 /// Throw an exception to signal that a `JSVal` can not be converted to any of
 /// the types in an IDL union type.
 pub unsafe fn throw_not_in_union(cx: *mut JSContext, names: &'static str) {
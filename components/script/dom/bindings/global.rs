@@ -23,7 +23,7 @@ use net_traits::{CoreResourceThread, RequestSource};
 use profile_traits::{mem, time};
 use script_runtime::{CommonScriptMsg, ScriptChan, ScriptPort};
 use script_thread::{MainThreadScriptChan, ScriptThread};
-use script_traits::{MsDuration, ScriptMsg as ConstellationMsg, TimerEventRequest};
+use script_traits::{MsDuration, PageError, ScriptMsg as ConstellationMsg, TimerEventRequest};
 use task_source::TaskSource;
 use task_source::dom_manipulation::DOMManipulationTask;
 use timers::{OneshotTimerCallback, OneshotTimerHandle};
@@ -105,6 +105,11 @@ impl<'a> GlobalRef<'a> {
         }
     }
 
+    /// Report an error of the page of this global to the embedder, through the constellation.
+    pub fn report_page_error(&self, error: PageError) {
+        let _ = self.constellation_chan().send(ConstellationMsg::PageError(self.pipeline(), error));
+    }
+
     /// Get the scheduler channel to request timer events.
     pub fn scheduler_chan(&self) -> &IpcSender<TimerEventRequest> {
         match *self {
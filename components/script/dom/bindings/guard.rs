@@ -5,6 +5,7 @@
 //! Machinery to conditionally expose things.
 
 use js::jsapi::{HandleObject, JSContext};
+use origin_trials;
 use util::prefs::get_pref;
 
 /// A container with a condition.
@@ -40,6 +41,8 @@ pub enum Condition {
     Func(unsafe fn(*mut JSContext, HandleObject) -> bool),
     /// The condition is satisfied if the preference is set.
     Pref(&'static str),
+    /// The condition is satisfied if the origin trial feature is enabled for the global.
+    OriginTrial(&'static str),
     /// The condition is always satisfied.
     Satisfied,
 }
@@ -49,6 +52,7 @@ impl Condition {
         match *self {
             Condition::Pref(name) => get_pref(name).as_boolean().unwrap_or(false),
             Condition::Func(f) => f(cx, obj),
+            Condition::OriginTrial(feature) => origin_trials::is_enabled(cx, obj, feature),
             Condition::Satisfied => true,
         }
     }
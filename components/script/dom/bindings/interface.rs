@@ -356,7 +356,9 @@ unsafe fn has_instance(
     Err(())
 }
 
-unsafe fn create_object(
+/// Create a new object with the given class and prototype, and define the given methods,
+/// properties and constants on it.
+pub unsafe fn create_object(
         cx: *mut JSContext,
         proto: HandleObject,
         class: &'static JSClass,
@@ -421,7 +423,8 @@ unsafe fn define_length(cx: *mut JSContext, obj: HandleObject, length: u32) {
                                None, None));
 }
 
-unsafe fn define_on_global_object(
+/// Define `obj` as the property `name` of `global`.
+pub unsafe fn define_on_global_object(
         cx: *mut JSContext,
         global: HandleObject,
         name: &[u8],
@@ -0,0 +1,200 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Implementation of `iterable<...>` and `iterable<..., ...>` WebIDL declarations.
+//!
+//! Value iterables get the `keys`, `values`, `entries` and `forEach` methods of
+//! `Array.prototype` in codegen, and only need an indexed getter and a `length`.
+//!
+//! Pair iterables implement the `Iterable` trait, and codegen generates their
+//! methods on top of it, returning `IterableIterator` objects for `keys`,
+//! `values` and `entries`.
+
+use dom::bindings::conversions::ToJSValConvertible;
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::global::{GlobalRef, global_root_from_reflector};
+use dom::bindings::js::{JS, Root};
+use dom::bindings::reflector::{Reflectable, Reflector, reflect_dom_object};
+use dom::bindings::trace::JSTraceable;
+use js::jsapi::{HandleObject, HandleValue, HandleValueArray, JSContext, JSObject};
+use js::jsapi::{JSPROP_ENUMERATE, JS_CallFunctionValue, JS_DefineProperty, JS_NewArrayObject};
+use js::jsapi::{JS_NewPlainObject, MutableHandleObject, RootedObject, RootedValue};
+use js::jsval::{BooleanValue, JSVal, ObjectValue, UndefinedValue};
+use std::cell::Cell;
+use std::ptr;
+
+/// The values that an iterator will iterate over.
+#[derive(JSTraceable, HeapSizeOf)]
+pub enum IteratorType {
+    /// The keys of the iterable object.
+    Keys,
+    /// The values of the iterable object.
+    Values,
+    /// The keys and values of the iterable object combined.
+    Entries,
+}
+
+/// A DOM object that can be iterated over using a pair value iterator.
+pub trait Iterable {
+    /// The type of the key of the iterator pair.
+    type Key: ToJSValConvertible;
+    /// The type of the value of the iterator pair.
+    type Value: ToJSValConvertible;
+    /// Return the number of entries that can be iterated over.
+    fn get_iterable_length(&self) -> u32;
+    /// Return the value at the provided index.
+    fn get_value_at_index(&self, index: u32) -> Self::Value;
+    /// Return the key at the provided index.
+    fn get_key_at_index(&self, index: u32) -> Self::Key;
+}
+
+/// An iterator over the iterable entries of a given DOM interface.
+// `#[dom_struct]` does not support type parameters, so this spells out what it generates.
+#[must_root]
+#[privatize]
+#[derive(JSTraceable)]
+#[derive(HeapSizeOf)]
+pub struct IterableIterator<T: Reflectable + JSTraceable + Iterable> {
+    reflector: Reflector,
+    iterable: JS<T>,
+    type_: IteratorType,
+    index: Cell<u32>,
+}
+
+impl<T: Reflectable + JSTraceable + Iterable> Reflectable for IterableIterator<T> {
+    fn reflector(&self) -> &Reflector {
+        &self.reflector
+    }
+    fn init_reflector(&mut self, obj: *mut JSObject) {
+        self.reflector.set_jsobject(obj);
+    }
+}
+
+impl<T: Reflectable + JSTraceable + Iterable> IterableIterator<T> {
+    /// Create a new iterator instance for the provided iterable DOM interface.
+    pub fn new(iterable: &T,
+               type_: IteratorType,
+               wrap: fn(*mut JSContext, GlobalRef, Box<IterableIterator<T>>)
+                        -> Root<Self>) -> Root<Self> {
+        let iterator = box IterableIterator {
+            reflector: Reflector::new(),
+            type_: type_,
+            iterable: JS::from_ref(iterable),
+            index: Cell::new(0),
+        };
+        let global = global_root_from_reflector(iterable);
+        reflect_dom_object(iterator, global.r(), wrap)
+    }
+
+    /// Return the next value from the iterable object.
+    #[allow(non_snake_case)]
+    pub fn Next(&self, cx: *mut JSContext) -> Fallible<*mut JSObject> {
+        let index = self.index.get();
+        let mut value = RootedValue::new(cx, UndefinedValue());
+        let mut rval = RootedObject::new(cx, ptr::null_mut());
+        let result = if index >= self.iterable.get_iterable_length() {
+            iterator_result(cx, rval.handle_mut(), true, value.handle())
+        } else {
+            unsafe {
+                match self.type_ {
+                    IteratorType::Keys => {
+                        self.iterable.get_key_at_index(index).to_jsval(cx, value.handle_mut());
+                    }
+                    IteratorType::Values => {
+                        self.iterable.get_value_at_index(index).to_jsval(cx, value.handle_mut());
+                    }
+                    IteratorType::Entries => {
+                        let mut key = RootedValue::new(cx, UndefinedValue());
+                        self.iterable.get_key_at_index(index).to_jsval(cx, key.handle_mut());
+                        self.iterable.get_value_at_index(index).to_jsval(cx, value.handle_mut());
+                        let pair = try!(key_value_pair(cx, key.handle(), value.handle()));
+                        value.ptr = ObjectValue(&*pair);
+                    }
+                }
+            }
+            iterator_result(cx, rval.handle_mut(), false, value.handle())
+        };
+        self.index.set(index + 1);
+        result.map(|_| {
+            assert!(!rval.ptr.is_null());
+            rval.ptr
+        })
+    }
+}
+
+/// Create an iterator result object, `{ value: value, done: done }`.
+fn iterator_result(cx: *mut JSContext,
+                   result: MutableHandleObject,
+                   done: bool,
+                   value: HandleValue) -> Fallible<()> {
+    unsafe {
+        result.set(JS_NewPlainObject(cx));
+        if result.get().is_null() {
+            return Err(Error::JSFailed);
+        }
+        let done = RootedValue::new(cx, BooleanValue(done));
+        if !JS_DefineProperty(cx, result.handle(), b"value\0".as_ptr() as *const _,
+                              value, JSPROP_ENUMERATE, None, None) ||
+           !JS_DefineProperty(cx, result.handle(), b"done\0".as_ptr() as *const _,
+                              done.handle(), JSPROP_ENUMERATE, None, None) {
+            return Err(Error::JSFailed);
+        }
+    }
+    Ok(())
+}
+
+/// Create the `[key, value]` array of an entries iterator.
+fn key_value_pair(cx: *mut JSContext, key: HandleValue, value: HandleValue)
+                  -> Fallible<*mut JSObject> {
+    let elements = [key.get(), value.get()];
+    let pair = unsafe {
+        JS_NewArrayObject(cx, &HandleValueArray {
+            length_: elements.len(),
+            elements_: elements.as_ptr(),
+        })
+    };
+    if pair.is_null() {
+        return Err(Error::JSFailed);
+    }
+    Ok(pair)
+}
+
+/// Call `callback` with the value, the key and `obj` for each entry of a pair iterable,
+/// implementing its `forEach` method.
+pub fn for_each<T: Iterable>(cx: *mut JSContext,
+                             iterable: &T,
+                             obj: HandleObject,
+                             callback: *mut JSObject,
+                             this_arg: HandleValue) -> Fallible<()> {
+    let callback = RootedValue::new(cx, ObjectValue(unsafe { &*callback }));
+    let this_arg = RootedObject::new(cx, if this_arg.is_object() {
+        this_arg.to_object()
+    } else {
+        ptr::null_mut()
+    });
+    let mut rval = RootedValue::new(cx, UndefinedValue());
+    let mut index = 0;
+    // The iterable may change while the callback runs, so its length is checked every time.
+    while index < iterable.get_iterable_length() {
+        let mut key = RootedValue::new(cx, UndefinedValue());
+        let mut value = RootedValue::new(cx, UndefinedValue());
+        unsafe {
+            iterable.get_key_at_index(index).to_jsval(cx, key.handle_mut());
+            iterable.get_value_at_index(index).to_jsval(cx, value.handle_mut());
+        }
+        let args: [JSVal; 3] = [value.ptr, key.ptr, ObjectValue(unsafe { &*obj.get() })];
+        let ok = unsafe {
+            JS_CallFunctionValue(cx, this_arg.handle(), callback.handle(),
+                                 &HandleValueArray {
+                                     length_: args.len(),
+                                     elements_: args.as_ptr(),
+                                 }, rval.handle_mut())
+        };
+        if !ok {
+            return Err(Error::JSFailed);
+        }
+        index += 1;
+    }
+    Ok(())
+}
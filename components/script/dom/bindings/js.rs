@@ -24,6 +24,7 @@
 //!
 
 use core::nonzero::NonZero;
+use dom::bindings::audit;
 use dom::bindings::conversions::DerivedFrom;
 use dom::bindings::inheritance::Castable;
 use dom::bindings::reflector::{Reflectable, Reflector};
@@ -34,6 +35,7 @@ use heapsize::HeapSizeOf;
 use js::jsapi::{Heap, JSObject, JSTracer};
 use js::jsval::JSVal;
 use layout_interface::TrustedNodeAddress;
+use libc;
 use script_thread::STACK_ROOTS;
 use std::cell::UnsafeCell;
 use std::default::Default;
@@ -100,6 +102,7 @@ impl<T: Reflectable> Deref for JS<T> {
 
     fn deref(&self) -> &T {
         debug_assert!(thread_state::get().is_script());
+        audit::check_alive(*self.ptr as *const libc::c_void);
         // We can only have &JS<T> from a rooted thing, so it's safe to deref
         // it to &T.
         unsafe { &**self.ptr }
@@ -582,6 +585,7 @@ impl<T: Reflectable> Root<T> {
     /// out references which cannot outlive this new `Root`.
     pub fn new(unrooted: NonZero<*const T>) -> Root<T> {
         debug_assert!(thread_state::get().is_script());
+        audit::check_alive(*unrooted as *const libc::c_void);
         STACK_ROOTS.with(|ref collection| {
             let RootCollectionPtr(collection) = collection.get().unwrap();
             unsafe { (*collection).root(&*(**unrooted).reflector()) }
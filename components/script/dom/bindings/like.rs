@@ -0,0 +1,235 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Implementation of `maplike<..., ...>` and `setlike<...>` WebIDL declarations.
+//!
+//! The entries of a maplike or setlike object are stored in a backing JS `Map`
+//! or `Set` object, created when it is first used and kept in the
+//! `DOM_BACKING_OBJECT_SLOT` slot of the reflector. Codegen implements the
+//! methods of the declaration on top of it, and the DOM object itself can
+//! change its entries with the functions below.
+
+use dom::bindings::error::{Error, Fallible};
+use js::jsapi::{HandleObject, HandleValue, HandleValueArray, JSContext, JSObject};
+use js::jsapi::{JS_CallFunctionValue, JS_GetElement, JS_GetProperty, JS_GetReservedSlot};
+use js::jsapi::{JS_SetReservedSlot, MapClear, MapDelete, MapEntries, MapGet, MapHas, MapKeys};
+use js::jsapi::{MapSet, MapSize, MapValues, MutableHandleObject, MutableHandleValue, NewMapObject};
+use js::jsapi::{NewSetObject, RootedObject, RootedValue, SetAdd, SetClear, SetDelete, SetEntries};
+use js::jsapi::{SetHas, SetSize, SetValues};
+use js::jsval::{JSVal, ObjectValue, UndefinedValue};
+use std::ptr;
+
+/// The index of the slot wherein the backing object of maplike and setlike
+/// bindings is stored. We use slot 1 for holding it, like weak-referenceable
+/// bindings do, so codegen disallows weak-referenceable maplike and setlike
+/// interfaces and maplike and setlike globals.
+pub const DOM_BACKING_OBJECT_SLOT: u32 = 1;
+
+/// Whether an interface is maplike or setlike.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Like {
+    /// A `maplike` declaration, backed by a `Map`.
+    Map,
+    /// A `setlike` declaration, backed by a `Set`.
+    Set,
+}
+
+/// The iterators of a maplike or setlike object.
+#[derive(Clone, Copy)]
+pub enum LikeIterator {
+    /// The iterator returned by `keys()`.
+    Keys,
+    /// The iterator returned by `values()`.
+    Values,
+    /// The iterator returned by `entries()`.
+    Entries,
+}
+
+/// Get the backing object of the maplike or setlike object `obj`, creating it
+/// if needed.
+pub unsafe fn get_backing_object(cx: *mut JSContext,
+                                 obj: HandleObject,
+                                 like: Like,
+                                 rval: MutableHandleObject)
+                                 -> Fallible<()> {
+    let slot = JS_GetReservedSlot(obj.get(), DOM_BACKING_OBJECT_SLOT);
+    if slot.is_object() {
+        rval.set(slot.to_object());
+        return Ok(());
+    }
+    rval.set(match like {
+        Like::Map => NewMapObject(cx),
+        Like::Set => NewSetObject(cx),
+    });
+    if rval.get().is_null() {
+        return Err(Error::JSFailed);
+    }
+    JS_SetReservedSlot(obj.get(), DOM_BACKING_OBJECT_SLOT, ObjectValue(&*rval.get()));
+    Ok(())
+}
+
+fn check(ok: bool) -> Fallible<()> {
+    if ok {
+        Ok(())
+    } else {
+        Err(Error::JSFailed)
+    }
+}
+
+/// Implements the `size` attribute.
+pub unsafe fn size(cx: *mut JSContext, obj: HandleObject, like: Like) -> Fallible<u32> {
+    let mut backing = RootedObject::new(cx, ptr::null_mut());
+    try!(get_backing_object(cx, obj, like, backing.handle_mut()));
+    Ok(match like {
+        Like::Map => MapSize(cx, backing.handle()),
+        Like::Set => SetSize(cx, backing.handle()),
+    })
+}
+
+/// Implements the `has` method.
+pub unsafe fn has(cx: *mut JSContext, obj: HandleObject, like: Like, key: HandleValue)
+                  -> Fallible<bool> {
+    let mut backing = RootedObject::new(cx, ptr::null_mut());
+    try!(get_backing_object(cx, obj, like, backing.handle_mut()));
+    let mut result = false;
+    try!(check(match like {
+        Like::Map => MapHas(cx, backing.handle(), key, &mut result),
+        Like::Set => SetHas(cx, backing.handle(), key, &mut result),
+    }));
+    Ok(result)
+}
+
+/// Implements the `get` method of maplike objects.
+pub unsafe fn get(cx: *mut JSContext, obj: HandleObject, key: HandleValue) -> Fallible<JSVal> {
+    let mut backing = RootedObject::new(cx, ptr::null_mut());
+    try!(get_backing_object(cx, obj, Like::Map, backing.handle_mut()));
+    let mut value = RootedValue::new(cx, UndefinedValue());
+    try!(check(MapGet(cx, backing.handle(), key, value.handle_mut())));
+    Ok(value.ptr)
+}
+
+/// Implements the `set` method of maplike objects, and lets the DOM object
+/// set an entry of its own.
+pub unsafe fn set(cx: *mut JSContext, obj: HandleObject, key: HandleValue, value: HandleValue)
+                  -> Fallible<*mut JSObject> {
+    let mut backing = RootedObject::new(cx, ptr::null_mut());
+    try!(get_backing_object(cx, obj, Like::Map, backing.handle_mut()));
+    try!(check(MapSet(cx, backing.handle(), key, value)));
+    Ok(obj.get())
+}
+
+/// Implements the `add` method of setlike objects, and lets the DOM object
+/// add an entry of its own.
+pub unsafe fn add(cx: *mut JSContext, obj: HandleObject, key: HandleValue)
+                  -> Fallible<*mut JSObject> {
+    let mut backing = RootedObject::new(cx, ptr::null_mut());
+    try!(get_backing_object(cx, obj, Like::Set, backing.handle_mut()));
+    try!(check(SetAdd(cx, backing.handle(), key)));
+    Ok(obj.get())
+}
+
+/// Implements the `delete` method, and lets the DOM object remove an entry.
+pub unsafe fn delete(cx: *mut JSContext, obj: HandleObject, like: Like, key: HandleValue)
+                     -> Fallible<bool> {
+    let mut backing = RootedObject::new(cx, ptr::null_mut());
+    try!(get_backing_object(cx, obj, like, backing.handle_mut()));
+    let mut result = false;
+    try!(check(match like {
+        Like::Map => MapDelete(cx, backing.handle(), key, &mut result),
+        Like::Set => SetDelete(cx, backing.handle(), key, &mut result),
+    }));
+    Ok(result)
+}
+
+/// Implements the `clear` method, and lets the DOM object remove all entries.
+pub unsafe fn clear(cx: *mut JSContext, obj: HandleObject, like: Like) -> Fallible<()> {
+    let mut backing = RootedObject::new(cx, ptr::null_mut());
+    try!(get_backing_object(cx, obj, like, backing.handle_mut()));
+    check(match like {
+        Like::Map => MapClear(cx, backing.handle()),
+        Like::Set => SetClear(cx, backing.handle()),
+    })
+}
+
+/// Implements the `keys`, `values` and `entries` methods.
+pub unsafe fn iterator(cx: *mut JSContext,
+                       obj: HandleObject,
+                       like: Like,
+                       type_: LikeIterator)
+                       -> Fallible<*mut JSObject> {
+    let mut backing = RootedObject::new(cx, ptr::null_mut());
+    try!(get_backing_object(cx, obj, like, backing.handle_mut()));
+    let mut iterator = RootedValue::new(cx, UndefinedValue());
+    try!(check(match (like, type_) {
+        (Like::Map, LikeIterator::Keys) => MapKeys(cx, backing.handle(), iterator.handle_mut()),
+        (Like::Map, LikeIterator::Values) => {
+            MapValues(cx, backing.handle(), iterator.handle_mut())
+        }
+        (Like::Map, LikeIterator::Entries) => {
+            MapEntries(cx, backing.handle(), iterator.handle_mut())
+        }
+        // The keys of a set are its values, and `keys()` is `values()`.
+        (Like::Set, LikeIterator::Keys) |
+        (Like::Set, LikeIterator::Values) => SetValues(cx, backing.handle(), iterator.handle_mut()),
+        (Like::Set, LikeIterator::Entries) => {
+            SetEntries(cx, backing.handle(), iterator.handle_mut())
+        }
+    }));
+    Ok(iterator.ptr.to_object())
+}
+
+/// Call the `next` method of `iterator`, storing the value of the result in
+/// `value`. Returns false once the iterator is done.
+unsafe fn next(cx: *mut JSContext, iterator: HandleObject, value: MutableHandleValue)
+               -> Fallible<bool> {
+    let mut next = RootedValue::new(cx, UndefinedValue());
+    try!(check(JS_GetProperty(cx, iterator, b"next\0".as_ptr() as *const _, next.handle_mut())));
+    let mut result = RootedValue::new(cx, UndefinedValue());
+    try!(check(JS_CallFunctionValue(cx, iterator, next.handle(),
+                                    &HandleValueArray { length_: 0, elements_: ptr::null() },
+                                    result.handle_mut())));
+    let result = RootedObject::new(cx, result.ptr.to_object());
+    let mut done = RootedValue::new(cx, UndefinedValue());
+    try!(check(JS_GetProperty(cx, result.handle(), b"done\0".as_ptr() as *const _,
+                              done.handle_mut())));
+    if done.ptr.to_boolean() {
+        return Ok(false);
+    }
+    try!(check(JS_GetProperty(cx, result.handle(), b"value\0".as_ptr() as *const _, value)));
+    Ok(true)
+}
+
+/// Implements the `forEach` method, calling `callback` with the value, the key
+/// and `obj` for each entry.
+pub unsafe fn for_each(cx: *mut JSContext,
+                       obj: HandleObject,
+                       like: Like,
+                       callback: *mut JSObject,
+                       this_arg: HandleValue)
+                       -> Fallible<()> {
+    let iterator = RootedObject::new(cx, try!(iterator(cx, obj, like, LikeIterator::Entries)));
+    let callback = RootedValue::new(cx, ObjectValue(&*callback));
+    let this_arg = RootedObject::new(cx, if this_arg.is_object() {
+        this_arg.to_object()
+    } else {
+        ptr::null_mut()
+    });
+    let mut entry = RootedValue::new(cx, UndefinedValue());
+    let mut rval = RootedValue::new(cx, UndefinedValue());
+    while try!(next(cx, iterator.handle(), entry.handle_mut())) {
+        // Entries are `[key, value]` arrays, also for sets whose keys are their values.
+        let entry = RootedObject::new(cx, entry.ptr.to_object());
+        let mut key = RootedValue::new(cx, UndefinedValue());
+        let mut value = RootedValue::new(cx, UndefinedValue());
+        try!(check(JS_GetElement(cx, entry.handle(), 0, key.handle_mut())));
+        try!(check(JS_GetElement(cx, entry.handle(), 1, value.handle_mut())));
+        let args: [JSVal; 3] = [value.ptr, key.ptr, ObjectValue(&*obj.get())];
+        try!(check(JS_CallFunctionValue(cx, this_arg.handle(), callback.handle(),
+                                        &HandleValueArray {
+                                            length_: args.len(),
+                                            elements_: args.as_ptr(),
+                                        }, rval.handle_mut())));
+    }
+    Ok(())
+}
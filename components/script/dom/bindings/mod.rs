@@ -128,6 +128,7 @@
 //! return `Err()` from the method with the appropriate [error value]
 //! (error/enum.Error.html).
 
+pub mod audit;
 pub mod callback;
 pub mod cell;
 pub mod conversions;
@@ -136,7 +137,10 @@ pub mod global;
 pub mod guard;
 pub mod inheritance;
 pub mod interface;
+pub mod iterable;
 pub mod js;
+pub mod like;
+pub mod namespace;
 pub mod num;
 pub mod proxyhandler;
 pub mod refcounted;
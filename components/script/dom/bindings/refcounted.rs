@@ -23,6 +23,7 @@
 //! is removed.
 
 use core::nonzero::NonZero;
+use dom::bindings::audit::{Owner, TracingOwner};
 use dom::bindings::js::Root;
 use dom::bindings::reflector::{Reflectable, Reflector};
 use dom::bindings::trace::trace_reflector;
@@ -202,6 +203,7 @@ impl LiveDOMReferences {
 pub unsafe extern "C" fn trace_refcounted_objects(tracer: *mut JSTracer,
                                                   _data: *mut os::raw::c_void) {
     debug!("tracing live refcounted references");
+    let _owner = TracingOwner::new(Owner::Roots("refcounted references"));
     LIVE_REFERENCES.with(|ref r| {
         let r = r.borrow();
         let live_references = r.as_ref().unwrap();
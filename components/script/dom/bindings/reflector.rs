@@ -4,9 +4,11 @@
 
 //! The `Reflector` struct.
 
+use dom::bindings::audit;
 use dom::bindings::global::{GlobalRef, GlobalRoot, global_root_from_reflector};
 use dom::bindings::js::Root;
 use js::jsapi::{HandleObject, JSContext, JSObject};
+use libc;
 use std::cell::UnsafeCell;
 use std::ptr;
 
@@ -16,7 +18,11 @@ pub fn reflect_dom_object<T: Reflectable>(obj: Box<T>,
                                           global: GlobalRef,
                                           wrap_fn: fn(*mut JSContext, GlobalRef, Box<T>) -> Root<T>)
                                           -> Root<T> {
-    wrap_fn(global.get_cx(), global, obj)
+    let object = wrap_fn(global.get_cx(), global, obj);
+    audit::register(&*object as *const T as *const libc::c_void,
+                    object.reflector().get_jsobject().get(),
+                    global.pipeline());
+    object
 }
 
 /// A struct to store a reference to the reflector of a DOM object.
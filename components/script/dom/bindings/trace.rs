@@ -34,6 +34,7 @@ use canvas_traits::{CompositionOrBlending, LineCapStyle, LineJoinStyle, Repetiti
 use cssparser::RGBA;
 use devtools_traits::CSSError;
 use devtools_traits::WorkerId;
+use dom::bindings::audit;
 use dom::bindings::js::{JS, Root};
 use dom::bindings::refcounted::Trusted;
 use dom::bindings::reflector::{Reflectable, Reflector};
@@ -67,6 +68,7 @@ use profile_traits::mem::ProfilerChan as MemProfilerChan;
 use profile_traits::time::ProfilerChan as TimeProfilerChan;
 use script_runtime::ScriptChan;
 use script_traits::{TimerEventId, TimerSource, TouchpadPressurePhase, UntrustedNodeAddress};
+use script_traits::VisualViewportData;
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use std::boxed::FnBox;
@@ -114,6 +116,9 @@ pub fn trace_jsval(tracer: *mut JSTracer, description: &str, val: &Heap<JSVal>)
         }
 
         debug!("tracing value {}", description);
+        if val.get().is_object() {
+            audit::record_edge(description, val.get().to_object());
+        }
         CallValueTracer(tracer,
                         val.ptr.get() as *mut _,
                         GCTraceKindToAscii(val.get().trace_kind()));
@@ -125,6 +130,7 @@ pub fn trace_jsval(tracer: *mut JSTracer, description: &str, val: &Heap<JSVal>)
 pub fn trace_reflector(tracer: *mut JSTracer, description: &str, reflector: &Reflector) {
     unsafe {
         debug!("tracing reflector {}", description);
+        audit::record_edge(description, reflector.get_jsobject().get());
         CallUnbarrieredObjectTracer(tracer,
                                     reflector.rootable(),
                                     GCTraceKindToAscii(TraceKind::Object));
@@ -135,6 +141,7 @@ pub fn trace_reflector(tracer: *mut JSTracer, description: &str, reflector: &Ref
 pub fn trace_object(tracer: *mut JSTracer, description: &str, obj: &Heap<*mut JSObject>) {
     unsafe {
         debug!("tracing {}", description);
+        audit::record_edge(description, obj.get());
         CallObjectTracer(tracer,
                          obj.ptr.get() as *mut _,
                          GCTraceKindToAscii(TraceKind::Object));
@@ -320,6 +327,7 @@ no_jsmanaged_fields!(ElementSnapshot);
 no_jsmanaged_fields!(HttpsState);
 no_jsmanaged_fields!(SharedRt);
 no_jsmanaged_fields!(TouchpadPressurePhase);
+no_jsmanaged_fields!(VisualViewportData);
 no_jsmanaged_fields!(ReferrerPolicy);
 no_jsmanaged_fields!(ResourceThreads);
 
@@ -10,6 +10,7 @@ use dom::bindings::global::GlobalRef;
 use dom::bindings::js::Root;
 use dom::bindings::reflector::{Reflectable, Reflector, reflect_dom_object};
 use dom::bindings::str::DOMString;
+use script_traits::PageError;
 use std::collections::HashMap;
 use time::{Timespec, get_time};
 
@@ -85,6 +86,8 @@ impl ConsoleMethods for Console {
     fn Error(&self, messages: Vec<DOMString>) {
         for message in messages {
             println!("{}", message);
+            let error = PageError::ConsoleError(String::from(message.clone()));
+            self.global().r().report_page_error(error);
             self.send_to_devtools(LogLevel::Error, message);
         }
     }
@@ -94,6 +97,8 @@ impl ConsoleMethods for Console {
         if !condition {
             let message = message.unwrap_or_else(|| DOMString::from("no message"));
             println!("Assertion failed: {}", message);
+            let error = PageError::ConsoleError(format!("Assertion failed: {}", message));
+            self.global().r().report_page_error(error);
             self.send_to_devtools(LogLevel::Error, message);
         }
     }
@@ -2,10 +2,13 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use app_units::Au;
 use document_loader::{DocumentLoader, LoadType};
 use dom::activation::{ActivationSource, synthetic_click_activation};
+use dom::animation::Animation;
 use dom::attr::{Attr, AttrValue};
 use dom::bindings::cell::DOMRefCell;
+use dom::bindings::codegen::Bindings::CharacterDataBinding::CharacterDataMethods;
 use dom::bindings::codegen::Bindings::DOMRectBinding::DOMRectMethods;
 use dom::bindings::codegen::Bindings::DocumentBinding;
 use dom::bindings::codegen::Bindings::DocumentBinding::{DocumentMethods, DocumentReadyState};
@@ -17,7 +20,7 @@ use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
 use dom::bindings::codegen::Bindings::NodeFilterBinding::NodeFilter;
 use dom::bindings::codegen::Bindings::PerformanceBinding::PerformanceMethods;
 use dom::bindings::codegen::Bindings::TouchBinding::TouchMethods;
-use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use dom::bindings::codegen::Bindings::WindowBinding::{ScrollBehavior, WindowMethods};
 use dom::bindings::codegen::UnionTypes::NodeOrString;
 use dom::bindings::error::{Error, ErrorResult, Fallible};
 use dom::bindings::global::GlobalRef;
@@ -32,6 +35,7 @@ use dom::bindings::trace::RootedVec;
 use dom::bindings::xmlname::XMLName::InvalidXMLName;
 use dom::bindings::xmlname::{validate_and_extract, namespace_from_domstring, xml_name_type};
 use dom::browsingcontext::BrowsingContext;
+use dom::characterdata::CharacterData;
 use dom::closeevent::CloseEvent;
 use dom::comment::Comment;
 use dom::customevent::CustomEvent;
@@ -58,10 +62,12 @@ use dom::htmlheadelement::HTMLHeadElement;
 use dom::htmlhtmlelement::HTMLHtmlElement;
 use dom::htmliframeelement::HTMLIFrameElement;
 use dom::htmlimageelement::HTMLImageElement;
+use dom::htmlinputelement::HTMLInputElement;
 use dom::htmllinkelement::HTMLLinkElement;
 use dom::htmlmetaelement::HTMLMetaElement;
 use dom::htmlscriptelement::HTMLScriptElement;
 use dom::htmlstyleelement::HTMLStyleElement;
+use dom::htmltextareaelement::HTMLTextAreaElement;
 use dom::htmltitleelement::HTMLTitleElement;
 use dom::keyboardevent::KeyboardEvent;
 use dom::location::Location;
@@ -87,7 +93,10 @@ use dom::webglcontextevent::WebGLContextEvent;
 use dom::window::{ReflowReason, Window};
 use encoding::EncodingRef;
 use encoding::all::UTF_8;
+use euclid::length::Length;
 use euclid::point::Point2D;
+use euclid::rect::Rect;
+use euclid::size::Size2D;
 use html5ever::tree_builder::{LimitedQuirks, NoQuirks, Quirks, QuirksMode};
 use ipc_channel::ipc::{self, IpcSender};
 use js::jsapi::JS_GetRuntime;
@@ -97,7 +106,8 @@ use msg::constellation_msg::{ALT, CONTROL, SHIFT, SUPER};
 use msg::constellation_msg::{Key, KeyModifiers, KeyState};
 use msg::constellation_msg::{PipelineId, ReferrerPolicy, SubpageId};
 use net_traits::CookieSource::NonHTTP;
-use net_traits::CoreResourceMsg::{GetCookiesForUrl, SetCookiesForUrl};
+use net_traits::CoreResourceMsg::{GetCookiesForUrl, QueueReport, SetCookiesForUrl};
+use net_traits::reporting::{Report, ReportType};
 use net_traits::response::HttpsState;
 use net_traits::{AsyncResponseTarget, PendingAsyncLoad, IpcSend};
 use num_traits::ToPrimitive;
@@ -105,7 +115,7 @@ use origin::Origin;
 use parse::{ParserRoot, ParserRef, MutNullableParserField};
 use script_thread::{MainThreadScriptMsg, Runnable};
 use script_traits::UntrustedNodeAddress;
-use script_traits::{AnimationState, MouseButton, MouseEventType, MozBrowserEvent};
+use script_traits::{AnimationState, MouseButton, MouseEventType, MozBrowserEvent, PageError};
 use script_traits::{ScriptMsg as ConstellationMsg, TouchpadPressurePhase};
 use script_traits::{TouchEventType, TouchId};
 use std::ascii::AsciiExt;
@@ -125,9 +135,10 @@ use style::restyle_hints::ElementSnapshot;
 use style::servo::Stylesheet;
 use task_source::dom_manipulation::DOMManipulationTask;
 use time;
+use timers::{OneshotTimerCallback, OneshotTimerHandle};
 use url::Url;
 use url::percent_encoding::percent_decode;
-use util::prefs::mozbrowser_enabled;
+use util::prefs::{self, mozbrowser_enabled};
 use util::str::{split_html_space_chars, str_join};
 
 #[derive(JSTraceable, PartialEq, HeapSizeOf)]
@@ -180,6 +191,10 @@ pub struct Document {
     possibly_focused: MutNullableHeap<JS<Element>>,
     /// The element that currently has the document focus context.
     focused: MutNullableHeap<JS<Element>>,
+    /// The text node that the caret of caret browsing is in.
+    caret: MutNullableHeap<JS<Text>>,
+    /// The byte offset of the caret of caret browsing in its text node.
+    caret_offset: Cell<usize>,
     /// The script element that is currently executing.
     current_script: MutNullableHeap<JS<HTMLScriptElement>>,
     /// https://html.spec.whatwg.org/multipage/#pending-parsing-blocking-script
@@ -239,6 +254,16 @@ pub struct Document {
     origin: Origin,
     ///  https://w3c.github.io/webappsec-referrer-policy/#referrer-policy-states
     referrer_policy: Option<ReferrerPolicy>,
+    /// The element under the mouse, whose tooltip is shown once the mouse has dwelt over it.
+    tooltip_target: MutNullableHeap<JS<Element>>,
+    /// The timer that shows the tooltip of `tooltip_target`.
+    tooltip_timer: DOMRefCell<Option<OneshotTimerHandle>>,
+    /// Whether the tooltip of `tooltip_target` is shown.
+    tooltip_shown: Cell<bool>,
+    /// The animations that script created with `Element.animate()` and that have not been
+    /// cancelled, by the number that layout knows them by.
+    animations: DOMRefCell<HashMap<u32, JS<Animation>>>,
+    next_animation_id: Cell<u32>,
 }
 
 #[derive(JSTraceable, HeapSizeOf)]
@@ -330,6 +355,23 @@ impl Document {
         self.trigger_mozbrowser_event(MozBrowserEvent::SecurityChange(https_state));
     }
 
+    /// Queues a report for delivery to this document's reporting endpoints.
+    /// https://w3c.github.io/reporting/#queue-report
+    pub fn queue_report(&self, report: Report) {
+        // Violations of the content security policy are also errors of the page.
+        if report.type_ == ReportType::CspViolation {
+            let body = &report.body;
+            let field = |key: &str| body.get(key).cloned().unwrap_or_default();
+            let error = PageError::CspViolation {
+                directive: field("effectiveDirective"),
+                blocked_url: field("blockedURL"),
+            };
+            let msg = ConstellationMsg::PageError(self.window.pipeline(), error);
+            let _ = self.window.constellation_chan().send(msg);
+        }
+        let _ = self.window.resource_threads().send(QueueReport(self.window.pipeline(), report));
+    }
+
     // https://html.spec.whatwg.org/multipage/#fully-active
     pub fn is_fully_active(&self) -> bool {
         let browsing_context = match self.browsing_context() {
@@ -844,6 +886,7 @@ impl Document {
                 // If there's no point, there's no target under the mouse
                 // FIXME: dispatch mouseout here. We have no point.
                 prev_mouse_over_target.set(None);
+                self.update_tooltip(None);
                 return;
             }
             Some(ref client_point) => {
@@ -860,6 +903,7 @@ impl Document {
                 .filter_map(Root::downcast::<Element>)
                 .next()
         });
+        self.update_tooltip(maybe_new_target.r());
 
         // Send mousemove event to topmost target, and forward it if it's an iframe
         if let Some(ref new_target) = maybe_new_target {
@@ -935,6 +979,119 @@ impl Document {
                            ReflowReason::MouseEvent);
     }
 
+    /// Shows the tooltip of `target`, the element under the mouse, once the mouse has dwelt
+    /// over it, and hides that of the previous element.
+    fn update_tooltip(&self, target: Option<&Element>) {
+        if self.tooltip_target == target {
+            return;
+        }
+        let previous_target = self.tooltip_target.get();
+        self.tooltip_target.set(target);
+
+        if let Some(handle) = self.tooltip_timer.borrow_mut().take() {
+            self.window.unschedule_callback(handle);
+        }
+        // The document in an iframe never learns that the mouse left it, so its tooltip is hidden
+        // from here.
+        let previous_target_is_iframe =
+            previous_target.map_or(false, |target| target.is::<HTMLIFrameElement>());
+        if self.tooltip_shown.get() || previous_target_is_iframe {
+            self.tooltip_shown.set(false);
+            let event = ConstellationMsg::HideTooltip(self.window.pipeline());
+            self.window.constellation_chan().send(event).unwrap();
+        }
+
+        match target {
+            // The document in the iframe shows the tooltips of its elements.
+            Some(target) if !target.is::<HTMLIFrameElement>() => {
+                if advisory_information(target).is_none() {
+                    return;
+                }
+            }
+            _ => return,
+        }
+        let callback = OneshotTimerCallback::Tooltip(TooltipCallback {
+            document: Trusted::new(self),
+        });
+        let duration = Length::new(TOOLTIP_DELAY_MS);
+        *self.tooltip_timer.borrow_mut() = Some(self.window.schedule_callback(callback, duration));
+    }
+
+    /// Shows the tooltip of the element under the mouse, now that the mouse has dwelt over it.
+    fn show_tooltip(&self) {
+        self.tooltip_timer.borrow_mut().take();
+        let target = match self.tooltip_target.get() {
+            Some(target) => target,
+            None => return,
+        };
+        // The title may have changed in the meantime.
+        let text = match advisory_information(target.r()) {
+            Some(text) => text,
+            None => return,
+        };
+        self.tooltip_shown.set(true);
+        let anchor = self.client_rect(target.upcast());
+        let event = ConstellationMsg::ShowTooltip(self.window.pipeline(),
+                                                  String::from(text),
+                                                  anchor);
+        self.window.constellation_chan().send(event).unwrap();
+    }
+
+    /// Returns a number for a new animation, which identifies it to layout.
+    pub fn next_animation_id(&self) -> u32 {
+        let id = self.next_animation_id.get();
+        self.next_animation_id.set(id + 1);
+        id
+    }
+
+    pub fn add_animation(&self, id: u32, animation: &Animation) {
+        self.animations.borrow_mut().insert(id, JS::from_ref(animation));
+    }
+
+    pub fn remove_animation(&self, id: u32) {
+        self.animations.borrow_mut().remove(&id);
+    }
+
+    pub fn get_animation(&self, id: u32) -> Option<Root<Animation>> {
+        self.animations.borrow().get(&id).map(|animation| Root::from_ref(&**animation))
+    }
+
+    /// Marks an animation that layout has finished running as finished.
+    pub fn finish_animation(&self, id: u32) {
+        let animation = self.animations.borrow_mut().remove(&id);
+        if let Some(animation) = animation {
+            animation.finish();
+        }
+    }
+
+    /// Shows the tooltip of the document in the iframe with the given subpage id, next to the
+    /// given rect of its viewport.
+    pub fn show_frame_tooltip(&self, subpage_id: SubpageId, text: String, anchor: Rect<f32>) {
+        let iframe = match self.find_iframe(subpage_id) {
+            Some(iframe) => iframe,
+            None => return,
+        };
+        let origin = self.client_rect(iframe.upcast()).origin;
+        let event = ConstellationMsg::ShowTooltip(self.window.pipeline(),
+                                                  text,
+                                                  anchor.translate(&origin));
+        self.window.constellation_chan().send(event).unwrap();
+    }
+
+    /// Hides the tooltip of the document in one of the iframes of this document.
+    pub fn hide_frame_tooltip(&self) {
+        let event = ConstellationMsg::HideTooltip(self.window.pipeline());
+        self.window.constellation_chan().send(event).unwrap();
+    }
+
+    /// The rect of the border box of `node` in the viewport, in CSS pixels.
+    fn client_rect(&self, node: &Node) -> Rect<f32> {
+        let rect = node.bounding_content_box();
+        Rect::new(Point2D::new(rect.origin.x.to_f32_px() - self.window.PageXOffset() as f32,
+                               rect.origin.y.to_f32_px() - self.window.PageYOffset() as f32),
+                  Size2D::new(rect.size.width.to_f32_px(), rect.size.height.to_f32_px()))
+    }
+
     pub fn handle_touch_event(&self,
                               js_runtime: *mut JSRuntime,
                               event_type: TouchEventType,
@@ -1121,7 +1278,12 @@ impl Document {
             // TODO: if keypress event is canceled, prevent firing input events
         }
 
-        if !prevented {
+        // Arrow keys that the page leaves alone move the caret or the focus in those modes,
+        // rather than scrolling.
+        let navigated = !prevented && state != KeyState::Released && modifiers.is_empty() &&
+                        self.navigate_with_key(key);
+
+        if !prevented && !navigated {
             constellation.send(ConstellationMsg::SendKeyEvent(key, state, modifiers)).unwrap();
         }
 
@@ -1153,11 +1315,189 @@ impl Document {
             _ => (),
         }
 
+        // Edits of text controls are laid out after the key events queued behind this one.
+        if self.window.text_editing_reflow_pending() {
+            return;
+        }
+
         self.window.reflow(ReflowGoal::ForDisplay,
                            ReflowQueryType::NoQuery,
                            ReflowReason::KeyEvent);
     }
 
+    /// Moves the caret or the focus with `key`, when the embedder enables caret browsing or
+    /// spatial navigation through the `dom.caret_browsing.enabled` and
+    /// `dom.spatial_navigation.enabled` preferences. Caret browsing takes the arrow keys when
+    /// both are enabled. Returns whether the key was used.
+    fn navigate_with_key(&self, key: Key) -> bool {
+        if prefs::get_pref("dom.caret_browsing.enabled").as_boolean().unwrap_or(false) {
+            // Text controls have a caret of their own.
+            let in_text_control = self.get_focused_element().map_or(false, |focused| {
+                focused.is::<HTMLInputElement>() || focused.is::<HTMLTextAreaElement>()
+            });
+            if !in_text_control && self.move_caret(key) {
+                return true;
+            }
+        }
+        if prefs::get_pref("dom.spatial_navigation.enabled").as_boolean().unwrap_or(false) {
+            return self.navigate_spatially(key);
+        }
+        false
+    }
+
+    /// Moves the focus to the focusable area that is nearest to the focused element, or to the
+    /// edge of the viewport if none is, in the direction of an arrow key.
+    /// https://drafts.csswg.org/css-nav-1/
+    fn navigate_spatially(&self, key: Key) -> bool {
+        let viewport = self.window.current_viewport();
+        let focused = self.get_focused_element();
+        let from = match focused {
+            Some(ref focused) => focused.upcast::<Node>().bounding_content_box(),
+            // Start from the edge of the viewport that is behind the direction.
+            None => {
+                let (origin, size) = (viewport.origin, viewport.size);
+                match key {
+                    Key::Down => Rect::new(origin, Size2D::new(size.width, Au(0))),
+                    Key::Up => Rect::new(Point2D::new(origin.x, viewport.max_y()),
+                                         Size2D::new(size.width, Au(0))),
+                    Key::Right => Rect::new(origin, Size2D::new(Au(0), size.height)),
+                    Key::Left => Rect::new(Point2D::new(viewport.max_x(), origin.y),
+                                           Size2D::new(Au(0), size.height)),
+                    _ => return false,
+                }
+            }
+        };
+
+        let mut best: Option<(Au, Root<Element>, Rect<Au>)> = None;
+        let elements = self.upcast::<Node>().traverse_preorder()
+                           .filter_map(Root::downcast::<Element>);
+        for element in elements {
+            if !element.is_focusable_area() || focused.r() == Some(&*element) {
+                continue;
+            }
+            let rect = element.upcast::<Node>().bounding_content_box();
+            // Elements that are not rendered have no box.
+            if rect.size.width == Au(0) && rect.size.height == Au(0) {
+                continue;
+            }
+            // Without a focused element, only what is on screen is considered.
+            if focused.is_none() && !rect.intersects(&viewport) {
+                continue;
+            }
+            let distance = match spatial_distance(&from, &rect, key) {
+                Some(distance) => distance,
+                None => continue,
+            };
+            // Ties go to the first candidate in tree order.
+            if best.as_ref().map_or(true, |&(best_distance, _, _)| distance < best_distance) {
+                best = Some((distance, element, rect));
+            }
+        }
+
+        let (element, rect) = match best {
+            Some((_, element, rect)) => (element, rect),
+            None => return false,
+        };
+        self.begin_focus_transaction();
+        self.request_focus(&element);
+        self.commit_focus_transaction(FocusType::Element);
+        self.scroll_rect_into_view(&rect);
+        true
+    }
+
+    /// Moves the caret of caret browsing with `key`, placing it at the start of the first text
+    /// of the body if there is no caret yet. Left and right move it by one character, while up
+    /// and down move it to the previous or next text node, as script does not know the lines
+    /// that text is laid out in.
+    fn move_caret(&self, key: Key) -> bool {
+        let texts = match self.GetBody() {
+            Some(body) => {
+                body.upcast::<Node>().traverse_preorder()
+                    .filter_map(Root::downcast::<Text>)
+                    .filter(|text| is_caret_browsing_text(text))
+                    .collect::<Vec<_>>()
+            }
+            None => return false,
+        };
+        let current = self.caret.get().and_then(|caret| {
+            texts.iter().position(|text| *text == caret)
+        });
+        let index = match current {
+            Some(index) => index,
+            None => {
+                match key {
+                    Key::Left | Key::Right | Key::Up | Key::Down | Key::Home | Key::End => (),
+                    _ => return false,
+                }
+                return match texts.first() {
+                    Some(text) => {
+                        self.set_caret(text, 0);
+                        true
+                    }
+                    None => false,
+                };
+            }
+        };
+
+        let data = String::from(texts[index].upcast::<CharacterData>().Data());
+        // The text may have changed since the caret was put in it.
+        let offset = match self.caret_offset.get() {
+            offset if data.is_char_boundary(offset) => offset,
+            _ => data.len(),
+        };
+        let (index, offset) = match key {
+            Key::Left if offset > 0 => {
+                (index, data[..offset].char_indices().next_back().map_or(0, |(i, _)| i))
+            }
+            Key::Right if offset < data.len() => {
+                (index, offset + data[offset..].chars().next().map_or(0, char::len_utf8))
+            }
+            Key::Left | Key::Up if index > 0 => {
+                let previous = texts[index - 1].upcast::<CharacterData>().Data();
+                (index - 1, if key == Key::Left { previous.len() } else { 0 })
+            }
+            Key::Right | Key::Down if index + 1 < texts.len() => (index + 1, 0),
+            Key::Home => (index, 0),
+            Key::End => (index, data.len()),
+            _ => return false,
+        };
+        self.set_caret(&texts[index], offset);
+        true
+    }
+
+    fn set_caret(&self, text: &Text, offset: usize) {
+        if let Some(old) = self.caret.get() {
+            old.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        }
+        self.caret.set(Some(text));
+        self.caret_offset.set(offset);
+        let node = text.upcast::<Node>();
+        node.dirty(NodeDamage::OtherNodeDamage);
+        self.scroll_rect_into_view(&node.bounding_content_box());
+    }
+
+    /// Scrolls the viewport as little as possible for `rect` to be in it.
+    fn scroll_rect_into_view(&self, rect: &Rect<Au>) {
+        let viewport = self.window.current_viewport();
+        let x = if rect.origin.x < viewport.origin.x || rect.size.width > viewport.size.width {
+            rect.origin.x
+        } else if rect.max_x() > viewport.max_x() {
+            rect.max_x() - viewport.size.width
+        } else {
+            viewport.origin.x
+        };
+        let y = if rect.origin.y < viewport.origin.y || rect.size.height > viewport.size.height {
+            rect.origin.y
+        } else if rect.max_y() > viewport.max_y() {
+            rect.max_y() - viewport.size.height
+        } else {
+            viewport.origin.y
+        };
+        if x != viewport.origin.x || y != viewport.origin.y {
+            self.window.scroll(x.to_f64_px(), y.to_f64_px(), ScrollBehavior::Auto);
+        }
+    }
+
     // https://dom.spec.whatwg.org/#converting-nodes-into-a-node
     pub fn node_from_nodes_and_strings(&self,
                                        mut nodes: Vec<NodeOrString>)
@@ -1302,17 +1642,18 @@ impl Document {
     }
 
     /// https://html.spec.whatwg.org/multipage/#run-the-animation-frame-callbacks
-    pub fn run_the_animation_frame_callbacks(&self) {
+    /// Runs the animation frame callbacks for the animation tick at `frame_time`, as returned by
+    /// `time::precise_time_s()`, which they are given relative to the start of navigation.
+    pub fn run_the_animation_frame_callbacks(&self, frame_time: f64) {
         let mut animation_frame_list =
             mem::replace(&mut *self.animation_frame_list.borrow_mut(), vec![]);
         self.running_animation_callbacks.set(true);
-        let performance = self.window.Performance();
-        let performance = performance.r();
-        let timing = performance.Now();
+        let navigation_start = self.window.Performance().Timing().NavigationStartPrecise();
+        let timing = (frame_time * 1000000000.0 - navigation_start) / 1000000.0;
 
         for (_, callback) in animation_frame_list.drain(..) {
             if let Some(callback) = callback {
-                callback(*timing);
+                callback(timing);
             }
         }
 
@@ -1330,9 +1671,7 @@ impl Document {
 
         self.running_animation_callbacks.set(false);
 
-        self.window.reflow(ReflowGoal::ForDisplay,
-                           ReflowQueryType::NoQuery,
-                           ReflowReason::RequestAnimationFrame);
+        self.window.reflow_for_animation_tick(frame_time);
     }
 
     /// Add a load to the list of loads blocking this document's load.
@@ -1580,6 +1919,7 @@ pub enum DocumentSource {
 pub trait LayoutDocumentHelpers {
     unsafe fn is_html_document_for_layout(&self) -> bool;
     unsafe fn drain_modified_elements(&self) -> Vec<(LayoutJS<Element>, ElementSnapshot)>;
+    unsafe fn caret_offset_for_layout(&self, text: LayoutJS<Text>) -> Option<usize>;
 }
 
 #[allow(unsafe_code)]
@@ -1596,6 +1936,60 @@ impl LayoutDocumentHelpers for LayoutJS<Document> {
         let result = elements.drain().map(|(k, v)| (k.to_layout(), v)).collect();
         result
     }
+
+    #[inline]
+    unsafe fn caret_offset_for_layout(&self, text: LayoutJS<Text>) -> Option<usize> {
+        let document = &*self.unsafe_get();
+        if document.caret.get_inner_as_layout() == Some(text) {
+            Some(document.caret_offset.get())
+        } else {
+            None
+        }
+    }
+}
+
+/// How far `to` is from `from` in the direction of an arrow key, if it is in that direction
+/// at all: the gap along that direction, plus the gap across it, which counts for more when
+/// moving sideways so that what is on the same line is preferred.
+/// https://drafts.csswg.org/css-nav-1/#find-the-shortest-distance
+fn spatial_distance(from: &Rect<Au>, to: &Rect<Au>, key: Key) -> Option<Au> {
+    // The near and far edges of both rects along the direction, and their extents across it,
+    // flipped so that the direction is positive.
+    let (from_far, to_near, to_far, from_across, to_across, weight) = match key {
+        Key::Down => (from.max_y(), to.origin.y, to.max_y(),
+                      (from.origin.x, from.max_x()), (to.origin.x, to.max_x()), 2),
+        Key::Up => (-from.origin.y, -to.max_y(), -to.origin.y,
+                    (from.origin.x, from.max_x()), (to.origin.x, to.max_x()), 2),
+        Key::Right => (from.max_x(), to.origin.x, to.max_x(),
+                       (from.origin.y, from.max_y()), (to.origin.y, to.max_y()), 30),
+        Key::Left => (-from.origin.x, -to.max_x(), -to.origin.x,
+                      (from.origin.y, from.max_y()), (to.origin.y, to.max_y()), 30),
+        _ => return None,
+    };
+    // Overlapping candidates count if they are mostly past the focused element.
+    let to_middle = (to_near + to_far).scale_by(0.5);
+    if to_near < from_far && to_middle <= from_far {
+        return None;
+    }
+    let along = if to_near > from_far { to_near - from_far } else { Au(0) };
+    let across = if to_across.1 < from_across.0 {
+        from_across.0 - to_across.1
+    } else if to_across.0 > from_across.1 {
+        to_across.0 - from_across.1
+    } else {
+        Au(0)
+    };
+    Some(along + across * weight)
+}
+
+/// Whether the caret of caret browsing can be put in `text`: rendered text that is not only
+/// whitespace, outside of scripts and style sheets.
+fn is_caret_browsing_text(text: &Text) -> bool {
+    let node = text.upcast::<Node>();
+    let in_source = node.GetParentNode().map_or(false, |parent| {
+        parent.is::<HTMLScriptElement>() || parent.is::<HTMLStyleElement>()
+    });
+    !in_source && !text.upcast::<CharacterData>().Data().chars().all(char::is_whitespace)
 }
 
 /// https://url.spec.whatwg.org/#network-scheme
@@ -1672,6 +2066,8 @@ impl Document {
             domcontentloaded_dispatched: Cell::new(domcontentloaded_dispatched),
             possibly_focused: Default::default(),
             focused: Default::default(),
+            caret: Default::default(),
+            caret_offset: Cell::new(0),
             current_script: Default::default(),
             pending_parsing_blocking_script: Default::default(),
             script_blocking_stylesheets_count: Cell::new(0u32),
@@ -1701,6 +2097,11 @@ impl Document {
             origin: origin,
             //TODO - setting this for now so no Referer header set
             referrer_policy: Some(ReferrerPolicy::NoReferrer),
+            tooltip_target: Default::default(),
+            tooltip_timer: DOMRefCell::new(None),
+            tooltip_shown: Cell::new(false),
+            animations: DOMRefCell::new(HashMap::new()),
+            next_animation_id: Cell::new(0),
         }
     }
 
@@ -2856,6 +3257,38 @@ impl Runnable for DocumentProgressHandler {
     }
 }
 
+/// How long the mouse has to dwell over an element for its tooltip to be shown, in milliseconds.
+const TOOLTIP_DELAY_MS: u64 = 500;
+
+/// Shows the tooltip of the element under the mouse, once the mouse has dwelt over it.
+#[derive(JSTraceable, HeapSizeOf)]
+pub struct TooltipCallback {
+    #[ignore_heap_size_of = "Because it is non-owning"]
+    document: Trusted<Document>,
+}
+
+impl TooltipCallback {
+    pub fn invoke(self) {
+        self.document.root().show_tooltip();
+    }
+}
+
+/// The advisory information of `element`, given by the `title` attribute of the element or of
+/// its nearest ancestor that has one.
+/// https://html.spec.whatwg.org/multipage/#the-title-attribute
+fn advisory_information(element: &Element) -> Option<DOMString> {
+    let title = element.upcast::<Node>()
+                       .inclusive_ancestors()
+                       .filter_map(Root::downcast::<HTMLElement>)
+                       .filter_map(|element| {
+                           element.upcast::<Element>().get_attribute(&ns!(), &atom!("title"))
+                       })
+                       .next();
+    title.map(|title| DOMString::from(&**title.value()))
+         // An empty title hides the titles of the ancestors.
+         .and_then(|title| if title.is_empty() { None } else { Some(title) })
+}
+
 /// Specifies the type of focus event that is sent to a pipeline
 #[derive(Copy, Clone, PartialEq)]
 pub enum FocusType {
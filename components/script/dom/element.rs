@@ -8,9 +8,11 @@ use app_units::Au;
 use cssparser::{Color, ToCss};
 use devtools_traits::AttrInfo;
 use dom::activation::Activatable;
+use dom::animation::Animation;
 use dom::attr::AttrValue;
 use dom::attr::{Attr, AttrHelpersForLayout};
 use dom::bindings::cell::DOMRefCell;
+use dom::bindings::codegen::Bindings::AnimatableBinding::{Keyframe, KeyframeAnimationOptions};
 use dom::bindings::codegen::Bindings::AttrBinding::AttrMethods;
 use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
 use dom::bindings::codegen::Bindings::ElementBinding;
@@ -298,6 +300,17 @@ impl LayoutElementHelpers for LayoutJS<Element> {
             DeclarationBlock::from_declarations(Arc::new(vec![rule]))
         }
 
+        // The language of the element, and so its quotes and the fonts that the user prefers
+        // for it, which its descendants inherit.
+        let lang = (*self.unsafe_get()).get_attr_val_for_layout(&ns!(), &atom!("lang"));
+        if let Some(lang) = lang {
+            hints.push(from_declaration(PropertyDeclaration::ServoLang(
+                DeclaredValue::Value(longhands::_servo_lang::for_language(lang)))));
+        }
+        if let Some(quotes) = lang.and_then(longhands::quotes::for_language) {
+            hints.push(from_declaration(PropertyDeclaration::Quotes(DeclaredValue::Value(quotes))));
+        }
+
         let bgcolor = if let Some(this) = self.downcast::<HTMLBodyElement>() {
             this.get_background_color()
         } else if let Some(this) = self.downcast::<HTMLTableElement>() {
@@ -1961,6 +1974,17 @@ impl ElementMethods for Element {
         self.upcast::<Node>().remove_self();
     }
 
+    // https://w3c.github.io/web-animations/#dom-animatable-animate
+    fn Animate(&self, keyframes: Vec<Keyframe>, options: &KeyframeAnimationOptions)
+               -> Fallible<Root<Animation>> {
+        Animation::animate(self, keyframes, options)
+    }
+
+    // https://w3c.github.io/web-animations/#dom-animatable-getanimations
+    fn GetAnimations(&self) -> Vec<Root<Animation>> {
+        Animation::get_animations(self)
+    }
+
     // https://dom.spec.whatwg.org/#dom-element-matches
     fn Matches(&self, selectors: DOMString) -> Fallible<bool> {
         match parse_author_origin_selector_list_from_str(&selectors) {
@@ -42,7 +42,7 @@ use string_cache::Atom;
 use style::element_state::*;
 use textinput::KeyReaction::{DispatchInput, Nothing, RedrawSelection, TriggerDefaultAction};
 use textinput::Lines::Single;
-use textinput::{TextInput, SelectionDirection};
+use textinput::{TextInput, SelectionDirection, UserSelect};
 
 const DEFAULT_SUBMIT_VALUE: &'static str = "Submit";
 const DEFAULT_RESET_VALUE: &'static str = "Reset";
@@ -924,6 +924,10 @@ impl VirtualMethods for HTMLInputElement {
             (self.input_type.get() == InputType::InputText ||
              self.input_type.get() == InputType::InputPassword) {
                 if let Some(keyevent) = event.downcast::<KeyboardEvent>() {
+                    // Only the keys pressed with these modifiers select text.
+                    if keyevent.ShiftKey() || keyevent.CtrlKey() || keyevent.MetaKey() {
+                        self.textinput.borrow_mut().user_select = UserSelect::of(self.upcast());
+                    }
                     // This can't be inlined, as holding on to textinput.borrow_mut()
                     // during self.implicit_submission will cause a panic.
                     let action = self.textinput.borrow_mut().handle_keydown(keyevent);
@@ -947,10 +951,12 @@ impl VirtualMethods for HTMLInputElement {
                             }
 
                             self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+                            window_from_node(self).schedule_text_editing_reflow();
                             event.PreventDefault();
                         }
                         RedrawSelection => {
                             self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+                            window_from_node(self).schedule_text_editing_reflow();
                             event.PreventDefault();
                         }
                         Nothing => (),
@@ -12,7 +12,7 @@ use dom::bindings::str::DOMString;
 use dom::document::Document;
 use dom::element::Element;
 use dom::htmlelement::HTMLElement;
-use dom::node::{Node, document_from_node};
+use dom::node::{Node, document_from_node, window_from_node};
 use dom::virtualmethods::VirtualMethods;
 use std::ascii::AsciiExt;
 use std::sync::Arc;
@@ -60,6 +60,20 @@ impl HTMLMetaElement {
                 self.apply_viewport();
             }
         }
+
+        if let Some(http_equiv) = element.get_attribute(&ns!(), &Atom::from("http-equiv")).r() {
+            if http_equiv.value().eq_ignore_ascii_case("origin-trial") {
+                self.apply_origin_trial();
+            }
+        }
+    }
+
+    fn apply_origin_trial(&self) {
+        let element = self.upcast::<Element>();
+        if let Some(content) = element.get_attribute(&ns!(), &atom!("content")).r() {
+            let window = window_from_node(self);
+            window.origin_trials_mut().add_token(&content.value());
+        }
     }
 
     fn apply_viewport(&self) {
@@ -78,6 +92,8 @@ impl HTMLMetaElement {
                         // Viewport constraints are always recomputed on resize; they don't need to
                         // force all styles to be recomputed.
                         dirty_on_viewport_size_change: false,
+                        source_map_url: None,
+                        url: document_from_node(self).url().clone(),
                     }));
                     let doc = document_from_node(self);
                     doc.invalidate_stylesheets();
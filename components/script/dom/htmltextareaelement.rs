@@ -7,6 +7,7 @@ use dom::bindings::cell::DOMRefCell;
 use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
 use dom::bindings::codegen::Bindings::HTMLTextAreaElementBinding;
 use dom::bindings::codegen::Bindings::HTMLTextAreaElementBinding::HTMLTextAreaElementMethods;
+use dom::bindings::codegen::Bindings::KeyboardEventBinding::KeyboardEventMethods;
 use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
 use dom::bindings::inheritance::Castable;
 use dom::bindings::js::{LayoutJS, Root};
@@ -30,7 +31,7 @@ use std::cell::Cell;
 use std::ops::Range;
 use string_cache::Atom;
 use style::element_state::*;
-use textinput::{KeyReaction, Lines, TextInput, SelectionDirection};
+use textinput::{KeyReaction, Lines, TextInput, SelectionDirection, UserSelect};
 
 #[dom_struct]
 pub struct HTMLTextAreaElement {
@@ -371,6 +372,10 @@ impl VirtualMethods for HTMLTextAreaElement {
             document_from_node(self).request_focus(self.upcast());
         } else if event.type_() == atom!("keydown") && !event.DefaultPrevented() {
             if let Some(kevent) = event.downcast::<KeyboardEvent>() {
+                // Only the keys pressed with these modifiers select text.
+                if kevent.ShiftKey() || kevent.CtrlKey() || kevent.MetaKey() {
+                    self.textinput.borrow_mut().user_select = UserSelect::of(self.upcast());
+                }
                 match self.textinput.borrow_mut().handle_keydown(kevent) {
                     KeyReaction::TriggerDefaultAction => (),
                     KeyReaction::DispatchInput => {
@@ -386,10 +391,12 @@ impl VirtualMethods for HTMLTextAreaElement {
                         }
 
                         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+                        window_from_node(self).schedule_text_editing_reflow();
                         event.PreventDefault();
                     }
                     KeyReaction::RedrawSelection => {
                         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+                        window_from_node(self).schedule_text_editing_reflow();
                         event.PreventDefault();
                     }
                     KeyReaction::Nothing => (),
@@ -210,6 +210,7 @@ pub mod types {
 }
 
 pub mod activation;
+pub mod animation;
 pub mod attr;
 pub mod beforeunloadevent;
 mod create;
@@ -360,6 +361,7 @@ pub mod pluginarray;
 pub mod popstateevent;
 pub mod processinginstruction;
 pub mod progressevent;
+pub mod promise;
 pub mod radionodelist;
 pub mod range;
 pub mod screen;
@@ -377,16 +379,20 @@ pub mod textencoder;
 pub mod touch;
 pub mod touchevent;
 pub mod touchlist;
+pub mod transitionevent;
 pub mod treewalker;
 pub mod uievent;
 pub mod url;
 pub mod urlhelper;
 pub mod urlsearchparams;
+pub mod usb;
+pub mod usbdevice;
 pub mod userscripts;
 pub mod validation;
 pub mod validitystate;
 pub mod values;
 pub mod virtualmethods;
+pub mod visualviewport;
 pub mod webglactiveinfo;
 pub mod webglbuffer;
 pub mod webglcontextevent;
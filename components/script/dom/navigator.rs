@@ -12,6 +12,7 @@ use dom::bluetooth::Bluetooth;
 use dom::mimetypearray::MimeTypeArray;
 use dom::navigatorinfo;
 use dom::pluginarray::PluginArray;
+use dom::usb::USB;
 use dom::window::Window;
 
 #[dom_struct]
@@ -20,6 +21,7 @@ pub struct Navigator {
     bluetooth: MutNullableHeap<JS<Bluetooth>>,
     plugins: MutNullableHeap<JS<PluginArray>>,
     mime_types: MutNullableHeap<JS<MimeTypeArray>>,
+    usb: MutNullableHeap<JS<USB>>,
 }
 
 impl Navigator {
@@ -29,6 +31,7 @@ impl Navigator {
             bluetooth: Default::default(),
             plugins: Default::default(),
             mime_types: Default::default(),
+            usb: Default::default(),
         }
     }
 
@@ -80,6 +83,11 @@ impl NavigatorMethods for Navigator {
         self.bluetooth.or_init(|| Bluetooth::new(self.global().r()))
     }
 
+    // https://wicg.github.io/webusb/#dom-navigator-usb
+    fn Usb(&self) -> Root<USB> {
+        self.usb.or_init(|| USB::new(self.global().r()))
+    }
+
     // https://html.spec.whatwg.org/multipage/#navigatorlanguage
     fn Language(&self) -> DOMString {
         navigatorinfo::Language()
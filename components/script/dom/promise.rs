@@ -0,0 +1,205 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Native representation of JS Promise values.
+//!
+//! Promises are implemented by SpiderMonkey, so unlike other DOM objects, a `Promise` does not
+//! own its reflector: it is a reference-counted handle to a JS promise object, which is
+//! explicitly rooted for as long as the handle is alive. Operations declared in WebIDL as
+//! returning `Promise<T>` return an `Rc<Promise>`, and the errors they throw reject the
+//! promise they would have returned instead.
+
+use dom::bindings::conversions::ToJSValConvertible;
+use dom::bindings::error::{Error, Fallible, throw_dom_exception};
+use dom::bindings::global::GlobalRef;
+use dom::bindings::reflector::{Reflectable, Reflector};
+use dom::bindings::trace::{JSTraceable, trace_reflector};
+use js::jsapi::{AddRawValueRoot, CallArgs, CallOriginalPromiseReject, CallOriginalPromiseResolve};
+use js::jsapi::{GetPromiseState, HandleObject, HandleValue, Heap, IsPromiseObject, JSContext};
+use js::jsapi::{JSObject, JSTracer, JS_ClearPendingException, JS_GetFunctionObject};
+use js::jsapi::{JS_GetPendingException, JS_NewFunction, MutableHandleValue, NewPromiseObject};
+use js::jsapi::{PromiseState, RejectPromise, RemoveRawValueRoot, ResolvePromise};
+use js::jsapi::{RootedObject, RootedValue};
+use js::jsval::{JSVal, ObjectValue, UndefinedValue};
+use std::ptr;
+use std::rc::Rc;
+
+/// A JS promise that the DOM can resolve or reject.
+#[derive(HeapSizeOf)]
+pub struct Promise {
+    reflector: Reflector,
+    /// Nothing in the JS heap keeps the promise object alive for the sake of this handle, so
+    /// it is rooted from here while the handle is alive. The value lives in a box of its own
+    /// so that its address does not change.
+    #[ignore_heap_size_of = "Defined in rust-mozjs"]
+    permanent_js_root: Box<Heap<JSVal>>,
+}
+
+impl Promise {
+    /// Create a new pending promise in the given global.
+    #[allow(unsafe_code)]
+    pub fn new(global: GlobalRef) -> Rc<Promise> {
+        let cx = global.get_cx();
+        unsafe {
+            let executor = JS_NewFunction(cx, Some(do_nothing_promise_executor), 2, 0,
+                                          ptr::null());
+            assert!(!executor.is_null());
+            let executor = RootedObject::new(cx, JS_GetFunctionObject(executor));
+            let obj = RootedObject::new(cx, NewPromiseObject(cx, executor.handle(),
+                                                             HandleObject::null()));
+            assert!(!obj.ptr.is_null());
+            Promise::new_with_js_promise(cx, obj.handle())
+        }
+    }
+
+    #[allow(unsafe_code)]
+    unsafe fn new_with_js_promise(cx: *mut JSContext, obj: HandleObject) -> Rc<Promise> {
+        assert!(IsPromiseObject(obj));
+        let mut promise = Promise {
+            reflector: Reflector::new(),
+            permanent_js_root: box Heap::default(),
+        };
+        promise.reflector.set_jsobject(obj.get());
+        promise.permanent_js_root.set(ObjectValue(&*obj.get()));
+        assert!(AddRawValueRoot(cx, promise.permanent_js_root.ptr.get(),
+                                b"Promise::root\0".as_ptr() as *const _));
+        Rc::new(promise)
+    }
+
+    /// Convert `value` to a promise, as done for arguments of type `Promise<T>`: promises are
+    /// returned as they are, and other values wrapped in a promise resolved with them.
+    #[allow(unsafe_code, non_snake_case)]
+    pub fn Resolve(cx: *mut JSContext, value: HandleValue) -> Fallible<Rc<Promise>> {
+        unsafe {
+            let obj = RootedObject::new(cx, CallOriginalPromiseResolve(cx, value));
+            if obj.ptr.is_null() {
+                return Err(Error::JSFailed);
+            }
+            Ok(Promise::new_with_js_promise(cx, obj.handle()))
+        }
+    }
+
+    /// Create a promise rejected with `value`.
+    #[allow(unsafe_code, non_snake_case)]
+    pub fn Reject(cx: *mut JSContext, value: HandleValue) -> Fallible<Rc<Promise>> {
+        unsafe {
+            let obj = RootedObject::new(cx, CallOriginalPromiseReject(cx, value));
+            if obj.ptr.is_null() {
+                return Err(Error::JSFailed);
+            }
+            Ok(Promise::new_with_js_promise(cx, obj.handle()))
+        }
+    }
+
+    /// Resolve the promise with the JS representation of `value`.
+    #[allow(unsafe_code)]
+    pub fn resolve_native<T: ToJSValConvertible>(&self, cx: *mut JSContext, value: &T) {
+        let mut v = RootedValue::new(cx, UndefinedValue());
+        unsafe {
+            value.to_jsval(cx, v.handle_mut());
+        }
+        self.resolve(cx, v.handle());
+    }
+
+    /// Resolve the promise with `value`.
+    #[allow(unsafe_code)]
+    pub fn resolve(&self, cx: *mut JSContext, value: HandleValue) {
+        unsafe {
+            if !ResolvePromise(cx, self.promise_obj(), value) {
+                JS_ClearPendingException(cx);
+            }
+        }
+    }
+
+    /// Reject the promise with the JS representation of `value`.
+    #[allow(unsafe_code)]
+    pub fn reject_native<T: ToJSValConvertible>(&self, cx: *mut JSContext, value: &T) {
+        let mut v = RootedValue::new(cx, UndefinedValue());
+        unsafe {
+            value.to_jsval(cx, v.handle_mut());
+        }
+        self.reject(cx, v.handle());
+    }
+
+    /// Reject the promise with the exception that `error` would throw, such as a
+    /// `DOMException` of the matching type.
+    #[allow(unsafe_code)]
+    pub fn reject_error(&self, cx: *mut JSContext, error: Error) {
+        let mut v = RootedValue::new(cx, UndefinedValue());
+        unsafe {
+            throw_dom_exception(cx, self.global().r(), error);
+            if !JS_GetPendingException(cx, v.handle_mut()) {
+                return;
+            }
+            JS_ClearPendingException(cx);
+        }
+        self.reject(cx, v.handle());
+    }
+
+    /// Reject the promise with `value`.
+    #[allow(unsafe_code)]
+    pub fn reject(&self, cx: *mut JSContext, value: HandleValue) {
+        unsafe {
+            if !RejectPromise(cx, self.promise_obj(), value) {
+                JS_ClearPendingException(cx);
+            }
+        }
+    }
+
+    /// Whether the promise has been resolved or rejected.
+    #[allow(unsafe_code)]
+    pub fn is_settled(&self) -> bool {
+        match unsafe { GetPromiseState(self.promise_obj()) } {
+            PromiseState::Pending => false,
+            PromiseState::Fulfilled | PromiseState::Rejected => true,
+        }
+    }
+
+    /// The JS promise object.
+    pub fn promise_obj(&self) -> HandleObject {
+        self.reflector.get_jsobject()
+    }
+}
+
+impl Reflectable for Promise {
+    fn reflector(&self) -> &Reflector {
+        &self.reflector
+    }
+
+    fn init_reflector(&mut self, obj: *mut JSObject) {
+        self.reflector.set_jsobject(obj);
+    }
+}
+
+impl JSTraceable for Promise {
+    fn trace(&self, trc: *mut JSTracer) {
+        trace_reflector(trc, "promise", &self.reflector);
+    }
+}
+
+impl ToJSValConvertible for Promise {
+    #[allow(unsafe_code)]
+    unsafe fn to_jsval(&self, _: *mut JSContext, rval: MutableHandleValue) {
+        rval.set(ObjectValue(&*self.promise_obj().get()));
+    }
+}
+
+impl Drop for Promise {
+    #[allow(unsafe_code)]
+    fn drop(&mut self) {
+        let cx = self.global().r().get_cx();
+        unsafe {
+            RemoveRawValueRoot(cx, self.permanent_js_root.ptr.get());
+        }
+    }
+}
+
+/// The executor of the promises created by `Promise::new`, which get settled from Rust.
+#[allow(unsafe_code)]
+unsafe extern "C" fn do_nothing_promise_executor(_: *mut JSContext, argc: u32, vp: *mut JSVal)
+                                                 -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    args.rval().set(UndefinedValue());
+    true
+}
@@ -25,7 +25,7 @@ use hyper::header::ContentType;
 use hyper::mime::{Mime, SubLevel, TopLevel};
 use js::jsapi::JSTracer;
 use msg::constellation_msg::{PipelineId, SubpageId};
-use net_traits::{AsyncResponseListener, Metadata, NetworkError};
+use net_traits::{AsyncResponseListener, Metadata, NetworkError, UrlThreat};
 use network_listener::PreInvoke;
 use parse::{TrustedParser, ParserRef, Parser};
 use profile_traits::time::ProfilerCategory;
@@ -79,9 +79,27 @@ impl ParserContext {
     }
 }
 
+/// The interstitial page shown instead of a document whose URL was flagged by the URL
+/// classifier. There is deliberately no way to proceed to the flagged page from it.
+fn unsafe_url_warning_page(url: &Url, threat: UrlThreat) -> String {
+    let title = match threat {
+        UrlThreat::Malware => "Reported attack page",
+        UrlThreat::Phishing => "Reported deceptive site",
+        UrlThreat::Unwanted => "Reported unwanted software page",
+    };
+    let url = url.as_str().replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    format!("<html><head><title>{title}</title></head>\
+             <body><h1>{title}</h1>\
+             <p>The page at {url} has been reported as unsafe and was not loaded.</p>\
+             <p>{description}</p>\
+             <button onclick=\"history.back()\">Go back</button></body></html>",
+            title = title, url = url, description = threat.description())
+}
+
 impl AsyncResponseListener for ParserContext {
     fn headers_available(&mut self, meta_result: Result<Metadata, NetworkError>) {
         let mut is_ssl_error = false;
+        let mut url_threat = None;
         let metadata = match meta_result {
             Ok(meta) => Some(meta),
             Err(NetworkError::SslValidation(url)) => {
@@ -91,6 +109,13 @@ impl AsyncResponseListener for ParserContext {
                 meta.set_content_type(mime.as_ref());
                 Some(meta)
             },
+            Err(NetworkError::UnsafeUrl(url, threat)) => {
+                url_threat = Some(threat);
+                let mut meta = Metadata::default(url);
+                let mime: Option<Mime> = "text/html".parse().ok();
+                meta.set_content_type(mime.as_ref());
+                Some(meta)
+            },
             Err(_) => None,
         };
         let content_type = metadata.clone().and_then(|meta| meta.content_type);
@@ -130,6 +155,11 @@ impl AsyncResponseListener for ParserContext {
                     let page = String::from_utf8(page_bytes).unwrap();
                     parser.pending_input().borrow_mut().push(page);
                     parser.parse_sync();
+                } else if let Some(threat) = url_threat {
+                    self.is_synthesized_document = true;
+                    let page = unsafe_url_warning_page(&self.url, threat);
+                    parser.pending_input().borrow_mut().push(page);
+                    parser.parse_sync();
                 }
             },
             Some(ContentType(Mime(TopLevel::Text, SubLevel::Xml, _))) => {}, // Handle text/xml
@@ -15,7 +15,7 @@ use dom::bindings::codegen::UnionTypes::{EventOrUSVString, HTMLElementOrLong};
 use dom::bindings::codegen::UnionTypes::{HTMLElementOrUnsignedLongOrStringOrBoolean, LongSequenceOrBoolean};
 use dom::bindings::codegen::UnionTypes::{StringOrLongSequence, StringOrStringSequence, StringSequenceOrUnsignedLong};
 use dom::bindings::codegen::UnionTypes::{StringOrUnsignedLong, StringOrBoolean, UnsignedLongOrBoolean};
-use dom::bindings::error::Fallible;
+use dom::bindings::error::{Error, Fallible};
 use dom::bindings::global::GlobalRef;
 use dom::bindings::js::Root;
 use dom::bindings::num::Finite;
@@ -23,6 +23,7 @@ use dom::bindings::reflector::{Reflectable, Reflector, reflect_dom_object};
 use dom::bindings::str::{ByteString, DOMString, USVString};
 use dom::bindings::weakref::MutableWeakRef;
 use dom::blob::{Blob, DataSlice};
+use dom::promise::Promise;
 use dom::url::URL;
 use js::jsapi::{HandleObject, HandleValue, JSContext, JSObject};
 use js::jsval::{JSVal, NullValue};
@@ -567,10 +568,25 @@ impl TestBindingMethods for TestBinding {
     fn PrefControlledAttributeEnabled(&self) -> bool { false }
     fn PrefControlledMethodDisabled(&self) {}
     fn PrefControlledMethodEnabled(&self) {}
+    fn OriginTrialControlledAttribute(&self) -> bool { false }
+    fn OriginTrialControlledMethod(&self) {}
     fn FuncControlledAttributeDisabled(&self) -> bool { false }
     fn FuncControlledAttributeEnabled(&self) -> bool { false }
     fn FuncControlledMethodDisabled(&self) {}
     fn FuncControlledMethodEnabled(&self) {}
+    fn ReturnResolvedPromise(&self, cx: *mut JSContext, value: HandleValue)
+                             -> Fallible<Rc<Promise>> {
+        Promise::Resolve(cx, value)
+    }
+    fn ReturnRejectedPromise(&self, cx: *mut JSContext, value: HandleValue)
+                             -> Fallible<Rc<Promise>> {
+        Promise::Reject(cx, value)
+    }
+    fn ReturnRejectedPromiseWithError(&self) -> Fallible<Rc<Promise>> {
+        Err(Error::NotSupported)
+    }
+    fn PassPromise(&self, _: &Promise) {}
+    fn PassOptionalPromise(&self, _: Option<&Promise>) {}
 }
 
 impl TestBinding {
@@ -585,6 +601,9 @@ impl TestBinding {
     pub fn FuncControlledStaticAttributeEnabled(_: GlobalRef) -> bool { false }
     pub fn FuncControlledStaticMethodDisabled(_: GlobalRef) {}
     pub fn FuncControlledStaticMethodEnabled(_: GlobalRef) {}
+    pub fn ReturnRejectedPromiseWithErrorStatic(_: GlobalRef) -> Fallible<Rc<Promise>> {
+        Err(Error::NotSupported)
+    }
 }
 
 #[allow(unsafe_code)]
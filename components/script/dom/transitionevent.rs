@@ -0,0 +1,91 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use dom::bindings::codegen::Bindings::TransitionEventBinding;
+use dom::bindings::codegen::Bindings::TransitionEventBinding::TransitionEventMethods;
+use dom::bindings::error::Fallible;
+use dom::bindings::global::GlobalRef;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::js::Root;
+use dom::bindings::num::Finite;
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::str::DOMString;
+use dom::event::{Event, EventBubbles, EventCancelable};
+use string_cache::Atom;
+
+// https://drafts.csswg.org/css-transitions/#interface-transitionevent
+#[dom_struct]
+pub struct TransitionEvent {
+    event: Event,
+    property_name: DOMString,
+    elapsed_time: f32,
+    pseudo_element: DOMString,
+}
+
+impl TransitionEvent {
+    fn new_inherited(property_name: DOMString,
+                     elapsed_time: f32,
+                     pseudo_element: DOMString)
+                     -> TransitionEvent {
+        TransitionEvent {
+            event: Event::new_inherited(),
+            property_name: property_name,
+            elapsed_time: elapsed_time,
+            pseudo_element: pseudo_element,
+        }
+    }
+
+    pub fn new(global: GlobalRef,
+               type_: Atom,
+               bubbles: EventBubbles,
+               cancelable: EventCancelable,
+               property_name: DOMString,
+               elapsed_time: f32,
+               pseudo_element: DOMString)
+               -> Root<TransitionEvent> {
+        let event = box TransitionEvent::new_inherited(property_name, elapsed_time, pseudo_element);
+        let ev = reflect_dom_object(event, global, TransitionEventBinding::Wrap);
+        {
+            let event = ev.upcast::<Event>();
+            event.init_event(type_, bool::from(bubbles), bool::from(cancelable));
+        }
+        ev
+    }
+
+    pub fn Constructor(global: GlobalRef,
+                       type_: DOMString,
+                       init: &TransitionEventBinding::TransitionEventInit)
+                       -> Fallible<Root<TransitionEvent>> {
+        Ok(TransitionEvent::new(global,
+                                Atom::from(type_),
+                                EventBubbles::from(init.parent.bubbles),
+                                EventCancelable::from(init.parent.cancelable),
+                                init.propertyName.clone(),
+                                *init.elapsedTime,
+                                init.pseudoElement.clone()))
+    }
+}
+
+impl TransitionEventMethods for TransitionEvent {
+    // https://drafts.csswg.org/css-transitions/#Events-TransitionEvent-propertyName
+    fn PropertyName(&self) -> DOMString {
+        self.property_name.clone()
+    }
+
+    // https://drafts.csswg.org/css-transitions/#Events-TransitionEvent-elapsedTime
+    fn ElapsedTime(&self) -> Finite<f32> {
+        Finite::wrap(self.elapsed_time)
+    }
+
+    // https://drafts.csswg.org/css-transitions/#Events-TransitionEvent-pseudoElement
+    fn PseudoElement(&self) -> DOMString {
+        self.pseudo_element.clone()
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-istrusted
+    fn IsTrusted(&self) -> bool {
+        self.event.IsTrusted()
+    }
+}
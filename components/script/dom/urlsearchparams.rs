@@ -8,6 +8,7 @@ use dom::bindings::codegen::Bindings::URLSearchParamsBinding::URLSearchParamsMet
 use dom::bindings::codegen::UnionTypes::USVStringOrURLSearchParams;
 use dom::bindings::error::Fallible;
 use dom::bindings::global::GlobalRef;
+use dom::bindings::iterable::Iterable;
 use dom::bindings::js::Root;
 use dom::bindings::reflector::{Reflector, reflect_dom_object};
 use dom::bindings::str::{DOMString, USVString};
@@ -163,3 +164,25 @@ impl URLSearchParams {
         }
     }
 }
+
+
+// The list can change while it is iterated over, by the page or by URL::SetSearch, so the
+// iterators look up each entry by its index when they get to it.
+impl Iterable for URLSearchParams {
+    type Key = USVString;
+    type Value = USVString;
+
+    fn get_iterable_length(&self) -> u32 {
+        self.list.borrow().len() as u32
+    }
+
+    fn get_value_at_index(&self, n: u32) -> USVString {
+        let value = self.list.borrow()[n as usize].1.clone();
+        USVString(value)
+    }
+
+    fn get_key_at_index(&self, n: u32) -> USVString {
+        let key = self.list.borrow()[n as usize].0.clone();
+        USVString(key)
+    }
+}
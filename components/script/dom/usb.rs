@@ -0,0 +1,83 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::USBBinding;
+use dom::bindings::codegen::Bindings::USBBinding::USBMethods;
+use dom::bindings::codegen::Bindings::USBBinding::{USBDeviceFilter, USBDeviceRequestOptions};
+use dom::bindings::error::Error::Type;
+use dom::bindings::error::Fallible;
+use dom::bindings::global::GlobalRef;
+use dom::bindings::js::Root;
+use dom::bindings::reflector::{Reflectable, Reflector, reflect_dom_object};
+use dom::usbdevice::USBDevice;
+use ipc_channel::ipc::{self, IpcSender};
+use net_traits::usb_thread::{UsbDeviceFilter, UsbMethodMsg};
+
+const PRODUCT_ID_ERROR: &'static str = "A 'productId' can't be given without a 'vendorId'.";
+const SUBCLASS_CODE_ERROR: &'static str = "A 'subclassCode' can't be given without a 'classCode'.";
+const PROTOCOL_CODE_ERROR: &'static str =
+    "A 'protocolCode' can't be given without a 'subclassCode'.";
+
+// https://wicg.github.io/webusb/#usb
+#[dom_struct]
+pub struct USB {
+    reflector_: Reflector,
+}
+
+impl USB {
+    pub fn new_inherited() -> USB {
+        USB {
+            reflector_: Reflector::new(),
+        }
+    }
+
+    pub fn new(global: GlobalRef) -> Root<USB> {
+        reflect_dom_object(box USB::new_inherited(),
+                           global,
+                           USBBinding::Wrap)
+    }
+
+    fn get_usb_thread(&self) -> IpcSender<UsbMethodMsg> {
+        let global_root = self.global();
+        let global_ref = global_root.r();
+        global_ref.as_window().usb_thread()
+    }
+}
+
+// https://wicg.github.io/webusb/#dom-usb-requestdevice
+fn convert_filter(filter: &USBDeviceFilter) -> Fallible<UsbDeviceFilter> {
+    if filter.productId.is_some() && filter.vendorId.is_none() {
+        return Err(Type(PRODUCT_ID_ERROR.to_owned()));
+    }
+    if filter.subclassCode.is_some() && filter.classCode.is_none() {
+        return Err(Type(SUBCLASS_CODE_ERROR.to_owned()));
+    }
+    if filter.protocolCode.is_some() && filter.subclassCode.is_none() {
+        return Err(Type(PROTOCOL_CODE_ERROR.to_owned()));
+    }
+    Ok(UsbDeviceFilter {
+        vendor_id: filter.vendorId,
+        product_id: filter.productId,
+        class_code: filter.classCode,
+        subclass_code: filter.subclassCode,
+        protocol_code: filter.protocolCode,
+        serial_number: filter.serialNumber.as_ref().map(|serial_number| serial_number.to_string()),
+    })
+}
+
+impl USBMethods for USB {
+    // https://wicg.github.io/webusb/#dom-usb-requestdevice
+    fn RequestDevice(&self, options: &USBDeviceRequestOptions) -> Fallible<Root<USBDevice>> {
+        let mut filters = vec!();
+        for filter in &options.filters {
+            filters.push(try!(convert_filter(filter)));
+        }
+        let (sender, receiver) = ipc::channel().unwrap();
+        self.get_usb_thread().send(UsbMethodMsg::RequestDevice(filters, sender)).unwrap();
+        match receiver.recv().unwrap() {
+            Ok(device) => Ok(USBDevice::new(self.global().r(), device)),
+            Err(error) => Err(Type(error)),
+        }
+    }
+}
@@ -0,0 +1,96 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::USBDeviceBinding;
+use dom::bindings::codegen::Bindings::USBDeviceBinding::USBDeviceMethods;
+use dom::bindings::global::GlobalRef;
+use dom::bindings::js::Root;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::str::DOMString;
+use net_traits::usb_thread::UsbDeviceMsg;
+
+// https://wicg.github.io/webusb/#usbdevice
+#[dom_struct]
+pub struct USBDevice {
+    reflector_: Reflector,
+    // The id of the device, as known to the USB thread.
+    id: String,
+    device_class: u8,
+    device_subclass: u8,
+    device_protocol: u8,
+    vendor_id: u16,
+    product_id: u16,
+    manufacturer_name: Option<DOMString>,
+    product_name: Option<DOMString>,
+    serial_number: Option<DOMString>,
+}
+
+impl USBDevice {
+    pub fn new_inherited(device: UsbDeviceMsg) -> USBDevice {
+        USBDevice {
+            reflector_: Reflector::new(),
+            id: device.id,
+            device_class: device.device_class,
+            device_subclass: device.device_subclass,
+            device_protocol: device.device_protocol,
+            vendor_id: device.vendor_id,
+            product_id: device.product_id,
+            manufacturer_name: device.manufacturer_name.map(DOMString::from),
+            product_name: device.product_name.map(DOMString::from),
+            serial_number: device.serial_number.map(DOMString::from),
+        }
+    }
+
+    pub fn new(global: GlobalRef, device: UsbDeviceMsg) -> Root<USBDevice> {
+        reflect_dom_object(box USBDevice::new_inherited(device),
+                           global,
+                           USBDeviceBinding::Wrap)
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl USBDeviceMethods for USBDevice {
+    // https://wicg.github.io/webusb/#dom-usbdevice-deviceclass
+    fn DeviceClass(&self) -> u8 {
+        self.device_class
+    }
+
+    // https://wicg.github.io/webusb/#dom-usbdevice-devicesubclass
+    fn DeviceSubclass(&self) -> u8 {
+        self.device_subclass
+    }
+
+    // https://wicg.github.io/webusb/#dom-usbdevice-deviceprotocol
+    fn DeviceProtocol(&self) -> u8 {
+        self.device_protocol
+    }
+
+    // https://wicg.github.io/webusb/#dom-usbdevice-vendorid
+    fn VendorId(&self) -> u16 {
+        self.vendor_id
+    }
+
+    // https://wicg.github.io/webusb/#dom-usbdevice-productid
+    fn ProductId(&self) -> u16 {
+        self.product_id
+    }
+
+    // https://wicg.github.io/webusb/#dom-usbdevice-manufacturername
+    fn GetManufacturerName(&self) -> Option<DOMString> {
+        self.manufacturer_name.clone()
+    }
+
+    // https://wicg.github.io/webusb/#dom-usbdevice-productname
+    fn GetProductName(&self) -> Option<DOMString> {
+        self.product_name.clone()
+    }
+
+    // https://wicg.github.io/webusb/#dom-usbdevice-serialnumber
+    fn GetSerialNumber(&self) -> Option<DOMString> {
+        self.serial_number.clone()
+    }
+}
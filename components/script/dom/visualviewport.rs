@@ -0,0 +1,80 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
+use dom::bindings::codegen::Bindings::VisualViewportBinding;
+use dom::bindings::codegen::Bindings::VisualViewportBinding::VisualViewportMethods;
+use dom::bindings::global::GlobalRef;
+use dom::bindings::js::{JS, Root};
+use dom::bindings::reflector::reflect_dom_object;
+use dom::eventtarget::EventTarget;
+use dom::window::Window;
+
+/// The part of the viewport that is visible on screen, which the compositor reports as the page
+/// is pinch-zoomed and on-screen keyboards come and go.
+#[dom_struct]
+pub struct VisualViewport {
+    eventtarget: EventTarget,
+    window: JS<Window>,
+}
+
+impl VisualViewport {
+    fn new_inherited(window: &Window) -> VisualViewport {
+        VisualViewport {
+            eventtarget: EventTarget::new_inherited(),
+            window: JS::from_ref(window),
+        }
+    }
+
+    pub fn new(window: &Window) -> Root<VisualViewport> {
+        reflect_dom_object(box VisualViewport::new_inherited(window),
+                           GlobalRef::Window(window),
+                           VisualViewportBinding::Wrap)
+    }
+}
+
+impl VisualViewportMethods for VisualViewport {
+    // https://wicg.github.io/visual-viewport/#dom-visualviewport-offsetleft
+    fn OffsetLeft(&self) -> f64 {
+        let layout_viewport = self.window.current_viewport();
+        (self.window.visual_viewport().page_offset.x - layout_viewport.origin.x.to_f32_px()) as f64
+    }
+
+    // https://wicg.github.io/visual-viewport/#dom-visualviewport-offsettop
+    fn OffsetTop(&self) -> f64 {
+        let layout_viewport = self.window.current_viewport();
+        (self.window.visual_viewport().page_offset.y - layout_viewport.origin.y.to_f32_px()) as f64
+    }
+
+    // https://wicg.github.io/visual-viewport/#dom-visualviewport-pageleft
+    fn PageLeft(&self) -> f64 {
+        self.window.visual_viewport().page_offset.x as f64
+    }
+
+    // https://wicg.github.io/visual-viewport/#dom-visualviewport-pagetop
+    fn PageTop(&self) -> f64 {
+        self.window.visual_viewport().page_offset.y as f64
+    }
+
+    // https://wicg.github.io/visual-viewport/#dom-visualviewport-width
+    fn Width(&self) -> f64 {
+        self.window.visual_viewport().size.width as f64
+    }
+
+    // https://wicg.github.io/visual-viewport/#dom-visualviewport-height
+    fn Height(&self) -> f64 {
+        self.window.visual_viewport().size.height as f64
+    }
+
+    // https://wicg.github.io/visual-viewport/#dom-visualviewport-scale
+    fn Scale(&self) -> f64 {
+        self.window.visual_viewport().scale as f64
+    }
+
+    // https://wicg.github.io/visual-viewport/#dom-visualviewport-onresize
+    event_handler!(resize, GetOnresize, SetOnresize);
+
+    // https://wicg.github.io/visual-viewport/#dom-visualviewport-onscroll
+    event_handler!(scroll, GetOnscroll, SetOnscroll);
+}
@@ -16,7 +16,7 @@ use dom::bindings::codegen::Bindings::WindowBinding::{self, FrameRequestCallback
 use dom::bindings::error::{Error, ErrorResult, Fallible, report_pending_exception};
 use dom::bindings::global::{GlobalRef, global_root_from_object};
 use dom::bindings::inheritance::Castable;
-use dom::bindings::js::{JS, MutNullableHeap, Root};
+use dom::bindings::js::{JS, MutNullableHeap, Root, RootedReference};
 use dom::bindings::num::Finite;
 use dom::bindings::reflector::Reflectable;
 use dom::bindings::str::DOMString;
@@ -34,6 +34,7 @@ use dom::node::{Node, TrustedNodeAddress, from_untrusted_node_address, window_fr
 use dom::performance::Performance;
 use dom::screen::Screen;
 use dom::storage::Storage;
+use dom::visualviewport::VisualViewport;
 use euclid::{Point2D, Rect, Size2D};
 use gfx_traits::LayerId;
 use ipc_channel::ipc::{self, IpcSender};
@@ -43,6 +44,7 @@ use js::rust::CompileOptionsWrapper;
 use js::rust::Runtime;
 use layout_interface::{ContentBoxResponse, ContentBoxesResponse, ResolvedStyleResponse, ScriptReflow};
 use layout_interface::{LayoutRPC, Msg, Reflow, ReflowQueryType, MarginStyleResponse};
+use layout_interface::{BoxModel, MatchedRulesResponse, NodeAnimation, PhaseMarkersResponse};
 use libc;
 use msg::constellation_msg::{LoadData, PanicMsg, PipelineId, SubpageId};
 use msg::constellation_msg::{WindowSizeData, WindowSizeType};
@@ -50,9 +52,11 @@ use msg::webdriver_msg::{WebDriverJSError, WebDriverJSResult};
 use net_traits::bluetooth_thread::BluetoothMethodMsg;
 use net_traits::image_cache_thread::{ImageCacheChan, ImageCacheThread};
 use net_traits::storage_thread::StorageType;
+use net_traits::usb_thread::UsbMethodMsg;
 use net_traits::{ResourceThreads, CustomResponseSender};
 use num_traits::ToPrimitive;
 use open;
+use origin_trials::OriginTrials;
 use profile_traits::mem;
 use profile_traits::time::{ProfilerCategory, TimerMetadata, TimerMetadataFrameType};
 use profile_traits::time::{ProfilerChan, TimerMetadataReflowType, profile};
@@ -64,9 +68,10 @@ use script_thread::{MainThreadScriptChan, MainThreadScriptMsg, RunnableWrapper};
 use script_traits::{ConstellationControlMsg, UntrustedNodeAddress};
 use script_traits::{DocumentState, MsDuration, TimerEvent, TimerEventId};
 use script_traits::{ScriptMsg as ConstellationMsg, TimerEventRequest, TimerSource};
+use script_traits::{UsedFontFace, VisualViewportData};
 use std::ascii::AsciiExt;
 use std::borrow::ToOwned;
-use std::cell::Cell;
+use std::cell::{Cell, Ref, RefMut};
 use std::collections::HashSet;
 use std::default::Default;
 use std::ffi::CString;
@@ -81,6 +86,7 @@ use style::context::ReflowGoal;
 use style::error_reporting::ParseErrorReporter;
 use style::properties::longhands::overflow_x;
 use style::selector_impl::PseudoElement;
+use style::selector_matching::MatchedRule;
 use task_source::TaskSource;
 use task_source::dom_manipulation::{DOMManipulationTaskSource, DOMManipulationTask};
 use task_source::file_reading::FileReadingTaskSource;
@@ -126,6 +132,46 @@ pub enum ReflowReason {
     FramedContentChanged,
     IFrameLoadEvent,
     MissingExplicitReflow,
+    InvalidationOverlaysChanged,
+    HighlightedNodeChanged,
+    StyleRuleEdited,
+    TextEditing,
+    AuthorStylesToggled,
+    ForcedColorsToggled,
+    TextZoomChanged,
+}
+
+impl ReflowReason {
+    /// The name of the reason, for debugging output.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ReflowReason::CachedPageNeededReflow => "CachedPageNeededReflow",
+            ReflowReason::RefreshTick => "RefreshTick",
+            ReflowReason::FirstLoad => "FirstLoad",
+            ReflowReason::KeyEvent => "KeyEvent",
+            ReflowReason::MouseEvent => "MouseEvent",
+            ReflowReason::Query => "Query",
+            ReflowReason::Timer => "Timer",
+            ReflowReason::Viewport => "Viewport",
+            ReflowReason::WindowResize => "WindowResize",
+            ReflowReason::DOMContentLoaded => "DOMContentLoaded",
+            ReflowReason::DocumentLoaded => "DocumentLoaded",
+            ReflowReason::StylesheetLoaded => "StylesheetLoaded",
+            ReflowReason::ImageLoaded => "ImageLoaded",
+            ReflowReason::RequestAnimationFrame => "RequestAnimationFrame",
+            ReflowReason::WebFontLoaded => "WebFontLoaded",
+            ReflowReason::FramedContentChanged => "FramedContentChanged",
+            ReflowReason::IFrameLoadEvent => "IFrameLoadEvent",
+            ReflowReason::MissingExplicitReflow => "MissingExplicitReflow",
+            ReflowReason::InvalidationOverlaysChanged => "InvalidationOverlaysChanged",
+            ReflowReason::HighlightedNodeChanged => "HighlightedNodeChanged",
+            ReflowReason::StyleRuleEdited => "StyleRuleEdited",
+            ReflowReason::TextEditing => "TextEditing",
+            ReflowReason::AuthorStylesToggled => "AuthorStylesToggled",
+            ReflowReason::ForcedColorsToggled => "ForcedColorsToggled",
+            ReflowReason::TextZoomChanged => "TextZoomChanged",
+        }
+    }
 }
 
 pub type ScrollPoint = Point2D<Au>;
@@ -159,6 +205,7 @@ pub struct Window {
     navigation_start: u64,
     navigation_start_precise: f64,
     screen: MutNullableHeap<JS<Screen>>,
+    visual_viewport: MutNullableHeap<JS<VisualViewport>>,
     session_storage: MutNullableHeap<JS<Storage>>,
     local_storage: MutNullableHeap<JS<Storage>>,
     status: DOMRefCell<DOMString>,
@@ -219,6 +266,9 @@ pub struct Window {
     /// The current size of the window, in pixels.
     window_size: Cell<Option<WindowSizeData>>,
 
+    /// The part of the viewport that is visible on screen, as last reported by the compositor.
+    visual_viewport_data: Cell<Option<VisualViewportData>>,
+
     /// Associated resource threads for use by DOM objects like XMLHttpRequest,
     /// including resource_thread, filemanager_thread and storage_thread
     resource_threads: ResourceThreads,
@@ -227,6 +277,10 @@ pub struct Window {
     #[ignore_heap_size_of = "channels are hard"]
     bluetooth_thread: IpcSender<BluetoothMethodMsg>,
 
+    /// A handle for communicating messages to the USB thread.
+    #[ignore_heap_size_of = "channels are hard"]
+    usb_thread: IpcSender<UsbMethodMsg>,
+
     /// A handle for communicating messages to the constellation thread.
     #[ignore_heap_size_of = "channels are hard"]
     constellation_chan: IpcSender<ConstellationMsg>,
@@ -243,9 +297,17 @@ pub struct Window {
     /// suppress others like MissingExplicitReflow.
     suppress_reflow: Cell<bool>,
 
+    /// The time of the animation tick whose animation frame callbacks have run, until the reflow
+    /// for display that renders it.
+    animation_tick: Cell<Option<f64>>,
+
     /// A counter of the number of pending reflows for this window.
     pending_reflow_count: Cell<u32>,
 
+    /// Whether a text control was edited since the last reflow for display. That reflow waits
+    /// until the events queued after the edit have been handled.
+    text_editing_reflow_pending: Cell<bool>,
+
     /// A channel for communicating results of async scripts back to the webdriver server
     #[ignore_heap_size_of = "channels are hard"]
     webdriver_script_chan: DOMRefCell<Option<IpcSender<WebDriverJSResult>>>,
@@ -263,6 +325,9 @@ pub struct Window {
 
     #[ignore_heap_size_of = "Defined in ipc-channel"]
     panic_chan: IpcSender<PanicMsg>,
+
+    /// The origin trial features enabled for this window's document.
+    origin_trials: DOMRefCell<OriginTrials>,
 }
 
 impl Window {
@@ -348,6 +413,10 @@ impl Window {
         self.bluetooth_thread.clone()
     }
 
+    pub fn usb_thread(&self) -> IpcSender<UsbMethodMsg> {
+        self.usb_thread.clone()
+    }
+
     pub fn css_error_reporter(&self) -> Box<ParseErrorReporter + Send> {
         self.error_reporter.clone()
     }
@@ -603,6 +672,11 @@ impl WindowMethods for Window {
         self.screen.or_init(|| Screen::new(self))
     }
 
+    // https://wicg.github.io/visual-viewport/#dom-window-visualviewport
+    fn VisualViewport(&self) -> Root<VisualViewport> {
+        self.visual_viewport.or_init(|| VisualViewport::new(self))
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-windowbase64-btoa
     fn Btoa(&self, btoa: DOMString) -> Fallible<DOMString> {
         base64_btoa(btoa)
@@ -684,6 +758,9 @@ impl WindowMethods for Window {
                 Some(PseudoElement::Before),
             Some(ref pseudo) if pseudo == ":after" || pseudo == "::after" =>
                 Some(PseudoElement::After),
+            Some(ref pseudo) if pseudo == ":first-line" || pseudo == "::first-line" =>
+                Some(PseudoElement::FirstLine),
+            Some(ref pseudo) if pseudo == "::marker" => Some(PseudoElement::Marker),
             _ => None
         };
 
@@ -984,10 +1061,15 @@ impl Window {
         //TODO Step 1
         let point = Point2D::new(x, y);
         let smooth = match behavior {
+            // The viewport scrolls with the `scroll-behavior` of the root element.
             ScrollBehavior::Auto => {
-                element.map_or(false, |_element| {
-                    // TODO check computed scroll-behaviour CSS property
-                    true
+                let root = self.Document().GetDocumentElement();
+                element.or(root.r()).map_or(false, |element| {
+                    let node = element.upcast::<Node>();
+                    let scroll_behavior = self.resolved_style_query(node.to_trusted_node_address(),
+                                                                    None,
+                                                                    &Atom::from("scroll-behavior"));
+                    scroll_behavior.map_or(false, |scroll_behavior| scroll_behavior == "smooth")
                 })
             }
             ScrollBehavior::Instant => false,
@@ -1054,6 +1136,14 @@ impl Window {
 
         let document = self.Document();
         let stylesheets_changed = document.get_and_reset_stylesheets_changed_since_reflow();
+        let dom_version = document.upcast::<Node>().inclusive_descendants_version();
+        let animation_tick = if for_display {
+            let animation_tick = self.animation_tick.get();
+            self.animation_tick.set(None);
+            animation_tick
+        } else {
+            None
+        };
 
         // Send new document and relevant styles to layout.
         let reflow = ScriptReflow {
@@ -1067,6 +1157,10 @@ impl Window {
             window_size: window_size,
             script_join_chan: join_chan,
             query_type: query_type,
+            dom_version: dom_version,
+            animation_tick: animation_tick,
+            record_phase_markers: marker.is_some(),
+            reason: reason.name(),
         };
 
         self.layout_chan.send(Msg::Reflow(reflow)).unwrap();
@@ -1086,14 +1180,25 @@ impl Window {
 
         debug!("script: layout joined");
 
+        // The response to a query must reflect all of the DOM mutations made before it.
+        debug_assert!(for_display || self.layout_rpc.dom_version() >= dom_version);
+
         // Pending reflows require display, so only reset the pending reflow count if this reflow
         // was to be displayed.
         if goal == ReflowGoal::ForDisplay {
             self.pending_reflow_count.set(0);
+            self.text_editing_reflow_pending.set(false);
         }
 
         if let Some(marker) = marker {
             self.emit_timeline_marker(marker.end());
+            let PhaseMarkersResponse(phases) = self.layout_rpc.phase_markers();
+            for phase in phases {
+                self.emit_timeline_marker(TimelineMarker::with_count(phase.name.to_owned(),
+                                                                     phase.start_time,
+                                                                     phase.end_time,
+                                                                     phase.count));
+            }
         }
     }
 
@@ -1102,6 +1207,20 @@ impl Window {
     /// yet, the page is presumed invisible and no reflow is performed.
     ///
     /// TODO(pcwalton): Only wait for style recalc, since we have off-main-thread layout.
+    /// Reflows for display after the animation frame callbacks of the animation tick at
+    /// `frame_time` have run, in the same layout that samples the animations of layout at that
+    /// time. If nothing needs to be reflowed, layout is ticked on its own instead.
+    pub fn reflow_for_animation_tick(&self, frame_time: f64) {
+        self.animation_tick.set(Some(frame_time));
+        self.reflow(ReflowGoal::ForDisplay,
+                    ReflowQueryType::NoQuery,
+                    ReflowReason::RequestAnimationFrame);
+        if let Some(frame_time) = self.animation_tick.get() {
+            self.animation_tick.set(None);
+            self.layout_chan.send(Msg::TickAnimations(frame_time)).unwrap();
+        }
+    }
+
     pub fn reflow(&self, goal: ReflowGoal, query_type: ReflowQueryType, reason: ReflowReason) {
         let for_display = query_type == ReflowQueryType::NoQuery;
 
@@ -1251,6 +1370,39 @@ impl Window {
         self.layout_rpc.margin_style()
     }
 
+    pub fn animation_state_query(&self, node: TrustedNodeAddress) -> Vec<NodeAnimation> {
+        self.reflow(ReflowGoal::ForScriptQuery,
+                    ReflowQueryType::AnimationStateQuery(node),
+                    ReflowReason::Query);
+        self.layout_rpc.animation_state().0
+    }
+
+    /// Returns the style rules that match a node or a pseudo-element of it, and whether that
+    /// pseudo-element exists.
+    pub fn matched_rules_query(&self, node: TrustedNodeAddress, pseudo: Option<PseudoElement>)
+                               -> (Vec<MatchedRule>, bool) {
+        self.reflow(ReflowGoal::ForScriptQuery,
+                    ReflowQueryType::MatchedRulesQuery(node, pseudo),
+                    ReflowReason::Query);
+        let MatchedRulesResponse(rules, exists) = self.layout_rpc.matched_rules();
+        (rules, exists)
+    }
+
+    pub fn box_model_query(&self, node: TrustedNodeAddress) -> Option<BoxModel> {
+        self.reflow(ReflowGoal::ForScriptQuery,
+                    ReflowQueryType::BoxModelQuery(node),
+                    ReflowReason::Query);
+        self.layout_rpc.box_model().0
+    }
+
+    /// Returns the font faces that the text of the document is laid out in.
+    pub fn used_font_faces_query(&self) -> Vec<UsedFontFace> {
+        self.reflow(ReflowGoal::ForScriptQuery,
+                    ReflowQueryType::UsedFontFacesQuery,
+                    ReflowReason::Query);
+        self.layout_rpc.used_font_faces().0
+    }
+
     #[allow(unsafe_code)]
     pub fn init_browsing_context(&self, browsing_context: &BrowsingContext) {
         assert!(self.browsing_context.get().is_none());
@@ -1355,6 +1507,17 @@ impl Window {
         self.pending_reflow_count.set(self.pending_reflow_count.get() + 1);
     }
 
+    /// Lays out the edit of a focused text control once the events queued after it have been
+    /// handled, rather than after every key, so that typing does not wait on layout. Queries
+    /// still lay the edit out as soon as they need it.
+    pub fn schedule_text_editing_reflow(&self) {
+        self.text_editing_reflow_pending.set(true);
+    }
+
+    pub fn text_editing_reflow_pending(&self) -> bool {
+        self.text_editing_reflow_pending.get()
+    }
+
     pub fn set_resize_event(&self, event: WindowSizeData, event_type: WindowSizeType) {
         self.resize_event.set(Some((event, event_type)));
     }
@@ -1365,6 +1528,44 @@ impl Window {
         event
     }
 
+    /// The layout viewport, in the coordinates of the page.
+    pub fn current_viewport(&self) -> Rect<Au> {
+        self.current_viewport.get()
+    }
+
+    /// The part of the viewport that is visible on screen. Until the compositor reports it, it
+    /// is the whole viewport.
+    pub fn visual_viewport(&self) -> VisualViewportData {
+        self.visual_viewport_data.get().unwrap_or_else(|| {
+            let viewport = self.current_viewport.get();
+            VisualViewportData {
+                page_offset: Point2D::new(viewport.origin.x.to_f32_px(),
+                                          viewport.origin.y.to_f32_px()),
+                size: self.window_size.get().map_or(Size2D::zero(), |window_size| {
+                    window_size.visible_viewport.to_untyped()
+                }),
+                scale: 1.0,
+            }
+        })
+    }
+
+    /// Updates the part of the viewport that is visible on screen, and fires the `resize` and
+    /// `scroll` events of `window.visualViewport` when it changes size or moves over the page.
+    pub fn set_visual_viewport(&self, data: VisualViewportData) {
+        let old_data = self.visual_viewport();
+        self.visual_viewport_data.set(Some(data));
+        let visual_viewport = match self.visual_viewport.get() {
+            Some(visual_viewport) => visual_viewport,
+            None => return,
+        };
+        if old_data.size != data.size || old_data.scale != data.scale {
+            visual_viewport.upcast::<EventTarget>().fire_simple_event("resize");
+        }
+        if old_data.page_offset != data.page_offset {
+            visual_viewport.upcast::<EventTarget>().fire_simple_event("scroll");
+        }
+    }
+
     pub fn set_page_clip_rect_with_new_viewport(&self, viewport: Rect<f32>) -> bool {
         let rect = geometry::f32_rect_to_au_rect(viewport.clone());
         self.current_viewport.set(rect);
@@ -1445,6 +1646,14 @@ impl Window {
         *self.webdriver_script_chan.borrow_mut() = chan;
     }
 
+    pub fn origin_trials(&self) -> Ref<OriginTrials> {
+        self.origin_trials.borrow()
+    }
+
+    pub fn origin_trials_mut(&self) -> RefMut<OriginTrials> {
+        self.origin_trials.borrow_mut()
+    }
+
     pub fn is_alive(&self) -> bool {
         self.current_state.get() == WindowState::Alive
     }
@@ -1488,6 +1697,7 @@ impl Window {
                image_cache_thread: ImageCacheThread,
                resource_threads: ResourceThreads,
                bluetooth_thread: IpcSender<BluetoothMethodMsg>,
+               usb_thread: IpcSender<UsbMethodMsg>,
                mem_profiler_chan: mem::ProfilerChan,
                time_profiler_chan: ProfilerChan,
                devtools_chan: Option<IpcSender<ScriptToDevtoolsControlMsg>>,
@@ -1533,6 +1743,7 @@ impl Window {
             navigation_start: (current_time.sec * 1000 + current_time.nsec as i64 / 1000000) as u64,
             navigation_start_precise: time::precise_time_ns() as f64,
             screen: Default::default(),
+            visual_viewport: Default::default(),
             session_storage: Default::default(),
             local_storage: Default::default(),
             status: DOMRefCell::new(DOMString::new()),
@@ -1545,6 +1756,7 @@ impl Window {
             js_runtime: DOMRefCell::new(Some(runtime.clone())),
             resource_threads: resource_threads,
             bluetooth_thread: bluetooth_thread,
+            usb_thread: usb_thread,
             constellation_chan: constellation_chan,
             page_clip_rect: Cell::new(MAX_RECT),
             fragment_name: DOMRefCell::new(None),
@@ -1553,9 +1765,12 @@ impl Window {
             layout_chan: layout_chan,
             layout_rpc: layout_rpc,
             window_size: Cell::new(window_size),
+            visual_viewport_data: Cell::new(None),
             current_viewport: Cell::new(Rect::zero()),
             suppress_reflow: Cell::new(true),
+            animation_tick: Cell::new(None),
             pending_reflow_count: Cell::new(0),
+            text_editing_reflow_pending: Cell::new(false),
             current_state: Cell::new(WindowState::Alive),
 
             devtools_marker_sender: DOMRefCell::new(None),
@@ -1565,6 +1780,7 @@ impl Window {
             ignore_further_async_events: Arc::new(AtomicBool::new(false)),
             error_reporter: error_reporter,
             panic_chan: panic_chan,
+            origin_trials: DOMRefCell::new(OriginTrials::new()),
         };
 
         WindowBinding::Wrap(runtime.cx(), win)
@@ -1593,47 +1809,10 @@ fn should_move_clip_rect(clip_rect: Rect<Au>, new_viewport: Rect<f32>) -> bool {
 }
 
 fn debug_reflow_events(id: PipelineId, goal: &ReflowGoal, query_type: &ReflowQueryType, reason: &ReflowReason) {
-    let mut debug_msg = format!("**** pipeline={}", id);
-    debug_msg.push_str(match *goal {
-        ReflowGoal::ForDisplay => "\tForDisplay",
-        ReflowGoal::ForScriptQuery => "\tForScriptQuery",
-    });
-
-    debug_msg.push_str(match *query_type {
-        ReflowQueryType::NoQuery => "\tNoQuery",
-        ReflowQueryType::ContentBoxQuery(_n) => "\tContentBoxQuery",
-        ReflowQueryType::ContentBoxesQuery(_n) => "\tContentBoxesQuery",
-        ReflowQueryType::HitTestQuery(_n, _o) => "\tHitTestQuery",
-        ReflowQueryType::NodeGeometryQuery(_n) => "\tNodeGeometryQuery",
-        ReflowQueryType::NodeLayerIdQuery(_n) => "\tNodeLayerIdQuery",
-        ReflowQueryType::NodeOverflowQuery(_n) => "\tNodeOverFlowQuery",
-        ReflowQueryType::NodeScrollGeometryQuery(_n) => "\tNodeScrollGeometryQuery",
-        ReflowQueryType::ResolvedStyleQuery(_, _, _) => "\tResolvedStyleQuery",
-        ReflowQueryType::OffsetParentQuery(_n) => "\tOffsetParentQuery",
-        ReflowQueryType::MarginStyleQuery(_n) => "\tMarginStyleQuery",
-    });
-
-    debug_msg.push_str(match *reason {
-        ReflowReason::CachedPageNeededReflow => "\tCachedPageNeededReflow",
-        ReflowReason::RefreshTick => "\tRefreshTick",
-        ReflowReason::FirstLoad => "\tFirstLoad",
-        ReflowReason::KeyEvent => "\tKeyEvent",
-        ReflowReason::MouseEvent => "\tMouseEvent",
-        ReflowReason::Query => "\tQuery",
-        ReflowReason::Timer => "\tTimer",
-        ReflowReason::Viewport => "\tViewport",
-        ReflowReason::WindowResize => "\tWindowResize",
-        ReflowReason::DOMContentLoaded => "\tDOMContentLoaded",
-        ReflowReason::DocumentLoaded => "\tDocumentLoaded",
-        ReflowReason::StylesheetLoaded => "\tStylesheetLoaded",
-        ReflowReason::ImageLoaded => "\tImageLoaded",
-        ReflowReason::RequestAnimationFrame => "\tRequestAnimationFrame",
-        ReflowReason::WebFontLoaded => "\tWebFontLoaded",
-        ReflowReason::FramedContentChanged => "\tFramedContentChanged",
-        ReflowReason::IFrameLoadEvent => "\tIFrameLoadEvent",
-        ReflowReason::MissingExplicitReflow => "\tMissingExplicitReflow",
-    });
-
-    println!("{}", debug_msg);
+    let goal = match *goal {
+        ReflowGoal::ForDisplay => "ForDisplay",
+        ReflowGoal::ForScriptQuery => "ForScriptQuery",
+    };
+    println!("**** pipeline={}\t{}\t{}\t{}", id, goal, query_type.name(), reason.name());
 }
 
@@ -46,6 +46,7 @@ use js::jsapi::{JSContext, JS_ParseJSON, RootedValue};
 use js::jsval::{JSVal, NullValue, UndefinedValue};
 use msg::constellation_msg::{PipelineId, ReferrerPolicy};
 use net_traits::CoreResourceMsg::Load;
+use net_traits::reporting::Report;
 use net_traits::{AsyncResponseListener, AsyncResponseTarget, Metadata, NetworkError, RequestSource};
 use net_traits::{LoadConsumer, LoadContext, LoadData, ResourceCORSData, CoreResourceThread, LoadOrigin};
 use network_listener::{NetworkListener, PreInvoke};
@@ -400,6 +401,14 @@ impl XMLHttpRequestMethods for XMLHttpRequest {
                 *self.request_method.borrow_mut() = parsed_method;
                 *self.request_url.borrow_mut() = Some(parsed_url);
                 self.sync.set(!async);
+                if !async {
+                    if let GlobalRoot::Window(ref window) = self.global() {
+                        let report = Report::deprecation("SyncXMLHttpRequest",
+                                                         "Synchronous XMLHttpRequest on the main \
+                                                          thread is deprecated.");
+                        window.Document().queue_report(report);
+                    }
+                }
                 *self.request_headers.borrow_mut() = Headers::new();
                 self.send_flag.set(false);
                 *self.status_text.borrow_mut() = ByteString::new(vec!());
@@ -10,20 +10,25 @@ use app_units::Au;
 use dom::node::OpaqueStyleAndLayoutData;
 use euclid::point::Point2D;
 use euclid::rect::Rect;
-use gfx_traits::{Epoch, LayerId};
+use gfx_traits::{ConsistencyToken, LayerId};
 use ipc_channel::ipc::{IpcReceiver, IpcSender};
 use msg::constellation_msg::{PanicMsg, PipelineId, WindowSizeData};
 use net_traits::image_cache_thread::ImageCacheThread;
 use profile_traits::mem::ReportsChan;
-use script_traits::UntrustedNodeAddress;
+use script_traits::{UntrustedNodeAddress, UsedFontFace};
 use script_traits::{ConstellationControlMsg, LayoutControlMsg, LayoutMsg as ConstellationMsg};
+use selectors::parser::Selector;
 use std::sync::Arc;
 use std::sync::mpsc::{Receiver, Sender};
 use string_cache::Atom;
+use style::animation::{AnimationTiming, KeyframeEffect};
 use style::context::ReflowGoal;
 use style::properties::longhands::{margin_top, margin_right, margin_bottom, margin_left, overflow_x};
-use style::selector_impl::PseudoElement;
-use style::servo::Stylesheet;
+use style::properties::longhands::{position, z_index};
+use style::selector_impl::{PseudoElement, ServoSelectorImpl};
+use style::selector_matching::{MatchedRule, SelectorCost, StylesheetCoverage};
+use style::servo::{SharedCascadeSnapshot, StyleRuleEdit, Stylesheet};
+use style_traits::forced_colors::ForcedColors;
 use url::Url;
 use util::ipc::OptionalOpaqueIpcSender;
 
@@ -37,14 +42,27 @@ pub enum Msg {
     /// Puts a document into quirks mode, causing the quirks mode stylesheet to be loaded.
     SetQuirksMode,
 
+    /// Sets whether author styles apply to the document, or only user-agent and user ones. Script
+    /// restyles the document afterwards.
+    SetAuthorStylesEnabled(bool),
+
+    /// Puts the document in the forced colors mode of the given palette, or out of forced colors
+    /// modes. Script restyles the document afterwards.
+    SetForcedColors(Option<ForcedColors>),
+
+    /// Sets the factor that the text of the document is zoomed by, without zooming images or the
+    /// viewport. Script restyles the document afterwards.
+    SetTextZoom(f32),
+
     /// Requests a reflow.
     Reflow(ScriptReflow),
 
     /// Get an RPC interface.
     GetRPC(Sender<Box<LayoutRPC + Send>>),
 
-    /// Requests that the layout thread render the next frame of all animations.
-    TickAnimations,
+    /// Requests that the layout thread render the frame of all animations at the given time of
+    /// the animation tick, as returned by `time::precise_time_s()`.
+    TickAnimations(f64),
 
     /// Requests that the layout thread reflow with a newly-loaded Web font.
     ReflowWithNewlyLoadedWebFont,
@@ -71,8 +89,9 @@ pub enum Msg {
     /// this, or layout will crash.
     ExitNow,
 
-    /// Get the last epoch counter for this layout thread.
-    GetCurrentEpoch(IpcSender<Epoch>),
+    /// Get the last epoch counter for this layout thread, with the version of the DOM that the
+    /// display list of that epoch reflects.
+    GetCurrentEpoch(IpcSender<ConsistencyToken>),
 
     /// Asks the layout thread whether any Web fonts have yet to load (if true, loads are pending;
     /// false otherwise).
@@ -85,6 +104,93 @@ pub enum Msg {
 
     /// Set the final Url.
     SetFinalUrl(Url),
+
+    /// Enables or disables the debugging overlays that visualize invalidated regions.
+    SetInvalidationOverlays(InvalidationOverlays),
+
+    /// Asks the layout thread how many of the rules of each of the given stylesheets have matched
+    /// an element so far, and which have not. The reply is `None` unless CSS coverage tracking is
+    /// enabled.
+    GetRuleCoverage(Vec<Arc<Stylesheet>>, Sender<Option<Vec<StylesheetCoverage>>>),
+
+    /// Asks the layout thread how costly the selectors of style rules have been to match, from
+    /// the costliest on, and whether to count from zero again afterwards. The reply is `None`
+    /// unless selector profiling is enabled.
+    GetSelectorCosts(bool, Sender<Option<Vec<SelectorCost>>>),
+
+    /// Requests the fragment tree of the last layout as JSON, or `None` if nothing has been laid
+    /// out yet.
+    GetFragmentTree(Sender<Option<String>>),
+
+    /// Requests the state of all running animations, for the devtools animation inspector.
+    GetRunningAnimations(Sender<Vec<RunningAnimation>>),
+
+    /// Pauses, resumes or seeks the running animations of the given property of the given node.
+    ControlAnimation(TrustedNodeAddress, String, AnimationControl),
+
+    /// Starts an animation that script created with `Element.animate()` on the given node, which
+    /// it knows by the given number.
+    AddKeyframesAnimation(TrustedNodeAddress, u32, KeyframeEffect, AnimationTiming),
+
+    /// Pauses, resumes or seeks the animation that script created with the given number.
+    ControlKeyframesAnimation(u32, AnimationControl),
+
+    /// Drops the animation that script created with the given number.
+    CancelKeyframesAnimation(u32),
+
+    /// Draws the devtools highlight overlay over the box model of the given node, or removes it.
+    SetHighlightedNode(Option<TrustedNodeAddress>),
+
+    /// Replaces the selectors or the declarations of the style rule whose selectors start at the
+    /// given line and column of the given stylesheet, as devtools asked. The reply is the
+    /// selectors that the rule had, or `None` if there is no such rule.
+    EditStyleRule(Arc<Stylesheet>,
+                  usize,
+                  usize,
+                  StyleRuleEdit,
+                  Sender<Option<Vec<Selector<ServoSelectorImpl>>>>),
+}
+
+/// The state of a running animation, as reported to devtools.
+pub struct RunningAnimation {
+    /// The node being animated.
+    pub node: UntrustedNodeAddress,
+    /// The name of the animated property.
+    pub property: String,
+    /// The duration of the animation, in seconds.
+    pub duration: f64,
+    /// The number of seconds since the animation started. This is negative while the animation
+    /// is delayed.
+    pub elapsed_time: f64,
+    /// Whether the animation has been paused.
+    pub paused: bool,
+}
+
+/// Changes to the playback of an animation that devtools can request.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AnimationControl {
+    Pause,
+    Play,
+    /// Moves the animation to the given number of seconds since its start.
+    Seek(f64),
+}
+
+/// Debugging overlays that visualize what was invalidated by the most recent layout.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InvalidationOverlays {
+    /// Whether to tint the areas of flows that were repainted.
+    pub paint_flashing: bool,
+    /// Whether to outline flows that were reflowed.
+    pub reflow_outlines: bool,
+    /// Whether to outline the flows that are painted into layers of their own.
+    pub layer_borders: bool,
+}
+
+impl InvalidationOverlays {
+    /// Returns true if any overlay is enabled.
+    pub fn any(&self) -> bool {
+        self.paint_flashing || self.reflow_outlines || self.layer_borders
+    }
 }
 
 /// Synchronous messages that script can send to layout.
@@ -115,6 +221,22 @@ pub trait LayoutRPC {
     fn offset_parent(&self) -> OffsetParentResponse;
     /// Query layout for the resolve values of the margin properties for an element.
     fn margin_style(&self) -> MarginStyleResponse;
+    /// Requests the transitions and the animations that script created that run on a node.
+    fn animation_state(&self) -> AnimationStateResponse;
+    /// Requests the style rules that match a node, or a pseudo-element of it.
+    fn matched_rules(&self) -> MatchedRulesResponse;
+    /// Requests the boxes of a node, for the devtools box model overlay.
+    fn box_model(&self) -> BoxModelResponse;
+    /// Requests the font faces that the text of the document is laid out in.
+    fn used_font_faces(&self) -> UsedFontFacesResponse;
+    /// Requests the phases that the last script reflow went through, if it recorded them.
+    fn phase_markers(&self) -> PhaseMarkersResponse;
+    /// Shares the snapshots of what styles are cascaded against, which layout keeps up to date
+    /// after each reflow, so that styles can be resolved without asking layout again.
+    fn cascade_snapshot(&self) -> SharedCascadeSnapshot;
+    /// The version of the DOM that the last reflow reflected, which the responses to queries are
+    /// as fresh as.
+    fn dom_version(&self) -> u64;
 
     fn nodes_from_point(&self, point: Point2D<f32>) -> Vec<UntrustedNodeAddress>;
 }
@@ -155,6 +277,78 @@ pub struct NodeLayerIdResponse {
 
 pub struct ResolvedStyleResponse(pub Option<String>);
 
+/// The animations running on a node, in the order that they started.
+pub struct AnimationStateResponse(pub Vec<NodeAnimation>);
+
+/// The style rules that match a node or a pseudo-element of it, in the order in which they are
+/// cascaded, and whether that pseudo-element exists. Rules can match pseudo-elements that do not,
+/// such as `::before` without `content`.
+pub struct MatchedRulesResponse(pub Vec<MatchedRule>, pub bool);
+
+/// The box model of a node, or `None` if it has no boxes.
+pub struct BoxModelResponse(pub Option<BoxModel>);
+
+/// The corners of a box in page coordinates, clockwise from its top left corner. The transforms
+/// of a node and of its ancestors can make boxes any quadrilateral.
+pub type BoxQuad = [Point2D<f32>; 4];
+
+/// The margin, border, padding and content boxes of a node, along with the properties that decide
+/// how it is positioned and stacked.
+#[derive(Clone, Debug)]
+pub struct BoxModel {
+    pub margin: BoxQuad,
+    pub border: BoxQuad,
+    pub padding: BoxQuad,
+    pub content: BoxQuad,
+    pub position: position::T,
+    pub z_index: z_index::T,
+}
+
+/// The font faces that text is laid out in, in the order in which the text that first uses each
+/// of them comes in the flow tree.
+pub struct UsedFontFacesResponse(pub Vec<UsedFontFace>);
+
+/// The phases of a reflow, in the order that they ran.
+pub struct PhaseMarkersResponse(pub Vec<LayoutPhaseMarker>);
+
+/// A phase of a reflow, for timelines to show where its time went.
+#[derive(Clone, Debug)]
+pub struct LayoutPhaseMarker {
+    pub name: &'static str,
+    /// When the phase started and ended, as returned by `time::precise_time_ns()`. Selector
+    /// matching and the cascade interleave with flow construction node by node, so they are laid
+    /// end to end at the start of the traversal instead, lasting the time that every thread spent
+    /// on them divided by the number of threads.
+    pub start_time: u64,
+    pub end_time: u64,
+    /// The number of nodes, flows or display items that the phase ran for.
+    pub count: usize,
+}
+
+/// What runs an animation on a node.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeAnimationSource {
+    /// A transition of the property with the given name.
+    Transition(String),
+    /// An animation that script created, with the number that script knows it by.
+    Script(u32),
+}
+
+/// The state of an animation running on a node.
+#[derive(Clone, Debug)]
+pub struct NodeAnimation {
+    pub source: NodeAnimationSource,
+    /// The time at which the animation started, after its delay, as returned by
+    /// `time::precise_time_s()`.
+    pub start_time: f64,
+    /// The duration of all its iterations, in seconds.
+    pub duration: f64,
+    /// Whether the animation has been paused.
+    pub paused: bool,
+    /// How far through the duration the animation is, between 0 and 1.
+    pub progress: f64,
+}
+
 #[derive(Clone)]
 pub struct OffsetParentResponse {
     pub node_address: Option<UntrustedNodeAddress>,
@@ -184,6 +378,33 @@ pub enum ReflowQueryType {
     ResolvedStyleQuery(TrustedNodeAddress, Option<PseudoElement>, Atom),
     OffsetParentQuery(TrustedNodeAddress),
     MarginStyleQuery(TrustedNodeAddress),
+    AnimationStateQuery(TrustedNodeAddress),
+    MatchedRulesQuery(TrustedNodeAddress, Option<PseudoElement>),
+    BoxModelQuery(TrustedNodeAddress),
+    UsedFontFacesQuery,
+}
+
+impl ReflowQueryType {
+    /// The name of the type of query, for debugging output.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ReflowQueryType::NoQuery => "NoQuery",
+            ReflowQueryType::ContentBoxQuery(_) => "ContentBoxQuery",
+            ReflowQueryType::ContentBoxesQuery(_) => "ContentBoxesQuery",
+            ReflowQueryType::NodeOverflowQuery(_) => "NodeOverflowQuery",
+            ReflowQueryType::HitTestQuery(_, _) => "HitTestQuery",
+            ReflowQueryType::NodeGeometryQuery(_) => "NodeGeometryQuery",
+            ReflowQueryType::NodeLayerIdQuery(_) => "NodeLayerIdQuery",
+            ReflowQueryType::NodeScrollGeometryQuery(_) => "NodeScrollGeometryQuery",
+            ReflowQueryType::ResolvedStyleQuery(_, _, _) => "ResolvedStyleQuery",
+            ReflowQueryType::OffsetParentQuery(_) => "OffsetParentQuery",
+            ReflowQueryType::MarginStyleQuery(_) => "MarginStyleQuery",
+            ReflowQueryType::AnimationStateQuery(_) => "AnimationStateQuery",
+            ReflowQueryType::MatchedRulesQuery(_, _) => "MatchedRulesQuery",
+            ReflowQueryType::BoxModelQuery(_) => "BoxModelQuery",
+            ReflowQueryType::UsedFontFacesQuery => "UsedFontFacesQuery",
+        }
+    }
 }
 
 /// Information needed for a reflow.
@@ -210,6 +431,16 @@ pub struct ScriptReflow {
     pub script_join_chan: Sender<()>,
     /// The type of query if any to perform during this reflow.
     pub query_type: ReflowQueryType,
+    /// The version of the DOM that this reflow reflects, which is that of the document.
+    pub dom_version: u64,
+    /// The time of the animation tick that this reflow renders, if it follows the animation
+    /// frame callbacks of that tick, as returned by `time::precise_time_s()`. Layout samples all
+    /// of its animations at that time too, instead of being ticked on its own.
+    pub animation_tick: Option<f64>,
+    /// Whether to record the phases that the reflow goes through, for `LayoutRPC::phase_markers`.
+    pub record_phase_markers: bool,
+    /// Why script asked for the reflow, for reflow logs.
+    pub reason: &'static str,
 }
 
 impl Drop for ScriptReflow {
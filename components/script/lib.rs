@@ -75,6 +75,7 @@ extern crate smallvec;
 #[macro_use(atom, ns)] extern crate string_cache;
 #[macro_use]
 extern crate style;
+extern crate style_traits;
 extern crate time;
 #[cfg(any(target_os = "macos", target_os = "linux"))]
 extern crate tinyfiledialogs;
@@ -87,6 +88,7 @@ extern crate webrender_traits;
 extern crate websocket;
 extern crate xml5ever;
 
+pub mod accessibility;
 pub mod clipboard_provider;
 pub mod cors;
 mod devtools;
@@ -97,6 +99,7 @@ pub mod layout_interface;
 mod mem;
 mod network_listener;
 pub mod origin;
+pub mod origin_trials;
 pub mod parse;
 pub mod reporter;
 pub mod script_runtime;
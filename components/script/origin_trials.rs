@@ -0,0 +1,118 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Runtime gating of the experimental interfaces and members marked `[OriginTrial="feature"]` in
+//! WebIDL. They are compiled in, but only exposed where their feature is enabled:
+//!
+//! * everywhere, if the `dom.origin-trials.<feature>.enabled` pref is set;
+//! * in windows whose origin is in the space-separated `dom.origin-trials.<feature>.origins` pref;
+//! * in windows whose document came with a token for the feature, in an `Origin-Trial` header or
+//!   an `<meta http-equiv="origin-trial">` element, that the `origin-trial-tokens.json` resource
+//!   file grants to the document's origin and that has not expired.
+
+use dom::bindings::global::{GlobalRef, global_root_from_object};
+use js::jsapi::{HandleObject, JSContext};
+use rustc_serialize::json::decode;
+use std::collections::HashMap;
+use std::str::from_utf8;
+use time;
+use url::Url;
+use util::prefs::get_pref;
+use util::resource_files::read_resource_file;
+
+/// What a token in the token file grants.
+#[derive(RustcDecodable)]
+struct TrialToken {
+    feature: String,
+    origin: String,
+    /// When the token expires, in seconds since the epoch.
+    expiry: Option<i64>,
+}
+
+fn read_tokens() -> HashMap<String, TrialToken> {
+    read_resource_file("origin-trial-tokens.json").ok()
+        .and_then(|bytes| from_utf8(&bytes).ok().and_then(|tokens| decode(tokens).ok()))
+        .unwrap_or_else(HashMap::new)
+}
+
+thread_local!(static TOKENS: HashMap<String, TrialToken> = read_tokens());
+
+/// The origin trial features enabled for a window.
+#[derive(HeapSizeOf, JSTraceable)]
+pub struct OriginTrials {
+    /// The URL of the window's document.
+    url: Option<Url>,
+    /// The features enabled by the document's tokens.
+    features: Vec<String>,
+}
+
+impl OriginTrials {
+    pub fn new() -> OriginTrials {
+        OriginTrials {
+            url: None,
+            features: vec![],
+        }
+    }
+
+    /// Start over for a new document.
+    pub fn reset(&mut self, url: Url) {
+        self.url = Some(url);
+        self.features.clear();
+    }
+
+    /// Enable the feature of the given token, if it is valid for the document's origin.
+    pub fn add_token(&mut self, token: &str) {
+        let url = match self.url {
+            Some(ref url) => url,
+            None => return,
+        };
+        let feature = TOKENS.with(|tokens| {
+            tokens.get(token.trim()).and_then(|token| {
+                let same_origin = Url::parse(&token.origin).ok().map_or(false, |origin| {
+                    origin.origin() == url.origin()
+                });
+                let expired = token.expiry.map_or(false, |expiry| expiry < time::get_time().sec);
+                if same_origin && !expired {
+                    Some(token.feature.clone())
+                } else {
+                    None
+                }
+            })
+        });
+        match feature {
+            Some(feature) => self.features.push(feature),
+            None => warn!("Ignoring invalid origin trial token {}", token),
+        }
+    }
+
+    pub fn is_enabled(&self, feature: &str) -> bool {
+        if self.features.iter().any(|enabled| enabled == feature) {
+            return true
+        }
+        let url = match self.url {
+            Some(ref url) => url,
+            None => return false,
+        };
+        let origins = get_pref(&format!("dom.origin-trials.{}.origins", feature));
+        origins.as_string().map_or(false, |origins| {
+            origins.split_whitespace().any(|origin| {
+                Url::parse(origin).ok().map_or(false, |origin| origin.origin() == url.origin())
+            })
+        })
+    }
+}
+
+/// Whether the given feature is enabled in the global of `obj`. This is the condition of
+/// `[OriginTrial]` interfaces and members.
+#[allow(unsafe_code)]
+pub unsafe fn is_enabled(_: *mut JSContext, obj: HandleObject, feature: &str) -> bool {
+    if get_pref(&format!("dom.origin-trials.{}.enabled", feature)).as_boolean().unwrap_or(false) {
+        return true
+    }
+    match global_root_from_object(obj.get()).r() {
+        GlobalRef::Window(window) => window.origin_trials().is_enabled(feature),
+        // Workers do not get tokens of their own.
+        _ => false,
+    }
+}
@@ -5,25 +5,31 @@
 //! The script runtime contains common traits and structs commonly used by the
 //! script thread, the dom, and the worker threads.
 
+use dom::bindings::audit::{Owner, TracingOwner};
+use dom::bindings::callback::ExceptionHandling;
+use dom::bindings::codegen::Bindings::PromiseBinding::PromiseJobCallback;
+use dom::bindings::global::global_root_from_object;
 use dom::bindings::js::{RootCollection, RootCollectionPtr, trace_roots};
 use dom::bindings::refcounted::{LiveDOMReferences, TrustedReference, trace_refcounted_objects};
-use dom::bindings::trace::trace_traceables;
+use dom::bindings::trace::{JSTraceable, trace_traceables};
 use dom::bindings::utils::DOM_CALLBACKS;
 use js::glue::CollectServoSizes;
-use js::jsapi::{DisableIncrementalGC, GCDescription, GCProgress};
+use js::jsapi::{DisableIncrementalGC, GCDescription, GCProgress, HandleObject};
 use js::jsapi::{JSContext, JS_GetRuntime, JSRuntime, JSTracer, SetDOMCallbacks, SetGCSliceCallback};
 use js::jsapi::{JSGCInvocationKind, JSGCStatus, JS_AddExtraGCRootsTracer, JS_SetGCCallback};
 use js::jsapi::{JSGCMode, JSGCParamKey, JS_SetGCParameter, JS_SetGlobalJitCompilerOption};
 use js::jsapi::{JSJitCompilerOption, JS_SetOffthreadIonCompilationEnabled, JS_SetParallelParsingEnabled};
-use js::jsapi::{JSObject, RuntimeOptionsRef, SetPreserveWrapperCallback};
+use js::jsapi::{JSObject, RuntimeOptionsRef, SetEnqueuePromiseJobCallback};
+use js::jsapi::SetPreserveWrapperCallback;
 use js::rust::Runtime;
 use profile_traits::mem::{Report, ReportKind, ReportsChan};
 use script_thread::{Runnable, STACK_ROOTS, trace_thread};
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::io::{Write, stdout};
 use std::marker::PhantomData;
 use std::os;
 use std::ptr;
+use std::rc::Rc;
 use time::{Tm, now};
 use util::opts;
 use util::prefs::get_pref;
@@ -101,6 +107,8 @@ pub unsafe fn new_rt_and_cx(parent_rt: *mut JSRuntime) -> Runtime {
 
     JS_AddExtraGCRootsTracer(runtime.rt(), Some(trace_rust_roots), ptr::null_mut());
     JS_AddExtraGCRootsTracer(runtime.rt(), Some(trace_refcounted_objects), ptr::null_mut());
+    JS_AddExtraGCRootsTracer(runtime.rt(), Some(trace_promise_jobs), ptr::null_mut());
+    SetEnqueuePromiseJobCallback(runtime.rt(), Some(enqueue_promise_job), ptr::null_mut());
 
     // Needed for debug assertions about whether GC is running.
     if cfg!(debug_assertions) {
@@ -374,12 +382,68 @@ unsafe extern "C" fn debug_gc_callback(_rt: *mut JSRuntime, status: JSGCStatus,
 #[allow(unsafe_code)]
 unsafe extern fn trace_rust_roots(tr: *mut JSTracer, _data: *mut os::raw::c_void) {
     debug!("starting custom root handler");
-    trace_thread(tr);
-    trace_traceables(tr);
-    trace_roots(tr);
+    {
+        let _owner = TracingOwner::new(Owner::Roots("the script thread"));
+        trace_thread(tr);
+    }
+    {
+        let _owner = TracingOwner::new(Owner::Roots("rooted traceables"));
+        trace_traceables(tr);
+    }
+    {
+        let _owner = TracingOwner::new(Owner::Roots("stack roots"));
+        trace_roots(tr);
+    }
     debug!("done custom root handler");
 }
 
+thread_local!(static PROMISE_JOBS: RefCell<Vec<Rc<PromiseJobCallback>>> = RefCell::new(vec![]));
+
+/// Queue a job that runs the reactions of a settled promise. The jobs run together, from a task
+/// posted to the event loop of the job's global when the queue was empty.
+#[allow(unsafe_code)]
+unsafe extern "C" fn enqueue_promise_job(_cx: *mut JSContext,
+                                         job: HandleObject,
+                                         _allocation_site: HandleObject,
+                                         _data: *mut os::raw::c_void)
+                                         -> bool {
+    let was_empty = PROMISE_JOBS.with(|jobs| {
+        let mut jobs = jobs.borrow_mut();
+        jobs.push(PromiseJobCallback::new(job.get()));
+        jobs.len() == 1
+    });
+    if was_empty {
+        let global = global_root_from_object(job.get());
+        let msg = CommonScriptMsg::RunnableMsg(ScriptThreadEventCategory::ScriptEvent,
+                                               box FlushPromiseJobs);
+        let _ = global.r().script_chan().send(msg);
+    }
+    true
+}
+
+/// Runs the promise jobs of the current thread, including those they enqueue.
+struct FlushPromiseJobs;
+
+impl Runnable for FlushPromiseJobs {
+    fn handler(self: Box<FlushPromiseJobs>) {
+        loop {
+            // The job stays in the queue, and traced, until it has run.
+            let job = match PROMISE_JOBS.with(|jobs| jobs.borrow().first().cloned()) {
+                Some(job) => job,
+                None => return,
+            };
+            let _ = job.Call__(ExceptionHandling::Report);
+            PROMISE_JOBS.with(|jobs| jobs.borrow_mut().remove(0));
+        }
+    }
+}
+
+#[allow(unsafe_code)]
+unsafe extern fn trace_promise_jobs(tr: *mut JSTracer, _data: *mut os::raw::c_void) {
+    let _owner = TracingOwner::new(Owner::Roots("promise jobs"));
+    PROMISE_JOBS.with(|jobs| jobs.borrow().trace(tr));
+}
+
 #[allow(unsafe_code)]
 #[cfg(feature = "debugmozjs")]
 unsafe fn set_gc_zeal_options(cx: *mut JSContext) {
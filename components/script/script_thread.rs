@@ -17,11 +17,13 @@
 //! a page runs its course and the script thread returns to processing events in the main event
 //! loop.
 
+use accessibility;
 use devtools;
 use devtools_traits::CSSError;
 use devtools_traits::{DevtoolScriptControlMsg, DevtoolsPageInfo};
 use devtools_traits::{ScriptToDevtoolsControlMsg, WorkerId};
 use document_loader::DocumentLoader;
+use dom::bindings::audit;
 use dom::bindings::cell::DOMRefCell;
 use dom::bindings::codegen::Bindings::DocumentBinding::{DocumentMethods, DocumentReadyState};
 use dom::bindings::conversions::{FromJSValConvertible, StringificationBehavior};
@@ -38,9 +40,9 @@ use dom::browsingcontext::BrowsingContext;
 use dom::document::{Document, DocumentProgressHandler, DocumentSource, FocusType, IsHTMLDocument};
 use dom::element::Element;
 use dom::event::{Event, EventBubbles, EventCancelable};
-use dom::htmlanchorelement::HTMLAnchorElement;
 use dom::node::{Node, NodeDamage, window_from_node};
 use dom::servohtmlparser::ParserContext;
+use dom::transitionevent::TransitionEvent;
 use dom::uievent::UIEvent;
 use dom::window::{ReflowReason, ScriptHelpers, Window};
 use dom::worker::TrustedWorkerAddress;
@@ -59,7 +61,7 @@ use js::jsapi::{JSAutoCompartment, JSContext, JS_SetWrapObjectCallbacks};
 use js::jsapi::{JSTracer, SetWindowProxyClass};
 use js::jsval::UndefinedValue;
 use js::rust::Runtime;
-use layout_interface::{self, NewLayoutThreadInfo, ReflowQueryType};
+use layout_interface::{self, AnimationControl, NewLayoutThreadInfo, ReflowQueryType};
 use mem::heap_size_of_self_and_children;
 use msg::constellation_msg::{LoadData, PanicMsg, PipelineId, PipelineNamespace};
 use msg::constellation_msg::{SubpageId, WindowSizeData, WindowSizeType};
@@ -67,6 +69,8 @@ use msg::webdriver_msg::WebDriverScriptCommand;
 use net_traits::LoadData as NetLoadData;
 use net_traits::bluetooth_thread::BluetoothMethodMsg;
 use net_traits::image_cache_thread::{ImageCacheChan, ImageCacheResult, ImageCacheThread};
+use net_traits::reporting::ReportingHeaders;
+use net_traits::usb_thread::UsbMethodMsg;
 use net_traits::{AsyncResponseTarget, CoreResourceMsg, LoadConsumer, LoadContext, Metadata, ResourceThreads};
 use net_traits::{RequestSource, CustomResponse, CustomResponseSender, IpcSend};
 use network_listener::NetworkListener;
@@ -77,13 +81,14 @@ use profile_traits::mem::{self, OpaqueSender, Report, ReportKind, ReportsChan};
 use profile_traits::time::{self, ProfilerCategory, profile};
 use script_runtime::{CommonScriptMsg, ScriptChan, ScriptThreadEventCategory};
 use script_runtime::{ScriptPort, StackRootTLS, new_rt_and_cx, get_reports};
-use script_traits::CompositorEvent::{KeyEvent, MouseButtonEvent, MouseMoveEvent, ResizeEvent};
-use script_traits::CompositorEvent::{TouchEvent, TouchpadPressureEvent};
+use script_traits::CompositorEvent::{KeyEvent, MouseButtonEvent, MouseMoveEvent, PickNodeEvent};
+use script_traits::CompositorEvent::{ResizeEvent, TouchEvent, TouchpadPressureEvent};
+use script_traits::{AccessibilityAction, AccessibleNode};
 use script_traits::{CompositorEvent, ConstellationControlMsg, EventResult};
 use script_traits::{InitialScriptState, MouseButton, MouseEventType, MozBrowserEvent};
 use script_traits::{NewLayoutInfo, ScriptMsg as ConstellationMsg};
 use script_traits::{ScriptThreadFactory, TimerEvent, TimerEventRequest, TimerSource};
-use script_traits::{TouchEventType, TouchId};
+use script_traits::{TouchEventType, TouchId, UsedFontFace, VisualViewportData};
 use std::borrow::ToOwned;
 use std::cell::{Cell, RefCell};
 use std::collections::HashSet;
@@ -94,7 +99,9 @@ use std::result::Result;
 use std::sync::atomic::{Ordering, AtomicBool};
 use std::sync::mpsc::{Receiver, Select, Sender, channel};
 use std::sync::{Arc, Mutex};
+use string_cache::Atom;
 use style::context::ReflowGoal;
+use style_traits::forced_colors::ForcedColors;
 use task_source::TaskSource;
 use task_source::dom_manipulation::{DOMManipulationTaskSource, DOMManipulationTask};
 use task_source::file_reading::FileReadingTaskSource;
@@ -316,6 +323,8 @@ pub struct ScriptThread {
     resource_threads: ResourceThreads,
     /// A handle to the bluetooth thread.
     bluetooth_thread: IpcSender<BluetoothMethodMsg>,
+    /// A handle to the USB thread.
+    usb_thread: IpcSender<UsbMethodMsg>,
 
     /// The port on which the script thread receives messages (load URL, exit, etc.)
     port: Receiver<MainThreadScriptMsg>,
@@ -556,6 +565,7 @@ impl ScriptThread {
 
             resource_threads: state.resource_threads,
             bluetooth_thread: state.bluetooth_thread,
+            usb_thread: state.usb_thread,
 
             port: port,
             custom_message_chan: ipc_custom_resp_chan,
@@ -709,7 +719,7 @@ impl ScriptThread {
                     })
                 }
                 FromConstellation(ConstellationControlMsg::TickAllAnimations(
-                        pipeline_id)) => {
+                        pipeline_id, _)) => {
                     if !animation_ticks.contains(&pipeline_id) {
                         animation_ticks.insert(pipeline_id);
                         sequential.push(event);
@@ -792,7 +802,11 @@ impl ScriptThread {
             for context in context.iter() {
                 let window = context.active_window();
                 let pending_reflows = window.get_pending_reflow_count();
-                if pending_reflows > 0 {
+                if window.text_editing_reflow_pending() {
+                    window.reflow(ReflowGoal::ForDisplay,
+                                  ReflowQueryType::NoQuery,
+                                  ReflowReason::TextEditing);
+                } else if pending_reflows > 0 {
                     window.reflow(ReflowGoal::ForDisplay,
                                   ReflowQueryType::NoQuery,
                                   ReflowReason::ImageLoaded);
@@ -877,6 +891,8 @@ impl ScriptThread {
                 self.handle_resize_inactive_msg(id, new_size),
             ConstellationControlMsg::Viewport(..) =>
                 panic!("should have handled Viewport already"),
+            ConstellationControlMsg::VisualViewport(id, data) =>
+                self.handle_visual_viewport(id, data),
             ConstellationControlMsg::Resize(..) =>
                 panic!("should have handled Resize already"),
             ConstellationControlMsg::ExitPipeline(..) =>
@@ -905,17 +921,39 @@ impl ScriptThread {
                 self.handle_focus_iframe_msg(containing_pipeline_id, subpage_id),
             ConstellationControlMsg::WebDriverScriptCommand(pipeline_id, msg) =>
                 self.handle_webdriver_msg(pipeline_id, msg),
-            ConstellationControlMsg::TickAllAnimations(pipeline_id) =>
-                self.handle_tick_all_animations(pipeline_id),
+            ConstellationControlMsg::TickAllAnimations(pipeline_id, frame_time) =>
+                self.handle_tick_all_animations(pipeline_id, frame_time),
             ConstellationControlMsg::WebFontLoaded(pipeline_id) =>
                 self.handle_web_font_loaded(pipeline_id),
+            ConstellationControlMsg::GeneratedContentImageLoaded(pipeline_id) =>
+                self.handle_generated_content_image_loaded(pipeline_id),
+            ConstellationControlMsg::TransitionEnd(pipeline_id, node, property, time, pseudo) =>
+                self.handle_transition_end(pipeline_id, node, property, time, pseudo),
+            ConstellationControlMsg::AnimationFinished(pipeline_id, id) =>
+                self.handle_animation_finished(pipeline_id, id),
             ConstellationControlMsg::DispatchFrameLoadEvent {
                 target: pipeline_id, parent: containing_id } =>
                 self.handle_frame_load_event(containing_id, pipeline_id),
             ConstellationControlMsg::FramedContentChanged(containing_pipeline_id, subpage_id) =>
                 self.handle_framed_content_changed(containing_pipeline_id, subpage_id),
+            ConstellationControlMsg::ShowFrameTooltip(parent_id, subpage_id, text, anchor) =>
+                self.handle_show_frame_tooltip(parent_id, subpage_id, text, anchor),
+            ConstellationControlMsg::HideFrameTooltip(parent_pipeline_id) =>
+                self.handle_hide_frame_tooltip(parent_pipeline_id),
             ConstellationControlMsg::ReportCSSError(pipeline_id, filename, line, column, msg) =>
                 self.handle_css_error_reporting(pipeline_id, filename, line, column, msg),
+            ConstellationControlMsg::GetAccessibilityTree(pipeline_id, reply) =>
+                self.handle_get_accessibility_tree(pipeline_id, reply),
+            ConstellationControlMsg::AccessibilityAction(pipeline_id, action) =>
+                self.handle_accessibility_action(pipeline_id, action),
+            ConstellationControlMsg::SetAuthorStylesEnabled(pipeline_id, enabled) =>
+                self.handle_set_author_styles_enabled(pipeline_id, enabled),
+            ConstellationControlMsg::SetForcedColors(pipeline_id, forced_colors) =>
+                self.handle_set_forced_colors(pipeline_id, forced_colors),
+            ConstellationControlMsg::SetTextZoom(pipeline_id, text_zoom) =>
+                self.handle_set_text_zoom(pipeline_id, text_zoom),
+            ConstellationControlMsg::GetUsedFontFaces(pipeline_id, reply) =>
+                self.handle_get_used_font_faces(pipeline_id, reply),
         }
     }
 
@@ -992,6 +1030,40 @@ impl ScriptThread {
                 devtools::handle_drop_timeline_markers(&context, marker_types),
             DevtoolScriptControlMsg::RequestAnimationFrame(pipeline_id, name) =>
                 devtools::handle_request_animation_frame(&context, pipeline_id, name),
+            DevtoolScriptControlMsg::SetInvalidationOverlays(pipeline_id, paint, reflow, layers) =>
+                devtools::handle_set_invalidation_overlays(&context,
+                                                           pipeline_id,
+                                                           paint,
+                                                           reflow,
+                                                           layers),
+            DevtoolScriptControlMsg::GetStyleSheets(id, reply) =>
+                devtools::handle_get_style_sheets(&context, id, reply),
+            DevtoolScriptControlMsg::GetCSSCoverage(id, reply) =>
+                devtools::handle_get_css_coverage(&context, id, reply),
+            DevtoolScriptControlMsg::GetMatchedRules(id, node_id, pseudo, reply) =>
+                devtools::handle_get_matched_rules(&context, id, node_id, pseudo, reply),
+            DevtoolScriptControlMsg::ModifyStyleRule(id, index, line, column, change, reply) =>
+                devtools::handle_modify_style_rule(&context, id, index, line, column, change,
+                                                   reply),
+            DevtoolScriptControlMsg::GetFragmentTree(id, reply) =>
+                devtools::handle_get_fragment_tree(&context, id, reply),
+            DevtoolScriptControlMsg::GetUsedFontFaces(id, reply) =>
+                devtools::handle_get_used_font_faces(&context, id, reply),
+            DevtoolScriptControlMsg::GetRunningAnimations(id, reply) =>
+                devtools::handle_get_running_animations(&context, id, reply),
+            DevtoolScriptControlMsg::SetAnimationPaused(id, node_id, property, paused) => {
+                let control = if paused { AnimationControl::Pause } else { AnimationControl::Play };
+                devtools::handle_control_animation(&context, id, node_id, property, control)
+            }
+            DevtoolScriptControlMsg::SeekAnimation(id, node_id, property, time) =>
+                devtools::handle_control_animation(&context, id, node_id, property,
+                                                   AnimationControl::Seek(time / 1000.)),
+            DevtoolScriptControlMsg::SetNodePicker(id, enabled) =>
+                devtools::handle_set_node_picker(&context, id, enabled),
+            DevtoolScriptControlMsg::HighlightNode(id, node_id) =>
+                devtools::handle_highlight_node(&context, id, node_id),
+            DevtoolScriptControlMsg::GetBoxModel(id, node_id, reply) =>
+                devtools::handle_get_box_model(&context, id, node_id, reply),
         }
     }
 
@@ -1077,6 +1149,74 @@ impl ScriptThread {
         panic!("Page rect message sent to nonexistent pipeline");
     }
 
+    fn handle_visual_viewport(&self, id: PipelineId, data: VisualViewportData) {
+        // Pipelines that are still loading get the visual viewport with their first scroll.
+        if let Some(context) = self.browsing_context.get().and_then(|context| context.find(id)) {
+            context.active_window().set_visual_viewport(data);
+        }
+    }
+
+    fn handle_get_accessibility_tree(&self,
+                                     id: PipelineId,
+                                     reply: IpcSender<Option<AccessibleNode>>) {
+        // Pipelines that are still loading have no document yet.
+        let tree = self.browsing_context.get().and_then(|context| context.find(id)).map(|context| {
+            accessibility::accessibility_tree(&context.active_document())
+        });
+        if let Err(e) = reply.send(tree) {
+            warn!("Sending accessibility tree failed ({}).", e);
+        }
+    }
+
+    fn handle_accessibility_action(&self, id: PipelineId, action: AccessibilityAction) {
+        if let Some(context) = self.browsing_context.get().and_then(|context| context.find(id)) {
+            accessibility::perform_action(&context.active_document(), action);
+        }
+    }
+
+    /// Restyles a page with or without its author styles. Does nothing if the page no longer
+    /// exists.
+    fn handle_set_author_styles_enabled(&self, id: PipelineId, enabled: bool) {
+        if let Some(context) = self.browsing_context.get().and_then(|context| context.find(id)) {
+            let window = context.active_window();
+            let msg = layout_interface::Msg::SetAuthorStylesEnabled(enabled);
+            window.layout_chan().send(msg).unwrap();
+            self.rebuild_and_force_reflow(&context, ReflowReason::AuthorStylesToggled);
+        }
+    }
+
+    /// Restyles a page in or out of a forced colors mode. Does nothing if the page no longer
+    /// exists.
+    fn handle_set_forced_colors(&self, id: PipelineId, forced_colors: Option<ForcedColors>) {
+        if let Some(context) = self.browsing_context.get().and_then(|context| context.find(id)) {
+            let window = context.active_window();
+            let msg = layout_interface::Msg::SetForcedColors(forced_colors);
+            window.layout_chan().send(msg).unwrap();
+            self.rebuild_and_force_reflow(&context, ReflowReason::ForcedColorsToggled);
+        }
+    }
+
+    /// Restyles a page with its text zoomed by the given factor. Does nothing if the page no
+    /// longer exists.
+    fn handle_set_text_zoom(&self, id: PipelineId, text_zoom: f32) {
+        if let Some(context) = self.browsing_context.get().and_then(|context| context.find(id)) {
+            let window = context.active_window();
+            window.layout_chan().send(layout_interface::Msg::SetTextZoom(text_zoom)).unwrap();
+            self.rebuild_and_force_reflow(&context, ReflowReason::TextZoomChanged);
+        }
+    }
+
+    fn handle_get_used_font_faces(&self,
+                                  id: PipelineId,
+                                  reply: IpcSender<Option<Vec<UsedFontFace>>>) {
+        // Pipelines that are still loading have no document yet.
+        let context = self.browsing_context.get().and_then(|context| context.find(id));
+        let font_faces = context.map(|context| context.active_window().used_font_faces_query());
+        if let Err(e) = reply.send(font_faces) {
+            warn!("Sending used font faces failed ({}).", e);
+        }
+    }
+
     fn handle_new_layout(&self, new_layout_info: NewLayoutInfo) {
         let NewLayoutInfo {
             containing_pipeline_id,
@@ -1224,6 +1364,22 @@ impl ScriptThread {
         }
     }
 
+    fn handle_show_frame_tooltip(&self,
+                                 parent_pipeline_id: PipelineId,
+                                 subpage_id: SubpageId,
+                                 text: String,
+                                 anchor: Rect<f32>) {
+        if let Some(context) = self.find_child_context(parent_pipeline_id) {
+            context.active_document().show_frame_tooltip(subpage_id, text, anchor);
+        }
+    }
+
+    fn handle_hide_frame_tooltip(&self, parent_pipeline_id: PipelineId) {
+        if let Some(context) = self.find_child_context(parent_pipeline_id) {
+            context.active_document().hide_frame_tooltip();
+        }
+    }
+
     fn handle_framed_content_changed(&self,
                                      parent_pipeline_id: PipelineId,
                                      subpage_id: SubpageId) {
@@ -1365,17 +1521,22 @@ impl ScriptThread {
         }
 
         // otherwise find just the matching context and exit all sub-contexts
+        let mut pipelines = vec![];
         if let Some(ref mut child_context) = context.remove(id) {
+            pipelines.extend(child_context.iter().map(|context| {
+                context.active_window().pipeline()
+            }));
             shut_down_layout(&child_context);
         }
+        audit::report_leaks(self.js_runtime.rt(), &pipelines);
         false
     }
 
     /// Handles when layout thread finishes all animation in one tick
-    fn handle_tick_all_animations(&self, id: PipelineId) {
+    fn handle_tick_all_animations(&self, id: PipelineId, frame_time: f64) {
         let context = get_browsing_context(&self.root_browsing_context(), id);
         let document = context.active_document();
-        document.run_the_animation_frame_callbacks();
+        document.run_the_animation_frame_callbacks(frame_time);
     }
 
     /// Handles a Web font being loaded. Does nothing if the page no longer exists.
@@ -1385,6 +1546,53 @@ impl ScriptThread {
         }
     }
 
+    /// Builds the boxes of the page again, so that generated images get their size.
+    fn handle_generated_content_image_loaded(&self, pipeline_id: PipelineId) {
+        if let Some(context) = self.find_child_context(pipeline_id)  {
+            self.rebuild_and_force_reflow(&context, ReflowReason::ImageLoaded);
+        }
+    }
+
+    /// Fires `transitionend` at the element whose transition finished, if it is still in the
+    /// document. Transitions only run on elements, not on pseudo-elements.
+    /// https://drafts.csswg.org/css-transitions/#transition-events
+    fn handle_transition_end(&self,
+                             pipeline_id: PipelineId,
+                             node_address: usize,
+                             property_name: String,
+                             elapsed_time: f64,
+                             pseudo_element: String) {
+        let context = match self.find_child_context(pipeline_id) {
+            Some(context) => context,
+            None => return,
+        };
+        // The node may have been collected since layout sent its address, so it is looked up
+        // instead of being trusted.
+        let document = context.active_document();
+        let node = document.upcast::<Node>().traverse_preorder().find(|node| {
+            node.reflector().get_jsobject().get() as usize == node_address
+        });
+        let node = match node {
+            Some(node) => node,
+            None => return,
+        };
+        let window = context.active_window();
+        let event = TransitionEvent::new(GlobalRef::Window(window.r()),
+                                         Atom::from("transitionend"),
+                                         EventBubbles::Bubbles,
+                                         EventCancelable::Cancelable,
+                                         DOMString::from(property_name),
+                                         elapsed_time as f32,
+                                         DOMString::from(pseudo_element));
+        event.upcast::<Event>().fire(node.upcast());
+    }
+
+    fn handle_animation_finished(&self, pipeline_id: PipelineId, id: u32) {
+        if let Some(context) = self.find_child_context(pipeline_id) {
+            context.active_document().finish_animation(id);
+        }
+    }
+
     /// Notify the containing document of a child frame that has completed loading.
     fn handle_frame_load_event(&self, containing_pipeline: PipelineId, id: PipelineId) {
         let context = get_browsing_context(&self.root_browsing_context(), containing_pipeline);
@@ -1455,6 +1663,7 @@ impl ScriptThread {
                                  self.image_cache_thread.clone(),
                                  self.resource_threads.clone(),
                                  self.bluetooth_thread.clone(),
+                                 self.usb_thread.clone(),
                                  self.mem_profiler_chan.clone(),
                                  self.time_profiler_chan.clone(),
                                  self.devtools_chan.clone(),
@@ -1615,6 +1824,21 @@ impl ScriptThread {
 
         document.set_https_state(metadata.https_state);
 
+        if let Some(ref headers) = metadata.headers {
+            let reporting_headers = ReportingHeaders::from_headers(headers);
+            let msg = CoreResourceMsg::SetReportingEndpoints(incomplete.pipeline_id,
+                                                             final_url.clone(),
+                                                             reporting_headers);
+            let _ = self.resource_threads.send(msg);
+        }
+
+        window.origin_trials_mut().reset(final_url.clone());
+        if let Some(tokens) = metadata.headers.as_ref().and_then(|h| h.get_raw("Origin-Trial")) {
+            for token in tokens {
+                window.origin_trials_mut().add_token(&String::from_utf8_lossy(token));
+            }
+        }
+
         let is_xml = match metadata.content_type {
             Some(ContentType(Mime(TopLevel::Application, SubLevel::Ext(ref sub_level), _)))
                 if sub_level.ends_with("+xml") => true,
@@ -1712,52 +1936,10 @@ impl ScriptThread {
                 let context = get_browsing_context(&self.root_browsing_context(), pipeline_id);
                 let document = context.active_document();
 
-                // Get the previous target temporarily
-                let prev_mouse_over_target = self.topmost_mouse_over_target.get();
-
+                // The compositor shows the URLs of the links under the mouse, from the hit-test
+                // tree of the pipeline.
                 document.handle_mouse_move_event(self.js_runtime.rt(), point,
                                                  &self.topmost_mouse_over_target);
-
-                // Short-circuit if nothing changed
-                if self.topmost_mouse_over_target.get() == prev_mouse_over_target {
-                    return;
-                }
-
-                let mut state_already_changed = false;
-
-                // Notify Constellation about the topmost anchor mouse over target.
-                if let Some(target) = self.topmost_mouse_over_target.get() {
-                    if let Some(anchor) = target.upcast::<Node>()
-                                                .inclusive_ancestors()
-                                                .filter_map(Root::downcast::<HTMLAnchorElement>)
-                                                .next() {
-                        let status = anchor.upcast::<Element>()
-                                           .get_attribute(&ns!(), &atom!("href"))
-                                           .and_then(|href| {
-                                               let value = href.value();
-                                               let url = document.url();
-                                               url.join(&value).map(|url| url.to_string()).ok()
-                                           });
-
-                        let event = ConstellationMsg::NodeStatus(status);
-                        self.constellation_chan.send(event).unwrap();
-
-                        state_already_changed = true;
-                    }
-                }
-
-                // We might have to reset the anchor state
-                if !state_already_changed {
-                    if let Some(target) = prev_mouse_over_target {
-                        if let Some(_) = target.upcast::<Node>()
-                                               .inclusive_ancestors()
-                                               .filter_map(Root::downcast::<HTMLAnchorElement>)
-                                               .next() {
-                            let event = ConstellationMsg::NodeStatus(None);
-                            self.constellation_chan.send(event).unwrap();
-                        }
-                    }
-                }
             }
             TouchEvent(event_type, identifier, point) => {
                 let handled = self.handle_touch_event(pipeline_id, event_type, identifier, point);
@@ -1789,6 +1971,13 @@ impl ScriptThread {
                 let document = context.active_document();
                 document.dispatch_key_event(key, state, modifiers, &self.constellation_chan);
             }
+
+            PickNodeEvent(point) => {
+                devtools::handle_pick_node(&self.root_browsing_context(),
+                                           pipeline_id,
+                                           self.js_runtime.rt(),
+                                           point);
+            }
         }
     }
 
@@ -7,6 +7,7 @@
 use clipboard_provider::ClipboardProvider;
 use dom::bindings::str::DOMString;
 use dom::keyboardevent::{KeyboardEvent, key_value};
+use dom::node::{Node, window_from_node};
 use msg::constellation_msg::{ALT, CONTROL, SHIFT, SUPER};
 use msg::constellation_msg::{Key, KeyModifiers};
 use std::borrow::ToOwned;
@@ -14,6 +15,7 @@ use std::cmp::{max, min};
 use std::default::Default;
 use std::ops::Range;
 use std::usize;
+use string_cache::Atom;
 
 #[derive(Copy, Clone, PartialEq)]
 pub enum Selection {
@@ -48,6 +50,32 @@ impl From<SelectionDirection> for DOMString {
     }
 }
 
+/// What the user can select in a text input control, following its `user-select`.
+///
+/// https://drafts.csswg.org/css-ui-4/#content-selection
+#[derive(JSTraceable, PartialEq, Copy, Clone, HeapSizeOf)]
+pub enum UserSelect {
+    Text,
+    None,
+    All,
+}
+
+impl UserSelect {
+    /// The `user-select` of `node`. Finding it takes a reflow, so only query it for the key
+    /// events that can select text.
+    pub fn of(node: &Node) -> UserSelect {
+        let window = window_from_node(node);
+        let value = window.resolved_style_query(node.to_trusted_node_address(),
+                                                None,
+                                                &Atom::from("user-select"));
+        match value.as_ref().map(|value| &**value) {
+            Some("none") => UserSelect::None,
+            Some("all") => UserSelect::All,
+            _ => UserSelect::Text,
+        }
+    }
+}
+
 #[derive(JSTraceable, Copy, Clone, HeapSizeOf, PartialEq)]
 pub struct TextPoint {
     /// 0-based line number
@@ -74,6 +102,8 @@ pub struct TextInput<T: ClipboardProvider> {
     /// https://html.spec.whatwg.org/multipage/#attr-fe-maxlength
     pub max_length: Option<usize>,
     pub selection_direction: SelectionDirection,
+    /// What the user can select with the keyboard. Script can always set the selection.
+    pub user_select: UserSelect,
 }
 
 /// Resulting action to be taken by the owner of a text input that is handling an event.
@@ -177,6 +207,7 @@ impl<T: ClipboardProvider> TextInput<T> {
             clipboard_provider: clipboard_provider,
             max_length: max_length,
             selection_direction: selection_direction,
+            user_select: UserSelect::Text,
         };
         i.set_content(initial);
         i
@@ -478,12 +509,25 @@ impl<T: ClipboardProvider> TextInput<T> {
         }
     }
     pub fn handle_keydown_aux(&mut self, key: Key, mods: KeyModifiers) -> KeyReaction {
-        let maybe_select = if mods.contains(SHIFT) { Selection::Selected } else { Selection::NotSelected };
+        let maybe_select = if mods.contains(SHIFT) && self.user_select != UserSelect::None {
+            Selection::Selected
+        } else {
+            Selection::NotSelected
+        };
         match key {
             Key::A if is_control_key(mods) => {
+                if self.user_select == UserSelect::None {
+                    return KeyReaction::Nothing;
+                }
                 self.select_all();
                 KeyReaction::RedrawSelection
             },
+            // Selections of `user-select: all` controls always span all of their text.
+            Key::Left | Key::Right | Key::Up | Key::Down | Key::PageUp | Key::PageDown
+                if maybe_select == Selection::Selected && self.user_select == UserSelect::All => {
+                self.select_all();
+                KeyReaction::RedrawSelection
+            }
             Key::C if is_control_key(mods) => {
                 if let Some(text) = self.get_selection_text() {
                     self.clipboard_provider.set_clipboard_contents(text);
@@ -8,6 +8,7 @@ use dom::bindings::codegen::Bindings::FunctionBinding::Function;
 use dom::bindings::global::GlobalRef;
 use dom::bindings::reflector::Reflectable;
 use dom::bindings::str::DOMString;
+use dom::document::TooltipCallback;
 use dom::window::ScriptHelpers;
 use dom::xmlhttprequest::XHRTimeoutCallback;
 use euclid::length::Length;
@@ -67,6 +68,7 @@ struct OneshotTimer {
 pub enum OneshotTimerCallback {
     XhrTimeout(XHRTimeoutCallback),
     JsTimer(JsTimerTask),
+    Tooltip(TooltipCallback),
 }
 
 impl OneshotTimerCallback {
@@ -74,6 +76,7 @@ impl OneshotTimerCallback {
         match self {
             OneshotTimerCallback::XhrTimeout(callback) => callback.invoke(),
             OneshotTimerCallback::JsTimer(task) => task.invoke(this, js_timers),
+            OneshotTimerCallback::Tooltip(callback) => callback.invoke(),
         }
     }
 }
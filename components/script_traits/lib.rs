@@ -37,23 +37,29 @@ use euclid::Size2D;
 use euclid::length::Length;
 use euclid::point::Point2D;
 use euclid::rect::Rect;
+use gfx_traits::ConsistencyToken;
 use gfx_traits::Epoch;
 use gfx_traits::LayerId;
 use ipc_channel::ipc::{IpcReceiver, IpcSender};
 use libc::c_void;
-use msg::constellation_msg::{FrameId, FrameType, Key, KeyModifiers, KeyState, LoadData};
+use msg::constellation_msg::LoadData;
+use msg::constellation_msg::{CustomCursor, FrameId, FrameType, Key, KeyModifiers, KeyState};
 use msg::constellation_msg::{NavigationDirection, PanicMsg, PipelineId};
-use msg::constellation_msg::{PipelineNamespaceId, SubpageId, WindowSizeData};
-use msg::constellation_msg::{WebDriverCommandMsg, WindowSizeType};
+use msg::constellation_msg::{PipelineNamespaceId, Snapshot, SnapshotFormat, SubpageId};
+use msg::constellation_msg::{WebDriverCommandMsg, WindowSizeData, WindowSizeType};
 use msg::webdriver_msg::WebDriverScriptCommand;
 use net_traits::ResourceThreads;
 use net_traits::bluetooth_thread::BluetoothMethodMsg;
 use net_traits::image_cache_thread::ImageCacheThread;
 use net_traits::response::HttpsState;
+use net_traits::usb_thread::UsbMethodMsg;
 use profile_traits::mem;
 use std::collections::HashMap;
 use std::sync::mpsc::{Sender, Receiver};
+use style_traits::cursor::Cursor;
+use style_traits::forced_colors::ForcedColors;
 use url::Url;
+use util::geometry::rounded_rect_contains_point;
 use util::ipc::OptionalOpaqueIpcSender;
 
 pub use script_msg::{LayoutMsg, ScriptMsg, EventResult};
@@ -70,10 +76,12 @@ unsafe impl Send for UntrustedNodeAddress {}
 pub enum LayoutControlMsg {
     /// Requests that this layout thread exit.
     ExitNow,
-    /// Requests the current epoch (layout counter) from this layout.
-    GetCurrentEpoch(IpcSender<Epoch>),
-    /// Asks layout to run another step in its animation.
-    TickAnimations,
+    /// Requests the current epoch (layout counter) from this layout, with the version of the DOM
+    /// that the display list of that epoch reflects.
+    GetCurrentEpoch(IpcSender<ConsistencyToken>),
+    /// Asks layout to run another step in its animation, sampling its animations at the given
+    /// time of the animation tick, as returned by `time::precise_time_s()`.
+    TickAnimations(f64),
     /// Informs layout as to which regions of the page are visible.
     SetVisibleRects(Vec<(LayerId, Rect<Au>)>),
     /// Requests the current load state of Web fonts. `true` is returned if fonts are still loading
@@ -122,6 +130,8 @@ pub enum ConstellationControlMsg {
     SendEvent(PipelineId, CompositorEvent),
     /// Notifies script of the viewport.
     Viewport(PipelineId, Rect<f32>),
+    /// Notifies script of the part of the viewport that is visible on screen.
+    VisualViewport(PipelineId, VisualViewportData),
     /// Requests that the script thread immediately send the constellation the title of a pipeline.
     GetTitle(PipelineId),
     /// Notifies script thread to suspend all its timers
@@ -138,11 +148,24 @@ pub enum ConstellationControlMsg {
     FocusIFrame(PipelineId, SubpageId),
     /// Passes a webdriver command to the script thread for execution
     WebDriverScriptCommand(PipelineId, WebDriverScriptCommand),
-    /// Notifies script thread that all animations are done
-    TickAllAnimations(PipelineId),
+    /// Asks the script thread to run the animation frame callbacks of a pipeline for the
+    /// animation tick at the given time, as returned by `time::precise_time_s()`, and then to
+    /// reflow, in which layout samples its animations at the same time.
+    TickAllAnimations(PipelineId, f64),
     /// Notifies the script thread that a new Web font has been loaded, and thus the page should be
     /// reflowed.
     WebFontLoaded(PipelineId),
+    /// Notifies the script thread that an image of generated content has been loaded, and thus
+    /// the page should be reflowed.
+    GeneratedContentImageLoaded(PipelineId),
+    /// Notifies the script thread that a transition of the given property of the node at the
+    /// given address, which is that of an `UntrustedNodeAddress`, has finished after running for
+    /// the given number of seconds. The last field names the pseudo-element of the node that
+    /// transitioned, such as `::before`, or is empty if the node itself did.
+    TransitionEnd(PipelineId, usize, String, f64, String),
+    /// Notifies the script thread that the animation that script created with the given number
+    /// has finished.
+    AnimationFinished(PipelineId, u32),
     /// Cause a `load` event to be dispatched at the appropriate frame element.
     DispatchFrameLoadEvent {
         /// The pipeline that has been marked as loaded.
@@ -152,8 +175,29 @@ pub enum ConstellationControlMsg {
     },
     /// Notifies a parent frame that one of its child frames is now active.
     FramedContentChanged(PipelineId, SubpageId),
+    /// Asks a parent frame to show the tooltip of one of its child frames, whose rect is in the
+    /// viewport of the child frame.
+    ShowFrameTooltip(PipelineId, SubpageId, String, Rect<f32>),
+    /// Asks a parent frame to hide the tooltip of one of its child frames.
+    HideFrameTooltip(PipelineId),
     /// Report an error from a CSS parser for the given pipeline
     ReportCSSError(PipelineId, String, usize, usize, String),
+    /// Requests the accessibility tree of the document of the given pipeline.
+    GetAccessibilityTree(PipelineId, IpcSender<Option<AccessibleNode>>),
+    /// Performs an action of an assistive technology on a node of the accessibility tree of the
+    /// document of the given pipeline.
+    AccessibilityAction(PipelineId, AccessibilityAction),
+    /// Sets whether author styles apply to the document of the given pipeline, for reader modes
+    /// that style documents with user-agent and user styles alone.
+    SetAuthorStylesEnabled(PipelineId, bool),
+    /// Puts the document of the given pipeline in the forced colors mode of the given palette,
+    /// such as a high contrast mode, or out of forced colors modes.
+    SetForcedColors(PipelineId, Option<ForcedColors>),
+    /// Sets the factor that the text of the document of the given pipeline is zoomed by.
+    SetTextZoom(PipelineId, f32),
+    /// Requests the font faces that the text of the document of the given pipeline is laid out
+    /// in, which is `None` if it has no document yet.
+    GetUsedFontFaces(PipelineId, IpcSender<Option<Vec<UsedFontFace>>>),
 }
 
 /// Used to determine if a script has any pending asynchronous activity.
@@ -235,6 +279,238 @@ pub enum CompositorEvent {
     TouchpadPressureEvent(Point2D<f32>, f32, TouchpadPressurePhase),
     /// A key was pressed.
     KeyEvent(Key, KeyState, KeyModifiers),
+    /// A click while devtools is picking a node. It only selects the node under the point, and
+    /// is not dispatched to the page.
+    PickNodeEvent(Point2D<f32>),
+}
+
+/// What pointer events can hit in the display list of a pipeline, which layout sends to the
+/// compositor along with each display list, so that the compositor can hit test input events
+/// without asking layout.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct HitTestTree {
+    /// The items that can be hit, topmost first. Items with `pointer-events: none` or that can
+    /// never be hit, such as box shadows, are left out.
+    pub items: Vec<HitTestItem>,
+    /// The scroll frames of the pipeline, each after the one it is inside of.
+    pub scroll_frames: Vec<HitTestScrollFrame>,
+    /// How the viewport snaps its scroll position, if the root element has a
+    /// `scroll-snap-type`.
+    pub viewport_snap: Option<ScrollSnap>,
+    /// The areas of the elements whose `touch-action` restricts what the touches that start on
+    /// them do.
+    pub touch_action_regions: Vec<HitTestTouchActionRegion>,
+    /// The custom cursors to show over the items of nodes, instead of their cursors.
+    pub custom_cursors: HashMap<usize, CustomCursor>,
+    /// The URLs of the links that nodes are in, which the browser chrome shows while the mouse
+    /// is over their items.
+    pub links: HashMap<usize, String>,
+}
+
+/// An item of a `HitTestTree`. Its rects are in the coordinates of the page of the pipeline,
+/// before scrolling; transformed items use the bounding boxes of their transformed rects.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HitTestItem {
+    /// The bounds of the item.
+    pub bounds: Rect<Au>,
+    /// The rect that the item is clipped to.
+    pub clip: Rect<Au>,
+    /// The rounded rects that the item is also clipped to, such as those of the ancestors with
+    /// `border-radius` that clip their overflow.
+    pub rounded_clips: Vec<HitTestRoundedClip>,
+    /// A part of the bounds that does not hit the item, such as the inside of a border.
+    pub interior: Option<Rect<Au>>,
+    /// The opaque address of the node that the item belongs to.
+    pub node: usize,
+    /// The cursor to show over the item.
+    pub cursor: Cursor,
+    /// The pipeline that the item displays, if it is an iframe.
+    pub subpage: Option<PipelineId>,
+    /// The index of the innermost scroll frame that the item is inside of.
+    pub scroll_frame: Option<usize>,
+}
+
+/// A rect with rounded corners that a `HitTestItem` is clipped to.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HitTestRoundedClip {
+    /// The rect, in the coordinates of the page.
+    pub rect: Rect<Au>,
+    /// The radii of the top left, top right, bottom right and bottom left corners.
+    pub radii: [Size2D<Au>; 4],
+}
+
+/// A stacking context of a `HitTestTree` that scrolls its overflow area.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HitTestScrollFrame {
+    /// The layer of the stacking context.
+    pub layer_id: LayerId,
+    /// The index of the scroll frame that this one is inside of.
+    pub parent: Option<usize>,
+    /// How the scroll frame snaps its scroll position, if it has a `scroll-snap-type`.
+    pub snap: Option<ScrollSnap>,
+}
+
+/// The area of an element of a `HitTestTree` with a `touch-action` other than `auto`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HitTestTouchActionRegion {
+    /// The border box of the element, in the coordinates of the page.
+    pub rect: Rect<Au>,
+    /// What touches that start in it may do.
+    pub allowed: AllowedTouchActions,
+}
+
+/// The default actions that a touch may perform, per `touch-action`.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AllowedTouchActions {
+    /// Whether it may pan horizontally.
+    pub pan_x: bool,
+    /// Whether it may pan vertically.
+    pub pan_y: bool,
+    /// Whether it may zoom, with a pinch.
+    pub zoom: bool,
+}
+
+impl AllowedTouchActions {
+    /// The actions allowed by `touch-action: auto`.
+    pub fn all() -> AllowedTouchActions {
+        AllowedTouchActions {
+            pan_x: true,
+            pan_y: true,
+            zoom: true,
+        }
+    }
+
+    /// The actions that both `self` and `other` allow.
+    pub fn intersection(&self, other: &AllowedTouchActions) -> AllowedTouchActions {
+        AllowedTouchActions {
+            pan_x: self.pan_x && other.pan_x,
+            pan_y: self.pan_y && other.pan_y,
+            zoom: self.zoom && other.zoom,
+        }
+    }
+
+    /// Whether a touch may pan or zoom at all, or only dispatches events to script.
+    pub fn any(&self) -> bool {
+        self.pan_x || self.pan_y || self.zoom
+    }
+}
+
+/// How strictly a scroll container snaps on an axis.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum ScrollSnapStrictness {
+    /// It always comes to rest at the nearest snap position.
+    Mandatory,
+    /// It only snaps to snap positions that are close to where it comes to rest.
+    Proximity,
+}
+
+/// Where a scroll container comes to rest once it has been scrolled, per CSS-SCROLL-SNAP.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ScrollSnap {
+    /// How strictly it snaps horizontally, if it does.
+    pub x: Option<ScrollSnapStrictness>,
+    /// How strictly it snaps vertically, if it does.
+    pub y: Option<ScrollSnapStrictness>,
+    /// The scrollport, in the coordinates of the page.
+    pub scrollport: Rect<Au>,
+    /// The scroll positions that align its snap areas with the scrollport.
+    pub positions: Vec<Point2D<Au>>,
+}
+
+impl ScrollSnap {
+    /// The scroll position that `position` snaps to. Proximity snapping only snaps an axis to
+    /// positions that are at most `proximity` away.
+    pub fn snap(&self, position: &Point2D<Au>, proximity: Au) -> Point2D<Au> {
+        fn distance(a: Au, b: Au) -> Au {
+            if a > b { a - b } else { b - a }
+        }
+
+        fn snap_axis(strictness: Option<ScrollSnapStrictness>,
+                     position: Au,
+                     positions: &mut Iterator<Item=Au>,
+                     proximity: Au)
+                     -> Au {
+            let strictness = match strictness {
+                Some(strictness) => strictness,
+                None => return position,
+            };
+            let nearest = positions.min_by_key(|snap_position| distance(*snap_position, position));
+            match (nearest, strictness) {
+                (Some(nearest), ScrollSnapStrictness::Mandatory) => nearest,
+                (Some(nearest), ScrollSnapStrictness::Proximity)
+                        if distance(nearest, position) <= proximity => nearest,
+                _ => position,
+            }
+        }
+
+        Point2D::new(snap_axis(self.x, position.x, &mut self.positions.iter().map(|p| p.x),
+                               proximity),
+                     snap_axis(self.y, position.y, &mut self.positions.iter().map(|p| p.y),
+                               proximity))
+    }
+}
+
+impl HitTestTree {
+    /// The topmost item at `point`, in the coordinates of the page.
+    pub fn hit_test(&self, point: &Point2D<Au>) -> Option<&HitTestItem> {
+        self.items.iter().find(|item| {
+            item.clip.contains(point) && item.bounds.contains(point) &&
+                !item.interior.map_or(false, |interior| interior.contains(point)) &&
+                item.rounded_clips.iter().all(|clip| {
+                    rounded_rect_contains_point(&clip.rect, &clip.radii, point)
+                })
+        })
+    }
+
+    /// The innermost scroll frame at `point` that snaps, with its layer, falling back to the
+    /// viewport, whose layer is the null layer.
+    pub fn scroll_snap_at(&self, point: &Point2D<Au>) -> Option<(LayerId, &ScrollSnap)> {
+        let mut scroll_frame = self.hit_test(point).and_then(|item| item.scroll_frame);
+        while let Some(index) = scroll_frame {
+            let frame = &self.scroll_frames[index];
+            if let Some(ref snap) = frame.snap {
+                return Some((frame.layer_id, snap))
+            }
+            scroll_frame = frame.parent;
+        }
+        self.viewport_snap.as_ref().map(|snap| (LayerId::null(), snap))
+    }
+
+    /// What a touch that starts at `point` may do: only what the `touch-action` of every
+    /// element that it is on allows.
+    pub fn allowed_touch_actions_at(&self, point: &Point2D<Au>) -> AllowedTouchActions {
+        self.touch_action_regions.iter().filter(|region| {
+            region.rect.contains(point)
+        }).fold(AllowedTouchActions::all(), |allowed, region| {
+            allowed.intersection(&region.allowed)
+        })
+    }
+}
+
+/// An error of a page that is reported to the embedder, apart from devtools, so that it can
+/// tell the user about it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum PageError {
+    /// An exception that no script caught.
+    UncaughtException {
+        /// The message of the exception.
+        message: String,
+        /// The URL of the script that threw it.
+        filename: String,
+        /// The line of the script that threw it.
+        line: u32,
+        /// The column of the script that threw it.
+        column: u32,
+    },
+    /// A violation of the content security policy of the document.
+    CspViolation {
+        /// The directive that was violated.
+        directive: String,
+        /// The URL of the resource that was blocked.
+        blocked_url: String,
+    },
+    /// A message logged with `console.error`, or a failed `console.assert`.
+    ConsoleError(String),
 }
 
 /// Touchpad pressure phase for TouchpadPressureEvent.
@@ -248,6 +524,171 @@ pub enum TouchpadPressurePhase {
     AfterSecondClick,
 }
 
+/// The part of the page that is visible on screen, which is smaller than the layout viewport
+/// when the page is pinch-zoomed or an on-screen keyboard covers part of the window.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct VisualViewportData {
+    /// The offset of its top left corner from that of the page, in CSS pixels.
+    pub page_offset: Point2D<f32>,
+    /// Its size, in CSS pixels.
+    pub size: Size2D<f32>,
+    /// The pinch-zoom scale.
+    pub scale: f32,
+}
+
+/// What kind of object a node of the accessibility tree is, as assistive technologies present
+/// it. Most of the roles are those of WAI-ARIA.
+/// https://w3c.github.io/aria/#role_definitions
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum AccessibleRole {
+    Alert,
+    AlertDialog,
+    Application,
+    Article,
+    Banner,
+    Button,
+    Cell,
+    Checkbox,
+    ColumnHeader,
+    Combobox,
+    Complementary,
+    ContentInfo,
+    Definition,
+    Dialog,
+    Directory,
+    Document,
+    Feed,
+    Figure,
+    Form,
+    Grid,
+    GridCell,
+    Group,
+    Heading,
+    Img,
+    Link,
+    List,
+    Listbox,
+    ListItem,
+    Log,
+    Main,
+    Marquee,
+    Math,
+    Menu,
+    Menubar,
+    MenuItem,
+    MenuItemCheckbox,
+    MenuItemRadio,
+    Navigation,
+    Note,
+    Option,
+    ProgressBar,
+    Radio,
+    RadioGroup,
+    Region,
+    Row,
+    RowGroup,
+    RowHeader,
+    Scrollbar,
+    Search,
+    Searchbox,
+    Separator,
+    Slider,
+    SpinButton,
+    Status,
+    Switch,
+    Tab,
+    Table,
+    TabList,
+    TabPanel,
+    Term,
+    Textbox,
+    Timer,
+    Toolbar,
+    Tooltip,
+    Tree,
+    TreeGrid,
+    TreeItem,
+    /// A run of text, which is not an element.
+    StaticText,
+}
+
+/// The value of a state that can be mixed as well as true or false, such as `aria-checked`.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Tristate {
+    False,
+    True,
+    Mixed,
+}
+
+/// The states and properties of a node of the accessibility tree, from its WAI-ARIA attributes
+/// or, where it has none, from its element. The states that do not apply are `None`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct AccessibleStates {
+    pub checked: Option<Tristate>,
+    pub pressed: Option<Tristate>,
+    pub expanded: Option<bool>,
+    pub selected: Option<bool>,
+    pub disabled: bool,
+    pub focusable: bool,
+    pub focused: bool,
+    pub required: bool,
+    pub readonly: bool,
+    pub invalid: bool,
+    pub busy: bool,
+    pub multiselectable: bool,
+    /// The level of a heading, or of an item in a tree.
+    pub level: Option<u32>,
+    /// The value of a range widget, such as a slider, as text.
+    pub value: Option<String>,
+}
+
+/// A node of the accessibility tree of a document, which assistive technologies present to
+/// their users instead of the rendering of the page.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AccessibleNode {
+    /// The unique id of the DOM node that the node stands for.
+    pub id: String,
+    pub role: AccessibleRole,
+    /// The accessible name, such as the label of a button.
+    /// https://w3c.github.io/accname/
+    pub name: String,
+    /// The accessible description, from `aria-describedby`.
+    pub description: String,
+    pub states: AccessibleStates,
+    pub children: Vec<AccessibleNode>,
+}
+
+/// What an assistive technology can do to a node of the accessibility tree, given by its id.
+/// The states are changed through the attributes that they come from, so that style depending
+/// on those attributes is updated as if script had changed them.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum AccessibilityAction {
+    /// Focuses the node.
+    Focus(String),
+    /// Activates the node, as clicking it would.
+    Activate(String),
+    /// Expands or collapses the node.
+    SetExpanded(String, bool),
+    /// Checks or unchecks the node.
+    SetChecked(String, Tristate),
+    /// Selects or unselects the node.
+    SetSelected(String, bool),
+}
+
+/// A font face that text of a document is laid out in, as of its last layout.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct UsedFontFace {
+    /// The name of the family of the face, as the font itself gives it, which can differ from
+    /// the family that the `@font-face` rule of a web font names.
+    pub family: String,
+    /// The name of the face within its family, such as `Bold Italic`.
+    pub name: String,
+    /// The URL that a web font was downloaded from, or `None` for fonts of the system.
+    pub url: Option<String>,
+    /// The number of glyphs of the text that is laid out in the face.
+    pub glyph_count: usize,
+}
+
 /// Requests a TimerEvent-Message be sent after the given duration.
 #[derive(Deserialize, Serialize)]
 pub struct TimerEventRequest(pub IpcSender<TimerEvent>,
@@ -319,6 +760,8 @@ pub struct InitialScriptState {
     pub resource_threads: ResourceThreads,
     /// A channel to the bluetooth thread.
     pub bluetooth_thread: IpcSender<BluetoothMethodMsg>,
+    /// A channel to the USB thread.
+    pub usb_thread: IpcSender<UsbMethodMsg>,
     /// A channel to the image cache thread.
     pub image_cache_thread: ImageCacheThread,
     /// A channel to the time profiler thread.
@@ -492,8 +935,27 @@ pub enum ConstellationMsg {
     Navigate(Option<(PipelineId, SubpageId)>, NavigationDirection),
     /// Inform the constellation of a window being resized.
     WindowSize(WindowSizeData, WindowSizeType),
-    /// Requests that the constellation instruct layout to begin a new tick of the animation.
-    TickAnimation(PipelineId, AnimationTickType),
+    /// Requests that the constellation instruct layout to begin a new tick of the animation, at
+    /// the given time of the frame, as returned by `time::precise_time_s()`.
+    TickAnimation(PipelineId, AnimationTickType, f64),
     /// Dispatch a webdriver command
     WebDriverCommand(WebDriverCommandMsg),
+    /// Enable or disable tracking protection for a site.
+    SetTrackingProtectionForSite(String, bool),
+    /// Request a snapshot of the current frame of the given pipeline, or of the given rectangle
+    /// of it in page pixels. The reply is `None` if the pipeline is not the root pipeline, or if
+    /// its rendering is not stable yet, in which case the request can be retried.
+    RenderToImage(PipelineId, Option<Rect<f32>>, SnapshotFormat, IpcSender<Option<Snapshot>>),
+    /// Request the accessibility tree of the document of the given pipeline.
+    GetAccessibilityTree(PipelineId, IpcSender<Option<AccessibleNode>>),
+    /// Perform an action of an assistive technology in the given pipeline.
+    AccessibilityAction(PipelineId, AccessibilityAction),
+    /// Enable or disable author styles in the given pipeline.
+    SetAuthorStylesEnabled(PipelineId, bool),
+    /// Enter or leave a forced colors mode in the given pipeline.
+    SetForcedColors(PipelineId, Option<ForcedColors>),
+    /// Zoom the text of the given pipeline by the given factor.
+    SetTextZoom(PipelineId, f32),
+    /// Request the font faces that the text of the given pipeline is laid out in.
+    GetUsedFontFaces(PipelineId, IpcSender<Option<Vec<UsedFontFace>>>),
 }
@@ -4,16 +4,19 @@
 
 use AnimationState;
 use DocumentState;
+use HitTestTree;
 use IFrameLoadInfo;
 use MouseButton;
 use MouseEventType;
 use MozBrowserEvent;
+use PageError;
 use canvas_traits::CanvasMsg;
 use euclid::point::Point2D;
+use euclid::rect::Rect;
 use euclid::size::Size2D;
-use gfx_traits::LayerId;
+use gfx_traits::{LayerAnimation, LayerId};
 use ipc_channel::ipc::IpcSender;
-use msg::constellation_msg::{Key, KeyModifiers, KeyState, LoadData};
+use msg::constellation_msg::{CustomCursor, Key, KeyModifiers, KeyState, LoadData};
 use msg::constellation_msg::{NavigationDirection, PipelineId, SubpageId};
 use offscreen_gl_context::{GLContextAttributes, GLLimits};
 use style_traits::cursor::Cursor;
@@ -25,10 +28,17 @@ use url::Url;
 pub enum LayoutMsg {
     /// Indicates whether this pipeline is currently running animations.
     ChangeRunningAnimationsState(PipelineId, AnimationState),
-    /// Requests that the constellation inform the compositor of the a cursor change.
-    SetCursor(Cursor),
+    /// Requests that the constellation inform the compositor of the a cursor change, and of the
+    /// custom cursor image to show instead if the embedder supports them.
+    SetCursor(Cursor, Option<CustomCursor>),
     /// Notifies the constellation that the viewport has been constrained in some manner
     ViewportConstrained(PipelineId, ViewportConstraints),
+    /// Hands the animations of layers of this pipeline off to the compositor, replacing any
+    /// handed off before.
+    SetLayerAnimations(PipelineId, Vec<LayerAnimation>),
+    /// Hands what pointer events can hit in the new display list of this pipeline to the
+    /// compositor.
+    SetHitTestTree(PipelineId, HitTestTree),
 }
 
 /// Whether a DOM event was prevented by web content
@@ -77,8 +87,13 @@ pub enum ScriptMsg {
     Navigate(Option<(PipelineId, SubpageId)>, NavigationDirection),
     /// Favicon detected
     NewFavicon(Url),
-    /// Status message to be displayed in the chrome, eg. a link URL on mouseover.
-    NodeStatus(Option<String>),
+    /// An error of the page of the pipeline, for the embedder.
+    PageError(PipelineId, PageError),
+    /// Show a tooltip with the given text in the chrome, next to the given rect of the viewport
+    /// of the pipeline, in CSS pixels.
+    ShowTooltip(PipelineId, String, Rect<f32>),
+    /// Hide the tooltip shown by the pipeline or one of its descendants.
+    HideTooltip(PipelineId),
     /// Notification that this iframe should be removed.
     RemoveIFrame(PipelineId, Option<IpcSender<()>>),
     /// A load has been requested in an IFrame.
@@ -110,6 +125,9 @@ pub enum ScriptMsg {
     TouchEventProcessed(EventResult),
     /// Get Scroll Offset
     GetScrollOffset(PipelineId, LayerId, IpcSender<Point2D<f32>>),
+    /// Makes the compositor turn the next click into a `PickNodeEvent` (if true), or stop doing
+    /// so.
+    SetNodePicker(bool),
     /// Requests that the compositor shut down.
     Exit,
 }
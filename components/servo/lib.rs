@@ -40,6 +40,7 @@ pub extern crate profile_traits;
 pub extern crate script;
 pub extern crate script_traits;
 pub extern crate style;
+pub extern crate style_traits;
 pub extern crate url;
 pub extern crate util;
 
@@ -59,27 +60,38 @@ fn webdriver(_port: u16, _constellation: Sender<ConstellationMsg>) { }
 
 use compositing::CompositorEventListener;
 use compositing::compositor_thread::InitialCompositorState;
+use compositing::compositor_thread::Msg as CompositorMsg;
 use compositing::windowing::WindowEvent;
 use compositing::windowing::WindowMethods;
 use compositing::{CompositorProxy, CompositorThread};
 #[cfg(not(target_os = "windows"))]
 use constellation::content_process_sandbox_profile;
 use constellation::{Constellation, InitialConstellationState, UnprivilegedPipelineContent};
+use euclid::Rect;
 #[cfg(not(target_os = "windows"))]
 use gaol::sandbox::{ChildSandbox, ChildSandboxMethods};
 use gfx::font_cache_thread::FontCacheThread;
 use ipc_channel::ipc::{self, IpcSender};
+use msg::constellation_msg::{Snapshot, SnapshotFormat};
 use net::bluetooth_thread::BluetoothThreadFactory;
 use net::image_cache_thread::new_image_cache_thread;
 use net::resource_thread::new_resource_threads;
+use net::url_classifier::LocalListClassifier;
+use net::usb_thread::UsbThreadFactory;
+use net_traits::BlockedRequestsListener;
+use net_traits::DeviceChooser;
 use net_traits::IpcSend;
+use net_traits::UrlClassifier;
 use net_traits::bluetooth_thread::BluetoothMethodMsg;
+use net_traits::usb_thread::UsbMethodMsg;
 use profile::mem as profile_mem;
 use profile::time as profile_time;
 use profile_traits::mem;
 use profile_traits::time;
-use script_traits::ConstellationMsg;
+use script_traits::{AccessibilityAction, AccessibleNode, ConstellationMsg, UsedFontFace};
+use style_traits::forced_colors::ForcedColors;
 use std::rc::Rc;
+use std::sync::Arc;
 use std::sync::mpsc::Sender;
 use util::resource_files::resources_dir_path;
 use util::{opts, prefs};
@@ -122,6 +134,11 @@ impl Browser {
         let devtools_chan = opts.devtools_port.map(|port| {
             devtools::start_server(port)
         });
+        let url_classifier = window.url_classifier().or_else(|| {
+            LocalListClassifier::from_servo_list().map(|classifier| {
+                Arc::new(classifier) as Arc<UrlClassifier>
+            })
+        });
 
         let (webrender, webrender_api_sender) = if opts::get().use_webrender {
             let mut resource_path = resources_dir_path();
@@ -159,7 +176,8 @@ impl Browser {
                                                       mem_profiler_chan.clone(),
                                                       devtools_chan,
                                                       supports_clipboard,
-                                                      webrender_api_sender.clone());
+                                                      webrender_api_sender.clone(),
+                                                      url_classifier);
 
         if cfg!(feature = "webdriver") {
             if let Some(port) = opts.webdriver_port {
@@ -199,6 +217,49 @@ impl Browser {
     pub fn request_title_for_main_frame(&self) {
         self.compositor.title_for_main_frame()
     }
+
+    /// Take a snapshot of the window contents, or of the given rectangle of them in page pixels,
+    /// for instance to make a thumbnail. Returns None if the rendering is not stable yet.
+    pub fn render_to_image(&mut self, rect: Option<Rect<f32>>, format: SnapshotFormat)
+                           -> Option<Snapshot> {
+        self.compositor.render_to_image(rect, format)
+    }
+
+    /// The accessibility tree of the document of the main frame, for assistive technologies.
+    pub fn accessibility_tree(&self) -> Option<AccessibleNode> {
+        self.compositor.accessibility_tree()
+    }
+
+    /// Performs an action that an assistive technology requests on a node of the accessibility
+    /// tree of the main frame, such as expanding it.
+    pub fn perform_accessibility_action(&self, action: AccessibilityAction) {
+        self.compositor.perform_accessibility_action(action)
+    }
+
+    /// Sets whether the document of the main frame is styled by its own stylesheets, or by the
+    /// user-agent and user stylesheets alone, as in reader modes.
+    pub fn set_author_styles_enabled(&self, enabled: bool) {
+        self.compositor.set_author_styles_enabled(enabled)
+    }
+
+    /// Puts the document of the main frame in a forced colors mode, such as a high contrast mode,
+    /// which styles it with the colors of the given palette, or takes it out of forced colors
+    /// modes if there is none.
+    pub fn set_forced_colors(&self, forced_colors: Option<ForcedColors>) {
+        self.compositor.set_forced_colors(forced_colors)
+    }
+
+    /// Zooms the text of the document of the main frame by the given factor, unlike page zoom,
+    /// which zooms images and the rest of the page along with it.
+    pub fn set_text_zoom(&self, text_zoom: f32) {
+        self.compositor.set_text_zoom(text_zoom)
+    }
+
+    /// The font faces that the text of the document of the main frame is laid out in, for
+    /// showing the fonts of a page.
+    pub fn used_font_faces(&self) -> Option<Vec<UsedFontFace>> {
+        self.compositor.used_font_faces()
+    }
 }
 
 fn create_constellation(opts: opts::Opts,
@@ -207,12 +268,23 @@ fn create_constellation(opts: opts::Opts,
                         mem_profiler_chan: mem::ProfilerChan,
                         devtools_chan: Option<Sender<devtools_traits::DevtoolsControlMsg>>,
                         supports_clipboard: bool,
-                        webrender_api_sender: Option<webrender_traits::RenderApiSender>) -> Sender<ConstellationMsg> {
-    let bluetooth_thread: IpcSender<BluetoothMethodMsg> = BluetoothThreadFactory::new();
-
+                        webrender_api_sender: Option<webrender_traits::RenderApiSender>,
+                        url_classifier: Option<Arc<UrlClassifier>>)
+                        -> Sender<ConstellationMsg> {
+    let bluetooth_thread: IpcSender<BluetoothMethodMsg> =
+        BluetoothThreadFactory::new(device_chooser(&*compositor_proxy));
+    let usb_thread: IpcSender<UsbMethodMsg> =
+        UsbThreadFactory::new(device_chooser(&*compositor_proxy));
+
+    let blocked_requests_proxy = compositor_proxy.clone_compositor_proxy();
+    let blocked_requests_listener: BlockedRequestsListener = Box::new(move |pipeline_id, count| {
+        blocked_requests_proxy.send(CompositorMsg::BlockedRequestsCount(pipeline_id, count))
+    });
     let resource_threads = new_resource_threads(opts.user_agent.clone(),
                                                 devtools_chan.clone(),
-                                                time_profiler_chan.clone());
+                                                time_profiler_chan.clone(),
+                                                url_classifier,
+                                                Some(blocked_requests_listener));
     let image_cache_thread = new_image_cache_thread(resource_threads.sender(),
                                                     webrender_api_sender.as_ref().map(|wr| wr.create_api()));
     let font_cache_thread = FontCacheThread::new(resource_threads.sender(),
@@ -222,6 +294,7 @@ fn create_constellation(opts: opts::Opts,
         compositor_proxy: compositor_proxy,
         devtools_chan: devtools_chan,
         bluetooth_thread: bluetooth_thread,
+        usb_thread: usb_thread,
         image_cache_thread: image_cache_thread,
         font_cache_thread: font_cache_thread,
         resource_threads: resource_threads,
@@ -246,6 +319,14 @@ fn create_constellation(opts: opts::Opts,
     constellation_chan
 }
 
+/// Lets the embedder choose the devices that pages may access, through the compositor.
+fn device_chooser(compositor_proxy: &CompositorProxy) -> DeviceChooser {
+    let compositor_proxy = compositor_proxy.clone_compositor_proxy();
+    Box::new(move |kind, devices, sender| {
+        compositor_proxy.send(CompositorMsg::ChooseDevice(kind, devices, sender))
+    })
+}
+
 /// Content process entry point.
 pub fn run_content_process(token: String) {
     let (unprivileged_content_sender, unprivileged_content_receiver) =
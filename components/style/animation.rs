@@ -3,49 +3,108 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use app_units::Au;
-use bezier::Bezier;
-use cssparser::{Color, RGBA};
+use cssparser::{Color, RGBA, ToCss};
 use dom::{OpaqueNode, TRestyleDamage};
-use euclid::point::Point2D;
+use easing;
+use error_reporting::StdoutErrorReporter;
+use euclid::size::Size2D;
+use interpolate::Interpolate;
 use properties::longhands::background_position::computed_value::T as BackgroundPosition;
 use properties::longhands::border_spacing::computed_value::T as BorderSpacing;
+use properties::longhands::box_shadow::computed_value::T as BoxShadowList;
 use properties::longhands::clip::computed_value::ClipRect;
 use properties::longhands::font_weight::computed_value::T as FontWeight;
 use properties::longhands::line_height::computed_value::T as LineHeight;
+use properties::longhands::perspective_origin::computed_value::T as PerspectiveOrigin;
 use properties::longhands::text_shadow::computed_value::T as TextShadowList;
-use properties::longhands::text_shadow::computed_value::TextShadow;
-use properties::longhands::transform::computed_value::ComputedMatrix;
-use properties::longhands::transform::computed_value::ComputedOperation as TransformOperation;
 use properties::longhands::transform::computed_value::T as TransformList;
+use properties::longhands::transform_origin::computed_value::T as TransformOrigin;
 use properties::longhands::transition_property;
 use properties::longhands::transition_property::computed_value::TransitionProperty;
-use properties::longhands::transition_timing_function::computed_value::StartEnd;
 use properties::longhands::transition_timing_function::computed_value::TransitionTimingFunction;
 use properties::longhands::vertical_align::computed_value::T as VerticalAlign;
 use properties::longhands::visibility::computed_value::T as Visibility;
 use properties::longhands::z_index::computed_value::T as ZIndex;
 use properties::style_struct_traits::Box;
-use properties::{ComputedValues, ServoComputedValues};
-use std::cmp::Ordering;
-use std::iter::repeat;
+use properties::{self, ComputedValues, PropertyDeclaration, ServoComputedValues};
+use selector_impl::PseudoElement;
+use selector_matching::DeclarationBlock;
+use std::cmp::max;
 use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
 use time;
+use util::prefs::get_pref;
 use values::CSSFloat;
-use values::computed::{Angle, LengthOrPercentageOrAuto, LengthOrPercentageOrNone};
-use values::computed::{CalcLengthOrPercentage, Length, LengthOrPercentage, Time};
+use values::computed::{BorderRadiusSize, Length, LengthOrNone, LengthOrPercentage};
+use values::computed::{LengthOrPercentageOrAuto, LengthOrPercentageOrNone, Time};
+
+/// What a transition runs on: a node, or one of the pseudo-elements of an element, which share
+/// its node but animate separately from it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AnimationTarget {
+    pub node: OpaqueNode,
+    /// Only `::before` and `::after` are animated.
+    pub pseudo: Option<PseudoElement>,
+}
+
+impl AnimationTarget {
+    #[inline]
+    pub fn new(node: OpaqueNode, pseudo: Option<PseudoElement>) -> AnimationTarget {
+        AnimationTarget {
+            node: node,
+            pseudo: pseudo,
+        }
+    }
+}
+
+/// How much motion animations show, which embedders set with the
+/// `layout.animations.reduced_motion` pref for users who are bothered by motion. Animations keep
+/// their timing either way, so their events still fire when they would.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReducedMotion {
+    /// Animations run as specified. This is the default.
+    NoPreference,
+    /// Animations show their final state for as long as they run.
+    Final,
+    /// Animations jump from their start to their end state halfway through, as the values of
+    /// properties that cannot be interpolated do.
+    Discrete,
+}
+
+impl ReducedMotion {
+    pub fn from_prefs() -> ReducedMotion {
+        match get_pref("layout.animations.reduced_motion").as_string() {
+            Some("final") => ReducedMotion::Final,
+            Some("discrete") => ReducedMotion::Discrete,
+            _ => ReducedMotion::NoPreference,
+        }
+    }
+
+    /// Returns how far along its change an animation shows itself to be when its timing function
+    /// outputs `progress`.
+    pub fn reduce(self, progress: f64) -> f64 {
+        match self {
+            ReducedMotion::NoPreference => progress,
+            ReducedMotion::Final => 1.0,
+            ReducedMotion::Discrete => if progress < 0.5 { 0.0 } else { 1.0 },
+        }
+    }
+}
 
 /// State relating to an animation.
 #[derive(Clone)]
 pub struct Animation {
-    /// An opaque reference to the DOM node participating in the animation.
-    pub node: OpaqueNode,
+    /// The node or pseudo-element participating in the animation.
+    pub target: AnimationTarget,
     /// A description of the property animation that is occurring.
     pub property_animation: PropertyAnimation,
     /// The start time of the animation, as returned by `time::precise_time_s()`.
     pub start_time: f64,
     /// The end time of the animation, as returned by `time::precise_time_s()`.
     pub end_time: f64,
+    /// The time at which the animation was paused, as returned by `time::precise_time_s()`, or
+    /// `None` if it is playing. Only devtools pause animations.
+    pub paused_at: Option<f64>,
 }
 
 impl Animation {
@@ -54,14 +113,283 @@ impl Animation {
     pub fn duration(&self) -> f64 {
         self.end_time - self.start_time
     }
+
+    /// Returns the number of seconds between the start of this animation and `now`, or the time
+    /// it was paused at if it is paused. This is negative while the animation is delayed.
+    #[inline]
+    pub fn elapsed_time(&self, now: f64) -> f64 {
+        self.paused_at.unwrap_or(now) - self.start_time
+    }
+
+    /// Freezes this animation at its current progress.
+    pub fn pause(&mut self, now: f64) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(now)
+        }
+    }
+
+    /// Resumes this animation from the progress it was paused at.
+    pub fn play(&mut self, now: f64) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.start_time += now - paused_at;
+            self.end_time += now - paused_at;
+        }
+    }
+
+    /// Moves this animation so that `elapsed_time` seconds have passed since its start, keeping
+    /// it paused if it is.
+    pub fn seek(&mut self, now: f64, elapsed_time: f64) {
+        let duration = self.duration();
+        self.start_time = self.paused_at.unwrap_or(now) - elapsed_time;
+        self.end_time = self.start_time + duration;
+    }
+
+    /// Returns the progress of this animation at `now`, from 0 while it is delayed to 1 once it
+    /// has finished.
+    pub fn progress(&self, now: f64) -> f64 {
+        let duration = self.duration();
+        if duration <= 0.0 {
+            return if self.elapsed_time(now) < 0.0 { 0.0 } else { 1.0 }
+        }
+        (self.elapsed_time(now) / duration).max(0.0).min(1.0)
+    }
 }
 
 
+/// A keyframe of an animation that script created: the declarations that hold at `offset`, the
+/// fraction of each iteration at which the keyframe is reached.
+#[derive(Clone, Debug)]
+pub struct Keyframe {
+    pub offset: f64,
+    /// The easing from this keyframe to the next one that sets the same property.
+    pub timing_function: TransitionTimingFunction,
+    pub declarations: Arc<Vec<PropertyDeclaration>>,
+}
+
+/// The keyframes of an animation that script created, with the animatable properties that they
+/// set.
+#[derive(Clone, Debug)]
+pub struct KeyframeEffect {
+    /// The keyframes, sorted by offset.
+    keyframes: Vec<Keyframe>,
+    /// The properties that the keyframes set, with the indices of the keyframes that set each.
+    properties: Vec<(TransitionProperty, Vec<usize>)>,
+}
+
+impl KeyframeEffect {
+    /// Creates the effect of `keyframes`, which must be sorted by offset. Each property that
+    /// they set must be set at both offset 0 and offset 1, since the values that properties have
+    /// without the animation are not filled in. Properties that cannot be animated are ignored.
+    pub fn new(keyframes: Vec<Keyframe>) -> Result<KeyframeEffect, ()> {
+        if keyframes.windows(2).any(|pair| pair[0].offset > pair[1].offset) {
+            return Err(())
+        }
+        let mut properties: Vec<(TransitionProperty, Vec<usize>)> = vec![];
+        for (index, keyframe) in keyframes.iter().enumerate() {
+            for declaration in keyframe.declarations.iter() {
+                let name = declaration.name().to_string();
+                let property = transition_property::computed_value::ALL_TRANSITION_PROPERTIES
+                    .iter()
+                    .find(|property| property.to_css_string() == name);
+                let property = match property {
+                    Some(property) => *property,
+                    None => continue,
+                };
+                let position = properties.iter().position(|&(other, _)| other == property);
+                match position {
+                    Some(position) => {
+                        let indices = &mut properties[position].1;
+                        if indices.last() != Some(&index) {
+                            indices.push(index)
+                        }
+                    }
+                    None => properties.push((property, vec![index])),
+                }
+            }
+        }
+        for &(_, ref indices) in &properties {
+            if keyframes[indices[0]].offset != 0.0 ||
+                    keyframes[indices[indices.len() - 1]].offset != 1.0 {
+                return Err(())
+            }
+        }
+        Ok(KeyframeEffect {
+            keyframes: keyframes,
+            properties: properties,
+        })
+    }
+
+    /// Sets the properties that the keyframes set in `style` to their values at `progress`
+    /// through an iteration lasting `duration` seconds. The keyframes are cascaded with `style`
+    /// as their parent, so that relative values in them resolve against it.
+    fn update(&self,
+              style: &mut ServoComputedValues,
+              viewport_size: Size2D<Au>,
+              duration: f64,
+              progress: f64) {
+        // The keyframes that `progress` is between for each property, and how far between them
+        // it is.
+        let segments: Vec<(TransitionProperty, usize, usize, f64)> =
+                self.properties.iter().map(|&(property, ref indices)| {
+            let next = indices.iter()
+                              .position(|&index| self.keyframes[index].offset > progress)
+                              .unwrap_or(indices.len() - 1);
+            let next = max(next, 1);
+            let (from, to) = (indices[next - 1], indices[next]);
+            let (from_offset, to_offset) = (self.keyframes[from].offset,
+                                            self.keyframes[to].offset);
+            let segment_progress = if to_offset > from_offset {
+                easing::output(self.keyframes[from].timing_function,
+                               duration * (to_offset - from_offset),
+                               (progress - from_offset) / (to_offset - from_offset))
+            } else {
+                1.0
+            };
+            (property, from, to, segment_progress)
+        }).collect();
+
+        let mut keyframe_styles: Vec<Option<ServoComputedValues>> =
+            self.keyframes.iter().map(|_| None).collect();
+        for &(_, from, to, _) in &segments {
+            for &index in &[from, to] {
+                if keyframe_styles[index].is_none() {
+                    let declarations = [DeclarationBlock::from_declarations(
+                        self.keyframes[index].declarations.clone())];
+                    let (keyframe_style, _) = properties::cascade(viewport_size,
+                                                                  1.0,
+                                                                  &declarations,
+                                                                  false,
+                                                                  Some(&*style),
+                                                                  None,
+                                                                  None,
+                                                                  box StdoutErrorReporter);
+                    keyframe_styles[index] = Some(keyframe_style)
+                }
+            }
+        }
+
+        for &(property, from, to, segment_progress) in &segments {
+            let property = AnimatedProperty::from_transition_property(
+                property,
+                keyframe_styles[from].as_ref().unwrap(),
+                keyframe_styles[to].as_ref().unwrap());
+            property.update(style, segment_progress)
+        }
+    }
+}
+
+/// The timing of an animation that script created.
+#[derive(Clone, Copy, Debug)]
+pub struct AnimationTiming {
+    /// The time at which the first iteration starts, as returned by `time::precise_time_s()`.
+    pub start_time: f64,
+    /// The duration of each iteration, in seconds.
+    pub duration: f64,
+    /// How many iterations there are, which may be fractional or infinite.
+    pub iterations: f64,
+    /// The timing function of each iteration.
+    pub timing_function: TransitionTimingFunction,
+}
+
+/// An animation that script created with `Element.animate()`. It runs on top of the cascaded
+/// style and the transitions of its node, until it finishes or script cancels it.
+#[derive(Clone, Debug)]
+pub struct KeyframesAnimation {
+    /// The number that script knows the animation by, which is unique within its document.
+    pub id: u32,
+    /// An opaque reference to the DOM node participating in the animation.
+    pub node: OpaqueNode,
+    pub effect: KeyframeEffect,
+    pub timing: AnimationTiming,
+    /// The time at which the animation was paused, as returned by `time::precise_time_s()`, or
+    /// `None` if it is playing.
+    pub paused_at: Option<f64>,
+}
+
+impl KeyframesAnimation {
+    /// Returns the time at which the last iteration of this animation ends.
+    pub fn end_time(&self) -> f64 {
+        if self.timing.duration <= 0.0 {
+            return self.timing.start_time
+        }
+        self.timing.start_time + self.timing.duration * self.timing.iterations
+    }
+
+    /// Returns the number of seconds between the start of this animation and `now`, or the time
+    /// it was paused at if it is paused. This is negative while the animation is delayed.
+    #[inline]
+    pub fn elapsed_time(&self, now: f64) -> f64 {
+        self.paused_at.unwrap_or(now) - self.timing.start_time
+    }
+
+    /// Returns whether this animation has gone through all of its iterations at `now`.
+    pub fn has_finished(&self, now: f64) -> bool {
+        self.paused_at.is_none() && now >= self.end_time()
+    }
+
+    /// Freezes this animation at its current progress.
+    pub fn pause(&mut self, now: f64) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(now)
+        }
+    }
+
+    /// Resumes this animation from the progress it was paused at.
+    pub fn play(&mut self, now: f64) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.timing.start_time += now - paused_at
+        }
+    }
+
+    /// Moves this animation so that `elapsed_time` seconds have passed since its start, keeping
+    /// it paused if it is.
+    pub fn seek(&mut self, now: f64, elapsed_time: f64) {
+        self.timing.start_time = self.paused_at.unwrap_or(now) - elapsed_time
+    }
+
+    /// Sets the properties that this animation sets in `style` to their values at `now`, unless
+    /// it is delayed then.
+    pub fn update(&self, style: &mut ServoComputedValues, viewport_size: Size2D<Au>, now: f64) {
+        if let Some(progress) = self.iteration_progress(now) {
+            let progress = ReducedMotion::from_prefs().reduce(progress);
+            self.effect.update(style, viewport_size, self.timing.duration, progress)
+        }
+    }
+
+    /// Returns how far through its current iteration this animation is at `now`, as output by
+    /// its timing function, or `None` while it is delayed.
+    fn iteration_progress(&self, now: f64) -> Option<f64> {
+        let elapsed_time = self.elapsed_time(now);
+        if elapsed_time < 0.0 {
+            return None
+        }
+        let (duration, iterations) = (self.timing.duration, self.timing.iterations);
+        let progress = if duration <= 0.0 || elapsed_time >= duration * iterations {
+            // Past the end, the animation stays at the end of its last iteration.
+            if iterations.is_infinite() || iterations.fract() == 0.0 {
+                1.0
+            } else {
+                iterations.fract()
+            }
+        } else {
+            (elapsed_time / duration).fract()
+        };
+        Some(easing::output(self.timing.timing_function, duration, progress))
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct PropertyAnimation {
+    transition_property: TransitionProperty,
     property: AnimatedProperty,
     timing_function: TransitionTimingFunction,
     duration: Time,
+    /// The end value of the transition that this one reverses, or its own start value if it
+    /// reverses none. Transitions back to this value are shortened.
+    /// https://drafts.csswg.org/css-transitions/#reversing-adjusted-start-value
+    reversing_adjusted_start_value: AnimatedProperty,
+    /// https://drafts.csswg.org/css-transitions/#reversing-shortening-factor
+    reversing_shortening_factor: f64,
 }
 
 impl PropertyAnimation {
@@ -105,7 +433,115 @@ impl PropertyAnimation {
                                 old_style: &ServoComputedValues,
                                 new_style: &mut ServoComputedValues)
                                 -> Option<PropertyAnimation> {
+        let animated_property =
+            AnimatedProperty::from_transition_property(transition_property, old_style, new_style);
         let box_style = new_style.get_box();
+        let property_animation = PropertyAnimation {
+            transition_property: transition_property,
+            reversing_adjusted_start_value: animated_property.start_value(),
+            property: animated_property,
+            timing_function:
+                *box_style.transition_timing_function.0.get_mod(transition_index),
+            duration: *box_style.transition_duration.0.get_mod(transition_index),
+            reversing_shortening_factor: 1.0,
+        };
+        if property_animation.does_not_animate() {
+            None
+        } else {
+            Some(property_animation)
+        }
+    }
+
+    /// Returns the property being animated.
+    #[inline]
+    pub fn property(&self) -> TransitionProperty {
+        self.transition_property
+    }
+
+    /// Returns the output of the timing function at `time`, the fraction of the duration that
+    /// has passed.
+    fn timing_function_output(&self, time: f64) -> f64 {
+        easing::output(self.timing_function, self.duration.seconds() as f64, time)
+    }
+
+    pub fn update(&self, style: &mut ServoComputedValues, time: f64) {
+        let progress = ReducedMotion::from_prefs().reduce(self.timing_function_output(time));
+        self.property.update(style, progress)
+    }
+
+    #[inline]
+    fn does_not_animate(&self) -> bool {
+        self.property.does_not_animate() || self.duration == Time(0.0)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum AnimatedProperty {
+    BackgroundColor(Color, Color),
+    BackgroundPosition(BackgroundPosition, BackgroundPosition),
+    BorderBottomColor(Color, Color),
+    BorderBottomLeftRadius(BorderRadiusSize, BorderRadiusSize),
+    BorderBottomRightRadius(BorderRadiusSize, BorderRadiusSize),
+    BorderBottomWidth(Length, Length),
+    BorderLeftColor(Color, Color),
+    BorderLeftWidth(Length, Length),
+    BorderRightColor(Color, Color),
+    BorderRightWidth(Length, Length),
+    BorderSpacing(BorderSpacing, BorderSpacing),
+    BorderTopColor(Color, Color),
+    BorderTopLeftRadius(BorderRadiusSize, BorderRadiusSize),
+    BorderTopRightRadius(BorderRadiusSize, BorderRadiusSize),
+    BorderTopWidth(Length, Length),
+    Bottom(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
+    BoxShadow(BoxShadowList, BoxShadowList),
+    Color(RGBA, RGBA),
+    Clip(Option<ClipRect>, Option<ClipRect>),
+    FontSize(Length, Length),
+    FontWeight(FontWeight, FontWeight),
+    Height(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
+    Left(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
+    LetterSpacing(Option<Au>, Option<Au>),
+    LineHeight(LineHeight, LineHeight),
+    MarginBottom(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
+    MarginLeft(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
+    MarginRight(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
+    MarginTop(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
+    MaxHeight(LengthOrPercentageOrNone, LengthOrPercentageOrNone),
+    MaxWidth(LengthOrPercentageOrNone, LengthOrPercentageOrNone),
+    MinHeight(LengthOrPercentage, LengthOrPercentage),
+    MinWidth(LengthOrPercentage, LengthOrPercentage),
+    Opacity(CSSFloat, CSSFloat),
+    Order(i32, i32),
+    OutlineColor(Color, Color),
+    OutlineOffset(Length, Length),
+    OutlineWidth(Length, Length),
+    PaddingBottom(LengthOrPercentage, LengthOrPercentage),
+    PaddingLeft(LengthOrPercentage, LengthOrPercentage),
+    PaddingRight(LengthOrPercentage, LengthOrPercentage),
+    PaddingTop(LengthOrPercentage, LengthOrPercentage),
+    Perspective(LengthOrNone, LengthOrNone),
+    PerspectiveOrigin(PerspectiveOrigin, PerspectiveOrigin),
+    Right(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
+    TextDecorationColor(Color, Color),
+    TextIndent(LengthOrPercentage, LengthOrPercentage),
+    TextShadow(TextShadowList, TextShadowList),
+    Top(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
+    Transform(TransformList, TransformList),
+    TransformOrigin(TransformOrigin, TransformOrigin),
+    VerticalAlign(VerticalAlign, VerticalAlign),
+    Visibility(Visibility, Visibility),
+    Width(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
+    WordSpacing(Option<Au>, Option<Au>),
+    ZIndex(ZIndex, ZIndex),
+}
+
+impl AnimatedProperty {
+    /// Returns the values of `transition_property` in `old_style` and `new_style`. It must not
+    /// be `TransitionProperty::All`.
+    fn from_transition_property(transition_property: TransitionProperty,
+                                old_style: &ServoComputedValues,
+                                new_style: &ServoComputedValues)
+                                -> AnimatedProperty {
         macro_rules! match_transition {
                 ( $( [$name:ident; $structname:ident; $field:ident] ),* ) => {
                     match transition_property {
@@ -119,6 +555,10 @@ impl PropertyAnimation {
                                                         new_style.$structname().$field)
                             }
                         )*
+                        TransitionProperty::BoxShadow => {
+                            AnimatedProperty::BoxShadow(old_style.get_effects().box_shadow.clone(),
+                                                        new_style.get_effects().box_shadow.clone())
+                        }
                         TransitionProperty::Clip => {
                             AnimatedProperty::Clip(old_style.get_effects().clip.0,
                                                    new_style.get_effects().clip.0)
@@ -142,10 +582,12 @@ impl PropertyAnimation {
                     }
                 }
         }
-        let animated_property = match_transition!(
+        match_transition!(
             [BackgroundColor; get_background; background_color],
             [BackgroundPosition; get_background; background_position],
             [BorderBottomColor; get_border; border_bottom_color],
+            [BorderBottomLeftRadius; get_border; border_bottom_left_radius],
+            [BorderBottomRightRadius; get_border; border_bottom_right_radius],
             [BorderBottomWidth; get_border; border_bottom_width],
             [BorderLeftColor; get_border; border_left_color],
             [BorderLeftWidth; get_border; border_left_width],
@@ -153,6 +595,8 @@ impl PropertyAnimation {
             [BorderRightWidth; get_border; border_right_width],
             [BorderSpacing; get_inheritedtable; border_spacing],
             [BorderTopColor; get_border; border_top_color],
+            [BorderTopLeftRadius; get_border; border_top_left_radius],
+            [BorderTopRightRadius; get_border; border_top_right_radius],
             [BorderTopWidth; get_border; border_top_width],
             [Bottom; get_position; bottom],
             [Color; get_color; color],
@@ -170,52 +614,33 @@ impl PropertyAnimation {
             [MinHeight; get_position; min_height],
             [MinWidth; get_position; min_width],
             [Opacity; get_effects; opacity],
+            [Order; get_position; order],
             [OutlineColor; get_outline; outline_color],
+            [OutlineOffset; get_outline; outline_offset],
             [OutlineWidth; get_outline; outline_width],
             [PaddingBottom; get_padding; padding_bottom],
             [PaddingLeft; get_padding; padding_left],
             [PaddingRight; get_padding; padding_right],
             [PaddingTop; get_padding; padding_top],
+            [Perspective; get_effects; perspective],
+            [PerspectiveOrigin; get_effects; perspective_origin],
             [Right; get_position; right],
+            [TextDecorationColor; get_text; text_decoration_color],
             [TextIndent; get_inheritedtext; text_indent],
             [Top; get_position; top],
+            [TransformOrigin; get_effects; transform_origin],
             [VerticalAlign; get_box; vertical_align],
             [Visibility; get_inheritedbox; visibility],
             [Width; get_position; width],
             [ZIndex; get_position; z_index]);
-
-        let property_animation = PropertyAnimation {
-            property: animated_property,
-            timing_function:
-                *box_style.transition_timing_function.0.get_mod(transition_index),
-            duration: *box_style.transition_duration.0.get_mod(transition_index),
-        };
-        if property_animation.does_not_animate() {
-            None
-        } else {
-            Some(property_animation)
-        }
     }
 
-    pub fn update(&self, style: &mut ServoComputedValues, time: f64) {
-        let progress = match self.timing_function {
-            TransitionTimingFunction::CubicBezier(p1, p2) => {
-                // See `WebCore::AnimationBase::solveEpsilon(double)` in WebKit.
-                let epsilon = 1.0 / (200.0 * (self.duration.seconds() as f64));
-                Bezier::new(Point2D::new(p1.x as f64, p1.y as f64),
-                            Point2D::new(p2.x as f64, p2.y as f64)).solve(time, epsilon)
-            }
-            TransitionTimingFunction::Steps(steps, StartEnd::Start) => {
-                (time * (steps as f64)).ceil() / (steps as f64)
-            }
-            TransitionTimingFunction::Steps(steps, StartEnd::End) => {
-                (time * (steps as f64)).floor() / (steps as f64)
-            }
-        };
-
+    /// Sets the property in `style` to its value at `progress` from the start value to the end
+    /// value.
+    fn update(&self, style: &mut ServoComputedValues, progress: f64) {
         macro_rules! match_property(
             ( $( [$name:ident; $structname:ident; $field:ident] ),* ) => {
-                match self.property {
+                match *self {
                     $(
                         AnimatedProperty::$name(ref start, ref end) => {
                             if let Some(value) = start.interpolate(end, progress) {
@@ -244,6 +669,8 @@ impl PropertyAnimation {
             [BackgroundColor; mutate_background; background_color],
             [BackgroundPosition; mutate_background; background_position],
             [BorderBottomColor; mutate_border; border_bottom_color],
+            [BorderBottomLeftRadius; mutate_border; border_bottom_left_radius],
+            [BorderBottomRightRadius; mutate_border; border_bottom_right_radius],
             [BorderBottomWidth; mutate_border; border_bottom_width],
             [BorderLeftColor; mutate_border; border_left_color],
             [BorderLeftWidth; mutate_border; border_left_width],
@@ -251,8 +678,11 @@ impl PropertyAnimation {
             [BorderRightWidth; mutate_border; border_right_width],
             [BorderSpacing; mutate_inheritedtable; border_spacing],
             [BorderTopColor; mutate_border; border_top_color],
+            [BorderTopLeftRadius; mutate_border; border_top_left_radius],
+            [BorderTopRightRadius; mutate_border; border_top_right_radius],
             [BorderTopWidth; mutate_border; border_top_width],
             [Bottom; mutate_position; bottom],
+            [BoxShadow; mutate_effects; box_shadow],
             [Color; mutate_color; color],
             [FontSize; mutate_font; font_size],
             [FontWeight; mutate_font; font_weight],
@@ -268,79 +698,29 @@ impl PropertyAnimation {
             [MinHeight; mutate_position; min_height],
             [MinWidth; mutate_position; min_width],
             [Opacity; mutate_effects; opacity],
+            [Order; mutate_position; order],
             [OutlineColor; mutate_outline; outline_color],
+            [OutlineOffset; mutate_outline; outline_offset],
             [OutlineWidth; mutate_outline; outline_width],
             [PaddingBottom; mutate_padding; padding_bottom],
             [PaddingLeft; mutate_padding; padding_left],
             [PaddingRight; mutate_padding; padding_right],
             [PaddingTop; mutate_padding; padding_top],
+            [Perspective; mutate_effects; perspective],
+            [PerspectiveOrigin; mutate_effects; perspective_origin],
             [Right; mutate_position; right],
+            [TextDecorationColor; mutate_text; text_decoration_color],
             [TextIndent; mutate_inheritedtext; text_indent],
             [TextShadow; mutate_inheritedtext; text_shadow],
             [Top; mutate_position; top],
             [Transform; mutate_effects; transform],
+            [TransformOrigin; mutate_effects; transform_origin],
             [VerticalAlign; mutate_box; vertical_align],
             [Visibility; mutate_inheritedbox; visibility],
             [Width; mutate_position; width],
             [ZIndex; mutate_position; z_index]);
     }
 
-    #[inline]
-    fn does_not_animate(&self) -> bool {
-        self.property.does_not_animate() || self.duration == Time(0.0)
-    }
-}
-
-#[derive(Clone, Debug)]
-enum AnimatedProperty {
-    BackgroundColor(Color, Color),
-    BackgroundPosition(BackgroundPosition, BackgroundPosition),
-    BorderBottomColor(Color, Color),
-    BorderBottomWidth(Length, Length),
-    BorderLeftColor(Color, Color),
-    BorderLeftWidth(Length, Length),
-    BorderRightColor(Color, Color),
-    BorderRightWidth(Length, Length),
-    BorderSpacing(BorderSpacing, BorderSpacing),
-    BorderTopColor(Color, Color),
-    BorderTopWidth(Length, Length),
-    Bottom(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
-    Color(RGBA, RGBA),
-    Clip(Option<ClipRect>, Option<ClipRect>),
-    FontSize(Length, Length),
-    FontWeight(FontWeight, FontWeight),
-    Height(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
-    Left(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
-    LetterSpacing(Option<Au>, Option<Au>),
-    LineHeight(LineHeight, LineHeight),
-    MarginBottom(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
-    MarginLeft(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
-    MarginRight(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
-    MarginTop(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
-    MaxHeight(LengthOrPercentageOrNone, LengthOrPercentageOrNone),
-    MaxWidth(LengthOrPercentageOrNone, LengthOrPercentageOrNone),
-    MinHeight(LengthOrPercentage, LengthOrPercentage),
-    MinWidth(LengthOrPercentage, LengthOrPercentage),
-    Opacity(CSSFloat, CSSFloat),
-    OutlineColor(Color, Color),
-    OutlineWidth(Length, Length),
-    PaddingBottom(LengthOrPercentage, LengthOrPercentage),
-    PaddingLeft(LengthOrPercentage, LengthOrPercentage),
-    PaddingRight(LengthOrPercentage, LengthOrPercentage),
-    PaddingTop(LengthOrPercentage, LengthOrPercentage),
-    Right(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
-    TextIndent(LengthOrPercentage, LengthOrPercentage),
-    TextShadow(TextShadowList, TextShadowList),
-    Top(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
-    Transform(TransformList, TransformList),
-    VerticalAlign(VerticalAlign, VerticalAlign),
-    Visibility(Visibility, Visibility),
-    Width(LengthOrPercentageOrAuto, LengthOrPercentageOrAuto),
-    WordSpacing(Option<Au>, Option<Au>),
-    ZIndex(ZIndex, ZIndex),
-}
-
-impl AnimatedProperty {
     #[inline]
     fn does_not_animate(&self) -> bool {
         match *self {
@@ -360,6 +740,7 @@ impl AnimatedProperty {
             AnimatedProperty::MinHeight(ref a, ref b) |
             AnimatedProperty::TextIndent(ref a, ref b) => a == b,
             AnimatedProperty::FontSize(ref a, ref b) |
+            AnimatedProperty::OutlineOffset(ref a, ref b) |
             AnimatedProperty::BorderTopWidth(ref a, ref b) |
             AnimatedProperty::BorderRightWidth(ref a, ref b) |
             AnimatedProperty::BorderBottomWidth(ref a, ref b) |
@@ -369,7 +750,12 @@ impl AnimatedProperty {
             AnimatedProperty::BorderBottomColor(ref a, ref b) |
             AnimatedProperty::BorderLeftColor(ref a, ref b) |
             AnimatedProperty::OutlineColor(ref a, ref b) |
+            AnimatedProperty::TextDecorationColor(ref a, ref b) |
             AnimatedProperty::BackgroundColor(ref a, ref b) => a == b,
+            AnimatedProperty::BorderTopLeftRadius(ref a, ref b) |
+            AnimatedProperty::BorderTopRightRadius(ref a, ref b) |
+            AnimatedProperty::BorderBottomRightRadius(ref a, ref b) |
+            AnimatedProperty::BorderBottomLeftRadius(ref a, ref b) => a == b,
             AnimatedProperty::PaddingTop(ref a, ref b) |
             AnimatedProperty::PaddingRight(ref a, ref b) |
             AnimatedProperty::PaddingBottom(ref a, ref b) |
@@ -378,540 +764,75 @@ impl AnimatedProperty {
             AnimatedProperty::LetterSpacing(ref a, ref b) => a == b,
             AnimatedProperty::BackgroundPosition(ref a, ref b) => a == b,
             AnimatedProperty::BorderSpacing(ref a, ref b) => a == b,
+            AnimatedProperty::BoxShadow(ref a, ref b) => a == b,
             AnimatedProperty::Clip(ref a, ref b) => a == b,
             AnimatedProperty::Color(ref a, ref b) => a == b,
             AnimatedProperty::FontWeight(ref a, ref b) => a == b,
             AnimatedProperty::Opacity(ref a, ref b) => a == b,
+            AnimatedProperty::Order(ref a, ref b) => a == b,
             AnimatedProperty::OutlineWidth(ref a, ref b) => a == b,
+            AnimatedProperty::Perspective(ref a, ref b) => a == b,
+            AnimatedProperty::PerspectiveOrigin(ref a, ref b) => a == b,
             AnimatedProperty::TextShadow(ref a, ref b) => a == b,
             AnimatedProperty::VerticalAlign(ref a, ref b) => a == b,
             AnimatedProperty::Visibility(ref a, ref b) => a == b,
             AnimatedProperty::WordSpacing(ref a, ref b) => a == b,
             AnimatedProperty::ZIndex(ref a, ref b) => a == b,
             AnimatedProperty::Transform(ref a, ref b) => a == b,
+            AnimatedProperty::TransformOrigin(ref a, ref b) => a == b,
         }
     }
 }
 
-trait Interpolate: Sized {
-    fn interpolate(&self, other: &Self, time: f64) -> Option<Self>;
-}
-
-impl Interpolate for Au {
-    #[inline]
-    fn interpolate(&self, other: &Au, time: f64) -> Option<Au> {
-        Some(Au((self.0 as f64 + (other.0 as f64 - self.0 as f64) * time).round() as i32))
-    }
-}
-
-impl <T> Interpolate for Option<T> where T: Interpolate {
-    #[inline]
-    fn interpolate(&self, other: &Option<T>, time: f64) -> Option<Option<T>> {
-        match (self, other) {
-            (&Some(ref this), &Some(ref other)) => {
-                this.interpolate(other, time).and_then(|value| {
-                    Some(Some(value))
-                })
-            }
-            (_, _) => None
-        }
-    }
-}
-
-impl Interpolate for f32 {
-    #[inline]
-    fn interpolate(&self, other: &f32, time: f64) -> Option<f32> {
-        Some(((*self as f64) + ((*other as f64) - (*self as f64)) * time) as f32)
-    }
-}
-
-impl Interpolate for f64 {
-    #[inline]
-    fn interpolate(&self, other: &f64, time: f64) -> Option<f64> {
-        Some(*self + (*other - *self) * time)
-    }
-}
-
-impl Interpolate for i32 {
-    #[inline]
-    fn interpolate(&self, other: &i32, time: f64) -> Option<i32> {
-        let a = *self as f64;
-        let b = *other as f64;
-        Some((a + (b - a) * time).round() as i32)
-    }
-}
-
-impl Interpolate for Angle {
-    #[inline]
-    fn interpolate(&self, other: &Angle, time: f64) -> Option<Angle> {
-        self.radians().interpolate(&other.radians(), time).map(Angle)
-    }
-}
-
-impl Interpolate for Visibility {
-    #[inline]
-    fn interpolate(&self, other: &Visibility, time: f64)
-                   -> Option<Visibility> {
-        match (*self, *other) {
-            (Visibility::visible, _) | (_, Visibility::visible) => {
-                if time >= 0.0 && time <= 1.0 {
-                    Some(Visibility::visible)
-                } else if time < 0.0 {
-                    Some(*self)
-                } else {
-                    Some(*other)
+macro_rules! animated_property_values {
+    ( $( $name:ident ),* ) => {
+        impl AnimatedProperty {
+            /// Returns the property animating from its start value to that same value.
+            fn start_value(&self) -> AnimatedProperty {
+                match *self {
+                    $(
+                        AnimatedProperty::$name(ref start, _) => {
+                            AnimatedProperty::$name(start.clone(), start.clone())
+                        }
+                    )*
                 }
             }
-            (_, _) => None,
-        }
-    }
-}
-
-impl Interpolate for ZIndex {
-    #[inline]
-    fn interpolate(&self, other: &ZIndex, time: f64)
-                   -> Option<ZIndex> {
-        match (*self, *other) {
-            (ZIndex::Number(ref this),
-             ZIndex::Number(ref other)) => {
-                this.interpolate(other, time).and_then(|value| {
-                    Some(ZIndex::Number(value))
-                })
-            }
-            (_, _) => None,
-        }
-    }
-}
-
-impl Interpolate for VerticalAlign {
-    #[inline]
-    fn interpolate(&self, other: &VerticalAlign, time: f64)
-                   -> Option<VerticalAlign> {
-        match (*self, *other) {
-            (VerticalAlign::LengthOrPercentage(LengthOrPercentage::Length(ref this)),
-             VerticalAlign::LengthOrPercentage(LengthOrPercentage::Length(ref other))) => {
-                this.interpolate(other, time).and_then(|value| {
-                    Some(VerticalAlign::LengthOrPercentage(LengthOrPercentage::Length(value)))
-                })
-            }
-            (_, _) => None,
-        }
-    }
-}
-
-impl Interpolate for BorderSpacing {
-    #[inline]
-    fn interpolate(&self, other: &BorderSpacing, time: f64)
-                   -> Option<BorderSpacing> {
-        self.horizontal.interpolate(&other.horizontal, time).and_then(|horizontal| {
-            self.vertical.interpolate(&other.vertical, time).and_then(|vertical| {
-                Some(BorderSpacing { horizontal: horizontal, vertical: vertical })
-            })
-        })
-    }
-}
-
-impl Interpolate for RGBA {
-    #[inline]
-    fn interpolate(&self, other: &RGBA, time: f64) -> Option<RGBA> {
-        match (self.red.interpolate(&other.red, time),
-               self.green.interpolate(&other.green, time),
-               self.blue.interpolate(&other.blue, time),
-               self.alpha.interpolate(&other.alpha, time)) {
-            (Some(red), Some(green), Some(blue), Some(alpha)) => {
-                Some(RGBA { red: red, green: green, blue: blue, alpha: alpha })
-            }
-            (_, _, _, _) => None
-        }
-    }
-}
-
-impl Interpolate for Color {
-    #[inline]
-    fn interpolate(&self, other: &Color, time: f64) -> Option<Color> {
-        match (*self, *other) {
-            (Color::RGBA(ref this), Color::RGBA(ref other)) => {
-                this.interpolate(other, time).and_then(|value| {
-                    Some(Color::RGBA(value))
-                })
-            }
-            (_, _) => None,
-        }
-    }
-}
-
-impl Interpolate for CalcLengthOrPercentage {
-    #[inline]
-    fn interpolate(&self, other: &CalcLengthOrPercentage, time: f64)
-                   -> Option<CalcLengthOrPercentage> {
-        Some(CalcLengthOrPercentage {
-            length: self.length().interpolate(&other.length(), time),
-            percentage: self.percentage().interpolate(&other.percentage(), time),
-        })
-    }
-}
-
-impl Interpolate for LengthOrPercentage {
-    #[inline]
-    fn interpolate(&self, other: &LengthOrPercentage, time: f64)
-                   -> Option<LengthOrPercentage> {
-        match (*self, *other) {
-            (LengthOrPercentage::Length(ref this),
-             LengthOrPercentage::Length(ref other)) => {
-                this.interpolate(other, time).and_then(|value| {
-                    Some(LengthOrPercentage::Length(value))
-                })
-            }
-            (LengthOrPercentage::Percentage(ref this),
-             LengthOrPercentage::Percentage(ref other)) => {
-                this.interpolate(other, time).and_then(|value| {
-                    Some(LengthOrPercentage::Percentage(value))
-                })
-            }
-            (this, other) => {
-                let this: CalcLengthOrPercentage = From::from(this);
-                let other: CalcLengthOrPercentage = From::from(other);
-                this.interpolate(&other, time).and_then(|value| {
-                    Some(LengthOrPercentage::Calc(value))
-                })
-            }
-        }
-    }
-}
-
-impl Interpolate for LengthOrPercentageOrAuto {
-    #[inline]
-    fn interpolate(&self, other: &LengthOrPercentageOrAuto, time: f64)
-                   -> Option<LengthOrPercentageOrAuto> {
-        match (*self, *other) {
-            (LengthOrPercentageOrAuto::Length(ref this),
-             LengthOrPercentageOrAuto::Length(ref other)) => {
-                this.interpolate(other, time).and_then(|value| {
-                    Some(LengthOrPercentageOrAuto::Length(value))
-                })
-            }
-            (LengthOrPercentageOrAuto::Percentage(ref this),
-             LengthOrPercentageOrAuto::Percentage(ref other)) => {
-                this.interpolate(other, time).and_then(|value| {
-                    Some(LengthOrPercentageOrAuto::Percentage(value))
-                })
-            }
-            (LengthOrPercentageOrAuto::Auto, LengthOrPercentageOrAuto::Auto) => {
-                Some(LengthOrPercentageOrAuto::Auto)
-            }
-            (this, other) => {
-                let this: Option<CalcLengthOrPercentage> = From::from(this);
-                let other: Option<CalcLengthOrPercentage> = From::from(other);
-                this.interpolate(&other, time).unwrap_or(None).and_then(|value| {
-                    Some(LengthOrPercentageOrAuto::Calc(value))
-                })
-            }
-        }
-    }
-}
 
-impl Interpolate for LengthOrPercentageOrNone {
-    #[inline]
-    fn interpolate(&self, other: &LengthOrPercentageOrNone, time: f64)
-                   -> Option<LengthOrPercentageOrNone> {
-        match (*self, *other) {
-            (LengthOrPercentageOrNone::Length(ref this),
-             LengthOrPercentageOrNone::Length(ref other)) => {
-                this.interpolate(other, time).and_then(|value| {
-                    Some(LengthOrPercentageOrNone::Length(value))
-                })
-            }
-            (LengthOrPercentageOrNone::Percentage(ref this),
-             LengthOrPercentageOrNone::Percentage(ref other)) => {
-                this.interpolate(other, time).and_then(|value| {
-                    Some(LengthOrPercentageOrNone::Percentage(value))
-                })
-            }
-            (LengthOrPercentageOrNone::None, LengthOrPercentageOrNone::None) => {
-                Some(LengthOrPercentageOrNone::None)
-            }
-            (_, _) => None,
-        }
-    }
-}
-
-impl Interpolate for LineHeight {
-    #[inline]
-    fn interpolate(&self, other: &LineHeight, time: f64)
-                   -> Option<LineHeight> {
-        match (*self, *other) {
-            (LineHeight::Length(ref this),
-             LineHeight::Length(ref other)) => {
-                this.interpolate(other, time).and_then(|value| {
-                    Some(LineHeight::Length(value))
-                })
-            }
-            (LineHeight::Number(ref this),
-             LineHeight::Number(ref other)) => {
-                this.interpolate(other, time).and_then(|value| {
-                    Some(LineHeight::Number(value))
-                })
-            }
-            (LineHeight::Normal, LineHeight::Normal) => {
-                Some(LineHeight::Normal)
-            }
-            (_, _) => None,
-        }
-    }
-}
-
-/// http://dev.w3.org/csswg/css-transitions/#animtype-font-weight
-impl Interpolate for FontWeight {
-    #[inline]
-    fn interpolate(&self, other: &FontWeight, time: f64)
-                   -> Option<FontWeight> {
-        let a = (*self as u32) as f64;
-        let b = (*other as u32) as f64;
-        let weight = a + (b - a) * time;
-        Some(if weight < 150. {
-            FontWeight::Weight100
-        } else if weight < 250. {
-            FontWeight::Weight200
-        } else if weight < 350. {
-            FontWeight::Weight300
-        } else if weight < 450. {
-            FontWeight::Weight400
-        } else if weight < 550. {
-            FontWeight::Weight500
-        } else if weight < 650. {
-            FontWeight::Weight600
-        } else if weight < 750. {
-            FontWeight::Weight700
-        } else if weight < 850. {
-            FontWeight::Weight800
-        } else {
-            FontWeight::Weight900
-        })
-    }
-}
-
-impl Interpolate for ClipRect {
-    #[inline]
-    fn interpolate(&self, other: &ClipRect, time: f64)
-                   -> Option<ClipRect> {
-        match (self.top.interpolate(&other.top, time),
-               self.right.interpolate(&other.right, time),
-               self.bottom.interpolate(&other.bottom, time),
-               self.left.interpolate(&other.left, time)) {
-            (Some(top), Some(right), Some(bottom), Some(left)) => {
-                Some(ClipRect { top: top, right: right, bottom: bottom, left: left })
-            },
-            (_, _, _, _) => None,
-        }
-    }
-}
-
-impl Interpolate for BackgroundPosition {
-    #[inline]
-    fn interpolate(&self, other: &BackgroundPosition, time: f64)
-                   -> Option<BackgroundPosition> {
-        match (self.horizontal.interpolate(&other.horizontal, time),
-               self.vertical.interpolate(&other.vertical, time)) {
-            (Some(horizontal), Some(vertical)) => {
-                Some(BackgroundPosition { horizontal: horizontal, vertical: vertical })
-            },
-            (_, _) => None,
-        }
-    }
-}
-
-impl Interpolate for TextShadow {
-    #[inline]
-    fn interpolate(&self, other: &TextShadow, time: f64)
-                   -> Option<TextShadow> {
-        match (self.offset_x.interpolate(&other.offset_x, time),
-               self.offset_y.interpolate(&other.offset_y, time),
-               self.blur_radius.interpolate(&other.blur_radius, time),
-               self.color.interpolate(&other.color, time)) {
-            (Some(offset_x), Some(offset_y), Some(blur_radius), Some(color)) => {
-                Some(TextShadow { offset_x: offset_x, offset_y: offset_y, blur_radius: blur_radius, color: color })
-            },
-            (_, _, _, _) => None,
-        }
-    }
-}
-
-impl Interpolate for TextShadowList {
-    #[inline]
-    fn interpolate(&self, other: &TextShadowList, time: f64)
-                   -> Option<TextShadowList> {
-        let zero = TextShadow {
-            offset_x: Au(0),
-            offset_y: Au(0),
-            blur_radius: Au(0),
-            color: Color::RGBA(RGBA {
-                red: 0.0, green: 0.0, blue: 0.0, alpha: 0.0
-            })
-        };
-
-        let interpolate_each = |(a, b): (&TextShadow, &TextShadow)| {
-            a.interpolate(b, time).unwrap()
-        };
-
-        Some(TextShadowList(match self.0.len().cmp(&other.0.len()) {
-            Ordering::Less => other.0.iter().chain(repeat(&zero)).zip(other.0.iter()).map(interpolate_each).collect(),
-            _ => self.0.iter().zip(other.0.iter().chain(repeat(&zero))).map(interpolate_each).collect(),
-        }))
-    }
-}
-
-/// Check if it's possible to do a direct numerical interpolation
-/// between these two transform lists.
-/// http://dev.w3.org/csswg/css-transforms/#transform-transform-animation
-fn can_interpolate_list(from_list: &[TransformOperation],
-                        to_list: &[TransformOperation]) -> bool {
-    // Lists must be equal length
-    if from_list.len() != to_list.len() {
-        return false;
-    }
-
-    // Each transform operation must match primitive type in other list
-    for (from, to) in from_list.iter().zip(to_list) {
-        match (from, to) {
-            (&TransformOperation::Matrix(..), &TransformOperation::Matrix(..)) |
-            (&TransformOperation::Skew(..), &TransformOperation::Skew(..)) |
-            (&TransformOperation::Translate(..), &TransformOperation::Translate(..)) |
-            (&TransformOperation::Scale(..), &TransformOperation::Scale(..)) |
-            (&TransformOperation::Rotate(..), &TransformOperation::Rotate(..)) |
-            (&TransformOperation::Perspective(..), &TransformOperation::Perspective(..)) => {}
-            _ => {
-                return false;
-            }
-        }
-    }
-
-    true
-}
-
-/// Interpolate two transform lists.
-/// http://dev.w3.org/csswg/css-transforms/#interpolation-of-transforms
-fn interpolate_transform_list(from_list: &[TransformOperation],
-                              to_list: &[TransformOperation],
-                              time: f64) -> TransformList {
-    let mut result = vec!();
-
-    if can_interpolate_list(from_list, to_list) {
-        for (from, to) in from_list.iter().zip(to_list) {
-            match (from, to) {
-                (&TransformOperation::Matrix(from),
-                 &TransformOperation::Matrix(_to)) => {
-                    // TODO(gw): Implement matrix decomposition and interpolation
-                    result.push(TransformOperation::Matrix(from));
-                }
-                (&TransformOperation::Skew(fx, fy),
-                 &TransformOperation::Skew(tx, ty)) => {
-                    let ix = fx.interpolate(&tx, time).unwrap();
-                    let iy = fy.interpolate(&ty, time).unwrap();
-                    result.push(TransformOperation::Skew(ix, iy));
-                }
-                (&TransformOperation::Translate(fx, fy, fz),
-                 &TransformOperation::Translate(tx, ty, tz)) => {
-                    let ix = fx.interpolate(&tx, time).unwrap();
-                    let iy = fy.interpolate(&ty, time).unwrap();
-                    let iz = fz.interpolate(&tz, time).unwrap();
-                    result.push(TransformOperation::Translate(ix, iy, iz));
-                }
-                (&TransformOperation::Scale(fx, fy, fz),
-                 &TransformOperation::Scale(tx, ty, tz)) => {
-                    let ix = fx.interpolate(&tx, time).unwrap();
-                    let iy = fy.interpolate(&ty, time).unwrap();
-                    let iz = fz.interpolate(&tz, time).unwrap();
-                    result.push(TransformOperation::Scale(ix, iy, iz));
-                }
-                (&TransformOperation::Rotate(fx, fy, fz, fa),
-                 &TransformOperation::Rotate(_tx, _ty, _tz, _ta)) => {
-                    // TODO(gw): Implement matrix decomposition and interpolation
-                    result.push(TransformOperation::Rotate(fx, fy, fz, fa));
-                }
-                (&TransformOperation::Perspective(fd),
-                 &TransformOperation::Perspective(_td)) => {
-                    // TODO(gw): Implement matrix decomposition and interpolation
-                    result.push(TransformOperation::Perspective(fd));
-                }
-                _ => {
-                    // This should be unreachable due to the can_interpolate_list() call.
-                    unreachable!();
+            /// Returns the property animating from its end value to that same value.
+            fn end_value(&self) -> AnimatedProperty {
+                match *self {
+                    $(
+                        AnimatedProperty::$name(_, ref end) => {
+                            AnimatedProperty::$name(end.clone(), end.clone())
+                        }
+                    )*
                 }
             }
-        }
-    } else {
-        // TODO(gw): Implement matrix decomposition and interpolation
-        result.extend_from_slice(from_list);
-    }
-
-    TransformList(Some(result))
-}
 
-/// Build an equivalent 'identity transform function list' based
-/// on an existing transform list.
-/// http://dev.w3.org/csswg/css-transforms/#none-transform-animation
-fn build_identity_transform_list(list: &[TransformOperation]) -> Vec<TransformOperation> {
-    let mut result = vec!();
-
-    for operation in list {
-        match *operation {
-            TransformOperation::Matrix(..) => {
-                let identity = ComputedMatrix::identity();
-                result.push(TransformOperation::Matrix(identity));
-            }
-            TransformOperation::Skew(..) => {
-                result.push(TransformOperation::Skew(Angle(0.0), Angle(0.0)));
-            }
-            TransformOperation::Translate(..) => {
-                result.push(TransformOperation::Translate(LengthOrPercentage::zero(),
-                                                          LengthOrPercentage::zero(),
-                                                          Au(0)));
-            }
-            TransformOperation::Scale(..) => {
-                result.push(TransformOperation::Scale(1.0, 1.0, 1.0));
-            }
-            TransformOperation::Rotate(..) => {
-                result.push(TransformOperation::Rotate(0.0, 0.0, 1.0, Angle(0.0)));
-            }
-            TransformOperation::Perspective(..) => {
-                // http://dev.w3.org/csswg/css-transforms/#identity-transform-function
-                let identity = ComputedMatrix::identity();
-                result.push(TransformOperation::Matrix(identity));
+            /// Returns whether both properties are the same and animate to the same value.
+            fn has_same_end_value(&self, other: &AnimatedProperty) -> bool {
+                match (self, other) {
+                    $(
+                        (&AnimatedProperty::$name(_, ref end),
+                         &AnimatedProperty::$name(_, ref other_end)) => end == other_end,
+                    )*
+                    _ => false,
+                }
             }
         }
     }
-
-    result
 }
 
-impl Interpolate for TransformList {
-    #[inline]
-    fn interpolate(&self, other: &TransformList, time: f64) -> Option<TransformList> {
-        // http://dev.w3.org/csswg/css-transforms/#interpolation-of-transforms
-        let result = match (&self.0, &other.0) {
-            (&Some(ref from_list), &Some(ref to_list)) => {
-                // Two lists of transforms
-                interpolate_transform_list(from_list, &to_list, time)
-            }
-            (&Some(ref from_list), &None) => {
-                // http://dev.w3.org/csswg/css-transforms/#none-transform-animation
-                let to_list = build_identity_transform_list(from_list);
-                interpolate_transform_list(from_list, &to_list, time)
-            }
-            (&None, &Some(ref to_list)) => {
-                // http://dev.w3.org/csswg/css-transforms/#none-transform-animation
-                let from_list = build_identity_transform_list(to_list);
-                interpolate_transform_list(&from_list, to_list, time)
-            }
-            _ => {
-                // http://dev.w3.org/csswg/css-transforms/#none-none-animation
-                TransformList(None)
-            }
-        };
-
-        Some(result)
-    }
-}
+animated_property_values!(
+    BackgroundColor, BackgroundPosition, BorderBottomColor, BorderBottomLeftRadius,
+    BorderBottomRightRadius, BorderBottomWidth, BorderLeftColor, BorderLeftWidth,
+    BorderRightColor, BorderRightWidth, BorderSpacing, BorderTopColor, BorderTopLeftRadius,
+    BorderTopRightRadius, BorderTopWidth, Bottom, BoxShadow, Color, Clip, FontSize, FontWeight,
+    Height, Left, LetterSpacing, LineHeight, MarginBottom, MarginLeft, MarginRight, MarginTop,
+    MaxHeight, MaxWidth, MinHeight, MinWidth, Opacity, Order, OutlineColor, OutlineOffset,
+    OutlineWidth, PaddingBottom, PaddingLeft, PaddingRight, PaddingTop, Perspective,
+    PerspectiveOrigin, Right, TextDecorationColor, TextIndent, TextShadow, Top, Transform,
+    TransformOrigin, VerticalAlign, Visibility, Width, WordSpacing, ZIndex);
 
 /// Accesses an element of an array, "wrapping around" using modular arithmetic. This is needed
 /// to handle values of differing lengths according to CSS-TRANSITIONS § 2.
@@ -927,50 +848,105 @@ impl<T> GetMod for Vec<T> {
     }
 }
 
-/// Inserts transitions into the queue of running animations as applicable for the given style
-/// difference. This is called from the layout worker threads. Returns true if any animations were
-/// kicked off and false otherwise.
+/// Starts, keeps, reverses and cancels the transitions of a node for a change from `old_style`,
+/// its style before the change with its running transitions applied, to `new_style`. The
+/// transitions that were running, which `running_animations` are, are cancelled unless they
+/// still transition to the value in `new_style`. Those that keep running and those that start are
+/// sent to `new_animations_sender`, and applied to `new_style`, since transitions take precedence
+/// over all declarations. This is called from the layout worker threads. Returns true if any
+/// transitions run afterwards and false otherwise.
+/// https://drafts.csswg.org/css-transitions/#starting
 pub fn start_transitions_if_applicable<C: ComputedValues>(new_animations_sender: &Mutex<Sender<Animation>>,
-                                                          node: OpaqueNode,
+                                                          target: &AnimationTarget,
                                                           old_style: &C,
-                                                          new_style: &mut C)
+                                                          new_style: &mut C,
+                                                          running_animations: Vec<Animation>)
                                                           -> bool {
+    // Of the transitions that name a property, the last one applies to it.
+    let mut property_animations: Vec<(usize, PropertyAnimation)> = vec![];
+    for i in (0..new_style.get_box().transition_count()).rev() {
+        let candidates =
+            PropertyAnimation::from_transition(i, old_style.as_servo(), new_style.as_servo_mut());
+        for candidate in candidates {
+            if property_animations.iter().all(|&(_, ref property_animation)| {
+                property_animation.property() != candidate.property()
+            }) {
+                property_animations.push((i, candidate))
+            }
+        }
+    }
+
+    let now = time::precise_time_s();
     let mut had_animations = false;
-    for i in 0..new_style.get_box().transition_count() {
-        // Create any property animations, if applicable.
-        let property_animations = PropertyAnimation::from_transition(i, old_style.as_servo(), new_style.as_servo_mut());
-        for property_animation in property_animations {
-            // Set the property to the initial value.
-            property_animation.update(new_style.as_servo_mut(), 0.0);
-
-            // Kick off the animation.
-            let now = time::precise_time_s();
+    for (i, mut property_animation) in property_animations {
+        let running_animation = running_animations.iter().find(|running_animation| {
+            running_animation.property_animation.property() == property_animation.property()
+        });
+
+        // A transition that still runs to the new value keeps running.
+        if let Some(running_animation) = running_animation {
+            if running_animation.property_animation.property
+                                .has_same_end_value(&property_animation.property) {
+                running_animation.property_animation.update(new_style.as_servo_mut(),
+                                                            running_animation.progress(now));
+                new_animations_sender.lock().unwrap().send(running_animation.clone()).unwrap();
+                had_animations = true;
+                continue
+            }
+        }
+
+        let (mut delay, mut duration) = {
             let box_style = new_style.as_servo().get_box();
-            let start_time =
-                now + (box_style.transition_delay.0.get_mod(i).seconds() as f64);
-            new_animations_sender.lock().unwrap().send(Animation {
-                node: node,
-                property_animation: property_animation,
-                start_time: start_time,
-                end_time: start_time +
-                    (box_style.transition_duration.0.get_mod(i).seconds() as f64),
-            }).unwrap();
-
-            had_animations = true
+            (box_style.transition_delay.0.get_mod(i).seconds() as f64,
+             box_style.transition_duration.0.get_mod(i).seconds() as f64)
+        };
+
+        // A transition back to the value that a running transition started from is shortened by
+        // how far that one had gone.
+        if let Some(running_animation) = running_animation {
+            let running = &running_animation.property_animation;
+            if running.reversing_adjusted_start_value
+                      .has_same_end_value(&property_animation.property) {
+                let output = running.timing_function_output(running_animation.progress(now));
+                let factor = (output * running.reversing_shortening_factor + 1.0 -
+                              running.reversing_shortening_factor).abs().min(1.0);
+                duration *= factor;
+                if delay < 0.0 {
+                    delay *= factor
+                }
+                property_animation.duration = Time(duration as CSSFloat);
+                property_animation.reversing_adjusted_start_value = running.property.end_value();
+                property_animation.reversing_shortening_factor = factor;
+            }
         }
+
+        // Set the property to the initial value.
+        property_animation.update(new_style.as_servo_mut(), 0.0);
+
+        // Kick off the animation.
+        let start_time = now + delay;
+        new_animations_sender.lock().unwrap().send(Animation {
+            target: target.clone(),
+            property_animation: property_animation,
+            start_time: start_time,
+            end_time: start_time + duration,
+            paused_at: None,
+        }).unwrap();
+
+        had_animations = true
     }
 
     had_animations
 }
 
-/// Updates a single animation and associated style based on the current time. If `damage` is
+/// Updates a single animation and associated style based on the time `now`. If `damage` is
 /// provided, inserts the appropriate restyle damage.
 pub fn update_style_for_animation<C: ComputedValues,
                                   Damage: TRestyleDamage<ConcreteComputedValues=C>>(animation: &Animation,
                                                                                     style: &mut Arc<C>,
+                                                                                    now: f64,
                                                                                     damage: Option<&mut Damage>) {
-    let now = time::precise_time_s();
-    let mut progress = (now - animation.start_time) / animation.duration();
+    let mut progress = animation.elapsed_time(now) / animation.duration();
     if progress > 1.0 {
         progress = 1.0
     }
@@ -986,3 +962,29 @@ pub fn update_style_for_animation<C: ComputedValues,
 
     *style = new_style
 }
+
+/// Applies an animation that script created to `style` at the time `now`. If `damage` is
+/// provided, inserts the appropriate restyle damage.
+pub fn update_style_for_keyframes_animation<C: ComputedValues,
+                                            Damage: TRestyleDamage<ConcreteComputedValues=C>>(
+                                            animation: &KeyframesAnimation,
+                                            style: &mut Arc<C>,
+                                            viewport_size: Size2D<Au>,
+                                            now: f64,
+                                            damage: Option<&mut Damage>) {
+    let progress = match animation.iteration_progress(now) {
+        Some(progress) => progress,
+        None => return,
+    };
+
+    let mut new_style = (*style).clone();
+    animation.effect.update(Arc::make_mut(&mut new_style).as_servo_mut(),
+                            viewport_size,
+                            animation.timing.duration,
+                            progress);
+    if let Some(damage) = damage {
+        *damage = *damage | Damage::compute(Some(style), &new_style);
+    }
+
+    *style = new_style
+}
@@ -0,0 +1,94 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Immutable snapshots of what the layout thread cascades styles against, which other threads
+//! share to resolve styles on their own instead of asking the layout thread, such as to measure
+//! text in the `font` of a canvas context.
+
+use error_reporting::StdoutErrorReporter;
+use media_queries::Device;
+use parser::ParserContextExtraData;
+use properties::{self, ComputedValues, parse_one_declaration};
+use selector_impl::SelectorImplExt;
+use selector_matching::{DeclarationBlock, Stylist};
+use std::ascii::AsciiExt;
+use std::sync::{Arc, RwLock};
+use url::Url;
+
+/// The snapshot that the layout thread took last, behind a lock that is only held long enough
+/// to clone or replace it.
+pub type SharedCascadeSnapshot<C> = Arc<RwLock<Arc<CascadeSnapshot<C>>>>;
+
+/// The font of canvas contexts before scripts set one.
+/// https://html.spec.whatwg.org/multipage/#dom-context-2d-font
+const DEFAULT_CANVAS_FONT: &'static str = "10px sans-serif";
+
+pub struct CascadeSnapshot<C: ComputedValues> {
+    /// The device that media queries and viewport-relative lengths are evaluated against.
+    pub device: Device,
+    /// The style of the root element, which `rem` lengths are relative to, if it has one yet.
+    pub root_style: Option<Arc<C>>,
+}
+
+impl<C: ComputedValues> CascadeSnapshot<C> {
+    pub fn new<Impl: SelectorImplExt>(stylist: &Stylist<Impl>, root_style: Option<Arc<C>>)
+                                      -> CascadeSnapshot<C> {
+        CascadeSnapshot {
+            device: Device::new(stylist.device.media_type, stylist.device.viewport_size),
+            root_style: root_style,
+        }
+    }
+
+    /// Computes the style that a value of the `font` shorthand gives, inheriting from `parent`,
+    /// or from the default font of canvas contexts when there is none, as for offscreen canvases.
+    /// Returns `None` for values that do not parse and for CSS-wide keywords, which canvas
+    /// contexts ignore.
+    pub fn resolve_font(&self, value: &str, parent: Option<&C>, base_url: &Url) -> Option<Arc<C>> {
+        if ["inherit", "initial", "unset"].iter().any(|keyword| {
+            value.trim().eq_ignore_ascii_case(keyword)
+        }) {
+            return None
+        }
+        let default_style;
+        let parent = match parent {
+            Some(parent) => parent,
+            None => {
+                default_style = match self.cascade_font(DEFAULT_CANVAS_FONT, None, base_url) {
+                    Some(style) => style,
+                    None => return None,
+                };
+                &default_style
+            }
+        };
+        self.cascade_font(value, Some(parent), base_url).map(Arc::new)
+    }
+
+    fn cascade_font(&self, value: &str, parent: Option<&C>, base_url: &Url) -> Option<C> {
+        let declarations = match parse_one_declaration("font",
+                                                       value,
+                                                       base_url,
+                                                       box StdoutErrorReporter,
+                                                       ParserContextExtraData::default()) {
+            Ok(declarations) => declarations,
+            Err(()) => return None,
+        };
+        let declarations = [DeclarationBlock::from_declarations(Arc::new(declarations))];
+        let (mut style, _) = properties::cascade(self.device.au_viewport_size(),
+                                                 1.0,
+                                                 &declarations,
+                                                 false,
+                                                 parent,
+                                                 None,
+                                                 None,
+                                                 box StdoutErrorReporter);
+        // Without a parent, the style is cascaded as that of a root element, which `rem`
+        // lengths would then be relative to.
+        if parent.is_none() {
+            if let Some(ref root_style) = self.root_style {
+                style.set_root_font_size(root_style.root_font_size());
+            }
+        }
+        Some(style)
+    }
+}
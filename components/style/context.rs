@@ -2,7 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use animation::Animation;
+use animation::{Animation, AnimationTarget, KeyframesAnimation};
 use app_units::Au;
 use dom::OpaqueNode;
 use error_reporting::ParseErrorReporter;
@@ -37,11 +37,24 @@ pub struct SharedStyleContext<Impl: SelectorImplExt> {
     /// Why is this reflow occurring
     pub goal: ReflowGoal,
 
-    /// The animations that are currently running.
-    pub running_animations: Arc<RwLock<HashMap<OpaqueNode, Vec<Animation>>>>,
+    /// The animations that are currently running, by the node or pseudo-element that they run
+    /// on.
+    pub running_animations: Arc<RwLock<HashMap<AnimationTarget, Vec<Animation>>>>,
 
     /// The list of animations that have expired since the last style recalculation.
-    pub expired_animations: Arc<RwLock<HashMap<OpaqueNode, Vec<Animation>>>>,
+    pub expired_animations: Arc<RwLock<HashMap<AnimationTarget, Vec<Animation>>>>,
+
+    /// The animations that script created, which apply on top of the transitions.
+    pub keyframes_animations: Arc<RwLock<HashMap<OpaqueNode, Vec<KeyframesAnimation>>>>,
+
+    /// The time that animations are sampled at, as returned by `time::precise_time_s()`. When
+    /// this restyle renders an animation tick, it is the time of that tick, so that the styles
+    /// that script changes in its animation frame callbacks stay in phase with the animations.
+    pub animation_time: f64,
+
+    /// Whether to time selector matching and the cascade of every node, which costs two clock
+    /// reads each, for `traversal::get_and_reset_phase_totals` to report.
+    pub record_phase_times: bool,
 
     ///The CSS error reporter for all CSS loaded in this layout thread
     pub error_reporter: Box<ParseErrorReporter + Sync>,
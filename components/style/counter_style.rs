@@ -0,0 +1,281 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The `@counter-style` rule, which defines a counter style that `list-style-type`, `counter()`
+//! and `counters()` can then name.
+//!
+//! https://drafts.csswg.org/css-counter-styles/#the-counter-style-rule
+
+use cssparser::{AtRuleParser, DeclarationListParser, DeclarationParser, Parser, Token};
+use parser::{ParserContext, log_css_error};
+use std::ascii::AsciiExt;
+use std::collections::HashMap;
+use std::hash::BuildHasherDefault;
+use string_cache::Atom;
+use stylesheets::SourceRange;
+
+/// The counter styles that `@counter-style` rules define, by name.
+pub type CounterStyles = HashMap<Atom, CounterStyleRule, BuildHasherDefault<::fnv::FnvHasher>>;
+
+/// How a counter style turns counter values into representations.
+/// https://drafts.csswg.org/css-counter-styles/#counter-style-system
+#[derive(Clone, Debug, HeapSizeOf, PartialEq, Eq)]
+pub enum System {
+    Cyclic,
+    Numeric,
+    Alphabetic,
+    Symbolic,
+    Additive,
+    /// The symbols represent consecutive values, starting at the given one.
+    Fixed(i32),
+    /// The system of another counter style, of which the descriptors that this rule leaves
+    /// unspecified are taken too.
+    Extends(Atom),
+}
+
+/// The bound of a counter range at one of its ends, or `None` if there is none there.
+pub type RangeBound = Option<i32>;
+
+#[derive(Clone, Debug, HeapSizeOf, PartialEq, Eq)]
+pub struct CounterStyleRule {
+    pub name: Atom,
+    pub system: System,
+    /// The descriptors below are `None` when they are not specified.
+    pub negative: Option<(String, String)>,
+    pub prefix: Option<String>,
+    pub suffix: Option<String>,
+    /// The ranges of values that the style can represent, or an empty list for `auto`.
+    pub range: Option<Vec<(RangeBound, RangeBound)>>,
+    pub pad: Option<(u32, String)>,
+    pub fallback: Option<Atom>,
+    pub symbols: Option<Vec<String>>,
+    pub additive_symbols: Option<Vec<(u32, String)>>,
+    /// The whole rule, from its at-keyword to its closing brace.
+    pub source_range: SourceRange,
+}
+
+/// The names that `@counter-style` rules cannot define, either because they mean something
+/// else or because the predefined styles by those names cannot be overridden.
+/// https://drafts.csswg.org/css-counter-styles/#the-counter-style-rule
+fn is_reserved_name(name: &str) -> bool {
+    ["none", "inherit", "initial", "unset", "default", "decimal", "disc", "square", "circle",
+     "disclosure-open", "disclosure-closed"].iter().any(|reserved| {
+        name.eq_ignore_ascii_case(reserved)
+    })
+}
+
+/// Parses the name in the prelude of a `@counter-style` rule.
+pub fn parse_counter_style_name(input: &mut Parser) -> Result<Atom, ()> {
+    let name = try!(input.expect_ident());
+    if is_reserved_name(&name) {
+        return Err(())
+    }
+    Ok(Atom::from(&*name))
+}
+
+/// Parses the descriptors of a `@counter-style` rule, which is invalid if its system lacks the
+/// symbols that it needs.
+pub fn parse_counter_style_block(context: &ParserContext, name: Atom, input: &mut Parser)
+                                 -> Result<CounterStyleRule, ()> {
+    let mut rule = CounterStyleRule {
+        name: name,
+        system: System::Symbolic,
+        negative: None,
+        prefix: None,
+        suffix: None,
+        range: None,
+        pad: None,
+        fallback: None,
+        symbols: None,
+        additive_symbols: None,
+        source_range: SourceRange::default(),
+    };
+    {
+        let mut iter = DeclarationListParser::new(input, CounterStyleRuleParser);
+        while let Some(declaration) = iter.next() {
+            match declaration {
+                Err(range) => {
+                    let pos = range.start;
+                    let message =
+                        format!("Unsupported @counter-style descriptor declaration: '{}'",
+                                iter.input.slice(range));
+                    log_css_error(iter.input, pos, &*message, context);
+                }
+                Ok(CounterStyleDescriptorDeclaration::System(value)) => rule.system = value,
+                Ok(CounterStyleDescriptorDeclaration::Negative(value)) => {
+                    rule.negative = Some(value)
+                }
+                Ok(CounterStyleDescriptorDeclaration::Prefix(value)) => rule.prefix = Some(value),
+                Ok(CounterStyleDescriptorDeclaration::Suffix(value)) => rule.suffix = Some(value),
+                Ok(CounterStyleDescriptorDeclaration::Range(value)) => rule.range = Some(value),
+                Ok(CounterStyleDescriptorDeclaration::Pad(value)) => rule.pad = Some(value),
+                Ok(CounterStyleDescriptorDeclaration::Fallback(value)) => {
+                    rule.fallback = Some(value)
+                }
+                Ok(CounterStyleDescriptorDeclaration::Symbols(value)) => {
+                    rule.symbols = Some(value)
+                }
+                Ok(CounterStyleDescriptorDeclaration::AdditiveSymbols(value)) => {
+                    rule.additive_symbols = Some(value)
+                }
+            }
+        }
+    }
+
+    // Rules without the symbols that their system needs are invalid.
+    // https://drafts.csswg.org/css-counter-styles/#counter-style-symbols
+    let symbols = rule.symbols.as_ref().map_or(0, |symbols| symbols.len());
+    let additive_symbols = rule.additive_symbols.as_ref().map_or(0, |symbols| symbols.len());
+    let valid = match rule.system {
+        System::Cyclic | System::Fixed(_) | System::Symbolic => symbols >= 1,
+        System::Alphabetic | System::Numeric => symbols >= 2,
+        System::Additive => additive_symbols >= 1,
+        System::Extends(_) => rule.symbols.is_none() && rule.additive_symbols.is_none(),
+    };
+    if valid {
+        Ok(rule)
+    } else {
+        Err(())
+    }
+}
+
+enum CounterStyleDescriptorDeclaration {
+    System(System),
+    Negative((String, String)),
+    Prefix(String),
+    Suffix(String),
+    Range(Vec<(RangeBound, RangeBound)>),
+    Pad((u32, String)),
+    Fallback(Atom),
+    Symbols(Vec<String>),
+    AdditiveSymbols(Vec<(u32, String)>),
+}
+
+struct CounterStyleRuleParser;
+
+/// Default methods reject all at rules.
+impl AtRuleParser for CounterStyleRuleParser {
+    type Prelude = ();
+    type AtRule = CounterStyleDescriptorDeclaration;
+}
+
+impl DeclarationParser for CounterStyleRuleParser {
+    type Declaration = CounterStyleDescriptorDeclaration;
+
+    fn parse_value(&self, name: &str, input: &mut Parser)
+                   -> Result<CounterStyleDescriptorDeclaration, ()> {
+        match_ignore_ascii_case! { name,
+            "system" => {
+                Ok(CounterStyleDescriptorDeclaration::System(try!(parse_system(input))))
+            },
+            "negative" => {
+                let before = try!(parse_symbol(input));
+                let after = input.try(parse_symbol).unwrap_or(String::new());
+                Ok(CounterStyleDescriptorDeclaration::Negative((before, after)))
+            },
+            "prefix" => {
+                Ok(CounterStyleDescriptorDeclaration::Prefix(try!(parse_symbol(input))))
+            },
+            "suffix" => {
+                Ok(CounterStyleDescriptorDeclaration::Suffix(try!(parse_symbol(input))))
+            },
+            "range" => {
+                if input.try(|input| input.expect_ident_matching("auto")).is_ok() {
+                    return Ok(CounterStyleDescriptorDeclaration::Range(vec![]))
+                }
+                let ranges = try!(input.parse_comma_separated(|input| {
+                    let lower = try!(parse_range_bound(input));
+                    let upper = try!(parse_range_bound(input));
+                    match (lower, upper) {
+                        (Some(lower), Some(upper)) if lower > upper => Err(()),
+                        _ => Ok((lower, upper)),
+                    }
+                }));
+                Ok(CounterStyleDescriptorDeclaration::Range(ranges))
+            },
+            "pad" => {
+                // The length and the symbol can come in either order.
+                let symbol = input.try(parse_symbol).ok();
+                let length = try!(input.expect_integer());
+                if length < 0 {
+                    return Err(())
+                }
+                let symbol = match symbol {
+                    Some(symbol) => symbol,
+                    None => try!(parse_symbol(input)),
+                };
+                Ok(CounterStyleDescriptorDeclaration::Pad((length as u32, symbol)))
+            },
+            "fallback" => {
+                let name = try!(input.expect_ident());
+                Ok(CounterStyleDescriptorDeclaration::Fallback(Atom::from(&*name)))
+            },
+            "symbols" => {
+                let mut symbols = vec![try!(parse_symbol(input))];
+                while let Ok(symbol) = input.try(parse_symbol) {
+                    symbols.push(symbol)
+                }
+                Ok(CounterStyleDescriptorDeclaration::Symbols(symbols))
+            },
+            "additive-symbols" => {
+                let symbols = try!(input.parse_comma_separated(|input| {
+                    let symbol = input.try(parse_symbol).ok();
+                    let weight = try!(input.expect_integer());
+                    if weight < 0 {
+                        return Err(())
+                    }
+                    let symbol = match symbol {
+                        Some(symbol) => symbol,
+                        None => try!(parse_symbol(input)),
+                    };
+                    Ok((weight as u32, symbol))
+                }));
+                // The weights must be in decreasing order.
+                if symbols.windows(2).any(|pair| pair[0].0 <= pair[1].0) {
+                    return Err(())
+                }
+                Ok(CounterStyleDescriptorDeclaration::AdditiveSymbols(symbols))
+            },
+            _ => Err(())
+        }
+    }
+}
+
+fn parse_system(input: &mut Parser) -> Result<System, ()> {
+    let ident = try!(input.expect_ident());
+    match_ignore_ascii_case! { ident,
+        "cyclic" => Ok(System::Cyclic),
+        "numeric" => Ok(System::Numeric),
+        "alphabetic" => Ok(System::Alphabetic),
+        "symbolic" => Ok(System::Symbolic),
+        "additive" => Ok(System::Additive),
+        "fixed" => {
+            let first = input.try(|input| input.expect_integer()).unwrap_or(1);
+            Ok(System::Fixed(first))
+        },
+        "extends" => {
+            let name = try!(input.expect_ident());
+            if name.eq_ignore_ascii_case("none") {
+                return Err(())
+            }
+            Ok(System::Extends(Atom::from(&*name)))
+        },
+        _ => Err(())
+    }
+}
+
+/// Parses a `<symbol>`. Images are not supported.
+fn parse_symbol(input: &mut Parser) -> Result<String, ()> {
+    match try!(input.next()) {
+        Token::QuotedString(value) | Token::Ident(value) => Ok(value.into_owned()),
+        _ => Err(())
+    }
+}
+
+fn parse_range_bound(input: &mut Parser) -> Result<RangeBound, ()> {
+    if input.try(|input| input.expect_ident_matching("infinite")).is_ok() {
+        return Ok(None)
+    }
+    input.expect_integer().map(Some)
+}
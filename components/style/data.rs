@@ -17,6 +17,10 @@ pub struct PrivateStyleData<Impl: SelectorImpl, ConcreteComputedValues: Computed
     pub per_pseudo: HashMap<Impl::PseudoElement, Arc<ConcreteComputedValues>,
                             BuildHasherDefault<::fnv::FnvHasher>>,
 
+    /// The lazily cascaded pseudo-elements whose styles in `per_pseudo` were computed ahead of
+    /// time, and that no box has been built with yet.
+    pub speculative_pseudos: Vec<Impl::PseudoElement>,
+
     /// Information needed during parallel traversals.
     pub parallel: DomParallelInfo,
 }
@@ -27,6 +31,7 @@ impl<Impl, ConcreteComputedValues> PrivateStyleData<Impl, ConcreteComputedValues
         PrivateStyleData {
             style: None,
             per_pseudo: HashMap::with_hasher(Default::default()),
+            speculative_pseudos: vec![],
             parallel: DomParallelInfo::new(),
         }
     }
@@ -0,0 +1,45 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Easing: how far along its change an animation is once a given fraction of its duration has
+//! passed, according to its timing function. Transitions and the animations that script creates
+//! share it, both for whole iterations and for the segments between keyframes.
+//!
+//! https://drafts.csswg.org/css-timing/#timing-functions
+
+use bezier::Bezier;
+use euclid::point::Point2D;
+use properties::longhands::transition_timing_function::computed_value::StartEnd;
+use properties::longhands::transition_timing_function::computed_value::TransitionTimingFunction;
+
+/// Returns the output of `timing_function` once `progress`, a fraction between 0 and 1, of an
+/// animation lasting `duration` seconds has passed. The duration sets how precisely cubic Bézier
+/// curves are solved, since longer animations show more of their detail.
+pub fn output(timing_function: TransitionTimingFunction, duration: f64, progress: f64) -> f64 {
+    let progress = progress.max(0.0).min(1.0);
+    match timing_function {
+        TransitionTimingFunction::CubicBezier(p1, p2) => {
+            // See `WebCore::AnimationBase::solveEpsilon(double)` in WebKit.
+            let epsilon = if duration > 0.0 { 1.0 / (200.0 * duration) } else { 1e-6 };
+            Bezier::new(Point2D::new(p1.x as f64, p1.y as f64),
+                        Point2D::new(p2.x as f64, p2.y as f64)).solve(progress, epsilon)
+        }
+        TransitionTimingFunction::Steps(steps, start_end) => {
+            step_output(steps, start_end, progress)
+        }
+    }
+}
+
+/// Returns the output of a step timing function. Steps that jump at their start reach their
+/// first step as soon as the animation starts, and those that jump at their end only reach their
+/// last step once it ends.
+/// https://drafts.csswg.org/css-timing/#step-timing-functions
+fn step_output(steps: u32, start_end: StartEnd, progress: f64) -> f64 {
+    let steps = steps as f64;
+    let mut step = (progress * steps).floor();
+    if start_end == StartEnd::Start {
+        step += 1.0;
+    }
+    step.min(steps) / steps
+}
@@ -3,9 +3,11 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use computed_values::font_family::FontFamily;
-use cssparser::{AtRuleParser, DeclarationListParser, DeclarationParser, Parser};
+use cssparser::{AtRuleParser, DeclarationListParser, DeclarationParser, Parser, Token};
 use parser::{ParserContext, log_css_error};
 use properties::longhands::font_family::parse_one_family;
+use std::cmp;
+use stylesheets::SourceRange;
 use url::Url;
 
 #[derive(Clone, Debug, HeapSizeOf, PartialEq, Eq, Deserialize, Serialize)]
@@ -20,16 +22,81 @@ pub struct UrlSource {
     pub format_hints: Vec<String>,
 }
 
+/// A range of code points from the `unicode-range` descriptor, including both ends.
+#[derive(Clone, Copy, Debug, HeapSizeOf, PartialEq, Eq, Deserialize, Serialize)]
+pub struct UnicodeRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl UnicodeRange {
+    /// The range of all code points, which is the initial value of `unicode-range`.
+    pub fn all() -> UnicodeRange {
+        UnicodeRange {
+            start: 0,
+            end: 0x10FFFF,
+        }
+    }
+
+    pub fn contains(&self, character: char) -> bool {
+        self.start <= character as u32 && character as u32 <= self.end
+    }
+}
+
+// The value of the `font-display` descriptor.
+// https://drafts.csswg.org/css-fonts-4/#font-display-desc
+define_css_keyword_enum!(FontDisplay:
+                         "auto" => Auto,
+                         "block" => Block,
+                         "swap" => Swap,
+                         "fallback" => Fallback,
+                         "optional" => Optional);
+
+impl FontDisplay {
+    /// How long text using the font is invisible while waiting for it to load, in milliseconds.
+    pub fn block_period(&self) -> u64 {
+        match *self {
+            FontDisplay::Auto | FontDisplay::Block => 3000,
+            FontDisplay::Swap => 0,
+            FontDisplay::Fallback | FontDisplay::Optional => 100,
+        }
+    }
+
+    /// How long after the end of the block period the font still replaces the fallback font
+    /// once it has loaded, in milliseconds. `None` means forever.
+    pub fn swap_period(&self) -> Option<u64> {
+        match *self {
+            FontDisplay::Auto | FontDisplay::Block | FontDisplay::Swap => None,
+            FontDisplay::Fallback => Some(3000),
+            FontDisplay::Optional => Some(0),
+        }
+    }
+}
+
 #[derive(Debug, HeapSizeOf, PartialEq, Eq)]
 pub struct FontFaceRule {
     pub family: FontFamily,
     pub sources: Vec<Source>,
+    pub unicode_range: Vec<UnicodeRange>,
+    pub display: FontDisplay,
+    /// The whole rule, from its at-keyword to its closing brace.
+    pub source_range: SourceRange,
+}
+
+impl FontFaceRule {
+    /// Whether this font face may be used for any character, in which case there is no point
+    /// in waiting for it to be needed before downloading it.
+    pub fn covers_all_code_points(&self) -> bool {
+        self.unicode_range.iter().any(|range| *range == UnicodeRange::all())
+    }
 }
 
 pub fn parse_font_face_block(context: &ParserContext, input: &mut Parser)
                              -> Result<FontFaceRule, ()> {
     let mut family = None;
     let mut src = None;
+    let mut unicode_range = None;
+    let mut display = None;
     let mut iter = DeclarationListParser::new(input, FontFaceRuleParser { context: context });
     while let Some(declaration) = iter.next() {
         match declaration {
@@ -45,6 +112,12 @@ pub fn parse_font_face_block(context: &ParserContext, input: &mut Parser)
             Ok(FontFaceDescriptorDeclaration::Src(value)) => {
                 src = Some(value);
             }
+            Ok(FontFaceDescriptorDeclaration::UnicodeRange(value)) => {
+                unicode_range = Some(value);
+            }
+            Ok(FontFaceDescriptorDeclaration::Display(value)) => {
+                display = Some(value);
+            }
         }
     }
     match (family, src) {
@@ -52,6 +125,9 @@ pub fn parse_font_face_block(context: &ParserContext, input: &mut Parser)
             Ok(FontFaceRule {
                 family: family,
                 sources: src,
+                unicode_range: unicode_range.unwrap_or(vec![UnicodeRange::all()]),
+                display: display.unwrap_or(FontDisplay::Auto),
+                source_range: SourceRange::default(),
             })
         }
         _ => Err(())
@@ -61,6 +137,8 @@ pub fn parse_font_face_block(context: &ParserContext, input: &mut Parser)
 enum FontFaceDescriptorDeclaration {
     Family(FontFamily),
     Src(Vec<Source>),
+    UnicodeRange(Vec<UnicodeRange>),
+    Display(FontDisplay),
 }
 
 
@@ -90,6 +168,13 @@ impl<'a, 'b> DeclarationParser for FontFaceRuleParser<'a, 'b> {
                     parse_one_src(self.context, input)
                 }))))
             },
+            "unicode-range" => {
+                Ok(FontFaceDescriptorDeclaration::UnicodeRange(try!(
+                            input.parse_comma_separated(parse_one_unicode_range))))
+            },
+            "font-display" => {
+                Ok(FontFaceDescriptorDeclaration::Display(try!(FontDisplay::parse(input))))
+            },
             _ => Err(())
         }
     }
@@ -119,3 +204,17 @@ fn parse_one_src(context: &ParserContext, input: &mut Parser) -> Result<Source,
         format_hints: format_hints,
     }))
 }
+
+fn parse_one_unicode_range(input: &mut Parser) -> Result<UnicodeRange, ()> {
+    match try!(input.next()) {
+        // Ranges ending past the last code point are clamped, and reversed ranges are invalid.
+        // https://drafts.csswg.org/css-fonts/#unicode-range-desc
+        Token::UnicodeRange(start, end) if start <= end && start <= 0x10FFFF => {
+            Ok(UnicodeRange {
+                start: start,
+                end: cmp::min(end, 0x10FFFF),
+            })
+        }
+        _ => Err(())
+    }
+}
@@ -0,0 +1,77 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The fonts that the user prefers for text that does not name its own, by language. Embedders
+//! set them as preferences:
+//!
+//!   * `font.name.<generic>.<language group>`, such as `font.name.serif.ja`, is the family that
+//!     a generic family maps to in text of that language group, and `font.name.<generic>` the
+//!     family that it maps to in any other text.
+//!   * `font.size.<language group>` and `font.size` are the sizes, in px, of the text of root
+//!     elements that do not set `font-size`, and so of the `rem` unit.
+//!
+//! The language of an element is that of its `lang` attribute or of its closest ancestor with
+//! one.
+
+use app_units::Au;
+use std::ascii::AsciiExt;
+use string_cache::Atom;
+use util::prefs::{PrefValue, get_pref};
+
+/// The group of languages that share preferences that a language tag belongs to: its primary
+/// subtag, lowercased. Chinese is split by script instead, as `zh-cn` for simplified Chinese,
+/// and `zh-tw` and `zh-hk` for the traditional Chinese of Taiwan and of Hong Kong.
+pub fn language_group(language_tag: &str) -> String {
+    let mut subtags = language_tag.split(|c| c == '-' || c == '_');
+    let language = subtags.next().unwrap_or("").to_ascii_lowercase();
+    if language != "zh" {
+        return language
+    }
+    for subtag in subtags {
+        match &*subtag.to_ascii_lowercase() {
+            "hans" | "cn" | "sg" => return "zh-cn".to_owned(),
+            "hant" | "tw" => return "zh-tw".to_owned(),
+            "hk" | "mo" => return "zh-hk".to_owned(),
+            _ => {}
+        }
+    }
+    language
+}
+
+/// The family that the user prefers for `generic` in text of the given language, if they set
+/// one for its language group or for every language.
+pub fn preferred_family(generic: &str, language_tag: Option<&str>) -> Option<Atom> {
+    let name = format!("font.name.{}", generic);
+    if let Some(language_tag) = language_tag {
+        let preference = get_pref(&format!("{}.{}", name, language_group(language_tag)));
+        if let Some(family) = preference.as_string() {
+            if !family.is_empty() {
+                return Some(Atom::from(family))
+            }
+        }
+    }
+    match get_pref(&name).as_string() {
+        Some(family) if !family.is_empty() => Some(Atom::from(family)),
+        _ => None,
+    }
+}
+
+/// The size that the user prefers for text of the given language, if they set one for its
+/// language group or for every language.
+pub fn preferred_size(language_tag: Option<&str>) -> Option<Au> {
+    if let Some(language_tag) = language_tag {
+        let preference = get_pref(&format!("font.size.{}", language_group(language_tag)));
+        if let Some(size) = size_from_preference(&preference) {
+            return Some(size)
+        }
+    }
+    size_from_preference(&get_pref("font.size"))
+}
+
+fn size_from_preference(preference: &PrefValue) -> Option<Au> {
+    match *preference {
+        PrefValue::Number(px) if px > 0. => Some(Au::from_f32_px(px as f32)),
+        _ => None,
+    }
+}
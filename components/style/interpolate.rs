@@ -0,0 +1,1133 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Interpolation between computed values, which transitions and the animations that script
+//! creates use to compute the values of the properties that they animate at each point in time.
+//!
+//! https://drafts.csswg.org/css-transitions/#animatable-types
+
+use app_units::Au;
+use cssparser::{Color, RGBA};
+use euclid::size::Size2D;
+use properties::longhands::background_position::computed_value::T as BackgroundPosition;
+use properties::longhands::border_spacing::computed_value::T as BorderSpacing;
+use properties::longhands::box_shadow::computed_value::BoxShadow;
+use properties::longhands::box_shadow::computed_value::T as BoxShadowList;
+use properties::longhands::clip::computed_value::ClipRect;
+use properties::longhands::font_weight::computed_value::T as FontWeight;
+use properties::longhands::line_height::computed_value::T as LineHeight;
+use properties::longhands::perspective_origin::computed_value::T as PerspectiveOrigin;
+use properties::longhands::text_shadow::computed_value::T as TextShadowList;
+use properties::longhands::text_shadow::computed_value::TextShadow;
+use properties::longhands::transform::computed_value::ComputedMatrix;
+use properties::longhands::transform::computed_value::ComputedOperation as TransformOperation;
+use properties::longhands::transform::computed_value::T as TransformList;
+use properties::longhands::transform_origin::computed_value::T as TransformOrigin;
+use properties::longhands::vertical_align::computed_value::T as VerticalAlign;
+use properties::longhands::visibility::computed_value::T as Visibility;
+use properties::longhands::z_index::computed_value::T as ZIndex;
+use std::cmp::{Ordering, max};
+use std::iter::repeat;
+use values::CSSFloat;
+use values::computed::{Angle, BorderRadiusSize, CalcLengthOrPercentage, LengthOrNone};
+use values::computed::{LengthOrPercentage, LengthOrPercentageOrAuto, LengthOrPercentageOrNone};
+
+/// A computed value that can be animated by interpolating between two of its values.
+pub trait Interpolate: Sized {
+    /// Returns the value `time` of the way from `self` to `other`, where `time` is usually
+    /// between 0 and 1, or `None` if the two values cannot be interpolated.
+    fn interpolate(&self, other: &Self, time: f64) -> Option<Self>;
+}
+
+impl Interpolate for Au {
+    #[inline]
+    fn interpolate(&self, other: &Au, time: f64) -> Option<Au> {
+        Some(Au((self.0 as f64 + (other.0 as f64 - self.0 as f64) * time).round() as i32))
+    }
+}
+
+impl <T> Interpolate for Option<T> where T: Interpolate {
+    #[inline]
+    fn interpolate(&self, other: &Option<T>, time: f64) -> Option<Option<T>> {
+        match (self, other) {
+            (&Some(ref this), &Some(ref other)) => {
+                this.interpolate(other, time).and_then(|value| {
+                    Some(Some(value))
+                })
+            }
+            (_, _) => None
+        }
+    }
+}
+
+impl Interpolate for f32 {
+    #[inline]
+    fn interpolate(&self, other: &f32, time: f64) -> Option<f32> {
+        Some(((*self as f64) + ((*other as f64) - (*self as f64)) * time) as f32)
+    }
+}
+
+impl Interpolate for f64 {
+    #[inline]
+    fn interpolate(&self, other: &f64, time: f64) -> Option<f64> {
+        Some(*self + (*other - *self) * time)
+    }
+}
+
+impl Interpolate for i32 {
+    #[inline]
+    fn interpolate(&self, other: &i32, time: f64) -> Option<i32> {
+        let a = *self as f64;
+        let b = *other as f64;
+        Some((a + (b - a) * time).round() as i32)
+    }
+}
+
+impl Interpolate for Angle {
+    #[inline]
+    fn interpolate(&self, other: &Angle, time: f64) -> Option<Angle> {
+        self.radians().interpolate(&other.radians(), time).map(Angle)
+    }
+}
+
+impl Interpolate for Visibility {
+    #[inline]
+    fn interpolate(&self, other: &Visibility, time: f64)
+                   -> Option<Visibility> {
+        match (*self, *other) {
+            (Visibility::visible, _) | (_, Visibility::visible) => {
+                if time >= 0.0 && time <= 1.0 {
+                    Some(Visibility::visible)
+                } else if time < 0.0 {
+                    Some(*self)
+                } else {
+                    Some(*other)
+                }
+            }
+            (_, _) => None,
+        }
+    }
+}
+
+impl Interpolate for ZIndex {
+    #[inline]
+    fn interpolate(&self, other: &ZIndex, time: f64)
+                   -> Option<ZIndex> {
+        match (*self, *other) {
+            (ZIndex::Number(ref this),
+             ZIndex::Number(ref other)) => {
+                this.interpolate(other, time).and_then(|value| {
+                    Some(ZIndex::Number(value))
+                })
+            }
+            (_, _) => None,
+        }
+    }
+}
+
+impl Interpolate for VerticalAlign {
+    #[inline]
+    fn interpolate(&self, other: &VerticalAlign, time: f64)
+                   -> Option<VerticalAlign> {
+        match (*self, *other) {
+            (VerticalAlign::LengthOrPercentage(LengthOrPercentage::Length(ref this)),
+             VerticalAlign::LengthOrPercentage(LengthOrPercentage::Length(ref other))) => {
+                this.interpolate(other, time).and_then(|value| {
+                    Some(VerticalAlign::LengthOrPercentage(LengthOrPercentage::Length(value)))
+                })
+            }
+            (_, _) => None,
+        }
+    }
+}
+
+impl Interpolate for BorderSpacing {
+    #[inline]
+    fn interpolate(&self, other: &BorderSpacing, time: f64)
+                   -> Option<BorderSpacing> {
+        self.horizontal.interpolate(&other.horizontal, time).and_then(|horizontal| {
+            self.vertical.interpolate(&other.vertical, time).and_then(|vertical| {
+                Some(BorderSpacing { horizontal: horizontal, vertical: vertical })
+            })
+        })
+    }
+}
+
+impl Interpolate for RGBA {
+    #[inline]
+    fn interpolate(&self, other: &RGBA, time: f64) -> Option<RGBA> {
+        match (self.red.interpolate(&other.red, time),
+               self.green.interpolate(&other.green, time),
+               self.blue.interpolate(&other.blue, time),
+               self.alpha.interpolate(&other.alpha, time)) {
+            (Some(red), Some(green), Some(blue), Some(alpha)) => {
+                Some(RGBA { red: red, green: green, blue: blue, alpha: alpha })
+            }
+            (_, _, _, _) => None
+        }
+    }
+}
+
+impl Interpolate for Color {
+    #[inline]
+    fn interpolate(&self, other: &Color, time: f64) -> Option<Color> {
+        match (*self, *other) {
+            (Color::RGBA(ref this), Color::RGBA(ref other)) => {
+                this.interpolate(other, time).and_then(|value| {
+                    Some(Color::RGBA(value))
+                })
+            }
+            (_, _) => None,
+        }
+    }
+}
+
+impl Interpolate for CalcLengthOrPercentage {
+    #[inline]
+    fn interpolate(&self, other: &CalcLengthOrPercentage, time: f64)
+                   -> Option<CalcLengthOrPercentage> {
+        Some(CalcLengthOrPercentage {
+            length: self.length().interpolate(&other.length(), time),
+            percentage: self.percentage().interpolate(&other.percentage(), time),
+        })
+    }
+}
+
+impl Interpolate for LengthOrPercentage {
+    #[inline]
+    fn interpolate(&self, other: &LengthOrPercentage, time: f64)
+                   -> Option<LengthOrPercentage> {
+        match (*self, *other) {
+            (LengthOrPercentage::Length(ref this),
+             LengthOrPercentage::Length(ref other)) => {
+                this.interpolate(other, time).and_then(|value| {
+                    Some(LengthOrPercentage::Length(value))
+                })
+            }
+            (LengthOrPercentage::Percentage(ref this),
+             LengthOrPercentage::Percentage(ref other)) => {
+                this.interpolate(other, time).and_then(|value| {
+                    Some(LengthOrPercentage::Percentage(value))
+                })
+            }
+            (this, other) => {
+                let this: CalcLengthOrPercentage = From::from(this);
+                let other: CalcLengthOrPercentage = From::from(other);
+                this.interpolate(&other, time).and_then(|value| {
+                    Some(LengthOrPercentage::Calc(value))
+                })
+            }
+        }
+    }
+}
+
+impl Interpolate for LengthOrPercentageOrAuto {
+    #[inline]
+    fn interpolate(&self, other: &LengthOrPercentageOrAuto, time: f64)
+                   -> Option<LengthOrPercentageOrAuto> {
+        match (*self, *other) {
+            (LengthOrPercentageOrAuto::Length(ref this),
+             LengthOrPercentageOrAuto::Length(ref other)) => {
+                this.interpolate(other, time).and_then(|value| {
+                    Some(LengthOrPercentageOrAuto::Length(value))
+                })
+            }
+            (LengthOrPercentageOrAuto::Percentage(ref this),
+             LengthOrPercentageOrAuto::Percentage(ref other)) => {
+                this.interpolate(other, time).and_then(|value| {
+                    Some(LengthOrPercentageOrAuto::Percentage(value))
+                })
+            }
+            (LengthOrPercentageOrAuto::Auto, LengthOrPercentageOrAuto::Auto) => {
+                Some(LengthOrPercentageOrAuto::Auto)
+            }
+            (this, other) => {
+                let this: Option<CalcLengthOrPercentage> = From::from(this);
+                let other: Option<CalcLengthOrPercentage> = From::from(other);
+                this.interpolate(&other, time).unwrap_or(None).and_then(|value| {
+                    Some(LengthOrPercentageOrAuto::Calc(value))
+                })
+            }
+        }
+    }
+}
+
+impl Interpolate for LengthOrPercentageOrNone {
+    #[inline]
+    fn interpolate(&self, other: &LengthOrPercentageOrNone, time: f64)
+                   -> Option<LengthOrPercentageOrNone> {
+        match (*self, *other) {
+            (LengthOrPercentageOrNone::Length(ref this),
+             LengthOrPercentageOrNone::Length(ref other)) => {
+                this.interpolate(other, time).and_then(|value| {
+                    Some(LengthOrPercentageOrNone::Length(value))
+                })
+            }
+            (LengthOrPercentageOrNone::Percentage(ref this),
+             LengthOrPercentageOrNone::Percentage(ref other)) => {
+                this.interpolate(other, time).and_then(|value| {
+                    Some(LengthOrPercentageOrNone::Percentage(value))
+                })
+            }
+            (LengthOrPercentageOrNone::None, LengthOrPercentageOrNone::None) => {
+                Some(LengthOrPercentageOrNone::None)
+            }
+            (_, _) => None,
+        }
+    }
+}
+
+impl Interpolate for LineHeight {
+    #[inline]
+    fn interpolate(&self, other: &LineHeight, time: f64)
+                   -> Option<LineHeight> {
+        match (*self, *other) {
+            (LineHeight::Length(ref this),
+             LineHeight::Length(ref other)) => {
+                this.interpolate(other, time).and_then(|value| {
+                    Some(LineHeight::Length(value))
+                })
+            }
+            (LineHeight::Number(ref this),
+             LineHeight::Number(ref other)) => {
+                this.interpolate(other, time).and_then(|value| {
+                    Some(LineHeight::Number(value))
+                })
+            }
+            (LineHeight::Normal, LineHeight::Normal) => {
+                Some(LineHeight::Normal)
+            }
+            (_, _) => None,
+        }
+    }
+}
+
+/// http://dev.w3.org/csswg/css-transitions/#animtype-font-weight
+impl Interpolate for FontWeight {
+    #[inline]
+    fn interpolate(&self, other: &FontWeight, time: f64)
+                   -> Option<FontWeight> {
+        let a = (*self as u32) as f64;
+        let b = (*other as u32) as f64;
+        let weight = a + (b - a) * time;
+        Some(if weight < 150. {
+            FontWeight::Weight100
+        } else if weight < 250. {
+            FontWeight::Weight200
+        } else if weight < 350. {
+            FontWeight::Weight300
+        } else if weight < 450. {
+            FontWeight::Weight400
+        } else if weight < 550. {
+            FontWeight::Weight500
+        } else if weight < 650. {
+            FontWeight::Weight600
+        } else if weight < 750. {
+            FontWeight::Weight700
+        } else if weight < 850. {
+            FontWeight::Weight800
+        } else {
+            FontWeight::Weight900
+        })
+    }
+}
+
+impl Interpolate for ClipRect {
+    #[inline]
+    fn interpolate(&self, other: &ClipRect, time: f64)
+                   -> Option<ClipRect> {
+        match (self.top.interpolate(&other.top, time),
+               self.right.interpolate(&other.right, time),
+               self.bottom.interpolate(&other.bottom, time),
+               self.left.interpolate(&other.left, time)) {
+            (Some(top), Some(right), Some(bottom), Some(left)) => {
+                Some(ClipRect { top: top, right: right, bottom: bottom, left: left })
+            },
+            (_, _, _, _) => None,
+        }
+    }
+}
+
+impl Interpolate for BackgroundPosition {
+    #[inline]
+    fn interpolate(&self, other: &BackgroundPosition, time: f64)
+                   -> Option<BackgroundPosition> {
+        match (self.horizontal.interpolate(&other.horizontal, time),
+               self.vertical.interpolate(&other.vertical, time)) {
+            (Some(horizontal), Some(vertical)) => {
+                Some(BackgroundPosition { horizontal: horizontal, vertical: vertical })
+            },
+            (_, _) => None,
+        }
+    }
+}
+
+impl Interpolate for TextShadow {
+    #[inline]
+    fn interpolate(&self, other: &TextShadow, time: f64)
+                   -> Option<TextShadow> {
+        match (self.offset_x.interpolate(&other.offset_x, time),
+               self.offset_y.interpolate(&other.offset_y, time),
+               self.blur_radius.interpolate(&other.blur_radius, time),
+               self.color.interpolate(&other.color, time)) {
+            (Some(offset_x), Some(offset_y), Some(blur_radius), Some(color)) => {
+                Some(TextShadow { offset_x: offset_x, offset_y: offset_y, blur_radius: blur_radius, color: color })
+            },
+            (_, _, _, _) => None,
+        }
+    }
+}
+
+impl Interpolate for TextShadowList {
+    #[inline]
+    fn interpolate(&self, other: &TextShadowList, time: f64)
+                   -> Option<TextShadowList> {
+        let zero = TextShadow {
+            offset_x: Au(0),
+            offset_y: Au(0),
+            blur_radius: Au(0),
+            color: Color::RGBA(RGBA {
+                red: 0.0, green: 0.0, blue: 0.0, alpha: 0.0
+            })
+        };
+
+        let interpolate_each = |(a, b): (&TextShadow, &TextShadow)| {
+            a.interpolate(b, time).unwrap()
+        };
+
+        Some(TextShadowList(match self.0.len().cmp(&other.0.len()) {
+            Ordering::Less => self.0.iter().chain(repeat(&zero)).zip(other.0.iter()).map(interpolate_each).collect(),
+            _ => self.0.iter().zip(other.0.iter().chain(repeat(&zero))).map(interpolate_each).collect(),
+        }))
+    }
+}
+
+impl Interpolate for BoxShadow {
+    #[inline]
+    fn interpolate(&self, other: &BoxShadow, time: f64) -> Option<BoxShadow> {
+        // Inner and outer shadows cannot be interpolated into each other.
+        if self.inset != other.inset {
+            return None
+        }
+        match (self.offset_x.interpolate(&other.offset_x, time),
+               self.offset_y.interpolate(&other.offset_y, time),
+               self.blur_radius.interpolate(&other.blur_radius, time),
+               self.spread_radius.interpolate(&other.spread_radius, time),
+               self.color.interpolate(&other.color, time)) {
+            (Some(offset_x), Some(offset_y), Some(blur_radius), Some(spread_radius),
+             Some(color)) => {
+                Some(BoxShadow {
+                    offset_x: offset_x,
+                    offset_y: offset_y,
+                    blur_radius: blur_radius,
+                    spread_radius: spread_radius,
+                    color: color,
+                    inset: self.inset,
+                })
+            }
+            (_, _, _, _, _) => None,
+        }
+    }
+}
+
+/// The shorter list is padded with transparent shadows that have no offset, blur or spread, and
+/// are inner shadows where the other list has inner shadows.
+/// https://drafts.csswg.org/css-transitions/#animtype-shadow-list
+impl Interpolate for BoxShadowList {
+    #[inline]
+    fn interpolate(&self, other: &BoxShadowList, time: f64) -> Option<BoxShadowList> {
+        let zero = |inset| {
+            BoxShadow {
+                offset_x: Au(0),
+                offset_y: Au(0),
+                blur_radius: Au(0),
+                spread_radius: Au(0),
+                color: Color::RGBA(RGBA {
+                    red: 0.0, green: 0.0, blue: 0.0, alpha: 0.0
+                }),
+                inset: inset,
+            }
+        };
+
+        let mut result = Vec::with_capacity(max(self.0.len(), other.0.len()));
+        for i in 0..max(self.0.len(), other.0.len()) {
+            let shadow = match (self.0.get(i), other.0.get(i)) {
+                (Some(from), Some(to)) => from.interpolate(to, time),
+                (Some(from), None) => from.interpolate(&zero(from.inset), time),
+                (None, Some(to)) => zero(to.inset).interpolate(to, time),
+                (None, None) => unreachable!(),
+            };
+            match shadow {
+                Some(shadow) => result.push(shadow),
+                None => return None,
+            }
+        }
+        Some(BoxShadowList(result))
+    }
+}
+
+impl Interpolate for BorderRadiusSize {
+    #[inline]
+    fn interpolate(&self, other: &BorderRadiusSize, time: f64) -> Option<BorderRadiusSize> {
+        match (self.0.width.interpolate(&other.0.width, time),
+               self.0.height.interpolate(&other.0.height, time)) {
+            (Some(width), Some(height)) => Some(BorderRadiusSize(Size2D::new(width, height))),
+            (_, _) => None,
+        }
+    }
+}
+
+impl Interpolate for LengthOrNone {
+    #[inline]
+    fn interpolate(&self, other: &LengthOrNone, time: f64) -> Option<LengthOrNone> {
+        match (*self, *other) {
+            (LengthOrNone::Length(ref this), LengthOrNone::Length(ref other)) => {
+                this.interpolate(other, time).map(LengthOrNone::Length)
+            }
+            (_, _) => None,
+        }
+    }
+}
+
+impl Interpolate for TransformOrigin {
+    #[inline]
+    fn interpolate(&self, other: &TransformOrigin, time: f64) -> Option<TransformOrigin> {
+        match (self.horizontal.interpolate(&other.horizontal, time),
+               self.vertical.interpolate(&other.vertical, time),
+               self.depth.interpolate(&other.depth, time)) {
+            (Some(horizontal), Some(vertical), Some(depth)) => {
+                Some(TransformOrigin { horizontal: horizontal, vertical: vertical, depth: depth })
+            }
+            (_, _, _) => None,
+        }
+    }
+}
+
+impl Interpolate for PerspectiveOrigin {
+    #[inline]
+    fn interpolate(&self, other: &PerspectiveOrigin, time: f64) -> Option<PerspectiveOrigin> {
+        match (self.horizontal.interpolate(&other.horizontal, time),
+               self.vertical.interpolate(&other.vertical, time)) {
+            (Some(horizontal), Some(vertical)) => {
+                Some(PerspectiveOrigin { horizontal: horizontal, vertical: vertical })
+            }
+            (_, _) => None,
+        }
+    }
+}
+
+/// Check if it's possible to do a direct numerical interpolation
+/// between these two transform lists.
+/// http://dev.w3.org/csswg/css-transforms/#transform-transform-animation
+fn can_interpolate_list(from_list: &[TransformOperation],
+                        to_list: &[TransformOperation]) -> bool {
+    // Lists must be equal length
+    if from_list.len() != to_list.len() {
+        return false;
+    }
+
+    // Each transform operation must match primitive type in other list
+    for (from, to) in from_list.iter().zip(to_list) {
+        match (from, to) {
+            (&TransformOperation::Matrix(..), &TransformOperation::Matrix(..)) |
+            (&TransformOperation::Skew(..), &TransformOperation::Skew(..)) |
+            (&TransformOperation::Translate(..), &TransformOperation::Translate(..)) |
+            (&TransformOperation::Scale(..), &TransformOperation::Scale(..)) |
+            (&TransformOperation::Rotate(..), &TransformOperation::Rotate(..)) |
+            (&TransformOperation::Perspective(..), &TransformOperation::Perspective(..)) => {}
+            _ => {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+/// Interpolate two transform lists.
+/// http://dev.w3.org/csswg/css-transforms/#interpolation-of-transforms
+fn interpolate_transform_list(from_list: &[TransformOperation],
+                              to_list: &[TransformOperation],
+                              time: f64) -> TransformList {
+    let mut result = vec!();
+
+    if can_interpolate_list(from_list, to_list) {
+        for (from, to) in from_list.iter().zip(to_list) {
+            match (from, to) {
+                (&TransformOperation::Matrix(..),
+                 &TransformOperation::Matrix(..)) => {
+                    result.push(interpolate_operations(from, to, time));
+                }
+                (&TransformOperation::Skew(fx, fy),
+                 &TransformOperation::Skew(tx, ty)) => {
+                    let ix = fx.interpolate(&tx, time).unwrap();
+                    let iy = fy.interpolate(&ty, time).unwrap();
+                    result.push(TransformOperation::Skew(ix, iy));
+                }
+                (&TransformOperation::Translate(fx, fy, fz),
+                 &TransformOperation::Translate(tx, ty, tz)) => {
+                    let ix = fx.interpolate(&tx, time).unwrap();
+                    let iy = fy.interpolate(&ty, time).unwrap();
+                    let iz = fz.interpolate(&tz, time).unwrap();
+                    result.push(TransformOperation::Translate(ix, iy, iz));
+                }
+                (&TransformOperation::Scale(fx, fy, fz),
+                 &TransformOperation::Scale(tx, ty, tz)) => {
+                    let ix = fx.interpolate(&tx, time).unwrap();
+                    let iy = fy.interpolate(&ty, time).unwrap();
+                    let iz = fz.interpolate(&tz, time).unwrap();
+                    result.push(TransformOperation::Scale(ix, iy, iz));
+                }
+                (&TransformOperation::Rotate(fx, fy, fz, fa),
+                 &TransformOperation::Rotate(tx, ty, tz, ta)) => {
+                    // Rotations about the same axis interpolate their angles, and others are
+                    // interpolated as matrices.
+                    let from_axis = normalize_axis(fx, fy, fz);
+                    let to_axis = normalize_axis(tx, ty, tz);
+                    if from_axis.is_some() && from_axis == to_axis {
+                        let ia = fa.interpolate(&ta, time).unwrap();
+                        result.push(TransformOperation::Rotate(fx, fy, fz, ia));
+                    } else {
+                        result.push(interpolate_operations(from, to, time));
+                    }
+                }
+                (&TransformOperation::Perspective(..),
+                 &TransformOperation::Perspective(..)) => {
+                    result.push(interpolate_operations(from, to, time));
+                }
+                _ => {
+                    // This should be unreachable due to the can_interpolate_list() call.
+                    unreachable!();
+                }
+            }
+        }
+    } else {
+        // Lists that do not match up are interpolated as a whole, as matrices.
+        // http://dev.w3.org/csswg/css-transforms/#interpolation-of-transforms
+        match (Matrix::from_operations(from_list), Matrix::from_operations(to_list)) {
+            (Some(from), Some(to)) => result.push(interpolate_matrices(from, to, time)),
+            // Translations by percentages depend on the size of the box, which is not known
+            // here, so such lists flip from one to the other halfway through instead.
+            _ => result.extend_from_slice(if time < 0.5 { from_list } else { to_list }),
+        }
+    }
+
+    TransformList(Some(result))
+}
+
+/// Build an equivalent 'identity transform function list' based
+/// on an existing transform list.
+/// http://dev.w3.org/csswg/css-transforms/#none-transform-animation
+fn build_identity_transform_list(list: &[TransformOperation]) -> Vec<TransformOperation> {
+    let mut result = vec!();
+
+    for operation in list {
+        match *operation {
+            TransformOperation::Matrix(..) => {
+                let identity = ComputedMatrix::identity();
+                result.push(TransformOperation::Matrix(identity));
+            }
+            TransformOperation::Skew(..) => {
+                result.push(TransformOperation::Skew(Angle(0.0), Angle(0.0)));
+            }
+            TransformOperation::Translate(..) => {
+                result.push(TransformOperation::Translate(LengthOrPercentage::zero(),
+                                                          LengthOrPercentage::zero(),
+                                                          Au(0)));
+            }
+            TransformOperation::Scale(..) => {
+                result.push(TransformOperation::Scale(1.0, 1.0, 1.0));
+            }
+            TransformOperation::Rotate(..) => {
+                result.push(TransformOperation::Rotate(0.0, 0.0, 1.0, Angle(0.0)));
+            }
+            TransformOperation::Perspective(..) => {
+                // http://dev.w3.org/csswg/css-transforms/#identity-transform-function
+                let identity = ComputedMatrix::identity();
+                result.push(TransformOperation::Matrix(identity));
+            }
+        }
+    }
+
+    result
+}
+
+impl Interpolate for TransformList {
+    #[inline]
+    fn interpolate(&self, other: &TransformList, time: f64) -> Option<TransformList> {
+        // http://dev.w3.org/csswg/css-transforms/#interpolation-of-transforms
+        let result = match (&self.0, &other.0) {
+            (&Some(ref from_list), &Some(ref to_list)) => {
+                // Two lists of transforms
+                interpolate_transform_list(from_list, &to_list, time)
+            }
+            (&Some(ref from_list), &None) => {
+                // http://dev.w3.org/csswg/css-transforms/#none-transform-animation
+                let to_list = build_identity_transform_list(from_list);
+                interpolate_transform_list(from_list, &to_list, time)
+            }
+            (&None, &Some(ref to_list)) => {
+                // http://dev.w3.org/csswg/css-transforms/#none-transform-animation
+                let from_list = build_identity_transform_list(to_list);
+                interpolate_transform_list(&from_list, to_list, time)
+            }
+            _ => {
+                // http://dev.w3.org/csswg/css-transforms/#none-none-animation
+                TransformList(None)
+            }
+        };
+
+        Some(result)
+    }
+}
+
+/// Interpolates two operations of a transform list as matrices.
+fn interpolate_operations(from: &TransformOperation, to: &TransformOperation, time: f64)
+                          -> TransformOperation {
+    let from_list = [from.clone()];
+    let to_list = [to.clone()];
+    match (Matrix::from_operations(&from_list), Matrix::from_operations(&to_list)) {
+        (Some(from_matrix), Some(to_matrix)) => {
+            interpolate_matrices(from_matrix, to_matrix, time)
+        }
+        _ => if time < 0.5 { from.clone() } else { to.clone() },
+    }
+}
+
+/// Interpolates two matrices by decomposing them. Matrices that cannot be decomposed, such as
+/// singular ones, flip from one to the other halfway through.
+fn interpolate_matrices(from: Matrix, to: Matrix, time: f64) -> TransformOperation {
+    let matrix = match (from.decompose(), to.decompose()) {
+        (Some(from), Some(to)) => from.interpolate(&to, time).recompose(),
+        _ => if time < 0.5 { from } else { to },
+    };
+    TransformOperation::Matrix(matrix.to_computed())
+}
+
+/// Returns the unit vector in the direction of the given axis of rotation, or `None` if it has
+/// no direction.
+fn normalize_axis(x: CSSFloat, y: CSSFloat, z: CSSFloat) -> Option<[f64; 3]> {
+    let (x, y, z) = (x as f64, y as f64, z as f64);
+    let length = (x * x + y * y + z * z).sqrt();
+    if length == 0.0 {
+        None
+    } else {
+        Some([x / length, y / length, z / length])
+    }
+}
+
+/// A 4x4 matrix, in the same convention as `ComputedMatrix`: points are row vectors that are
+/// multiplied by the matrix, so translations are in the last row.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Matrix([[f64; 4]; 4]);
+
+impl Matrix {
+    fn identity() -> Matrix {
+        Matrix([[1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0]])
+    }
+
+    fn from_computed(m: &ComputedMatrix) -> Matrix {
+        Matrix([[m.m11 as f64, m.m12 as f64, m.m13 as f64, m.m14 as f64],
+                [m.m21 as f64, m.m22 as f64, m.m23 as f64, m.m24 as f64],
+                [m.m31 as f64, m.m32 as f64, m.m33 as f64, m.m34 as f64],
+                [m.m41 as f64, m.m42 as f64, m.m43 as f64, m.m44 as f64]])
+    }
+
+    fn to_computed(&self) -> ComputedMatrix {
+        let m = &self.0;
+        ComputedMatrix {
+            m11: m[0][0] as CSSFloat, m12: m[0][1] as CSSFloat,
+            m13: m[0][2] as CSSFloat, m14: m[0][3] as CSSFloat,
+            m21: m[1][0] as CSSFloat, m22: m[1][1] as CSSFloat,
+            m23: m[1][2] as CSSFloat, m24: m[1][3] as CSSFloat,
+            m31: m[2][0] as CSSFloat, m32: m[2][1] as CSSFloat,
+            m33: m[2][2] as CSSFloat, m34: m[2][3] as CSSFloat,
+            m41: m[3][0] as CSSFloat, m42: m[3][1] as CSSFloat,
+            m43: m[3][2] as CSSFloat, m44: m[3][3] as CSSFloat,
+        }
+    }
+
+    /// Returns the matrix of a transform list, as layout builds it, or `None` if it translates
+    /// by a percentage of the size of the box.
+    fn from_operations(operations: &[TransformOperation]) -> Option<Matrix> {
+        let mut result = Matrix::identity();
+        for operation in operations {
+            let mut matrix = Matrix::identity();
+            match *operation {
+                TransformOperation::Matrix(ref m) => matrix = Matrix::from_computed(m),
+                TransformOperation::Skew(ax, ay) => {
+                    matrix.0[0][1] = (ay.radians() as f64).tan();
+                    matrix.0[1][0] = (ax.radians() as f64).tan();
+                }
+                TransformOperation::Translate(tx, ty, tz) => {
+                    match (length_in_px(tx), length_in_px(ty)) {
+                        (Some(tx), Some(ty)) => {
+                            matrix.0[3][0] = tx;
+                            matrix.0[3][1] = ty;
+                        }
+                        (_, _) => return None,
+                    }
+                    matrix.0[3][2] = tz.to_f64_px();
+                }
+                TransformOperation::Scale(sx, sy, sz) => {
+                    matrix.0[0][0] = sx as f64;
+                    matrix.0[1][1] = sy as f64;
+                    matrix.0[2][2] = sz as f64;
+                }
+                TransformOperation::Rotate(ax, ay, az, angle) => {
+                    if let Some(axis) = normalize_axis(ax, ay, az) {
+                        let (x, y, z) = (axis[0], axis[1], axis[2]);
+                        let half_angle = angle.radians() as f64 / 2.0;
+                        let sc = half_angle.sin() * half_angle.cos();
+                        let sq = half_angle.sin() * half_angle.sin();
+                        matrix.0[0][0] = 1.0 - 2.0 * (y * y + z * z) * sq;
+                        matrix.0[0][1] = 2.0 * (x * y * sq + z * sc);
+                        matrix.0[0][2] = 2.0 * (x * z * sq - y * sc);
+                        matrix.0[1][0] = 2.0 * (x * y * sq - z * sc);
+                        matrix.0[1][1] = 1.0 - 2.0 * (x * x + z * z) * sq;
+                        matrix.0[1][2] = 2.0 * (y * z * sq + x * sc);
+                        matrix.0[2][0] = 2.0 * (x * z * sq + y * sc);
+                        matrix.0[2][1] = 2.0 * (y * z * sq - x * sc);
+                        matrix.0[2][2] = 1.0 - 2.0 * (x * x + y * y) * sq;
+                    }
+                }
+                TransformOperation::Perspective(d) => {
+                    // Perspectives that are not positive have no effect.
+                    let d = d.to_f64_px();
+                    if d > 0.0 {
+                        matrix.0[2][3] = -1.0 / d;
+                    }
+                }
+            }
+            // Later operations apply to points first.
+            result = matrix.multiply(&result);
+        }
+        Some(result)
+    }
+
+    fn multiply(&self, other: &Matrix) -> Matrix {
+        let mut result = [[0.0; 4]; 4];
+        for i in 0..4 {
+            for j in 0..4 {
+                result[i][j] = (0..4).fold(0.0, |sum, k| sum + self.0[i][k] * other.0[k][j]);
+            }
+        }
+        Matrix(result)
+    }
+
+    fn transpose(&self) -> Matrix {
+        let mut result = [[0.0; 4]; 4];
+        for i in 0..4 {
+            for j in 0..4 {
+                result[i][j] = self.0[j][i];
+            }
+        }
+        Matrix(result)
+    }
+
+    /// The determinant of the matrix without the given row and column.
+    fn minor(&self, row: usize, column: usize) -> f64 {
+        let mut m = [[0.0; 3]; 3];
+        for (i, source_row) in (0..4).filter(|&i| i != row).enumerate() {
+            for (j, source_column) in (0..4).filter(|&j| j != column).enumerate() {
+                m[i][j] = self.0[source_row][source_column];
+            }
+        }
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1]) -
+        m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0]) +
+        m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    fn cofactor(&self, row: usize, column: usize) -> f64 {
+        let minor = self.minor(row, column);
+        if (row + column) % 2 == 0 { minor } else { -minor }
+    }
+
+    fn determinant(&self) -> f64 {
+        (0..4).fold(0.0, |sum, j| sum + self.0[0][j] * self.cofactor(0, j))
+    }
+
+    fn inverse(&self) -> Option<Matrix> {
+        let determinant = self.determinant();
+        if determinant == 0.0 {
+            return None
+        }
+        let mut result = [[0.0; 4]; 4];
+        for i in 0..4 {
+            for j in 0..4 {
+                result[i][j] = self.cofactor(j, i) / determinant;
+            }
+        }
+        Some(Matrix(result))
+    }
+
+    /// Decomposes the matrix into the transforms that it is made of, or returns `None` if it
+    /// cannot be decomposed.
+    /// https://drafts.csswg.org/css-transforms/#decomposing-a-3d-matrix
+    fn decompose(&self) -> Option<DecomposedMatrix> {
+        let mut matrix = self.0;
+
+        // Normalize the matrix.
+        if matrix[3][3] == 0.0 {
+            return None
+        }
+        let scale_factor = matrix[3][3];
+        for row in matrix.iter_mut() {
+            for value in row.iter_mut() {
+                *value /= scale_factor;
+            }
+        }
+
+        // The perspective matrix is used to solve for perspective, and also provides an easy
+        // way to test for singularity of the upper 3x3 part.
+        let mut perspective_matrix = Matrix(matrix);
+        for i in 0..3 {
+            perspective_matrix.0[i][3] = 0.0;
+        }
+        perspective_matrix.0[3][3] = 1.0;
+        let inverse_perspective_matrix = match perspective_matrix.inverse() {
+            Some(inverse) => inverse,
+            None => return None,
+        };
+
+        // First, isolate perspective.
+        let perspective = if matrix[0][3] != 0.0 || matrix[1][3] != 0.0 || matrix[2][3] != 0.0 {
+            let right_hand_side = [matrix[0][3], matrix[1][3], matrix[2][3], matrix[3][3]];
+            let transposed = inverse_perspective_matrix.transpose();
+            let mut perspective = [0.0; 4];
+            for i in 0..4 {
+                perspective[i] = (0..4).fold(0.0, |sum, j| {
+                    sum + right_hand_side[j] * transposed.0[j][i]
+                });
+            }
+            perspective
+        } else {
+            [0.0, 0.0, 0.0, 1.0]
+        };
+
+        // Next take care of translation.
+        let translate = [matrix[3][0], matrix[3][1], matrix[3][2]];
+
+        // Now get scale and shear.
+        let mut row = [[0.0; 3]; 3];
+        for i in 0..3 {
+            for j in 0..3 {
+                row[i][j] = matrix[i][j];
+            }
+        }
+        let mut scale = [0.0; 3];
+        let mut skew = [0.0; 3];
+
+        // Compute the X scale factor and normalize the first row.
+        scale[0] = length(&row[0]);
+        row[0] = normalize(&row[0]);
+
+        // Compute the XY shear factor and make the second row orthogonal to the first.
+        skew[0] = dot(&row[0], &row[1]);
+        row[1] = combine(&row[1], &row[0], 1.0, -skew[0]);
+
+        // Now, compute the Y scale and normalize the second row.
+        scale[1] = length(&row[1]);
+        row[1] = normalize(&row[1]);
+        skew[0] /= scale[1];
+
+        // Compute the XZ and YZ shears, and orthogonalize the third row.
+        skew[1] = dot(&row[0], &row[2]);
+        row[2] = combine(&row[2], &row[0], 1.0, -skew[1]);
+        skew[2] = dot(&row[1], &row[2]);
+        row[2] = combine(&row[2], &row[1], 1.0, -skew[2]);
+
+        // Next, get the Z scale and normalize the third row.
+        scale[2] = length(&row[2]);
+        row[2] = normalize(&row[2]);
+        skew[1] /= scale[2];
+        skew[2] /= scale[2];
+
+        // At this point, the matrix in rows is orthonormal. Check for a coordinate system flip:
+        // if the determinant is -1, negate the matrix and the scaling factors.
+        if dot(&row[0], &cross(&row[1], &row[2])) < 0.0 {
+            for i in 0..3 {
+                scale[i] = -scale[i];
+                for j in 0..3 {
+                    row[i][j] = -row[i][j];
+                }
+            }
+        }
+
+        // Now, get the rotations out.
+        let mut quaternion = [
+            0.5 * (1.0 + row[0][0] - row[1][1] - row[2][2]).max(0.0).sqrt(),
+            0.5 * (1.0 - row[0][0] + row[1][1] - row[2][2]).max(0.0).sqrt(),
+            0.5 * (1.0 - row[0][0] - row[1][1] + row[2][2]).max(0.0).sqrt(),
+            0.5 * (1.0 + row[0][0] + row[1][1] + row[2][2]).max(0.0).sqrt(),
+        ];
+        if row[2][1] > row[1][2] {
+            quaternion[0] = -quaternion[0];
+        }
+        if row[0][2] > row[2][0] {
+            quaternion[1] = -quaternion[1];
+        }
+        if row[1][0] > row[0][1] {
+            quaternion[2] = -quaternion[2];
+        }
+
+        Some(DecomposedMatrix {
+            translate: translate,
+            scale: scale,
+            skew: skew,
+            perspective: perspective,
+            quaternion: quaternion,
+        })
+    }
+}
+
+fn length_in_px(length: LengthOrPercentage) -> Option<f64> {
+    match length {
+        LengthOrPercentage::Length(length) => Some(length.to_f64_px()),
+        LengthOrPercentage::Percentage(percentage) if percentage == 0.0 => Some(0.0),
+        LengthOrPercentage::Calc(calc) if calc.percentage().is_none() => {
+            Some(calc.length().to_f64_px())
+        }
+        _ => None,
+    }
+}
+
+fn length(vector: &[f64; 3]) -> f64 {
+    dot(vector, vector).sqrt()
+}
+
+fn normalize(vector: &[f64; 3]) -> [f64; 3] {
+    let length = length(vector);
+    if length == 0.0 {
+        return *vector
+    }
+    [vector[0] / length, vector[1] / length, vector[2] / length]
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [a[1] * b[2] - a[2] * b[1],
+     a[2] * b[0] - a[0] * b[2],
+     a[0] * b[1] - a[1] * b[0]]
+}
+
+fn combine(a: &[f64; 3], b: &[f64; 3], ascl: f64, bscl: f64) -> [f64; 3] {
+    [a[0] * ascl + b[0] * bscl,
+     a[1] * ascl + b[1] * bscl,
+     a[2] * ascl + b[2] * bscl]
+}
+
+/// The transforms that a matrix is made of.
+/// https://drafts.csswg.org/css-transforms/#decomposing-a-3d-matrix
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct DecomposedMatrix {
+    translate: [f64; 3],
+    scale: [f64; 3],
+    skew: [f64; 3],
+    perspective: [f64; 4],
+    quaternion: [f64; 4],
+}
+
+impl DecomposedMatrix {
+    /// https://drafts.csswg.org/css-transforms/#interpolation-of-decomposed-3d-matrix-values
+    fn interpolate(&self, other: &DecomposedMatrix, time: f64) -> DecomposedMatrix {
+        fn lerp3(a: &[f64; 3], b: &[f64; 3], time: f64) -> [f64; 3] {
+            [a[0] + (b[0] - a[0]) * time,
+             a[1] + (b[1] - a[1]) * time,
+             a[2] + (b[2] - a[2]) * time]
+        }
+
+        let mut perspective = [0.0; 4];
+        for i in 0..4 {
+            perspective[i] =
+                self.perspective[i] + (other.perspective[i] - self.perspective[i]) * time;
+        }
+
+        // Rotations are spherically interpolated.
+        let mut quaternion = self.quaternion;
+        let product = (0..4).fold(0.0, |sum, i| {
+            sum + self.quaternion[i] * other.quaternion[i]
+        }).max(-1.0).min(1.0);
+        if product < 1.0 {
+            let theta = product.acos();
+            let w = (time * theta).sin() / (1.0 - product * product).sqrt();
+            for i in 0..4 {
+                quaternion[i] = self.quaternion[i] * ((time * theta).cos() - product * w) +
+                                other.quaternion[i] * w;
+            }
+        }
+
+        DecomposedMatrix {
+            translate: lerp3(&self.translate, &other.translate, time),
+            scale: lerp3(&self.scale, &other.scale, time),
+            skew: lerp3(&self.skew, &other.skew, time),
+            perspective: perspective,
+            quaternion: quaternion,
+        }
+    }
+
+    /// https://drafts.csswg.org/css-transforms/#recomposing-to-a-3d-matrix
+    fn recompose(&self) -> Matrix {
+        let mut matrix = Matrix::identity();
+
+        // Apply perspective.
+        for i in 0..4 {
+            matrix.0[i][3] = self.perspective[i];
+        }
+
+        // Apply translation.
+        for i in 0..4 {
+            for j in 0..3 {
+                matrix.0[3][i] += self.translate[j] * matrix.0[j][i];
+            }
+        }
+
+        // Apply rotation.
+        let (x, y, z, w) =
+            (self.quaternion[0], self.quaternion[1], self.quaternion[2], self.quaternion[3]);
+        let mut rotation = Matrix::identity();
+        rotation.0[0][0] = 1.0 - 2.0 * (y * y + z * z);
+        rotation.0[0][1] = 2.0 * (x * y + z * w);
+        rotation.0[0][2] = 2.0 * (x * z - y * w);
+        rotation.0[1][0] = 2.0 * (x * y - z * w);
+        rotation.0[1][1] = 1.0 - 2.0 * (x * x + z * z);
+        rotation.0[1][2] = 2.0 * (y * z + x * w);
+        rotation.0[2][0] = 2.0 * (x * z + y * w);
+        rotation.0[2][1] = 2.0 * (y * z - x * w);
+        rotation.0[2][2] = 1.0 - 2.0 * (x * x + y * y);
+        matrix = rotation.multiply(&matrix);
+
+        // Apply skew.
+        let mut temp = Matrix::identity();
+        if self.skew[2] != 0.0 {
+            temp.0[2][1] = self.skew[2];
+            matrix = temp.multiply(&matrix);
+        }
+        if self.skew[1] != 0.0 {
+            temp.0[2][1] = 0.0;
+            temp.0[2][0] = self.skew[1];
+            matrix = temp.multiply(&matrix);
+        }
+        if self.skew[0] != 0.0 {
+            temp.0[2][0] = 0.0;
+            temp.0[1][0] = self.skew[0];
+            matrix = temp.multiply(&matrix);
+        }
+
+        // Apply scale.
+        for i in 0..3 {
+            for j in 0..4 {
+                matrix.0[i][j] *= self.scale[i];
+            }
+        }
+
+        matrix
+    }
+}
@@ -54,16 +54,22 @@ extern crate util;
 pub mod animation;
 pub mod attr;
 pub mod bezier;
+pub mod cascade_snapshot;
 pub mod context;
+pub mod counter_style;
 pub mod custom_properties;
 pub mod data;
 pub mod dom;
+pub mod easing;
 pub mod element_state;
 pub mod error_reporting;
 pub mod font_face;
+pub mod font_preferences;
+pub mod interpolate;
 pub mod logical_geometry;
 pub mod matching;
 pub mod media_queries;
+pub mod page;
 pub mod parallel;
 pub mod parser;
 pub mod restyle_hints;
@@ -71,6 +77,7 @@ pub mod selector_impl;
 pub mod selector_matching;
 pub mod sequential;
 pub mod servo;
+pub mod stylesheet_loader;
 pub mod stylesheets;
 pub mod traversal;
 #[macro_use]
@@ -4,7 +4,7 @@
 
 #![allow(unsafe_code)]
 
-use animation::{self, Animation};
+use animation::{self, Animation, AnimationTarget};
 use context::SharedStyleContext;
 use data::PrivateStyleData;
 use dom::{TElement, TNode, TRestyleDamage};
@@ -22,6 +22,7 @@ use std::slice::Iter;
 use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
 use string_cache::{Atom, Namespace};
+use style_traits::forced_colors::ColorRole;
 use util::arc_ptr_eq;
 use util::cache::{LRUCache, SimpleHashCache};
 use util::opts;
@@ -375,11 +376,18 @@ trait PrivateMatchMethods: TNode
                                     &mut ApplicableDeclarationsCache<Self::ConcreteComputedValues>,
                                    new_animations_sender: &Mutex<Sender<Animation>>,
                                    shareable: bool,
-                                   animate_properties: bool)
+                                   animation_target: Option<AnimationTarget>,
+                                   color_role: ColorRole)
                                    -> (Self::ConcreteRestyleDamage, Arc<Self::ConcreteComputedValues>) {
+        let forced_colors = context.stylist.forced_colors().map(|palette| (palette, color_role));
         let mut cacheable = true;
-        if animate_properties {
-            cacheable = !self.update_animations_for_cascade(context, &mut style) && cacheable;
+        let mut running_animations = vec![];
+        if let Some(ref target) = animation_target {
+            let (had_animations, animations) = self.update_animations_for_cascade(context,
+                                                                                  target,
+                                                                                  &mut style);
+            cacheable = !had_animations && cacheable;
+            running_animations = animations;
         }
 
         let mut this_style;
@@ -391,20 +399,24 @@ trait PrivateMatchMethods: TNode
                     Some(ref style) => Some(&**style),
                 };
                 let (the_style, is_cacheable) = cascade(context.viewport_size,
+                                                        context.stylist.text_zoom(),
                                                         applicable_declarations,
                                                         shareable,
                                                         Some(&***parent_style),
                                                         cached_computed_values,
+                                                        forced_colors,
                                                         context.error_reporter.clone());
                 cacheable = cacheable && is_cacheable;
                 this_style = the_style
             }
             None => {
                 let (the_style, is_cacheable) = cascade(context.viewport_size,
+                                                        context.stylist.text_zoom(),
                                                         applicable_declarations,
                                                         shareable,
                                                         None,
                                                         None,
+                                                        forced_colors,
                                                         context.error_reporter.clone());
                 cacheable = cacheable && is_cacheable;
                 this_style = the_style
@@ -412,17 +424,33 @@ trait PrivateMatchMethods: TNode
         };
 
         // Trigger transitions if necessary. This will reset `this_style` back to its old value if
-        // it did trigger a transition.
-        if animate_properties {
+        // it did trigger a transition, and cancel the running ones that no longer apply.
+        if let Some(ref target) = animation_target {
             if let Some(ref style) = style {
                 let animations_started =
                     animation::start_transitions_if_applicable::<Self::ConcreteComputedValues>(
                         new_animations_sender,
-                        self.opaque(),
+                        target,
                         &**style,
-                        &mut this_style);
+                        &mut this_style,
+                        running_animations);
                 cacheable = cacheable && !animations_started
             }
+
+            // Apply the animations that script created, which also makes the style uncacheable.
+            // Script only animates elements themselves.
+            let keyframes_animations = context.keyframes_animations.read().unwrap();
+            match keyframes_animations.get(&target.node) {
+                Some(animations) if target.pseudo.is_none() => {
+                    for animation in animations {
+                        animation.update(this_style.as_servo_mut(),
+                                         context.viewport_size,
+                                         context.animation_time)
+                    }
+                    cacheable = false
+                }
+                _ => {}
+            }
         }
 
         // Calculate style difference.
@@ -441,19 +469,19 @@ trait PrivateMatchMethods: TNode
 
     fn update_animations_for_cascade(&self,
                                      context: &SharedStyleContext<<Self::ConcreteElement as Element>::Impl>,
+                                     target: &AnimationTarget,
                                      style: &mut Option<&mut Arc<Self::ConcreteComputedValues>>)
-                                     -> bool {
+                                     -> (bool, Vec<Animation>) {
         let style = match *style {
-            None => return false,
+            None => return (false, vec![]),
             Some(ref mut style) => style,
         };
 
         // Finish any expired transitions.
-        let this_opaque = self.opaque();
         let had_animations_to_expire;
         {
             let all_expired_animations = context.expired_animations.read().unwrap();
-            let animations_to_expire = all_expired_animations.get(&this_opaque);
+            let animations_to_expire = all_expired_animations.get(target);
             had_animations_to_expire = animations_to_expire.is_some();
             if let Some(ref animations) = animations_to_expire {
                 for animation in *animations {
@@ -463,25 +491,28 @@ trait PrivateMatchMethods: TNode
         }
 
         if had_animations_to_expire {
-            context.expired_animations.write().unwrap().remove(&this_opaque);
+            context.expired_animations.write().unwrap().remove(target);
         }
 
-        // Merge any running transitions into the current style, and cancel them.
+        // Merge any running transitions into the current style, and take them out of the running
+        // animations, for the new style to keep, reverse or cancel them.
         let had_running_animations = context.running_animations
                                             .read()
                                             .unwrap()
-                                            .get(&this_opaque)
+                                            .get(target)
                                             .is_some();
+        let mut running_animations = vec![];
         if had_running_animations {
             let mut all_running_animations = context.running_animations.write().unwrap();
-            for running_animation in all_running_animations.get(&this_opaque).unwrap() {
+            running_animations = all_running_animations.remove(target).unwrap();
+            for running_animation in &running_animations {
                 animation::update_style_for_animation::<Self::ConcreteComputedValues,
-                                                        Self::ConcreteRestyleDamage>(running_animation, style, None);
+                                                        Self::ConcreteRestyleDamage>(
+                    running_animation, style, context.animation_time, None);
             }
-            all_running_animations.remove(&this_opaque);
         }
 
-        had_animations_to_expire || had_running_animations
+        (had_animations_to_expire || had_running_animations, running_animations)
     }
 }
 
@@ -669,6 +700,10 @@ pub trait MatchMethods : TNode {
                                                           &*cloned_parent_style);
             data.style = Some(cloned_parent_style);
         } else {
+            let color_role = match self.as_element() {
+                Some(ref element) if element.is_link() => ColorRole::Link,
+                _ => ColorRole::Text,
+            };
             damage = {
                 let mut data_ref = self.mutate_data().unwrap();
                 let mut data = &mut *data_ref;
@@ -680,7 +715,8 @@ pub trait MatchMethods : TNode {
                     applicable_declarations_cache,
                     new_animations_sender,
                     applicable_declarations.normal_shareable,
-                    true);
+                    Some(AnimationTarget::new(self.opaque(), None)),
+                    color_role);
 
                 data.style = Some(final_style);
 
@@ -690,6 +726,12 @@ pub trait MatchMethods : TNode {
 
 
                     if !applicable_declarations_for_this_pseudo.is_empty() {
+                        let animated_pseudo =
+                            <Self::ConcreteElement as Element>::Impl::animated_pseudo_element(
+                                &pseudo);
+                        let animation_target = animated_pseudo.map(|pseudo| {
+                            AnimationTarget::new(self.opaque(), Some(pseudo))
+                        });
                         let (new_damage, style) = self.cascade_node_pseudo_element(
                             context,
                             Some(data.style.as_ref().unwrap()),
@@ -698,13 +740,35 @@ pub trait MatchMethods : TNode {
                             applicable_declarations_cache,
                             new_animations_sender,
                             false,
-                            false);
+                            animation_target,
+                            ColorRole::Text);
                         data.per_pseudo.insert(pseudo, style);
 
                         damage = damage | new_damage;
                     }
                 });
 
+                // Lazily cascaded pseudo-elements inherit from the new style, so they are
+                // computed again the next time that they are asked for, by the boxes that are
+                // built again for them. Styles that were only computed ahead of time have no
+                // boxes to build again.
+                <Self::ConcreteElement as Element>::Impl::each_pseudo_element(|pseudo| {
+                    let cascade_type =
+                        <Self::ConcreteElement as Element>::Impl::pseudo_element_cascade_type(
+                            &pseudo);
+                    if cascade_type.is_lazy() && data.per_pseudo.remove(&pseudo).is_some() {
+                        match data.speculative_pseudos.iter().position(|p| *p == pseudo) {
+                            Some(index) => {
+                                data.speculative_pseudos.swap_remove(index);
+                            }
+                            None => {
+                                damage = damage |
+                                         Self::ConcreteRestyleDamage::rebuild_and_reflow();
+                            }
+                        }
+                    }
+                });
+
                 damage
             };
 
@@ -712,7 +776,10 @@ pub trait MatchMethods : TNode {
             // scope first.
             self.set_can_be_fragmented(parent.map_or(false, |p| {
                 p.can_be_fragmented() ||
-                parent_style.as_ref().unwrap().is_multicol()
+                parent_style.as_ref().unwrap().is_multicol() ||
+                // The root element of a paginated document is fragmented into pages.
+                (opts::get().paginate && p.as_element().is_some() &&
+                 p.parent_node().map_or(false, |grandparent| grandparent.as_document().is_some()))
             }));
         }
 
@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use animation::ReducedMotion;
 use app_units::Au;
 use cssparser::{Delimiter, Parser, Token};
 use euclid::size::{Size2D, TypedSize2D};
@@ -62,6 +63,9 @@ impl<T: Ord> Range<T> {
 pub enum Expression {
     /// http://dev.w3.org/csswg/mediaqueries-3/#width
     Width(Range<specified::Length>),
+    /// Whether the query is for `reduce` rather than `no-preference`.
+    /// https://drafts.csswg.org/mediaqueries-5/#prefers-reduced-motion
+    PrefersReducedMotion(bool),
 }
 
 /// http://dev.w3.org/csswg/mediaqueries-3/#media0
@@ -139,6 +143,14 @@ impl Expression {
                 "max-width" => {
                     Ok(Expression::Width(Range::Max(try!(specified::Length::parse_non_negative(input)))))
                 },
+                "prefers-reduced-motion" => {
+                    let value = try!(input.expect_ident());
+                    match_ignore_ascii_case! { value,
+                        "reduce" => Ok(Expression::PrefersReducedMotion(true)),
+                        "no-preference" => Ok(Expression::PrefersReducedMotion(false)),
+                        _ => Err(())
+                    }
+                },
                 _ => Err(())
             }
         })
@@ -225,6 +237,9 @@ impl MediaQueryList {
                 match *expression {
                     Expression::Width(ref value) =>
                         value.to_computed_range(viewport_size).evaluate(viewport_size.width),
+                    Expression::PrefersReducedMotion(reduce) => {
+                        reduce == (ReducedMotion::from_prefs() != ReducedMotion::NoPreference)
+                    }
                 }
             });
 
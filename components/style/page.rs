@@ -0,0 +1,392 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The `@page` rule, which sets the size and the margins of the page boxes that a paginated
+//! document is laid out into.
+//!
+//! https://drafts.csswg.org/css-page/#at-page-rule
+
+use app_units::Au;
+use cssparser::{AtRuleParser, DeclarationListParser, DeclarationParser, Parser, Token};
+use euclid::{SideOffsets2D, Size2D};
+use parser::{ParserContext, log_css_error};
+use stylesheets::SourceRange;
+use values::specified::{Length, LengthOrPercentageOrAuto, Percentage};
+
+/// Whether a page is on the left or on the right of a spread.
+#[derive(Clone, Copy, Debug, HeapSizeOf, PartialEq, Eq)]
+pub enum PageSide {
+    Left,
+    Right,
+}
+
+/// A page selector, made of pseudo-classes only: named pages need the `page` property, which is
+/// not supported.
+/// https://drafts.csswg.org/css-page/#page-selectors
+#[derive(Clone, Copy, Debug, HeapSizeOf, PartialEq, Eq)]
+pub struct PageSelector {
+    /// Whether the selector has `:first`.
+    pub first: bool,
+    /// The side that `:left` or `:right` select, if any.
+    pub side: Option<PageSide>,
+}
+
+impl PageSelector {
+    /// Whether the selector matches the page at `index`. The first page is a right page, as
+    /// pages progress from left to right.
+    pub fn matches(&self, index: usize) -> bool {
+        let side = if index % 2 == 0 { PageSide::Right } else { PageSide::Left };
+        (!self.first || index == 0) && self.side.map_or(true, |s| s == side)
+    }
+
+    /// `:first` is more specific than `:left` and `:right`.
+    /// https://drafts.csswg.org/css-page/#cascading-and-page-context
+    pub fn specificity(&self) -> u32 {
+        ((self.first as u32) << 1) | (self.side.is_some() as u32)
+    }
+}
+
+#[derive(Clone, Copy, Debug, HeapSizeOf, PartialEq, Eq)]
+pub enum PageOrientation {
+    Portrait,
+    Landscape,
+}
+
+/// The value of the `size` descriptor.
+/// https://drafts.csswg.org/css-page/#page-size-prop
+#[derive(Clone, Copy, Debug, HeapSizeOf, PartialEq, Eq)]
+pub enum PageSize {
+    Auto,
+    /// The size of the target medium, turned to the given orientation.
+    Orientation(PageOrientation),
+    /// A width and a height.
+    Size(Au, Au),
+}
+
+/// A page margin. Only absolute lengths are accepted, since page boxes have no font or
+/// viewport for other lengths to be relative to.
+#[derive(Clone, Copy, Debug, HeapSizeOf, PartialEq)]
+pub enum PageLength {
+    Length(Au),
+    Percentage(f32),
+    Auto,
+}
+
+impl PageLength {
+    /// Resolves a margin along an axis of the page whose length is `basis`. `auto` margins are
+    /// zero.
+    fn resolve(&self, basis: Au) -> Au {
+        match *self {
+            PageLength::Length(length) => length,
+            PageLength::Percentage(percentage) => basis.scale_by(percentage),
+            PageLength::Auto => Au(0),
+        }
+    }
+}
+
+#[derive(Clone, Debug, HeapSizeOf, PartialEq)]
+pub struct PageRule {
+    pub selectors: Vec<PageSelector>,
+    /// The descriptors below are `None` when they are not specified.
+    pub size: Option<PageSize>,
+    pub margin_top: Option<PageLength>,
+    pub margin_right: Option<PageLength>,
+    pub margin_bottom: Option<PageLength>,
+    pub margin_left: Option<PageLength>,
+    /// The whole rule, from its at-keyword to its closing brace.
+    pub source_range: SourceRange,
+}
+
+/// The size and the margins of a page, which lays its contents out in the page area inside its
+/// margins.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PageBox {
+    pub size: Size2D<Au>,
+    pub margins: SideOffsets2D<Au>,
+}
+
+impl PageBox {
+    /// The size of the page area.
+    pub fn page_area_size(&self) -> Size2D<Au> {
+        Size2D::new(self.size.width - self.margins.horizontal(),
+                    self.size.height - self.margins.vertical())
+    }
+}
+
+/// Cascades the `rules` that match the page at `index` into its page box. Rules with more
+/// specific selectors win, then those that come last. `default_size` is the size of the target
+/// medium.
+pub fn page_box(rules: &[PageRule], index: usize, default_size: Size2D<Au>) -> PageBox {
+    let mut matching: Vec<(u32, &PageRule)> = rules.iter().filter_map(|rule| {
+        rule.selectors.iter()
+                      .filter(|selector| selector.matches(index))
+                      .map(|selector| selector.specificity())
+                      .max()
+                      .map(|specificity| (specificity, rule))
+    }).collect();
+    // The sort is stable, so source order breaks ties.
+    matching.sort_by_key(|&(specificity, _)| specificity);
+
+    let mut size = PageSize::Auto;
+    let mut margins = [PageLength::Auto; 4];
+    for &(_, rule) in &matching {
+        if let Some(rule_size) = rule.size {
+            size = rule_size
+        }
+        let rule_margins = [rule.margin_top, rule.margin_right, rule.margin_bottom,
+                            rule.margin_left];
+        for (margin, rule_margin) in margins.iter_mut().zip(rule_margins.iter()) {
+            if let Some(rule_margin) = *rule_margin {
+                *margin = rule_margin
+            }
+        }
+    }
+
+    let (width, height) = match size {
+        PageSize::Auto => (default_size.width, default_size.height),
+        PageSize::Orientation(orientation) => {
+            oriented(default_size.width, default_size.height, orientation)
+        }
+        PageSize::Size(width, height) => (width, height),
+    };
+    PageBox {
+        size: Size2D::new(width, height),
+        margins: SideOffsets2D::new(margins[0].resolve(height),
+                                    margins[1].resolve(width),
+                                    margins[2].resolve(height),
+                                    margins[3].resolve(width)),
+    }
+}
+
+/// Swaps `width` and `height` if need be for the page to have `orientation`.
+fn oriented(width: Au, height: Au, orientation: PageOrientation) -> (Au, Au) {
+    let is_landscape = width > height;
+    if is_landscape == (orientation == PageOrientation::Landscape) {
+        (width, height)
+    } else {
+        (height, width)
+    }
+}
+
+/// Parses the page selectors in the prelude of a `@page` rule. A rule without any selects all
+/// pages.
+pub fn parse_page_selectors(input: &mut Parser) -> Result<Vec<PageSelector>, ()> {
+    if input.is_exhausted() {
+        return Ok(vec![PageSelector { first: false, side: None }])
+    }
+    input.parse_comma_separated(parse_page_selector)
+}
+
+fn parse_page_selector(input: &mut Parser) -> Result<PageSelector, ()> {
+    let mut selector = PageSelector { first: false, side: None };
+    try!(input.expect_colon());
+    loop {
+        // Pseudo-classes follow their colon without whitespace.
+        let name = match try!(input.next_including_whitespace()) {
+            Token::Ident(name) => name,
+            _ => return Err(())
+        };
+        let side = match_ignore_ascii_case! { name,
+            "first" => {
+                selector.first = true;
+                None
+            },
+            "left" => Some(PageSide::Left),
+            "right" => Some(PageSide::Right),
+            _ => return Err(())
+        };
+        if let Some(side) = side {
+            // `:left:right` would never match.
+            if selector.side.map_or(false, |s| s != side) {
+                return Err(())
+            }
+            selector.side = Some(side);
+        }
+        if input.try(|input| {
+            match input.next_including_whitespace() {
+                Ok(Token::Colon) => Ok(()),
+                _ => Err(())
+            }
+        }).is_err() {
+            return Ok(selector)
+        }
+    }
+}
+
+/// Parses the descriptors of a `@page` rule.
+pub fn parse_page_block(context: &ParserContext, selectors: Vec<PageSelector>, input: &mut Parser)
+                        -> Result<PageRule, ()> {
+    let mut rule = PageRule {
+        selectors: selectors,
+        size: None,
+        margin_top: None,
+        margin_right: None,
+        margin_bottom: None,
+        margin_left: None,
+        source_range: SourceRange::default(),
+    };
+    let mut iter = DeclarationListParser::new(input, PageRuleParser);
+    while let Some(declaration) = iter.next() {
+        match declaration {
+            Err(range) => {
+                let pos = range.start;
+                let message = format!("Unsupported @page descriptor declaration: '{}'",
+                                      iter.input.slice(range));
+                log_css_error(iter.input, pos, &*message, context);
+            }
+            Ok(declarations) => {
+                for declaration in declarations {
+                    match declaration {
+                        PageDescriptorDeclaration::Size(value) => rule.size = Some(value),
+                        PageDescriptorDeclaration::MarginTop(value) => {
+                            rule.margin_top = Some(value)
+                        }
+                        PageDescriptorDeclaration::MarginRight(value) => {
+                            rule.margin_right = Some(value)
+                        }
+                        PageDescriptorDeclaration::MarginBottom(value) => {
+                            rule.margin_bottom = Some(value)
+                        }
+                        PageDescriptorDeclaration::MarginLeft(value) => {
+                            rule.margin_left = Some(value)
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(rule)
+}
+
+enum PageDescriptorDeclaration {
+    Size(PageSize),
+    MarginTop(PageLength),
+    MarginRight(PageLength),
+    MarginBottom(PageLength),
+    MarginLeft(PageLength),
+}
+
+struct PageRuleParser;
+
+/// Default methods reject all at rules, page-margin boxes included.
+impl AtRuleParser for PageRuleParser {
+    type Prelude = ();
+    type AtRule = Vec<PageDescriptorDeclaration>;
+}
+
+impl DeclarationParser for PageRuleParser {
+    type Declaration = Vec<PageDescriptorDeclaration>;
+
+    fn parse_value(&self, name: &str, input: &mut Parser)
+                   -> Result<Vec<PageDescriptorDeclaration>, ()> {
+        match_ignore_ascii_case! { name,
+            "size" => Ok(vec![PageDescriptorDeclaration::Size(try!(parse_size(input)))]),
+            "margin" => {
+                // One to four values, as for the `margin` property.
+                let top = try!(parse_page_length(input));
+                let right = input.try(parse_page_length).unwrap_or(top);
+                let bottom = input.try(parse_page_length).unwrap_or(top);
+                let left = input.try(parse_page_length).unwrap_or(right);
+                Ok(vec![PageDescriptorDeclaration::MarginTop(top),
+                        PageDescriptorDeclaration::MarginRight(right),
+                        PageDescriptorDeclaration::MarginBottom(bottom),
+                        PageDescriptorDeclaration::MarginLeft(left)])
+            },
+            "margin-top" => {
+                Ok(vec![PageDescriptorDeclaration::MarginTop(try!(parse_page_length(input)))])
+            },
+            "margin-right" => {
+                Ok(vec![PageDescriptorDeclaration::MarginRight(try!(parse_page_length(input)))])
+            },
+            "margin-bottom" => {
+                Ok(vec![PageDescriptorDeclaration::MarginBottom(try!(parse_page_length(input)))])
+            },
+            "margin-left" => {
+                Ok(vec![PageDescriptorDeclaration::MarginLeft(try!(parse_page_length(input)))])
+            },
+            _ => Err(())
+        }
+    }
+}
+
+fn parse_page_length(input: &mut Parser) -> Result<PageLength, ()> {
+    match try!(LengthOrPercentageOrAuto::parse(input)) {
+        LengthOrPercentageOrAuto::Length(Length::Absolute(length)) => {
+            Ok(PageLength::Length(length))
+        }
+        LengthOrPercentageOrAuto::Percentage(Percentage(percentage)) => {
+            Ok(PageLength::Percentage(percentage))
+        }
+        LengthOrPercentageOrAuto::Auto => Ok(PageLength::Auto),
+        _ => Err(())
+    }
+}
+
+fn parse_absolute_length(input: &mut Parser) -> Result<Au, ()> {
+    match try!(Length::parse_non_negative(input)) {
+        Length::Absolute(length) => Ok(length),
+        _ => Err(())
+    }
+}
+
+fn parse_orientation(input: &mut Parser) -> Result<PageOrientation, ()> {
+    let ident = try!(input.expect_ident());
+    match_ignore_ascii_case! { ident,
+        "portrait" => Ok(PageOrientation::Portrait),
+        "landscape" => Ok(PageOrientation::Landscape),
+        _ => Err(())
+    }
+}
+
+/// Parses a page size keyword into the width and the height of the portrait page.
+/// https://drafts.csswg.org/css-page/#page-size
+fn parse_page_size_keyword(input: &mut Parser) -> Result<(Au, Au), ()> {
+    fn mm(width: f32, height: f32) -> (Au, Au) {
+        (Au::from_f32_px(width * 96. / 25.4), Au::from_f32_px(height * 96. / 25.4))
+    }
+    fn inches(width: f32, height: f32) -> (Au, Au) {
+        (Au::from_f32_px(width * 96.), Au::from_f32_px(height * 96.))
+    }
+
+    let ident = try!(input.expect_ident());
+    match_ignore_ascii_case! { ident,
+        "a5" => Ok(mm(148., 210.)),
+        "a4" => Ok(mm(210., 297.)),
+        "a3" => Ok(mm(297., 420.)),
+        "b5" => Ok(mm(176., 250.)),
+        "b4" => Ok(mm(250., 353.)),
+        "jis-b5" => Ok(mm(182., 257.)),
+        "jis-b4" => Ok(mm(257., 364.)),
+        "letter" => Ok(inches(8.5, 11.)),
+        "legal" => Ok(inches(8.5, 14.)),
+        "ledger" => Ok(inches(11., 17.)),
+        _ => Err(())
+    }
+}
+
+fn parse_size(input: &mut Parser) -> Result<PageSize, ()> {
+    if input.try(|input| input.expect_ident_matching("auto")).is_ok() {
+        return Ok(PageSize::Auto)
+    }
+    if let Ok(width) = input.try(parse_absolute_length) {
+        // A single length gives a square page.
+        let height = input.try(parse_absolute_length).unwrap_or(width);
+        return Ok(PageSize::Size(width, height))
+    }
+
+    // A keyword and an orientation, in either order.
+    let orientation = input.try(parse_orientation).ok();
+    let (width, height) = match input.try(parse_page_size_keyword) {
+        Ok(size) => size,
+        Err(()) => {
+            return orientation.map(PageSize::Orientation).ok_or(())
+        }
+    };
+    let orientation = match orientation {
+        Some(orientation) => orientation,
+        None => input.try(parse_orientation).unwrap_or(PageOrientation::Portrait),
+    };
+    let (width, height) = oriented(width, height, orientation);
+    Ok(PageSize::Size(width, height))
+}
@@ -78,7 +78,17 @@ fn top_down_dom<N, C>(unsafe_nodes: UnsafeNodeList,
         // Possibly enqueue the children.
         if child_count != 0 {
             for kid in node.children() {
-                discovered_child_nodes.push(kid.to_unsafe())
+                discovered_child_nodes.push(kid.to_unsafe());
+                // Hand out full chunks right away, so that idle workers can steal them while
+                // this one styles the rest of its nodes and walks back up from its leaves.
+                if discovered_child_nodes.len() == CHUNK_SIZE {
+                    let chunk = mem::replace(&mut discovered_child_nodes,
+                                             Vec::with_capacity(CHUNK_SIZE));
+                    proxy.push(WorkUnit {
+                        fun:  top_down_dom::<N, C>,
+                        data: (box chunk, unsafe_nodes.1),
+                    });
+                }
             }
         } else {
             // If there were no more children, start walking back up.
@@ -86,10 +96,10 @@ fn top_down_dom<N, C>(unsafe_nodes: UnsafeNodeList,
         }
     }
 
-    for chunk in discovered_child_nodes.chunks(CHUNK_SIZE) {
+    if !discovered_child_nodes.is_empty() {
         proxy.push(WorkUnit {
             fun:  top_down_dom::<N, C>,
-            data: (box chunk.iter().cloned().collect(), unsafe_nodes.1),
+            data: (box discovered_child_nodes, unsafe_nodes.1),
         });
     }
 }
@@ -47,7 +47,7 @@ class Keyword(object):
 class Longhand(object):
     def __init__(self, style_struct, name, derived_from=None, keyword=None,
                  predefined_type=None, custom_cascade=False, experimental=False, internal=False,
-                 need_clone=False, gecko_ffi_name=None):
+                 need_clone=False, gecko_ffi_name=None, logical=None):
         self.name = name
         self.keyword = keyword
         self.predefined_type = predefined_type
@@ -60,6 +60,9 @@ class Longhand(object):
         self.need_clone = need_clone
         self.gecko_ffi_name = gecko_ffi_name or "m" + self.camel_case
         self.derived_from = (derived_from or "").split()
+        # For logical properties, the pattern of the names of the physical properties that they
+        # map to, and the logical side or axis that picks one of them.
+        self.logical = logical
 
 
 class Shorthand(object):
@@ -136,7 +139,9 @@ class PropertiesData(object):
             return
 
         longand = Longhand(self.current_style_struct, name, **kwargs)
-        self.current_style_struct.longhands.append(longand)
+        # Logical properties have no computed values of their own.
+        if not longand.logical:
+            self.current_style_struct.longhands.append(longand)
         self.longhands.append(longand)
         self.longhands_by_name[name] = longand
 
@@ -73,7 +73,7 @@
                 }
                 _ => panic!("entered the wrong cascade_property() implementation"),
             };
-            % if not property.derived_from:
+            % if not property.derived_from and not property.logical:
                 if seen.get_${property.ident}() {
                     return
                 }
@@ -119,6 +119,16 @@
                                             cacheable,
                                             error_reporter);
                 % endif
+            % elif property.logical:
+                // The writing mode has been cascaded early, so the physical property that this
+                // one maps to is known, and is set as though it had been declared here.
+                let mode = ::properties::get_writing_mode(context.style().get_inheritedbox());
+                <%call expr="logical_match(property, 'mode')" args="physical">
+                    let physical =
+                        PropertyDeclaration::${physical.camel_case}(declared_value.clone());
+                    longhands::${physical.ident}::cascade_property(
+                        &physical, inherited_style, context, seen, cacheable, error_reporter);
+                </%call>
             % else:
                 // Do not allow stylesheets to set derived properties.
             % endif
@@ -158,6 +168,51 @@
     }
 </%def>
 
+## A logical property: one that is parsed like any other, but whose value is set at cascade time
+## on the physical property that it maps to in the writing mode of the element.
+## `physical_pattern` is the name of the physical properties with `%s` in place of the side or
+## the dimension, and `logical_side` is the logical side (such as `inline-start`) or axis (`inline`
+## or `block`) of the property.
+<%def name="logical_longhand(name, physical_pattern, logical_side, **kwargs)">
+    <%
+        if logical_side in ["inline", "block"]:
+            representative = physical_pattern % "width"
+        else:
+            representative = physical_pattern % "top"
+        representative = data.longhands_by_name[representative]
+    %>
+    <%call expr="longhand(name, logical=(physical_pattern, logical_side), **kwargs)">
+        pub use properties::longhands::${representative.ident}::{SpecifiedValue, computed_value};
+        pub use properties::longhands::${representative.ident}::{get_initial_value, parse};
+    </%call>
+</%def>
+
+## Matches the writing mode `mode` on the physical properties that the logical `property` maps
+## to, with the body called for each of them as `physical`.
+<%def name="logical_match(property, mode)">
+    <% physical_pattern, logical_side = property.logical %>
+    % if logical_side in ["inline", "block"]:
+        <%
+            horizontal, vertical = ["width", "height"]
+            if logical_side == "block":
+                horizontal, vertical = vertical, horizontal
+        %>
+        if ${mode}.is_vertical() {
+            ${caller.body(physical=data.longhands_by_name[physical_pattern % vertical])}
+        } else {
+            ${caller.body(physical=data.longhands_by_name[physical_pattern % horizontal])}
+        }
+    % else:
+        match ${mode}.${to_rust_ident(logical_side)}_physical_side() {
+            % for side in ["top", "right", "bottom", "left"]:
+                ::logical_geometry::PhysicalSide::${side.capitalize()} => {
+                    ${caller.body(physical=data.longhands_by_name[physical_pattern % side])}
+                }
+            % endfor
+        }
+    % endif
+</%def>
+
 <%def name="single_keyword(name, values, **kwargs)">
     <%call expr="single_keyword_computed(name, values, **kwargs)">
         use values::computed::ComputedValueAsSpecified;
@@ -257,6 +312,20 @@
     % endif
 </%def>
 
+## A shorthand for the logical properties of both sides of an axis, such as `margin-inline`.
+<%def name="logical_axis_shorthand(name, sub_property_pattern, axis, parser_function)">
+    <%self:shorthand name="${name}" sub_properties="${
+            ' '.join(sub_property_pattern % (axis + '-' + edge) for edge in ['start', 'end'])}">
+        use super::parse_start_and_end;
+        let _unused = context;
+        let (start, end) = try!(parse_start_and_end(input, ${parser_function}));
+        Ok(Longhands {
+            ${to_rust_ident(sub_property_pattern % (axis + '-start'))}: Some(start),
+            ${to_rust_ident(sub_property_pattern % (axis + '-end'))}: Some(end),
+        })
+    </%self:shorthand>
+</%def>
+
 <%def name="four_sides_shorthand(name, sub_property_pattern, parser_function)">
     <%self:shorthand name="${name}" sub_properties="${
             ' '.join(sub_property_pattern % side
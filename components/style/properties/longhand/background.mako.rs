@@ -13,7 +13,6 @@ ${helpers.predefined_type(
     use cssparser::ToCss;
     use std::fmt;
     use values::specified::Image;
-    use values::LocalToCss;
 
     pub mod computed_value {
         use values::computed;
@@ -25,9 +24,7 @@ ${helpers.predefined_type(
         fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
             match self.0 {
                 None => dest.write_str("none"),
-                Some(computed::Image::Url(ref url)) => url.to_css(dest),
-                Some(computed::Image::LinearGradient(ref gradient)) =>
-                    gradient.to_css(dest)
+                Some(ref image) => image.to_css(dest),
             }
         }
     }
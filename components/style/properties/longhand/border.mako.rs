@@ -17,8 +17,13 @@
     ${helpers.predefined_type("border-%s-style" % side, "BorderStyle", "specified::BorderStyle::none", need_clone=True)}
 % endfor
 
+// The sides share their values, so that logical properties can map to any of them.
 % for side in ["top", "right", "bottom", "left"]:
     <%helpers:longhand name="border-${side}-width">
+    % if side != "top":
+        pub use properties::longhands::border_top_width::{SpecifiedValue, computed_value};
+        pub use properties::longhands::border_top_width::{get_initial_value, parse};
+    % else:
         use app_units::Au;
         use cssparser::ToCss;
         use std::fmt;
@@ -52,9 +57,18 @@
                 self.0.to_computed_value(context)
             }
         }
+    % endif
     </%helpers:longhand>
 % endfor
 
+// https://drafts.csswg.org/css-logical-props/#border-properties
+% for side in ["block-start", "block-end", "inline-start", "inline-end"]:
+    % for property in ["color", "style", "width"]:
+        ${helpers.logical_longhand("border-%s-%s" % (side, property), "border-%%s-%s" % property,
+                                   side)}
+    % endfor
+% endfor
+
 // FIXME(#4126): when gfx supports painting it, make this Size2D<LengthOrPercentage>
 % for corner in ["top-left", "top-right", "bottom-right", "bottom-left"]:
     ${helpers.predefined_type("border-" + corner + "-radius", "BorderRadiusSize",
@@ -62,6 +76,456 @@
                               "parse")}
 % endfor
 
+<%helpers:longhand name="border-image-source">
+    use cssparser::ToCss;
+    use std::fmt;
+    use values::specified::Image;
+
+    pub mod computed_value {
+        use values::computed;
+        #[derive(Debug, Clone, PartialEq, HeapSizeOf)]
+        pub struct T(pub Option<computed::Image>);
+    }
+
+    #[derive(Debug, Clone, PartialEq, HeapSizeOf)]
+    pub struct SpecifiedValue(pub Option<Image>);
+
+    impl ToCss for SpecifiedValue {
+        fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+            match *self {
+                SpecifiedValue(Some(ref image)) => image.to_css(dest),
+                SpecifiedValue(None) => dest.write_str("none"),
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get_initial_value() -> computed_value::T {
+        computed_value::T(None)
+    }
+    pub fn parse(context: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue, ()> {
+        if input.try(|input| input.expect_ident_matching("none")).is_ok() {
+            Ok(SpecifiedValue(None))
+        } else {
+            Ok(SpecifiedValue(Some(try!(Image::parse(context, input)))))
+        }
+    }
+    impl ToComputedValue for SpecifiedValue {
+        type ComputedValue = computed_value::T;
+
+        #[inline]
+        fn to_computed_value<Cx: TContext>(&self, context: &Cx) -> computed_value::T {
+            match *self {
+                SpecifiedValue(None) => computed_value::T(None),
+                SpecifiedValue(Some(ref image)) =>
+                    computed_value::T(Some(image.to_computed_value(context))),
+            }
+        }
+    }
+</%helpers:longhand>
+
+/// Parses the one to four values of the `border-image-*` properties that have one per side,
+/// like `margin` does.
+pub fn parse_border_image_sides<T, F>(input: &mut Parser, parse_one: F) -> Result<[T; 4], ()>
+                                      where F: Fn(&mut Parser) -> Result<T, ()>, T: Copy {
+    let top = try!(parse_one(input));
+    let right = input.try(&parse_one).unwrap_or(top);
+    let bottom = input.try(&parse_one).unwrap_or(top);
+    let left = input.try(&parse_one).unwrap_or(right);
+    Ok([top, right, bottom, left])
+}
+
+<%helpers:longhand name="border-image-slice">
+    use cssparser::ToCss;
+    use std::fmt;
+    use values::computed::ComputedValueAsSpecified;
+
+    impl ComputedValueAsSpecified for SpecifiedValue {}
+    pub type SpecifiedValue = computed_value::T;
+
+    pub mod computed_value {
+        use cssparser::ToCss;
+        use std::fmt;
+        use values::CSSFloat;
+
+        /// How far from an edge of the image to slice it.
+        #[derive(Debug, Clone, Copy, PartialEq, HeapSizeOf)]
+        pub enum Offset {
+            /// A number of image pixels.
+            Number(CSSFloat),
+            /// A fraction of the size of the image.
+            Percentage(CSSFloat),
+        }
+
+        impl ToCss for Offset {
+            fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+                match *self {
+                    Offset::Number(number) => write!(dest, "{}", number),
+                    Offset::Percentage(percentage) => write!(dest, "{}%", percentage * 100.),
+                }
+            }
+        }
+
+        #[derive(Debug, Clone, Copy, PartialEq, HeapSizeOf)]
+        pub struct T {
+            pub top: Offset,
+            pub right: Offset,
+            pub bottom: Offset,
+            pub left: Offset,
+            /// Whether the middle of the image is drawn too.
+            pub fill: bool,
+        }
+
+        impl ToCss for T {
+            fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+                try!(self.top.to_css(dest));
+                try!(dest.write_str(" "));
+                try!(self.right.to_css(dest));
+                try!(dest.write_str(" "));
+                try!(self.bottom.to_css(dest));
+                try!(dest.write_str(" "));
+                try!(self.left.to_css(dest));
+                if self.fill {
+                    try!(dest.write_str(" fill"));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get_initial_value() -> computed_value::T {
+        let offset = computed_value::Offset::Percentage(1.0);
+        computed_value::T {
+            top: offset,
+            right: offset,
+            bottom: offset,
+            left: offset,
+            fill: false,
+        }
+    }
+
+    fn parse_offset(input: &mut Parser) -> Result<computed_value::Offset, ()> {
+        if let Ok(number) = input.try(specified::Number::parse_non_negative) {
+            return Ok(computed_value::Offset::Number(number.0))
+        }
+        match try!(specified::LengthOrPercentage::parse_non_negative(input)) {
+            specified::LengthOrPercentage::Percentage(percentage) => {
+                Ok(computed_value::Offset::Percentage(percentage.0))
+            }
+            _ => Err(()),
+        }
+    }
+
+    pub fn parse(_context: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue, ()> {
+        let mut fill = input.try(|input| input.expect_ident_matching("fill")).is_ok();
+        let offsets = try!(super::parse_border_image_sides(input, parse_offset));
+        if !fill {
+            fill = input.try(|input| input.expect_ident_matching("fill")).is_ok();
+        }
+        Ok(computed_value::T {
+            top: offsets[0],
+            right: offsets[1],
+            bottom: offsets[2],
+            left: offsets[3],
+            fill: fill,
+        })
+    }
+</%helpers:longhand>
+
+<%helpers:longhand name="border-image-width">
+    use cssparser::ToCss;
+    use std::fmt;
+
+    pub mod computed_value {
+        use values::CSSFloat;
+        use values::computed::LengthOrPercentage;
+
+        /// The width of one side of the border image.
+        #[derive(Debug, Clone, Copy, PartialEq, HeapSizeOf)]
+        pub enum Width {
+            /// A length, or a percentage of the size of the border image area.
+            LengthOrPercentage(LengthOrPercentage),
+            /// A multiple of the width of the border on that side.
+            Number(CSSFloat),
+            /// The size of the matching slice of the image.
+            Auto,
+        }
+
+        #[derive(Debug, Clone, Copy, PartialEq, HeapSizeOf)]
+        pub struct T {
+            pub top: Width,
+            pub right: Width,
+            pub bottom: Width,
+            pub left: Width,
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, HeapSizeOf)]
+    pub enum SpecifiedWidth {
+        LengthOrPercentage(specified::LengthOrPercentage),
+        Number(specified::Number),
+        Auto,
+    }
+
+    impl ToCss for SpecifiedWidth {
+        fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+            match *self {
+                SpecifiedWidth::LengthOrPercentage(ref value) => value.to_css(dest),
+                SpecifiedWidth::Number(ref number) => number.to_css(dest),
+                SpecifiedWidth::Auto => dest.write_str("auto"),
+            }
+        }
+    }
+
+    impl ToComputedValue for SpecifiedWidth {
+        type ComputedValue = computed_value::Width;
+
+        #[inline]
+        fn to_computed_value<Cx: TContext>(&self, context: &Cx) -> computed_value::Width {
+            match *self {
+                SpecifiedWidth::LengthOrPercentage(ref value) => {
+                    computed_value::Width::LengthOrPercentage(value.to_computed_value(context))
+                }
+                SpecifiedWidth::Number(number) => computed_value::Width::Number(number.0),
+                SpecifiedWidth::Auto => computed_value::Width::Auto,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, HeapSizeOf)]
+    pub struct SpecifiedValue {
+        pub top: SpecifiedWidth,
+        pub right: SpecifiedWidth,
+        pub bottom: SpecifiedWidth,
+        pub left: SpecifiedWidth,
+    }
+
+    impl ToCss for SpecifiedValue {
+        fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+            try!(self.top.to_css(dest));
+            try!(dest.write_str(" "));
+            try!(self.right.to_css(dest));
+            try!(dest.write_str(" "));
+            try!(self.bottom.to_css(dest));
+            try!(dest.write_str(" "));
+            self.left.to_css(dest)
+        }
+    }
+
+    impl ToComputedValue for SpecifiedValue {
+        type ComputedValue = computed_value::T;
+
+        #[inline]
+        fn to_computed_value<Cx: TContext>(&self, context: &Cx) -> computed_value::T {
+            computed_value::T {
+                top: self.top.to_computed_value(context),
+                right: self.right.to_computed_value(context),
+                bottom: self.bottom.to_computed_value(context),
+                left: self.left.to_computed_value(context),
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get_initial_value() -> computed_value::T {
+        let width = computed_value::Width::Number(1.0);
+        computed_value::T {
+            top: width,
+            right: width,
+            bottom: width,
+            left: width,
+        }
+    }
+
+    fn parse_width(input: &mut Parser) -> Result<SpecifiedWidth, ()> {
+        if input.try(|input| input.expect_ident_matching("auto")).is_ok() {
+            return Ok(SpecifiedWidth::Auto)
+        }
+        // Unitless zeros are numbers rather than lengths.
+        if let Ok(number) = input.try(specified::Number::parse_non_negative) {
+            return Ok(SpecifiedWidth::Number(number))
+        }
+        specified::LengthOrPercentage::parse_non_negative(input)
+            .map(SpecifiedWidth::LengthOrPercentage)
+    }
+
+    pub fn parse(_context: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue, ()> {
+        let widths = try!(super::parse_border_image_sides(input, parse_width));
+        Ok(SpecifiedValue {
+            top: widths[0],
+            right: widths[1],
+            bottom: widths[2],
+            left: widths[3],
+        })
+    }
+</%helpers:longhand>
+
+<%helpers:longhand name="border-image-outset">
+    use cssparser::ToCss;
+    use std::fmt;
+
+    pub mod computed_value {
+        use app_units::Au;
+        use values::CSSFloat;
+
+        /// How far the border image area extends past one side of the border box.
+        #[derive(Debug, Clone, Copy, PartialEq, HeapSizeOf)]
+        pub enum Outset {
+            /// A length.
+            Length(Au),
+            /// A multiple of the width of the border on that side.
+            Number(CSSFloat),
+        }
+
+        #[derive(Debug, Clone, Copy, PartialEq, HeapSizeOf)]
+        pub struct T {
+            pub top: Outset,
+            pub right: Outset,
+            pub bottom: Outset,
+            pub left: Outset,
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, HeapSizeOf)]
+    pub enum SpecifiedOutset {
+        Length(specified::Length),
+        Number(specified::Number),
+    }
+
+    impl ToCss for SpecifiedOutset {
+        fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+            match *self {
+                SpecifiedOutset::Length(ref length) => length.to_css(dest),
+                SpecifiedOutset::Number(ref number) => number.to_css(dest),
+            }
+        }
+    }
+
+    impl ToComputedValue for SpecifiedOutset {
+        type ComputedValue = computed_value::Outset;
+
+        #[inline]
+        fn to_computed_value<Cx: TContext>(&self, context: &Cx) -> computed_value::Outset {
+            match *self {
+                SpecifiedOutset::Length(ref length) => {
+                    computed_value::Outset::Length(length.to_computed_value(context))
+                }
+                SpecifiedOutset::Number(number) => computed_value::Outset::Number(number.0),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, HeapSizeOf)]
+    pub struct SpecifiedValue {
+        pub top: SpecifiedOutset,
+        pub right: SpecifiedOutset,
+        pub bottom: SpecifiedOutset,
+        pub left: SpecifiedOutset,
+    }
+
+    impl ToCss for SpecifiedValue {
+        fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+            try!(self.top.to_css(dest));
+            try!(dest.write_str(" "));
+            try!(self.right.to_css(dest));
+            try!(dest.write_str(" "));
+            try!(self.bottom.to_css(dest));
+            try!(dest.write_str(" "));
+            self.left.to_css(dest)
+        }
+    }
+
+    impl ToComputedValue for SpecifiedValue {
+        type ComputedValue = computed_value::T;
+
+        #[inline]
+        fn to_computed_value<Cx: TContext>(&self, context: &Cx) -> computed_value::T {
+            computed_value::T {
+                top: self.top.to_computed_value(context),
+                right: self.right.to_computed_value(context),
+                bottom: self.bottom.to_computed_value(context),
+                left: self.left.to_computed_value(context),
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get_initial_value() -> computed_value::T {
+        let outset = computed_value::Outset::Number(0.0);
+        computed_value::T {
+            top: outset,
+            right: outset,
+            bottom: outset,
+            left: outset,
+        }
+    }
+
+    fn parse_outset(input: &mut Parser) -> Result<SpecifiedOutset, ()> {
+        if let Ok(number) = input.try(specified::Number::parse_non_negative) {
+            return Ok(SpecifiedOutset::Number(number))
+        }
+        specified::Length::parse_non_negative(input).map(SpecifiedOutset::Length)
+    }
+
+    pub fn parse(_context: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue, ()> {
+        let outsets = try!(super::parse_border_image_sides(input, parse_outset));
+        Ok(SpecifiedValue {
+            top: outsets[0],
+            right: outsets[1],
+            bottom: outsets[2],
+            left: outsets[3],
+        })
+    }
+</%helpers:longhand>
+
+<%helpers:longhand name="border-image-repeat">
+    use cssparser::ToCss;
+    use std::fmt;
+    use values::computed::ComputedValueAsSpecified;
+
+    impl ComputedValueAsSpecified for SpecifiedValue {}
+    pub type SpecifiedValue = computed_value::T;
+
+    pub mod computed_value {
+        use cssparser::ToCss;
+        use std::fmt;
+
+        define_css_keyword_enum!(RepeatKeyword:
+                                 "stretch" => Stretch,
+                                 "repeat" => Repeat,
+                                 "round" => Round,
+                                 "space" => Space);
+
+        /// How the sides and middle of the image are tiled horizontally, then vertically.
+        #[derive(Debug, Clone, Copy, PartialEq, HeapSizeOf)]
+        pub struct T(pub RepeatKeyword, pub RepeatKeyword);
+
+        impl ToCss for T {
+            fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+                try!(self.0.to_css(dest));
+                if self.1 != self.0 {
+                    try!(dest.write_str(" "));
+                    try!(self.1.to_css(dest));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get_initial_value() -> computed_value::T {
+        computed_value::T(computed_value::RepeatKeyword::Stretch,
+                          computed_value::RepeatKeyword::Stretch)
+    }
+
+    pub fn parse(_context: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue, ()> {
+        let horizontal = try!(computed_value::RepeatKeyword::parse(input));
+        let vertical = input.try(computed_value::RepeatKeyword::parse).unwrap_or(horizontal);
+        Ok(computed_value::T(horizontal, vertical))
+    }
+</%helpers:longhand>
+
 ${helpers.single_keyword("box-decoration-break", "slice clone", products="gecko")}
 
 ${helpers.single_keyword("-moz-float-edge",
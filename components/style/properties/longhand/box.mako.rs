@@ -17,6 +17,7 @@
             table inline-table table-row-group table-header-group table-footer-group
             table-row table-column-group table-column table-cell table-caption
             list-item flex
+            ruby ruby-base ruby-text ruby-base-container ruby-text-container
             none
         """.split()
         if product == "gecko":
@@ -79,7 +80,6 @@
                                    _cacheable: &mut bool,
                                    _error_reporter: &mut StdBox<ParseErrorReporter + Send>) {
             longhands::_servo_display_for_hypothetical_box::derive_from_display(context);
-            longhands::_servo_text_decorations_in_effect::derive_from_display(context);
             longhands::_servo_under_display_none::derive_from_display(context);
         }
     % endif
@@ -485,20 +485,29 @@ ${helpers.single_keyword("overflow-x", "visible hidden scroll auto", need_clone=
                         p2y = try!(specified::parse_number(input));
                         Ok(())
                     }));
+                    // The x coordinates of the control points must be within the duration.
+                    if p1x < 0.0 || p1x > 1.0 || p2x < 0.0 || p2x > 1.0 {
+                        return Err(())
+                    }
                     let (p1, p2) = (Point2D::new(p1x, p1y), Point2D::new(p2x, p2y));
                     Ok(TransitionTimingFunction::CubicBezier(p1, p2))
                 },
                 "steps" => {
-                    let (mut step_count, mut start_end) = (0, computed_value::StartEnd::Start);
+                    // Steps jump at their end unless they say otherwise.
+                    let (mut step_count, mut start_end) = (0, computed_value::StartEnd::End);
                     try!(input.parse_nested_block(|input| {
                         step_count = try!(specified::parse_integer(input));
-                        try!(input.expect_comma());
-                        start_end = try!(match_ignore_ascii_case! {
-                            try!(input.expect_ident()),
-                            "start" => Ok(computed_value::StartEnd::Start),
-                            "end" => Ok(computed_value::StartEnd::End),
-                            _ => Err(())
-                        });
+                        if step_count <= 0 {
+                            return Err(())
+                        }
+                        if input.try(|input| input.expect_comma()).is_ok() {
+                            start_end = try!(match_ignore_ascii_case! {
+                                try!(input.expect_ident()),
+                                "start" => Ok(computed_value::StartEnd::Start),
+                                "end" => Ok(computed_value::StartEnd::End),
+                                _ => Err(())
+                            });
+                        }
                         Ok(())
                     }));
                     Ok(TransitionTimingFunction::Steps(step_count as u32, start_end))
@@ -543,6 +552,8 @@ ${helpers.single_keyword("overflow-x", "visible hidden scroll auto", need_clone=
             BackgroundColor,
             BackgroundPosition,
             BorderBottomColor,
+            BorderBottomLeftRadius,
+            BorderBottomRightRadius,
             BorderBottomWidth,
             BorderLeftColor,
             BorderLeftWidth,
@@ -550,8 +561,11 @@ ${helpers.single_keyword("overflow-x", "visible hidden scroll auto", need_clone=
             BorderRightWidth,
             BorderSpacing,
             BorderTopColor,
+            BorderTopLeftRadius,
+            BorderTopRightRadius,
             BorderTopWidth,
             Bottom,
+            BoxShadow,
             Color,
             Clip,
             FontSize,
@@ -569,17 +583,23 @@ ${helpers.single_keyword("overflow-x", "visible hidden scroll auto", need_clone=
             MinHeight,
             MinWidth,
             Opacity,
+            Order,
             OutlineColor,
+            OutlineOffset,
             OutlineWidth,
             PaddingBottom,
             PaddingLeft,
             PaddingRight,
             PaddingTop,
+            Perspective,
+            PerspectiveOrigin,
             Right,
+            TextDecorationColor,
             TextIndent,
             TextShadow,
             Top,
             Transform,
+            TransformOrigin,
             VerticalAlign,
             Visibility,
             Width,
@@ -587,10 +607,12 @@ ${helpers.single_keyword("overflow-x", "visible hidden scroll auto", need_clone=
             ZIndex,
         }
 
-        pub static ALL_TRANSITION_PROPERTIES: [TransitionProperty; 45] = [
+        pub static ALL_TRANSITION_PROPERTIES: [TransitionProperty; 56] = [
             TransitionProperty::BackgroundColor,
             TransitionProperty::BackgroundPosition,
             TransitionProperty::BorderBottomColor,
+            TransitionProperty::BorderBottomLeftRadius,
+            TransitionProperty::BorderBottomRightRadius,
             TransitionProperty::BorderBottomWidth,
             TransitionProperty::BorderLeftColor,
             TransitionProperty::BorderLeftWidth,
@@ -598,8 +620,11 @@ ${helpers.single_keyword("overflow-x", "visible hidden scroll auto", need_clone=
             TransitionProperty::BorderRightWidth,
             TransitionProperty::BorderSpacing,
             TransitionProperty::BorderTopColor,
+            TransitionProperty::BorderTopLeftRadius,
+            TransitionProperty::BorderTopRightRadius,
             TransitionProperty::BorderTopWidth,
             TransitionProperty::Bottom,
+            TransitionProperty::BoxShadow,
             TransitionProperty::Color,
             TransitionProperty::Clip,
             TransitionProperty::FontSize,
@@ -617,17 +642,23 @@ ${helpers.single_keyword("overflow-x", "visible hidden scroll auto", need_clone=
             TransitionProperty::MinHeight,
             TransitionProperty::MinWidth,
             TransitionProperty::Opacity,
+            TransitionProperty::Order,
             TransitionProperty::OutlineColor,
+            TransitionProperty::OutlineOffset,
             TransitionProperty::OutlineWidth,
             TransitionProperty::PaddingBottom,
             TransitionProperty::PaddingLeft,
             TransitionProperty::PaddingRight,
             TransitionProperty::PaddingTop,
+            TransitionProperty::Perspective,
+            TransitionProperty::PerspectiveOrigin,
             TransitionProperty::Right,
+            TransitionProperty::TextDecorationColor,
             TransitionProperty::TextIndent,
             TransitionProperty::TextShadow,
             TransitionProperty::Top,
             TransitionProperty::Transform,
+            TransitionProperty::TransformOrigin,
             TransitionProperty::VerticalAlign,
             TransitionProperty::Visibility,
             TransitionProperty::Width,
@@ -642,6 +673,12 @@ ${helpers.single_keyword("overflow-x", "visible hidden scroll auto", need_clone=
                     TransitionProperty::BackgroundColor => dest.write_str("background-color"),
                     TransitionProperty::BackgroundPosition => dest.write_str("background-position"),
                     TransitionProperty::BorderBottomColor => dest.write_str("border-bottom-color"),
+                    TransitionProperty::BorderBottomLeftRadius => {
+                        dest.write_str("border-bottom-left-radius")
+                    }
+                    TransitionProperty::BorderBottomRightRadius => {
+                        dest.write_str("border-bottom-right-radius")
+                    }
                     TransitionProperty::BorderBottomWidth => dest.write_str("border-bottom-width"),
                     TransitionProperty::BorderLeftColor => dest.write_str("border-left-color"),
                     TransitionProperty::BorderLeftWidth => dest.write_str("border-left-width"),
@@ -649,8 +686,15 @@ ${helpers.single_keyword("overflow-x", "visible hidden scroll auto", need_clone=
                     TransitionProperty::BorderRightWidth => dest.write_str("border-right-width"),
                     TransitionProperty::BorderSpacing => dest.write_str("border-spacing"),
                     TransitionProperty::BorderTopColor => dest.write_str("border-top-color"),
+                    TransitionProperty::BorderTopLeftRadius => {
+                        dest.write_str("border-top-left-radius")
+                    }
+                    TransitionProperty::BorderTopRightRadius => {
+                        dest.write_str("border-top-right-radius")
+                    }
                     TransitionProperty::BorderTopWidth => dest.write_str("border-top-width"),
                     TransitionProperty::Bottom => dest.write_str("bottom"),
+                    TransitionProperty::BoxShadow => dest.write_str("box-shadow"),
                     TransitionProperty::Color => dest.write_str("color"),
                     TransitionProperty::Clip => dest.write_str("clip"),
                     TransitionProperty::FontSize => dest.write_str("font-size"),
@@ -668,17 +712,25 @@ ${helpers.single_keyword("overflow-x", "visible hidden scroll auto", need_clone=
                     TransitionProperty::MinHeight => dest.write_str("min-height"),
                     TransitionProperty::MinWidth => dest.write_str("min-width"),
                     TransitionProperty::Opacity => dest.write_str("opacity"),
+                    TransitionProperty::Order => dest.write_str("order"),
                     TransitionProperty::OutlineColor => dest.write_str("outline-color"),
+                    TransitionProperty::OutlineOffset => dest.write_str("outline-offset"),
                     TransitionProperty::OutlineWidth => dest.write_str("outline-width"),
                     TransitionProperty::PaddingBottom => dest.write_str("padding-bottom"),
                     TransitionProperty::PaddingLeft => dest.write_str("padding-left"),
                     TransitionProperty::PaddingRight => dest.write_str("padding-right"),
                     TransitionProperty::PaddingTop => dest.write_str("padding-top"),
+                    TransitionProperty::Perspective => dest.write_str("perspective"),
+                    TransitionProperty::PerspectiveOrigin => dest.write_str("perspective-origin"),
                     TransitionProperty::Right => dest.write_str("right"),
+                    TransitionProperty::TextDecorationColor => {
+                        dest.write_str("text-decoration-color")
+                    }
                     TransitionProperty::TextIndent => dest.write_str("text-indent"),
                     TransitionProperty::TextShadow => dest.write_str("text-shadow"),
                     TransitionProperty::Top => dest.write_str("top"),
                     TransitionProperty::Transform => dest.write_str("transform"),
+                    TransitionProperty::TransformOrigin => dest.write_str("transform-origin"),
                     TransitionProperty::VerticalAlign => dest.write_str("vertical-align"),
                     TransitionProperty::Visibility => dest.write_str("visibility"),
                     TransitionProperty::Width => dest.write_str("width"),
@@ -719,6 +771,8 @@ ${helpers.single_keyword("overflow-x", "visible hidden scroll auto", need_clone=
             "background-color" => Ok(TransitionProperty::BackgroundColor),
             "background-position" => Ok(TransitionProperty::BackgroundPosition),
             "border-bottom-color" => Ok(TransitionProperty::BorderBottomColor),
+            "border-bottom-left-radius" => Ok(TransitionProperty::BorderBottomLeftRadius),
+            "border-bottom-right-radius" => Ok(TransitionProperty::BorderBottomRightRadius),
             "border-bottom-width" => Ok(TransitionProperty::BorderBottomWidth),
             "border-left-color" => Ok(TransitionProperty::BorderLeftColor),
             "border-left-width" => Ok(TransitionProperty::BorderLeftWidth),
@@ -726,8 +780,11 @@ ${helpers.single_keyword("overflow-x", "visible hidden scroll auto", need_clone=
             "border-right-width" => Ok(TransitionProperty::BorderRightWidth),
             "border-spacing" => Ok(TransitionProperty::BorderSpacing),
             "border-top-color" => Ok(TransitionProperty::BorderTopColor),
+            "border-top-left-radius" => Ok(TransitionProperty::BorderTopLeftRadius),
+            "border-top-right-radius" => Ok(TransitionProperty::BorderTopRightRadius),
             "border-top-width" => Ok(TransitionProperty::BorderTopWidth),
             "bottom" => Ok(TransitionProperty::Bottom),
+            "box-shadow" => Ok(TransitionProperty::BoxShadow),
             "color" => Ok(TransitionProperty::Color),
             "clip" => Ok(TransitionProperty::Clip),
             "font-size" => Ok(TransitionProperty::FontSize),
@@ -745,17 +802,23 @@ ${helpers.single_keyword("overflow-x", "visible hidden scroll auto", need_clone=
             "min-height" => Ok(TransitionProperty::MinHeight),
             "min-width" => Ok(TransitionProperty::MinWidth),
             "opacity" => Ok(TransitionProperty::Opacity),
+            "order" => Ok(TransitionProperty::Order),
             "outline-color" => Ok(TransitionProperty::OutlineColor),
+            "outline-offset" => Ok(TransitionProperty::OutlineOffset),
             "outline-width" => Ok(TransitionProperty::OutlineWidth),
             "padding-bottom" => Ok(TransitionProperty::PaddingBottom),
             "padding-left" => Ok(TransitionProperty::PaddingLeft),
             "padding-right" => Ok(TransitionProperty::PaddingRight),
             "padding-top" => Ok(TransitionProperty::PaddingTop),
+            "perspective" => Ok(TransitionProperty::Perspective),
+            "perspective-origin" => Ok(TransitionProperty::PerspectiveOrigin),
             "right" => Ok(TransitionProperty::Right),
+            "text-decoration-color" => Ok(TransitionProperty::TextDecorationColor),
             "text-indent" => Ok(TransitionProperty::TextIndent),
             "text-shadow" => Ok(TransitionProperty::TextShadow),
             "top" => Ok(TransitionProperty::Top),
             "transform" => Ok(TransitionProperty::Transform),
+            "transform-origin" => Ok(TransitionProperty::TransformOrigin),
             "vertical-align" => Ok(TransitionProperty::VerticalAlign),
             "visibility" => Ok(TransitionProperty::Visibility),
             "width" => Ok(TransitionProperty::Width),
@@ -786,24 +849,159 @@ ${helpers.single_keyword("overflow-x", "visible hidden scroll auto", need_clone=
     pub use properties::longhands::transition_duration::{get_initial_value, parse, parse_one};
 </%helpers:longhand>
 
+// https://drafts.csswg.org/css-animations/#animation-play-state
+//
+// This only applies to CSS animations, one value for each name in `animation-name`, so it pauses
+// nothing until those are supported. Transitions keep running whatever its value.
+<%helpers:longhand name="animation-play-state" products="servo">
+    use values::computed::ComputedValueAsSpecified;
+
+    pub use self::computed_value::SingleComputedValue as SingleSpecifiedValue;
+    pub use self::computed_value::T as SpecifiedValue;
+
+    pub mod computed_value {
+        use cssparser::ToCss;
+        use std::fmt;
+
+        define_css_keyword_enum! { SingleComputedValue:
+            "running" => running,
+            "paused" => paused,
+        }
+
+        #[derive(Clone, Debug, HeapSizeOf, PartialEq)]
+        pub struct T(pub Vec<SingleComputedValue>);
+
+        impl T {
+            /// The play state of the animation at `index` in `animation-name`. The list of play
+            /// states is repeated as many times as needed to have one for each animation.
+            #[inline]
+            pub fn get_mod(&self, index: usize) -> SingleComputedValue {
+                self.0[index % self.0.len()]
+            }
+        }
+
+        impl ToCss for T {
+            fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+                for (i, value) in self.0.iter().enumerate() {
+                    if i != 0 {
+                        try!(dest.write_str(", "))
+                    }
+                    try!(value.to_css(dest))
+                }
+                Ok(())
+            }
+        }
+    }
+
+    impl ComputedValueAsSpecified for SpecifiedValue {}
+
+    #[inline]
+    pub fn get_initial_value() -> computed_value::T {
+        computed_value::T(vec![SingleSpecifiedValue::running])
+    }
+
+    pub fn parse(_: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue, ()> {
+        Ok(computed_value::T(try!(input.parse_comma_separated(SingleSpecifiedValue::parse))))
+    }
+</%helpers:longhand>
+
 // CSSOM View Module
 // https://www.w3.org/TR/cssom-view-1/
 ${helpers.single_keyword("scroll-behavior",
                          "auto smooth",
-                         products="gecko")}
+                         products="gecko servo")}
 
 // Non-standard: https://developer.mozilla.org/en-US/docs/Web/CSS/scroll-snap-type-x
 ${helpers.single_keyword("scroll-snap-type-x",
                          "none mandatory proximity",
-                         products="gecko",
+                         products="gecko servo",
                          gecko_constant_prefix="NS_STYLE_SCROLL_SNAP_TYPE")}
 
 // Non-standard: https://developer.mozilla.org/en-US/docs/Web/CSS/scroll-snap-type-y
 ${helpers.single_keyword("scroll-snap-type-y",
                          "none mandatory proximity",
-                         products="gecko",
+                         products="gecko servo",
                          gecko_constant_prefix="NS_STYLE_SCROLL_SNAP_TYPE")}
 
+// https://drafts.csswg.org/css-scroll-snap-1/#scroll-snap-align
+${helpers.single_keyword("scroll-snap-align",
+                         "none start end center",
+                         products="servo")}
+
+// https://w3c.github.io/pointerevents/#the-touch-action-css-property
+<%helpers:longhand name="touch-action" products="servo">
+    use cssparser::ToCss;
+    use std::fmt;
+    use values::computed::ComputedValueAsSpecified;
+
+    pub use self::computed_value::T as SpecifiedValue;
+
+    pub mod computed_value {
+        /// The touch behaviors of the browser that an element allows to start on it.
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone, Copy, PartialEq, HeapSizeOf, Deserialize, Serialize)]
+        pub enum T {
+            auto,
+            none,
+            pan_x,
+            pan_y,
+            pan_x_pan_y,
+            manipulation,
+        }
+    }
+
+    impl ComputedValueAsSpecified for SpecifiedValue {}
+
+    impl ToCss for SpecifiedValue {
+        fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+            dest.write_str(match *self {
+                computed_value::T::auto => "auto",
+                computed_value::T::none => "none",
+                computed_value::T::pan_x => "pan-x",
+                computed_value::T::pan_y => "pan-y",
+                computed_value::T::pan_x_pan_y => "pan-x pan-y",
+                computed_value::T::manipulation => "manipulation",
+            })
+        }
+    }
+
+    #[inline]
+    pub fn get_initial_value() -> computed_value::T {
+        computed_value::T::auto
+    }
+
+    pub fn parse(_: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue, ()> {
+        if input.try(|input| input.expect_ident_matching("auto")).is_ok() {
+            return Ok(computed_value::T::auto)
+        }
+        if input.try(|input| input.expect_ident_matching("none")).is_ok() {
+            return Ok(computed_value::T::none)
+        }
+        if input.try(|input| input.expect_ident_matching("manipulation")).is_ok() {
+            return Ok(computed_value::T::manipulation)
+        }
+
+        let (mut pan_x, mut pan_y) = (false, false);
+        while let Ok(ident) = input.try(|input| input.expect_ident()) {
+            let pan = match_ignore_ascii_case! { ident,
+                "pan-x" => &mut pan_x,
+                "pan-y" => &mut pan_y,
+                _ => return Err(())
+            };
+            if *pan {
+                return Err(())
+            }
+            *pan = true;
+        }
+        match (pan_x, pan_y) {
+            (true, true) => Ok(computed_value::T::pan_x_pan_y),
+            (true, false) => Ok(computed_value::T::pan_x),
+            (false, true) => Ok(computed_value::T::pan_y),
+            (false, false) => Err(()),
+        }
+    }
+</%helpers:longhand>
+
 // Compositing and Blending Level 1
 // http://www.w3.org/TR/compositing-1/
 ${helpers.single_keyword("isolation",
@@ -828,6 +1026,9 @@ ${helpers.single_keyword("resize",
                          "none both horizontal vertical",
                          products="gecko")}
 
+// https://drafts.csswg.org/css-ui-4/#appearance-switching
+${helpers.single_keyword("appearance", "none auto", products="servo")}
+
 // Non-standard
 ${helpers.single_keyword("-moz-appearance",
                          """none button button-arrow-down button-arrow-next button-arrow-previous button-arrow-up
@@ -929,3 +1130,458 @@ ${helpers.single_keyword("-moz-appearance",
         }
     }
 </%helpers:longhand>
+
+// https://drafts.csswg.org/css-will-change/#will-change
+<%helpers:longhand name="will-change" products="servo">
+    use cssparser::ToCss;
+    use std::ascii::AsciiExt;
+    use std::fmt;
+    use values::computed::ComputedValueAsSpecified;
+
+    pub use self::computed_value::T as SpecifiedValue;
+
+    pub mod computed_value {
+        #[derive(Debug, Clone, PartialEq, HeapSizeOf)]
+        pub enum T {
+            Auto,
+            /// The lowercased features that are expected to change.
+            AnimateableFeatures(Vec<String>),
+        }
+
+        impl T {
+            /// Whether the given property, in lowercase, is expected to change.
+            pub fn contains(&self, property: &str) -> bool {
+                match *self {
+                    T::Auto => false,
+                    T::AnimateableFeatures(ref features) => {
+                        features.iter().any(|feature| feature == property)
+                    }
+                }
+            }
+        }
+    }
+
+    impl ComputedValueAsSpecified for SpecifiedValue {}
+
+    impl ToCss for SpecifiedValue {
+        fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+            match *self {
+                SpecifiedValue::Auto => dest.write_str("auto"),
+                SpecifiedValue::AnimateableFeatures(ref features) => {
+                    for (i, feature) in features.iter().enumerate() {
+                        if i != 0 {
+                            try!(dest.write_str(", "))
+                        }
+                        try!(dest.write_str(feature))
+                    }
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get_initial_value() -> computed_value::T {
+        computed_value::T::Auto
+    }
+
+    pub fn parse_one(input: &mut Parser) -> Result<String, ()> {
+        let feature = try!(input.expect_ident()).to_ascii_lowercase();
+        match &*feature {
+            "will-change" | "none" | "all" | "auto" | "initial" | "inherit" | "unset" |
+            "default" => Err(()),
+            _ => Ok(feature),
+        }
+    }
+
+    pub fn parse(_: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue, ()> {
+        if input.try(|input| input.expect_ident_matching("auto")).is_ok() {
+            return Ok(SpecifiedValue::Auto)
+        }
+        Ok(SpecifiedValue::AnimateableFeatures(try!(input.parse_comma_separated(parse_one))))
+    }
+</%helpers:longhand>
+
+// https://drafts.csswg.org/css-containment/#containment-property
+<%helpers:longhand name="contain" products="servo">
+    use cssparser::ToCss;
+    use std::fmt;
+    use values::computed::ComputedValueAsSpecified;
+
+    pub use self::computed_value::T as SpecifiedValue;
+
+    pub mod computed_value {
+        /// The kinds of containment that apply to an element.
+        #[derive(Debug, Clone, Copy, PartialEq, HeapSizeOf)]
+        pub struct T {
+            pub size: bool,
+            pub layout: bool,
+            pub style: bool,
+            pub paint: bool,
+        }
+    }
+
+    impl ComputedValueAsSpecified for SpecifiedValue {}
+
+    impl ToCss for SpecifiedValue {
+        fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+            if *self == get_initial_value() {
+                return dest.write_str("none")
+            }
+            let kinds = [(self.size, "size"), (self.layout, "layout"), (self.style, "style"),
+                         (self.paint, "paint")];
+            let mut first = true;
+            for &(contained, name) in &kinds {
+                if contained {
+                    if !first {
+                        try!(dest.write_str(" "))
+                    }
+                    try!(dest.write_str(name));
+                    first = false;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[inline]
+    pub fn get_initial_value() -> computed_value::T {
+        computed_value::T { size: false, layout: false, style: false, paint: false }
+    }
+
+    pub fn parse(_: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue, ()> {
+        if input.try(|input| input.expect_ident_matching("none")).is_ok() {
+            return Ok(get_initial_value())
+        }
+        if input.try(|input| input.expect_ident_matching("strict")).is_ok() {
+            return Ok(computed_value::T { size: true, layout: true, style: true, paint: true })
+        }
+        if input.try(|input| input.expect_ident_matching("content")).is_ok() {
+            return Ok(computed_value::T { size: false, layout: true, style: true, paint: true })
+        }
+
+        let mut result = get_initial_value();
+        while let Ok(ident) = input.try(|input| input.expect_ident()) {
+            let kind = match_ignore_ascii_case! { ident,
+                "size" => &mut result.size,
+                "layout" => &mut result.layout,
+                "style" => &mut result.style,
+                "paint" => &mut result.paint,
+                _ => return Err(())
+            };
+            if *kind {
+                return Err(())
+            }
+            *kind = true;
+        }
+        if result == get_initial_value() {
+            return Err(())
+        }
+        Ok(result)
+    }
+</%helpers:longhand>
+
+// https://drafts.csswg.org/css-shapes/#shape-outside-property
+<%helpers:longhand name="shape-outside" products="servo">
+    use super::background_position;
+    use self::computed_value::{BasicShape, FillRule, ShapeBox, ShapeOutside, ShapeRadius};
+
+    pub type SpecifiedValue = ShapeOutside<specified::LengthOrPercentage,
+                                           background_position::SpecifiedValue>;
+
+    pub mod computed_value {
+        use cssparser::ToCss;
+        use std::fmt;
+        use super::super::background_position::computed_value::T as Position;
+        use values::computed::LengthOrPercentage;
+
+        pub type T = ShapeOutside<LengthOrPercentage, Position>;
+
+        /// The float area of a float, with lengths of type `L` and positions of type `P`.
+        /// Images are not supported.
+        #[derive(Clone, Debug, PartialEq, HeapSizeOf)]
+        pub enum ShapeOutside<L, P> {
+            None,
+            /// The given box of the float.
+            Box(ShapeBox),
+            /// A shape in the given box of the float.
+            Shape(BasicShape<L, P>, ShapeBox),
+        }
+
+        /// https://drafts.csswg.org/css-shapes/#basic-shape-functions
+        #[derive(Clone, Debug, PartialEq, HeapSizeOf)]
+        pub enum BasicShape<L, P> {
+            /// The offsets of the top, right, bottom and left sides of a rectangle from those of
+            /// the reference box. Rounded corners are not supported.
+            Inset(L, L, L, L),
+            Circle(ShapeRadius<L>, P),
+            /// An ellipse with the given horizontal and vertical radii.
+            Ellipse(ShapeRadius<L>, ShapeRadius<L>, P),
+            Polygon(FillRule, Vec<(L, L)>),
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, HeapSizeOf)]
+        pub enum ShapeRadius<L> {
+            Length(L),
+            ClosestSide,
+            FarthestSide,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, HeapSizeOf)]
+        pub enum FillRule {
+            Nonzero,
+            Evenodd,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, HeapSizeOf)]
+        pub enum ShapeBox {
+            MarginBox,
+            BorderBox,
+            PaddingBox,
+            ContentBox,
+        }
+
+        impl<L: ToCss, P: ToCss> ToCss for ShapeOutside<L, P> {
+            fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+                match *self {
+                    ShapeOutside::None => dest.write_str("none"),
+                    ShapeOutside::Box(reference_box) => reference_box.to_css(dest),
+                    ShapeOutside::Shape(ref shape, reference_box) => {
+                        try!(shape.to_css(dest));
+                        if reference_box != ShapeBox::MarginBox {
+                            try!(dest.write_str(" "));
+                            try!(reference_box.to_css(dest));
+                        }
+                        Ok(())
+                    }
+                }
+            }
+        }
+
+        impl<L: ToCss, P: ToCss> ToCss for BasicShape<L, P> {
+            fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+                match *self {
+                    BasicShape::Inset(ref top, ref right, ref bottom, ref left) => {
+                        try!(dest.write_str("inset("));
+                        for (i, side) in [top, right, bottom, left].iter().enumerate() {
+                            if i != 0 {
+                                try!(dest.write_str(" "));
+                            }
+                            try!(side.to_css(dest));
+                        }
+                    }
+                    BasicShape::Circle(ref radius, ref position) => {
+                        try!(dest.write_str("circle("));
+                        try!(radius.to_css(dest));
+                        try!(dest.write_str(" at "));
+                        try!(position.to_css(dest));
+                    }
+                    BasicShape::Ellipse(ref radius_x, ref radius_y, ref position) => {
+                        try!(dest.write_str("ellipse("));
+                        try!(radius_x.to_css(dest));
+                        try!(dest.write_str(" "));
+                        try!(radius_y.to_css(dest));
+                        try!(dest.write_str(" at "));
+                        try!(position.to_css(dest));
+                    }
+                    BasicShape::Polygon(fill_rule, ref points) => {
+                        try!(dest.write_str("polygon("));
+                        if fill_rule == FillRule::Evenodd {
+                            try!(dest.write_str("evenodd, "));
+                        }
+                        for (i, &(ref x, ref y)) in points.iter().enumerate() {
+                            if i != 0 {
+                                try!(dest.write_str(", "));
+                            }
+                            try!(x.to_css(dest));
+                            try!(dest.write_str(" "));
+                            try!(y.to_css(dest));
+                        }
+                    }
+                }
+                dest.write_str(")")
+            }
+        }
+
+        impl<L: ToCss> ToCss for ShapeRadius<L> {
+            fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+                match *self {
+                    ShapeRadius::Length(ref length) => length.to_css(dest),
+                    ShapeRadius::ClosestSide => dest.write_str("closest-side"),
+                    ShapeRadius::FarthestSide => dest.write_str("farthest-side"),
+                }
+            }
+        }
+
+        impl ToCss for ShapeBox {
+            fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+                dest.write_str(match *self {
+                    ShapeBox::MarginBox => "margin-box",
+                    ShapeBox::BorderBox => "border-box",
+                    ShapeBox::PaddingBox => "padding-box",
+                    ShapeBox::ContentBox => "content-box",
+                })
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get_initial_value() -> computed_value::T {
+        ShapeOutside::None
+    }
+
+    impl ToComputedValue for SpecifiedValue {
+        type ComputedValue = computed_value::T;
+
+        #[inline]
+        fn to_computed_value<Cx: TContext>(&self, context: &Cx) -> computed_value::T {
+            match *self {
+                ShapeOutside::None => ShapeOutside::None,
+                ShapeOutside::Box(reference_box) => ShapeOutside::Box(reference_box),
+                ShapeOutside::Shape(ref shape, reference_box) => {
+                    ShapeOutside::Shape(compute_shape(shape, context), reference_box)
+                }
+            }
+        }
+    }
+
+    fn compute_shape<Cx: TContext>(shape: &BasicShape<specified::LengthOrPercentage,
+                                                      background_position::SpecifiedValue>,
+                                   context: &Cx)
+                                   -> BasicShape<computed::LengthOrPercentage,
+                                                 background_position::computed_value::T> {
+        match *shape {
+            BasicShape::Inset(top, right, bottom, left) => {
+                BasicShape::Inset(top.to_computed_value(context),
+                                  right.to_computed_value(context),
+                                  bottom.to_computed_value(context),
+                                  left.to_computed_value(context))
+            }
+            BasicShape::Circle(radius, ref position) => {
+                BasicShape::Circle(compute_radius(radius, context),
+                                   position.to_computed_value(context))
+            }
+            BasicShape::Ellipse(radius_x, radius_y, ref position) => {
+                BasicShape::Ellipse(compute_radius(radius_x, context),
+                                    compute_radius(radius_y, context),
+                                    position.to_computed_value(context))
+            }
+            BasicShape::Polygon(fill_rule, ref points) => {
+                BasicShape::Polygon(fill_rule, points.iter().map(|&(x, y)| {
+                    (x.to_computed_value(context), y.to_computed_value(context))
+                }).collect())
+            }
+        }
+    }
+
+    fn compute_radius<Cx: TContext>(radius: ShapeRadius<specified::LengthOrPercentage>,
+                                    context: &Cx)
+                                    -> ShapeRadius<computed::LengthOrPercentage> {
+        match radius {
+            ShapeRadius::Length(length) => ShapeRadius::Length(length.to_computed_value(context)),
+            ShapeRadius::ClosestSide => ShapeRadius::ClosestSide,
+            ShapeRadius::FarthestSide => ShapeRadius::FarthestSide,
+        }
+    }
+
+    pub fn parse(context: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue, ()> {
+        if input.try(|input| input.expect_ident_matching("none")).is_ok() {
+            return Ok(ShapeOutside::None)
+        }
+        // The shape and the box can come in either order.
+        let reference_box = input.try(parse_shape_box).ok();
+        let shape = match input.try(|input| parse_basic_shape(context, input)) {
+            Ok(shape) => shape,
+            Err(()) => return reference_box.map(ShapeOutside::Box).ok_or(()),
+        };
+        let reference_box = match reference_box {
+            Some(reference_box) => reference_box,
+            None => input.try(parse_shape_box).unwrap_or(ShapeBox::MarginBox),
+        };
+        Ok(ShapeOutside::Shape(shape, reference_box))
+    }
+
+    fn parse_shape_box(input: &mut Parser) -> Result<ShapeBox, ()> {
+        match_ignore_ascii_case! { try!(input.expect_ident()),
+            "margin-box" => Ok(ShapeBox::MarginBox),
+            "border-box" => Ok(ShapeBox::BorderBox),
+            "padding-box" => Ok(ShapeBox::PaddingBox),
+            "content-box" => Ok(ShapeBox::ContentBox),
+            _ => Err(())
+        }
+    }
+
+    fn parse_basic_shape(context: &ParserContext, input: &mut Parser)
+                         -> Result<BasicShape<specified::LengthOrPercentage,
+                                              background_position::SpecifiedValue>, ()> {
+        let name = try!(input.expect_function());
+        input.parse_nested_block(|input| {
+            match_ignore_ascii_case! { name,
+                "inset" => {
+                    let top = try!(specified::LengthOrPercentage::parse(input));
+                    let right = input.try(specified::LengthOrPercentage::parse).unwrap_or(top);
+                    let bottom = input.try(specified::LengthOrPercentage::parse).unwrap_or(top);
+                    let left = input.try(specified::LengthOrPercentage::parse).unwrap_or(right);
+                    Ok(BasicShape::Inset(top, right, bottom, left))
+                },
+                "circle" => {
+                    let radius = input.try(parse_shape_radius).unwrap_or(ShapeRadius::ClosestSide);
+                    let position = try!(parse_position(context, input));
+                    Ok(BasicShape::Circle(radius, position))
+                },
+                "ellipse" => {
+                    let (radius_x, radius_y) = match input.try(parse_shape_radius) {
+                        Ok(radius_x) => (radius_x, try!(parse_shape_radius(input))),
+                        Err(()) => (ShapeRadius::ClosestSide, ShapeRadius::ClosestSide),
+                    };
+                    let position = try!(parse_position(context, input));
+                    Ok(BasicShape::Ellipse(radius_x, radius_y, position))
+                },
+                "polygon" => {
+                    let fill_rule = input.try(|input| {
+                        let fill_rule = match_ignore_ascii_case! { try!(input.expect_ident()),
+                            "nonzero" => FillRule::Nonzero,
+                            "evenodd" => FillRule::Evenodd,
+                            _ => return Err(())
+                        };
+                        try!(input.expect_comma());
+                        Ok(fill_rule)
+                    }).unwrap_or(FillRule::Nonzero);
+                    let points = try!(input.parse_comma_separated(|input| {
+                        let x = try!(specified::LengthOrPercentage::parse(input));
+                        let y = try!(specified::LengthOrPercentage::parse(input));
+                        Ok((x, y))
+                    }));
+                    Ok(BasicShape::Polygon(fill_rule, points))
+                },
+                _ => Err(())
+            }
+        })
+    }
+
+    fn parse_shape_radius(input: &mut Parser)
+                          -> Result<ShapeRadius<specified::LengthOrPercentage>, ()> {
+        if let Ok(length) = input.try(specified::LengthOrPercentage::parse_non_negative) {
+            return Ok(ShapeRadius::Length(length))
+        }
+        match_ignore_ascii_case! { try!(input.expect_ident()),
+            "closest-side" => Ok(ShapeRadius::ClosestSide),
+            "farthest-side" => Ok(ShapeRadius::FarthestSide),
+            _ => Err(())
+        }
+    }
+
+    /// Parses the optional `at <position>` of circles and ellipses, which are centered in the
+    /// reference box by default.
+    fn parse_position(context: &ParserContext, input: &mut Parser)
+                      -> Result<background_position::SpecifiedValue, ()> {
+        if input.try(|input| input.expect_ident_matching("at")).is_err() {
+            let center = specified::LengthOrPercentage::Percentage(specified::Percentage(0.5));
+            return Ok(background_position::SpecifiedValue {
+                horizontal: center,
+                vertical: center,
+            })
+        }
+        background_position::parse(context, input)
+    }
+</%helpers:longhand>
@@ -14,7 +14,7 @@
     use super::list_style_type;
 
     pub use self::computed_value::T as SpecifiedValue;
-    pub use self::computed_value::ContentItem;
+    pub use self::computed_value::{AttrType, ContentItem};
 
     impl ComputedValueAsSpecified for SpecifiedValue {}
 
@@ -23,6 +23,8 @@
 
         use cssparser::{self, ToCss};
         use std::fmt;
+        use url::Url;
+        use values::LocalToCss;
 
         #[derive(Debug, PartialEq, Eq, Clone, HeapSizeOf)]
         pub enum ContentItem {
@@ -40,6 +42,48 @@
             NoOpenQuote,
             /// `no-close-quote`.
             NoCloseQuote,
+            /// `url(...)`, an image.
+            Url(Url),
+            /// `attr(name type, fallback)`, the value of an attribute of the element, which is
+            /// looked up when its generated content is built.
+            Attr(String, AttrType, Option<String>),
+        }
+
+        /// How `attr()` reads the value of an attribute.
+        /// https://drafts.csswg.org/css-values/#attr-notation
+        #[derive(Debug, PartialEq, Eq, Clone, Copy, HeapSizeOf)]
+        pub enum AttrType {
+            String,
+            Integer,
+            Number,
+        }
+
+        impl AttrType {
+            /// The text that `attr()` generates for an attribute with `value`, or for a missing
+            /// one if `value` is `None`. Values that do not parse as the type fall back to
+            /// `fallback`, or to a value of the type if there is none.
+            pub fn generate(&self, value: Option<&str>, fallback: &Option<String>) -> String {
+                let generated = value.and_then(|value| {
+                    match *self {
+                        AttrType::String => Some(value.to_owned()),
+                        AttrType::Integer => {
+                            value.trim().parse::<i32>().ok().map(|value| value.to_string())
+                        }
+                        AttrType::Number => {
+                            value.trim().parse::<f32>().ok()
+                                 .and_then(|value| {
+                                     if value.is_finite() { Some(value.to_string()) } else { None }
+                                 })
+                        }
+                    }
+                });
+                generated.or_else(|| fallback.clone()).unwrap_or_else(|| {
+                    match *self {
+                        AttrType::String => String::new(),
+                        AttrType::Integer | AttrType::Number => "0".to_owned(),
+                    }
+                })
+            }
         }
 
         impl ToCss for ContentItem {
@@ -68,6 +112,21 @@
                     ContentItem::CloseQuote => dest.write_str("close-quote"),
                     ContentItem::NoOpenQuote => dest.write_str("no-open-quote"),
                     ContentItem::NoCloseQuote => dest.write_str("no-close-quote"),
+                    ContentItem::Url(ref url) => url.to_css(dest),
+                    ContentItem::Attr(ref name, type_, ref fallback) => {
+                        try!(dest.write_str("attr("));
+                        try!(cssparser::serialize_identifier(&**name, dest));
+                        match type_ {
+                            AttrType::String => {}
+                            AttrType::Integer => try!(dest.write_str(" integer")),
+                            AttrType::Number => try!(dest.write_str(" number")),
+                        }
+                        if let Some(ref fallback) = *fallback {
+                            try!(dest.write_str(", "));
+                            try!(cssparser::serialize_string(&**fallback, dest));
+                        }
+                        dest.write_str(")")
+                    }
                 }
             }
         }
@@ -108,9 +167,8 @@
             name.eq_ignore_ascii_case("initial")
     }
 
-    // normal | none | [ <string> | <counter> | open-quote | close-quote | no-open-quote |
-    // no-close-quote ]+
-    // TODO: <uri>, attr(<identifier>)
+    // normal | none | [ <string> | <uri> | <counter> | attr(<identifier>) | open-quote |
+    // close-quote | no-open-quote | no-close-quote ]+
     pub fn parse(context: &ParserContext, input: &mut Parser)
                  -> Result<SpecifiedValue, ()> {
         if input.try(|input| input.expect_ident_matching("normal")).is_ok() {
@@ -125,6 +183,9 @@
                 Ok(Token::QuotedString(value)) => {
                     content.push(ContentItem::String(value.into_owned()))
                 }
+                Ok(Token::Url(url)) => {
+                    content.push(ContentItem::Url(context.parse_url(&url)))
+                }
                 Ok(Token::Function(name)) => {
                     content.push(try!(match_ignore_ascii_case! { name,
                         "counter" => input.parse_nested_block(|input| {
@@ -145,6 +206,25 @@
                             }).unwrap_or(list_style_type::computed_value::T::decimal);
                             Ok(ContentItem::Counters(name, separator, style))
                         }),
+                        "attr" => input.parse_nested_block(|input| {
+                            // Attribute names are matched in lower case, as in HTML documents.
+                            let name = try!(input.expect_ident()).to_ascii_lowercase();
+                            let type_ = input.try(|input| {
+                                let type_ = try!(input.expect_ident());
+                                match_ignore_ascii_case! { type_,
+                                    "string" => Ok(AttrType::String),
+                                    "integer" => Ok(AttrType::Integer),
+                                    "number" => Ok(AttrType::Number),
+                                    _ => Err(())
+                                }
+                            }).unwrap_or(AttrType::String);
+                            let fallback = if input.try(|input| input.expect_comma()).is_ok() {
+                                Some(try!(input.expect_string()).into_owned())
+                            } else {
+                                None
+                            };
+                            Ok(ContentItem::Attr(name, type_, fallback))
+                        }),
                         _ => return Err(())
                     }));
                 }
@@ -388,21 +388,24 @@ ${helpers.predefined_type("opacity",
 
 <%helpers:longhand name="filter">
     //pub use self::computed_value::T as SpecifiedValue;
+    use app_units::Au;
     use cssparser::ToCss;
+    use properties::style_struct_traits::Color;
     use std::fmt;
     use values::AuExtensionMethods;
     use values::CSSFloat;
-    use values::specified::{Angle, Length};
+    use values::specified::{Angle, CSSColor, Length};
 
     #[derive(Debug, Clone, PartialEq, HeapSizeOf)]
     pub struct SpecifiedValue(Vec<SpecifiedFilter>);
 
-    // TODO(pcwalton): `drop-shadow`
     #[derive(Clone, PartialEq, Debug, HeapSizeOf)]
     pub enum SpecifiedFilter {
         Blur(Length),
         Brightness(CSSFloat),
         Contrast(CSSFloat),
+        /// The horizontal and vertical offsets, the blur radius and the color of the shadow.
+        DropShadow(Length, Length, Length, Option<CSSColor>),
         Grayscale(CSSFloat),
         HueRotate(Angle),
         Invert(CSSFloat),
@@ -413,6 +416,7 @@ ${helpers.predefined_type("opacity",
 
     pub mod computed_value {
         use app_units::Au;
+        use cssparser::RGBA;
         use values::CSSFloat;
         use values::specified::{Angle};
 
@@ -421,6 +425,9 @@ ${helpers.predefined_type("opacity",
             Blur(Au),
             Brightness(CSSFloat),
             Contrast(CSSFloat),
+            /// The horizontal and vertical offsets, the blur radius and the color of the shadow,
+            /// with `currentColor` resolved.
+            DropShadow(Au, Au, Au, RGBA),
             Grayscale(CSSFloat),
             HueRotate(Angle),
             Invert(CSSFloat),
@@ -513,6 +520,17 @@ ${helpers.predefined_type("opacity",
                 }
                 computed_value::Filter::Brightness(value) => try!(write!(dest, "brightness({})", value)),
                 computed_value::Filter::Contrast(value) => try!(write!(dest, "contrast({})", value)),
+                computed_value::Filter::DropShadow(offset_x, offset_y, blur_radius, ref color) => {
+                    try!(dest.write_str("drop-shadow("));
+                    try!(offset_x.to_css(dest));
+                    try!(dest.write_str(" "));
+                    try!(offset_y.to_css(dest));
+                    try!(dest.write_str(" "));
+                    try!(blur_radius.to_css(dest));
+                    try!(dest.write_str(" "));
+                    try!(color.to_css(dest));
+                    try!(dest.write_str(")"));
+                }
                 computed_value::Filter::Grayscale(value) => try!(write!(dest, "grayscale({})", value)),
                 computed_value::Filter::HueRotate(value) => {
                     try!(dest.write_str("hue-rotate("));
@@ -538,6 +556,21 @@ ${helpers.predefined_type("opacity",
                 }
                 SpecifiedFilter::Brightness(value) => try!(write!(dest, "brightness({})", value)),
                 SpecifiedFilter::Contrast(value) => try!(write!(dest, "contrast({})", value)),
+                SpecifiedFilter::DropShadow(offset_x, offset_y, blur_radius, ref color) => {
+                    try!(dest.write_str("drop-shadow("));
+                    try!(offset_x.to_css(dest));
+                    try!(dest.write_str(" "));
+                    try!(offset_y.to_css(dest));
+                    if blur_radius != Length::Absolute(Au(0)) {
+                        try!(dest.write_str(" "));
+                        try!(blur_radius.to_css(dest));
+                    }
+                    if let Some(ref color) = *color {
+                        try!(dest.write_str(" "));
+                        try!(color.to_css(dest));
+                    }
+                    try!(dest.write_str(")"));
+                }
                 SpecifiedFilter::Grayscale(value) => try!(write!(dest, "grayscale({})", value)),
                 SpecifiedFilter::HueRotate(value) => {
                     try!(dest.write_str("hue-rotate("));
@@ -570,6 +603,7 @@ ${helpers.predefined_type("opacity",
                         "blur" => specified::Length::parse_non_negative(input).map(SpecifiedFilter::Blur),
                         "brightness" => parse_factor(input).map(SpecifiedFilter::Brightness),
                         "contrast" => parse_factor(input).map(SpecifiedFilter::Contrast),
+                        "drop-shadow" => parse_drop_shadow(input),
                         "grayscale" => parse_factor(input).map(SpecifiedFilter::Grayscale),
                         "hue-rotate" => Angle::parse(input).map(SpecifiedFilter::HueRotate),
                         "invert" => parse_factor(input).map(SpecifiedFilter::Invert),
@@ -587,6 +621,18 @@ ${helpers.predefined_type("opacity",
         }
     }
 
+    fn parse_drop_shadow(input: &mut Parser) -> Result<SpecifiedFilter, ()> {
+        let mut color = input.try(CSSColor::parse).ok();
+        let offset_x = try!(Length::parse(input));
+        let offset_y = try!(Length::parse(input));
+        let blur_radius = input.try(Length::parse_non_negative)
+                               .unwrap_or(Length::Absolute(Au(0)));
+        if color.is_none() {
+            color = input.try(CSSColor::parse).ok();
+        }
+        Ok(SpecifiedFilter::DropShadow(offset_x, offset_y, blur_radius, color))
+    }
+
     fn parse_factor(input: &mut Parser) -> Result<::values::CSSFloat, ()> {
         use cssparser::Token;
         match input.next() {
@@ -606,6 +652,18 @@ ${helpers.predefined_type("opacity",
                         computed_value::Filter::Blur(factor.to_computed_value(context)),
                     SpecifiedFilter::Brightness(factor) => computed_value::Filter::Brightness(factor),
                     SpecifiedFilter::Contrast(factor) => computed_value::Filter::Contrast(factor),
+                    SpecifiedFilter::DropShadow(offset_x, offset_y, blur_radius, ref color) => {
+                        let color = match color.as_ref().map(|color| color.parsed) {
+                            Some(::cssparser::Color::RGBA(rgba)) => rgba,
+                            Some(::cssparser::Color::CurrentColor) | None => {
+                                context.style().get_color().clone_color()
+                            }
+                        };
+                        computed_value::Filter::DropShadow(offset_x.to_computed_value(context),
+                                                           offset_y.to_computed_value(context),
+                                                           blur_radius.to_computed_value(context),
+                                                           color)
+                    }
                     SpecifiedFilter::Grayscale(factor) => computed_value::Filter::Grayscale(factor),
                     SpecifiedFilter::HueRotate(factor) => computed_value::Filter::HueRotate(factor),
                     SpecifiedFilter::Invert(factor) => computed_value::Filter::Invert(factor),
@@ -263,26 +263,37 @@ ${helpers.single_keyword("font-variant", "normal small-caps")}
     impl ToComputedValue for SpecifiedValue {
         type ComputedValue = computed_value::T;
 
+        /// Absolute sizes are scaled by the text zoom. Those that are relative to the font size of
+        /// the parent are not, since it was zoomed already, and those of the root are relative to
+        /// the zoomed initial font size.
         #[inline]
         fn to_computed_value<Cx: TContext>(&self, context: &Cx) -> computed_value::T {
+            let parent_font_size = if context.is_root_element() {
+                get_initial_value().scale_by(context.text_zoom())
+            } else {
+                context.inherited_style().get_font().clone_font_size()
+            };
             match self.0 {
                 LengthOrPercentage::Length(Length::FontRelative(value)) => {
-                    value.to_computed_value(context.inherited_style().get_font().clone_font_size(),
-                                            context.style().root_font_size())
+                    value.to_computed_value(parent_font_size, context.style().root_font_size())
                 }
                 LengthOrPercentage::Length(Length::ServoCharacterWidth(value)) => {
-                    value.to_computed_value(context.inherited_style().get_font().clone_font_size())
+                    value.to_computed_value(parent_font_size)
+                }
+                LengthOrPercentage::Length(Length::Absolute(length)) => {
+                    length.scale_by(context.text_zoom())
                 }
                 LengthOrPercentage::Length(l) => {
                     l.to_computed_value(context)
                 }
                 LengthOrPercentage::Percentage(Percentage(value)) => {
-                    context.inherited_style().get_font().clone_font_size().scale_by(value)
+                    parent_font_size.scale_by(value)
                 }
-                LengthOrPercentage::Calc(calc) => {
+                LengthOrPercentage::Calc(mut calc) => {
+                    let text_zoom = context.text_zoom();
+                    calc.absolute = calc.absolute.map(|length| length.scale_by(text_zoom));
                     let calc = calc.to_computed_value(context);
-                    calc.length() + context.inherited_style().get_font().clone_font_size()
-                                           .scale_by(calc.percentage())
+                    calc.length() + parent_font_size.scale_by(calc.percentage())
                 }
             }
         }
@@ -307,3 +318,256 @@ ${helpers.single_keyword("font-stretch",
                  expanded extra-expanded ultra-expanded")}
 
 ${helpers.single_keyword("font-kerning", "auto none normal", products="gecko")}
+
+// https://drafts.csswg.org/css-fonts-3/#propdef-font-variant-ligatures
+<%helpers:longhand name="font-variant-ligatures" products="servo">
+    use values::computed::ComputedValueAsSpecified;
+    pub use self::computed_value::T as SpecifiedValue;
+
+    impl ComputedValueAsSpecified for SpecifiedValue {}
+
+    pub mod computed_value {
+        use cssparser::ToCss;
+        use std::fmt;
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, HeapSizeOf)]
+        pub enum T {
+            /// The ligatures and contextual forms the font enables by default are used.
+            Normal,
+            /// All ligatures and contextual forms are disabled.
+            None,
+            /// Each kind of ligature is explicitly enabled (`Some(true)`), explicitly disabled
+            /// (`Some(false)`), or left to the font.
+            Values(Ligatures),
+        }
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, HeapSizeOf)]
+        pub struct Ligatures {
+            pub common: Option<bool>,
+            pub discretionary: Option<bool>,
+            pub historical: Option<bool>,
+            pub contextual: Option<bool>,
+        }
+
+        impl ToCss for T {
+            fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+                let ligatures = match *self {
+                    T::Normal => return dest.write_str("normal"),
+                    T::None => return dest.write_str("none"),
+                    T::Values(ref ligatures) => ligatures,
+                };
+
+                let mut first = true;
+                for &(value, enabled, disabled) in &[
+                    (ligatures.common, "common-ligatures", "no-common-ligatures"),
+                    (ligatures.discretionary, "discretionary-ligatures", "no-discretionary-ligatures"),
+                    (ligatures.historical, "historical-ligatures", "no-historical-ligatures"),
+                    (ligatures.contextual, "contextual", "no-contextual"),
+                ] {
+                    if let Some(value) = value {
+                        if !first {
+                            try!(dest.write_str(" "));
+                        }
+                        first = false;
+                        try!(dest.write_str(if value { enabled } else { disabled }));
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get_initial_value() -> computed_value::T {
+        computed_value::T::Normal
+    }
+
+    /// normal | none | [ <common-lig-values> || <discretionary-lig-values> ||
+    ///                   <historical-lig-values> || <contextual-alt-values> ]
+    pub fn parse(_context: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue, ()> {
+        if input.try(|input| input.expect_ident_matching("normal")).is_ok() {
+            return Ok(computed_value::T::Normal)
+        }
+        if input.try(|input| input.expect_ident_matching("none")).is_ok() {
+            return Ok(computed_value::T::None)
+        }
+
+        let mut ligatures = computed_value::Ligatures {
+            common: None,
+            discretionary: None,
+            historical: None,
+            contextual: None,
+        };
+        while let Ok(ident) = input.try(|input| input.expect_ident()) {
+            let (kind, enabled) = match_ignore_ascii_case! { ident,
+                "common-ligatures" => (&mut ligatures.common, true),
+                "no-common-ligatures" => (&mut ligatures.common, false),
+                "discretionary-ligatures" => (&mut ligatures.discretionary, true),
+                "no-discretionary-ligatures" => (&mut ligatures.discretionary, false),
+                "historical-ligatures" => (&mut ligatures.historical, true),
+                "no-historical-ligatures" => (&mut ligatures.historical, false),
+                "contextual" => (&mut ligatures.contextual, true),
+                "no-contextual" => (&mut ligatures.contextual, false),
+                _ => return Err(())
+            };
+            // Each kind of ligature may only be given once.
+            if kind.is_some() {
+                return Err(())
+            }
+            *kind = Some(enabled);
+        }
+
+        if ligatures.common.is_none() && ligatures.discretionary.is_none() &&
+                ligatures.historical.is_none() && ligatures.contextual.is_none() {
+            return Err(())
+        }
+        Ok(computed_value::T::Values(ligatures))
+    }
+</%helpers:longhand>
+
+// https://drafts.csswg.org/css-fonts-3/#propdef-font-feature-settings
+<%helpers:longhand name="font-feature-settings" products="servo">
+    use values::computed::ComputedValueAsSpecified;
+    pub use self::computed_value::T as SpecifiedValue;
+
+    impl ComputedValueAsSpecified for SpecifiedValue {}
+
+    pub mod computed_value {
+        use cssparser::{self, ToCss};
+        use std::fmt;
+
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, HeapSizeOf)]
+        pub enum T {
+            Normal,
+            Tags(Vec<FeatureTagValue>),
+        }
+
+        /// An OpenType feature tag, such as `smcp`, and the value to set it to. Zero disables
+        /// the feature, one enables it, and larger values select alternates.
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, HeapSizeOf)]
+        pub struct FeatureTagValue {
+            pub tag: String,
+            pub value: u32,
+        }
+
+        impl ToCss for FeatureTagValue {
+            fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+                try!(cssparser::serialize_string(&self.tag, dest));
+                match self.value {
+                    1 => Ok(()),
+                    0 => dest.write_str(" off"),
+                    value => write!(dest, " {}", value),
+                }
+            }
+        }
+
+        impl ToCss for T {
+            fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+                match *self {
+                    T::Normal => dest.write_str("normal"),
+                    T::Tags(ref tags) => {
+                        let mut iter = tags.iter();
+                        try!(iter.next().unwrap().to_css(dest));
+                        for tag in iter {
+                            try!(dest.write_str(", "));
+                            try!(tag.to_css(dest));
+                        }
+                        Ok(())
+                    }
+                }
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get_initial_value() -> computed_value::T {
+        computed_value::T::Normal
+    }
+
+    /// normal | <feature-tag-value>#
+    pub fn parse(_context: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue, ()> {
+        if input.try(|input| input.expect_ident_matching("normal")).is_ok() {
+            return Ok(computed_value::T::Normal)
+        }
+        input.parse_comma_separated(parse_one_feature_tag_value).map(computed_value::T::Tags)
+    }
+
+    /// <string> [ <integer> | on | off ]?
+    fn parse_one_feature_tag_value(input: &mut Parser)
+                                   -> Result<computed_value::FeatureTagValue, ()> {
+        let tag = try!(input.expect_string());
+
+        // Tags are exactly four printable ASCII characters.
+        if tag.len() != 4 || tag.chars().any(|c| c < ' ' || c > '~') {
+            return Err(())
+        }
+
+        let value = if let Ok(value) = input.try(|input| specified::parse_integer(input)) {
+            if value < 0 {
+                return Err(())
+            }
+            value as u32
+        } else if input.try(|input| input.expect_ident_matching("off")).is_ok() {
+            0
+        } else {
+            let _ = input.try(|input| input.expect_ident_matching("on"));
+            1
+        };
+
+        Ok(computed_value::FeatureTagValue {
+            tag: tag.into_owned(),
+            value: value,
+        })
+    }
+</%helpers:longhand>
+
+// The language of the text of an element, which generic families and the default font size
+// follow the font preferences of. Script sets it from `lang` attributes.
+<%helpers:longhand name="-servo-lang" products="servo" internal="True">
+    use string_cache::Atom;
+    use values::computed::ComputedValueAsSpecified;
+    pub use self::computed_value::T as SpecifiedValue;
+
+    impl ComputedValueAsSpecified for SpecifiedValue {}
+
+    pub mod computed_value {
+        use cssparser::{self, ToCss};
+        use std::fmt;
+        use string_cache::Atom;
+
+        /// A language tag, or `None` when the language is unknown.
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, HeapSizeOf)]
+        pub struct T(pub Option<Atom>);
+
+        impl ToCss for T {
+            fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+                match self.0 {
+                    Some(ref language_tag) => cssparser::serialize_string(language_tag, dest),
+                    None => dest.write_str("none"),
+                }
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get_initial_value() -> computed_value::T {
+        computed_value::T(None)
+    }
+
+    /// The value of a `lang` attribute, which is empty when the language is unknown.
+    pub fn for_language(language_tag: &str) -> SpecifiedValue {
+        if language_tag.is_empty() {
+            computed_value::T(None)
+        } else {
+            computed_value::T(Some(Atom::from(language_tag)))
+        }
+    }
+
+    /// none | <string>
+    pub fn parse(_context: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue, ()> {
+        if input.try(|input| input.expect_ident_matching("none")).is_ok() {
+            return Ok(computed_value::T(None))
+        }
+        Ok(for_language(&try!(input.expect_string())))
+    }
+</%helpers:longhand>
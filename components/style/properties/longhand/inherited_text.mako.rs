@@ -304,20 +304,29 @@ ${helpers.single_keyword("text-justify",
                          products="servo")}
 
 <%helpers:longhand name="-servo-text-decorations-in-effect"
-                derived_from="display text-decoration" need_clone="True" products="servo">
-    use cssparser::{RGBA, ToCss};
+                derived_from="display text-decoration-line" need_clone="True" products="servo">
+    use cssparser::{Color as CSSParserColor, RGBA, ToCss};
     use std::fmt;
 
     use values::computed::ComputedValueAsSpecified;
+    use properties::longhands::text_decoration_style;
     use properties::style_struct_traits::{Box, Color, Text};
 
     impl ComputedValueAsSpecified for SpecifiedValue {}
 
     #[derive(Clone, PartialEq, Copy, Debug, HeapSizeOf)]
     pub struct SpecifiedValue {
-        pub underline: Option<RGBA>,
-        pub overline: Option<RGBA>,
-        pub line_through: Option<RGBA>,
+        pub underline: Option<TextDecoration>,
+        pub overline: Option<TextDecoration>,
+        pub line_through: Option<TextDecoration>,
+    }
+
+    /// A line decorating text, painted with the color and style of the box that it is
+    /// propagated from.
+    #[derive(Clone, PartialEq, Copy, Debug, HeapSizeOf)]
+    pub struct TextDecoration {
+        pub color: RGBA,
+        pub style: text_decoration_style::computed_value::T,
     }
 
     pub mod computed_value {
@@ -340,39 +349,53 @@ ${helpers.single_keyword("text-justify",
         }
     }
 
-    fn maybe<Cx: TContext>(flag: bool, context: &Cx) -> Option<RGBA> {
-        if flag {
-            Some(context.style().get_color().clone_color())
-        } else {
-            None
+    /// The decoration that this box specifies, which replaces the line of the same kind that it
+    /// would propagate from its ancestors.
+    fn own_decoration<Cx: TContext>(context: &Cx) -> TextDecoration {
+        let text = context.style().get_text();
+        let color = match text.clone_text_decoration_color() {
+            CSSParserColor::RGBA(rgba) => rgba,
+            CSSParserColor::CurrentColor => context.style().get_color().clone_color(),
+        };
+        TextDecoration {
+            color: color,
+            style: text.clone_text_decoration_style(),
         }
     }
 
     fn derive<Cx: TContext>(context: &Cx) -> computed_value::T {
-        // Start with no declarations if this is an atomic inline-level box; otherwise, start with the
-        // declarations in effect and add in the text decorations that this block specifies.
-        let mut result = match context.style().get_box().clone_display() {
-            super::display::computed_value::T::inline_block |
-            super::display::computed_value::T::inline_table => SpecifiedValue {
-                underline: None,
-                overline: None,
-                line_through: None,
-            },
-            _ => context.inherited_style().get_inheritedtext().clone__servo_text_decorations_in_effect()
+        use super::display::computed_value::T as display;
+        use super::float::computed_value::T as float;
+        use super::position::computed_value::T as position;
+
+        // Decorations are not propagated to atomic inline-level boxes nor to out-of-flow boxes,
+        // so start with no decorations for those; otherwise, start with the decorations in
+        // effect and add in the ones that this box specifies.
+        let box_ = context.style().get_box();
+        let atomic = matches!(box_.clone_display(), display::inline_block | display::inline_table);
+        let out_of_flow = box_.clone_float() != float::none ||
+            matches!(box_.clone_position(), position::absolute | position::fixed);
+        let mut result = if atomic || out_of_flow {
+            get_initial_value()
+        } else {
+            context.inherited_style().get_inheritedtext().clone__servo_text_decorations_in_effect()
         };
 
-        result.underline = maybe(context.style().get_text().has_underline()
-                                 || result.underline.is_some(), context);
-        result.overline = maybe(context.style().get_text().has_overline()
-                                || result.overline.is_some(), context);
-        result.line_through = maybe(context.style().get_text().has_line_through()
-                                    || result.line_through.is_some(), context);
-
+        let text = context.style().get_text();
+        if text.has_underline() {
+            result.underline = Some(own_decoration(context));
+        }
+        if text.has_overline() {
+            result.overline = Some(own_decoration(context));
+        }
+        if text.has_line_through() {
+            result.line_through = Some(own_decoration(context));
+        }
         result
     }
 
     #[inline]
-    pub fn derive_from_text_decoration<Cx: TContext>(context: &mut Cx) {
+    pub fn derive_from_text_decoration_line<Cx: TContext>(context: &mut Cx) {
         let derived = derive(context);
         context.mutate_style().mutate_inheritedtext().set__servo_text_decorations_in_effect(derived);
     }
@@ -422,6 +445,76 @@ ${helpers.single_keyword("text-justify",
     }
 </%helpers:single_keyword_computed>
 
+// https://drafts.csswg.org/css-text-3/#tab-size-property
+<%helpers:longhand name="tab-size" products="servo">
+    use cssparser::ToCss;
+    use std::fmt;
+    use values::AuExtensionMethods;
+
+    #[derive(Debug, Clone, Copy, PartialEq, HeapSizeOf)]
+    pub enum SpecifiedValue {
+        Spaces(u32),
+        Length(specified::Length),
+    }
+
+    impl ToCss for SpecifiedValue {
+        fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+            match *self {
+                SpecifiedValue::Spaces(count) => write!(dest, "{}", count),
+                SpecifiedValue::Length(l) => l.to_css(dest),
+            }
+        }
+    }
+
+    pub mod computed_value {
+        use app_units::Au;
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, HeapSizeOf)]
+        pub enum T {
+            /// A multiple of the advance width of the space character.
+            Spaces(u32),
+            Length(Au),
+        }
+    }
+
+    impl ToCss for computed_value::T {
+        fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+            match *self {
+                computed_value::T::Spaces(count) => write!(dest, "{}", count),
+                computed_value::T::Length(l) => l.to_css(dest),
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get_initial_value() -> computed_value::T {
+        computed_value::T::Spaces(8)
+    }
+
+    impl ToComputedValue for SpecifiedValue {
+        type ComputedValue = computed_value::T;
+
+        #[inline]
+        fn to_computed_value<Cx: TContext>(&self, context: &Cx) -> computed_value::T {
+            match *self {
+                SpecifiedValue::Spaces(count) => computed_value::T::Spaces(count),
+                SpecifiedValue::Length(l) =>
+                    computed_value::T::Length(l.to_computed_value(context)),
+            }
+        }
+    }
+
+    pub fn parse(_context: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue, ()> {
+        if let Ok(count) = input.try(|input| specified::parse_integer(input)) {
+            // Negative values are invalid
+            if count < 0 {
+                return Err(())
+            }
+            return Ok(SpecifiedValue::Spaces(count as u32))
+        }
+        specified::Length::parse_non_negative(input).map(SpecifiedValue::Length)
+    }
+</%helpers:longhand>
+
 <%helpers:longhand name="text-shadow">
     use cssparser::{self, ToCss};
     use std::fmt;
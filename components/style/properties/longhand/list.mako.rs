@@ -4,6 +4,8 @@
 
 <%namespace name="helpers" file="/helpers.mako.rs" />
 
+<% from data import to_rust_ident %>
+
 <% data.new_style_struct("List", inherited=True) %>
 
 ${helpers.single_keyword("list-style-position", "outside inside")}
@@ -18,12 +20,80 @@ ${helpers.single_keyword("list-style-position", "outside inside")}
 // [1]: http://dev.w3.org/csswg/css-counter-styles/
 ${helpers.single_keyword("list-style-type", """
     disc none circle square decimal lower-alpha upper-alpha  disclosure-open disclosure-closed
-""", extra_servo_values="""arabic-indic bengali cambodian cjk-decimal devanagari
-                           gujarati gurmukhi kannada khmer lao malayalam mongolian
-                           myanmar oriya persian telugu thai tibetan cjk-earthly-branch
-                           cjk-heavenly-stem lower-greek hiragana hiragana-iroha katakana
-                           katakana-iroha""",
-    gecko_constant_prefix="NS_STYLE_LIST_STYLE")}
+""", gecko_constant_prefix="NS_STYLE_LIST_STYLE", products="gecko")}
+
+// Servo also accepts the names of the counter styles that `@counter-style` rules define.
+<%helpers:longhand name="list-style-type" products="servo">
+    use std::ascii::AsciiExt;
+    use values::computed::ComputedValueAsSpecified;
+
+    <% list_style_type_keywords = """
+        disc none circle square decimal lower-alpha upper-alpha disclosure-open disclosure-closed
+        arabic-indic bengali cambodian cjk-decimal devanagari gujarati gurmukhi kannada khmer lao
+        malayalam mongolian myanmar oriya persian telugu thai tibetan cjk-earthly-branch
+        cjk-heavenly-stem lower-greek hiragana hiragana-iroha katakana katakana-iroha
+    """.split() %>
+
+    pub use self::computed_value::T as SpecifiedValue;
+
+    impl ComputedValueAsSpecified for SpecifiedValue {}
+
+    pub mod computed_value {
+        use cssparser::ToCss;
+        use std::fmt;
+        use string_cache::Atom;
+
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone, PartialEq, Eq, HeapSizeOf)]
+        pub enum T {
+            % for keyword in list_style_type_keywords:
+                ${to_rust_ident(keyword)},
+            % endfor
+            /// A counter style that a `@counter-style` rule may define.
+            Custom(Atom),
+        }
+
+        impl T {
+            /// The predefined counter style by the name `ident`, if there is one.
+            pub fn from_ident(ident: &str) -> Option<T> {
+                match_ignore_ascii_case! { ident,
+                    % for keyword in list_style_type_keywords:
+                        "${keyword}" => Some(T::${to_rust_ident(keyword)}),
+                    % endfor
+                    _ => None
+                }
+            }
+        }
+
+        impl ToCss for T {
+            fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+                match *self {
+                    % for keyword in list_style_type_keywords:
+                        T::${to_rust_ident(keyword)} => dest.write_str("${keyword}"),
+                    % endfor
+                    T::Custom(ref name) => dest.write_str(&name),
+                }
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get_initial_value() -> computed_value::T {
+        computed_value::T::disc
+    }
+
+    /// <predefined counter style> | <counter-style-name>
+    pub fn parse(_context: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue, ()> {
+        let ident = try!(input.expect_ident());
+        if let Some(value) = SpecifiedValue::from_ident(&ident) {
+            return Ok(value)
+        }
+        if ident.eq_ignore_ascii_case("default") {
+            return Err(())
+        }
+        Ok(SpecifiedValue::Custom(Atom::from(&*ident)))
+    }
+</%helpers:longhand>
 
 <%helpers:longhand name="list-style-image">
     use cssparser::{ToCss, Token};
@@ -91,6 +161,7 @@ ${helpers.single_keyword("list-style-type", """
 </%helpers:longhand>
 
 <%helpers:longhand name="quotes">
+    use std::ascii::AsciiExt;
     use std::borrow::Cow;
     use std::fmt;
     use values::computed::ComputedValueAsSpecified;
@@ -108,6 +179,9 @@ ${helpers.single_keyword("list-style-type", """
 
     impl ToCss for SpecifiedValue {
         fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+            if self.0.is_empty() {
+                return dest.write_str("none")
+            }
             let mut first = true;
             for pair in &self.0 {
                 if !first {
@@ -130,6 +204,33 @@ ${helpers.single_keyword("list-style-type", """
         ])
     }
 
+    /// The quotes that are used in the language of the given tag, if it is known. The `lang`
+    /// attribute sets them as a presentational hint. They are the delimiters of CLDR.
+    pub fn for_language(language_tag: &str) -> Option<SpecifiedValue> {
+        let language = language_tag.split(|c| c == '-' || c == '_').next().unwrap_or("");
+        let (outer, inner) = match &*language.to_ascii_lowercase() {
+            "en" | "nl" | "pt" | "tr" | "ko" | "zh" => {
+                (("\u{201c}", "\u{201d}"), ("\u{2018}", "\u{2019}"))
+            }
+            "fr" => (("\u{ab}\u{a0}", "\u{a0}\u{bb}"), ("\u{201c}", "\u{201d}")),
+            "es" | "it" | "ca" | "el" => (("\u{ab}", "\u{bb}"), ("\u{201c}", "\u{201d}")),
+            "de" | "cs" | "sk" | "is" | "lt" => {
+                (("\u{201e}", "\u{201c}"), ("\u{201a}", "\u{2018}"))
+            }
+            "ru" | "uk" | "be" => (("\u{ab}", "\u{bb}"), ("\u{201e}", "\u{201c}")),
+            "pl" | "hu" | "ro" => (("\u{201e}", "\u{201d}"), ("\u{ab}", "\u{bb}")),
+            "nb" | "nn" | "no" => (("\u{ab}", "\u{bb}"), ("\u{2018}", "\u{2019}")),
+            "sv" | "fi" => (("\u{201d}", "\u{201d}"), ("\u{2019}", "\u{2019}")),
+            "da" => (("\u{bb}", "\u{ab}"), ("\u{203a}", "\u{2039}")),
+            "ja" => (("\u{300c}", "\u{300d}"), ("\u{300e}", "\u{300f}")),
+            _ => return None,
+        };
+        Some(SpecifiedValue(vec![
+            (outer.0.to_owned(), outer.1.to_owned()),
+            (inner.0.to_owned(), inner.1.to_owned()),
+        ]))
+    }
+
     pub fn parse(_: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue,()> {
         if input.try(|input| input.expect_ident_matching("none")).is_ok() {
             return Ok(SpecifiedValue(Vec::new()))
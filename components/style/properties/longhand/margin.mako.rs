@@ -10,3 +10,8 @@
     ${helpers.predefined_type("margin-" + side, "LengthOrPercentageOrAuto",
                               "computed::LengthOrPercentageOrAuto::Length(Au(0))")}
 % endfor
+
+// https://drafts.csswg.org/css-logical-props/#margin-properties
+% for side in ["block-start", "block-end", "inline-start", "inline-end"]:
+    ${helpers.logical_longhand("margin-" + side, "margin-%s", side)}
+% endfor
@@ -11,3 +11,8 @@
                                "computed::LengthOrPercentage::Length(Au(0))",
                                "parse_non_negative")}
 % endfor
+
+// https://drafts.csswg.org/css-logical-props/#padding-properties
+% for side in ["block-start", "block-end", "inline-start", "inline-end"]:
+    ${helpers.logical_longhand("padding-" + side, "padding-%s", side)}
+% endfor
@@ -16,44 +16,169 @@
         use cssparser::ToCss;
         use std::fmt;
         use style_traits::cursor::Cursor;
+        use url::Url;
+        use values::LocalToCss;
 
         #[derive(Clone, PartialEq, Eq, Copy, Debug, HeapSizeOf)]
-        pub enum T {
+        pub enum Keyword {
             AutoCursor,
             SpecifiedCursor(Cursor),
         }
 
-        impl ToCss for T {
+        /// A `url()` cursor image, with the position of its hotspot if it was specified.
+        #[derive(Clone, PartialEq, Debug, HeapSizeOf)]
+        pub struct Image {
+            pub url: Url,
+            pub hotspot: Option<(f32, f32)>,
+        }
+
+        /// The cursor images to try in order, then the cursor to use if none of them loads.
+        #[derive(Clone, PartialEq, Debug, HeapSizeOf)]
+        pub struct T {
+            pub images: Vec<Image>,
+            pub keyword: Keyword,
+        }
+
+        impl ToCss for Keyword {
             fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
                 match *self {
-                    T::AutoCursor => dest.write_str("auto"),
-                    T::SpecifiedCursor(c) => c.to_css(dest),
+                    Keyword::AutoCursor => dest.write_str("auto"),
+                    Keyword::SpecifiedCursor(c) => c.to_css(dest),
+                }
+            }
+        }
+
+        impl ToCss for Image {
+            fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+                try!(self.url.to_css(dest));
+                if let Some((x, y)) = self.hotspot {
+                    try!(write!(dest, " {} {}", x, y));
+                }
+                Ok(())
+            }
+        }
+
+        impl ToCss for T {
+            fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+                for image in &self.images {
+                    try!(image.to_css(dest));
+                    try!(dest.write_str(", "));
                 }
+                self.keyword.to_css(dest)
             }
         }
     }
 
     #[inline]
     pub fn get_initial_value() -> computed_value::T {
-        computed_value::T::AutoCursor
+        computed_value::T {
+            images: vec![],
+            keyword: computed_value::Keyword::AutoCursor,
+        }
     }
-    pub fn parse(_context: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue, ()> {
+
+    // [ [<url> [<x> <y>]?,]* [ auto | <keyword> ] ]
+    pub fn parse(context: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue, ()> {
         use std::ascii::AsciiExt;
         use style_traits::cursor::Cursor;
+        let mut images = vec![];
+        loop {
+            let url = match input.try(|input| input.expect_url()) {
+                Ok(url) => context.parse_url(&url),
+                Err(()) => break,
+            };
+            let hotspot = input.try(|input| -> Result<(f32, f32), ()> {
+                let x = try!(input.expect_number());
+                let y = try!(input.expect_number());
+                Ok((x, y))
+            }).ok();
+            try!(input.expect_comma());
+            images.push(computed_value::Image {
+                url: url,
+                hotspot: hotspot,
+            });
+        }
         let ident = try!(input.expect_ident());
-        if ident.eq_ignore_ascii_case("auto") {
-            Ok(SpecifiedValue::AutoCursor)
+        let keyword = if ident.eq_ignore_ascii_case("auto") {
+            computed_value::Keyword::AutoCursor
         } else {
-            Cursor::from_css_keyword(&ident)
-            .map(SpecifiedValue::SpecifiedCursor)
-        }
+            computed_value::Keyword::SpecifiedCursor(try!(Cursor::from_css_keyword(&ident)))
+        };
+        Ok(SpecifiedValue {
+            images: images,
+            keyword: keyword,
+        })
     }
 </%helpers:longhand>
 
-// NB: `pointer-events: auto` (and use of `pointer-events` in anything that isn't SVG, in fact)
-// is nonstandard, slated for CSS4-UI.
-// TODO(pcwalton): SVG-only values.
-${helpers.single_keyword("pointer-events", "auto none")}
+// The values other than `auto` and `none` only apply to SVG, and behave as `auto` elsewhere.
+${helpers.single_keyword("pointer-events",
+                         "auto none visiblepainted visiblefill visiblestroke visible painted fill "
+                         + "stroke all")}
+
+<%helpers:longhand name="caret-color" products="servo">
+    use cssparser::ToCss;
+    use std::fmt;
+
+    #[derive(Debug, Clone, PartialEq, HeapSizeOf)]
+    pub enum SpecifiedValue {
+        Auto,
+        Specified(specified::CSSColor),
+    }
+
+    impl ToCss for SpecifiedValue {
+        fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+            match *self {
+                SpecifiedValue::Auto => dest.write_str("auto"),
+                SpecifiedValue::Specified(ref color) => color.to_css(dest),
+            }
+        }
+    }
+
+    pub mod computed_value {
+        use cssparser::Color;
+
+        /// The color of the caret, or `None` for `auto`, which paints it with the color of the
+        /// text.
+        #[derive(Debug, Clone, Copy, PartialEq, HeapSizeOf)]
+        pub struct T(pub Option<Color>);
+    }
+
+    impl ToCss for computed_value::T {
+        fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+            match self.0 {
+                None => dest.write_str("auto"),
+                Some(color) => color.to_css(dest),
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get_initial_value() -> computed_value::T {
+        computed_value::T(None)
+    }
+
+    impl ToComputedValue for SpecifiedValue {
+        type ComputedValue = computed_value::T;
+
+        #[inline]
+        fn to_computed_value<Cx: TContext>(&self, context: &Cx) -> computed_value::T {
+            match *self {
+                SpecifiedValue::Auto => computed_value::T(None),
+                SpecifiedValue::Specified(ref color) =>
+                    computed_value::T(Some(color.to_computed_value(context)))
+            }
+        }
+    }
+
+    pub fn parse(_context: &ParserContext, input: &mut Parser) -> Result<SpecifiedValue, ()> {
+        if input.try(|input| input.expect_ident_matching("auto")).is_ok() {
+            Ok(SpecifiedValue::Auto)
+        } else {
+            specified::CSSColor::parse(input).map(SpecifiedValue::Specified)
+        }
+    }
+</%helpers:longhand>
 
 ${helpers.single_keyword("-moz-user-input", "none enabled disabled", products="gecko",
                          gecko_ffi_name="mUserInput", gecko_constant_prefix="NS_STYLE_USER_INPUT")}
@@ -124,9 +124,34 @@ ${helpers.predefined_type("max-height",
                           "computed::LengthOrPercentageOrNone::None",
                           "parse_non_negative")}
 
+// https://drafts.csswg.org/css-logical-props/#position-properties
+% for side in ["block-start", "block-end", "inline-start", "inline-end"]:
+    ${helpers.logical_longhand("inset-" + side, "%s", side)}
+% endfor
+
+// https://drafts.csswg.org/css-logical-props/#dimension-properties
+% for axis in ["inline", "block"]:
+    ${helpers.logical_longhand(axis + "-size", "%s", axis)}
+    ${helpers.logical_longhand("min-" + axis + "-size", "min-%s", axis)}
+    ${helpers.logical_longhand("max-" + axis + "-size", "max-%s", axis)}
+% endfor
+
 ${helpers.single_keyword("box-sizing",
                          "content-box border-box")}
 
 // CSS Image Values and Replaced Content Module Level 3
 // https://drafts.csswg.org/css-images-3/
-${helpers.single_keyword("object-fit", "fill contain cover none scale-down", products="gecko")}
+${helpers.single_keyword("object-fit", "fill contain cover none scale-down")}
+
+<%helpers:longhand name="object-position">
+    pub use super::background_position::{SpecifiedValue, computed_value, parse};
+    use values::computed::LengthOrPercentage;
+
+    #[inline]
+    pub fn get_initial_value() -> computed_value::T {
+        computed_value::T {
+            horizontal: LengthOrPercentage::Percentage(0.5),
+            vertical: LengthOrPercentage::Percentage(0.5),
+        }
+    }
+</%helpers:longhand>
@@ -39,3 +39,25 @@ ${helpers.predefined_type(
 // CSS Masking Module Level 1
 // https://www.w3.org/TR/css-masking-1/
 ${helpers.single_keyword("mask-type", "luminance alpha", products="gecko")}
+
+${helpers.single_keyword("mask-mode", "match-source alpha luminance", products="servo")}
+
+<%helpers:longhand name="mask-image" products="servo">
+    pub use properties::longhands::background_image::{SpecifiedValue, computed_value};
+    pub use properties::longhands::background_image::{get_initial_value, parse};
+</%helpers:longhand>
+
+<%helpers:longhand name="mask-position" products="servo">
+    pub use properties::longhands::background_position::{SpecifiedValue, computed_value};
+    pub use properties::longhands::background_position::{get_initial_value, parse};
+</%helpers:longhand>
+
+<%helpers:longhand name="mask-repeat" products="servo">
+    pub use properties::longhands::background_repeat::{SpecifiedValue, computed_value};
+    pub use properties::longhands::background_repeat::{get_initial_value, parse};
+</%helpers:longhand>
+
+<%helpers:longhand name="mask-size" products="servo">
+    pub use properties::longhands::background_size::{SpecifiedValue, computed_value};
+    pub use properties::longhands::background_size::{get_initial_value, parse};
+</%helpers:longhand>
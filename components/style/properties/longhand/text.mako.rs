@@ -16,8 +16,7 @@ ${helpers.single_keyword("text-overflow", "clip ellipsis")}
 
 ${helpers.single_keyword("unicode-bidi", "normal embed isolate bidi-override isolate-override plaintext")}
 
-<%helpers:longhand name="${'text-decoration' if product == 'servo' else 'text-decoration-line'}"
-                   custom_cascade="${product == 'servo'}">
+<%helpers:longhand name="text-decoration-line">
     use cssparser::ToCss;
     use std::fmt;
     use values::computed::ComputedValueAsSpecified;
@@ -99,25 +98,14 @@ ${helpers.single_keyword("unicode-bidi", "normal embed isolate bidi-override iso
 
         if !empty { Ok(result) } else { Err(()) }
     }
-
-    % if product == "servo":
-        fn cascade_property_custom<C: ComputedValues>(
-                                   _declaration: &PropertyDeclaration,
-                                   _inherited_style: &C,
-                                   context: &mut computed::Context<C>,
-                                   _seen: &mut PropertyBitField,
-                                   _cacheable: &mut bool,
-                                   _error_reporter: &mut StdBox<ParseErrorReporter + Send>) {
-                longhands::_servo_text_decorations_in_effect::derive_from_text_decoration(context);
-        }
-    % endif
 </%helpers:longhand>
 
 ${helpers.single_keyword("text-decoration-style",
-                         "solid double dotted dashed wavy -moz-none",
-                         products="gecko")}
+                         "solid double dotted dashed wavy",
+                         extra_gecko_values="-moz-none",
+                         need_clone=True)}
 
-${helpers.predefined_type(
-    "text-decoration-color", "CSSColor",
-    "CSSParserColor::RGBA(RGBA { red: 0.0, green: 0.0, blue: 0.0, alpha: 1.0 })",
-    products="gecko")}
+${helpers.predefined_type("text-decoration-color",
+                          "CSSColor",
+                          "::cssparser::Color::CurrentColor",
+                          need_clone=True)}
@@ -12,5 +12,8 @@
 ${helpers.single_keyword("ime-mode", "normal auto active disabled inactive", products="gecko",
                          gecko_ffi_name="mIMEMode")}
 
+// https://drafts.csswg.org/css-ui-4/#content-selection
+${helpers.single_keyword("user-select", "auto text none all", products="servo")}
+
 ${helpers.single_keyword("-moz-user-select", "auto text none all", products="gecko",
                          gecko_ffi_name="mUserSelect", gecko_constant_prefix="NS_STYLE_USER_SELECT")}
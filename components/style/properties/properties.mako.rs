@@ -18,11 +18,13 @@ use std::fmt::Write;
 use std::intrinsics;
 use std::mem;
 use std::sync::Arc;
+use std::sync::atomic::{ATOMIC_USIZE_INIT, AtomicUsize, Ordering};
 
 use app_units::Au;
 use cssparser::Color as CSSParserColor;
 use cssparser::{Parser, RGBA, AtRuleParser, DeclarationParser, Delimiter,
-                DeclarationListParser, parse_important, ToCss, TokenSerializationType};
+                DeclarationListParser, parse_important, SourcePosition, ToCss,
+                TokenSerializationType};
 use error_reporting::ParseErrorReporter;
 use url::Url;
 use euclid::SideOffsets2D;
@@ -32,7 +34,10 @@ use computed_values;
 use logical_geometry::{LogicalMargin, PhysicalSide, WritingMode};
 use parser::{ParserContext, ParserContextExtraData, log_css_error};
 use selectors::matching::DeclarationBlock;
+use style_traits::forced_colors::{ColorRole, ForcedColors};
 use stylesheets::Origin;
+use time::precise_time_ns;
+use util::opts;
 use values::AuExtensionMethods;
 use values::computed::{self, TContext, ToComputedValue};
 use values::specified::BorderStyle;
@@ -121,6 +126,15 @@ pub mod shorthands {
         Ok((top, right, bottom, left))
     }
 
+    /// Parses the values of the start and end sides of an axis, where the end defaults to the
+    /// start.
+    fn parse_start_and_end<F, T>(input: &mut Parser, parse_one: F) -> Result<(T, T), ()>
+    where F: Fn(&mut Parser) -> Result<T, ()>, F: Copy, T: Clone {
+        let start = try!(parse_one(input));
+        let end = input.try(parse_one).unwrap_or(start.clone());
+        Ok((start, end))
+    }
+
     <%include file="/shorthand/background.mako.rs" />
     <%include file="/shorthand/border.mako.rs" />
     <%include file="/shorthand/box.mako.rs" />
@@ -131,6 +145,8 @@ pub mod shorthands {
     <%include file="/shorthand/margin.mako.rs" />
     <%include file="/shorthand/outline.mako.rs" />
     <%include file="/shorthand/padding.mako.rs" />
+    <%include file="/shorthand/position.mako.rs" />
+    <%include file="/shorthand/svg.mako.rs" />
     <%include file="/shorthand/text.mako.rs" />
 }
 
@@ -534,13 +550,32 @@ impl<'a, 'b> DeclarationParser for PropertyDeclarationParser<'a, 'b> {
 
 pub fn parse_property_declaration_list(context: &ParserContext, input: &mut Parser)
                                        -> PropertyDeclarationBlock {
+    parse_property_declaration_list_with_positions(context, input, |_, _, _| {})
+}
+
+/// Parses a list of declarations like `parse_property_declaration_list`, and
+/// calls `declaration_found` with the positions before and after each of them,
+/// valid or not. These include the whitespace and semicolons around them.
+pub fn parse_property_declaration_list_with_positions<F>(context: &ParserContext,
+                                                         input: &mut Parser,
+                                                         mut declaration_found: F)
+                                                         -> PropertyDeclarationBlock
+                                                         where F: FnMut(&Parser,
+                                                                        SourcePosition,
+                                                                        SourcePosition) {
     let mut important_declarations = Vec::new();
     let mut normal_declarations = Vec::new();
     let parser = PropertyDeclarationParser {
         context: context,
     };
     let mut iter = DeclarationListParser::new(input, parser);
-    while let Some(declaration) = iter.next() {
+    loop {
+        let start = iter.input.position();
+        let declaration = match iter.next() {
+            Some(declaration) => declaration,
+            None => break,
+        };
+        declaration_found(&*iter.input, start, iter.input.position());
         match declaration {
             Ok((results, important)) => {
                 if important {
@@ -1167,15 +1202,22 @@ pub mod style_structs {
                     self.outline_width != ::app_units::Au(0)
                 }
             % elif style_struct.trait_name == "Text":
-                <% text_decoration_field = 'text_decoration' if product == 'servo' else 'text_decoration_line' %>
                 fn has_underline(&self) -> bool {
-                    self.${text_decoration_field}.underline
+                    self.text_decoration_line.underline
                 }
                 fn has_overline(&self) -> bool {
-                    self.${text_decoration_field}.overline
+                    self.text_decoration_line.overline
                 }
                 fn has_line_through(&self) -> bool {
-                    self.${text_decoration_field}.line_through
+                    self.text_decoration_line.line_through
+                }
+                fn clone_text_decoration_style(&self) ->
+                    longhands::text_decoration_style::computed_value::T {
+                    self.text_decoration_style.clone()
+                }
+                fn clone_text_decoration_color(&self) ->
+                    longhands::text_decoration_color::computed_value::T {
+                    self.text_decoration_color.clone()
                 }
             % endif
         }
@@ -1482,6 +1524,55 @@ impl ServoComputedValues {
         false
     }
 
+    /// Returns true if `will-change` announces a change of a property that is animated on a
+    /// layer of its own.
+    pub fn will_change_requires_layer(&self) -> bool {
+        let will_change = &self.get_box().will_change;
+        will_change.contains("transform") || will_change.contains("opacity")
+    }
+
+    /// Returns true if this element is the containing block of its fixed-position descendants,
+    /// rather than the viewport, as elements with a filter or with layout or paint containment
+    /// are.
+    pub fn is_containing_block_for_fixed_descendants(&self) -> bool {
+        let contain = self.get_box().contain;
+        !self.get_effects().filter.is_empty() || contain.layout || contain.paint
+    }
+
+    /// The overflow of this element in each axis, where paint containment clips contents that
+    /// would otherwise overflow visibly, as `overflow: hidden` does.
+    pub fn clipped_overflow(&self) -> (computed_values::overflow_x::T,
+                                       computed_values::overflow_x::T) {
+        let box_style = self.get_box();
+        let (overflow_x, overflow_y) = (box_style.overflow_x, box_style.overflow_y.0);
+        if !box_style.contain.paint {
+            return (overflow_x, overflow_y)
+        }
+        let clip = |overflow| match overflow {
+            computed_values::overflow_x::T::visible => computed_values::overflow_x::T::hidden,
+            overflow => overflow,
+        };
+        (clip(overflow_x), clip(overflow_y))
+    }
+
+    /// Returns true if what happens inside this element cannot change the layout of anything
+    /// outside of it, so that its subtree can be laid out again on its own: either it is
+    /// absolutely positioned with a definite size, or it has layout and size containment.
+    pub fn is_reflow_root(&self) -> bool {
+        let contain = self.get_box().contain;
+        if contain.layout && contain.size {
+            return true
+        }
+        let position = self.get_position();
+        match self.get_box().position {
+            computed_values::position::T::absolute | computed_values::position::T::fixed => {
+                position.width != computed::LengthOrPercentageOrAuto::Auto &&
+                    position.height != computed::LengthOrPercentageOrAuto::Auto
+            }
+            _ => false,
+        }
+    }
+
     pub fn computed_value_to_string(&self, name: &str) -> Result<String, ()> {
         match name {
             % for style_struct in data.active_style_structs():
@@ -1489,6 +1580,16 @@ impl ServoComputedValues {
                 "${longhand.name}" => Ok(self.${style_struct.ident}.${longhand.ident}.to_css_string()),
                 % endfor
             % endfor
+            % for property in data.longhands:
+                % if property.logical:
+                    "${property.name}" => {
+                        <%call expr="helpers.logical_match(property, 'self.writing_mode')"
+                               args="physical">
+                            self.computed_value_to_string("${physical.name}")
+                        </%call>
+                    }
+                % endif
+            % endfor
             _ => {
                 let name = try!(::custom_properties::parse_name(name));
                 let map = try!(self.custom_properties.as_ref().ok_or(()));
@@ -1567,16 +1668,19 @@ lazy_static! {
 #[allow(unused_mut, unused_imports)]
 fn cascade_with_cached_declarations<C: ComputedValues>(
         viewport_size: Size2D<Au>,
+        text_zoom: f32,
         applicable_declarations: &[DeclarationBlock<Vec<PropertyDeclaration>>],
         shareable: bool,
         parent_style: &C,
         cached_style: &C,
         custom_properties: Option<Arc<::custom_properties::ComputedValuesMap>>,
+        forced_colors: Option<(&ForcedColors, ColorRole)>,
         mut error_reporter: StdBox<ParseErrorReporter + Send>)
         -> C {
     let mut context = computed::Context {
         is_root_element: false,
         viewport_size: viewport_size,
+        text_zoom: text_zoom,
         inherited_style: parent_style,
         style: C::new(
             custom_properties,
@@ -1594,11 +1698,13 @@ fn cascade_with_cached_declarations<C: ComputedValues>(
         ),
     };
     let mut seen = PropertyBitField::new();
+    let profile_cascade = opts::get().profile_cascade;
     // Declaration blocks are stored in increasing precedence order,
     // we want them in decreasing order here.
     for sub_list in applicable_declarations.iter().rev() {
         // Declarations are already stored in reverse order.
         for declaration in sub_list.declarations.iter() {
+            let start_time = if profile_cascade { precise_time_ns() } else { 0 };
             match *declaration {
                 % for style_struct in data.active_style_structs():
                     % for property in style_struct.longhands:
@@ -1657,8 +1763,21 @@ fn cascade_with_cached_declarations<C: ComputedValues>(
                         % endif
                     % endfor
                 % endfor
+                % for property in data.longhands:
+                    % if property.logical:
+                        PropertyDeclaration::${property.camel_case}(_) => {
+                            // Logical properties map to properties that are not inherited.
+                        }
+                    % endif
+                % endfor
                 PropertyDeclaration::Custom(..) => {}
             }
+            if profile_cascade {
+                let discriminant = unsafe {
+                    intrinsics::discriminant_value(declaration) as usize
+                };
+                note_cascaded_declaration(discriminant, start_time);
+            }
         }
     }
 
@@ -1668,6 +1787,16 @@ fn cascade_with_cached_declarations<C: ComputedValues>(
         context.mutate_style().mutate_font().compute_font_hash();
     }
 
+    // The structs that are not inherited come from a style that forced colors already replaced
+    // the colors of, but the color of the element is for its role to decide.
+    % if product == "servo":
+        if let Some((palette, role)) = forced_colors {
+            force_colors(&mut context, palette, role);
+        }
+    % else:
+        let _ = forced_colors;
+    % endif
+
     context.style
 }
 
@@ -1681,19 +1810,17 @@ pub type CascadePropertyFn<C /*: ComputedValues */> =
 
 pub fn make_cascade_vec<C: ComputedValues>() -> Vec<Option<CascadePropertyFn<C>>> {
     let mut result: Vec<Option<CascadePropertyFn<C>>> = Vec::new();
-    % for style_struct in data.active_style_structs():
-        % for property in style_struct.longhands:
-            let discriminant;
-            unsafe {
-                let variant = PropertyDeclaration::${property.camel_case}(intrinsics::uninit());
-                discriminant = intrinsics::discriminant_value(&variant) as usize;
-                mem::forget(variant);
-            }
-            while result.len() < discriminant + 1 {
-                result.push(None)
-            }
-            result[discriminant] = Some(longhands::${property.ident}::cascade_property);
-        % endfor
+    % for property in data.longhands:
+        let discriminant;
+        unsafe {
+            let variant = PropertyDeclaration::${property.camel_case}(intrinsics::uninit());
+            discriminant = intrinsics::discriminant_value(&variant) as usize;
+            mem::forget(variant);
+        }
+        while result.len() < discriminant + 1 {
+            result.push(None)
+        }
+        result[discriminant] = Some(longhands::${property.ident}::cascade_property);
     % endfor
     result
 }
@@ -1704,11 +1831,124 @@ thread_local!(static CASCADE_PROPERTY: Vec<Option<CascadePropertyFn<ServoCompute
     make_cascade_vec::<ServoComputedValues>()
 });
 
+/// The number of declarations of each longhand that the cascade applied, and the nanoseconds that
+/// it spent on them, indexed by the discriminant of their `PropertyDeclaration`. They are only
+/// accumulated with `-Z profile-cascade`, by all the threads that cascade styles.
+static CASCADED_DECLARATION_COUNTS: [AtomicUsize; ${len(data.longhands)}] = [
+    % for property in data.longhands:
+        ATOMIC_USIZE_INIT,
+    % endfor
+];
+static CASCADED_DECLARATION_TIMES: [AtomicUsize; ${len(data.longhands)}] = [
+    % for property in data.longhands:
+        ATOMIC_USIZE_INIT,
+    % endfor
+];
+
+#[inline]
+fn note_cascaded_declaration(discriminant: usize, start_time: u64) {
+    if let Some(count) = CASCADED_DECLARATION_COUNTS.get(discriminant) {
+        count.fetch_add(1, Ordering::Relaxed);
+        CASCADED_DECLARATION_TIMES[discriminant].fetch_add(
+            (precise_time_ns() - start_time) as usize, Ordering::Relaxed);
+    }
+}
+
+/// Returns the name of each longhand that the cascade applied declarations of since the last
+/// call, with the number of those declarations and the nanoseconds that it spent on them, and
+/// starts counting again from zero.
+pub fn get_and_reset_cascade_timings() -> Vec<(&'static str, u64, u64)> {
+    static NAMES: [&'static str; ${len(data.longhands)}] = [
+        % for property in data.longhands:
+            "${property.name}",
+        % endfor
+    ];
+    NAMES.iter().enumerate().filter_map(|(discriminant, name)| {
+        let count = CASCADED_DECLARATION_COUNTS[discriminant].swap(0, Ordering::SeqCst);
+        let time = CASCADED_DECLARATION_TIMES[discriminant].swap(0, Ordering::SeqCst);
+        if count == 0 {
+            None
+        } else {
+            Some((*name, count as u64, time as u64))
+        }
+    }).collect()
+}
+
+% if product == "servo":
+/// Replaces the colors of a cascaded style by those of a forced colors mode. Links and the root
+/// take the colors of their roles, other elements inherit theirs whatever their authors declared,
+/// and borders, outlines and decorations follow the color of the text. Backgrounds are
+/// transparent but for that of the root, which is the canvas, and images and shadows are dropped,
+/// since text over them may no longer be legible.
+fn force_colors<C: ComputedValues>(context: &mut computed::Context<C>,
+                                   palette: &ForcedColors,
+                                   role: ColorRole) {
+    use properties::style_struct_traits::{Background, Border, Color, Effects, InheritedText};
+    use properties::style_struct_traits::{Outline, Text};
+
+    let color = match role {
+        ColorRole::Link => palette.link_text,
+        ColorRole::Text if context.is_root_element => palette.canvas_text,
+        ColorRole::Text => context.inherited_style.get_color().clone_color(),
+    };
+    let background_color = if context.is_root_element {
+        palette.canvas
+    } else {
+        RGBA { red: 0., green: 0., blue: 0., alpha: 0. }
+    };
+    let style = &mut context.style;
+    style.mutate_color().set_color(color);
+    {
+        let background = style.mutate_background();
+        background.set_background_color(CSSParserColor::RGBA(background_color));
+        background.set_background_image(longhands::background_image::computed_value::T(None));
+    }
+    {
+        let border = style.mutate_border();
+        % for side in ["top", "right", "bottom", "left"]:
+            border.set_border_${side}_color(CSSParserColor::CurrentColor);
+        % endfor
+    }
+    style.mutate_outline().set_outline_color(CSSParserColor::CurrentColor);
+    style.mutate_text().set_text_decoration_color(CSSParserColor::CurrentColor);
+    style.mutate_effects().set_box_shadow(longhands::box_shadow::computed_value::T(vec![]));
+    style.mutate_inheritedtext().set_text_shadow(longhands::text_shadow::computed_value::T(vec![]));
+}
+% endif
+
+/// The font size of a root element that does not declare one: the size that the user prefers
+/// for the language that its declarations give it, or for every language.
+fn default_font_size(applicable_declarations: &[DeclarationBlock<Vec<PropertyDeclaration>>])
+                     -> Au {
+    % if product == "servo":
+    let mut language_tag = None;
+    'declarations: for sub_list in applicable_declarations.iter().rev() {
+        // Declarations are already stored in reverse order.
+        for declaration in sub_list.declarations.iter() {
+            if let PropertyDeclaration::ServoLang(ref value) = *declaration {
+                if let DeclaredValue::Value(ref value) = *value {
+                    language_tag = value.0.as_ref().map(|language_tag| &**language_tag);
+                }
+                break 'declarations
+            }
+        }
+    }
+    ::font_preferences::preferred_size(language_tag)
+        .unwrap_or_else(longhands::font_size::get_initial_value)
+    % else:
+    let _ = applicable_declarations;
+    longhands::font_size::get_initial_value()
+    % endif
+}
+
 /// Performs the CSS cascade, computing new styles for an element from its parent style and
 /// optionally a cached related style. The arguments are:
 ///
 ///   * `viewport_size`: The size of the initial viewport.
 ///
+///   * `text_zoom`: The factor that absolute font sizes are scaled by, and so the lengths that are
+///     relative to them, without zooming the other lengths.
+///
 ///   * `applicable_declarations`: The list of CSS rules that matched.
 ///
 ///   * `shareable`: Whether the `ComputedValues` structure to be constructed should be considered
@@ -1721,13 +1961,18 @@ thread_local!(static CASCADE_PROPERTY: Vec<Option<CascadePropertyFn<ServoCompute
 ///     this that it is safe to only provide inherited declarations. If `parent_style` is `None`,
 ///     this is ignored.
 ///
+///   * `forced_colors`: If present, the palette of the forced colors mode that replaces the
+///     colors of the cascaded style, and what the colors of the element stand for.
+///
 /// Returns the computed values and a boolean indicating whether the result is cacheable.
 pub fn cascade<C: ComputedValues>(
                viewport_size: Size2D<Au>,
+               text_zoom: f32,
                applicable_declarations: &[DeclarationBlock<Vec<PropertyDeclaration>>],
                shareable: bool,
                parent_style: Option<<&C>,
                cached_style: Option<<&C>,
+               forced_colors: Option<(&ForcedColors, ColorRole)>,
                mut error_reporter: StdBox<ParseErrorReporter + Send>)
                -> (C, bool) {
     use properties::style_struct_traits::{Border, Box, Font, Outline};
@@ -1758,24 +2003,34 @@ pub fn cascade<C: ComputedValues>(
 
     if let (Some(cached_style), Some(parent_style)) = (cached_style, parent_style) {
         let style = cascade_with_cached_declarations(viewport_size,
+                                                     text_zoom,
                                                      applicable_declarations,
                                                      shareable,
                                                      parent_style,
                                                      cached_style,
                                                      custom_properties,
+                                                     forced_colors,
                                                      error_reporter);
         return (style, false)
     }
 
+    // The initial font size, which the root has unless it declares another and which its `rem`
+    // lengths are relative to, is the one that the user prefers for its language, zoomed.
+    let root_font_size = if is_root_element {
+        default_font_size(applicable_declarations).scale_by(text_zoom)
+    } else {
+        inherited_style.root_font_size()
+    };
     let mut context = computed::Context {
         is_root_element: is_root_element,
         viewport_size: viewport_size,
+        text_zoom: text_zoom,
         inherited_style: inherited_style,
         style: C::new(
             custom_properties,
             shareable,
             WritingMode::empty(),
-            inherited_style.root_font_size(),
+            root_font_size,
             % for style_struct in data.active_style_structs():
             % if style_struct.inherited:
             inherited_style
@@ -1786,10 +2041,14 @@ pub fn cascade<C: ComputedValues>(
             % endfor
         ),
     };
+    if is_root_element {
+        context.style.mutate_font().set_font_size(root_font_size);
+    }
 
     // Set computed values, overwriting earlier declarations for the same property.
     let mut cacheable = true;
     let mut seen = PropertyBitField::new();
+    let profile_cascade = opts::get().profile_cascade;
     // Declaration blocks are stored in increasing precedence order, we want them in decreasing
     // order here.
     //
@@ -1816,7 +2075,10 @@ pub fn cascade<C: ComputedValues>(
                         PropertyDeclaration::Color(_) |
                         PropertyDeclaration::Position(_) |
                         PropertyDeclaration::Float(_) |
-                        PropertyDeclaration::TextDecoration${'' if product == 'servo' else 'Line'}(_)
+                        // Logical properties map to physical ones in the writing mode.
+                        PropertyDeclaration::Direction(_) |
+                        PropertyDeclaration::WritingMode(_) |
+                        PropertyDeclaration::TextOrientation(_)
                     );
                     if
                         % if category_to_cascade_now == "early":
@@ -1829,17 +2091,37 @@ pub fn cascade<C: ComputedValues>(
                     let discriminant = unsafe {
                         intrinsics::discriminant_value(declaration) as usize
                     };
+                    let start_time = if profile_cascade { precise_time_ns() } else { 0 };
                     (cascade_property[discriminant].unwrap())(declaration,
                                                               inherited_style,
                                                               &mut context,
                                                               &mut seen,
                                                               &mut cacheable,
                                                               &mut error_reporter);
+                    if profile_cascade {
+                        note_cascaded_declaration(discriminant, start_time);
+                    }
                 }
             }
         % endfor
     });
 
+    % if product == "servo":
+        if let Some((palette, role)) = forced_colors {
+            force_colors(&mut context, palette, role);
+        }
+
+        // The text decorations in effect depend on several properties, so they are derived once
+        // all of them have been cascaded.
+        if seen.get_display() || seen.get_position() || seen.get_float() ||
+           seen.get_text_decoration_line() || forced_colors.is_some() {
+            longhands::_servo_text_decorations_in_effect::derive_from_text_decoration_line(
+                &mut context);
+        }
+    % else:
+        let _ = forced_colors;
+    % endif
+
     let mut style = context.style;
 
     let positioned = matches!(style.get_box().clone_position(),
@@ -1858,7 +2140,8 @@ pub fn cascade<C: ComputedValues>(
             T::table_row_group | T::table_column |
             T::table_column_group | T::table_header_group |
             T::table_footer_group | T::table_row | T::table_cell |
-            T::table_caption => {
+            T::table_caption | T::ruby | T::ruby_base | T::ruby_text |
+            T::ruby_base_container | T::ruby_text_container => {
                 Some(T::block)
             }
             _ => None
@@ -2087,6 +2370,23 @@ pub fn modify_style_for_input_text(style: &mut Arc<ServoComputedValues>) {
     inherited_text.white_space = longhands::white_space::computed_value::T::pre;
 }
 
+/// Gives a widget that the theme paints, such as a checkbox, the size that the theme wants for it
+/// in the dimensions that the author left `auto`.
+pub fn modify_style_for_widget(style: &mut Arc<ServoComputedValues>, width: Au, height: Au) {
+    if style.position.width != computed::LengthOrPercentageOrAuto::Auto &&
+            style.position.height != computed::LengthOrPercentageOrAuto::Auto {
+        return
+    }
+    let mut style = Arc::make_mut(style);
+    let mut position = Arc::make_mut(&mut style.position);
+    if position.width == computed::LengthOrPercentageOrAuto::Auto {
+        position.width = computed::LengthOrPercentageOrAuto::Length(width);
+    }
+    if position.height == computed::LengthOrPercentageOrAuto::Auto {
+        position.height = computed::LengthOrPercentageOrAuto::Length(height);
+    }
+}
+
 /// Adjusts the `clip` property so that an inline absolute hypothetical fragment doesn't clip its
 /// children.
 pub fn modify_style_for_inline_absolute_hypothetical_fragment(style: &mut Arc<ServoComputedValues>) {
@@ -93,6 +93,54 @@ pub fn parse_border(context: &ParserContext, input: &mut Parser)
     })
 </%helpers:shorthand>
 
+// https://drafts.csswg.org/css-logical-props/#border-shorthands
+use properties::longhands::border_top_width;
+
+pub fn parse_border_width_value(input: &mut Parser)
+                                -> Result<border_top_width::SpecifiedValue, ()> {
+    specified::parse_border_width(input).map(border_top_width::SpecifiedValue)
+}
+
+% for axis in ["block", "inline"]:
+    ${helpers.logical_axis_shorthand("border-%s-color" % axis, "border-%s-color", axis,
+                                     "::values::specified::CSSColor::parse")}
+    ${helpers.logical_axis_shorthand("border-%s-style" % axis, "border-%s-style", axis,
+                                     "::values::specified::BorderStyle::parse")}
+    ${helpers.logical_axis_shorthand("border-%s-width" % axis, "border-%s-width", axis,
+                                     "super::parse_border_width_value")}
+
+    % for side in [axis + "-start", axis + "-end"]:
+        <%helpers:shorthand name="border-${side}" sub_properties="${' '.join(
+            'border-%s-%s' % (side, prop)
+            for prop in ['color', 'style', 'width']
+        )}">
+            let (color, style, width) = try!(super::parse_border(context, input));
+            Ok(Longhands {
+                ${to_rust_ident('border-%s-color' % side)}: color,
+                ${to_rust_ident('border-%s-style' % side)}: style,
+                ${to_rust_ident('border-%s-width' % side)}: width.map(
+                    longhands::${to_rust_ident('border-%s-width' % side)}::SpecifiedValue),
+            })
+        </%helpers:shorthand>
+    % endfor
+
+    <%helpers:shorthand name="border-${axis}" sub_properties="${' '.join(
+        'border-%s-%s-%s' % (axis, edge, prop)
+        for edge in ['start', 'end']
+        for prop in ['color', 'style', 'width']
+    )}">
+        let (color, style, width) = try!(super::parse_border(context, input));
+        Ok(Longhands {
+            % for side in [axis + "-start", axis + "-end"]:
+                ${to_rust_ident('border-%s-color' % side)}: color.clone(),
+                ${to_rust_ident('border-%s-style' % side)}: style,
+                ${to_rust_ident('border-%s-width' % side)}: width.map(
+                    longhands::${to_rust_ident('border-%s-width' % side)}::SpecifiedValue),
+            % endfor
+        })
+    </%helpers:shorthand>
+% endfor
+
 <%helpers:shorthand name="border-radius" sub_properties="${' '.join(
     'border-%s-radius' % (corner)
      for corner in ['top-left', 'top-right', 'bottom-right', 'bottom-left']
@@ -147,3 +195,60 @@ pub fn parse_border(context: &ParserContext, input: &mut Parser)
         border_bottom_left_radius: Some(radii[3]),
     })
 </%helpers:shorthand>
+
+<%helpers:shorthand name="border-image" sub_properties="border-image-source border-image-slice
+                                                        border-image-width border-image-outset
+                                                        border-image-repeat">
+    use properties::longhands::{border_image_outset, border_image_repeat, border_image_slice};
+    use properties::longhands::{border_image_source, border_image_width};
+
+    let mut source = None;
+    let mut slice = None;
+    let mut width = None;
+    let mut outset = None;
+    let mut repeat = None;
+    let mut any = false;
+    loop {
+        if source.is_none() {
+            if let Ok(value) = input.try(|input| border_image_source::parse(context, input)) {
+                source = Some(value);
+                any = true;
+                continue
+            }
+        }
+        if slice.is_none() {
+            if let Ok(value) = input.try(|input| border_image_slice::parse(context, input)) {
+                slice = Some(value);
+                any = true;
+                // The width and the outset can only follow the slice, after slashes.
+                if input.try(|input| input.expect_delim('/')).is_ok() {
+                    width = input.try(|input| border_image_width::parse(context, input)).ok();
+                    if input.try(|input| input.expect_delim('/')).is_ok() {
+                        outset = Some(try!(border_image_outset::parse(context, input)));
+                    } else if width.is_none() {
+                        return Err(())
+                    }
+                }
+                continue
+            }
+        }
+        if repeat.is_none() {
+            if let Ok(value) = input.try(|input| border_image_repeat::parse(context, input)) {
+                repeat = Some(value);
+                any = true;
+                continue
+            }
+        }
+        break
+    }
+    if !any {
+        return Err(())
+    }
+    Ok(Longhands {
+        border_image_source: source,
+        border_image_slice: slice,
+        border_image_width: width,
+        border_image_outset: outset,
+        border_image_repeat: repeat,
+    })
+</%helpers:shorthand>
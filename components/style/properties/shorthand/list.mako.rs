@@ -22,6 +22,15 @@
             continue
         }
 
+        // The position goes first, as the names of counter styles can be any identifier.
+        if position.is_none() {
+            if let Ok(value) = input.try(|input| list_style_position::parse(context, input)) {
+                position = Some(value);
+                any = true;
+                continue
+            }
+        }
+
         if list_style_type.is_none() {
             if let Ok(value) = input.try(|input| list_style_type::parse(context, input)) {
                 list_style_type = Some(value);
@@ -37,14 +46,6 @@
                 continue
             }
         }
-
-        if position.is_none() {
-            if let Ok(value) = input.try(|input| list_style_position::parse(context, input)) {
-                position = Some(value);
-                any = true;
-                continue
-            }
-        }
         break
     }
 
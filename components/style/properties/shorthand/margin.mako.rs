@@ -5,3 +5,9 @@
 <%namespace name="helpers" file="/helpers.mako.rs" />
 
 ${helpers.four_sides_shorthand("margin", "margin-%s", "specified::LengthOrPercentageOrAuto::parse")}
+
+// https://drafts.csswg.org/css-logical-props/#margin-shorthands
+% for axis in ["block", "inline"]:
+    ${helpers.logical_axis_shorthand("margin-" + axis, "margin-%s", axis,
+                                     "::values::specified::LengthOrPercentageOrAuto::parse")}
+% endfor
@@ -5,3 +5,9 @@
 <%namespace name="helpers" file="/helpers.mako.rs" />
 
 ${helpers.four_sides_shorthand("padding", "padding-%s", "specified::LengthOrPercentage::parse")}
+
+// https://drafts.csswg.org/css-logical-props/#padding-shorthands
+% for axis in ["block", "inline"]:
+    ${helpers.logical_axis_shorthand("padding-" + axis, "padding-%s", axis,
+                                     "::values::specified::LengthOrPercentage::parse")}
+% endfor
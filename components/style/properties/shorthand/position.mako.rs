@@ -0,0 +1,12 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+<%namespace name="helpers" file="/helpers.mako.rs" />
+
+// https://drafts.csswg.org/css-logical-props/#inset-shorthands
+${helpers.four_sides_shorthand("inset", "%s", "specified::LengthOrPercentageOrAuto::parse")}
+% for axis in ["block", "inline"]:
+    ${helpers.logical_axis_shorthand("inset-" + axis, "inset-%s", axis,
+                                     "::values::specified::LengthOrPercentageOrAuto::parse")}
+% endfor
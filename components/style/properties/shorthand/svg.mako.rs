@@ -0,0 +1,69 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+<%namespace name="helpers" file="/helpers.mako.rs" />
+
+// https://drafts.fxtf.org/css-masking-1/#the-mask
+<%helpers:shorthand name="mask" products="servo"
+                    sub_properties="mask-image mask-position mask-size mask-repeat mask-mode">
+    use properties::longhands::{mask_image, mask_mode, mask_position, mask_repeat, mask_size};
+
+    let mut image = None;
+    let mut position = None;
+    let mut size = None;
+    let mut repeat = None;
+    let mut mode = None;
+    let mut any = false;
+
+    loop {
+        if image.is_none() {
+            if let Ok(value) = input.try(|input| mask_image::parse(context, input)) {
+                image = Some(value);
+                any = true;
+                continue
+            }
+        }
+        if position.is_none() {
+            if let Ok(value) = input.try(|input| mask_position::parse(context, input)) {
+                position = Some(value);
+                any = true;
+
+                // Parse mask size, if applicable.
+                size = input.try(|input| {
+                    try!(input.expect_delim('/'));
+                    mask_size::parse(context, input)
+                }).ok();
+
+                continue
+            }
+        }
+        if repeat.is_none() {
+            if let Ok(value) = input.try(|input| mask_repeat::parse(context, input)) {
+                repeat = Some(value);
+                any = true;
+                continue
+            }
+        }
+        if mode.is_none() {
+            if let Ok(value) = input.try(|input| mask_mode::parse(context, input)) {
+                mode = Some(value);
+                any = true;
+                continue
+            }
+        }
+        break
+    }
+
+    if any {
+        Ok(Longhands {
+            mask_image: image,
+            mask_position: position,
+            mask_size: size,
+            mask_repeat: repeat,
+            mask_mode: mode,
+        })
+    } else {
+        Err(())
+    }
+</%helpers:shorthand>
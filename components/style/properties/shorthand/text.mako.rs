@@ -7,8 +7,7 @@
 <%helpers:shorthand name="text-decoration"
                     sub_properties="text-decoration-color
                                     text-decoration-line
-                                    text-decoration-style"
-                    products="gecko">
+                                    text-decoration-style">
     use cssparser::Color as CSSParserColor;
     use properties::longhands::{text_decoration_color, text_decoration_line, text_decoration_style};
     use values::specified::CSSColor;
@@ -194,6 +194,10 @@ impl<'a, E> Element for ElementWrapper<'a, E>
 fn selector_to_state<Impl: SelectorImplExt>(sel: &SimpleSelector<Impl>) -> ElementState {
     match *sel {
         SimpleSelector::NonTSPseudoClass(ref pc) => Impl::pseudo_class_state_flag(pc),
+        // `:not(:checked)` changes when `:checked` does.
+        SimpleSelector::Negation(ref negated) => {
+            negated.iter().fold(ElementState::empty(), |state, s| state | selector_to_state(s))
+        }
         _ => ElementState::empty(),
     }
 }
@@ -209,6 +213,8 @@ fn is_attr_selector<Impl: SelectorImpl>(sel: &SimpleSelector<Impl>) -> bool {
         SimpleSelector::AttrPrefixMatch(_, _) |
         SimpleSelector::AttrSubstringMatch(_, _) |
         SimpleSelector::AttrSuffixMatch(_, _) => true,
+        // Such as `:not([aria-expanded=true])`.
+        SimpleSelector::Negation(ref negated) => negated.iter().any(is_attr_selector),
         _ => false,
     }
 }
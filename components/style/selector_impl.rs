@@ -88,6 +88,13 @@ pub trait SelectorImplExt : SelectorImpl + Sized {
     }
 
 
+    /// Returns the pseudo-element that transitions run on when `pseudo` is cascaded, or `None`
+    /// if its styles are not animated.
+    #[inline]
+    fn animated_pseudo_element(_pseudo: &Self::PseudoElement) -> Option<PseudoElement> {
+        None
+    }
+
     fn pseudo_class_state_flag(pc: &Self::NonTSPseudoClass) -> ElementState;
 
     fn get_user_or_user_agent_stylesheets() -> &'static [Stylesheet<Self>];
@@ -99,9 +106,11 @@ pub trait SelectorImplExt : SelectorImpl + Sized {
 pub enum PseudoElement {
     Before,
     After,
+    FirstLine,
     Selection,
     DetailsSummary,
     DetailsContent,
+    Marker,
 }
 
 impl PseudoElement {
@@ -111,7 +120,9 @@ impl PseudoElement {
             PseudoElement::Before |
             PseudoElement::After |
             PseudoElement::Selection => PseudoElementCascadeType::Eager,
-            PseudoElement::DetailsSummary => PseudoElementCascadeType::Lazy,
+            PseudoElement::FirstLine |
+            PseudoElement::DetailsSummary |
+            PseudoElement::Marker => PseudoElementCascadeType::Lazy,
             PseudoElement::DetailsContent => PseudoElementCascadeType::Precomputed,
         }
     }
@@ -197,7 +208,9 @@ impl SelectorImpl for ServoSelectorImpl {
         let pseudo_element = match_ignore_ascii_case! { name,
             "before" => Before,
             "after" => After,
+            "first-line" => FirstLine,
             "selection" => Selection,
+            "marker" => Marker,
             "-servo-details-summary" => {
                 if !context.in_user_agent_stylesheet {
                     return Err(())
@@ -230,9 +243,19 @@ impl SelectorImplExt for ServoSelectorImpl {
         where F: FnMut(PseudoElement) {
         fun(PseudoElement::Before);
         fun(PseudoElement::After);
+        fun(PseudoElement::FirstLine);
         fun(PseudoElement::DetailsContent);
         fun(PseudoElement::DetailsSummary);
         fun(PseudoElement::Selection);
+        fun(PseudoElement::Marker);
+    }
+
+    #[inline]
+    fn animated_pseudo_element(pseudo: &PseudoElement) -> Option<PseudoElement> {
+        match *pseudo {
+            PseudoElement::Before | PseudoElement::After => Some(pseudo.clone()),
+            _ => None,
+        }
     }
 
     #[inline]
@@ -5,10 +5,14 @@
 // For lazy_static
 #![allow(unsafe_code)]
 
+use app_units::Au;
+use counter_style::CounterStyles;
 use dom::PresentationalHintsSynthetizer;
 use element_state::*;
 use error_reporting::StdoutErrorReporter;
-use media_queries::{Device, MediaType};
+use euclid::Size2D;
+use media_queries::Device;
+use page::{self, PageBox, PageRule};
 use parser::ParserContextExtraData;
 use properties::{self, PropertyDeclaration, PropertyDeclarationBlock};
 use restyle_hints::{ElementSnapshot, RestyleHint, DependencySet};
@@ -16,18 +20,22 @@ use selector_impl::{SelectorImplExt, ServoSelectorImpl};
 use selectors::Element;
 use selectors::bloom::BloomFilter;
 use selectors::matching::DeclarationBlock as GenericDeclarationBlock;
-use selectors::matching::{Rule, SelectorMap};
-use selectors::parser::SelectorImpl;
+use selectors::matching::{Rule, SelectorMap, matches_compound_selector};
+use selectors::parser::{CompoundSelector, Selector, SelectorImpl, SimpleSelector};
 use smallvec::VecLike;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::hash::BuildHasherDefault;
+use std::mem;
 use std::process;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use string_cache::Atom;
+use style_traits::forced_colors::{ColorRole, ForcedColors};
 use style_traits::viewport::ViewportConstraints;
-use stylesheets::{CSSRuleIteratorExt, Origin, Stylesheet};
+use time::precise_time_ns;
+use stylesheet_loader;
+use stylesheets::{CSSRuleIteratorExt, Origin, SourceRange, StyleRule, Stylesheet};
 use url::Url;
 use util::opts;
-use util::resource_files::read_resource_file;
 use viewport::{MaybeNew, ViewportRuleCascade};
 
 
@@ -39,11 +47,13 @@ lazy_static! {
         // FIXME: presentational-hints.css should be at author origin with zero specificity.
         //        (Does it make a difference?)
         for &filename in &["user-agent.css", "servo.css", "presentational-hints.css"] {
-            match read_resource_file(filename) {
+            // Embedders can serve these themselves, through a handler of the `chrome` scheme.
+            let url = Url::parse(&format!("chrome://resources/{}", filename)).unwrap();
+            match stylesheet_loader::load(&url) {
                 Ok(res) => {
                     let ua_stylesheet = Stylesheet::from_bytes(
                         &res,
-                        Url::parse(&format!("chrome://resources/{:?}", filename)).unwrap(),
+                        url,
                         None,
                         None,
                         Origin::UserAgent,
@@ -68,11 +78,12 @@ lazy_static! {
 
 lazy_static! {
     pub static ref QUIRKS_MODE_STYLESHEET: Stylesheet<ServoSelectorImpl> = {
-        match read_resource_file("quirks-mode.css") {
+        let url = Url::parse("chrome://resources/quirks-mode.css").unwrap();
+        match stylesheet_loader::load(&url) {
             Ok(res) => {
                 Stylesheet::from_bytes(
                     &res,
-                    Url::parse("chrome://resources/quirks-mode.css").unwrap(),
+                    url,
                     None,
                     None,
                     Origin::UserAgent,
@@ -114,6 +125,21 @@ pub struct Stylist<Impl: SelectorImplExt> {
     /// If true, the quirks-mode stylesheet is applied.
     quirks_mode: bool,
 
+    /// If false, elements are styled by user-agent and user rules alone, as
+    /// in the reader modes of embedders. This is the
+    /// `layout.css.author-styles.enabled` pref until embedders set it.
+    author_styles_enabled: bool,
+
+    /// The palette that replaces the colors of styles when the device is in
+    /// a forced colors mode, such as a high contrast mode. The default
+    /// palette is forced if the `layout.css.forced-colors.enabled` pref is
+    /// set, until embedders choose.
+    forced_colors: Option<ForcedColors>,
+
+    /// The factor that absolute font sizes are scaled by, which zooms text
+    /// without zooming images or the viewport.
+    text_zoom: f32,
+
     /// If true, the device has changed, and the stylist needs to be updated.
     is_device_dirty: bool,
 
@@ -134,10 +160,56 @@ pub struct Stylist<Impl: SelectorImplExt> {
                                               Vec<DeclarationBlock>,
                                               BuildHasherDefault<::fnv::FnvHasher>>,
 
+    /// The selector map buckets that the rules of each lazily cascaded
+    /// pseudo-element are filed under.
+    lazy_pseudo_buckets: HashMap<Impl::PseudoElement,
+                                 LazyPseudoBuckets,
+                                 BuildHasherDefault<::fnv::FnvHasher>>,
+
     rules_source_order: usize,
 
+    /// The counter styles of the effective `@counter-style` rules.
+    counter_styles: CounterStyles,
+
+    /// The effective `@page` rules, in the order they were found.
+    page_rules: Vec<PageRule>,
+
+    /// The URLs of the effective stylesheets, in the order they were added.
+    stylesheet_urls: Vec<Url>,
+
+    /// Where each style rule comes from, by rule source order.
+    rule_sources: Vec<RuleSource>,
+
     /// Selector dependencies used to compute restyle hints.
     state_deps: DependencySet<Impl>,
+
+    /// The author rules that have matched so far, if CSS coverage tracking
+    /// is enabled with the `layout.css.coverage.enabled` pref.
+    #[ignore_heap_size_of = "debugging aid"]
+    coverage: Option<RuleCoverage<Impl>>,
+
+    /// How costly each selector has been to match, if selector profiling is
+    /// enabled with the `layout.css.selector-profiling.enabled` pref.
+    #[ignore_heap_size_of = "debugging aid"]
+    selector_profile: Option<SelectorProfile<Impl>>,
+
+    /// The edits that devtools made to style rules, by the address of the
+    /// rules, along with the stylesheets the rules belong to, which are kept
+    /// alive here so that the addresses stay valid.
+    #[ignore_heap_size_of = "debugging aid"]
+    rule_edits: HashMap<usize, (Arc<Stylesheet<Impl>>, StyleRuleEdit<Impl>)>,
+
+    /// Whether rules have been edited since the selector maps were built.
+    rule_edits_changed: bool,
+}
+
+/// A replacement that devtools made for the selectors or the declarations of
+/// a style rule, which lasts as long as its stylesheet applies to the
+/// document.
+pub struct StyleRuleEdit<Impl: SelectorImpl> {
+    /// The new selectors, along with their text.
+    pub selectors: Option<(Vec<Selector<Impl>>, String)>,
+    pub declarations: Option<PropertyDeclarationBlock>,
 }
 
 impl<Impl: SelectorImplExt> Stylist<Impl> {
@@ -148,12 +220,40 @@ impl<Impl: SelectorImplExt> Stylist<Impl> {
             device: device,
             is_device_dirty: true,
             quirks_mode: false,
+            author_styles_enabled: ::util::prefs::get_pref("layout.css.author-styles.enabled")
+                                                   .as_boolean().unwrap_or(true),
+            forced_colors: if ::util::prefs::get_pref("layout.css.forced-colors.enabled")
+                                  .as_boolean().unwrap_or(false) {
+                Some(ForcedColors::default())
+            } else {
+                None
+            },
+            text_zoom: 1.0,
 
             element_map: PerPseudoElementSelectorMap::new(),
             pseudos_map: HashMap::with_hasher(Default::default()),
             precomputed_pseudo_element_decls: HashMap::with_hasher(Default::default()),
+            lazy_pseudo_buckets: HashMap::with_hasher(Default::default()),
             rules_source_order: 0,
+            counter_styles: HashMap::with_hasher(Default::default()),
+            page_rules: vec![],
+            stylesheet_urls: vec![],
+            rule_sources: vec![],
             state_deps: DependencySet::new(),
+            coverage: if ::util::prefs::get_pref("layout.css.coverage.enabled").as_boolean()
+                                                                                 .unwrap_or(false) {
+                Some(RuleCoverage::new())
+            } else {
+                None
+            },
+            selector_profile: if ::util::prefs::get_pref("layout.css.selector-profiling.enabled")
+                                     .as_boolean().unwrap_or(false) {
+                Some(SelectorProfile::new())
+            } else {
+                None
+            },
+            rule_edits: HashMap::new(),
+            rule_edits_changed: false,
         };
 
         Impl::each_eagerly_cascaded_pseudo_element(|pseudo| {
@@ -165,12 +265,25 @@ impl<Impl: SelectorImplExt> Stylist<Impl> {
         stylist
     }
 
+    /// Rebuilds the selector maps if the device, the stylesheets or the rules
+    /// have changed. Returns whether every element has to be restyled, which
+    /// edits of rules do not require by themselves.
     pub fn update(&mut self, doc_stylesheets: &[Arc<Stylesheet<Impl>>],
                   stylesheets_changed: bool) -> bool
                   where Impl: 'static {
-        if !(self.is_device_dirty || stylesheets_changed) {
+        if !(self.is_device_dirty || stylesheets_changed || self.rule_edits_changed) {
             return false;
         }
+        let needs_restyle = self.is_device_dirty || stylesheets_changed;
+
+        // Edits of the rules of stylesheets that no longer apply are forgotten.
+        let rule_edits = mem::replace(&mut self.rule_edits, HashMap::new());
+        self.rule_edits = rule_edits.into_iter().filter(|&(_, (ref stylesheet, _))| {
+            doc_stylesheets.iter().any(|applied| {
+                &**applied as *const Stylesheet<Impl> == &**stylesheet as *const Stylesheet<Impl>
+            })
+        }).collect();
+        self.rule_edits_changed = false;
 
         self.element_map = PerPseudoElementSelectorMap::new();
         self.pseudos_map = HashMap::with_hasher(Default::default());
@@ -179,8 +292,19 @@ impl<Impl: SelectorImplExt> Stylist<Impl> {
         });
 
         self.precomputed_pseudo_element_decls = HashMap::with_hasher(Default::default());
+        self.lazy_pseudo_buckets = HashMap::with_hasher(Default::default());
         self.rules_source_order = 0;
+        self.counter_styles = HashMap::with_hasher(Default::default());
+        self.page_rules.clear();
+        self.stylesheet_urls.clear();
+        self.rule_sources.clear();
         self.state_deps.clear();
+        if let Some(ref mut coverage) = self.coverage {
+            coverage.reset(doc_stylesheets);
+        }
+        if let Some(ref mut selector_profile) = self.selector_profile {
+            selector_profile.clear();
+        }
 
         for ref stylesheet in Impl::get_user_or_user_agent_stylesheets().iter() {
             self.add_stylesheet(&stylesheet);
@@ -197,7 +321,46 @@ impl<Impl: SelectorImplExt> Stylist<Impl> {
         }
 
         self.is_device_dirty = false;
-        true
+        needs_restyle
+    }
+
+    /// Replaces the selectors or the declarations of the style rule whose
+    /// selectors start at the given line and column of the given stylesheet,
+    /// from the next update on. Returns the selectors that the rule had, so
+    /// that the elements they matched can be restyled, or `None` if there is
+    /// no such rule.
+    pub fn edit_style_rule(&mut self,
+                           stylesheet: &Arc<Stylesheet<Impl>>,
+                           line: usize,
+                           column: usize,
+                           edit: StyleRuleEdit<Impl>)
+                           -> Option<Vec<Selector<Impl>>> {
+        let rule = match stylesheet.rules().style().find(|rule| {
+            rule.line == line && rule.column == column
+        }) {
+            Some(rule) => rule,
+            None => return None,
+        };
+
+        let address = rule_address(rule);
+        let previous_selectors = match self.rule_edits.get(&address) {
+            Some(&(_, StyleRuleEdit { selectors: Some((ref selectors, _)), .. })) => {
+                selectors.clone()
+            }
+            _ => rule.selectors.clone(),
+        };
+
+        let &mut (_, ref mut current_edit) = self.rule_edits.entry(address).or_insert_with(|| {
+            (stylesheet.clone(), StyleRuleEdit { selectors: None, declarations: None })
+        });
+        if edit.selectors.is_some() {
+            current_edit.selectors = edit.selectors;
+        }
+        if edit.declarations.is_some() {
+            current_edit.declarations = edit.declarations;
+        }
+        self.rule_edits_changed = true;
+        Some(previous_selectors)
     }
 
     fn add_stylesheet(&mut self, stylesheet: &Stylesheet<Impl>) {
@@ -205,14 +368,21 @@ impl<Impl: SelectorImplExt> Stylist<Impl> {
             return;
         }
         let mut rules_source_order = self.rules_source_order;
+        self.stylesheet_urls.push(stylesheet.url.clone());
 
         // Take apart the StyleRule into individual Rules and insert
         // them into the SelectorMap of that priority.
         macro_rules! append(
-            ($style_rule: ident, $priority: ident) => {
-                if !$style_rule.declarations.$priority.is_empty() {
-                    for selector in &$style_rule.selectors {
+            ($selectors: ident, $declarations: ident, $priority: ident) => {
+                if !$declarations.$priority.is_empty() {
+                    for selector in $selectors {
                         let map = if let Some(ref pseudo) = selector.pseudo_element {
+                            if Impl::pseudo_element_cascade_type(pseudo).is_lazy() {
+                                self.lazy_pseudo_buckets
+                                    .entry(pseudo.clone())
+                                    .or_insert_with(LazyPseudoBuckets::new)
+                                    .note_selector(&selector.compound_selectors);
+                            }
                             self.pseudos_map
                                 .entry(pseudo.clone())
                                 .or_insert_with(PerPseudoElementSelectorMap::new)
@@ -225,7 +395,7 @@ impl<Impl: SelectorImplExt> Stylist<Impl> {
                             selector: selector.compound_selectors.clone(),
                             declarations: DeclarationBlock {
                                 specificity: selector.specificity,
-                                declarations: $style_rule.declarations.$priority.clone(),
+                                declarations: $declarations.$priority.clone(),
                                 source_order: rules_source_order,
                             },
                         });
@@ -235,16 +405,49 @@ impl<Impl: SelectorImplExt> Stylist<Impl> {
         );
 
         for style_rule in stylesheet.effective_rules(&self.device).style() {
-            append!(style_rule, normal);
-            append!(style_rule, important);
+            // Rules that devtools edited keep their place in the cascade.
+            let edit = self.rule_edits.get(&rule_address(style_rule)).map(|&(_, ref edit)| edit);
+            let (selectors, selector_text) = match edit.and_then(|edit| edit.selectors.as_ref()) {
+                Some(&(ref selectors, ref selector_text)) => (selectors, selector_text),
+                None => (&style_rule.selectors, &style_rule.selector_text),
+            };
+            let declarations = edit.and_then(|edit| edit.declarations.as_ref())
+                                   .unwrap_or(&style_rule.declarations);
+
+            append!(selectors, declarations, normal);
+            append!(selectors, declarations, important);
             rules_source_order += 1;
-            for selector in &style_rule.selectors {
+            self.rule_sources.push(RuleSource {
+                stylesheet: self.stylesheet_urls.len() - 1,
+                origin: stylesheet.origin,
+                selector_text: selector_text.clone(),
+                line: style_rule.line,
+                column: style_rule.column,
+                normal: declarations_address(&declarations.normal),
+                important: declarations_address(&declarations.important),
+            });
+            if let Some(ref mut coverage) = self.coverage {
+                coverage.rules_by_source_order.push(rule_address(style_rule));
+            }
+            if let Some(ref mut selector_profile) = self.selector_profile {
+                for (index, selector) in selectors.iter().enumerate() {
+                    selector_profile.add_selector(selector, self.rule_sources.len() - 1, index);
+                }
+            }
+            for selector in selectors {
                 self.state_deps.note_selector(selector.compound_selectors.clone());
             }
         }
 
         self.rules_source_order = rules_source_order;
 
+        // The last rule for a name wins.
+        for rule in stylesheet.effective_rules(&self.device).counter_style() {
+            self.counter_styles.insert(rule.name.clone(), rule.clone());
+        }
+
+        self.page_rules.extend(stylesheet.effective_rules(&self.device).page().cloned());
+
         Impl::each_precomputed_pseudo_element(|pseudo| {
             // TODO: Consider not doing this and just getting the rules on the
             // fly. It should be a bit slower, but we'd take rid of the
@@ -270,8 +473,10 @@ impl<Impl: SelectorImplExt> Stylist<Impl> {
         if let Some(declarations) = self.precomputed_pseudo_element_decls.get(pseudo) {
             let (computed, _) =
                 properties::cascade(self.device.au_viewport_size(),
+                                    self.text_zoom,
                                     &declarations, false,
                                     parent.map(|p| &**p), None,
+                                    self.forced_colors().map(|palette| (palette, ColorRole::Text)),
                                     box StdoutErrorReporter);
             Some(Arc::new(computed))
         } else {
@@ -303,12 +508,28 @@ impl<Impl: SelectorImplExt> Stylist<Impl> {
 
         let (computed, _) =
             properties::cascade(self.device.au_viewport_size(),
+                                self.text_zoom,
                                 &declarations, false,
                                 Some(&**parent), None,
+                                self.forced_colors().map(|palette| (palette, ColorRole::Text)),
                                 box StdoutErrorReporter);
         Some(Arc::new(computed))
     }
 
+    /// The lazily cascaded pseudo-elements that have rules which may match
+    /// `element`, going by the selector map buckets that they are filed under.
+    /// Their styles still have to be computed to know whether they do.
+    pub fn lazy_pseudo_elements_that_may_match<E>(&self, element: &E) -> Vec<Impl::PseudoElement>
+                                                  where E: Element<Impl=Impl> {
+        self.lazy_pseudo_buckets.iter().filter_map(|(pseudo, buckets)| {
+            if buckets.may_match(element) {
+                Some(pseudo.clone())
+            } else {
+                None
+            }
+        }).collect()
+    }
+
     pub fn compute_restyle_hint<E>(&self, element: &E,
                                    snapshot: &ElementSnapshot,
                                    // NB: We need to pass current_state as an argument because
@@ -328,7 +549,7 @@ impl<Impl: SelectorImplExt> Stylist<Impl> {
 
         self.viewport_constraints = ViewportConstraints::maybe_new(device.viewport_size, &cascaded_rule);
         if let Some(ref constraints) = self.viewport_constraints {
-            device = Device::new(MediaType::Screen, constraints.size);
+            device = Device::new(device.media_type, constraints.size);
         }
 
         self.is_device_dirty |= stylesheets.iter().any(|stylesheet| {
@@ -343,10 +564,55 @@ impl<Impl: SelectorImplExt> Stylist<Impl> {
         &self.viewport_constraints
     }
 
+    pub fn counter_styles(&self) -> &CounterStyles {
+        &self.counter_styles
+    }
+
+    /// The page box of the page at `index` of a paginated document, as set up by the `@page`
+    /// rules. `default_size` is the size of the pages that the rules leave `auto`.
+    pub fn page_box(&self, index: usize, default_size: Size2D<Au>) -> PageBox {
+        page::page_box(&self.page_rules, index, default_size)
+    }
+
     pub fn set_quirks_mode(&mut self, enabled: bool) {
         self.quirks_mode = enabled;
     }
 
+    /// Sets whether author rules, style attributes and presentational hints
+    /// apply to elements, from the next restyle on.
+    pub fn set_author_styles_enabled(&mut self, enabled: bool) {
+        self.author_styles_enabled = enabled;
+    }
+
+    /// Puts the device in the forced colors mode of the given palette, or
+    /// out of forced colors modes. Since styles cascaded in the previous
+    /// mode no longer apply, the stylist is dirty if the mode changes.
+    pub fn set_forced_colors(&mut self, forced_colors: Option<ForcedColors>) {
+        if self.forced_colors != forced_colors {
+            self.forced_colors = forced_colors;
+            self.is_device_dirty = true;
+        }
+    }
+
+    /// The palette of the forced colors mode that the device is in, if any.
+    pub fn forced_colors(&self) -> Option<&ForcedColors> {
+        self.forced_colors.as_ref()
+    }
+
+    /// Sets the factor that text is zoomed by. Since styles cascaded with the
+    /// previous factor no longer apply, the stylist is dirty if it changes.
+    pub fn set_text_zoom(&mut self, text_zoom: f32) {
+        if self.text_zoom != text_zoom {
+            self.text_zoom = text_zoom;
+            self.is_device_dirty = true;
+        }
+    }
+
+    /// The factor that text is zoomed by.
+    pub fn text_zoom(&self) -> f32 {
+        self.text_zoom
+    }
+
     /// Returns the applicable CSS declarations for the given element.
     /// This corresponds to `ElementRuleCollector` in WebKit.
     ///
@@ -375,6 +641,10 @@ impl<Impl: SelectorImplExt> Stylist<Impl> {
             None => &self.element_map,
         };
 
+        if let Some(ref selector_profile) = self.selector_profile {
+            selector_profile.note_element(element, parent_bf, pseudo_element);
+        }
+
         let mut shareable = true;
 
         // Step 1: Normal user-agent rules.
@@ -383,12 +653,18 @@ impl<Impl: SelectorImplExt> Stylist<Impl> {
                                                      applicable_declarations,
                                                      &mut shareable);
 
+        // Everything that authors style elements with is left out when
+        // author styles are disabled.
+        let style_attribute = if self.author_styles_enabled { style_attribute } else { None };
+
         // Step 2: Presentational hints.
-        let length = applicable_declarations.len();
-        element.synthesize_presentational_hints_for_legacy_attributes(applicable_declarations);
-        if applicable_declarations.len() != length {
-            // Never share style for elements with preshints
-            shareable = false;
+        if self.author_styles_enabled {
+            let length = applicable_declarations.len();
+            element.synthesize_presentational_hints_for_legacy_attributes(applicable_declarations);
+            if applicable_declarations.len() != length {
+                // Never share style for elements with preshints
+                shareable = false;
+            }
         }
 
         // Step 3: User and author normal rules.
@@ -396,10 +672,14 @@ impl<Impl: SelectorImplExt> Stylist<Impl> {
                                                parent_bf,
                                                applicable_declarations,
                                                &mut shareable);
-        map.author.normal.get_all_matching_rules(element,
-                                                 parent_bf,
-                                                 applicable_declarations,
-                                                 &mut shareable);
+        if self.author_styles_enabled {
+            let author_start = applicable_declarations.len();
+            map.author.normal.get_all_matching_rules(element,
+                                                     parent_bf,
+                                                     applicable_declarations,
+                                                     &mut shareable);
+            self.note_matched_rules(applicable_declarations, author_start);
+        }
 
         // Step 4: Normal style attributes.
         style_attribute.map(|sa| {
@@ -409,10 +689,14 @@ impl<Impl: SelectorImplExt> Stylist<Impl> {
         });
 
         // Step 5: Author-supplied `!important` rules.
-        map.author.important.get_all_matching_rules(element,
-                                                    parent_bf,
-                                                    applicable_declarations,
-                                                    &mut shareable);
+        if self.author_styles_enabled {
+            let author_start = applicable_declarations.len();
+            map.author.important.get_all_matching_rules(element,
+                                                        parent_bf,
+                                                        applicable_declarations,
+                                                        &mut shareable);
+            self.note_matched_rules(applicable_declarations, author_start);
+        }
 
         // Step 6: `!important` style attributes.
         style_attribute.map(|sa| {
@@ -434,12 +718,509 @@ impl<Impl: SelectorImplExt> Stylist<Impl> {
         shareable
     }
 
+    /// Returns the style rules that match the given element, or the given
+    /// pseudo-element of it, in the order in which they are cascaded, along
+    /// with whether each of their declarations wins the cascade. This is what
+    /// developer tools show about the rules of an element.
+    ///
+    /// A rule with both normal and `!important` declarations is returned once
+    /// for each. The declarations of the style attribute and of presentational
+    /// hints take part in the cascade, but are not returned.
+    pub fn matched_rules<E>(&self,
+                            element: &E,
+                            style_attribute: Option<&PropertyDeclarationBlock>,
+                            pseudo_element: Option<&Impl::PseudoElement>)
+                            -> Vec<MatchedRule>
+                            where E: Element<Impl=Impl> + PresentationalHintsSynthetizer {
+        let mut applicable_declarations = vec![];
+        match pseudo_element {
+            Some(pseudo) if Impl::pseudo_element_cascade_type(pseudo).is_precomputed() => {
+                if let Some(declarations) = self.precomputed_pseudo_element_decls.get(pseudo) {
+                    applicable_declarations = declarations.clone();
+                }
+            }
+            Some(pseudo) if self.pseudos_map.get(pseudo).is_none() => {}
+            _ => {
+                self.push_applicable_declarations(element,
+                                                  None,
+                                                  style_attribute,
+                                                  pseudo_element,
+                                                  &mut applicable_declarations);
+            }
+        }
+
+        // The last declaration of each property wins, so the cascade is
+        // walked backwards.
+        let mut seen = vec![];
+        let mut matched_rules = vec![];
+        for block in applicable_declarations.iter().rev() {
+            let mut wins = block.declarations.iter().rev().map(|declaration| {
+                let name = declaration.name();
+                if seen.contains(&name) {
+                    false
+                } else {
+                    seen.push(name);
+                    true
+                }
+            }).collect::<Vec<_>>();
+            wins.reverse();
+
+            let address = declarations_address(&block.declarations);
+            let source = match self.rule_sources.get(block.source_order) {
+                Some(source) if address == source.normal || address == source.important => source,
+                // The style attribute or presentational hints.
+                _ => continue,
+            };
+            matched_rules.push(MatchedRule {
+                stylesheet_url: self.stylesheet_urls[source.stylesheet].clone(),
+                origin: source.origin,
+                selector_text: source.selector_text.clone(),
+                line: source.line,
+                column: source.column,
+                specificity: block.specificity,
+                important: address == source.important,
+                declarations: block.declarations.iter().zip(wins).map(|(declaration, wins)| {
+                    MatchedDeclaration {
+                        declaration: declaration.clone(),
+                        wins_cascade: wins,
+                    }
+                }).collect(),
+            });
+        }
+        matched_rules.reverse();
+        matched_rules
+    }
+
+    /// Records the author rules of the declarations from `start` onwards as
+    /// used, if CSS coverage tracking is enabled.
+    fn note_matched_rules<V>(&self, applicable_declarations: &V, start: usize)
+                             where V: VecLike<DeclarationBlock> {
+        let coverage = match self.coverage {
+            Some(ref coverage) => coverage,
+            None => return,
+        };
+        if start == applicable_declarations.len() {
+            return;
+        }
+
+        let mut matched = coverage.matched.lock().unwrap();
+        for index in start..applicable_declarations.len() {
+            let source_order = applicable_declarations[index].source_order;
+            matched.insert(coverage.rules_by_source_order[source_order]);
+        }
+    }
+
+    /// Returns how many of the style rules of the given stylesheet have
+    /// matched an element so far, or `None` if CSS coverage tracking is
+    /// disabled. Rules in conditional group rules are counted whether or not
+    /// their condition holds.
+    pub fn rule_coverage(&self, stylesheet: &Stylesheet<Impl>) -> Option<StylesheetCoverage> {
+        self.coverage.as_ref().map(|coverage| {
+            // Rules of stylesheets that are not applied to the document are
+            // never used, even if their addresses have since been reused.
+            let is_tracked = coverage.stylesheets.iter().any(|tracked| {
+                &**tracked as *const Stylesheet<Impl> == stylesheet as *const Stylesheet<Impl>
+            });
+            let matched = coverage.matched.lock().unwrap();
+            let mut used_rules = 0;
+            let mut unused = vec![];
+            for rule in stylesheet.rules().style() {
+                if is_tracked && matched.contains(&rule_address(rule)) {
+                    used_rules += 1;
+                } else {
+                    unused.push(UnusedRule {
+                        selector_text: rule.selector_text.clone(),
+                        line: rule.line,
+                        column: rule.column,
+                        source_range: rule.source_range,
+                    });
+                }
+            }
+            StylesheetCoverage {
+                used_rules: used_rules,
+                unused_rules: unused.len(),
+                unused: unused,
+            }
+        })
+    }
+
+    /// Returns how costly the selectors of style rules have been to match
+    /// since the selector maps were last rebuilt or the costs were reset, from
+    /// the costliest on, or `None` if selector profiling is disabled.
+    /// Selectors that were never tried are left out. If `reset` is set, the
+    /// costs are counted from zero again afterwards.
+    pub fn selector_costs(&self, reset: bool) -> Option<Vec<SelectorCost>> {
+        self.selector_profile.as_ref().map(|profile| {
+            let mut totals = profile.totals.lock().unwrap();
+            let mut costs = profile.selectors.iter().zip(totals.iter()).filter(|&(_, total)| {
+                total.attempts > 0
+            }).map(|(selector, total)| {
+                let source = &self.rule_sources[selector.rule_source];
+                SelectorCost {
+                    stylesheet_url: self.stylesheet_urls[source.stylesheet].clone(),
+                    selector_text: source.selector_text.clone(),
+                    selector_index: selector.selector_index,
+                    line: source.line,
+                    column: source.column,
+                    attempts: total.attempts,
+                    matches: total.matches,
+                    time: total.time,
+                }
+            }).collect::<Vec<_>>();
+            costs.sort_by(|a, b| b.time.cmp(&a.time));
+            if reset {
+                for total in totals.iter_mut() {
+                    *total = SelectorTotal::default();
+                }
+            }
+            costs
+        })
+    }
+
     #[inline]
     pub fn is_device_dirty(&self) -> bool {
         self.is_device_dirty
     }
 }
 
+/// A style rule that matches an element, as returned by
+/// `Stylist::matched_rules`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchedRule {
+    /// The URL of the stylesheet of the rule, or that of its document if the
+    /// stylesheet is inline.
+    pub stylesheet_url: Url,
+    pub origin: Origin,
+    /// The selectors of the rule, as they are written in the stylesheet.
+    pub selector_text: String,
+    /// The line and column where the selectors of the rule start, both
+    /// counted from 1.
+    pub line: usize,
+    pub column: usize,
+    /// The specificity of the most specific selector of the rule that matches.
+    pub specificity: u32,
+    /// Whether these are the `!important` declarations of the rule.
+    pub important: bool,
+    pub declarations: Vec<MatchedDeclaration>,
+}
+
+/// A declaration of a `MatchedRule`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchedDeclaration {
+    pub declaration: PropertyDeclaration,
+    /// Whether no later declaration in the cascade sets the same property.
+    pub wins_cascade: bool,
+}
+
+/// Where a style rule that has been taken apart into selector map entries
+/// comes from.
+#[derive(HeapSizeOf)]
+struct RuleSource {
+    /// The index of the URL of the stylesheet of the rule in
+    /// `Stylist::stylesheet_urls`.
+    stylesheet: usize,
+    origin: Origin,
+    selector_text: String,
+    line: usize,
+    column: usize,
+    /// The addresses of the normal and `!important` declarations of the rule,
+    /// which the declaration blocks of its entries share.
+    normal: usize,
+    important: usize,
+}
+
+fn declarations_address(declarations: &Arc<Vec<PropertyDeclaration>>) -> usize {
+    &**declarations as *const Vec<PropertyDeclaration> as usize
+}
+
+/// The key of the selector map bucket that a rule is filed under.
+#[derive(Clone)]
+enum BucketKey {
+    ID(Atom),
+    Class(Atom),
+    /// The local name of an element, and its lowercase version.
+    LocalName(Atom, Atom),
+    /// For rules that may match any element.
+    Universal,
+}
+
+impl BucketKey {
+    /// Returns the key that `SelectorMap::insert` files a rule under: the
+    /// first of an ID, a class and a local name in its rightmost compound
+    /// selector.
+    fn of<Impl: SelectorImpl>(selector: &CompoundSelector<Impl>) -> BucketKey {
+        for simple in &selector.simple_selectors {
+            if let SimpleSelector::ID(ref id) = *simple {
+                return BucketKey::ID(id.clone())
+            }
+        }
+        for simple in &selector.simple_selectors {
+            if let SimpleSelector::Class(ref class) = *simple {
+                return BucketKey::Class(class.clone())
+            }
+        }
+        for simple in &selector.simple_selectors {
+            if let SimpleSelector::LocalName(ref local_name) = *simple {
+                return BucketKey::LocalName(local_name.name.clone(), local_name.lower_name.clone())
+            }
+        }
+        BucketKey::Universal
+    }
+
+    /// Whether a selector map looks an element up in the bucket.
+    fn may_match<E: Element>(&self, element: &E) -> bool {
+        match *self {
+            BucketKey::ID(ref id) => element.get_id().as_ref() == Some(id),
+            BucketKey::Class(ref class) => element.has_class(class),
+            BucketKey::LocalName(ref name, ref lower_name) => {
+                let local_name = element.get_local_name();
+                *local_name == *name || *local_name == *lower_name
+            }
+            BucketKey::Universal => true,
+        }
+    }
+}
+
+/// The keys of the selector map buckets that the rules of a lazily cascaded
+/// pseudo-element are filed under, which tell cheaply which elements they
+/// cannot match.
+#[derive(HeapSizeOf)]
+struct LazyPseudoBuckets {
+    ids: HashSet<Atom>,
+    classes: HashSet<Atom>,
+    local_names: HashSet<Atom>,
+    /// Whether some of the rules have no ID, class or local name to file them
+    /// under, so that they may match any element.
+    universal: bool,
+}
+
+impl LazyPseudoBuckets {
+    fn new() -> LazyPseudoBuckets {
+        LazyPseudoBuckets {
+            ids: HashSet::new(),
+            classes: HashSet::new(),
+            local_names: HashSet::new(),
+            universal: false,
+        }
+    }
+
+    /// Files a rule the way `SelectorMap::insert` does.
+    fn note_selector<Impl: SelectorImpl>(&mut self, selector: &CompoundSelector<Impl>) {
+        match BucketKey::of(selector) {
+            BucketKey::ID(id) => {
+                self.ids.insert(id);
+            }
+            BucketKey::Class(class) => {
+                self.classes.insert(class);
+            }
+            BucketKey::LocalName(name, lower_name) => {
+                self.local_names.insert(name);
+                self.local_names.insert(lower_name);
+            }
+            BucketKey::Universal => self.universal = true,
+        }
+    }
+
+    fn may_match<E: Element>(&self, element: &E) -> bool {
+        if self.universal || self.local_names.contains(&*element.get_local_name()) {
+            return true
+        }
+        if let Some(id) = element.get_id() {
+            if self.ids.contains(&id) {
+                return true
+            }
+        }
+        let mut has_class = false;
+        element.each_class(|class| has_class = has_class || self.classes.contains(class));
+        has_class
+    }
+}
+
+/// How many of the style rules of a stylesheet have been used.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StylesheetCoverage {
+    /// The number of style rules that matched at least one element.
+    pub used_rules: usize,
+    /// The number of style rules that never matched an element.
+    pub unused_rules: usize,
+    /// The style rules that never matched an element, in source order.
+    pub unused: Vec<UnusedRule>,
+}
+
+/// A style rule that never matched an element.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnusedRule {
+    /// The selectors of the rule, as they are written in the stylesheet.
+    pub selector_text: String,
+    /// The line and column where the selectors of the rule start, both
+    /// counted from 1.
+    pub line: usize,
+    pub column: usize,
+    /// The whole rule, from its selectors to its closing brace.
+    pub source_range: SourceRange,
+}
+
+/// Keeps track of which author style rules have ever matched an element, so
+/// that unused CSS can be reported to developers.
+///
+/// Rules are identified by their address, which stays valid because the
+/// stylesheets they belong to are kept alive here.
+struct RuleCoverage<Impl: SelectorImplExt> {
+    /// The document stylesheets the tracked rules belong to.
+    stylesheets: Vec<Arc<Stylesheet<Impl>>>,
+    /// The address of the style rule each rule source order corresponds to.
+    rules_by_source_order: Vec<usize>,
+    /// The addresses of the rules that have matched at least one element.
+    /// This is shared by the threads doing selector matching.
+    matched: Mutex<HashSet<usize>>,
+}
+
+impl<Impl: SelectorImplExt> RuleCoverage<Impl> {
+    fn new() -> RuleCoverage<Impl> {
+        RuleCoverage {
+            stylesheets: vec![],
+            rules_by_source_order: vec![],
+            matched: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Prepares for the selector maps to be rebuilt from the given document
+    /// stylesheets. Rules of stylesheets that are still in use keep their
+    /// coverage, which is otherwise forgotten.
+    fn reset(&mut self, doc_stylesheets: &[Arc<Stylesheet<Impl>>]) {
+        self.rules_by_source_order.clear();
+
+        let current_rules = doc_stylesheets.iter().flat_map(|stylesheet| {
+            stylesheet.rules().style().map(rule_address)
+        }).collect::<HashSet<_>>();
+        let mut matched = self.matched.lock().unwrap();
+        *matched = matched.intersection(&current_rules).cloned().collect();
+
+        self.stylesheets = doc_stylesheets.to_vec();
+    }
+}
+
+/// How costly a selector of a style rule has been to match, as returned by
+/// `Stylist::selector_costs`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectorCost {
+    /// The URL of the stylesheet of the rule, or that of its document if the
+    /// stylesheet is inline.
+    pub stylesheet_url: Url,
+    /// The selectors of the rule, as they are written in the stylesheet, and
+    /// the index of this one among them.
+    pub selector_text: String,
+    pub selector_index: usize,
+    /// The line and column where the selectors of the rule start, both
+    /// counted from 1.
+    pub line: usize,
+    pub column: usize,
+    /// How many times the selector was matched against an element, which is
+    /// each time a selector map looked the element up in its bucket.
+    pub attempts: usize,
+    /// How many of those attempts succeeded.
+    pub matches: usize,
+    /// The time that the attempts took altogether, in nanoseconds.
+    pub time: u64,
+}
+
+/// Measures how costly each selector of the style rules is to match, so that
+/// developers can find the selectors that slow restyles down.
+///
+/// The selector maps cannot be instrumented, so each element is matched again
+/// against the selectors of the buckets that the maps look it up in.
+struct SelectorProfile<Impl: SelectorImplExt> {
+    /// The selectors of the style rules, in the order they were added.
+    selectors: Vec<ProfiledSelector<Impl>>,
+    /// The attempts, matches and time of each selector. This is shared by the
+    /// threads doing selector matching.
+    totals: Mutex<Vec<SelectorTotal>>,
+}
+
+struct ProfiledSelector<Impl: SelectorImplExt> {
+    compound_selectors: Arc<CompoundSelector<Impl>>,
+    pseudo_element: Option<Impl::PseudoElement>,
+    bucket: BucketKey,
+    /// The index of the source of the rule in `Stylist::rule_sources`.
+    rule_source: usize,
+    /// The index of the selector among those of its rule.
+    selector_index: usize,
+}
+
+#[derive(Clone, Copy, Default)]
+struct SelectorTotal {
+    attempts: usize,
+    matches: usize,
+    time: u64,
+}
+
+impl<Impl: SelectorImplExt> SelectorProfile<Impl> {
+    fn new() -> SelectorProfile<Impl> {
+        SelectorProfile {
+            selectors: vec![],
+            totals: Mutex::new(vec![]),
+        }
+    }
+
+    /// Forgets the selectors and their costs, for the selector maps to be
+    /// rebuilt.
+    fn clear(&mut self) {
+        self.selectors.clear();
+        self.totals.lock().unwrap().clear();
+    }
+
+    fn add_selector(&mut self,
+                    selector: &Selector<Impl>,
+                    rule_source: usize,
+                    selector_index: usize) {
+        self.selectors.push(ProfiledSelector {
+            compound_selectors: selector.compound_selectors.clone(),
+            pseudo_element: selector.pseudo_element.clone(),
+            bucket: BucketKey::of(&selector.compound_selectors),
+            rule_source: rule_source,
+            selector_index: selector_index,
+        });
+        self.totals.lock().unwrap().push(SelectorTotal::default());
+    }
+
+    /// Matches an element, or a pseudo-element of it, against the selectors
+    /// that the selector maps try for it, and adds up what it costs.
+    fn note_element<E>(&self,
+                       element: &E,
+                       parent_bf: Option<&BloomFilter>,
+                       pseudo_element: Option<&Impl::PseudoElement>)
+                       where E: Element<Impl=Impl> {
+        let mut costs = vec![];
+        for (index, selector) in self.selectors.iter().enumerate() {
+            if selector.pseudo_element.as_ref() != pseudo_element ||
+               !selector.bucket.may_match(element) {
+                continue
+            }
+            let start_time = precise_time_ns();
+            let matched = matches_compound_selector(&*selector.compound_selectors,
+                                                    element,
+                                                    parent_bf,
+                                                    &mut false);
+            costs.push((index, matched, precise_time_ns() - start_time));
+        }
+
+        // The lock is only taken once the element is done with.
+        let mut totals = self.totals.lock().unwrap();
+        for (index, matched, time) in costs {
+            let total = &mut totals[index];
+            total.attempts += 1;
+            if matched {
+                total.matches += 1;
+            }
+            total.time += time;
+        }
+    }
+}
+
+fn rule_address<Impl: SelectorImpl>(rule: &StyleRule<Impl>) -> usize {
+    rule as *const StyleRule<Impl> as usize
+}
+
 /// Map that contains the CSS rules for a given origin.
 #[derive(HeapSizeOf)]
 struct PerOriginSelectorMap<Impl: SelectorImpl> {
@@ -1,6 +1,7 @@
 /* This Source Code Form is subject to the terms of the Mozilla Public
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+use cascade_snapshot;
 use context;
 use data;
 use properties::ServoComputedValues;
@@ -12,4 +13,7 @@ use stylesheets;
 pub type Stylesheet = stylesheets::Stylesheet<ServoSelectorImpl>;
 pub type PrivateStyleData = data::PrivateStyleData<ServoSelectorImpl, ServoComputedValues>;
 pub type Stylist = selector_matching::Stylist<ServoSelectorImpl>;
+pub type StyleRuleEdit = selector_matching::StyleRuleEdit<ServoSelectorImpl>;
 pub type SharedStyleContext = context::SharedStyleContext<ServoSelectorImpl>;
+pub type CascadeSnapshot = cascade_snapshot::CascadeSnapshot<ServoComputedValues>;
+pub type SharedCascadeSnapshot = cascade_snapshot::SharedCascadeSnapshot<ServoComputedValues>;
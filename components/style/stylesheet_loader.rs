@@ -0,0 +1,57 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Loading of the user-agent and user stylesheets, and of the stylesheets that they import,
+//! which are not fetched like those of documents. Embedders register handlers for the schemes
+//! of the URLs that they serve them from. Without one, `chrome://resources/` URLs are the files
+//! of the resources directory, and `file` URLs those of the file system.
+
+use std::ascii::AsciiExt;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::sync::{Arc, RwLock};
+use url::Url;
+use util::resource_files::read_resource_file;
+
+/// Resolves the URLs of a scheme to the stylesheets there.
+pub trait StylesheetSchemeHandler: Send + Sync {
+    /// Returns the bytes of the stylesheet at `url`, or `Err` if there is none.
+    fn load(&self, url: &Url) -> Result<Vec<u8>, ()>;
+}
+
+lazy_static! {
+    static ref SCHEME_HANDLERS: RwLock<HashMap<String, Arc<StylesheetSchemeHandler>>> = {
+        RwLock::new(HashMap::new())
+    };
+}
+
+/// Registers the handler of the URLs of `scheme`, in place of any earlier one or of the default
+/// handling of `chrome` and `file` URLs. The user-agent stylesheets are loaded once, when the
+/// first document is styled, so handlers that serve them have to be registered before.
+pub fn register_scheme_handler(scheme: &str, handler: Arc<StylesheetSchemeHandler>) {
+    SCHEME_HANDLERS.write().unwrap().insert(scheme.to_ascii_lowercase(), handler);
+}
+
+/// Returns the bytes of the stylesheet at `url`, or `Err` if there is none, or if no handler
+/// knows its scheme.
+pub fn load(url: &Url) -> Result<Vec<u8>, ()> {
+    let handler = SCHEME_HANDLERS.read().unwrap().get(url.scheme()).cloned();
+    if let Some(handler) = handler {
+        return handler.load(url)
+    }
+    match url.scheme() {
+        "chrome" if url.host_str() == Some("resources") => {
+            // The path is normalized as the URL is parsed, so it stays in the directory.
+            read_resource_file(url.path().trim_left_matches('/')).map_err(|_| ())
+        }
+        "file" => {
+            let mut file = try!(File::open(try!(url.to_file_path())).map_err(|_| ()));
+            let mut bytes = vec![];
+            try!(file.read_to_end(&mut bytes).map_err(|_| ()));
+            Ok(bytes)
+        }
+        _ => Err(()),
+    }
+}
@@ -3,20 +3,24 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use cssparser::{AtRuleParser, Parser, QualifiedRuleParser, decode_stylesheet_bytes};
-use cssparser::{AtRuleType, RuleListParser};
+use counter_style::{CounterStyleRule, parse_counter_style_block, parse_counter_style_name};
+use cssparser::{AtRuleType, RuleListParser, SourceLocation, SourcePosition};
 use encoding::EncodingRef;
 use error_reporting::ParseErrorReporter;
 use font_face::{FontFaceRule, parse_font_face_block};
 use media_queries::{Device, MediaQueryList, parse_media_query_list};
+use page::{PageRule, PageSelector, parse_page_block, parse_page_selectors};
 use parser::{ParserContext, ParserContextExtraData, log_css_error};
-use properties::{PropertyDeclarationBlock, parse_property_declaration_list};
+use properties::{PropertyDeclarationBlock, parse_property_declaration_list_with_positions};
 use selectors::parser::{Selector, SelectorImpl, parse_selector_list};
 use smallvec::SmallVec;
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
+use std::cmp::max;
 use std::iter::Iterator;
 use std::marker::PhantomData;
 use std::slice;
 use string_cache::{Atom, Namespace};
+use stylesheet_loader;
 use url::Url;
 use viewport::ViewportRule;
 
@@ -46,6 +50,11 @@ pub struct Stylesheet<Impl: SelectorImpl> {
     pub media: Option<MediaQueryList>,
     pub origin: Origin,
     pub dirty_on_viewport_size_change: bool,
+    /// The URL of the source map for this stylesheet, as given by a
+    /// `/*# sourceMappingURL=... */` comment, if any.
+    pub source_map_url: Option<Url>,
+    /// The URL of the stylesheet, or that of its document if it is inline.
+    pub url: Url,
 }
 
 
@@ -53,16 +62,41 @@ pub struct Stylesheet<Impl: SelectorImpl> {
 pub enum CSSRule<Impl: SelectorImpl> {
     Charset(String),
     Namespace(Option<String>, Namespace),
+    Import(ImportRule<Impl>),
     Style(StyleRule<Impl>),
     Media(MediaRule<Impl>),
     FontFace(FontFaceRule),
     Viewport(ViewportRule),
+    CounterStyle(CounterStyleRule),
+    Page(PageRule),
+}
+
+/// An `@import` rule of a user-agent or user stylesheet, along with the stylesheet that it
+/// imports, which the stylesheet loader loads as the rule is parsed. The `@import` rules of
+/// the stylesheets of documents are dropped, until they are fetched like their stylesheets.
+#[derive(Debug, HeapSizeOf, PartialEq)]
+pub struct ImportRule<Impl: SelectorImpl> {
+    pub url: Url,
+    pub media_queries: MediaQueryList,
+    /// The imported stylesheet, which is empty if it could not be loaded.
+    pub stylesheet: Stylesheet<Impl>,
+    /// The whole rule, from its at-keyword to its last media query.
+    pub source_range: SourceRange,
+}
+
+impl<Impl: SelectorImpl> ImportRule<Impl> {
+    #[inline]
+    pub fn evaluate(&self, device: &Device) -> bool {
+        self.media_queries.evaluate(device)
+    }
 }
 
 #[derive(Debug, HeapSizeOf, PartialEq)]
 pub struct MediaRule<Impl: SelectorImpl> {
     pub media_queries: MediaQueryList,
     pub rules: Vec<CSSRule<Impl>>,
+    /// The whole rule, from its at-keyword to its closing brace.
+    pub source_range: SourceRange,
 }
 
 impl<Impl: SelectorImpl> MediaRule<Impl> {
@@ -75,7 +109,114 @@ impl<Impl: SelectorImpl> MediaRule<Impl> {
 #[derive(Debug, HeapSizeOf, PartialEq)]
 pub struct StyleRule<Impl: SelectorImpl> {
     pub selectors: Vec<Selector<Impl>>,
+    /// The selectors as they are written in the stylesheet, without the
+    /// whitespace around them.
+    pub selector_text: String,
     pub declarations: PropertyDeclarationBlock,
+    /// The line and column where the selectors of the rule start in the
+    /// stylesheet, both counted from 1.
+    pub line: usize,
+    pub column: usize,
+    /// The whole rule, from its selectors to its closing brace.
+    pub source_range: SourceRange,
+    /// The declarations of the rule as they are written, in source order,
+    /// including those that are invalid or overridden by later ones.
+    pub declaration_sources: Vec<DeclarationSource>,
+}
+
+/// A range of the source text of a stylesheet, or of the text of its `<style>`
+/// element if it is inline.
+#[derive(Clone, Copy, Debug, Default, HeapSizeOf, PartialEq, Eq)]
+pub struct SourceRange {
+    /// The byte offsets where the range starts and ends.
+    pub start: usize,
+    pub end: usize,
+    /// The lines and columns where the range starts and ends, all counted from
+    /// 1. Columns are counted in bytes, as in parse error reports.
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+/// A declaration of a style rule, as it is written in the stylesheet.
+#[derive(Clone, Debug, HeapSizeOf, PartialEq, Eq)]
+pub struct DeclarationSource {
+    /// The name of the property, as it is written.
+    pub name: String,
+    /// The declaration from its name to its value or `!important`, without the
+    /// semicolon after it.
+    pub range: SourceRange,
+}
+
+/// The source text of a stylesheet that is being parsed, which the positions
+/// of its parser are converted into ranges of.
+struct SourceText<'a> {
+    css: &'a str,
+    /// The byte offset where each line starts, with line breaks counted as the
+    /// tokenizer counts them.
+    line_starts: Vec<usize>,
+}
+
+impl<'a> SourceText<'a> {
+    fn new(css: &'a str) -> SourceText<'a> {
+        let bytes = css.as_bytes();
+        let mut line_starts = vec![0];
+        for (index, &byte) in bytes.iter().enumerate() {
+            match byte {
+                b'\n' | b'\x0C' => line_starts.push(index + 1),
+                b'\r' if bytes.get(index + 1) != Some(&b'\n') => line_starts.push(index + 1),
+                _ => {}
+            }
+        }
+        SourceText {
+            css: css,
+            line_starts: line_starts,
+        }
+    }
+
+    /// Returns the byte offset of a position of the parser.
+    fn offset(&self, input: &Parser, position: SourcePosition) -> usize {
+        let location = input.source_location(position);
+        self.line_starts[location.line - 1] + location.column - 1
+    }
+
+    /// Returns the offset just after the closing brace of the block that the
+    /// parser is at the end of, or that of its end if it is unclosed.
+    fn block_end(&self, input: &Parser) -> usize {
+        let end = self.offset(input, input.position());
+        if self.css[end..].starts_with("}") { end + 1 } else { end }
+    }
+
+    fn location(&self, offset: usize) -> (usize, usize) {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        (line + 1, offset - self.line_starts[line] + 1)
+    }
+
+    /// Returns the range between two offsets, without the whitespace and
+    /// semicolons at its ends.
+    fn range(&self, start: usize, end: usize) -> SourceRange {
+        fn is_separator(c: char) -> bool {
+            c.is_whitespace() || c == ';'
+        }
+        let text = &self.css[start..end];
+        let (start, end) = (end - text.trim_left_matches(is_separator).len(),
+                            start + text.trim_right_matches(is_separator).len());
+        let end = max(start, end);
+        let (start_line, start_column) = self.location(start);
+        let (end_line, end_column) = self.location(end);
+        SourceRange {
+            start: start,
+            end: end,
+            start_line: start_line,
+            start_column: start_column,
+            end_line: end_line,
+            end_column: end_column,
+        }
+    }
 }
 
 
@@ -114,6 +255,7 @@ impl<Impl: SelectorImpl> Stylesheet<Impl> {
         let rule_parser = TopLevelRuleParser {
             context: ParserContext::new_with_extra_data(origin, &base_url, error_reporter.clone(),
                                                         extra_data),
+            source: SourceText::new(css),
             state: Cell::new(State::Start),
             _impl: PhantomData,
         };
@@ -121,11 +263,16 @@ impl<Impl: SelectorImpl> Stylesheet<Impl> {
         input.look_for_viewport_percentages();
 
         let mut rules = Vec::new();
+        let mut dirty_on_viewport_size_change = false;
         {
             let mut iter = RuleListParser::new_for_stylesheet(&mut input, rule_parser);
             while let Some(result) = iter.next() {
                 match result {
                     Ok(rule) => {
+                        if let CSSRule::Import(ref rule) = rule {
+                            dirty_on_viewport_size_change |=
+                                rule.stylesheet.dirty_on_viewport_size_change;
+                        }
                         if let CSSRule::Namespace(ref prefix, ref namespace) = rule {
                             if let Some(prefix) = prefix.as_ref() {
                                 iter.parser.context.selector_context.namespace_prefixes.insert(
@@ -150,7 +297,10 @@ impl<Impl: SelectorImpl> Stylesheet<Impl> {
             origin: origin,
             rules: rules,
             media: None,
-            dirty_on_viewport_size_change: input.seen_viewport_percentages(),
+            dirty_on_viewport_size_change: dirty_on_viewport_size_change ||
+                                           input.seen_viewport_percentages(),
+            source_map_url: parse_source_map_url(css, &base_url),
+            url: base_url,
         }
     }
 
@@ -185,6 +335,39 @@ impl<Impl: SelectorImpl> Stylesheet<Impl> {
     }
 }
 
+/// Finds the source map URL of a stylesheet, as specified by the last
+/// `/*# sourceMappingURL=<url> */` comment in it, and resolves it against the
+/// stylesheet's URL. The deprecated `/*@ sourceMappingURL=<url> */` form is
+/// also accepted.
+///
+/// https://sourcemaps.info/spec.html#h.lmz475t4mvbx
+pub fn parse_source_map_url(css: &str, base_url: &Url) -> Option<Url> {
+    let mut source_map_url = None;
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        let comment = &rest[start + 2..];
+        let (body, remainder) = match comment.find("*/") {
+            Some(end) => (&comment[..end], &comment[end + 2..]),
+            None => (comment, ""),
+        };
+        rest = remainder;
+
+        let body = body.trim_left();
+        if !(body.starts_with("# ") || body.starts_with("@ ")) {
+            continue
+        }
+        let body = body[2..].trim_left();
+        if !body.starts_with("sourceMappingURL=") {
+            continue
+        }
+        let url = body["sourceMappingURL=".len()..].split_whitespace().next();
+        if let Some(url) = url {
+            source_map_url = base_url.join(url).ok();
+        }
+    }
+    source_map_url
+}
+
 /// `CSSRule` iterator.
 ///
 /// The iteration order is pre-order. Specifically, this implies that a
@@ -223,6 +406,18 @@ impl<'a, Impl: SelectorImpl + 'a> Iterator for Rules<'a, Impl> {
                         self.stack.push(rule.rules.iter());
                     }
                 }
+                // and imported stylesheets, whose rules come where they are imported
+                if let &CSSRule::Import(ref rule) = rule {
+                    if let Some(device) = self.device {
+                        if rule.evaluate(device) {
+                            self.stack.push(rule.stylesheet.rules.iter());
+                        } else {
+                            continue
+                        }
+                    } else {
+                        self.stack.push(rule.stylesheet.rules.iter());
+                    }
+                }
 
                 return Some(rule)
             }
@@ -244,7 +439,9 @@ pub mod rule_filter {
 
     use selectors::parser::SelectorImpl;
     use std::marker::PhantomData;
+    use super::super::counter_style::CounterStyleRule;
     use super::super::font_face::FontFaceRule;
+    use super::super::page::PageRule;
     use super::super::viewport::ViewportRule;
     use super::{CSSRule, MediaRule, StyleRule};
 
@@ -293,6 +490,8 @@ pub mod rule_filter {
     rule_filter!(Style -> StyleRule<Impl>);
     rule_filter!(FontFace -> FontFaceRule);
     rule_filter!(Viewport -> ViewportRule);
+    rule_filter!(CounterStyle -> CounterStyleRule);
+    rule_filter!(Page -> PageRule);
 }
 
 /// Extension methods for `CSSRule` iterators.
@@ -308,6 +507,12 @@ pub trait CSSRuleIteratorExt<'a, Impl: SelectorImpl + 'a>: Iterator<Item=&'a CSS
 
     /// Yield only @viewport rules.
     fn viewport(self) -> rule_filter::Viewport<'a, Self>;
+
+    /// Yield only @counter-style rules.
+    fn counter_style(self) -> rule_filter::CounterStyle<'a, Self>;
+
+    /// Yield only @page rules.
+    fn page(self) -> rule_filter::Page<'a, Self>;
 }
 
 impl<'a, I, Impl: SelectorImpl + 'a> CSSRuleIteratorExt<'a, Impl> for I where I: Iterator<Item=&'a CSSRule<Impl>> {
@@ -330,12 +535,26 @@ impl<'a, I, Impl: SelectorImpl + 'a> CSSRuleIteratorExt<'a, Impl> for I where I:
     fn viewport(self) -> rule_filter::Viewport<'a, I> {
         rule_filter::Viewport::new(self)
     }
+
+    #[inline]
+    fn counter_style(self) -> rule_filter::CounterStyle<'a, I> {
+        rule_filter::CounterStyle::new(self)
+    }
+
+    #[inline]
+    fn page(self) -> rule_filter::Page<'a, I> {
+        rule_filter::Page::new(self)
+    }
 }
 
-fn parse_nested_rules<Impl: SelectorImpl>(context: &ParserContext, input: &mut Parser) -> Vec<CSSRule<Impl>> {
+fn parse_nested_rules<Impl: SelectorImpl>(context: &ParserContext,
+                                          source: &SourceText,
+                                          input: &mut Parser)
+                                          -> Vec<CSSRule<Impl>> {
     let mut iter = RuleListParser::new_for_nested_rule(input,
                                                        NestedRuleParser {
                                                            context: context,
+                                                           source: source,
                                                            _impl: PhantomData
                                                        });
     let mut rules = Vec::new();
@@ -355,10 +574,51 @@ fn parse_nested_rules<Impl: SelectorImpl>(context: &ParserContext, input: &mut P
 
 struct TopLevelRuleParser<'a, Impl: SelectorImpl> {
     context: ParserContext<'a>,
+    source: SourceText<'a>,
     state: Cell<State>,
     _impl: PhantomData<Impl>
 }
 
+impl<'a, Impl: SelectorImpl> TopLevelRuleParser<'a, Impl> {
+    fn nested(&self) -> NestedRuleParser<Impl> {
+        NestedRuleParser {
+            context: &self.context,
+            source: &self.source,
+            _impl: PhantomData,
+        }
+    }
+}
+
+thread_local!(static IMPORTING: RefCell<Vec<Url>> = RefCell::new(vec![]));
+
+/// Loads and parses the stylesheet that an `@import` rule imports into a stylesheet of the
+/// origin of `context`. The stylesheet is empty if it cannot be loaded, or if it is already
+/// being imported, as when stylesheets import each other.
+fn import_stylesheet<Impl: SelectorImpl>(url: &Url, context: &ParserContext) -> Stylesheet<Impl> {
+    let cyclic = *url == *context.base_url || IMPORTING.with(|importing| {
+        importing.borrow().contains(url)
+    });
+    let bytes = if cyclic {
+        warn!("Not importing {}, which is already being imported", url);
+        vec![]
+    } else {
+        stylesheet_loader::load(url).unwrap_or_else(|()| {
+            warn!("Failed to import {}", url);
+            vec![]
+        })
+    };
+    IMPORTING.with(|importing| importing.borrow_mut().push(url.clone()));
+    let stylesheet = Stylesheet::from_bytes(&bytes,
+                                            url.clone(),
+                                            None,
+                                            None,
+                                            context.stylesheet_origin,
+                                            context.error_reporter.clone(),
+                                            ParserContextExtraData::default());
+    IMPORTING.with(|importing| importing.borrow_mut().pop());
+    stylesheet
+}
+
 #[derive(Eq, PartialEq, Ord, PartialOrd, Copy, Clone)]
 enum State {
     Start = 1,
@@ -372,15 +632,17 @@ enum AtRulePrelude {
     FontFace,
     Media(MediaQueryList),
     Viewport,
+    CounterStyle(Atom),
+    Page(Vec<PageSelector>),
 }
 
 
 impl<'a, Impl: SelectorImpl> AtRuleParser for TopLevelRuleParser<'a, Impl> {
-    type Prelude = AtRulePrelude;
+    type Prelude = (AtRulePrelude, usize);
     type AtRule = CSSRule<Impl>;
 
     fn parse_prelude(&self, name: &str, input: &mut Parser)
-                     -> Result<AtRuleType<AtRulePrelude, CSSRule<Impl>>, ()> {
+                     -> Result<AtRuleType<(AtRulePrelude, usize), CSSRule<Impl>>, ()> {
         match_ignore_ascii_case! { name,
             "charset" => {
                 if self.state.get() <= State::Start {
@@ -395,8 +657,22 @@ impl<'a, Impl: SelectorImpl> AtRuleParser for TopLevelRuleParser<'a, Impl> {
             "import" => {
                 if self.state.get() <= State::Imports {
                     self.state.set(State::Imports);
-                    // TODO: support @import
-                    return Err(())  // "@import is not supported yet"
+                    if self.context.stylesheet_origin == Origin::Author {
+                        // TODO: support @import in the stylesheets of documents
+                        return Err(())  // "@import is not supported yet"
+                    }
+                    let start = self.source.offset(input, input.position()) - "@import".len();
+                    let url = try!(self.context.base_url.join(&try!(input.expect_url_or_string()))
+                                                        .map_err(|_| ()));
+                    let media_queries = parse_media_query_list(input);
+                    let stylesheet = import_stylesheet(&url, &self.context);
+                    let end = self.source.offset(input, input.position());
+                    return Ok(AtRuleType::WithoutBlock(CSSRule::Import(ImportRule {
+                        url: url,
+                        media_queries: media_queries,
+                        stylesheet: stylesheet,
+                        source_range: self.source.range(start, end),
+                    })))
                 } else {
                     return Err(())  // "@import must be before any rule but @charset"
                 }
@@ -416,96 +692,161 @@ impl<'a, Impl: SelectorImpl> AtRuleParser for TopLevelRuleParser<'a, Impl> {
         }
 
         self.state.set(State::Body);
-        AtRuleParser::parse_prelude(&NestedRuleParser { context: &self.context, _impl: PhantomData }, name, input)
+        AtRuleParser::parse_prelude(&self.nested(), name, input)
     }
 
     #[inline]
-    fn parse_block(&self, prelude: AtRulePrelude, input: &mut Parser) -> Result<CSSRule<Impl>, ()> {
-        AtRuleParser::parse_block(&NestedRuleParser { context: &self.context, _impl: PhantomData }, prelude, input)
+    fn parse_block(&self, prelude: (AtRulePrelude, usize), input: &mut Parser)
+                   -> Result<CSSRule<Impl>, ()> {
+        AtRuleParser::parse_block(&self.nested(), prelude, input)
     }
 }
 
 
 impl<'a, Impl: SelectorImpl> QualifiedRuleParser for TopLevelRuleParser<'a, Impl> {
-    type Prelude = Vec<Selector<Impl>>;
+    type Prelude = StyleRulePrelude<Impl>;
     type QualifiedRule = CSSRule<Impl>;
 
     #[inline]
-    fn parse_prelude(&self, input: &mut Parser) -> Result<Vec<Selector<Impl>>, ()> {
+    fn parse_prelude(&self, input: &mut Parser) -> Result<StyleRulePrelude<Impl>, ()> {
         self.state.set(State::Body);
-        QualifiedRuleParser::parse_prelude(&NestedRuleParser { context: &self.context, _impl: PhantomData }, input)
+        QualifiedRuleParser::parse_prelude(&self.nested(), input)
     }
 
     #[inline]
-    fn parse_block(&self, prelude: Vec<Selector<Impl>>, input: &mut Parser) -> Result<CSSRule<Impl>, ()> {
-        QualifiedRuleParser::parse_block(&NestedRuleParser { context: &self.context, _impl: PhantomData },
-                                         prelude, input)
+    fn parse_block(&self, prelude: StyleRulePrelude<Impl>, input: &mut Parser)
+                   -> Result<CSSRule<Impl>, ()> {
+        QualifiedRuleParser::parse_block(&self.nested(), prelude, input)
     }
 }
 
 
 struct NestedRuleParser<'a, 'b: 'a, Impl: SelectorImpl> {
     context: &'a ParserContext<'b>,
+    source: &'a SourceText<'a>,
     _impl: PhantomData<Impl>,
 }
 
 
 impl<'a, 'b, Impl: SelectorImpl> AtRuleParser for NestedRuleParser<'a, 'b, Impl> {
-    type Prelude = AtRulePrelude;
+    /// The prelude, and the offset where the at-keyword of the rule starts.
+    type Prelude = (AtRulePrelude, usize);
     type AtRule = CSSRule<Impl>;
 
     fn parse_prelude(&self, name: &str, input: &mut Parser)
-                     -> Result<AtRuleType<AtRulePrelude, CSSRule<Impl>>, ()> {
-        match_ignore_ascii_case! { name,
+                     -> Result<AtRuleType<(AtRulePrelude, usize), CSSRule<Impl>>, ()> {
+        let start = self.source.offset(input, input.position()) - name.len() - "@".len();
+        let prelude = match_ignore_ascii_case! { name,
             "media" => {
-                let media_queries = parse_media_query_list(input);
-                Ok(AtRuleType::WithBlock(AtRulePrelude::Media(media_queries)))
+                AtRulePrelude::Media(parse_media_query_list(input))
             },
             "font-face" => {
-                Ok(AtRuleType::WithBlock(AtRulePrelude::FontFace))
+                AtRulePrelude::FontFace
             },
             "viewport" => {
                 if ::util::prefs::get_pref("layout.viewport.enabled").as_boolean().unwrap_or(false) {
-                    Ok(AtRuleType::WithBlock(AtRulePrelude::Viewport))
+                    AtRulePrelude::Viewport
                 } else {
-                    Err(())
+                    return Err(())
                 }
             },
-            _ => Err(())
-        }
+            "counter-style" => {
+                AtRulePrelude::CounterStyle(try!(parse_counter_style_name(input)))
+            },
+            "page" => {
+                AtRulePrelude::Page(try!(parse_page_selectors(input)))
+            },
+            _ => return Err(())
+        };
+        Ok(AtRuleType::WithBlock((prelude, start)))
     }
 
-    fn parse_block(&self, prelude: AtRulePrelude, input: &mut Parser) -> Result<CSSRule<Impl>, ()> {
+    fn parse_block(&self, prelude: (AtRulePrelude, usize), input: &mut Parser)
+                   -> Result<CSSRule<Impl>, ()> {
+        let (prelude, start) = prelude;
         match prelude {
             AtRulePrelude::FontFace => {
-                parse_font_face_block(self.context, input).map(CSSRule::FontFace)
+                parse_font_face_block(self.context, input).map(|mut rule| {
+                    rule.source_range = self.source.range(start, self.source.block_end(input));
+                    CSSRule::FontFace(rule)
+                })
             }
             AtRulePrelude::Media(media_queries) => {
+                let rules = parse_nested_rules(self.context, self.source, input);
                 Ok(CSSRule::Media(MediaRule {
                     media_queries: media_queries,
-                    rules: parse_nested_rules(self.context, input),
+                    rules: rules,
+                    source_range: self.source.range(start, self.source.block_end(input)),
                 }))
             }
             AtRulePrelude::Viewport => {
                 ViewportRule::parse(input, self.context).map(CSSRule::Viewport)
             }
+            AtRulePrelude::CounterStyle(name) => {
+                parse_counter_style_block(self.context, name, input).map(|mut rule| {
+                    rule.source_range = self.source.range(start, self.source.block_end(input));
+                    CSSRule::CounterStyle(rule)
+                })
+            }
+            AtRulePrelude::Page(selectors) => {
+                parse_page_block(self.context, selectors, input).map(|mut rule| {
+                    rule.source_range = self.source.range(start, self.source.block_end(input));
+                    CSSRule::Page(rule)
+                })
+            }
         }
     }
 }
 
 
+/// The prelude of a style rule.
+struct StyleRulePrelude<Impl: SelectorImpl> {
+    selectors: Vec<Selector<Impl>>,
+    /// The selectors as they are written, without the whitespace around them.
+    selector_text: String,
+    /// Where the selectors start, as a line and column and as an offset.
+    location: SourceLocation,
+    start: usize,
+}
+
+
 impl<'a, 'b, Impl: SelectorImpl> QualifiedRuleParser for NestedRuleParser<'a, 'b, Impl> {
-    type Prelude = Vec<Selector<Impl>>;
+    type Prelude = StyleRulePrelude<Impl>;
     type QualifiedRule = CSSRule<Impl>;
 
-    fn parse_prelude(&self, input: &mut Parser) -> Result<Vec<Selector<Impl>>, ()> {
-        parse_selector_list(&self.context.selector_context, input)
+    fn parse_prelude(&self, input: &mut Parser) -> Result<StyleRulePrelude<Impl>, ()> {
+        let location = input.current_source_location();
+        let start = input.position();
+        let selectors = try!(parse_selector_list(&self.context.selector_context, input));
+        Ok(StyleRulePrelude {
+            selectors: selectors,
+            selector_text: input.slice_from(start).trim().to_owned(),
+            location: location,
+            start: self.source.offset(input, start),
+        })
     }
 
-    fn parse_block(&self, prelude: Vec<Selector<Impl>>, input: &mut Parser) -> Result<CSSRule<Impl>, ()> {
+    fn parse_block(&self, prelude: StyleRulePrelude<Impl>, input: &mut Parser)
+                   -> Result<CSSRule<Impl>, ()> {
+        let source = self.source;
+        let mut declaration_sources = vec![];
+        let declarations = parse_property_declaration_list_with_positions(
+            self.context, input, |input, start, end| {
+                let range = source.range(source.offset(input, start), source.offset(input, end));
+                let text = &source.css[range.start..range.end];
+                declaration_sources.push(DeclarationSource {
+                    name: text.split(':').next().unwrap_or("").trim().to_owned(),
+                    range: range,
+                });
+            });
         Ok(CSSRule::Style(StyleRule {
-            selectors: prelude,
-            declarations: parse_property_declaration_list(self.context, input)
+            selectors: prelude.selectors,
+            selector_text: prelude.selector_text,
+            declarations: declarations,
+            line: prelude.location.line,
+            column: prelude.location.column,
+            source_range: source.range(prelude.start, source.block_end(input)),
+            declaration_sources: declaration_sources,
         }))
     }
 }
@@ -9,6 +9,8 @@ use selector_impl::SelectorImplExt;
 use selectors::Element;
 use selectors::bloom::BloomFilter;
 use std::cell::RefCell;
+use std::sync::atomic::{ATOMIC_USIZE_INIT, AtomicUsize, Ordering};
+use time::precise_time_ns;
 use util::opts;
 use util::tid::tid;
 
@@ -39,6 +41,42 @@ pub type Generation = u32;
 thread_local!(
     pub static STYLE_BLOOM: RefCell<Option<(Box<BloomFilter>, UnsafeNode, Generation)>> = RefCell::new(None));
 
+/// The nanoseconds that selector matching and the cascade took, and the number of nodes that they
+/// ran for. They are only accumulated when the shared style context records phase times, by all
+/// the threads that style nodes, so the times add up those of every thread.
+static SELECTOR_MATCHING_TIME: AtomicUsize = ATOMIC_USIZE_INIT;
+static SELECTOR_MATCHING_COUNT: AtomicUsize = ATOMIC_USIZE_INIT;
+static CASCADE_TIME: AtomicUsize = ATOMIC_USIZE_INIT;
+static CASCADE_COUNT: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// The time that a phase of styling took over all the threads, and the number of nodes it ran for.
+#[derive(Clone, Copy, Debug)]
+pub struct PhaseTotal {
+    /// The time in nanoseconds.
+    pub time: u64,
+    pub count: usize,
+}
+
+/// Returns the totals of selector matching and of the cascade since the last call, and starts
+/// counting again from zero.
+pub fn get_and_reset_phase_totals() -> (PhaseTotal, PhaseTotal) {
+    let selector_matching = PhaseTotal {
+        time: SELECTOR_MATCHING_TIME.swap(0, Ordering::SeqCst) as u64,
+        count: SELECTOR_MATCHING_COUNT.swap(0, Ordering::SeqCst),
+    };
+    let cascade = PhaseTotal {
+        time: CASCADE_TIME.swap(0, Ordering::SeqCst) as u64,
+        count: CASCADE_COUNT.swap(0, Ordering::SeqCst),
+    };
+    (selector_matching, cascade)
+}
+
+#[inline]
+fn note_phase_time(time: &AtomicUsize, count: &AtomicUsize, start_time: u64) {
+    time.fetch_add((precise_time_ns() - start_time) as usize, Ordering::Relaxed);
+    count.fetch_add(1, Ordering::Relaxed);
+}
+
 /// Returns the thread local bloom filter.
 ///
 /// If one does not exist, a new one will be made for you. If it is out of date,
@@ -163,6 +201,7 @@ pub fn recalc_style_at<'a, N, C>(context: &'a C,
         };
 
         // Otherwise, match and cascade selectors.
+        let record_phase_times = context.shared_context().record_phase_times;
         match sharing_result {
             StyleSharingResult::CannotShare => {
                 let mut applicable_declarations = ApplicableDeclarations::new();
@@ -172,9 +211,16 @@ pub fn recalc_style_at<'a, N, C>(context: &'a C,
                         // Perform the CSS selector matching.
                         let stylist = &context.shared_context().stylist;
 
-                        if element.match_element(&**stylist,
-                                                 Some(&*bf),
-                                                 &mut applicable_declarations) {
+                        let start_time = if record_phase_times { precise_time_ns() } else { 0 };
+                        let shareable = element.match_element(&**stylist,
+                                                              Some(&*bf),
+                                                              &mut applicable_declarations);
+                        if record_phase_times {
+                            note_phase_time(&SELECTOR_MATCHING_TIME,
+                                            &SELECTOR_MATCHING_COUNT,
+                                            start_time);
+                        }
+                        if shareable {
                             Some(element)
                         } else {
                             None
@@ -189,6 +235,7 @@ pub fn recalc_style_at<'a, N, C>(context: &'a C,
                 };
 
                 // Perform the CSS cascade.
+                let start_time = if record_phase_times { precise_time_ns() } else { 0 };
                 unsafe {
                     node.cascade_node(&context.shared_context(),
                                       parent_opt,
@@ -196,6 +243,9 @@ pub fn recalc_style_at<'a, N, C>(context: &'a C,
                                       &mut context.local_context().applicable_declarations_cache.borrow_mut(),
                                       &context.shared_context().new_animations_sender);
                 }
+                if record_phase_times {
+                    note_phase_time(&CASCADE_TIME, &CASCADE_COUNT, start_time);
+                }
 
                 // Add ourselves to the LRU cache.
                 if let Some(element) = shareable_element {
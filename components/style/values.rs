@@ -1218,6 +1218,8 @@ pub mod specified {
     pub enum Image {
         Url(Url),
         LinearGradient(LinearGradient),
+        ImageSet(Vec<ImageSetOption>),
+        CrossFade(Box<CrossFade>),
     }
 
     impl ToCss for Image {
@@ -1227,7 +1229,19 @@ pub mod specified {
                 Image::Url(ref url) => {
                     url.to_css(dest)
                 }
-                Image::LinearGradient(ref gradient) => gradient.to_css(dest)
+                Image::LinearGradient(ref gradient) => gradient.to_css(dest),
+                Image::ImageSet(ref options) => {
+                    try!(dest.write_str("image-set("));
+                    for (i, option) in options.iter().enumerate() {
+                        if i > 0 {
+                            try!(dest.write_str(", "));
+                        }
+                        try!(option.image.to_css(dest));
+                        try!(write!(dest, " {}x", option.resolution));
+                    }
+                    dest.write_str(")")
+                }
+                Image::CrossFade(ref cross_fade) => cross_fade.to_css(dest),
             }
         }
     }
@@ -1242,12 +1256,104 @@ pub mod specified {
                         Ok(Image::LinearGradient(try!(
                             input.parse_nested_block(LinearGradient::parse_function))))
                     },
+                    "image-set" => {
+                        input.parse_nested_block(|input| {
+                            input.parse_comma_separated(|input| {
+                                ImageSetOption::parse(context, input)
+                            }).map(Image::ImageSet)
+                        })
+                    },
+                    "cross-fade" => {
+                        input.parse_nested_block(|input| CrossFade::parse(context, input))
+                             .map(|cross_fade| Image::CrossFade(Box::new(cross_fade)))
+                    },
                     _ => Err(())
                 }
             }
         }
     }
 
+    /// One of the candidates of an `image-set()`.
+    #[derive(Clone, PartialEq, Debug, HeapSizeOf)]
+    pub struct ImageSetOption {
+        pub image: Image,
+        /// The resolution of the image, in dots per `px`.
+        pub resolution: CSSFloat,
+    }
+
+    impl ImageSetOption {
+        fn parse(context: &ParserContext, input: &mut Parser) -> Result<ImageSetOption, ()> {
+            // Plain strings are URLs.
+            let image = match input.try(|input| input.expect_string()) {
+                Ok(url) => Image::Url(context.parse_url(&url)),
+                Err(()) => try!(Image::parse(context, input)),
+            };
+            let resolution = match try!(input.next()) {
+                Token::Dimension(ref value, ref unit) if value.value > 0. => {
+                    match_ignore_ascii_case! { &**unit,
+                        "x" => value.value,
+                        "dppx" => value.value,
+                        "dpi" => value.value / 96.,
+                        "dpcm" => value.value * 2.54 / 96.,
+                        _ => return Err(())
+                    }
+                }
+                _ => return Err(()),
+            };
+            Ok(ImageSetOption {
+                image: image,
+                resolution: resolution,
+            })
+        }
+    }
+
+    /// Specified values for a `cross-fade()` of two images.
+    #[derive(Clone, PartialEq, Debug, HeapSizeOf)]
+    pub struct CrossFade {
+        /// How much of `image` is in the result, between 0 and 1.
+        pub percentage: Option<CSSFloat>,
+        /// The image faded in.
+        pub image: Image,
+        /// The image faded out.
+        pub final_image: Image,
+    }
+
+    impl ToCss for CrossFade {
+        fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+            try!(dest.write_str("cross-fade("));
+            if let Some(percentage) = self.percentage {
+                try!(write!(dest, "{}% ", percentage * 100.));
+            }
+            try!(self.image.to_css(dest));
+            try!(dest.write_str(", "));
+            try!(self.final_image.to_css(dest));
+            dest.write_str(")")
+        }
+    }
+
+    impl CrossFade {
+        fn parse(context: &ParserContext, input: &mut Parser) -> Result<CrossFade, ()> {
+            fn parse_percentage(input: &mut Parser) -> Result<CSSFloat, ()> {
+                input.try(|input| input.expect_percentage())
+                     .map(|percentage| percentage.max(0.).min(1.))
+            }
+
+            // The percentage can come either before or after the image.
+            let mut percentage = parse_percentage(input).ok();
+            let image = try!(Image::parse(context, input));
+            if percentage.is_none() {
+                percentage = parse_percentage(input).ok();
+            }
+            try!(input.expect_comma());
+            let final_image = try!(Image::parse(context, input));
+            Ok(CrossFade {
+                percentage: percentage,
+                image: image,
+                final_image: final_image,
+            })
+        }
+    }
+
     /// Specified values for a CSS linear gradient.
     #[derive(Clone, PartialEq, Debug, HeapSizeOf)]
     pub struct LinearGradient {
@@ -1557,6 +1663,7 @@ pub mod computed {
         type ConcreteComputedValues: ComputedValues;
         fn is_root_element(&self) -> bool;
         fn viewport_size(&self) -> Size2D<Au>;
+        fn text_zoom(&self) -> f32;
         fn inherited_style(&self) -> &Self::ConcreteComputedValues;
         fn style(&self) -> &Self::ConcreteComputedValues;
         fn mutate_style(&mut self) -> &mut Self::ConcreteComputedValues;
@@ -1565,10 +1672,13 @@ pub mod computed {
     pub struct Context<'a, C: ComputedValues> {
         pub is_root_element: bool,
         pub viewport_size: Size2D<Au>,
+        /// The factor that font sizes which are not relative to that of the parent are scaled by,
+        /// which zooms text but leaves images and other lengths be.
+        pub text_zoom: f32,
         pub inherited_style: &'a C,
 
         /// Values access through this need to be in the properties "computed early":
-        /// color, text-decoration, font-size, display, position, float, border-*-style, outline-style
+        /// color, font-size, display, position, float, border-*-style, outline-style
         pub style: C,
     }
 
@@ -1576,6 +1686,7 @@ pub mod computed {
         type ConcreteComputedValues = C;
         fn is_root_element(&self) -> bool { self.is_root_element }
         fn viewport_size(&self) -> Size2D<Au> { self.viewport_size }
+        fn text_zoom(&self) -> f32 { self.text_zoom }
         fn inherited_style(&self) -> &C { &self.inherited_style }
         fn style(&self) -> &C { &self.style }
         fn mutate_style(&mut self) -> &mut C { &mut self.style }
@@ -1733,7 +1844,7 @@ pub mod computed {
     }
 
 
-    #[derive(PartialEq, Clone, Copy, HeapSizeOf)]
+    #[derive(PartialEq, Clone, Copy, Debug, HeapSizeOf)]
     pub struct BorderRadiusSize(pub Size2D<LengthOrPercentage>);
 
     impl BorderRadiusSize {
@@ -2055,6 +2166,21 @@ pub mod computed {
                 specified::Image::LinearGradient(ref linear_gradient) => {
                     Image::LinearGradient(linear_gradient.to_computed_value(context))
                 }
+                specified::Image::ImageSet(ref options) => {
+                    Image::ImageSet(options.iter().map(|option| {
+                        ImageSetOption {
+                            image: option.image.to_computed_value(context),
+                            resolution: option.resolution,
+                        }
+                    }).collect())
+                }
+                specified::Image::CrossFade(ref cross_fade) => {
+                    Image::CrossFade(Box::new(CrossFade {
+                        percentage: cross_fade.percentage.unwrap_or(0.5),
+                        image: cross_fade.image.to_computed_value(context),
+                        final_image: cross_fade.final_image.to_computed_value(context),
+                    }))
+                }
             }
         }
     }
@@ -2065,6 +2191,10 @@ pub mod computed {
     pub enum Image {
         Url(Url),
         LinearGradient(LinearGradient),
+        /// The candidates of an `image-set()`, which layout picks from for the resolution of the
+        /// device.
+        ImageSet(Vec<ImageSetOption>),
+        CrossFade(Box<CrossFade>),
     }
 
     impl fmt::Debug for Image {
@@ -2072,10 +2202,71 @@ pub mod computed {
             match *self {
                 Image::Url(ref url) => write!(f, "url(\"{}\")", url),
                 Image::LinearGradient(ref grad) => write!(f, "linear-gradient({:?})", grad),
+                Image::ImageSet(ref options) => {
+                    try!(write!(f, "image-set("));
+                    for (i, option) in options.iter().enumerate() {
+                        if i > 0 {
+                            try!(write!(f, ", "));
+                        }
+                        try!(write!(f, "{:?} {}x", option.image, option.resolution));
+                    }
+                    write!(f, ")")
+                }
+                Image::CrossFade(ref cross_fade) => {
+                    write!(f, "cross-fade({}% {:?}, {:?})",
+                           cross_fade.percentage * 100., cross_fade.image, cross_fade.final_image)
+                }
+            }
+        }
+    }
+
+    impl ::cssparser::ToCss for Image {
+        fn to_css<W>(&self, dest: &mut W) -> fmt::Result where W: fmt::Write {
+            use values::LocalToCss;
+            match *self {
+                Image::Url(ref url) => url.to_css(dest),
+                Image::LinearGradient(ref gradient) => gradient.to_css(dest),
+                Image::ImageSet(ref options) => {
+                    try!(dest.write_str("image-set("));
+                    for (i, option) in options.iter().enumerate() {
+                        if i > 0 {
+                            try!(dest.write_str(", "));
+                        }
+                        try!(option.image.to_css(dest));
+                        try!(write!(dest, " {}x", option.resolution));
+                    }
+                    dest.write_str(")")
+                }
+                Image::CrossFade(ref cross_fade) => {
+                    try!(write!(dest, "cross-fade({}% ", cross_fade.percentage * 100.));
+                    try!(cross_fade.image.to_css(dest));
+                    try!(dest.write_str(", "));
+                    try!(cross_fade.final_image.to_css(dest));
+                    dest.write_str(")")
+                }
             }
         }
     }
 
+    /// Computed values for one of the candidates of an `image-set()`.
+    #[derive(Clone, PartialEq, Debug, HeapSizeOf)]
+    pub struct ImageSetOption {
+        pub image: Image,
+        /// The resolution of the image, in dots per `px`.
+        pub resolution: CSSFloat,
+    }
+
+    /// Computed values for a `cross-fade()` of two images.
+    #[derive(Clone, PartialEq, Debug, HeapSizeOf)]
+    pub struct CrossFade {
+        /// How much of `image` is in the result, between 0 and 1.
+        pub percentage: CSSFloat,
+        /// The image faded in.
+        pub image: Image,
+        /// The image faded out.
+        pub final_image: Image,
+    }
+
     /// Computed values for a CSS linear gradient.
     #[derive(Clone, PartialEq, HeapSizeOf)]
     pub struct LinearGradient {
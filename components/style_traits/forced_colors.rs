@@ -0,0 +1,42 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The palettes of forced colors modes, such as high contrast modes, which replace the colors
+//! that authors choose by a few system colors.
+//!
+//! https://drafts.csswg.org/css-color-adjust/#forced-colors-mode
+
+use cssparser::RGBA;
+
+/// The system colors that forced colors modes style documents with.
+#[derive(Clone, Copy, Debug, Deserialize, HeapSizeOf, PartialEq, Serialize)]
+pub struct ForcedColors {
+    /// The background of the document, which shows through the transparent backgrounds of all
+    /// other elements.
+    pub canvas: RGBA,
+    /// The color of text, and of borders and decorations, outside of links.
+    pub canvas_text: RGBA,
+    /// The color of the text of links, and of everything inside them.
+    pub link_text: RGBA,
+}
+
+impl Default for ForcedColors {
+    /// A high contrast palette of white text and yellow links on black.
+    fn default() -> ForcedColors {
+        ForcedColors {
+            canvas: RGBA { red: 0., green: 0., blue: 0., alpha: 1. },
+            canvas_text: RGBA { red: 1., green: 1., blue: 1., alpha: 1. },
+            link_text: RGBA { red: 1., green: 1., blue: 0., alpha: 1. },
+        }
+    }
+}
+
+/// What the colors of an element stand for, which forced colors modes keep telling apart.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ColorRole {
+    /// An element whose colors follow those of its parent, or those of text for the root.
+    Text,
+    /// A link.
+    Link,
+}
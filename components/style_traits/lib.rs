@@ -24,6 +24,7 @@ extern crate serde;
 extern crate util;
 
 pub mod cursor;
+pub mod forced_colors;
 #[macro_use]
 pub mod values;
 pub mod viewport;
@@ -81,6 +81,34 @@ pub fn au_rect_to_f32_rect(rect: Rect<Au>) -> Rect<f32> {
               Size2D::new(rect.size.width.to_f32_px(), rect.size.height.to_f32_px()))
 }
 
+/// Whether `point` is inside `rect` with rounded corners, whose elliptical radii are given for
+/// the top left, top right, bottom right and bottom left corners, in that order.
+pub fn rounded_rect_contains_point(rect: &Rect<Au>, radii: &[Size2D<Au>; 4], point: &Point2D<Au>)
+                                   -> bool {
+    if !rect.contains(point) {
+        return false
+    }
+    // How far the point is past the center of the ellipse of each corner, toward the corner.
+    let corners = [
+        (radii[0], rect.origin.x + radii[0].width - point.x,
+         rect.origin.y + radii[0].height - point.y),
+        (radii[1], point.x - (rect.max_x() - radii[1].width),
+         rect.origin.y + radii[1].height - point.y),
+        (radii[2], point.x - (rect.max_x() - radii[2].width),
+         point.y - (rect.max_y() - radii[2].height)),
+        (radii[3], rect.origin.x + radii[3].width - point.x,
+         point.y - (rect.max_y() - radii[3].height)),
+    ];
+    corners.iter().all(|&(radius, dx, dy)| {
+        if dx <= Au(0) || dy <= Au(0) {
+            return true
+        }
+        let x = dx.to_f32_px() / radius.width.to_f32_px();
+        let y = dy.to_f32_px() / radius.height.to_f32_px();
+        x * x + y * y <= 1.0
+    })
+}
+
 pub trait ExpandToPixelBoundaries {
     fn expand_to_px_boundaries(&self) -> Self;
 }
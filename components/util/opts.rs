@@ -80,12 +80,24 @@ pub struct Opts {
     /// Log GC passes and their durations.
     pub gc_profile: bool,
 
+    /// Check that the DOM objects in use have not been freed by the GC, and report the DOM
+    /// objects that outlive their pipeline. Only has an effect in debug builds.
+    pub audit_dom_tracing: bool,
+
     /// Load web fonts synchronously to avoid non-deterministic network-driven reflows.
     pub load_webfonts_synchronously: bool,
 
     pub headless: bool,
     pub hard_fail: bool,
 
+    /// True to exit when a page reports an error to the embedder, such as an uncaught
+    /// exception, so that headless runs fail on page errors.
+    pub fail_on_page_error: bool,
+
+    /// True to lay pages out for print (`--paginate`): style is matched against the `print`
+    /// media type, and the document is fragmented into the page boxes of its `@page` rules.
+    pub paginate: bool,
+
     /// True if we should bubble intrinsic widths sequentially (`-b`). If this is true, then
     /// intrinsic widths are computed as a separate pass instead of during flow construction. You
     /// may wish to turn this flag on in order to benchmark style recalculation against other
@@ -130,6 +142,9 @@ pub struct Opts {
     /// Enable all heartbeats for profiling.
     pub profile_heartbeats: bool,
 
+    /// Report the time that the cascade spends on each CSS property to the time profiler.
+    pub profile_cascade: bool,
+
     /// `None` to disable devtools or `Some` with a port number to start a server to listen to
     /// remote Firefox devtools connections.
     pub devtools_port: Option<u16>,
@@ -161,6 +176,9 @@ pub struct Opts {
     /// Dumps the flow tree after a layout.
     pub dump_flow_tree: bool,
 
+    /// Dumps the fragment tree in JSON form after a layout.
+    pub dump_fragment_tree_json: bool,
+
     /// Dumps the display list after a layout.
     pub dump_display_list: bool,
 
@@ -173,6 +191,9 @@ pub struct Opts {
     /// Emits notifications when there is a relayout.
     pub relayout_event: bool,
 
+    /// Logs each reflow as a line of JSON, with what triggered it and how long it took.
+    pub log_reflows: bool,
+
     /// Whether Style Sharing Cache is used
     pub disable_share_style_cache: bool,
 
@@ -231,6 +252,9 @@ pub struct DebugOptions {
     /// Print the flow tree after each layout.
     pub dump_flow_tree: bool,
 
+    /// Print the fragment tree in JSON form after each layout.
+    pub dump_fragment_tree_json: bool,
+
     /// Print the display list after each layout.
     pub dump_display_list: bool,
 
@@ -243,12 +267,18 @@ pub struct DebugOptions {
     /// Print notifications when there is a relayout.
     pub relayout_event: bool,
 
+    /// Log each reflow as a line of JSON, with what triggered it and how long it took.
+    pub log_reflows: bool,
+
     /// Profile which events script threads spend their time on.
     pub profile_script_events: bool,
 
     /// Enable all heartbeats for profiling.
     pub profile_heartbeats: bool,
 
+    /// Report the time that the cascade spends on each CSS property to the time profiler.
+    pub profile_cascade: bool,
+
     /// Paint borders along layer and tile boundaries.
     pub show_compositor_borders: bool,
 
@@ -280,6 +310,9 @@ pub struct DebugOptions {
     /// Log GC passes and their durations.
     pub gc_profile: bool,
 
+    /// Check the tracing of DOM objects, and report those that outlive their pipeline.
+    pub audit_dom_tracing: bool,
+
     /// Load web fonts synchronously to avoid non-deterministic network-driven reflows.
     pub load_webfonts_synchronously: bool,
 
@@ -309,12 +342,15 @@ impl DebugOptions {
                 "disable-text-aa" => debug_options.disable_text_aa = true,
                 "disable-canvas-aa" => debug_options.disable_text_aa = true,
                 "dump-flow-tree" => debug_options.dump_flow_tree = true,
+                "dump-fragment-tree-json" => debug_options.dump_fragment_tree_json = true,
                 "dump-display-list" => debug_options.dump_display_list = true,
                 "dump-display-list-json" => debug_options.dump_display_list_json = true,
                 "dump-layer-tree" => debug_options.dump_layer_tree = true,
                 "relayout-event" => debug_options.relayout_event = true,
+                "log-reflows" => debug_options.log_reflows = true,
                 "profile-script-events" => debug_options.profile_script_events = true,
                 "profile-heartbeats" => debug_options.profile_heartbeats = true,
+                "profile-cascade" => debug_options.profile_cascade = true,
                 "show-compositor-borders" => debug_options.show_compositor_borders = true,
                 "show-fragment-borders" => debug_options.show_fragment_borders = true,
                 "show-parallel-paint" => debug_options.show_parallel_paint = true,
@@ -325,6 +361,7 @@ impl DebugOptions {
                 "convert-mouse-to-touch" => debug_options.convert_mouse_to_touch = true,
                 "replace-surrogates" => debug_options.replace_surrogates = true,
                 "gc-profile" => debug_options.gc_profile = true,
+                "audit-dom-tracing" => debug_options.audit_dom_tracing = true,
                 "load-webfonts-synchronously" => debug_options.load_webfonts_synchronously = true,
                 "disable-vsync" => debug_options.disable_vsync = true,
                 "wr-stats" => debug_options.webrender_stats = true,
@@ -351,12 +388,18 @@ pub fn print_debug_usage(app: &str) -> ! {
     print_option("disable-text-aa", "Disable antialiasing of rendered text.");
     print_option("disable-canvas-aa", "Disable antialiasing on the HTML canvas element.");
     print_option("dump-flow-tree", "Print the flow tree after each layout.");
+    print_option("dump-fragment-tree-json", "Print the fragment tree in JSON form after each layout.");
     print_option("dump-display-list", "Print the display list after each layout.");
     print_option("dump-display-list-json", "Print the display list in JSON form.");
     print_option("dump-layer-tree", "Print the layer tree whenever it changes.");
     print_option("relayout-event", "Print notifications when there is a relayout.");
+    print_option("log-reflows",
+                 "Log each reflow as a line of JSON, with what triggered it and how long it took.");
     print_option("profile-script-events", "Enable profiling of script-related events.");
     print_option("profile-heartbeats", "Enable heartbeats for all thread categories.");
+    print_option("profile-cascade",
+                 "Report the time that the cascade spends on each CSS property to the time \
+                  profiler.");
     print_option("show-compositor-borders", "Paint borders along layer and tile boundaries.");
     print_option("show-fragment-borders", "Paint borders along fragment boundaries.");
     print_option("show-parallel-paint", "Overlay tiles with colors showing which thread painted them.");
@@ -370,6 +413,9 @@ pub fn print_debug_usage(app: &str) -> ! {
     print_option("replace-surrogates", "Replace unpaires surrogates in DOM strings with U+FFFD. \
                                         See https://github.com/servo/servo/issues/6564");
     print_option("gc-profile", "Log GC passes and their durations.");
+    print_option("audit-dom-tracing",
+                 "Panic on uses of DOM objects freed by the GC, and report the DOM objects kept \
+                  alive after their pipeline exits. Debug builds only.");
     print_option("load-webfonts-synchronously",
                  "Load web fonts synchronously to avoid non-deterministic network-driven reflows");
     print_option("disable-vsync",
@@ -479,9 +525,12 @@ pub fn default_opts() -> Opts {
         output_file: None,
         replace_surrogates: false,
         gc_profile: false,
+        audit_dom_tracing: false,
         load_webfonts_synchronously: false,
         headless: true,
         hard_fail: true,
+        fail_on_page_error: false,
+        paginate: false,
         bubble_inline_sizes_separately: false,
         show_debug_borders: false,
         show_debug_fragment_borders: false,
@@ -500,12 +549,15 @@ pub fn default_opts() -> Opts {
         random_pipeline_closure_seed: None,
         sandbox: false,
         dump_flow_tree: false,
+        dump_fragment_tree_json: false,
         dump_display_list: false,
         dump_display_list_json: false,
         dump_layer_tree: false,
         relayout_event: false,
+        log_reflows: false,
         profile_script_events: false,
         profile_heartbeats: false,
+        profile_cascade: false,
         disable_share_style_cache: false,
         convert_mouse_to_touch: false,
         exit_after_load: false,
@@ -547,6 +599,8 @@ pub fn from_cmdline_args(args: &[String]) -> ArgumentParsingResult {
     opts.optflag("z", "headless", "Headless mode");
     opts.optflag("f", "hard-fail", "Exit on thread failure instead of displaying about:failure");
     opts.optflag("F", "soft-fail", "Display about:failure on thread failure instead of exiting");
+    opts.optflag("", "fail-on-page-error", "Exit when a page reports an error");
+    opts.optflag("", "paginate", "Lay pages out into page boxes, as for print");
     opts.optflagopt("", "devtools", "Start remote devtools server on port", "6000");
     opts.optflagopt("", "webdriver", "Start remote WebDriver server on port", "7000");
     opts.optopt("", "resolution", "Set window resolution.", "800x600");
@@ -782,12 +836,16 @@ pub fn from_cmdline_args(args: &[String]) -> ArgumentParsingResult {
         output_file: opt_match.opt_str("o"),
         replace_surrogates: debug_options.replace_surrogates,
         gc_profile: debug_options.gc_profile,
+        audit_dom_tracing: debug_options.audit_dom_tracing,
         load_webfonts_synchronously: debug_options.load_webfonts_synchronously,
         headless: opt_match.opt_present("z"),
         hard_fail: opt_match.opt_present("f") && !opt_match.opt_present("F"),
+        fail_on_page_error: opt_match.opt_present("fail-on-page-error"),
+        paginate: opt_match.opt_present("paginate"),
         bubble_inline_sizes_separately: bubble_inline_sizes_separately,
         profile_script_events: debug_options.profile_script_events,
         profile_heartbeats: debug_options.profile_heartbeats,
+        profile_cascade: debug_options.profile_cascade,
         trace_layout: debug_options.trace_layout,
         devtools_port: devtools_port,
         webdriver_port: webdriver_port,
@@ -806,10 +864,12 @@ pub fn from_cmdline_args(args: &[String]) -> ArgumentParsingResult {
         enable_text_antialiasing: !debug_options.disable_text_aa,
         enable_canvas_antialiasing: !debug_options.disable_canvas_aa,
         dump_flow_tree: debug_options.dump_flow_tree,
+        dump_fragment_tree_json: debug_options.dump_fragment_tree_json,
         dump_display_list: debug_options.dump_display_list,
         dump_display_list_json: debug_options.dump_display_list_json,
         dump_layer_tree: debug_options.dump_layer_tree,
         relayout_event: debug_options.relayout_event,
+        log_reflows: debug_options.log_reflows,
         disable_share_style_cache: debug_options.disable_share_style_cache,
         convert_mouse_to_touch: debug_options.convert_mouse_to_touch,
         exit_after_load: opt_match.opt_present("x"),
@@ -12,7 +12,6 @@
 
 extern crate euclid;
 extern crate hyper;
-extern crate image;
 extern crate ipc_channel;
 extern crate msg;
 extern crate regex;
@@ -27,11 +26,10 @@ mod keys;
 
 use euclid::Size2D;
 use hyper::method::Method::{self, Post};
-use image::{DynamicImage, ImageFormat, RgbImage};
 use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
 use keys::keycodes_to_keys;
 use msg::constellation_msg::{FrameId, LoadData, PipelineId};
-use msg::constellation_msg::{NavigationDirection, PixelFormat, WebDriverCommandMsg};
+use msg::constellation_msg::{NavigationDirection, SnapshotFormat, WebDriverCommandMsg};
 use msg::webdriver_msg::{LoadStatus, WebDriverFrameId, WebDriverJSError, WebDriverJSResult, WebDriverScriptCommand};
 use regex::Captures;
 use rustc_serialize::base64::{CharacterSet, Config, Newline, ToBase64};
@@ -707,8 +705,9 @@ impl Handler {
 
         for _ in 0..iterations {
             let (sender, receiver) = ipc::channel().unwrap();
-            let cmd_msg = WebDriverCommandMsg::TakeScreenshot(pipeline_id, sender);
-            self.constellation_chan.send(ConstellationMsg::WebDriverCommand(cmd_msg)).unwrap();
+            let msg = ConstellationMsg::RenderToImage(pipeline_id, None, SnapshotFormat::Png,
+                                                      sender);
+            self.constellation_chan.send(msg).unwrap();
 
             if let Some(x) = receiver.recv().unwrap() {
                 img = Some(x);
@@ -724,20 +723,13 @@ impl Handler {
                                                    "Taking screenshot timed out")),
         };
 
-        // The compositor always sends RGB pixels.
-        assert!(img.format == PixelFormat::RGB8, "Unexpected screenshot pixel format");
-        let rgb = RgbImage::from_raw(img.width, img.height, img.bytes.to_vec()).unwrap();
-
-        let mut png_data = Vec::new();
-        DynamicImage::ImageRgb8(rgb).save(&mut png_data, ImageFormat::PNG).unwrap();
-
         let config = Config {
             char_set: CharacterSet::Standard,
             newline: Newline::LF,
             pad: true,
             line_length: None
         };
-        let encoded = png_data.to_base64(config);
+        let encoded = img.bytes.to_base64(config);
         Ok(WebDriverResponse::Generic(ValueResponse::new(encoded.to_json())))
     }
 
@@ -15,24 +15,29 @@ use interfaces::CefApp;
 use interfaces::CefBrowser;
 use render_handler::CefRenderHandlerExtensions;
 use rustc_unicode::str::Utf16Encoder;
+use string::{cef_string_utf16_clear, cef_string_utf16_set, empty_utf16_string};
 use types::{cef_cursor_handle_t, cef_cursor_type_t, cef_rect_t};
 use wrappers::CefWrap;
 
 use compositing::compositor_thread::{self, CompositorProxy, CompositorReceiver};
-use compositing::windowing::{WindowEvent, WindowMethods};
+use compositing::windowing::{DeviceChooserResponder, WindowEvent, WindowMethods};
 use euclid::point::Point2D;
+use euclid::rect::TypedRect;
 use euclid::scale_factor::ScaleFactor;
 use euclid::size::{Size2D, TypedSize2D};
 use gleam::gl;
 use layers::geometry::DevicePixel;
 use layers::platform::surface::NativeDisplay;
-use msg::constellation_msg::{Key, KeyModifiers};
+use msg::constellation_msg::{CustomCursor, Key, KeyModifiers, PipelineId};
+use net_traits::{DeviceChoice, DeviceKind, UrlClassifier};
 use net_traits::net_error_list::NetError;
+use script_traits::PageError;
 use std::cell::RefCell;
 use std::ffi::CString;
-use std::os::raw::{c_char, c_void};
+use std::os::raw::{c_char, c_int, c_void};
 use std::ptr;
 use std::rc::Rc;
+use std::sync::Arc;
 use std::sync::mpsc::{Sender, channel};
 use std_url::Url;
 use style_traits::cursor::Cursor;
@@ -168,6 +173,26 @@ impl Window {
     fn cursor_handle_for_cursor(&self, _: Cursor) -> cef_cursor_handle_t {
         0
     }
+
+    /// Hands a tooltip to the display handler; an empty text hides the tooltip.
+    fn set_tooltip(&self, text: &str) {
+        let browser = self.cef_browser.borrow();
+        let browser = match *browser {
+            None => return,
+            Some(ref browser) => browser,
+        };
+        if check_ptr_exist!(browser.get_host().get_client(), get_display_handler) &&
+           check_ptr_exist!(browser.get_host().get_client().get_display_handler(), on_tooltip) {
+            let utf16_chars: Vec<u16> = Utf16Encoder::new(text.chars()).collect();
+            let mut cef_text = empty_utf16_string();
+            cef_string_utf16_set(utf16_chars.as_ptr(), utf16_chars.len(), &mut cef_text, 1);
+            browser.get_host()
+                   .get_client()
+                   .get_display_handler()
+                   .on_tooltip((*browser).clone(), &mut cef_text);
+            cef_string_utf16_clear(&mut cef_text);
+        }
+    }
 }
 
 impl WindowMethods for Window {
@@ -355,6 +380,40 @@ impl WindowMethods for Window {
         }
     }
 
+    fn show_tooltip(&self, text: String, _: TypedRect<DevicePixel, f32>) {
+        self.set_tooltip(&text);
+    }
+
+    fn hide_tooltip(&self) {
+        self.set_tooltip("");
+    }
+
+    fn page_error(&self, _: PipelineId, error: PageError) {
+        let browser = self.cef_browser.borrow();
+        let browser = match *browser {
+            None => return,
+            Some(ref browser) => browser,
+        };
+        let (message, source, line) = match error {
+            PageError::UncaughtException { message, filename, line, .. } => {
+                (message, filename, line)
+            }
+            PageError::CspViolation { directive, blocked_url } => {
+                (format!("Refused to load {} because it violates the {} directive",
+                         blocked_url, directive), String::new(), 0)
+            }
+            PageError::ConsoleError(message) => (message, String::new(), 0),
+        };
+        let message: Vec<u16> = Utf16Encoder::new(message.chars()).collect();
+        let source: Vec<u16> = Utf16Encoder::new(source.chars()).collect();
+        if check_ptr_exist!(browser.get_host().get_client(), get_display_handler) &&
+           check_ptr_exist!(browser.get_host().get_client().get_display_handler(),
+                            on_console_message) {
+            browser.get_host().get_client().get_display_handler().on_console_message(
+                (*browser).clone(), &message, &source, line as c_int);
+        }
+    }
+
     fn load_start(&self, back: bool, forward: bool) {
         let browser = self.cef_browser.borrow();
         let browser = match *browser {
@@ -503,9 +562,53 @@ impl WindowMethods for Window {
         }
     }
 
+    fn set_custom_cursor(&self, cursor: &CustomCursor) -> bool {
+        use types::{CefCursorInfo,cef_point_t,cef_size_t};
+        let browser = self.cef_browser.borrow();
+        match *browser {
+            None => false,
+            Some(ref browser) => {
+                if !check_ptr_exist!(browser.get_host().get_client(), get_render_handler) ||
+                   !check_ptr_exist!(browser.get_host().get_client().get_render_handler(),
+                                     on_cursor_change) {
+                    return false
+                }
+                // Decoded images are BGRA, as custom cursors should be.
+                let image = &cursor.image;
+                let info = CefCursorInfo {
+                    hotspot: cef_point_t { x: cursor.hotspot.x as i32, y: cursor.hotspot.y as i32 },
+                    image_scale_factor: 1.0,
+                    buffer: image.bytes.as_ptr() as *mut isize,
+                    size: cef_size_t { width: image.width as i32, height: image.height as i32 },
+                };
+                browser.get_host()
+                       .get_client()
+                       .get_render_handler()
+                       .on_cursor_change(browser.clone(), 0 as cef_cursor_handle_t,
+                                         cef_cursor_type_t::CT_CUSTOM, &info);
+                true
+            }
+        }
+    }
+
     fn supports_clipboard(&self) -> bool {
         true
     }
+
+    fn url_classifier(&self) -> Option<Arc<UrlClassifier>> {
+        None
+    }
+
+    fn set_blocked_request_count(&self, _count: usize) {
+    }
+
+    fn choose_device(&self,
+                     _: DeviceKind,
+                     devices: Vec<DeviceChoice>,
+                     responder: DeviceChooserResponder) {
+        // CEF has no handler to choose devices with, so the first device is chosen.
+        responder.respond(devices.into_iter().next().map(|device| device.id));
+    }
 }
 
 struct CefCompositorProxy {
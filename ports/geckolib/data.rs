@@ -11,7 +11,7 @@ use std::cmp;
 use std::collections::HashMap;
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::{Arc, RwLock};
-use style::animation::Animation;
+use style::animation::{Animation, AnimationTarget, KeyframesAnimation};
 use style::dom::OpaqueNode;
 use style::media_queries::{Device, MediaType};
 use style::parallel::WorkQueueData;
@@ -32,8 +32,9 @@ pub struct PerDocumentStyleData {
     // FIXME(bholley): Hook these up to something.
     pub new_animations_sender: Sender<Animation>,
     pub new_animations_receiver: Receiver<Animation>,
-    pub running_animations: Arc<RwLock<HashMap<OpaqueNode, Vec<Animation>>>>,
-    pub expired_animations: Arc<RwLock<HashMap<OpaqueNode, Vec<Animation>>>>,
+    pub running_animations: Arc<RwLock<HashMap<AnimationTarget, Vec<Animation>>>>,
+    pub expired_animations: Arc<RwLock<HashMap<AnimationTarget, Vec<Animation>>>>,
+    pub keyframes_animations: Arc<RwLock<HashMap<OpaqueNode, Vec<KeyframesAnimation>>>>,
 
     // FIXME(bholley): This shouldn't be per-document.
     pub work_queue: WorkQueue<SharedStyleContext, WorkQueueData>,
@@ -56,6 +57,7 @@ impl PerDocumentStyleData {
             new_animations_receiver: new_anims_receiver,
             running_animations: Arc::new(RwLock::new(HashMap::new())),
             expired_animations: Arc::new(RwLock::new(HashMap::new())),
+            keyframes_animations: Arc::new(RwLock::new(HashMap::new())),
             work_queue: WorkQueue::new("StyleWorker", thread_state::LAYOUT, num_threads),
         }
     }
@@ -100,6 +100,10 @@ fn restyle_subtree(node: GeckoNode, raw_data: *mut RawServoStyleSet) {
         new_animations_sender: Mutex::new(data.new_animations_sender.clone()),
         running_animations: data.running_animations.clone(),
         expired_animations: data.expired_animations.clone(),
+        keyframes_animations: data.keyframes_animations.clone(),
+        // Gecko runs its own animations, so none are sampled here.
+        animation_time: 0.0,
+        record_phase_times: false,
         error_reporter: Box::new(StdoutErrorReporter),
     };
 
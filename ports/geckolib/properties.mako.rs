@@ -734,6 +734,15 @@ fn static_assert() {
     <% impl_color("text_decoration_color", "mTextDecorationColor",
                   color_flags_ffi_name="mTextDecorationStyle") %>
 
+    fn clone_text_decoration_color(&self) -> longhands::text_decoration_color::computed_value::T {
+        use cssparser::Color;
+        if ${get_current_color_flag_from("self.gecko.mTextDecorationStyle")} {
+            Color::CurrentColor
+        } else {
+            Color::RGBA(convert_nscolor_to_rgba(self.gecko.mTextDecorationColor))
+        }
+    }
+
     fn set_text_decoration_line(&mut self, v: longhands::text_decoration_line::computed_value::T) {
         let mut bits: u8 = 0;
         if v.underline {
@@ -21,6 +21,7 @@ extern crate script_traits;
 extern crate style_traits;
 extern crate url;
 extern crate util;
+#[cfg(target_os = "linux")] extern crate tinyfiledialogs;
 #[cfg(target_os = "linux")] extern crate x11;
 #[cfg(target_os = "windows")] extern crate winapi;
 #[cfg(target_os = "windows")] extern crate user32;
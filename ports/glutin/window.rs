@@ -6,8 +6,9 @@
 
 use NestedEventLoopListener;
 use compositing::compositor_thread::{self, CompositorProxy, CompositorReceiver};
-use compositing::windowing::{MouseWindowEvent, WindowNavigateMsg};
+use compositing::windowing::{DeviceChooserResponder, MouseWindowEvent, WindowNavigateMsg};
 use compositing::windowing::{WindowEvent, WindowMethods};
+use euclid::rect::TypedRect;
 use euclid::scale_factor::ScaleFactor;
 use euclid::size::TypedSize2D;
 use euclid::{Size2D, Point2D};
@@ -21,13 +22,15 @@ use glutin::{Api, ElementState, Event, GlRequest, MouseButton, VirtualKeyCode, M
 use layers::geometry::DevicePixel;
 use layers::platform::surface::NativeDisplay;
 use msg::constellation_msg::{KeyState, NONE, CONTROL, SHIFT, ALT, SUPER};
-use msg::constellation_msg::{self, Key};
+use msg::constellation_msg::{self, CustomCursor, Key, PipelineId};
+use net_traits::{DeviceChoice, DeviceKind, UrlClassifier};
 use net_traits::net_error_list::NetError;
-use script_traits::{TouchEventType, TouchpadPressurePhase};
+use script_traits::{PageError, TouchEventType, TouchpadPressurePhase};
 use std::cell::{Cell, RefCell};
 #[cfg(not(target_os = "android"))]
 use std::os::raw::c_void;
 use std::rc::Rc;
+use std::sync::Arc;
 use std::sync::mpsc::{channel, Sender};
 use style_traits::cursor::Cursor;
 use url::Url;
@@ -72,6 +75,13 @@ const LINE_HEIGHT: f32 = 38.0;
 
 const MULTISAMPLES: u16 = 16;
 
+#[cfg(target_os = "linux")]
+const DEVICE_DIALOG_TITLE: &'static str = "Choose a device";
+#[cfg(target_os = "linux")]
+const DEVICE_DIALOG_COLUMN_ID: &'static str = "Id";
+#[cfg(target_os = "linux")]
+const DEVICE_DIALOG_COLUMN_NAME: &'static str = "Name";
+
 #[cfg(target_os = "macos")]
 fn builder_with_platform_options(mut builder: glutin::WindowBuilder) -> glutin::WindowBuilder {
     if opts::get().headless || opts::get().output_file.is_some() {
@@ -697,6 +707,15 @@ impl WindowMethods for Window {
     fn status(&self, _: Option<String>) {
     }
 
+    fn show_tooltip(&self, _: String, _: TypedRect<DevicePixel, f32>) {
+    }
+
+    fn hide_tooltip(&self) {
+    }
+
+    fn page_error(&self, _: PipelineId, _: PageError) {
+    }
+
     fn load_start(&self, _: bool, _: bool) {
     }
 
@@ -756,6 +775,11 @@ impl WindowMethods for Window {
         self.window.set_cursor(glutin_cursor);
     }
 
+    /// Glutin cannot show cursor images.
+    fn set_custom_cursor(&self, _: &CustomCursor) -> bool {
+        false
+    }
+
     fn set_favicon(&self, _: Url) {
     }
 
@@ -864,6 +888,39 @@ impl WindowMethods for Window {
     fn supports_clipboard(&self) -> bool {
         true
     }
+
+    fn url_classifier(&self) -> Option<Arc<UrlClassifier>> {
+        None
+    }
+
+    fn set_blocked_request_count(&self, count: usize) {
+        debug!("{} requests blocked by tracking protection", count);
+    }
+
+    #[cfg(target_os = "linux")]
+    fn choose_device(&self,
+                     _: DeviceKind,
+                     devices: Vec<DeviceChoice>,
+                     responder: DeviceChooserResponder) {
+        let dialog_rows: Vec<&str> = devices.iter().flat_map(|device| {
+            vec![&*device.id, &*device.name]
+        }).collect();
+        let chosen = tinyfiledialogs::list_dialog(DEVICE_DIALOG_TITLE,
+                                                  &[DEVICE_DIALOG_COLUMN_ID,
+                                                    DEVICE_DIALOG_COLUMN_NAME],
+                                                  Some(&dialog_rows[..]));
+        // The row is given as "Id|Name".
+        responder.respond(chosen.and_then(|row| row.split("|").next().map(|id| id.to_owned())));
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn choose_device(&self,
+                     _: DeviceKind,
+                     devices: Vec<DeviceChoice>,
+                     responder: DeviceChooserResponder) {
+        // There is no chooser yet, so the first device is chosen.
+        responder.respond(devices.into_iter().next().map(|device| device.id));
+    }
 }
 
 struct GlutinCompositorProxy {
@@ -5,7 +5,7 @@
 use gfx::font_cache_thread::FontCacheThread;
 use ipc_channel::ipc;
 use style::computed_values::font_family::FontFamily;
-use style::font_face::Source;
+use style::font_face::{Source, UnicodeRange};
 
 #[test]
 fn test_local_web_font() {
@@ -15,7 +15,10 @@ fn test_local_web_font() {
   let family_name = FontFamily::FamilyName(From::from("test family"));
   let variant_name = FontFamily::FamilyName(From::from("test font face"));
 
-  font_cache_thread.add_web_font(family_name, Source::Local(variant_name), out_chan);
+  font_cache_thread.add_web_font(family_name,
+                                 Source::Local(variant_name),
+                                 vec![UnicodeRange::all()],
+                                 out_chan);
 
   assert_eq!(out_receiver.recv().unwrap(), ());
 }
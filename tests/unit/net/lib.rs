@@ -27,7 +27,10 @@ extern crate util;
 #[cfg(test)] mod file_loader;
 #[cfg(test)] mod fetch;
 #[cfg(test)] mod mime_classifier;
+#[cfg(test)] mod reporting;
 #[cfg(test)] mod resource_thread;
 #[cfg(test)] mod hsts;
 #[cfg(test)] mod http_loader;
 #[cfg(test)] mod filemanager_thread;
+#[cfg(test)] mod tracking_protection;
+#[cfg(test)] mod url_classifier;
@@ -0,0 +1,45 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use msg::constellation_msg::PipelineId;
+use net::reporting::{ReportingManager, parse_report_to};
+use net_traits::reporting::{ReportType, ReportingHeaders};
+use url::Url;
+
+#[test]
+fn test_parse_report_to_groups() {
+    let base_url = Url::parse("https://example.com/page").unwrap();
+    let value = "{\"group\": \"csp\", \"endpoints\": [{\"url\": \"/csp-reports\"}]}, \
+                 {\"endpoints\": [{\"url\": \"https://reports.example/\"}]}";
+    let groups = parse_report_to(value, &base_url);
+
+    assert_eq!(groups, vec![
+        ("csp".to_owned(), vec![Url::parse("https://example.com/csp-reports").unwrap()]),
+        ("default".to_owned(), vec![Url::parse("https://reports.example/").unwrap()]),
+    ]);
+    assert!(parse_report_to("not json", &base_url).is_empty());
+}
+
+#[test]
+fn test_reporting_endpoints_for_report_types() {
+    let pipeline_id = PipelineId::fake_root_pipeline_id();
+    let mut reporting = ReportingManager::new("".to_owned());
+    reporting.set_endpoints(pipeline_id,
+                            Url::parse("https://example.com/").unwrap(),
+                            ReportingHeaders {
+                                report_to: vec!["{\"group\": \"csp\", \"endpoints\": \
+                                                 [{\"url\": \"/to\"}]}".to_owned()],
+                                content_security_policy: vec!["script-src 'self'; \
+                                                               report-uri /uri; \
+                                                               report-to csp".to_owned()],
+                            });
+
+    assert_eq!(reporting.endpoints_for(pipeline_id, ReportType::CspViolation),
+               vec![Url::parse("https://example.com/to").unwrap(),
+                    Url::parse("https://example.com/uri").unwrap()]);
+    assert!(reporting.endpoints_for(pipeline_id, ReportType::Deprecation).is_empty());
+
+    reporting.clear_endpoints(pipeline_id);
+    assert!(reporting.endpoints_for(pipeline_id, ReportType::CspViolation).is_empty());
+}
@@ -5,13 +5,15 @@
 use ipc_channel::ipc;
 use msg::constellation_msg::{PipelineId, ReferrerPolicy};
 use net::resource_thread::new_core_resource_thread;
+use net::url_classifier::LocalListClassifier;
 use net_traits::hosts::{parse_hostsfile, host_replacement};
 use net_traits::{CoreResourceMsg, LoadData, LoadConsumer, LoadContext};
-use net_traits::{NetworkError, ProgressMsg, LoadOrigin, RequestSource};
+use net_traits::{NetworkError, ProgressMsg, LoadOrigin, RequestSource, UrlClassifier, UrlThreat};
 use profile_traits::time::ProfilerChan;
 use std::borrow::ToOwned;
 use std::collections::HashMap;
 use std::net::IpAddr;
+use std::sync::Arc;
 use std::sync::mpsc::channel;
 use url::Url;
 
@@ -39,14 +41,22 @@ impl LoadOrigin for ResourceTest {
 #[test]
 fn test_exit() {
     let (tx, _rx) = ipc::channel().unwrap();
-    let resource_thread = new_core_resource_thread("".to_owned(), None, ProfilerChan(tx));
+    let resource_thread = new_core_resource_thread("".to_owned(),
+                                                   None,
+                                                   ProfilerChan(tx),
+                                                   None,
+                                                   None);
     resource_thread.send(CoreResourceMsg::Exit).unwrap();
 }
 
 #[test]
 fn test_bad_scheme() {
     let (tx, _rx) = ipc::channel().unwrap();
-    let resource_thread = new_core_resource_thread("".to_owned(), None, ProfilerChan(tx));
+    let resource_thread = new_core_resource_thread("".to_owned(),
+                                                   None,
+                                                   ProfilerChan(tx),
+                                                   None,
+                                                   None);
     let (start_chan, start) = ipc::channel().unwrap();
     let url = Url::parse("bogus://whatever").unwrap();
     resource_thread.send(CoreResourceMsg::Load(LoadData::new(LoadContext::Browsing, url, &ResourceTest),
@@ -223,7 +233,11 @@ fn test_cancelled_listener() {
     });
 
     let (tx, _rx) = ipc::channel().unwrap();
-    let resource_thread = new_core_resource_thread("".to_owned(), None, ProfilerChan(tx));
+    let resource_thread = new_core_resource_thread("".to_owned(),
+                                                   None,
+                                                   ProfilerChan(tx),
+                                                   None,
+                                                   None);
     let (sender, receiver) = ipc::channel().unwrap();
     let (id_sender, id_receiver) = ipc::channel().unwrap();
     let (sync_sender, sync_receiver) = ipc::channel().unwrap();
@@ -246,3 +260,25 @@ fn test_cancelled_listener() {
                ProgressMsg::Done(Err(NetworkError::LoadCancelled)));
     resource_thread.send(CoreResourceMsg::Exit).unwrap();
 }
+
+#[test]
+fn test_load_of_flagged_url_is_blocked() {
+    let list = b"{\"phishing\": [\"phish.example\"]}";
+    let classifier: Arc<UrlClassifier> = Arc::new(LocalListClassifier::from_list(list).unwrap());
+    let (tx, _rx) = ipc::channel().unwrap();
+    let resource_thread = new_core_resource_thread("".to_owned(),
+                                                   None,
+                                                   ProfilerChan(tx),
+                                                   Some(classifier),
+                                                   None);
+    let (start_chan, start) = ipc::channel().unwrap();
+    let url = Url::parse("http://www.phish.example/login").unwrap();
+    let load_data = LoadData::new(LoadContext::Browsing, url.clone(), &ResourceTest);
+    resource_thread.send(CoreResourceMsg::Load(load_data,
+                                               LoadConsumer::Channel(start_chan),
+                                               None)).unwrap();
+    let response = start.recv().unwrap();
+    assert_eq!(response.progress_port.recv().unwrap(),
+               ProgressMsg::Done(Err(NetworkError::UnsafeUrl(url, UrlThreat::Phishing))));
+    resource_thread.send(CoreResourceMsg::Exit).unwrap();
+}
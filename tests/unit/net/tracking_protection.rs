@@ -0,0 +1,61 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use msg::constellation_msg::{PipelineId, ReferrerPolicy};
+use net::tracking_protection::TrackingProtection;
+use net_traits::{LoadContext, LoadData, LoadOrigin, RequestSource};
+use url::Url;
+
+struct PageOrigin(Url);
+
+impl LoadOrigin for PageOrigin {
+    fn referrer_url(&self) -> Option<Url> {
+        Some(self.0.clone())
+    }
+    fn referrer_policy(&self) -> Option<ReferrerPolicy> {
+        None
+    }
+    fn request_source(&self) -> RequestSource {
+        RequestSource::None
+    }
+    fn pipeline_id(&self) -> Option<PipelineId> {
+        None
+    }
+}
+
+fn load_from(context: LoadContext, url: &str, page: &str) -> LoadData {
+    LoadData::new(context, Url::parse(url).unwrap(), &PageOrigin(Url::parse(page).unwrap()))
+}
+
+#[test]
+fn test_tracking_protection_blocks_third_party_trackers() {
+    let tracking_protection = TrackingProtection::from_list(b"[\"tracker.example\"]").unwrap();
+
+    let tracker = "http://cdn.tracker.example/pixel.js";
+    assert!(tracking_protection.should_block(&load_from(LoadContext::Script,
+                                                        tracker,
+                                                        "http://news.example/")));
+    assert!(!tracking_protection.should_block(&load_from(LoadContext::Script,
+                                                         tracker,
+                                                         "http://www.tracker.example/")));
+    assert!(!tracking_protection.should_block(&load_from(LoadContext::Browsing,
+                                                         tracker,
+                                                         "http://news.example/")));
+    assert!(!tracking_protection.should_block(&load_from(LoadContext::Image,
+                                                         "http://cdn.example/a.png",
+                                                         "http://news.example/")));
+}
+
+#[test]
+fn test_tracking_protection_site_exceptions() {
+    let mut tracking_protection = TrackingProtection::from_list(b"[\"tracker.example\"]").unwrap();
+    let load_data = load_from(LoadContext::Script,
+                              "http://tracker.example/pixel.js",
+                              "http://news.example/");
+
+    tracking_protection.set_enabled_for_site("News.example".to_owned(), false);
+    assert!(!tracking_protection.should_block(&load_data));
+    tracking_protection.set_enabled_for_site("news.example".to_owned(), true);
+    assert!(tracking_protection.should_block(&load_data));
+}
@@ -0,0 +1,23 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use net::url_classifier::LocalListClassifier;
+use net_traits::UrlThreat;
+
+#[test]
+fn test_local_list_classifier_from_list_matches_subdomains() {
+    let list = b"{\"malware\": [\"malware.example\"], \"phishing\": [\"Phish.example\"]}";
+    let classifier = LocalListClassifier::from_list(list).unwrap();
+
+    assert_eq!(classifier.threat_for_host("malware.example"), Some(UrlThreat::Malware));
+    assert_eq!(classifier.threat_for_host("www.malware.example"), Some(UrlThreat::Malware));
+    assert_eq!(classifier.threat_for_host("phish.EXAMPLE"), Some(UrlThreat::Phishing));
+    assert_eq!(classifier.threat_for_host("notmalware.example"), None);
+    assert_eq!(classifier.threat_for_host("example"), None);
+}
+
+#[test]
+fn test_local_list_classifier_from_invalid_list() {
+    assert!(LocalListClassifier::from_list(b"[\"malware.example\"]").is_none());
+}
@@ -0,0 +1,44 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use script::accessibility::{RoleAttribute, parse_positive_integer, parse_role};
+use script::accessibility::{parse_true_false, parse_tristate};
+use script_traits::{AccessibleRole, Tristate};
+
+#[test]
+fn test_parse_role() {
+    assert_eq!(parse_role("button"), Some(RoleAttribute::Role(AccessibleRole::Button)));
+    assert_eq!(parse_role("  TreeItem "), Some(RoleAttribute::Role(AccessibleRole::TreeItem)));
+    assert_eq!(parse_role("presentation"), Some(RoleAttribute::Presentation));
+    assert_eq!(parse_role("none button"), Some(RoleAttribute::Presentation));
+    assert_eq!(parse_role(""), None);
+}
+
+#[test]
+fn test_parse_role_fallback() {
+    // The first known role applies; abstract roles are not known.
+    assert_eq!(parse_role("switch checkbox"), Some(RoleAttribute::Role(AccessibleRole::Switch)));
+    assert_eq!(parse_role("widget foo checkbox"),
+               Some(RoleAttribute::Role(AccessibleRole::Checkbox)));
+    assert_eq!(parse_role("widget structure"), None);
+}
+
+#[test]
+fn test_parse_states() {
+    assert_eq!(parse_true_false("true"), Some(true));
+    assert_eq!(parse_true_false(" FALSE "), Some(false));
+    assert_eq!(parse_true_false("undefined"), None);
+    assert_eq!(parse_true_false("mixed"), None);
+    assert_eq!(parse_true_false(""), None);
+
+    assert_eq!(parse_tristate("mixed"), Some(Tristate::Mixed));
+    assert_eq!(parse_tristate("true"), Some(Tristate::True));
+    assert_eq!(parse_tristate("false"), Some(Tristate::False));
+    assert_eq!(parse_tristate("yes"), None);
+
+    assert_eq!(parse_positive_integer("3"), Some(3));
+    assert_eq!(parse_positive_integer("0"), None);
+    assert_eq!(parse_positive_integer("-1"), None);
+    assert_eq!(parse_positive_integer("two"), None);
+}
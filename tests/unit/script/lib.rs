@@ -7,8 +7,10 @@
 
 extern crate msg;
 extern crate script;
+extern crate script_traits;
 extern crate url;
 
+#[cfg(test)] mod accessibility;
 #[cfg(test)] mod origin;
 #[cfg(all(test, target_pointer_width = "64"))] mod size_of;
 #[cfg(test)] mod textinput;
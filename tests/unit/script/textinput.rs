@@ -11,9 +11,10 @@
 use msg::constellation_msg::CONTROL;
 #[cfg(target_os = "macos")]
 use msg::constellation_msg::SUPER;
-use msg::constellation_msg::{Key, KeyModifiers};
+use msg::constellation_msg::{Key, KeyModifiers, SHIFT};
 use script::clipboard_provider::DummyClipboardContext;
 use script::dom::bindings::str::DOMString;
+use script::textinput::UserSelect;
 use script::textinput::{TextInput, TextPoint, Selection, Lines, Direction, SelectionDirection};
 
 fn text_input(lines: Lines, s: &str) -> TextInput<DummyClipboardContext> {
@@ -405,6 +406,19 @@ fn test_clipboard_paste() {
     assert_eq!(textinput.get_content(), "abcdefg");
 }
 
+#[test]
+fn test_textinput_user_select() {
+    let mut textinput = text_input(Lines::Single, "abcdef");
+    textinput.user_select = UserSelect::None;
+    textinput.handle_keydown_aux(Key::Right, SHIFT);
+    assert_eq!(textinput.get_selection_text(), None);
+    assert_eq!(textinput.edit_point.index, 1);
+
+    textinput.user_select = UserSelect::All;
+    textinput.handle_keydown_aux(Key::Right, SHIFT);
+    assert_eq!(textinput.get_selection_text(), Some(String::from("abcdef")));
+}
+
 #[test]
 fn test_textinput_cursor_position_correct_after_clearing_selection() {
     let mut textinput = text_input(Lines::Single, "abcdef");
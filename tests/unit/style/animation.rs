@@ -0,0 +1,133 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use app_units::Au;
+use euclid::size::Size2D;
+use std::sync::Mutex;
+use std::sync::mpsc::channel;
+use style::animation::{AnimationTarget, ReducedMotion, start_transitions_if_applicable};
+use style::dom::OpaqueNode;
+use style::error_reporting::StdoutErrorReporter;
+use style::parser::ParserContextExtraData;
+use style::properties::{ComputedValues, ServoComputedValues, cascade, parse_style_attribute};
+use style::selector_impl::PseudoElement;
+use style::selector_matching::DeclarationBlock;
+use style::values::computed::LengthOrPercentageOrAuto;
+use url::Url;
+
+fn style(declarations: &str) -> ServoComputedValues {
+    let url = Url::parse("http://localhost").unwrap();
+    let block = parse_style_attribute(declarations,
+                                      &url,
+                                      Box::new(StdoutErrorReporter),
+                                      ParserContextExtraData::default());
+    cascade(Size2D::new(Au(0), Au(0)),
+            1.0,
+            &[DeclarationBlock::from_declarations(block.normal)],
+            false,
+            None,
+            None,
+            None,
+            Box::new(StdoutErrorReporter)).0
+}
+
+fn width(style: &ServoComputedValues) -> LengthOrPercentageOrAuto {
+    style.get_position().width
+}
+
+#[test]
+fn transitions_keep_running_while_their_end_value_does_not_change() {
+    let (sender, receiver) = channel();
+    let sender = Mutex::new(sender);
+    let target = AnimationTarget::new(OpaqueNode(1), None);
+
+    let old_style = style("width: 0px; transition: width 10s linear");
+    let mut new_style = style("width: 100px; transition: width 10s linear");
+    assert!(start_transitions_if_applicable(&sender, &target, &old_style, &mut new_style, vec![]));
+    let animation = receiver.try_recv().unwrap();
+    assert_eq!(width(&new_style), LengthOrPercentageOrAuto::Length(Au(0)));
+
+    let mut before_change_style = new_style.clone();
+    animation.property_animation.update(&mut before_change_style, 0.5);
+    let mut after_change_style = style("width: 100px; color: red; transition: width 10s linear");
+    assert!(start_transitions_if_applicable(&sender,
+                                            &target,
+                                            &before_change_style,
+                                            &mut after_change_style,
+                                            vec![animation.clone()]));
+    let kept_animation = receiver.try_recv().unwrap();
+    assert_eq!(kept_animation.start_time, animation.start_time);
+    assert_eq!(kept_animation.end_time, animation.end_time);
+    assert!(receiver.try_recv().is_err());
+}
+
+#[test]
+fn reversed_transitions_are_shortened() {
+    let (sender, receiver) = channel();
+    let sender = Mutex::new(sender);
+    let target = AnimationTarget::new(OpaqueNode(1), None);
+
+    let old_style = style("width: 0px; transition: width 10s linear");
+    let mut new_style = style("width: 100px; transition: width 10s linear");
+    start_transitions_if_applicable(&sender, &target, &old_style, &mut new_style, vec![]);
+    let mut animation = receiver.try_recv().unwrap();
+
+    // Halfway through, the width transitions back to where it started from.
+    animation.start_time -= 5.0;
+    animation.end_time -= 5.0;
+    let mut before_change_style = new_style.clone();
+    animation.property_animation.update(&mut before_change_style, 0.5);
+    let mut after_change_style = style("width: 0px; transition: width 10s linear");
+    assert!(start_transitions_if_applicable(&sender,
+                                            &target,
+                                            &before_change_style,
+                                            &mut after_change_style,
+                                            vec![animation]));
+    let reversed_animation = receiver.try_recv().unwrap();
+    assert!((reversed_animation.duration() - 5.0).abs() < 0.01);
+    assert_eq!(width(&after_change_style), LengthOrPercentageOrAuto::Length(Au::from_px(50)));
+}
+
+#[test]
+fn running_transitions_are_cancelled_without_a_matching_transition() {
+    let (sender, receiver) = channel();
+    let sender = Mutex::new(sender);
+    let target = AnimationTarget::new(OpaqueNode(1), None);
+
+    let old_style = style("width: 0px; transition: width 10s linear");
+    let mut new_style = style("width: 100px; transition: width 10s linear");
+    start_transitions_if_applicable(&sender, &target, &old_style, &mut new_style, vec![]);
+    let animation = receiver.try_recv().unwrap();
+
+    let mut before_change_style = new_style.clone();
+    animation.property_animation.update(&mut before_change_style, 0.5);
+    let mut after_change_style = style("width: 100px");
+    assert!(!start_transitions_if_applicable(&sender,
+                                             &target,
+                                             &before_change_style,
+                                             &mut after_change_style,
+                                             vec![animation]));
+    assert!(receiver.try_recv().is_err());
+    assert_eq!(width(&after_change_style), LengthOrPercentageOrAuto::Length(Au::from_px(100)));
+}
+
+#[test]
+fn transitions_of_pseudo_elements_run_on_them() {
+    let (sender, receiver) = channel();
+    let sender = Mutex::new(sender);
+    let target = AnimationTarget::new(OpaqueNode(1), Some(PseudoElement::Before));
+
+    let old_style = style("width: 0px; transition: width 10s linear");
+    let mut new_style = style("width: 100px; transition: width 10s linear");
+    assert!(start_transitions_if_applicable(&sender, &target, &old_style, &mut new_style, vec![]));
+    assert_eq!(receiver.try_recv().unwrap().target, target);
+}
+
+#[test]
+fn reduced_motion_shows_final_states_or_jumps_halfway() {
+    assert_eq!(ReducedMotion::NoPreference.reduce(0.3), 0.3);
+    assert_eq!(ReducedMotion::Final.reduce(0.0), 1.0);
+    assert_eq!(ReducedMotion::Discrete.reduce(0.3), 0.0);
+    assert_eq!(ReducedMotion::Discrete.reduce(0.5), 1.0);
+}
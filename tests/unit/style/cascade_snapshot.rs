@@ -0,0 +1,33 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use app_units::Au;
+use euclid::size::Size2D;
+use style::computed_values::font_weight;
+use style::media_queries::{Device, MediaType};
+use style::properties::ComputedValues;
+use style::servo::CascadeSnapshot;
+use url::Url;
+
+fn snapshot() -> CascadeSnapshot {
+    CascadeSnapshot {
+        device: Device::new(MediaType::Screen, Size2D::typed(800., 600.)),
+        root_style: None,
+    }
+}
+
+#[test]
+fn fonts_without_a_parent_are_relative_to_the_default_canvas_font() {
+    let url = Url::parse("http://localhost").unwrap();
+    let style = snapshot().resolve_font("bold 2em serif", None, &url).unwrap();
+    assert_eq!(style.get_font().font_size, Au::from_px(20));
+    assert_eq!(style.get_font().font_weight, font_weight::T::Weight700);
+}
+
+#[test]
+fn css_wide_keywords_and_invalid_fonts_are_ignored() {
+    let url = Url::parse("http://localhost").unwrap();
+    assert!(snapshot().resolve_font("inherit", None, &url).is_none());
+    assert!(snapshot().resolve_font("bold serif", None, &url).is_none());
+}
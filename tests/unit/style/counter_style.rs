@@ -0,0 +1,65 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use media_queries::CSSErrorReporterTest;
+use string_cache::Atom;
+use style::counter_style::{CounterStyleRule, System};
+use style::parser::ParserContextExtraData;
+use style::servo::Stylesheet;
+use style::stylesheets::{CSSRuleIteratorExt, Origin, SourceRange};
+use url::Url;
+
+fn counter_style_rules(css: &str) -> Vec<CounterStyleRule> {
+    let url = Url::parse("http://localhost").unwrap();
+    let stylesheet = Stylesheet::from_str(css, url, Origin::Author, Box::new(CSSErrorReporterTest),
+                                          ParserContextExtraData::default());
+    stylesheet.rules().counter_style().cloned().collect()
+}
+
+#[test]
+fn parse_counter_style_rule() {
+    let rules = counter_style_rules("@counter-style thumbs {
+        system: cyclic;
+        symbols: '👍' thumb;
+        suffix: ' ';
+        range: 1 10, 20 infinite;
+        fallback: lower-alpha;
+    }");
+    assert_eq!(rules, vec![CounterStyleRule {
+        name: Atom::from("thumbs"),
+        system: System::Cyclic,
+        negative: None,
+        prefix: None,
+        suffix: Some(" ".to_owned()),
+        range: Some(vec![(Some(1), Some(10)), (Some(20), None)]),
+        pad: None,
+        fallback: Some(Atom::from("lower-alpha")),
+        symbols: Some(vec!["👍".to_owned(), "thumb".to_owned()]),
+        additive_symbols: None,
+        source_range: SourceRange {
+            start: 0,
+            end: 170,
+            start_line: 1,
+            start_column: 1,
+            end_line: 7,
+            end_column: 6,
+        },
+    }]);
+}
+
+#[test]
+fn invalid_counter_style_rules() {
+    // Numeric systems need two symbols at least.
+    assert!(counter_style_rules("@counter-style a { system: numeric; symbols: x; }").is_empty());
+    // Additive symbols must be in decreasing order of weight.
+    assert!(counter_style_rules("@counter-style b {
+        system: additive;
+        additive-symbols: 1 I, 5 V;
+    }").is_empty());
+    // Styles that extend others cannot have symbols of their own.
+    assert!(counter_style_rules("@counter-style c { system: extends decimal; symbols: x; }")
+                .is_empty());
+    // The predefined `decimal` style cannot be overridden.
+    assert!(counter_style_rules("@counter-style decimal { symbols: x; }").is_empty());
+}
@@ -0,0 +1,54 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use cssparser::Parser;
+use style::easing::output;
+use style::properties::longhands::transition_timing_function::computed_value::StartEnd;
+use style::properties::longhands::transition_timing_function::computed_value::TransitionTimingFunction;
+use style::properties::longhands::transition_timing_function::parse_one;
+
+fn timing_function(css: &str) -> Result<TransitionTimingFunction, ()> {
+    parse_one(&mut Parser::new(css))
+}
+
+#[test]
+fn steps_jump_at_their_end_by_default() {
+    assert_eq!(timing_function("steps(4)"),
+               Ok(TransitionTimingFunction::Steps(4, StartEnd::End)));
+    assert_eq!(timing_function("steps(4, start)"),
+               Ok(TransitionTimingFunction::Steps(4, StartEnd::Start)));
+    assert!(timing_function("steps(0)").is_err());
+    assert!(timing_function("steps(-1, end)").is_err());
+}
+
+#[test]
+fn cubic_bezier_control_points_must_be_within_the_duration() {
+    assert!(timing_function("cubic-bezier(0.1, -2, 0.9, 3)").is_ok());
+    assert!(timing_function("cubic-bezier(-0.1, 0, 0.9, 1)").is_err());
+    assert!(timing_function("cubic-bezier(0.1, 0, 1.1, 1)").is_err());
+}
+
+#[test]
+fn steps_output() {
+    let start = TransitionTimingFunction::Steps(4, StartEnd::Start);
+    let end = TransitionTimingFunction::Steps(4, StartEnd::End);
+    assert_eq!(output(start, 1.0, 0.0), 0.25);
+    assert_eq!(output(start, 1.0, 0.3), 0.5);
+    assert_eq!(output(start, 1.0, 1.0), 1.0);
+    assert_eq!(output(end, 1.0, 0.0), 0.0);
+    assert_eq!(output(end, 1.0, 0.3), 0.25);
+    assert_eq!(output(end, 1.0, 1.0), 1.0);
+}
+
+#[test]
+fn cubic_bezier_output() {
+    let linear = timing_function("linear").unwrap();
+    assert!((output(linear, 1.0, 0.25) - 0.25).abs() < 1e-3);
+    let ease_in = timing_function("ease-in").unwrap();
+    assert_eq!(output(ease_in, 1.0, 0.0), 0.0);
+    assert!((output(ease_in, 1.0, 1.0) - 1.0).abs() < 1e-3);
+    assert!(output(ease_in, 1.0, 0.5) < 0.5);
+    // Progress outside of the duration is clamped, and zero durations do not divide by zero.
+    assert_eq!(output(ease_in, 0.0, -1.0), output(ease_in, 0.0, 0.0));
+}
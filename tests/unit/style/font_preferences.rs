@@ -0,0 +1,61 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use app_units::Au;
+use euclid::size::Size2D;
+use std::sync::Arc;
+use string_cache::Atom;
+use style::error_reporting::StdoutErrorReporter;
+use style::font_preferences::{language_group, preferred_family};
+use style::properties::longhands::_servo_lang;
+use style::properties::{ComputedValues, DeclaredValue, PropertyDeclaration, ServoComputedValues};
+use style::properties::cascade;
+use style::selector_matching::DeclarationBlock;
+use util::prefs::{PrefValue, set_pref};
+
+fn style_in_language(language_tag: &str, parent: Option<&ServoComputedValues>)
+                     -> ServoComputedValues {
+    let declaration = PropertyDeclaration::ServoLang(
+        DeclaredValue::Value(_servo_lang::for_language(language_tag)));
+    cascade(Size2D::new(Au(0), Au(0)),
+            1.0,
+            &[DeclarationBlock::from_declarations(Arc::new(vec![declaration]))],
+            false,
+            parent,
+            None,
+            None,
+            Box::new(StdoutErrorReporter)).0
+}
+
+#[test]
+fn languages_are_grouped_by_primary_subtag_and_chinese_by_script() {
+    assert_eq!(language_group("ja-JP"), "ja");
+    assert_eq!(language_group("EN_gb"), "en");
+    assert_eq!(language_group("zh-Hant"), "zh-tw");
+    assert_eq!(language_group("zh-Hans-HK"), "zh-cn");
+    assert_eq!(language_group("ZH-hk"), "zh-hk");
+    assert_eq!(language_group("zh"), "zh");
+}
+
+#[test]
+fn generic_families_map_to_the_family_preferred_for_the_language_group() {
+    set_pref("font.name.fantasy.x-family", PrefValue::String("Preferred Fantasy".to_owned()));
+    assert_eq!(preferred_family("fantasy", Some("x-family-variant")),
+               Some(Atom::from("Preferred Fantasy")));
+    assert_eq!(preferred_family("cursive", Some("x-family")), None);
+}
+
+#[test]
+fn the_root_takes_the_font_size_preferred_for_its_language() {
+    set_pref("font.size.x-size", PrefValue::Number(20.));
+    let root = style_in_language("x-size", None);
+    assert_eq!(root.get_font().font_size, Au::from_px(20));
+    assert_eq!(root.root_font_size(), Au::from_px(20));
+
+    // Only the root does, since other elements inherit their font size.
+    let child = style_in_language("x-other", Some(&root));
+    assert_eq!(child.get_font().font_size, Au::from_px(20));
+    let root = style_in_language("x-other", None);
+    assert_eq!(root.get_font().font_size, Au::from_px(16));
+}
@@ -0,0 +1,58 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use app_units::Au;
+use cssparser::{Color, RGBA};
+use euclid::size::Size2D;
+use style::error_reporting::StdoutErrorReporter;
+use style::parser::ParserContextExtraData;
+use style::properties::{ComputedValues, ServoComputedValues, cascade, parse_style_attribute};
+use style::selector_matching::DeclarationBlock;
+use style_traits::forced_colors::{ColorRole, ForcedColors};
+use url::Url;
+
+fn style(declarations: &str,
+         parent: Option<&ServoComputedValues>,
+         role: ColorRole)
+         -> ServoComputedValues {
+    let url = Url::parse("http://localhost").unwrap();
+    let block = parse_style_attribute(declarations,
+                                      &url,
+                                      Box::new(StdoutErrorReporter),
+                                      ParserContextExtraData::default());
+    cascade(Size2D::new(Au(0), Au(0)),
+            1.0,
+            &[DeclarationBlock::from_declarations(block.normal)],
+            false,
+            parent,
+            None,
+            Some((&ForcedColors::default(), role)),
+            Box::new(StdoutErrorReporter)).0
+}
+
+#[test]
+fn the_root_is_styled_with_the_canvas_colors() {
+    let palette = ForcedColors::default();
+    let root = style("color: gray; background: white url(bg.png)", None, ColorRole::Text);
+    assert_eq!(root.get_color().color, palette.canvas_text);
+    assert_eq!(root.get_background().background_color, Color::RGBA(palette.canvas));
+    assert!(root.get_background().background_image.0.is_none());
+}
+
+#[test]
+fn links_keep_their_color_apart_from_that_of_text() {
+    let palette = ForcedColors::default();
+    let root = style("", None, ColorRole::Text);
+    let link = style("color: gray; border: 1px solid red", Some(&root), ColorRole::Link);
+    assert_eq!(link.get_color().color, palette.link_text);
+    assert_eq!(link.get_border().border_top_color, Color::CurrentColor);
+
+    let inside_link = style("color: green; background-color: white; box-shadow: 1px 1px red",
+                            Some(&link),
+                            ColorRole::Text);
+    assert_eq!(inside_link.get_color().color, palette.link_text);
+    assert_eq!(inside_link.get_background().background_color,
+               Color::RGBA(RGBA { red: 0., green: 0., blue: 0., alpha: 0. }));
+    assert!(inside_link.get_effects().box_shadow.0.is_empty());
+}
@@ -0,0 +1,90 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use app_units::Au;
+use cssparser::{Color, RGBA};
+use std::f32::consts::PI;
+use style::interpolate::Interpolate;
+use style::properties::longhands::box_shadow::computed_value::BoxShadow;
+use style::properties::longhands::box_shadow::computed_value::T as BoxShadowList;
+use style::properties::longhands::transform::computed_value::ComputedMatrix;
+use style::properties::longhands::transform::computed_value::ComputedOperation;
+use style::properties::longhands::transform::computed_value::T as TransformList;
+use style::values::computed::{Angle, LengthOrPercentage};
+
+fn rotate(radians: f32) -> ComputedOperation {
+    ComputedOperation::Rotate(0.0, 0.0, 1.0, Angle(radians))
+}
+
+fn shadow(offset: Au, alpha: f32, inset: bool) -> BoxShadow {
+    BoxShadow {
+        offset_x: offset,
+        offset_y: offset,
+        blur_radius: Au(0),
+        spread_radius: offset,
+        color: Color::RGBA(RGBA { red: 0.0, green: 0.0, blue: 0.0, alpha: alpha }),
+        inset: inset,
+    }
+}
+
+#[test]
+fn mismatched_transforms_interpolate_as_decomposed_matrices() {
+    let quarter_turn = ComputedMatrix {
+        m11: 0.0, m12: 1.0, m13: 0.0, m14: 0.0,
+        m21: -1.0, m22: 0.0, m23: 0.0, m24: 0.0,
+        m31: 0.0, m32: 0.0, m33: 1.0, m34: 0.0,
+        m41: 0.0, m42: 0.0, m43: 0.0, m44: 1.0,
+    };
+    let from = TransformList(Some(vec![rotate(0.0)]));
+    let to = TransformList(Some(vec![ComputedOperation::Matrix(quarter_turn)]));
+
+    let halfway = from.interpolate(&to, 0.5).unwrap().0.unwrap();
+    assert_eq!(halfway.len(), 1);
+    let halfway = match halfway[0] {
+        ComputedOperation::Matrix(matrix) => matrix,
+        ref operation => panic!("expected a matrix, got {:?}", operation),
+    };
+    let eighth_turn = (PI / 4.0).cos();
+    for &(actual, expected) in &[(halfway.m11, eighth_turn), (halfway.m12, eighth_turn),
+                                 (halfway.m21, -eighth_turn), (halfway.m22, eighth_turn),
+                                 (halfway.m33, 1.0), (halfway.m41, 0.0)] {
+        assert!((actual - expected).abs() < 1e-5, "{} != {}", actual, expected);
+    }
+}
+
+#[test]
+fn rotations_about_the_same_axis_interpolate_their_angles() {
+    let from = TransformList(Some(vec![rotate(0.0)]));
+    let to = TransformList(Some(vec![rotate(2.0 * PI)]));
+    assert_eq!(from.interpolate(&to, 0.5), Some(TransformList(Some(vec![rotate(PI)]))));
+}
+
+#[test]
+fn transforms_with_percentages_flip_halfway_when_they_need_matrices() {
+    let from = TransformList(Some(vec![
+        ComputedOperation::Translate(LengthOrPercentage::Percentage(0.5),
+                                     LengthOrPercentage::zero(),
+                                     Au(0))
+    ]));
+    let to = TransformList(Some(vec![rotate(PI / 2.0), rotate(PI / 2.0)]));
+    assert_eq!(from.interpolate(&to, 0.25), Some(from.clone()));
+    assert_eq!(from.interpolate(&to, 0.75), Some(to.clone()));
+}
+
+#[test]
+fn box_shadows_pad_the_shorter_list_with_transparent_shadows() {
+    let from = BoxShadowList(vec![shadow(Au::from_px(2), 1.0, false)]);
+    let to = BoxShadowList(vec![shadow(Au::from_px(4), 1.0, false),
+                                shadow(Au::from_px(4), 1.0, true)]);
+    assert_eq!(from.interpolate(&to, 0.5),
+               Some(BoxShadowList(vec![shadow(Au::from_px(3), 1.0, false),
+                                       shadow(Au::from_px(2), 0.5, true)])));
+}
+
+#[test]
+fn inner_and_outer_box_shadows_do_not_interpolate() {
+    let from = BoxShadowList(vec![shadow(Au(0), 1.0, false)]);
+    let to = BoxShadowList(vec![shadow(Au(0), 1.0, true)]);
+    assert_eq!(from.interpolate(&to, 0.5), None);
+}
@@ -17,11 +17,20 @@ extern crate style_traits;
 extern crate url;
 extern crate util;
 
+mod animation;
 mod attr;
+mod cascade_snapshot;
+mod counter_style;
+mod easing;
+mod font_preferences;
+mod forced_colors;
+mod interpolate;
 mod logical_geometry;
 mod media_queries;
+mod page;
 mod properties;
 mod stylesheets;
+mod text_zoom;
 mod viewport;
 
 mod writing_modes {
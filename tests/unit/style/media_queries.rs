@@ -433,3 +433,16 @@ fn test_matching_invalid() {
     media_query_test(&device, "@media screen and (height: 100px) { a { color: red; } }", 0);
     media_query_test(&device, "@media not print and (width: 100) { a { color: red; } }", 0);
 }
+
+#[test]
+fn test_matching_prefers_reduced_motion() {
+    let device = Device {
+        media_type: MediaType::Screen,
+        viewport_size: Size2D::typed(200.0, 100.0),
+    };
+
+    media_query_test(
+        &device, "@media (prefers-reduced-motion: no-preference) { a { color: red; } }", 1);
+    media_query_test(&device, "@media (prefers-reduced-motion: reduce) { a { color: red; } }", 0);
+    media_query_test(&device, "@media (prefers-reduced-motion: none) { a { color: red; } }", 0);
+}
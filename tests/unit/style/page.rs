@@ -0,0 +1,77 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use app_units::Au;
+use euclid::{SideOffsets2D, Size2D};
+use media_queries::CSSErrorReporterTest;
+use style::page::{PageLength, PageRule, PageSide, PageSize, PageSelector, page_box};
+use style::parser::ParserContextExtraData;
+use style::servo::Stylesheet;
+use style::stylesheets::{CSSRuleIteratorExt, Origin, SourceRange};
+use url::Url;
+
+fn page_rules(css: &str) -> Vec<PageRule> {
+    let url = Url::parse("http://localhost").unwrap();
+    let stylesheet = Stylesheet::from_str(css, url, Origin::Author, Box::new(CSSErrorReporterTest),
+                                          ParserContextExtraData::default());
+    stylesheet.rules().page().cloned().collect()
+}
+
+#[test]
+fn parse_page_rule() {
+    let rules = page_rules("@page :first, :left { size: 4in 6in; margin: 1in 10%; }");
+    assert_eq!(rules, vec![PageRule {
+        selectors: vec![PageSelector { first: true, side: None },
+                        PageSelector { first: false, side: Some(PageSide::Left) }],
+        size: Some(PageSize::Size(Au::from_px(384), Au::from_px(576))),
+        margin_top: Some(PageLength::Length(Au::from_px(96))),
+        margin_right: Some(PageLength::Percentage(0.1)),
+        margin_bottom: Some(PageLength::Length(Au::from_px(96))),
+        margin_left: Some(PageLength::Percentage(0.1)),
+        source_range: SourceRange {
+            start: 0,
+            end: 55,
+            start_line: 1,
+            start_column: 1,
+            end_line: 1,
+            end_column: 56,
+        },
+    }]);
+
+    // Page size keywords are portrait unless they are turned.
+    let rules = page_rules("@page { size: landscape letter }");
+    assert_eq!(rules[0].selectors, vec![PageSelector { first: false, side: None }]);
+    assert_eq!(rules[0].size, Some(PageSize::Size(Au::from_px(1056), Au::from_px(816))));
+
+    // Named pages and conflicting sides are not supported.
+    assert!(page_rules("@page chapter { margin: 0 }").is_empty());
+    assert!(page_rules("@page :left:right { margin: 0 }").is_empty());
+}
+
+#[test]
+fn cascade_page_boxes() {
+    let rules = page_rules("
+        @page :first { margin-top: 2in; }
+        @page { size: 400px 600px; margin: 10px; }
+        @page :left { margin-left: 40px; }
+        @page :right { margin-right: 40px; }
+    ");
+    let default_size = Size2D::new(Au::from_px(800), Au::from_px(600));
+
+    // The first page is a right page, and `:first` wins over the rule that comes after it.
+    let first = page_box(&rules, 0, default_size);
+    assert_eq!(first.size, Size2D::new(Au::from_px(400), Au::from_px(600)));
+    assert_eq!(first.margins, SideOffsets2D::new(Au::from_px(192), Au::from_px(40),
+                                                 Au::from_px(10), Au::from_px(10)));
+    assert_eq!(first.page_area_size(), Size2D::new(Au::from_px(350), Au::from_px(398)));
+
+    let second = page_box(&rules, 1, default_size);
+    assert_eq!(second.margins, SideOffsets2D::new(Au::from_px(10), Au::from_px(10),
+                                                  Au::from_px(10), Au::from_px(40)));
+
+    // Pages without any rules are as big as the medium, without margins.
+    let blank = page_box(&[], 3, default_size);
+    assert_eq!(blank.size, default_size);
+    assert_eq!(blank.margins, SideOffsets2D::new_all_same(Au(0)));
+}
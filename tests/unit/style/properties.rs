@@ -2,15 +2,22 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use app_units::Au;
 use cssparser::ToCss;
+use euclid::size::Size2D;
 use rustc_serialize::json::Json;
 use std::env;
 use std::fs::{File, remove_file};
 use std::path::Path;
 use std::process::Command;
 use std::sync::Arc;
+use style::computed_values::direction;
 use style::computed_values::display::T::inline_block;
-use style::properties::{PropertyDeclaration, PropertyDeclarationBlock, DeclaredValue};
+use style::error_reporting::StdoutErrorReporter;
+use style::properties::{ComputedValues, PropertyDeclaration, PropertyDeclarationBlock};
+use style::properties::{DeclaredValue, ServoComputedValues, cascade};
+use style::selector_matching::DeclarationBlock;
+use style::values::computed;
 use style::values::specified::{Length, LengthOrPercentageOrAuto, LengthOrPercentage};
 
 #[test]
@@ -91,3 +98,129 @@ fn property_declaration_block_should_serialize_correctly() {
         "width: 70px; min-height: 20px; display: inline-block; height: 20px !important;"
     );
 }
+
+#[test]
+fn logical_properties_should_set_the_physical_properties_of_the_writing_mode() {
+    fn margin(px: f32) -> DeclaredValue<LengthOrPercentageOrAuto> {
+        DeclaredValue::Value(LengthOrPercentageOrAuto::Length(Length::from_px(px)))
+    }
+
+    // `direction: rtl; margin-left: 5px; margin-inline-start: 10px; margin-inline-end: 15px;
+    // margin-left: 20px`, with the declarations in reverse order, as they are stored.
+    let declarations = vec![
+        PropertyDeclaration::MarginLeft(margin(20.)),
+        PropertyDeclaration::MarginInlineEnd(margin(15.)),
+        PropertyDeclaration::MarginInlineStart(margin(10.)),
+        PropertyDeclaration::MarginLeft(margin(5.)),
+        PropertyDeclaration::Direction(DeclaredValue::Value(direction::T::rtl)),
+    ];
+    let (style, _) = cascade::<ServoComputedValues>(
+        Size2D::new(Au(0), Au(0)),
+        &[DeclarationBlock::from_declarations(Arc::new(declarations))],
+        false,
+        None,
+        None,
+        Box::new(StdoutErrorReporter));
+
+    // The inline start is on the right, and the last declaration of the left margin wins over
+    // the logical one that maps to it.
+    assert_eq!(style.get_margin().margin_right,
+               computed::LengthOrPercentageOrAuto::Length(Au::from_px(10)));
+    assert_eq!(style.get_margin().margin_left,
+               computed::LengthOrPercentageOrAuto::Length(Au::from_px(20)));
+}
+
+#[test]
+fn logical_shorthands_should_set_the_physical_properties_of_both_sides() {
+    use style::parser::ParserContextExtraData;
+    use style::properties::parse_style_attribute;
+    use url::Url;
+
+    let url = Url::parse("http://localhost").unwrap();
+    let block = parse_style_attribute("direction: rtl; margin-inline: 10px 20px; \
+                                       border-block-start: 2px solid; padding-block: 5px",
+                                      &url,
+                                      Box::new(StdoutErrorReporter),
+                                      ParserContextExtraData::default());
+    let (style, _) = cascade::<ServoComputedValues>(
+        Size2D::new(Au(0), Au(0)),
+        &[DeclarationBlock::from_declarations(block.normal)],
+        false,
+        None,
+        None,
+        Box::new(StdoutErrorReporter));
+
+    assert_eq!(style.get_margin().margin_right,
+               computed::LengthOrPercentageOrAuto::Length(Au::from_px(10)));
+    assert_eq!(style.get_margin().margin_left,
+               computed::LengthOrPercentageOrAuto::Length(Au::from_px(20)));
+    assert_eq!(style.get_border().border_top_width, Au::from_px(2));
+    assert_eq!(style.get_border().border_bottom_width, Au(0));
+    assert_eq!(style.get_padding().padding_top,
+               computed::LengthOrPercentage::Length(Au::from_px(5)));
+    assert_eq!(style.get_padding().padding_bottom,
+               computed::LengthOrPercentage::Length(Au::from_px(5)));
+}
+
+#[test]
+fn quotes_should_follow_the_primary_language_subtag() {
+    use style::properties::longhands::quotes;
+
+    let french = quotes::for_language("fr-CA").unwrap();
+    assert_eq!(french.0[0], ("\u{ab}\u{a0}".to_owned(), "\u{a0}\u{bb}".to_owned()));
+    assert_eq!(quotes::for_language("DE").unwrap().0[1],
+               ("\u{201a}".to_owned(), "\u{2018}".to_owned()));
+    assert!(quotes::for_language("x-klingon").is_none());
+    assert_eq!(quotes::SpecifiedValue(vec![]).to_css_string(), "none");
+}
+
+#[test]
+fn content_attr_should_fall_back_when_the_attribute_does_not_parse() {
+    use cssparser::Parser;
+    use media_queries::CSSErrorReporterTest;
+    use style::parser::ParserContext;
+    use style::properties::longhands::content::{self, AttrType, ContentItem};
+    use style::stylesheets::Origin;
+    use url::Url;
+
+    let url = Url::parse("http://localhost").unwrap();
+    let context = ParserContext::new(Origin::Author, &url, Box::new(CSSErrorReporterTest));
+    let mut input = Parser::new("attr(Data-Count integer, '-') attr(title)");
+    let value = content::parse(&context, &mut input).unwrap();
+    assert_eq!(value, content::SpecifiedValue::Content(vec![
+        ContentItem::Attr("data-count".to_owned(), AttrType::Integer, Some("-".to_owned())),
+        ContentItem::Attr("title".to_owned(), AttrType::String, None),
+    ]));
+    assert_eq!(value.to_css_string(), "attr(data-count integer, \"-\") attr(title)");
+
+    assert_eq!(AttrType::Integer.generate(Some(" 12 "), &None), "12");
+    assert_eq!(AttrType::Integer.generate(Some("twelve"), &Some("-".to_owned())), "-");
+    assert_eq!(AttrType::Number.generate(None, &None), "0");
+    assert_eq!(AttrType::String.generate(None, &None), "");
+}
+
+#[test]
+fn shape_outside_should_serialize_the_shape_before_the_box() {
+    use cssparser::Parser;
+    use media_queries::CSSErrorReporterTest;
+    use style::parser::ParserContext;
+    use style::properties::longhands::shape_outside;
+    use style::stylesheets::Origin;
+    use url::Url;
+
+    let url = Url::parse("http://localhost").unwrap();
+    let context = ParserContext::new(Origin::Author, &url, Box::new(CSSErrorReporterTest));
+    let serialize = |css: &str| {
+        shape_outside::parse(&context, &mut Parser::new(css)).map(|value| value.to_css_string())
+    };
+    assert_eq!(serialize("content-box circle(10px at left 20%)"),
+               Ok("circle(10px at 0% 20%) content-box".to_owned()));
+    assert_eq!(serialize("ellipse() margin-box"),
+               Ok("ellipse(closest-side closest-side at 50% 50%)".to_owned()));
+    assert_eq!(serialize("inset(10px 20%)"), Ok("inset(10px 20% 10px 20%)".to_owned()));
+    assert_eq!(serialize("polygon(evenodd, 0 0, 100% 0, 50% 100%)"),
+               Ok("polygon(evenodd, 0px 0px, 100% 0px, 50% 100%)".to_owned()));
+    assert_eq!(serialize("padding-box"), Ok("padding-box".to_owned()));
+    assert_eq!(serialize("none"), Ok("none".to_owned()));
+    assert_eq!(serialize("circle(-10px)"), Err(()));
+}
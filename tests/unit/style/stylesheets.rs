@@ -11,11 +11,25 @@ use std::sync::Mutex;
 use string_cache::{Atom, Namespace};
 use style::parser::ParserContextExtraData;
 use style::properties::{PropertyDeclaration, PropertyDeclarationBlock, DeclaredValue, longhands};
-use style::stylesheets::{CSSRule, StyleRule, Origin};
+use style::stylesheets::{CSSRule, DeclarationSource, SourceRange, StyleRule, Origin};
+use style::stylesheet_loader::{StylesheetSchemeHandler, register_scheme_handler};
+use style::stylesheets::{CSSRuleIteratorExt, parse_source_map_url};
 use style::error_reporting::ParseErrorReporter;
 use style::servo::Stylesheet;
 use url::Url;
 
+fn source_range(start: usize, end: usize, start_location: (usize, usize),
+                end_location: (usize, usize)) -> SourceRange {
+    SourceRange {
+        start: start,
+        end: end,
+        start_line: start_location.0,
+        start_column: start_location.1,
+        end_line: end_location.0,
+        end_column: end_location.1,
+    }
+}
+
 #[test]
 fn test_parse_stylesheet() {
     let css = r"
@@ -26,13 +40,15 @@ fn test_parse_stylesheet() {
         #d1 > .ok { background: blue; }
     ";
     let url = Url::parse("about::test").unwrap();
-    let stylesheet = Stylesheet::from_str(css, url, Origin::UserAgent,
+    let stylesheet = Stylesheet::from_str(css, url.clone(), Origin::UserAgent,
                                           Box::new(CSSErrorReporterTest),
                                           ParserContextExtraData::default());
     assert_eq!(stylesheet, Stylesheet {
         origin: Origin::UserAgent,
         media: None,
         dirty_on_viewport_size_change: false,
+        source_map_url: None,
+        url: url,
         rules: vec![
             CSSRule::Namespace(None, Namespace(Atom::from("http://www.w3.org/1999/xhtml"))),
             CSSRule::Style(StyleRule {
@@ -57,6 +73,7 @@ fn test_parse_stylesheet() {
                         specificity: (0 << 20) + (1 << 10) + (1 << 0),
                     },
                 ],
+                selector_text: "input[type=hidden i]".to_owned(),
                 declarations: PropertyDeclarationBlock {
                     normal: Arc::new(vec![]),
                     important: Arc::new(vec![
@@ -64,6 +81,15 @@ fn test_parse_stylesheet() {
                             longhands::display::SpecifiedValue::none)),
                     ]),
                 },
+                line: 4,
+                column: 9,
+                source_range: source_range(113, 163, (4, 9), (4, 59)),
+                declaration_sources: vec![
+                    DeclarationSource {
+                        name: "display".to_owned(),
+                        range: source_range(136, 160, (4, 32), (4, 56)),
+                    },
+                ],
             }),
             CSSRule::Style(StyleRule {
                 selectors: vec![
@@ -96,6 +122,7 @@ fn test_parse_stylesheet() {
                         specificity: (0 << 20) + (0 << 10) + (1 << 0),
                     },
                 ],
+                selector_text: "html , body /**/".to_owned(),
                 declarations: PropertyDeclarationBlock {
                     normal: Arc::new(vec![
                         PropertyDeclaration::Display(DeclaredValue::Value(
@@ -103,6 +130,15 @@ fn test_parse_stylesheet() {
                     ]),
                     important: Arc::new(vec![]),
                 },
+                line: 5,
+                column: 9,
+                source_range: source_range(172, 208, (5, 9), (5, 45)),
+                declaration_sources: vec![
+                    DeclarationSource {
+                        name: "display".to_owned(),
+                        range: source_range(191, 205, (5, 28), (5, 42)),
+                    },
+                ],
             }),
             CSSRule::Style(StyleRule {
                 selectors: vec![
@@ -124,6 +160,7 @@ fn test_parse_stylesheet() {
                         specificity: (1 << 20) + (1 << 10) + (0 << 0),
                     },
                 ],
+                selector_text: "#d1 > .ok".to_owned(),
                 declarations: PropertyDeclarationBlock {
                     normal: Arc::new(vec![
                         PropertyDeclaration::BackgroundClip(DeclaredValue::Initial),
@@ -144,11 +181,62 @@ fn test_parse_stylesheet() {
                     ]),
                     important: Arc::new(vec![]),
                 },
+                line: 6,
+                column: 9,
+                source_range: source_range(217, 248, (6, 9), (6, 40)),
+                declaration_sources: vec![
+                    DeclarationSource {
+                        name: "background".to_owned(),
+                        range: source_range(229, 245, (6, 21), (6, 37)),
+                    },
+                ],
             }),
         ],
     });
 }
 
+fn declaration_source(name: &str, range: SourceRange) -> DeclarationSource {
+    DeclarationSource {
+        name: name.to_owned(),
+        range: range,
+    }
+}
+
+#[test]
+fn test_source_ranges() {
+    let css = "@media screen {\r\n  p { color: red; ;bogus; margin: 0 }\r\n}\r\nem { color: blue";
+    let url = Url::parse("about::test").unwrap();
+    let stylesheet = Stylesheet::from_str(css, url, Origin::Author, Box::new(CSSErrorReporterTest),
+                                          ParserContextExtraData::default());
+    let media_rule = match stylesheet.rules[0] {
+        CSSRule::Media(ref media_rule) => media_rule,
+        ref rule => panic!("expected a media rule, got {:?}", rule),
+    };
+    assert_eq!(media_rule.source_range, source_range(0, 57, (1, 1), (3, 2)));
+
+    // Invalid declarations are kept, and whitespace and stray semicolons are left out.
+    let style_rule = match media_rule.rules[0] {
+        CSSRule::Style(ref style_rule) => style_rule,
+        ref rule => panic!("expected a style rule, got {:?}", rule),
+    };
+    assert_eq!(style_rule.source_range, source_range(19, 54, (2, 3), (2, 38)));
+    assert_eq!(style_rule.declaration_sources, vec![
+        declaration_source("color", source_range(23, 33, (2, 7), (2, 17))),
+        declaration_source("bogus", source_range(36, 41, (2, 20), (2, 25))),
+        declaration_source("margin", source_range(43, 52, (2, 27), (2, 36))),
+    ]);
+
+    // Rules that are left unclosed end with the stylesheet.
+    let style_rule = match stylesheet.rules[1] {
+        CSSRule::Style(ref style_rule) => style_rule,
+        ref rule => panic!("expected a style rule, got {:?}", rule),
+    };
+    assert_eq!(style_rule.source_range, source_range(59, 75, (4, 1), (4, 17)));
+    assert_eq!(style_rule.declaration_sources, vec![
+        declaration_source("color", source_range(64, 75, (4, 6), (4, 17))),
+    ]);
+}
+
 struct CSSError {
     pub line: usize,
     pub column: usize,
@@ -222,3 +310,54 @@ fn test_report_error_stylesheet() {
     assert_eq!(4, error.line);
     assert_eq!(9, error.column);
 }
+
+#[test]
+fn test_parse_source_map_url() {
+    let url = Url::parse("http://example.com/css/main.css").unwrap();
+    let css = "a { color: red; }\n/*# sourceMappingURL=main.css.map */\n";
+    let stylesheet = Stylesheet::from_str(css, url, Origin::Author,
+                                          Box::new(CSSErrorReporterTest),
+                                          ParserContextExtraData::default());
+    assert_eq!(stylesheet.source_map_url,
+               Some(Url::parse("http://example.com/css/main.css.map").unwrap()));
+}
+
+#[test]
+fn test_parse_source_map_url_uses_last_comment() {
+    let url = Url::parse("http://example.com/main.css").unwrap();
+    let css = "/*@ sourceMappingURL=old.map */ a { color: red; } /* comment */\n\
+               /*# sourceMappingURL=/maps/new.map */";
+    assert_eq!(parse_source_map_url(css, &url),
+               Some(Url::parse("http://example.com/maps/new.map").unwrap()));
+    assert_eq!(parse_source_map_url("/* sourceMappingURL=none.map */", &url), None);
+}
+
+struct ImportTestHandler;
+
+impl StylesheetSchemeHandler for ImportTestHandler {
+    fn load(&self, url: &Url) -> Result<Vec<u8>, ()> {
+        match url.path() {
+            "/imported.css" => Ok(b"@import url(cycle.css); p { color: red; }".to_vec()),
+            "/cycle.css" => Ok(b"@import 'imported.css'; em { color: blue; }".to_vec()),
+            _ => Err(()),
+        }
+    }
+}
+
+#[test]
+fn test_user_stylesheets_import_through_scheme_handlers() {
+    register_scheme_handler("import-test", Arc::new(ImportTestHandler));
+    let url = Url::parse("import-test://sheets/main.css").unwrap();
+    let css = "@import 'imported.css'; @import url(missing.css); a { color: green; }";
+    let selectors = |origin| {
+        let stylesheet = Stylesheet::from_str(css, url.clone(), origin,
+                                              Box::new(CSSErrorReporterTest),
+                                              ParserContextExtraData::default());
+        stylesheet.rules().style().map(|rule| rule.selector_text.clone()).collect::<Vec<_>>()
+    };
+
+    // Imported rules come where they are imported. `cycle.css` imports nothing, since
+    // `imported.css` is being imported, and neither does the `@import` of a missing stylesheet.
+    assert_eq!(selectors(Origin::User), ["em", "p", "a"]);
+    assert_eq!(selectors(Origin::Author), ["a"]);
+}
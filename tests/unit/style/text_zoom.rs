@@ -0,0 +1,46 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use app_units::Au;
+use euclid::size::Size2D;
+use style::error_reporting::StdoutErrorReporter;
+use style::parser::ParserContextExtraData;
+use style::properties::{ComputedValues, ServoComputedValues, cascade, parse_style_attribute};
+use style::selector_matching::DeclarationBlock;
+use style::values::computed::LengthOrPercentageOrAuto;
+use url::Url;
+
+fn style(declarations: &str, parent: Option<&ServoComputedValues>) -> ServoComputedValues {
+    let url = Url::parse("http://localhost").unwrap();
+    let block = parse_style_attribute(declarations,
+                                      &url,
+                                      Box::new(StdoutErrorReporter),
+                                      ParserContextExtraData::default());
+    cascade(Size2D::new(Au(0), Au(0)),
+            2.0,
+            &[DeclarationBlock::from_declarations(block.normal)],
+            false,
+            parent,
+            None,
+            None,
+            Box::new(StdoutErrorReporter)).0
+}
+
+#[test]
+fn the_initial_font_size_of_the_root_is_zoomed() {
+    let root = style("width: 2rem", None);
+    assert_eq!(root.get_font().font_size, Au::from_px(32));
+    assert_eq!(root.root_font_size(), Au::from_px(32));
+    assert_eq!(root.get_position().width, LengthOrPercentageOrAuto::Length(Au::from_px(64)));
+}
+
+#[test]
+fn absolute_font_sizes_are_zoomed_once_and_other_lengths_not_at_all() {
+    let root = style("font-size: 10px", None);
+    assert_eq!(root.get_font().font_size, Au::from_px(20));
+
+    let child = style("font-size: 1.5em; margin-left: 10px", Some(&root));
+    assert_eq!(child.get_font().font_size, Au::from_px(30));
+    assert_eq!(child.get_margin().margin_left, LengthOrPercentageOrAuto::Length(Au::from_px(10)));
+}